@@ -1,4 +1,5 @@
 use anyhow::Result;
+use gcodekit5_core::{apply_post, PostContext, PostDefinition};
 use serde::{Deserialize, Serialize};
 
 /// Parameters for the Drill Press CAMTool
@@ -31,12 +32,22 @@ pub struct DrillPressParameters {
 /// Generator for Drill Press G-Code
 pub struct DrillPressGenerator {
     params: DrillPressParameters,
+    post_processor: Option<PostDefinition>,
 }
 
 impl DrillPressGenerator {
     /// Create a new DrillPressGenerator with the given parameters
     pub fn new(params: DrillPressParameters) -> Self {
-        Self { params }
+        Self {
+            params,
+            post_processor: None,
+        }
+    }
+
+    /// Run the generated program through a post-processor
+    pub fn with_post_processor(mut self, post: PostDefinition) -> Self {
+        self.post_processor = Some(post);
+        self
     }
 
     /// Generate the G-Code for the drilling operation
@@ -75,7 +86,11 @@ impl DrillPressGenerator {
         gcode.push_str("M5 ; Stop spindle\n");
         gcode.push_str("M30 ; End program\n");
 
-        Ok(gcode)
+        let context = PostContext::new("Drill Press")
+            .with_safe_z(p.safe_z)
+            .with_spindle_speed(p.spindle_speed)
+            .with_feed_rate(p.feed_rate);
+        Ok(apply_post(self.post_processor.as_ref(), gcode, &context))
     }
 
     /// Generate standard or peck drilling G-Code
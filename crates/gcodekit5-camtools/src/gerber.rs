@@ -3,6 +3,7 @@ use anyhow::Result;
 use cavalier_contours::polyline::{PlineSource, PlineSourceMut, PlineVertex, Polyline};
 use csgrs::sketch::Sketch;
 use csgrs::traits::CSG;
+use gcodekit5_core::{apply_post, PostContext, PostDefinition};
 use gerber_parser::parse;
 use gerber_types::{
     Command, CoordinateNumber, DCode, FunctionCode, InterpolationMode, Operation, QuadrantMode,
@@ -117,6 +118,15 @@ pub struct GerberConverter;
 
 impl GerberConverter {
     pub fn generate(params: &GerberParameters, gerber_content: &str) -> Result<String> {
+        Self::generate_with_post(params, gerber_content, None)
+    }
+
+    /// Generate G-code and run it through a post-processor when one is given
+    pub fn generate_with_post(
+        params: &GerberParameters,
+        gerber_content: &str,
+        post: Option<&PostDefinition>,
+    ) -> Result<String> {
         let mut gcode = String::new();
 
         // Initialization sequence
@@ -181,7 +191,11 @@ impl GerberConverter {
         writeln!(gcode, "M5")?;
         writeln!(gcode, "G0 X0 Y0")?;
 
-        Ok(gcode)
+        let context = PostContext::new("Gerber")
+            .with_safe_z(params.safe_z as f64)
+            .with_spindle_speed(params.spindle_speed as f64)
+            .with_feed_rate(params.feed_rate as f64);
+        Ok(apply_post(post, gcode, &context))
     }
 
     fn create_thick_segment(p1: (f64, f64), p2: (f64, f64), width: f64) -> Sketch<()> {
@@ -2,6 +2,7 @@
 //!
 //! Generates G-code toolpaths for laser/CNC cutting jigsaw puzzles with interlocking pieces.
 
+use gcodekit5_core::{apply_post, PostContext, PostDefinition};
 use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
@@ -70,6 +71,7 @@ pub struct JigsawPuzzleMaker {
     params: PuzzleParameters,
     paths: Vec<Vec<Point>>,
     rng_state: f32,
+    post_processor: Option<PostDefinition>,
 }
 
 impl JigsawPuzzleMaker {
@@ -79,9 +81,16 @@ impl JigsawPuzzleMaker {
             params: params.clone(),
             paths: Vec::new(),
             rng_state: params.seed as f32,
+            post_processor: None,
         })
     }
 
+    /// Run the generated program through a post-processor
+    pub fn with_post_processor(mut self, post: PostDefinition) -> Self {
+        self.post_processor = Some(post);
+        self
+    }
+
     fn random(&mut self) -> f32 {
         let x = (self.rng_state.sin() * 10000.0).abs();
         self.rng_state += 1.0;
@@ -590,6 +599,9 @@ impl JigsawPuzzleMaker {
         gcode.push_str("G0 X0 Y0 ; Return to origin\n");
         gcode.push_str("M2 ; Program end\n");
 
-        gcode
+        let context = PostContext::new("Jigsaw Puzzle")
+            .with_spindle_speed(self.params.laser_power as f64)
+            .with_feed_rate(self.params.feed_rate as f64);
+        apply_post(self.post_processor.as_ref(), gcode, &context)
     }
 }
@@ -7,6 +7,7 @@
 
 use anyhow::{Context, Result};
 use gcodekit5_core::types::BoxedIterator;
+use gcodekit5_core::{apply_post, PostContext, PostDefinition};
 use image::{DynamicImage, GrayImage};
 use std::path::Path;
 
@@ -143,6 +144,7 @@ pub struct BitmapImageEngraver {
     params: EngravingParameters,
     output_width: u32,
     output_height: u32,
+    post_processor: Option<PostDefinition>,
 }
 
 impl BitmapImageEngraver {
@@ -206,9 +208,16 @@ impl BitmapImageEngraver {
             params,
             output_width,
             output_height,
+            post_processor: None,
         })
     }
 
+    /// Run the generated program through a post-processor
+    pub fn with_post_processor(mut self, post: PostDefinition) -> Self {
+        self.post_processor = Some(post);
+        self
+    }
+
     /// Apply rotation to image
     pub fn apply_rotation_image(image: GrayImage, rotation: RotationAngle) -> GrayImage {
         match rotation {
@@ -527,7 +536,10 @@ impl BitmapImageEngraver {
 
         progress_callback(1.0);
 
-        Ok(gcode)
+        let context = PostContext::new("Laser Image Engraving")
+            .with_spindle_speed(self.params.power_scale as f64)
+            .with_feed_rate(self.params.feed_rate as f64);
+        Ok(apply_post(self.post_processor.as_ref(), gcode, &context))
     }
 
     fn generate_horizontal_scan_with_progress<F>(
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use gcodekit5_core::{apply_post, PostContext, PostDefinition};
 
 #[derive(Debug, Clone)]
 pub struct SpoilboardGridParameters {
@@ -12,11 +13,21 @@ pub struct SpoilboardGridParameters {
 
 pub struct SpoilboardGridGenerator {
     params: SpoilboardGridParameters,
+    post_processor: Option<PostDefinition>,
 }
 
 impl SpoilboardGridGenerator {
     pub fn new(params: SpoilboardGridParameters) -> Self {
-        Self { params }
+        Self {
+            params,
+            post_processor: None,
+        }
+    }
+
+    /// Run the generated program through a post-processor
+    pub fn with_post_processor(mut self, post: PostDefinition) -> Self {
+        self.post_processor = Some(post);
+        self
     }
 
     pub fn generate(&self) -> Result<String> {
@@ -93,6 +104,9 @@ impl SpoilboardGridGenerator {
         gcode.push_str("G0 X0 Y0 ; Return to origin\n");
         gcode.push_str("M30 ; End program\n");
 
-        Ok(gcode)
+        let context = PostContext::new("Spoilboard Grid")
+            .with_spindle_speed(p.laser_power)
+            .with_feed_rate(p.feed_rate);
+        Ok(apply_post(self.post_processor.as_ref(), gcode, &context))
     }
 }
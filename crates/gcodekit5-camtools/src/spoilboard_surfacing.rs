@@ -1,4 +1,5 @@
 use anyhow::Result;
+use gcodekit5_core::{apply_post, PostContext, PostDefinition};
 
 #[derive(Debug, Clone)]
 pub struct SpoilboardSurfacingParameters {
@@ -14,11 +15,21 @@ pub struct SpoilboardSurfacingParameters {
 
 pub struct SpoilboardSurfacingGenerator {
     params: SpoilboardSurfacingParameters,
+    post_processor: Option<PostDefinition>,
 }
 
 impl SpoilboardSurfacingGenerator {
     pub fn new(params: SpoilboardSurfacingParameters) -> Self {
-        Self { params }
+        Self {
+            params,
+            post_processor: None,
+        }
+    }
+
+    /// Run the generated program through a post-processor
+    pub fn with_post_processor(mut self, post: PostDefinition) -> Self {
+        self.post_processor = Some(post);
+        self
     }
 
     pub fn generate(&self) -> Result<String> {
@@ -84,6 +95,10 @@ impl SpoilboardSurfacingGenerator {
         gcode.push_str("M5\n");
         gcode.push_str("M30\n");
 
-        Ok(gcode)
+        let context = PostContext::new("Spoilboard Surfacing")
+            .with_safe_z(p.safe_z)
+            .with_spindle_speed(p.spindle_speed)
+            .with_feed_rate(p.feed_rate);
+        Ok(apply_post(self.post_processor.as_ref(), gcode, &context))
     }
 }
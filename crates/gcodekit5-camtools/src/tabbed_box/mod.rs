@@ -7,6 +7,8 @@ mod types;
 
 pub use types::*;
 
+use gcodekit5_core::{apply_post, PostContext, PostDefinition};
+
 #[derive(Clone, Copy, Debug)]
 struct LayoutCursor {
     x: f32,
@@ -43,6 +45,7 @@ pub struct TabbedBoxMaker {
     t: f32,
     paths: Vec<Vec<Point>>,
     path_groups: Vec<Vec<usize>>,
+    post_processor: Option<PostDefinition>,
 }
 
 impl TabbedBoxMaker {
@@ -69,9 +72,16 @@ impl TabbedBoxMaker {
             t,
             paths: Vec::new(),
             path_groups: Vec::new(),
+            post_processor: None,
         })
     }
 
+    /// Run the generated program through a post-processor
+    pub fn with_post_processor(mut self, post: PostDefinition) -> Self {
+        self.post_processor = Some(post);
+        self
+    }
+
     fn validate_parameters(params: &BoxParameters) -> Result<(), String> {
         if params.x < 20.0 || params.y < 20.0 || params.h < 20.0 {
             return Err("All dimensions must be at least 20mm".to_string());
@@ -1312,6 +1322,9 @@ impl TabbedBoxMaker {
         gcode.push_str("G0 X0 Y0 ; Return to origin\n");
         gcode.push_str("M2 ; Program end\n");
 
-        gcode
+        let context = PostContext::new("Tabbed Box")
+            .with_spindle_speed(self.params.laser_power as f64)
+            .with_feed_rate(self.params.feed_rate as f64);
+        apply_post(self.post_processor.as_ref(), gcode, &context)
     }
 }
@@ -4,6 +4,7 @@
 //! Supports path stroking, fill patterns, and various vector formats.
 
 use anyhow::{Context, Result};
+use gcodekit5_core::{apply_post, PostContext, PostDefinition};
 use image::{Rgb, RgbImage};
 use lyon::algorithms::path::iterator::PathIterator;
 use lyon::geom::Arc;
@@ -92,6 +93,8 @@ pub struct VectorEngraver {
     /// Scale factor from SVG units to mm
     #[allow(dead_code)]
     pub scale_factor: f32,
+    /// Post-processor for the generated program
+    pub post_processor: Option<PostDefinition>,
 }
 
 impl VectorEngraver {
@@ -125,9 +128,16 @@ impl VectorEngraver {
             params,
             paths,
             scale_factor,
+            post_processor: None,
         })
     }
 
+    /// Run the generated program through a post-processor
+    pub fn with_post_processor(mut self, post: PostDefinition) -> Self {
+        self.post_processor = Some(post);
+        self
+    }
+
     /// Parse SVG file and extract paths
     fn parse_svg(file_path: &str) -> Result<(Vec<Path>, f32)> {
         use regex::Regex;
@@ -1130,7 +1140,10 @@ impl VectorEngraver {
 
         progress_callback(1.0);
 
-        Ok(gcode)
+        let context = PostContext::new("Laser Vector Engraving")
+            .with_spindle_speed(self.params.power_scale as f64)
+            .with_feed_rate(self.params.feed_rate as f64);
+        Ok(apply_post(self.post_processor.as_ref(), gcode, &context))
     }
 }
 
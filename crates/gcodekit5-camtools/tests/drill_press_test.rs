@@ -1,4 +1,5 @@
 use gcodekit5_camtools::drill_press::{DrillPressGenerator, DrillPressParameters};
+use gcodekit5_core::PostDefinition;
 
 #[test]
 fn test_simple_drilling() {
@@ -81,3 +82,31 @@ fn test_helical_drilling() {
     // Return to center
     assert!(gcode.contains("G1 X0.000 Y0.000 F500.0"));
 }
+
+#[test]
+fn test_drilling_with_post_processor() {
+    let params = DrillPressParameters {
+        hole_diameter: 5.0,
+        tool_diameter: 5.0,
+        top_z: 0.0,
+        bottom_z: -10.0,
+        peck_depth: 0.0,
+        plunge_rate: 100.0,
+        feed_rate: 500.0,
+        spindle_speed: 1000.0,
+        safe_z: 5.0,
+        x: 10.0,
+        y: 20.0,
+    };
+
+    let post = PostDefinition::builtin("Mach3").unwrap();
+    let generator = DrillPressGenerator::new(params).with_post_processor(post);
+    let gcode = generator.generate().expect("generate failed");
+
+    assert!(gcode.contains("(Drill Press Toolpath)"));
+    assert!(gcode.contains("G1 Z-10 F100"), "{}", gcode);
+    assert!(gcode
+        .lines()
+        .any(|l| l.starts_with('N') && l.contains("G0 X10 Y20")));
+    assert!(!gcode.contains(';'));
+}
@@ -28,6 +28,8 @@ pub enum GrblResponse {
     BuildInfo(String),
     /// Status reports mask ($10)
     StatusMask(u8),
+    /// Probe result (`[PRB:x,y,z:success]`), position in machine coordinates
+    ProbeResult { position: CNCPoint, success: bool },
     /// Startup message or other text
     Message(String),
 }
@@ -44,6 +46,14 @@ impl fmt::Display for GrblResponse {
             Self::Version(version) => write!(f, "version:{}", version),
             Self::BuildInfo(info) => write!(f, "build_info:{}", info),
             Self::StatusMask(mask) => write!(f, "status_mask:{}", mask),
+            Self::ProbeResult { position, success } => write!(
+                f,
+                "probe:{:.3},{:.3},{:.3}:{}",
+                position.x,
+                position.y,
+                position.z,
+                u8::from(*success)
+            ),
             Self::Message(msg) => write!(f, "message:{}", msg),
         }
     }
@@ -144,6 +154,14 @@ impl GrblResponseParser {
             return Some(GrblResponse::Version(line.to_string()));
         }
 
+        // Check for probe result ([PRB:x,y,z:1])
+        if let Some(probe) = line
+            .strip_prefix("[PRB:")
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            return self.parse_probe_result(probe);
+        }
+
         // Check for build info (starts with "[")
         if line.starts_with('[') && line.ends_with(']') {
             return Some(GrblResponse::BuildInfo(line.to_string()));
@@ -217,6 +235,15 @@ impl GrblResponseParser {
         Some(CNCPoint::with_axes(x, y, z, a, b, c, unit))
     }
 
+    /// Parse the body of a probe result (`x,y,z:success`)
+    fn parse_probe_result(&self, probe_str: &str) -> Option<GrblResponse> {
+        let (pos_str, success_str) = probe_str.rsplit_once(':')?;
+        let position = self.parse_position(pos_str, Units::MM)?;
+        let success = success_str.trim() == "1";
+
+        Some(GrblResponse::ProbeResult { position, success })
+    }
+
     /// Parse buffer state
     fn parse_buffer_state(&self, buf_str: &str) -> Option<BufferState> {
         let parts: Vec<&str> = buf_str.split(':').collect();
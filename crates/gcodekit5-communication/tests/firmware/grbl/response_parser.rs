@@ -89,6 +89,25 @@ fn test_parse_build_info() {
     ));
 }
 
+#[test]
+fn test_parse_probe_result() {
+    let parser = GrblResponseParser::new();
+    match parser.parse("[PRB:12.500,-3.000,-1.234:1]") {
+        Some(GrblResponse::ProbeResult { position, success }) => {
+            assert!(success);
+            assert_eq!(position.x, 12.5);
+            assert_eq!(position.y, -3.0);
+            assert_eq!(position.z, -1.234);
+        }
+        other => panic!("unexpected response: {:?}", other),
+    }
+
+    assert!(matches!(
+        parser.parse("[PRB:0.000,0.000,-10.000:0]"),
+        Some(GrblResponse::ProbeResult { success: false, .. })
+    ));
+}
+
 #[test]
 fn test_error_description() {
     assert_eq!(
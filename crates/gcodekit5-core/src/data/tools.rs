@@ -363,6 +363,36 @@ impl Tool {
         !material_category.is_empty()
     }
 
+    /// Get the cutting profile used to simulate this tool.
+    ///
+    /// Engraving bits are modelled as tapered cutters whose flat tip radius
+    /// is taken from `corner_radius`; drills and spot drills use their point
+    /// angle as a V profile.
+    pub fn profile(&self) -> ToolProfile {
+        let diameter = self.diameter;
+        match self.tool_type {
+            ToolType::EndMillBall => ToolProfile::Ball { diameter },
+            ToolType::EndMillCornerRadius => ToolProfile::BullNose {
+                diameter,
+                corner_radius: self.corner_radius.unwrap_or(0.0),
+            },
+            ToolType::VBit | ToolType::ChamferTool => ToolProfile::VBit {
+                diameter,
+                tip_angle: self.tip_angle.unwrap_or(90.0),
+            },
+            ToolType::DrillBit | ToolType::SpotDrill => ToolProfile::VBit {
+                diameter,
+                tip_angle: self.tip_angle.unwrap_or(118.0),
+            },
+            ToolType::EngravingBit => ToolProfile::Tapered {
+                diameter,
+                tip_diameter: self.corner_radius.unwrap_or(0.0) * 2.0,
+                tip_angle: self.tip_angle.unwrap_or(30.0),
+            },
+            ToolType::EndMillFlat | ToolType::Specialty => ToolProfile::Flat { diameter },
+        }
+    }
+
     /// Builder method to set description.
     pub fn with_description(mut self, description: impl Into<String>) -> Self {
         self.description = description.into();
@@ -454,6 +484,300 @@ impl Tool {
     }
 }
 
+/// Cutting profile of a tool, as seen in a section through its axis.
+///
+/// Used by material removal simulation to decide how deep the cutter reaches
+/// at a given distance from its axis. All dimensions are in millimeters and
+/// angles are included angles in degrees.
+///
+/// # Example
+/// ```
+/// use gcodekit5_core::data::tools::ToolProfile;
+///
+/// let vbit = ToolProfile::VBit { diameter: 6.0, tip_angle: 90.0 };
+/// assert_eq!(vbit.radius(), 3.0);
+/// assert!((vbit.height_at(1.0).unwrap() - 1.0).abs() < 1e-5);
+/// assert!(vbit.height_at(3.5).is_none());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ToolProfile {
+    /// Flat bottomed end mill
+    Flat { diameter: f32 },
+    /// Ball nose end mill
+    Ball { diameter: f32 },
+    /// End mill with a radiused corner
+    BullNose { diameter: f32, corner_radius: f32 },
+    /// Conical V-bit with a sharp tip
+    VBit { diameter: f32, tip_angle: f32 },
+    /// Conical cutter with a flat tip
+    Tapered {
+        diameter: f32,
+        tip_diameter: f32,
+        tip_angle: f32,
+    },
+}
+
+impl ToolProfile {
+    /// Outer cutting diameter in mm
+    pub fn diameter(&self) -> f32 {
+        match *self {
+            Self::Flat { diameter }
+            | Self::Ball { diameter }
+            | Self::BullNose { diameter, .. }
+            | Self::VBit { diameter, .. }
+            | Self::Tapered { diameter, .. } => diameter,
+        }
+    }
+
+    /// Outer cutting radius in mm
+    pub fn radius(&self) -> f32 {
+        self.diameter() / 2.0
+    }
+
+    /// Name of the profile's shape
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Flat { .. } => "Flat",
+            Self::Ball { .. } => "Ball",
+            Self::BullNose { .. } => "Bull Nose",
+            Self::VBit { .. } => "V-Bit",
+            Self::Tapered { .. } => "Tapered",
+        }
+    }
+
+    /// Radius of the rounded corner at the tip; zero for flat and conical
+    /// cutters
+    pub fn corner_radius(&self) -> f32 {
+        match *self {
+            Self::Ball { .. } => self.radius(),
+            Self::BullNose { corner_radius, .. } => corner_radius.clamp(0.0, self.radius()),
+            _ => 0.0,
+        }
+    }
+
+    /// Height of the cutting surface above the tool tip at distance `r` from
+    /// the tool axis.
+    ///
+    /// Returns `None` when `r` lies outside the cutting radius.
+    pub fn height_at(&self, r: f32) -> Option<f32> {
+        let radius = self.radius();
+        let r = r.abs();
+        if r > radius {
+            return None;
+        }
+        let height = match *self {
+            Self::Flat { .. } => 0.0,
+            Self::Ball { .. } => radius - (radius * radius - r * r).max(0.0).sqrt(),
+            Self::BullNose { .. } => {
+                let corner = self.corner_radius();
+                let d = r - (radius - corner);
+                if d <= 0.0 {
+                    0.0
+                } else {
+                    corner - (corner * corner - d * d).max(0.0).sqrt()
+                }
+            }
+            Self::VBit { tip_angle, .. } => cone_height(r, tip_angle),
+            Self::Tapered {
+                tip_diameter,
+                tip_angle,
+                ..
+            } => cone_height((r - tip_diameter / 2.0).max(0.0), tip_angle),
+        };
+        Some(height)
+    }
+
+    /// Distance from the tool axis at which the cutting surface touches a
+    /// plane rising at `slope` (rise over run) when lowered onto it.
+    pub fn contact_radius(&self, slope: f32) -> f32 {
+        let radius = self.radius();
+        let slope = slope.abs();
+        match *self {
+            Self::VBit { tip_angle, .. } | Self::Tapered { tip_angle, .. } => {
+                // The flank touches a plane steeper than itself only at the rim
+                if slope * half_angle(tip_angle).tan() > 1.0 {
+                    radius
+                } else if let Self::Tapered { tip_diameter, .. } = *self {
+                    (tip_diameter / 2.0).min(radius)
+                } else {
+                    0.0
+                }
+            }
+            _ => {
+                let corner = self.corner_radius();
+                (radius - corner + corner * slope / (1.0 + slope * slope).sqrt()).min(radius)
+            }
+        }
+    }
+
+    /// The profile whose cutting surface lies `amount` further out all round,
+    /// with its tip `amount` lower.
+    ///
+    /// Dropping the grown profile onto a surface and raising it by `amount`
+    /// leaves that much stock. The offset of a conical tip is rounded, which
+    /// no profile describes, so cones grow into tapered cutters whose flat
+    /// tip lies just under it; they leave slightly more in sharp valleys.
+    pub fn grown(&self, amount: f32) -> Self {
+        if amount <= 0.0 {
+            return *self;
+        }
+        let diameter = self.diameter() + 2.0 * amount;
+        match *self {
+            Self::Ball { .. } => Self::Ball { diameter },
+            Self::Flat { .. } | Self::BullNose { .. } => Self::BullNose {
+                diameter,
+                corner_radius: self.corner_radius() + amount,
+            },
+            Self::VBit { tip_angle, .. } | Self::Tapered { tip_angle, .. } => {
+                let tip_diameter = match *self {
+                    Self::Tapered { tip_diameter, .. } => tip_diameter,
+                    _ => 0.0,
+                };
+                let (sin, cos) = half_angle(tip_angle).sin_cos();
+                Self::Tapered {
+                    diameter,
+                    tip_diameter: tip_diameter + 2.0 * amount * (1.0 - sin) / cos,
+                    tip_angle,
+                }
+            }
+        }
+    }
+
+    /// Lowest Z reached by the cutting surface at `(x, y)` while the tool tip
+    /// travels in a straight line from `start` to `end`.
+    ///
+    /// Returns `None` when the swept tool never covers the point. The height
+    /// along the move is a convex function of the move parameter for every
+    /// supported profile, so the minimum is found by golden-section search
+    /// over the interval where the point lies under the cutter.
+    pub fn swept_bottom(
+        &self,
+        x: f32,
+        y: f32,
+        start: (f32, f32, f32),
+        end: (f32, f32, f32),
+    ) -> Option<f32> {
+        let radius = self.radius();
+        let (ax, ay) = (start.0 - x, start.1 - y);
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let dz = end.2 - start.2;
+        let len_sq = dx * dx + dy * dy;
+        let dist_sq_start = ax * ax + ay * ay;
+
+        if len_sq <= f32::EPSILON {
+            // Pure plunge or retract: the tool bottom ends at the lower of the two Z values
+            return self
+                .height_at(dist_sq_start.sqrt())
+                .map(|h| start.2.min(end.2) + h);
+        }
+
+        // Parameter range where the point lies within the cutter radius
+        let b = ax * dx + ay * dy;
+        let disc = b * b - len_sq * (dist_sq_start - radius * radius);
+        if disc < 0.0 {
+            return None;
+        }
+        let root = disc.sqrt();
+        let t0 = ((-b - root) / len_sq).max(0.0);
+        let t1 = ((-b + root) / len_sq).min(1.0);
+        if t0 > t1 {
+            return None;
+        }
+
+        let height = |t: f32| {
+            let px = ax + dx * t;
+            let py = ay + dy * t;
+            let r = (px * px + py * py).sqrt().min(radius);
+            start.2 + dz * t + self.height_at(r).unwrap_or(0.0)
+        };
+
+        if dz.abs() <= f32::EPSILON {
+            // Level move: the lowest point is directly over the closest approach
+            let t = (-b / len_sq).clamp(t0, t1);
+            return Some(height(t));
+        }
+
+        const INV_PHI: f32 = 0.618_034;
+        let (mut lo, mut hi) = (t0, t1);
+        let mut m1 = hi - (hi - lo) * INV_PHI;
+        let mut m2 = lo + (hi - lo) * INV_PHI;
+        let (mut f1, mut f2) = (height(m1), height(m2));
+        for _ in 0..24 {
+            if f1 <= f2 {
+                hi = m2;
+                m2 = m1;
+                f2 = f1;
+                m1 = hi - (hi - lo) * INV_PHI;
+                f1 = height(m1);
+            } else {
+                lo = m1;
+                m1 = m2;
+                f1 = f2;
+                m2 = lo + (hi - lo) * INV_PHI;
+                f2 = height(m2);
+            }
+        }
+        Some(f1.min(f2).min(height(t0)).min(height(t1)))
+    }
+
+    /// X range covered by the cutter on the row at `y` while the tool tip
+    /// travels in a straight line from `start` to `end`.
+    ///
+    /// Returns `None` when the swept cutter misses the row. The outline of
+    /// the sweep is a capsule, so it meets each row in a single span, and
+    /// simulators only need to call [`Self::swept_bottom`] inside it.
+    pub fn sweep_span(
+        &self,
+        y: f32,
+        start: (f32, f32, f32),
+        end: (f32, f32, f32),
+    ) -> Option<(f32, f32)> {
+        let radius = self.radius();
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let offset = y - start.1;
+
+        if dy.abs() <= f32::EPSILON {
+            if offset.abs() > radius {
+                return None;
+            }
+            let half = (radius * radius - offset * offset).sqrt();
+            return Some((start.0.min(end.0) - half, start.0.max(end.0) + half));
+        }
+
+        // With the tool axis `u` below the row, its X is `x0 - slope * u`
+        let slope = dx / dy;
+        let x0 = start.0 + slope * offset;
+        let lo = (offset - dy).min(offset).max(-radius);
+        let hi = (offset - dy).max(offset).min(radius);
+        if lo > hi {
+            return None;
+        }
+        let edge =
+            |u: f32, side: f32| x0 - slope * u + side * (radius * radius - u * u).max(0.0).sqrt();
+        let turn = slope * radius / (1.0 + slope * slope).sqrt();
+        Some((
+            edge(turn.clamp(lo, hi), -1.0),
+            edge((-turn).clamp(lo, hi), 1.0),
+        ))
+    }
+}
+
+/// Height of a cone with the given included angle at distance `r` from its tip axis
+fn cone_height(r: f32, tip_angle: f32) -> f32 {
+    r / half_angle(tip_angle).tan()
+}
+
+/// Half of an included tip angle, in radians
+fn half_angle(tip_angle: f32) -> f32 {
+    (tip_angle.clamp(1.0, 179.0) / 2.0).to_radians()
+}
+
+impl From<&Tool> for ToolProfile {
+    fn from(tool: &Tool) -> Self {
+        tool.profile()
+    }
+}
+
 /// Tool library — manages a collection of tools.
 ///
 /// Provides add, remove, search, and filter operations. Standard tools
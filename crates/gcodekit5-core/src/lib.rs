@@ -9,6 +9,7 @@ pub mod core;
 pub mod data;
 pub mod error;
 pub mod event_bus;
+pub mod post;
 pub mod types;
 pub mod units;
 
@@ -25,6 +26,11 @@ pub use data::{
 
 pub use error::{ConnectionError, ControllerError, Error, FirmwareError, GcodeError, Result};
 
+pub use post::{
+    apply_post, ArcOutputMode, CommentStyle, LineNumbering, NumberFormat, PostContext,
+    PostDefinition, PostError, PostLibrary,
+};
+
 // Re-export event bus for convenience
 pub use event_bus::{
     event_bus, AppEvent, EventBus, EventBusConfig, EventCategory, EventFilter, SubscriptionId,
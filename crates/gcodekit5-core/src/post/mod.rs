@@ -0,0 +1,1208 @@
+//! Post-processors
+//!
+//! A post-processor rewrites the generic G-code produced by the designer and
+//! CAM tools for a particular controller. Each one is described by a
+//! [`PostDefinition`], a JSON document users can copy and edit, covering:
+//! - Header and footer templates
+//! - Tool-change and spindle blocks
+//! - Number formatting per address letter
+//! - Line numbering
+//! - Arc output (I/J offsets, R radius or line segments)
+//! - Maximum line length, comment style and the accepted word set
+//!
+//! Definitions for GRBL, grblHAL, LinuxCNC, Mach3, Marlin and Smoothieware
+//! are built in; [`PostLibrary`] combines them with user definition files.
+
+use crate::Units;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+
+const BUILTIN_DEFINITIONS: [&str; 6] = [
+    include_str!("definitions/grbl.json"),
+    include_str!("definitions/grblhal.json"),
+    include_str!("definitions/linuxcnc.json"),
+    include_str!("definitions/mach3.json"),
+    include_str!("definitions/marlin.json"),
+    include_str!("definitions/smoothieware.json"),
+];
+
+/// Placeholders available in every template
+const CONTEXT_PLACEHOLDERS: [&str; 8] = [
+    "program", "post", "date", "units", "safe_z", "tool", "speed", "feed",
+];
+
+/// Post-processor errors
+#[derive(Error, Debug)]
+pub enum PostError {
+    /// Definition could not be read or written
+    #[error("Post-processor I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Definition is not valid JSON for a post-processor
+    #[error("Invalid post-processor definition: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    /// Definition parses but cannot be used
+    #[error("Post-processor '{name}': {message}")]
+    Invalid {
+        /// Name of the definition
+        name: String,
+        /// What is wrong with it
+        message: String,
+    },
+}
+
+/// How arcs are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArcOutputMode {
+    /// G2/G3 with I/J/K centre offsets
+    #[default]
+    Ij,
+    /// G2/G3 with an R radius (full circles are split in two)
+    R,
+    /// G1 segments within `arc_tolerance`
+    Linear,
+}
+
+/// How comments are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentStyle {
+    /// `; comment`
+    #[default]
+    Semicolon,
+    /// `(comment)`
+    Parentheses,
+    /// Comments are removed
+    Strip,
+}
+
+/// Formatting of the value after an address letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NumberFormat {
+    /// Digits after the decimal point
+    pub decimals: u8,
+    /// Remove trailing zeros (and a trailing point)
+    #[serde(default = "default_true")]
+    pub trim_zeros: bool,
+    /// Keep the point on whole numbers (`X10.`), as Fanuc-style controls expect
+    #[serde(default)]
+    pub force_decimal_point: bool,
+    /// Write `0.5` rather than `.5`
+    #[serde(default = "default_true")]
+    pub leading_zero: bool,
+}
+
+impl NumberFormat {
+    pub fn new(decimals: u8) -> Self {
+        Self {
+            decimals,
+            trim_zeros: true,
+            force_decimal_point: false,
+            leading_zero: true,
+        }
+    }
+
+    /// Format a value
+    pub fn format(&self, value: f64) -> String {
+        let mut text = format!("{:.*}", self.decimals as usize, value);
+        if self.trim_zeros && text.contains('.') {
+            text = text.trim_end_matches('0').to_string();
+        }
+        if text.ends_with('.') && !self.force_decimal_point {
+            text.pop();
+        } else if self.force_decimal_point && !text.contains('.') {
+            text.push('.');
+        }
+        if text == "-0" || text == "-0." {
+            text.remove(0);
+        }
+        if !self.leading_zero {
+            if let Some(rest) = text.strip_prefix("0.") {
+                if !rest.is_empty() {
+                    text = format!(".{}", rest);
+                }
+            } else if let Some(rest) = text.strip_prefix("-0.") {
+                text = format!("-.{}", rest);
+            }
+        }
+        text
+    }
+}
+
+/// Line numbering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineNumbering {
+    pub enabled: bool,
+    #[serde(default = "default_line_start")]
+    pub start: u32,
+    #[serde(default = "default_line_increment")]
+    pub increment: u32,
+    /// Restart from `start` after this number
+    #[serde(default)]
+    pub max: Option<u32>,
+}
+
+impl Default for LineNumbering {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: default_line_start(),
+            increment: default_line_increment(),
+            max: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_line_start() -> u32 {
+    10
+}
+
+fn default_line_increment() -> u32 {
+    10
+}
+
+fn default_arc_tolerance() -> f64 {
+    0.01
+}
+
+fn default_separator() -> String {
+    " ".to_string()
+}
+
+fn default_formats() -> BTreeMap<String, NumberFormat> {
+    let mut formats = BTreeMap::new();
+    formats.insert("XYZIJKR".to_string(), NumberFormat::new(3));
+    formats.insert("ABC".to_string(), NumberFormat::new(3));
+    formats.insert("F".to_string(), NumberFormat::new(1));
+    formats.insert("S".to_string(), NumberFormat::new(0));
+    formats.insert("P".to_string(), NumberFormat::new(3));
+    formats
+}
+
+/// Values substituted into templates
+///
+/// Templates use `{name}` placeholders: `program`, `post`, `date`, `units`
+/// (`G21`/`G20`), `safe_z`, `tool`, `speed` and `feed`, plus `{var.name}` for
+/// values added with [`Self::with_var`]. Tool-change blocks also see
+/// `prev_tool`, and spindle blocks see `direction` (`M3`/`M4`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostContext {
+    pub program_name: String,
+    pub units: Units,
+    pub safe_z: f64,
+    pub tool: u32,
+    pub spindle_speed: f64,
+    pub feed_rate: f64,
+    pub vars: BTreeMap<String, String>,
+}
+
+impl Default for PostContext {
+    fn default() -> Self {
+        Self {
+            program_name: "Untitled".to_string(),
+            units: Units::MM,
+            safe_z: 5.0,
+            tool: 1,
+            spindle_speed: 0.0,
+            feed_rate: 0.0,
+            vars: BTreeMap::new(),
+        }
+    }
+}
+
+impl PostContext {
+    pub fn new(program_name: impl Into<String>) -> Self {
+        Self {
+            program_name: program_name.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_units(mut self, units: Units) -> Self {
+        self.units = units;
+        self
+    }
+
+    pub fn with_safe_z(mut self, safe_z: f64) -> Self {
+        self.safe_z = safe_z;
+        self
+    }
+
+    pub fn with_tool(mut self, tool: u32) -> Self {
+        self.tool = tool;
+        self
+    }
+
+    pub fn with_spindle_speed(mut self, speed: f64) -> Self {
+        self.spindle_speed = speed;
+        self
+    }
+
+    pub fn with_feed_rate(mut self, feed: f64) -> Self {
+        self.feed_rate = feed;
+        self
+    }
+
+    /// Add a custom template value
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// A post-processor definition
+///
+/// Definitions are stored as JSON; every field except `name` is optional.
+/// Templates are lists of lines. An empty tool-change or spindle block
+/// leaves those commands as they are; an empty footer keeps the program's
+/// own `M2`/`M30`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Lines written before the program
+    #[serde(default)]
+    pub header: Vec<String>,
+    /// Lines written in place of the program end (`M2`/`M30`)
+    #[serde(default)]
+    pub footer: Vec<String>,
+    /// Block replacing `M6`
+    #[serde(default)]
+    pub tool_change: Vec<String>,
+    /// Block replacing `M3`/`M4`
+    #[serde(default)]
+    pub spindle_on: Vec<String>,
+    /// Block replacing `M5`
+    #[serde(default)]
+    pub spindle_off: Vec<String>,
+    /// Number formats keyed by address letters; `"XYZ"` sets all three
+    #[serde(default = "default_formats")]
+    pub formats: BTreeMap<String, NumberFormat>,
+    #[serde(default)]
+    pub line_numbers: LineNumbering,
+    #[serde(default)]
+    pub arc_mode: ArcOutputMode,
+    /// Largest distance between a linearised arc and the true arc
+    #[serde(default = "default_arc_tolerance")]
+    pub arc_tolerance: f64,
+    #[serde(default)]
+    pub max_line_length: Option<usize>,
+    #[serde(default)]
+    pub comment_style: CommentStyle,
+    /// Accepted words: a letter (`"X"`) allows any value, a code (`"G38.2"`)
+    /// allows just that one. Empty accepts everything. Lines using other G
+    /// or M codes are commented out; other words are dropped.
+    #[serde(default)]
+    pub allowed_words: Vec<String>,
+    #[serde(default = "default_separator")]
+    pub word_separator: String,
+}
+
+impl Default for PostDefinition {
+    fn default() -> Self {
+        Self {
+            name: "Generic".to_string(),
+            description: String::new(),
+            header: Vec::new(),
+            footer: Vec::new(),
+            tool_change: Vec::new(),
+            spindle_on: Vec::new(),
+            spindle_off: Vec::new(),
+            formats: default_formats(),
+            line_numbers: LineNumbering::default(),
+            arc_mode: ArcOutputMode::default(),
+            arc_tolerance: default_arc_tolerance(),
+            max_line_length: None,
+            comment_style: CommentStyle::default(),
+            allowed_words: Vec::new(),
+            word_separator: default_separator(),
+        }
+    }
+}
+
+impl PostDefinition {
+    /// All built-in definitions
+    pub fn builtins() -> Vec<Self> {
+        BUILTIN_DEFINITIONS
+            .iter()
+            .map(|json| Self::from_json(json).expect("built-in post-processor is valid"))
+            .collect()
+    }
+
+    /// Built-in definition by name (case-insensitive)
+    pub fn builtin(name: &str) -> Option<Self> {
+        Self::builtins()
+            .into_iter()
+            .find(|d| d.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Parse and validate a JSON definition
+    pub fn from_json(json: &str) -> Result<Self, PostError> {
+        let definition: Self = serde_json::from_str(json)?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    pub fn to_json(&self) -> Result<String, PostError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<Self, PostError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<(), PostError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Check templates, formats and limits
+    pub fn validate(&self) -> Result<(), PostError> {
+        let invalid = |message: String| PostError::Invalid {
+            name: self.name.clone(),
+            message,
+        };
+        if self.name.trim().is_empty() {
+            return Err(invalid("name is empty".to_string()));
+        }
+        let blocks: [(&[String], &[&str]); 5] = [
+            (&self.header, &[]),
+            (&self.footer, &[]),
+            (&self.tool_change, &["prev_tool"]),
+            (&self.spindle_on, &["direction"]),
+            (&self.spindle_off, &[]),
+        ];
+        for (lines, extra) in blocks {
+            for line in lines {
+                for placeholder in placeholders(line) {
+                    if !CONTEXT_PLACEHOLDERS.contains(&placeholder) && !extra.contains(&placeholder)
+                    {
+                        // Custom values come from the context at run time
+                        if !placeholder.starts_with("var.") {
+                            return Err(invalid(format!(
+                                "unknown placeholder {{{}}} in '{}'",
+                                placeholder, line
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+        for (letters, format) in &self.formats {
+            if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+                return Err(invalid(format!(
+                    "format key '{}' is not address letters",
+                    letters
+                )));
+            }
+            if format.decimals > 6 {
+                return Err(invalid(format!(
+                    "{} decimals for {} (max 6)",
+                    format.decimals, letters
+                )));
+            }
+        }
+        for word in &self.allowed_words {
+            let mut chars = word.chars();
+            let valid = chars.next().is_some_and(|c| c.is_ascii_uppercase())
+                && chars
+                    .as_str()
+                    .parse::<f64>()
+                    .map_or(chars.as_str().is_empty(), |_| true);
+            if !valid {
+                return Err(invalid(format!(
+                    "'{}' is not a word or address letter",
+                    word
+                )));
+            }
+        }
+        if self.arc_tolerance <= 0.0 {
+            return Err(invalid("arc tolerance must be positive".to_string()));
+        }
+        if self.max_line_length.is_some_and(|len| len < 8) {
+            return Err(invalid("maximum line length is too short".to_string()));
+        }
+        if self.line_numbers.enabled && self.line_numbers.increment == 0 {
+            return Err(invalid("line number increment is zero".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Number format for an address letter
+    pub fn format_for(&self, letter: char) -> Option<&NumberFormat> {
+        self.formats
+            .iter()
+            .find(|(letters, _)| letters.contains(letter))
+            .map(|(_, format)| format)
+    }
+
+    /// Rewrite a program for this controller
+    pub fn process(&self, gcode: &str, context: &PostContext) -> String {
+        let mut run = PostRun::new(self, context);
+        for line in &self.header {
+            run.emit_template(line, &[]);
+        }
+        for line in gcode.lines() {
+            if run.ended {
+                break;
+            }
+            run.process_line(line);
+        }
+        if !run.ended {
+            for line in &self.footer {
+                run.emit_template(line, &[]);
+            }
+        }
+        run.out
+    }
+
+    /// Rewrite a single line (formatting, words and comments only)
+    ///
+    /// No header, footer, numbering or block substitution; arcs keep their
+    /// form since the start point is unknown.
+    pub fn convert_line(&self, line: &str) -> String {
+        let Ok(tokens) = tokenize(line) else {
+            return line.to_string();
+        };
+        let (words, comment) = split_tokens(tokens);
+        let words: Vec<Word> = words.into_iter().filter(|w| w.letter != 'N').collect();
+        let context = PostContext::default();
+        let run = PostRun::new(self, &context);
+        run.format_line(&words, comment.as_deref(), None)
+            .unwrap_or_default()
+    }
+}
+
+/// Run a generated program through an optional post-processor
+///
+/// Generators keep their own output when no post-processor is selected.
+pub fn apply_post(post: Option<&PostDefinition>, gcode: String, context: &PostContext) -> String {
+    match post {
+        Some(post) => post.process(&gcode, context),
+        None => gcode,
+    }
+}
+
+/// Built-in and user post-processor definitions
+#[derive(Debug, Clone, Default)]
+pub struct PostLibrary {
+    definitions: Vec<PostDefinition>,
+}
+
+impl PostLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Library holding the built-in definitions
+    pub fn with_builtins() -> Self {
+        Self {
+            definitions: PostDefinition::builtins(),
+        }
+    }
+
+    /// Add a definition, replacing one with the same name
+    pub fn add(&mut self, definition: PostDefinition) {
+        match self
+            .definitions
+            .iter_mut()
+            .find(|d| d.name.eq_ignore_ascii_case(&definition.name))
+        {
+            Some(existing) => *existing = definition,
+            None => self.definitions.push(definition),
+        }
+    }
+
+    /// Add every `*.json` definition in a directory
+    ///
+    /// Returns the files that could not be loaded; the rest are still added.
+    pub fn load_dir(
+        &mut self,
+        dir: &Path,
+    ) -> Result<Vec<(std::path::PathBuf, PostError)>, PostError> {
+        let mut failures = Vec::new();
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match PostDefinition::load_from_file(&path) {
+                Ok(definition) => self.add(definition),
+                Err(e) => failures.push((path, e)),
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Definition by name (case-insensitive)
+    pub fn get(&self, name: &str) -> Option<&PostDefinition> {
+        self.definitions
+            .iter()
+            .find(|d| d.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.definitions.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    pub fn definitions(&self) -> &[PostDefinition] {
+        &self.definitions
+    }
+}
+
+/// `{name}` placeholders in a template line
+fn placeholders(line: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        found.push(&rest[start + 1..start + len]);
+        rest = &rest[start + len + 1..];
+    }
+    found
+}
+
+#[derive(Debug, Clone)]
+struct Word {
+    letter: char,
+    value: f64,
+    text: String,
+}
+
+impl Word {
+    fn new(letter: char, value: f64) -> Self {
+        Self {
+            letter,
+            value,
+            text: String::new(),
+        }
+    }
+
+    /// Normalised code such as `G1` or `G38.2`
+    fn code(&self) -> String {
+        format!("{}{}", self.letter, NumberFormat::new(4).format(self.value))
+    }
+
+    fn is(&self, letter: char, value: f64) -> bool {
+        self.letter == letter && (self.value - value).abs() < 1e-6
+    }
+}
+
+enum Token {
+    Word(Word),
+    Comment(String),
+}
+
+/// Split a line into words and comments; fails on anything that is not
+/// plain G-code (`$` commands, parameters, expressions)
+fn tokenize(line: &str) -> Result<Vec<Token>, ()> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => {
+                let mut text = String::new();
+                for c in chars.by_ref() {
+                    if c == ')' {
+                        break;
+                    }
+                    text.push(c);
+                }
+                tokens.push(Token::Comment(text.trim().to_string()));
+            }
+            ';' => {
+                let text: String = chars.by_ref().collect();
+                tokens.push(Token::Comment(text.trim().to_string()));
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut number = String::new();
+                while let Some(&d) = chars.peek() {
+                    if d.is_ascii_digit() || matches!(d, '.' | '-' | '+') {
+                        number.push(d);
+                        chars.next();
+                    } else if d == ' ' && number.is_empty() {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let value = number.parse::<f64>().map_err(|_| ())?;
+                let letter = c.to_ascii_uppercase();
+                tokens.push(Token::Word(Word {
+                    letter,
+                    value,
+                    text: format!("{}{}", letter, number),
+                }));
+            }
+            _ => return Err(()),
+        }
+    }
+    Ok(tokens)
+}
+
+fn split_tokens(tokens: Vec<Token>) -> (Vec<Word>, Option<String>) {
+    let mut words = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Word(w) => words.push(w),
+            Token::Comment(c) if !c.is_empty() => comments.push(c),
+            Token::Comment(_) => {}
+        }
+    }
+    let comment = (!comments.is_empty()).then(|| comments.join(" "));
+    (words, comment)
+}
+
+/// Extra placeholder values for a template block
+type TemplateVars = Vec<(&'static str, String)>;
+
+/// State while rewriting one program
+struct PostRun<'a> {
+    definition: &'a PostDefinition,
+    context: &'a PostContext,
+    out: String,
+    line_number: u32,
+    pos: [Option<f64>; 3],
+    relative: bool,
+    plane: u8,
+    /// Motion mode ×10 (G38.2 → 382), `None` when unknown
+    motion: Option<i32>,
+    tool: u32,
+    pending_tool: Option<u32>,
+    speed: f64,
+    ended: bool,
+}
+
+impl<'a> PostRun<'a> {
+    fn new(definition: &'a PostDefinition, context: &'a PostContext) -> Self {
+        Self {
+            definition,
+            context,
+            out: String::new(),
+            line_number: definition.line_numbers.start,
+            pos: [Some(0.0); 3],
+            relative: false,
+            plane: 17,
+            motion: None,
+            tool: context.tool,
+            pending_tool: None,
+            speed: context.spindle_speed,
+            ended: false,
+        }
+    }
+
+    fn process_line(&mut self, line: &str) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            self.out.push('\n');
+            return;
+        }
+        if trimmed == "%" {
+            return;
+        }
+        let Ok(tokens) = tokenize(trimmed) else {
+            // Controller commands like `$H` go out unchanged
+            self.emit(trimmed.to_string(), false);
+            return;
+        };
+        let (words, comment) = split_tokens(tokens);
+        let mut words: Vec<Word> = words.into_iter().filter(|w| w.letter != 'N').collect();
+
+        // Words that change state or are replaced by blocks
+        let mut blocks: Vec<(&'a [String], TemplateVars)> = Vec::new();
+        let mut program_end = false;
+        // Moves in other coordinates, and offsets, make the position unknown
+        let untracked = words.iter().any(|w| {
+            w.letter == 'G'
+                && ([28.0, 30.0, 53.0].contains(&w.value.floor())
+                    || (w.value.floor() == 92.0 && w.value != 92.0))
+        });
+        for word in &words {
+            match word.letter {
+                'G' => match (word.value * 10.0).round() as i32 {
+                    170 => self.plane = 17,
+                    180 => self.plane = 18,
+                    190 => self.plane = 19,
+                    900 => self.relative = false,
+                    910 => self.relative = true,
+                    code @ (0 | 10 | 20 | 30 | 800..=890 | 380..=385) => self.motion = Some(code),
+                    _ => {}
+                },
+                'T' => self.pending_tool = Some(word.value.max(0.0) as u32),
+                'S' => self.speed = word.value,
+                _ => {}
+            }
+        }
+        let definition = self.definition;
+        if !definition.tool_change.is_empty() && words.iter().any(|w| w.is('M', 6.0)) {
+            let tool = self.pending_tool.take().unwrap_or(self.tool);
+            let vars = vec![
+                ("tool", tool.to_string()),
+                ("prev_tool", self.tool.to_string()),
+            ];
+            self.tool = tool;
+            words.retain(|w| w.letter != 'T' && !w.is('M', 6.0));
+            blocks.push((definition.tool_change.as_slice(), vars));
+        }
+        let spindle_on = words
+            .iter()
+            .find(|w| w.is('M', 3.0) || w.is('M', 4.0))
+            .map(Word::code);
+        if let (false, Some(direction)) = (definition.spindle_on.is_empty(), spindle_on) {
+            let vars = vec![
+                ("direction", direction),
+                ("speed", self.format_value('S', self.speed)),
+            ];
+            words.retain(|w| !w.is('M', 3.0) && !w.is('M', 4.0) && w.letter != 'S');
+            blocks.push((definition.spindle_on.as_slice(), vars));
+        }
+        if !definition.spindle_off.is_empty() && words.iter().any(|w| w.is('M', 5.0)) {
+            words.retain(|w| !w.is('M', 5.0));
+            blocks.push((definition.spindle_off.as_slice(), Vec::new()));
+        }
+        if !definition.footer.is_empty() && words.iter().any(|w| w.is('M', 2.0) || w.is('M', 30.0))
+        {
+            words.retain(|w| !w.is('M', 2.0) && !w.is('M', 30.0));
+            program_end = true;
+        }
+
+        let has_axes = words.iter().any(|w| matches!(w.letter, 'X' | 'Y' | 'Z'));
+        let has_arc_words = words
+            .iter()
+            .any(|w| matches!(w.letter, 'I' | 'J' | 'K' | 'R'));
+        let sets_position = words.iter().any(|w| w.is('G', 92.0));
+        let is_arc = matches!(self.motion, Some(20) | Some(30))
+            && (has_axes || has_arc_words)
+            && !untracked
+            && !sets_position;
+
+        let mut lines: Vec<Vec<Word>> = Vec::new();
+        if is_arc {
+            match self.rewrite_arc(&words) {
+                Some(rewritten) => lines = rewritten,
+                None => lines.push(words.clone()),
+            }
+        } else {
+            lines.push(words.clone());
+        }
+        self.track_position(&words, untracked, sets_position);
+
+        let mut comment = comment;
+        for line_words in &lines {
+            self.emit_words(line_words, comment.as_deref());
+            comment = None;
+        }
+        for (block, vars) in blocks {
+            for template in block {
+                self.emit_template(template, &vars);
+            }
+        }
+        if program_end {
+            for template in &definition.footer {
+                self.emit_template(template, &[]);
+            }
+            self.ended = true;
+        }
+    }
+
+    /// Update the tracked position after a move
+    fn track_position(&mut self, words: &[Word], untracked: bool, sets_position: bool) {
+        for (axis, letter) in ['X', 'Y', 'Z'].into_iter().enumerate() {
+            if let Some(word) = words.iter().find(|w| w.letter == letter) {
+                self.pos[axis] = if sets_position {
+                    Some(word.value)
+                } else if untracked || matches!(self.motion, Some(380..=385)) {
+                    None
+                } else if self.relative {
+                    self.pos[axis].map(|p| p + word.value)
+                } else {
+                    Some(word.value)
+                };
+            }
+        }
+        if words.iter().any(|w| w.is('G', 28.0) || w.is('G', 30.0)) {
+            self.pos = [None; 3];
+        }
+        if matches!(self.motion, Some(810..=890)) && !sets_position {
+            // Canned cycles end at the R plane or initial Z
+            self.pos[2] = None;
+        }
+    }
+
+    /// Arc in the requested output form, or `None` to keep it as is
+    fn rewrite_arc(&self, words: &[Word]) -> Option<Vec<Vec<Word>>> {
+        let mode = self.definition.arc_mode;
+        let has_r = words.iter().any(|w| w.letter == 'R');
+        if mode == ArcOutputMode::Ij && !has_r {
+            return None;
+        }
+        if mode == ArcOutputMode::R && has_r {
+            return None;
+        }
+
+        // Axis indices for the plane: first, second, helical
+        let (a0, a1, a2, o0, o1) = match self.plane {
+            18 => (2, 0, 1, 'K', 'I'),
+            19 => (1, 2, 0, 'J', 'K'),
+            _ => (0, 1, 2, 'I', 'J'),
+        };
+        let axis_letter = |axis: usize| ['X', 'Y', 'Z'][axis];
+        let value = |letter: char| words.iter().find(|w| w.letter == letter).map(|w| w.value);
+        let start = [self.pos[0]?, self.pos[1]?, self.pos[2]?];
+        let mut end = start;
+        for axis in 0..3 {
+            if let Some(v) = value(axis_letter(axis)) {
+                end[axis] = if self.relative { start[axis] + v } else { v };
+            }
+        }
+        let clockwise = self.motion == Some(20);
+        let (s0, s1) = (start[a0], start[a1]);
+        let (e0, e1) = (end[a0], end[a1]);
+
+        let center = if let Some(r) = value('R') {
+            let (dx, dy) = (e0 - s0, e1 - s1);
+            let chord = (dx * dx + dy * dy).sqrt();
+            if chord < 1e-9 || chord > 2.0 * r.abs() + 1e-6 {
+                return None;
+            }
+            let h = (r * r - chord * chord / 4.0).max(0.0).sqrt();
+            // Centre to the right of the chord for CW short arcs
+            let side = if clockwise == (r > 0.0) { -1.0 } else { 1.0 };
+            let (mx, my) = ((s0 + e0) / 2.0, (s1 + e1) / 2.0);
+            (mx - side * h * dy / chord, my + side * h * dx / chord)
+        } else {
+            (s0 + value(o0).unwrap_or(0.0), s1 + value(o1).unwrap_or(0.0))
+        };
+        let radius = (s0 - center.0).hypot(s1 - center.1);
+        if radius < 1e-9 {
+            return None;
+        }
+        let start_angle = (s1 - center.1).atan2(s0 - center.0);
+        let end_angle = (e1 - center.1).atan2(e0 - center.0);
+        let mut sweep = end_angle - start_angle;
+        if clockwise {
+            if sweep >= -1e-9 {
+                sweep -= std::f64::consts::TAU;
+            }
+        } else if sweep <= 1e-9 {
+            sweep += std::f64::consts::TAU;
+        }
+
+        let others: Vec<Word> = words
+            .iter()
+            .filter(|w| !matches!(w.letter, 'X' | 'Y' | 'Z' | 'I' | 'J' | 'K' | 'R'))
+            .filter(|w| !(w.letter == 'G' && (w.value == 2.0 || w.value == 3.0)))
+            .cloned()
+            .collect();
+        let point_at = |t: f64| {
+            let angle = start_angle + sweep * t;
+            let mut p = [0.0; 3];
+            p[a0] = center.0 + radius * angle.cos();
+            p[a1] = center.1 + radius * angle.sin();
+            p[a2] = start[a2] + (end[a2] - start[a2]) * t;
+            p
+        };
+        let target_words = |from: [f64; 3], to: [f64; 3]| -> Vec<Word> {
+            (0..3)
+                .filter(|&axis| {
+                    axis != a2
+                        || (to[a2] - from[a2]).abs() > 1e-9
+                        || value(axis_letter(axis)).is_some()
+                })
+                .map(|axis| {
+                    let v = if self.relative {
+                        to[axis] - from[axis]
+                    } else {
+                        to[axis]
+                    };
+                    Word::new(axis_letter(axis), v)
+                })
+                .collect()
+        };
+
+        let mut lines = Vec::new();
+        match mode {
+            ArcOutputMode::Linear => {
+                let tolerance = self.definition.arc_tolerance.min(radius);
+                let step = 2.0 * (1.0 - tolerance / radius).acos();
+                let segments = ((sweep.abs() / step).ceil() as usize).max(1);
+                let mut from = start;
+                for i in 1..=segments {
+                    let to = if i == segments {
+                        end
+                    } else {
+                        point_at(i as f64 / segments as f64)
+                    };
+                    let mut line = vec![Word::new('G', 1.0)];
+                    line.extend(target_words(from, to));
+                    if i == 1 {
+                        line.extend(others.iter().cloned());
+                    }
+                    lines.push(line);
+                    from = to;
+                }
+            }
+            ArcOutputMode::R => {
+                let code = if clockwise { 2.0 } else { 3.0 };
+                // R cannot describe a full circle; write two halves
+                let halves = if sweep.abs() > std::f64::consts::PI * 1.999 {
+                    2
+                } else {
+                    1
+                };
+                let mut from = start;
+                for i in 1..=halves {
+                    let to = if i == halves { end } else { point_at(0.5) };
+                    let part = sweep.abs() / halves as f64;
+                    let r = if part > std::f64::consts::PI + 1e-9 {
+                        -radius
+                    } else {
+                        radius
+                    };
+                    let mut line = vec![Word::new('G', code)];
+                    line.extend(target_words(from, to));
+                    line.push(Word::new('R', r));
+                    if i == 1 {
+                        line.extend(others.iter().cloned());
+                    }
+                    lines.push(line);
+                    from = to;
+                }
+            }
+            ArcOutputMode::Ij => {
+                let code = if clockwise { 2.0 } else { 3.0 };
+                let mut line = vec![Word::new('G', code)];
+                line.extend(target_words(start, end));
+                line.push(Word::new(o0, center.0 - s0));
+                line.push(Word::new(o1, center.1 - s1));
+                line.extend(others.iter().cloned());
+                lines.push(line);
+            }
+        }
+        Some(lines)
+    }
+
+    fn format_value(&self, letter: char, value: f64) -> String {
+        match self.definition.format_for(letter) {
+            Some(format) => format.format(value),
+            None => NumberFormat::new(4).format(value),
+        }
+    }
+
+    fn allowed(&self, word: &Word) -> bool {
+        let allowed = &self.definition.allowed_words;
+        if allowed.is_empty() {
+            return true;
+        }
+        allowed.iter().any(|a| {
+            let Some(rest) = a.strip_prefix(word.letter) else {
+                return false;
+            };
+            rest.is_empty()
+                || rest
+                    .parse::<f64>()
+                    .is_ok_and(|v| (v - word.value).abs() < 1e-6)
+        })
+    }
+
+    /// Words and comment as output text, `None` if nothing is left
+    fn format_line(
+        &self,
+        words: &[Word],
+        comment: Option<&str>,
+        line_number: Option<u32>,
+    ) -> Option<String> {
+        let definition = self.definition;
+        let mut parts: Vec<String> = Vec::with_capacity(words.len());
+        let mut comment = comment.map(str::to_string);
+        let unsupported: Vec<String> = words
+            .iter()
+            .filter(|w| matches!(w.letter, 'G' | 'M') && !self.allowed(w))
+            .map(Word::code)
+            .collect();
+        if !unsupported.is_empty() {
+            // Running the rest of the line without these could be unsafe
+            tracing::warn!(
+                "{}: unsupported {} commented out",
+                definition.name,
+                unsupported.join(" ")
+            );
+            let line: Vec<String> = words.iter().map(|w| w.text_or_code()).collect();
+            comment = Some(format!("unsupported: {}", line.join(" ")));
+        } else {
+            for word in words.iter().filter(|w| self.allowed(w)) {
+                let text = match definition.format_for(word.letter) {
+                    Some(format) => format!("{}{}", word.letter, format.format(word.value)),
+                    None => word.text_or_code(),
+                };
+                parts.push(text);
+            }
+        }
+
+        let mut code = parts.join(&definition.word_separator);
+        if let (false, Some(number)) = (code.is_empty(), line_number) {
+            code = format!("N{}{}{}", number, definition.word_separator, code);
+        }
+        let comment = match (definition.comment_style, comment) {
+            (CommentStyle::Strip, _) | (_, None) => None,
+            (style, Some(text)) => Some(format_comment(style, &text)),
+        };
+        let mut line = match (&comment, code.is_empty()) {
+            (Some(c), true) => c.clone(),
+            (Some(c), false) => format!("{} {}", code, c),
+            (None, false) => code.clone(),
+            (None, true) => return None,
+        };
+        if let Some(max) = definition.max_line_length {
+            if line.len() > max {
+                line = match comment {
+                    Some(c) if code.is_empty() || code.len() + 4 < max => {
+                        let room = if code.is_empty() {
+                            max
+                        } else {
+                            max - code.len() - 1
+                        };
+                        let shortened = shorten_comment(definition.comment_style, &c, room);
+                        if code.is_empty() {
+                            shortened
+                        } else {
+                            format!("{} {}", code, shortened)
+                        }
+                    }
+                    _ => code.clone(),
+                };
+                if line.len() > max {
+                    tracing::warn!(
+                        "{}: line exceeds {} characters: {}",
+                        definition.name,
+                        max,
+                        line
+                    );
+                }
+            }
+        }
+        Some(line)
+    }
+
+    /// Render a template line and write it out
+    fn emit_template(&mut self, template: &str, extra: &[(&str, String)]) {
+        let mut line = template.to_string();
+        for placeholder in placeholders(template) {
+            let value = extra
+                .iter()
+                .find(|(name, _)| *name == placeholder)
+                .map(|(_, v)| v.clone())
+                .or_else(|| self.context_value(placeholder));
+            if let Some(value) = value {
+                line = line.replacen(&format!("{{{}}}", placeholder), &value, 1);
+            }
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            self.out.push('\n');
+            return;
+        }
+        match tokenize(trimmed) {
+            Ok(tokens) => {
+                let (words, comment) = split_tokens(tokens);
+                self.emit_words(&words, comment.as_deref());
+            }
+            Err(()) => self.emit(trimmed.to_string(), false),
+        }
+    }
+
+    fn context_value(&self, name: &str) -> Option<String> {
+        let context = self.context;
+        let value = match name {
+            "program" => context.program_name.clone(),
+            "post" => self.definition.name.clone(),
+            "date" => chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            "units" => match context.units {
+                Units::INCH => "G20".to_string(),
+                _ => "G21".to_string(),
+            },
+            "safe_z" => self.format_value('Z', context.safe_z),
+            "tool" => self.tool.to_string(),
+            "speed" => self.format_value('S', self.speed),
+            "feed" => self.format_value('F', context.feed_rate),
+            other => {
+                let key = other.strip_prefix("var.").unwrap_or(other);
+                return context.vars.get(key).cloned();
+            }
+        };
+        Some(value)
+    }
+
+    /// Format and append a line, numbering it when it holds code
+    ///
+    /// Program numbers (`O` words) are never numbered.
+    fn emit_words(&mut self, words: &[Word], comment: Option<&str>) {
+        let numbered = self.definition.line_numbers.enabled
+            && !words.is_empty()
+            && !words.iter().any(|w| w.letter == 'O');
+        let number = numbered.then_some(self.line_number);
+        if let Some(text) = self.format_line(words, comment, number) {
+            let has_code = !text.starts_with([';', '(']);
+            self.emit(text, numbered && has_code);
+        }
+    }
+
+    /// Append a line, advancing the line number if it used one
+    fn emit(&mut self, text: String, numbered: bool) {
+        let numbering = self.definition.line_numbers;
+        if numbered {
+            self.line_number += numbering.increment;
+            if numbering.max.is_some_and(|max| self.line_number > max) {
+                self.line_number = numbering.start;
+            }
+        }
+        self.out.push_str(&text);
+        self.out.push('\n');
+    }
+}
+
+impl Word {
+    fn text_or_code(&self) -> String {
+        if self.text.is_empty() {
+            self.code()
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+fn format_comment(style: CommentStyle, text: &str) -> String {
+    match style {
+        CommentStyle::Parentheses => format!("({})", text.replace('(', "[").replace(')', "]")),
+        _ => format!("; {}", text),
+    }
+}
+
+/// Comment cut to fit `room` characters (possibly empty)
+fn shorten_comment(style: CommentStyle, comment: &str, room: usize) -> String {
+    // "()" or "; "
+    let overhead = 2;
+    if room <= overhead + 1 {
+        return String::new();
+    }
+    let inner = match style {
+        CommentStyle::Parentheses => &comment[1..comment.len() - 1],
+        _ => &comment[2..],
+    };
+    let keep: String = inner.chars().take(room - overhead).collect();
+    format_comment(style, keep.trim_end())
+}
@@ -195,3 +195,166 @@ fn test_tool_case_insensitive_search() {
     assert_eq!(results_lower.len(), results_upper.len());
     assert_eq!(results_lower.len(), results_mixed.len());
 }
+
+#[test]
+fn test_tool_profile_from_tool_type() {
+    let mut ball = Tool::new(
+        ToolId("ball".to_string()),
+        1,
+        "Ball".to_string(),
+        ToolType::EndMillBall,
+        6.0,
+        50.0,
+    );
+    assert_eq!(ball.profile(), ToolProfile::Ball { diameter: 6.0 });
+
+    ball.tool_type = ToolType::VBit;
+    ball.tip_angle = Some(60.0);
+    assert_eq!(
+        ToolProfile::from(&ball),
+        ToolProfile::VBit {
+            diameter: 6.0,
+            tip_angle: 60.0
+        }
+    );
+}
+
+#[test]
+fn test_tool_profile_heights() {
+    let flat = ToolProfile::Flat { diameter: 6.0 };
+    assert_eq!(flat.height_at(2.9), Some(0.0));
+    assert_eq!(flat.height_at(3.1), None);
+
+    let ball = ToolProfile::Ball { diameter: 6.0 };
+    assert_eq!(ball.height_at(0.0), Some(0.0));
+    assert!((ball.height_at(3.0).unwrap() - 3.0).abs() < 1e-5);
+
+    let bull = ToolProfile::BullNose {
+        diameter: 6.0,
+        corner_radius: 1.0,
+    };
+    assert_eq!(bull.height_at(2.0), Some(0.0));
+    assert!((bull.height_at(3.0).unwrap() - 1.0).abs() < 1e-5);
+
+    let tapered = ToolProfile::Tapered {
+        diameter: 6.0,
+        tip_diameter: 2.0,
+        tip_angle: 90.0,
+    };
+    assert_eq!(tapered.height_at(1.0), Some(0.0));
+    assert!((tapered.height_at(2.0).unwrap() - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn test_tool_profile_swept_bottom() {
+    let vbit = ToolProfile::VBit {
+        diameter: 10.0,
+        tip_angle: 90.0,
+    };
+
+    // Level move: depth follows the distance from the line
+    let bottom = vbit
+        .swept_bottom(5.0, 2.0, (0.0, 0.0, -3.0), (10.0, 0.0, -3.0))
+        .expect("point is under the cutter");
+    assert!((bottom - -1.0).abs() < 1e-4);
+    assert!(vbit
+        .swept_bottom(5.0, 6.0, (0.0, 0.0, -3.0), (10.0, 0.0, -3.0))
+        .is_none());
+
+    // Ramp: the deepest point is reached at the end of the move
+    let bottom = vbit
+        .swept_bottom(10.0, 0.0, (0.0, 0.0, 0.0), (10.0, 0.0, -2.0))
+        .expect("point is under the cutter");
+    assert!((bottom - -2.0).abs() < 1e-3);
+
+    // Plunge
+    let flat = ToolProfile::Flat { diameter: 4.0 };
+    assert_eq!(
+        flat.swept_bottom(1.0, 1.0, (0.0, 0.0, 1.0), (0.0, 0.0, -4.0)),
+        Some(-4.0)
+    );
+}
+
+#[test]
+fn test_tool_profile_sweep_span_covers_swept_points() {
+    let ball = ToolProfile::Ball { diameter: 4.0 };
+    let moves = [
+        ((0.0, 0.0, -1.0), (30.0, 17.0, -2.0)),
+        ((5.0, 12.0, -1.0), (-8.0, 1.0, -1.0)),
+        ((3.0, -4.0, 0.0), (3.0, 9.0, -1.0)),
+        ((-6.0, 2.0, -1.0), (7.0, 2.0, -1.0)),
+        ((1.0, 1.0, 0.0), (1.0, 1.0, -3.0)),
+    ];
+    for (start, end) in moves {
+        for row in -40..=80 {
+            let y = row as f32 * 0.25;
+            let span = ball.sweep_span(y, start, end);
+            for column in -60..=140 {
+                let x = column as f32 * 0.25;
+                let covered = ball.swept_bottom(x, y, start, end).is_some();
+                let inside =
+                    span.is_some_and(|(left, right)| x >= left - 1e-3 && x <= right + 1e-3);
+                if covered {
+                    assert!(inside, "({x}, {y}) swept but outside {span:?}");
+                }
+                if let Some((left, right)) = span {
+                    if x > left + 1e-3 && x < right - 1e-3 {
+                        assert!(covered, "({x}, {y}) inside {span:?} but not swept");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tool_profile_contact_radius() {
+    let ball = ToolProfile::Ball { diameter: 6.0 };
+    assert_eq!(ball.contact_radius(0.0), 0.0);
+    assert!((ball.contact_radius(1.0) - 3.0 * 0.5f32.sqrt()).abs() < 1e-5);
+
+    let bull = ToolProfile::BullNose {
+        diameter: 6.0,
+        corner_radius: 1.0,
+    };
+    assert_eq!(bull.contact_radius(0.0), 2.0);
+    assert!((bull.contact_radius(1.0) - (2.0 + 0.5f32.sqrt())).abs() < 1e-5);
+
+    let vbit = ToolProfile::VBit {
+        diameter: 6.0,
+        tip_angle: 90.0,
+    };
+    assert_eq!(vbit.contact_radius(0.5), 0.0);
+    assert_eq!(vbit.contact_radius(2.0), 3.0);
+}
+
+#[test]
+fn test_tool_profile_grown() {
+    let ball = ToolProfile::Ball { diameter: 6.0 };
+    assert_eq!(ball.grown(0.5), ToolProfile::Ball { diameter: 7.0 });
+    assert_eq!(
+        ToolProfile::Flat { diameter: 6.0 }.grown(0.5),
+        ToolProfile::BullNose {
+            diameter: 7.0,
+            corner_radius: 0.5
+        }
+    );
+    assert_eq!(ball.grown(0.0), ball);
+
+    // The grown cone stays at least `amount` clear of the original one
+    let vbit = ToolProfile::VBit {
+        diameter: 6.0,
+        tip_angle: 60.0,
+    };
+    let amount = 0.5;
+    let grown = vbit.grown(amount);
+    let flank = (3.0f32, vbit.height_at(3.0).unwrap());
+    let length = flank.0.hypot(flank.1);
+    for step in 0..=70 {
+        let r = step as f32 * 0.05;
+        let (x, z) = (r, grown.height_at(r).unwrap() - amount);
+        let t = ((x * flank.0 + z * flank.1) / (length * length)).clamp(0.0, 1.0);
+        let distance = (x - t * flank.0).hypot(z - t * flank.1);
+        assert!(distance >= amount - 1e-4, "r = {r}: {distance}");
+    }
+}
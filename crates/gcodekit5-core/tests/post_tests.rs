@@ -0,0 +1,183 @@
+//! Tests for the template-driven post-processor
+//!
+//! Covers the built-in definitions, templates, number formatting, arc
+//! output modes, word filtering and user definition files.
+
+use gcodekit5_core::{
+    ArcOutputMode, CommentStyle, NumberFormat, PostContext, PostDefinition, PostLibrary, Units,
+};
+
+const PROGRAM: &str = "G21 G90\nT2 M6\nM3 S12000\nG0 X0 Y0 Z5\nG1 Z-1.00000 F300 (plunge)\nG2 X10 Y0 I5 J0\nM5\nM30\n";
+
+fn lines(gcode: &str) -> Vec<&str> {
+    gcode.lines().collect()
+}
+
+#[test]
+fn test_builtins_are_valid() {
+    let names: Vec<String> = PostDefinition::builtins()
+        .into_iter()
+        .map(|d| d.name)
+        .collect();
+    for expected in [
+        "GRBL",
+        "grblHAL",
+        "LinuxCNC",
+        "Mach3",
+        "Marlin",
+        "Smoothieware",
+    ] {
+        assert!(names.iter().any(|n| n == expected), "missing {}", expected);
+        let definition = PostDefinition::builtin(expected).unwrap();
+        assert!(definition.validate().is_ok());
+        // Round-trips through JSON
+        let json = definition.to_json().unwrap();
+        assert_eq!(PostDefinition::from_json(&json).unwrap(), definition);
+    }
+    assert!(PostDefinition::builtin("grbl").is_some());
+}
+
+#[test]
+fn test_number_format() {
+    assert_eq!(NumberFormat::new(3).format(1.5), "1.5");
+    assert_eq!(NumberFormat::new(3).format(-0.0001), "0");
+    assert_eq!(NumberFormat::new(0).format(12000.4), "12000");
+    let fanuc = NumberFormat {
+        decimals: 4,
+        trim_zeros: true,
+        force_decimal_point: true,
+        leading_zero: false,
+    };
+    assert_eq!(fanuc.format(10.0), "10.");
+    assert_eq!(fanuc.format(0.25), ".25");
+    assert_eq!(fanuc.format(-0.5), "-.5");
+}
+
+#[test]
+fn test_linuxcnc_blocks_and_comments() {
+    let post = PostDefinition::builtin("LinuxCNC").unwrap();
+    let context = PostContext::new("bracket").with_safe_z(10.0);
+    let out = post.process(PROGRAM, &context);
+    let out = lines(&out);
+
+    assert_eq!(out[0], "%");
+    assert_eq!(out[1], "(bracket)");
+    assert!(out.contains(&"T2 M6"));
+    assert!(out.contains(&"G43 H2"));
+    assert!(out.contains(&"M3 S12000"));
+    assert!(out.contains(&"G1 Z-1 F300 (plunge)"));
+    // Footer replaces M30 and ends the program
+    assert_eq!(&out[out.len() - 3..], ["G0 Z10", "M2", "%"]);
+    assert!(!out.contains(&"M30"));
+}
+
+#[test]
+fn test_mach3_line_numbers() {
+    let post = PostDefinition::builtin("Mach3").unwrap();
+    let out = post.process("G0 X1\n; note\nG1 X2 F100\n", &PostContext::default());
+    let numbered: Vec<&str> = out.lines().filter(|l| l.starts_with('N')).collect();
+    assert!(numbered.iter().any(|l| l.ends_with("G0 X1")));
+    // Comment lines are not numbered, code lines count up by 10
+    assert!(out.contains("\n(note)\n"));
+    let numbers: Vec<u32> = numbered
+        .iter()
+        .map(|l| l[1..l.find(' ').unwrap()].parse().unwrap())
+        .collect();
+    assert!(numbers.windows(2).all(|w| w[1] == w[0] + 10));
+}
+
+#[test]
+fn test_arc_output_modes() {
+    let mut post = PostDefinition {
+        header: Vec::new(),
+        ..PostDefinition::default()
+    };
+    let program = "G0 X10 Y0\nG3 X-10 Y0 I-10 J0\nG2 X-10 Y0 I10 J0\n";
+
+    post.arc_mode = ArcOutputMode::R;
+    let out = post.process(program, &PostContext::default());
+    let out = lines(&out);
+    assert_eq!(out[1], "G3 X-10 Y0 R10");
+    // Full circle is written as two halves
+    assert_eq!(out[2], "G2 X10 Y0 R10");
+    assert_eq!(out[3], "G2 X-10 Y0 R10");
+
+    post.arc_mode = ArcOutputMode::Linear;
+    let out = post.process(program, &PostContext::default());
+    assert!(out.lines().skip(1).all(|l| l.starts_with("G1")));
+    for line in out.lines().skip(1) {
+        let x: f64 = line.split(' ').nth(1).unwrap()[1..].parse().unwrap();
+        let y: f64 = line.split(' ').nth(2).unwrap()[1..].parse().unwrap();
+        assert!(((x * x + y * y).sqrt() - 10.0).abs() < 0.011, "{}", line);
+    }
+
+    // R input comes back as centre offsets
+    post.arc_mode = ArcOutputMode::Ij;
+    let out = post.process("G0 X0 Y0\nG2 X10 Y0 R5\n", &PostContext::default());
+    assert_eq!(lines(&out)[1], "G2 X10 Y0 I5 J0");
+}
+
+#[test]
+fn test_grbl_word_filter_and_line_length() {
+    let post = PostDefinition::builtin("GRBL").unwrap();
+    let long_comment = "x".repeat(120);
+    let program = format!("G81 X1 Y1 Z-2 R1\nG0 X5 ; {}\nG1 X6 Q3\n", long_comment);
+    let out = post.process(&program, &PostContext::default());
+
+    assert!(out.contains("; unsupported: G81 X1 Y1 Z-2 R1"));
+    assert!(out.contains("\nG1 X6\n"), "{}", out);
+    assert!(out.lines().all(|l| l.len() <= 80), "{}", out);
+    // Tool changes pause instead of sending M6
+    let out = post.process("T3 M6\n", &PostContext::default().with_safe_z(8.0));
+    assert!(out.contains("G0 Z8\n; Change to tool 3 and resume\nM0\n"));
+    assert!(!out.contains("M6"));
+}
+
+#[test]
+fn test_passthrough_and_strip_comments() {
+    let post = PostDefinition {
+        comment_style: CommentStyle::Strip,
+        ..PostDefinition::default()
+    };
+    let out = post.process("$H\n(setup)\nG01 X1.00000\n", &PostContext::default());
+    assert_eq!(out, "$H\nG01 X1\n");
+    assert_eq!(post.convert_line("N40 G1 X2.5000 ; cut"), "G1 X2.5");
+}
+
+#[test]
+fn test_templates_and_validation() {
+    let json = r#"{
+        "name": "Custom",
+        "header": ["({program} for {var.machine})", "{units}"],
+        "comment_style": "parentheses"
+    }"#;
+    let post = PostDefinition::from_json(json).unwrap();
+    let context = PostContext::new("part")
+        .with_units(Units::INCH)
+        .with_var("machine", "router");
+    let out = post.process("G0 X1\n", &context);
+    assert!(out.starts_with("(part for router)\nG20\n"));
+
+    let bad = r#"{ "name": "Bad", "header": ["{nope}"] }"#;
+    assert!(PostDefinition::from_json(bad).is_err());
+    let bad = r#"{ "name": "Bad", "allowed_words": ["G1X"] }"#;
+    assert!(PostDefinition::from_json(bad).is_err());
+}
+
+#[test]
+fn test_library_loads_user_definitions() {
+    let dir = std::env::temp_dir().join(format!("gk5_posts_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut grbl = PostDefinition::builtin("GRBL").unwrap();
+    grbl.description = "Customised".to_string();
+    grbl.save_to_file(&dir.join("grbl.json")).unwrap();
+    std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+    let mut library = PostLibrary::with_builtins();
+    let failures = library.load_dir(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(failures.len(), 1);
+    assert_eq!(library.names().len(), 6);
+    assert_eq!(library.get("grbl").unwrap().description, "Customised");
+}
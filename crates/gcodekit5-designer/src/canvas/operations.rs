@@ -184,9 +184,18 @@ impl Canvas {
                 ramp_angle: obj.ramp_angle,
                 pocket_strategy: obj.pocket_strategy,
                 raster_fill_ratio: obj.raster_fill_ratio,
+                cut_side: obj.cut_side,
+                cut_direction: obj.cut_direction,
+                finishing_allowance: obj.finishing_allowance,
+                tabs: obj.tabs.clone(),
+                leads: obj.leads.clone(),
+                vcarve: obj.vcarve.clone(),
+                drill: obj.drill.clone(),
+                tool: obj.tool.clone(),
                 offset: obj.offset,
                 fillet: obj.fillet,
                 chamfer: obj.chamfer,
+                dogbone: obj.dogbone.clone(),
                 lock_aspect_ratio: obj.lock_aspect_ratio,
             };
 
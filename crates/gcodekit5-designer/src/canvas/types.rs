@@ -1,10 +1,17 @@
 //! Canvas type definitions: CanvasSnapshot, CanvasPoint, DrawingMode, DrawingObject, Alignment.
 
+use crate::dogbone::DogboneSettings;
+use crate::drilling_patterns::DrillSettings;
+use crate::leads::LeadSettings;
 use crate::model::{DesignerShape, Point, Shape, ShapeType};
 use crate::pocket_operations::PocketStrategy;
+use crate::profile_operations::{CutDirection, CutSide};
 use crate::shape_store::ShapeStore;
 use crate::shapes::OperationType;
 use crate::spatial_manager::SpatialManager;
+use crate::tabs::TabSettings;
+use crate::tool_changes::AssignedTool;
+use crate::vcarve::VCarveSettings;
 
 /// Snapshot of canvas state for undo/redo
 #[derive(Clone)]
@@ -73,9 +80,20 @@ pub struct DrawingObject {
     pub ramp_angle: f32,
     pub pocket_strategy: PocketStrategy,
     pub raster_fill_ratio: f64,
+    pub cut_side: CutSide,
+    pub cut_direction: CutDirection,
+    pub finishing_allowance: f64,
+    pub tabs: TabSettings,
+    pub leads: LeadSettings,
+    pub vcarve: VCarveSettings,
+    pub drill: DrillSettings,
+    /// Library tool for this object; `None` uses the designer's tool settings.
+    pub tool: Option<AssignedTool>,
     pub offset: f64,
     pub fillet: f64,
     pub chamfer: f64,
+    /// Dogbone or T-bone relief for inside corners.
+    pub dogbone: DogboneSettings,
     pub lock_aspect_ratio: bool,
 }
 
@@ -91,12 +109,21 @@ impl DrawingObject {
         if self.chamfer != 0.0 {
             shape = crate::ops::perform_chamfer(&shape, self.chamfer);
         }
+        if self.dogbone.is_active() {
+            shape = crate::ops::perform_dogbone(&shape, &self.dogbone, self.cuts_inside());
+        }
         shape
     }
 
+    /// Whether the cutter runs within the shape rather than around it.
+    pub fn cuts_inside(&self) -> bool {
+        self.operation_type == OperationType::Pocket || self.cut_side == CutSide::Inside
+    }
+
     pub fn get_total_bounds(&self) -> (f64, f64, f64, f64) {
         let (x1, y1, x2, y2) = self.shape.bounds();
-        if self.offset.abs() < 1e-6 && self.fillet.abs() < 1e-6 && self.chamfer.abs() < 1e-6 {
+        if self.offset.abs() < 1e-6 && self.fillet.abs() < 1e-6 && self.chamfer.abs() < 1e-6
+            && !self.dogbone.is_active() {
             return (x1, y1, x2, y2);
         }
         let (ex1, ey1, ex2, ey2) = self.get_effective_shape().bounds();
@@ -151,9 +178,18 @@ impl DrawingObject {
             ramp_angle: 0.0,
             pocket_strategy: PocketStrategy::ContourParallel,
             raster_fill_ratio: 0.5,
+            cut_side: CutSide::OnLine,
+            cut_direction: CutDirection::Climb,
+            finishing_allowance: 0.0,
+            tabs: TabSettings::default(),
+            leads: LeadSettings::default(),
+            vcarve: VCarveSettings::default(),
+            drill: DrillSettings::default(),
+            tool: None,
             offset: 0.0,
             fillet: 0.0,
             chamfer: 0.0,
+            dogbone: DogboneSettings::default(),
             lock_aspect_ratio: true,
         }
     }
@@ -29,6 +29,8 @@ impl DesignerState {
         design.toolpath_params.spindle_speed = self.tool_settings.spindle_speed as f64;
         design.toolpath_params.tool_diameter = self.tool_settings.tool_diameter;
         design.toolpath_params.cut_depth = self.tool_settings.cut_depth;
+        design.toolpath_params.tool_change = self.tool_change.clone();
+        design.toolpath_params.optimize_order = self.optimize_order;
 
         // Save stock settings
         if let Some(stock) = &self.stock_material {
@@ -82,6 +84,8 @@ impl DesignerState {
         self.tool_settings.spindle_speed = design.toolpath_params.spindle_speed as u32;
         self.tool_settings.tool_diameter = design.toolpath_params.tool_diameter;
         self.tool_settings.cut_depth = design.toolpath_params.cut_depth;
+        self.tool_change = design.toolpath_params.tool_change.clone();
+        self.optimize_order = design.toolpath_params.optimize_order;
 
         // Also update the toolpath generator to match
         self.toolpath_generator
@@ -6,8 +6,13 @@ use crate::model::DesignerShape;
 use crate::shapes::OperationType;
 // use crate::{Circle, Point, ToolpathToGcode};
 use crate::{ToolpathToGcode};
-use gcodekit5_core::Units;
+use gcodekit5_core::{PostContext, Units};
 use crate::designer_state::MachineMode;
+use crate::leads::LeadSettings;
+use crate::profile_operations::CutSide;
+use crate::tool_changes::{group_by_tool, AssignedTool};
+use crate::job_order::{optimize_order, OrderJob, OrderReport};
+use crate::model::Point;
 
 impl DesignerState {
     /// Generates G-code from the current design.
@@ -47,11 +52,23 @@ impl DesignerState {
         // Collect shape IDs in reverse draw order (front to back) for G-code generation
         let shape_ids: Vec<u64> = self.canvas.shape_store.draw_order_iter().rev().collect();
 
+        // Objects without a library tool are cut with the generator's own settings
+        let default_diameter = self.toolpath_generator.tool_diameter();
+        let default_feed = self.toolpath_generator.feed_rate();
+        let default_speed = self.toolpath_generator.spindle_speed();
+
         for shape_id in shape_ids {
             let Some(shape_obj) = self.canvas.shape_store.get(shape_id) else {
                 continue;
             };
 
+            let (diameter, feed, speed) = match &shape_obj.tool {
+                Some(tool) => (tool.diameter, tool.feed_rate, tool.spindle_speed),
+                None => (default_diameter, default_feed, default_speed),
+            };
+            self.toolpath_generator.set_tool_diameter(diameter);
+            self.toolpath_generator.set_feed_rate(feed);
+            self.toolpath_generator.set_spindle_speed(speed);
             self.toolpath_generator
             .set_pocket_strategy(shape_obj.pocket_strategy);
             self.toolpath_generator
@@ -64,8 +81,51 @@ impl DesignerState {
             .set_ramp_angle(shape_obj.ramp_angle as f64);
             self.toolpath_generator
             .set_raster_fill_ratio(shape_obj.raster_fill_ratio);
+            // Engraving follows the drawn line itself
+            let is_engrave = shape_obj.operation_type == OperationType::Engrave;
+            self.toolpath_generator
+            .set_cut_side(if is_engrave { CutSide::OnLine } else { shape_obj.cut_side });
+            self.toolpath_generator
+            .set_cut_direction(shape_obj.cut_direction);
+            self.toolpath_generator
+            .set_finishing_allowance(if is_engrave { 0.0 } else { shape_obj.finishing_allowance });
 
             let effective_shape = shape_obj.get_effective_shape();
+            let tab_points = if shape_obj.operation_type == OperationType::Profile {
+                crate::tabs::tab_points(&effective_shape, &shape_obj.tabs)
+            } else {
+                Vec::new()
+            };
+            self.toolpath_generator
+            .set_tabs(shape_obj.tabs.clone(), tab_points);
+            if shape_obj.operation_type == OperationType::Profile {
+                let lead_start = crate::leads::lead_start_point(&effective_shape, &shape_obj.leads);
+                self.toolpath_generator
+                .set_leads(shape_obj.leads.clone(), lead_start);
+            } else {
+                self.toolpath_generator.set_leads(LeadSettings::default(), None);
+            }
+
+            // V-carving and drilling don't depend on the kind of shape
+            match shape_obj.operation_type {
+                OperationType::VCarve => {
+                    let toolpaths = self.toolpath_generator.generate_vcarve(
+                        &effective_shape,
+                        &shape_obj.vcarve,
+                        shape_obj.step_down as f64,
+                    );
+                    shape_toolpaths.push((shape_obj.clone(), toolpaths, false));
+                    continue;
+                }
+                OperationType::Drill => {
+                    let toolpaths = self
+                    .toolpath_generator
+                    .generate_drill(&effective_shape, &shape_obj.drill);
+                    shape_toolpaths.push((shape_obj.clone(), toolpaths, false));
+                    continue;
+                }
+                _ => {}
+            }
 
             let (toolpaths, pocket_fallback_to_profile) = match &effective_shape {
                 crate::model::Shape::Rectangle(rect) => {
@@ -247,6 +307,44 @@ crate::model::Shape::Ellipse(ellipse) => {
             };
             shape_toolpaths.push((shape_obj.clone(), toolpaths, pocket_fallback_to_profile));
         }
+        self.toolpath_generator.set_tool_diameter(default_diameter);
+        self.toolpath_generator.set_feed_rate(default_feed);
+        self.toolpath_generator.set_spindle_speed(default_speed);
+
+        // Cut with one tool at a time, largest first, trimming rest machining
+        let groups: Vec<Vec<((DrawingObject, bool), OrderJob)>> = group_by_tool(
+            shape_toolpaths
+            .into_iter()
+            .map(|(obj, toolpaths, fallback)| (obj.tool.clone(), (obj, fallback), toolpaths))
+            .collect(),
+        )
+        .into_iter()
+        .map(|group| {
+            group
+            .jobs
+            .into_iter()
+            .map(|((obj, fallback), toolpaths)| {
+                let job = OrderJob::for_object(&obj, toolpaths);
+                ((obj, fallback), job)
+            })
+            .collect()
+        })
+        .collect();
+
+        // Then sequence each tool's jobs to cut down rapid travel
+        let groups = if self.optimize_order {
+            let (groups, report) = optimize_order(groups, Point::new(0.0, 0.0));
+            self.order_report = report;
+            groups
+        } else {
+            self.order_report = OrderReport::default();
+            groups
+        };
+        let shape_toolpaths: Vec<(DrawingObject, Vec<crate::Toolpath>, bool)> = groups
+        .into_iter()
+        .flatten()
+        .map(|((obj, fallback), job)| (obj, job.toolpaths, fallback))
+        .collect();
 
         // Calculate total length from all toolpaths
         let total_length: f64 = shape_toolpaths
@@ -284,12 +382,33 @@ crate::model::Shape::Ellipse(ellipse) => {
             header_depth,
             total_length,
         ));
+        if self.order_report.jobs > 1 {
+            gcode.push_str(&format!(
+                "; Cut order optimized: rapid travel {:.1}mm (was {:.1}mm, saved {:.1}mm)\n",
+                self.order_report.rapid_after,
+                self.order_report.rapid_before,
+                self.order_report.saved()
+            ));
+        }
 
         let mut line_number = 10;
+        let mut loaded_tool: Option<&AssignedTool> = None;
 
         // ------ Bucle Shape --------
 
         for (shape, toolpaths, pocket_fallback_to_profile) in shape_toolpaths.iter() {
+            // Change tools between groups (lasers have only the one)
+            if let Some(tool) = &shape.tool {
+                if !gcode_gen.is_laser_2d && !loaded_tool.is_some_and(|t| t.same_tool(tool)) {
+                    gcode.push_str(&format!(
+                        "\n; Tool change: T{} {} ({:.3}mm)\n",
+                        tool.number, tool.name, tool.diameter
+                    ));
+                    gcode.push_str(&self.tool_change.expand(tool, gcode_gen.safe_z));
+                    loaded_tool = Some(tool);
+                }
+            }
+
             // Add shape metadata as comments
             gcode.push_str(&format!(
                 "\n; Shape ID={}, Type={:?}\n",
@@ -298,6 +417,14 @@ crate::model::Shape::Ellipse(ellipse) => {
             ));
             gcode.push_str(&format!("; Name: {}\n", shape.name));
             gcode.push_str(&format!("; Operation: {:?}\n", shape.operation_type));
+            if let Some(tool) = &shape.tool {
+                gcode.push_str(&format!(
+                    "; Tool: T{} {}{}\n",
+                    tool.number,
+                    tool.name,
+                    if tool.rest_machining { ", rest machining" } else { "" }
+                ));
+            }
             if *pocket_fallback_to_profile {
                 gcode.push_str("; NOTE: Text pocketing produced no valid pocket area for the current tool/text size; fell back to profile toolpath.\n");
             }
@@ -311,11 +438,63 @@ crate::model::Shape::Ellipse(ellipse) => {
                     shape.pocket_depth, shape.step_down, shape.step_in
                 ));
                 gcode.push_str(&format!("; Strategy: {:?}\n", shape.pocket_strategy));
+            } else if shape.operation_type == OperationType::Engrave {
+                gcode.push_str(&format!(
+                    "; Engrave depth: {:.3}mm, Step down: {:.3}mm\n",
+                    shape.pocket_depth, shape.step_down
+                ));
+            } else if shape.operation_type == OperationType::VCarve {
+                gcode.push_str(&format!(
+                    "; V-bit angle: {:.1}deg, Max depth: {:.3}mm, Step down: {:.3}mm\n",
+                    shape.vcarve.tip_angle, shape.pocket_depth, shape.step_down
+                ));
+            } else if shape.operation_type == OperationType::Drill {
+                gcode.push_str(&format!(
+                    "; Drill depth: {:.3}mm, Peck: {:.3}mm, Peck retract: {:.3}mm\n",
+                    shape.pocket_depth, shape.drill.peck_depth, shape.drill.peck_retract
+                ));
             } else {
                 gcode.push_str(&format!(
                     "; Cut depth: {:.3}mm, Step down: {:.3}mm\n",
                     shape.pocket_depth, shape.step_down
                 ));
+                gcode.push_str(&format!(
+                    "; Cut side: {}, Direction: {}, Finishing allowance: {:.3}mm\n",
+                    shape.cut_side.name(),
+                    shape.cut_direction.name(),
+                    shape.finishing_allowance
+                ));
+                if shape.tabs.enabled {
+                    gcode.push_str(&format!(
+                        "; Tabs: {} {}, Width: {:.3}mm, Height: {:.3}mm\n",
+                        if shape.tabs.is_manual() {
+                            shape.tabs.positions.len()
+                        } else {
+                            shape.tabs.count as usize
+                        },
+                        shape.tabs.shape.name(),
+                        shape.tabs.width,
+                        shape.tabs.height
+                    ));
+                }
+                if shape.leads.is_active() {
+                    gcode.push_str(&format!(
+                        "; Lead in: {}, Lead out: {}, Length: {:.3}mm, Radius: {:.3}mm, Overlap: {:.3}mm\n",
+                        shape.leads.lead_in.name(),
+                        shape.leads.lead_out.name(),
+                        shape.leads.length,
+                        shape.leads.radius,
+                        shape.leads.overlap
+                    ));
+                }
+            }
+            if shape.dogbone.is_active() {
+                gcode.push_str(&format!(
+                    "; Corner relief: {}, Tool: {:.3}mm, Max angle: {:.1}deg\n",
+                    shape.dogbone.style.name(),
+                    shape.dogbone.tool_diameter,
+                    shape.dogbone.max_angle
+                ));
             }
 
             // Generate G-code for all toolpaths associated with this shape
@@ -326,6 +505,12 @@ crate::model::Shape::Ellipse(ellipse) => {
             let num_passes = if gcode_gen.is_laser_2d {
                 // LASER MODE: global value
                 global_step_down.max(1.0) as usize
+            } else if matches!(
+                shape.operation_type,
+                OperationType::Engrave | OperationType::VCarve | OperationType::Drill
+            ) {
+                // These toolpaths already step down to their full depth
+                1
             } else {
                 // CNC MODE: use step_down
                 let total_depth = (shape.start_depth - shape.pocket_depth).abs();
@@ -375,6 +560,14 @@ crate::model::Shape::Ellipse(ellipse) => {
 
         gcode.push_str(&gcode_gen.generate_footer());
 
+        if let Some(post) = &self.post_processor {
+            let context = PostContext::new(self.design_name.clone())
+            .with_safe_z(gcode_gen.safe_z)
+            .with_spindle_speed(header_speed as f64)
+            .with_feed_rate(header_feed);
+            gcode = post.process(&gcode, &context);
+        }
+
         self.generated_gcode = gcode.clone();
         self.gcode_generated = self.canvas.shape_count() > 0;
         gcode
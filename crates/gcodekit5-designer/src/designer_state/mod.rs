@@ -10,10 +10,12 @@
 //! - `properties`: Property setters for selected shapes
 //! - `gcode`: G-code generation
 //! - `file_io`: Save/load operations
+//! - `nesting`: Nesting parts on stock sheets
 
 mod file_io;
 mod gcode;
 mod history;
+mod nesting;
 mod properties;
 mod selection;
 mod shapes;
@@ -21,7 +23,9 @@ mod transforms;
 mod viewport;
 
 use crate::commands::DesignerCommand;
+use crate::job_order::OrderReport;
 use crate::stock_removal::{SimulationResult, StockMaterial};
+use crate::tool_changes::ToolChangeSettings;
 use crate::{Canvas, ToolpathGenerator};
 
 /// Tool settings for the designer
@@ -69,6 +73,12 @@ pub struct DesignerState {
     pub canvas: Canvas,
     pub toolpath_generator: ToolpathGenerator,
     pub tool_settings: ToolSettings,
+    /// How tool changes between objects' library tools are written.
+    pub tool_change: ToolChangeSettings,
+    /// Reorder operations to cut down rapid travel.
+    pub optimize_order: bool,
+    /// Rapid travel saved by the last G-code generation.
+    pub order_report: OrderReport,
     pub generated_gcode: String,
     pub gcode_generated: bool,
     pub current_file_path: Option<std::path::PathBuf>,
@@ -90,6 +100,8 @@ pub struct DesignerState {
     pub simulation_result: Option<SimulationResult>,
     /// Number of axes on the active device (default 3).
     pub num_axes: u8,
+    /// Post-processor for generated G-code (none keeps the generic output).
+    pub post_processor: Option<gcodekit5_core::PostDefinition>,
 }
 
 impl DesignerState {
@@ -99,6 +111,9 @@ impl DesignerState {
             canvas: Canvas::with_size(800.0, 600.0),
             toolpath_generator: ToolpathGenerator::new(),
             tool_settings: ToolSettings::default(),
+            tool_change: ToolChangeSettings::default(),
+            optimize_order: true,
+            order_report: OrderReport::default(),
             generated_gcode: String::new(),
             gcode_generated: false,
             current_file_path: None,
@@ -129,6 +144,7 @@ impl DesignerState {
             simulation_resolution: 0.1,
             simulation_result: None,
             num_axes: 3,
+            post_processor: None,
         }
     }
 
@@ -214,6 +230,18 @@ impl DesignerState {
         self.gcode_generated = false;
     }
 
+    /// Sets the G-code macro emitted at tool changes.
+    pub fn set_tool_change_macro(&mut self, template: String) {
+        self.tool_change.macro_template = template;
+        self.gcode_generated = false;
+    }
+
+    /// Sets whether operations are reordered to cut down rapid travel.
+    pub fn set_optimize_order(&mut self, optimize: bool) {
+        self.optimize_order = optimize;
+        self.gcode_generated = false;
+    }
+
 
 }
 
@@ -0,0 +1,140 @@
+//! Nesting of the selected parts on stock sheets for designer state.
+
+use super::DesignerState;
+use crate::canvas::DrawingObject;
+use crate::commands::*;
+use crate::model::DesignerShape;
+use crate::nesting::{nest, NestPart, NestResult, NestSettings};
+use crate::tabs::Outline;
+use lyon::math::{vector, Angle, Transform};
+
+impl DesignerState {
+    /// Names of the parts `nest_selected` would nest: each selected shape,
+    /// with grouped shapes kept together as one part.
+    pub fn selected_nest_parts(&self) -> Vec<String> {
+        self.selected_part_objects()
+            .iter()
+            .map(|objects| match objects.as_slice() {
+                [single] => single.name.clone(),
+                [first, ..] => format!("{} (group of {})", first.name, objects.len()),
+                [] => String::new(),
+            })
+            .collect()
+    }
+
+    /// Nests copies of the selected parts on sheets of the stock material.
+    ///
+    /// `quantities` gives the copies wanted of each part, in the order of
+    /// `selected_nest_parts`; missing entries nest one copy. The first sheet
+    /// sits at the stock origin and the rest follow to its right. The
+    /// originals are moved to their first placement and further copies are
+    /// added, all as one undoable step. Returns `None` when there is no
+    /// stock or nothing is selected.
+    pub fn nest_selected(
+        &mut self,
+        quantities: &[usize],
+        settings: &NestSettings,
+    ) -> Option<NestResult> {
+        let stock = self.stock_material.clone()?;
+        let parts = self.selected_part_objects();
+        if parts.is_empty() {
+            return None;
+        }
+
+        let nest_parts: Vec<NestPart> = parts
+            .iter()
+            .enumerate()
+            .map(|(index, objects)| {
+                let rings = objects
+                    .iter()
+                    .flat_map(|obj| {
+                        Outline::from_shape(&obj.get_effective_shape())
+                            .contours()
+                            .filter(|(_, closed)| *closed)
+                            .map(|(points, _)| points[..points.len() - 1].to_vec())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                NestPart::new(rings, quantities.get(index).copied().unwrap_or(1))
+            })
+            .collect();
+
+        let (width, height) = (stock.width as f64, stock.height as f64);
+        let result = nest(&nest_parts, width, height, settings);
+
+        self.canvas.deselect_all();
+        let mut commands = Vec::new();
+        let mut originals_placed = vec![false; parts.len()];
+        for placement in &result.placements {
+            let sheet_x =
+                stock.origin.0 as f64 + placement.sheet as f64 * (width + settings.sheet_gap);
+            let transform =
+                Transform::rotation(Angle::radians(placement.angle.to_radians() as f32))
+                    .then_translate(vector(
+                        (sheet_x + placement.offset.x) as f32,
+                        (stock.origin.1 as f64 + placement.offset.y) as f32,
+                    ));
+
+            let objects = &parts[placement.part];
+            let is_copy = std::mem::replace(&mut originals_placed[placement.part], true);
+            let group_id = objects[0].group_id.map(|gid| {
+                if is_copy {
+                    self.canvas.generate_id()
+                } else {
+                    gid
+                }
+            });
+
+            for obj in objects {
+                let mut new_obj = obj.clone();
+                new_obj.shape.transform(&transform);
+                new_obj.selected = true;
+
+                if is_copy {
+                    let id = self.canvas.generate_id();
+                    new_obj.id = id;
+                    new_obj.group_id = group_id;
+                    commands.push(DesignerCommand::AddShape(AddShape {
+                        id,
+                        object: Some(new_obj),
+                    }));
+                } else {
+                    commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                        id: obj.id,
+                        old_state: obj.clone(),
+                        new_state: new_obj,
+                    }));
+                }
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Nest Parts".to_string(),
+            });
+            self.push_command(cmd);
+        }
+
+        Some(result)
+    }
+
+    /// Selected shapes as parts, grouped shapes together, in drawing order.
+    fn selected_part_objects(&self) -> Vec<Vec<DrawingObject>> {
+        let mut parts: Vec<Vec<DrawingObject>> = Vec::new();
+        let mut groups = std::collections::HashMap::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            match obj.group_id {
+                Some(gid) => {
+                    let index = *groups.entry(gid).or_insert_with(|| {
+                        parts.push(Vec::new());
+                        parts.len() - 1
+                    });
+                    parts[index].push(obj.clone());
+                }
+                None => parts.push(vec![obj.clone()]),
+            }
+        }
+        parts
+    }
+}
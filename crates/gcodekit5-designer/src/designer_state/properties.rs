@@ -2,9 +2,15 @@
 
 use super::DesignerState;
 use crate::canvas::DrawingObject;
+use crate::drilling_patterns::DrillSettings;
+use crate::leads::{LeadSettings, LeadType};
 use crate::commands::*;
 use crate::model::{DesignerShape, Shape};
+use crate::profile_operations::{CutDirection, CutSide};
 use crate::shapes::OperationType;
+use crate::tabs::{TabSettings, TabShape};
+use crate::tool_changes::AssignedTool;
+use crate::vcarve::VCarveSettings;
 use crate::{Point, Rectangle};
 
 impl DesignerState {
@@ -33,13 +39,17 @@ impl DesignerState {
 
     /// Sets the pocket properties of selected shapes.
     pub fn set_selected_pocket_properties(&mut self, is_pocket: bool, depth: f64) {
-        let mut commands = Vec::new();
         let new_type = if is_pocket {
             OperationType::Pocket
         } else {
             OperationType::Profile
         };
+        self.set_selected_operation(new_type, depth);
+    }
 
+    /// Sets the operation type and cut depth of selected shapes.
+    pub fn set_selected_operation(&mut self, new_type: OperationType, depth: f64) {
+        let mut commands = Vec::new();
         for obj in self.canvas.shapes().filter(|s| s.selected) {
             if obj.operation_type != new_type || (obj.pocket_depth - depth).abs() > f64::EPSILON {
                 let mut new_obj = obj.clone();
@@ -57,7 +67,7 @@ impl DesignerState {
         if !commands.is_empty() {
             let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
                 commands,
-                name: "Change Pocket Properties".to_string(),
+                name: "Change Operation".to_string(),
             });
             self.push_command(cmd);
         }
@@ -542,6 +552,312 @@ impl DesignerState {
         }
     }
 
+    /// Sets the profile cut side for selected shapes.
+    pub fn set_selected_cut_side(&mut self, side: CutSide) {
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            if obj.cut_side != side {
+                let mut new_obj = obj.clone();
+                new_obj.cut_side = side;
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Change Cut Side".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
+    /// Sets the profile cut direction for selected shapes.
+    pub fn set_selected_cut_direction(&mut self, direction: CutDirection) {
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            if obj.cut_direction != direction {
+                let mut new_obj = obj.clone();
+                new_obj.cut_direction = direction;
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Change Cut Direction".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
+    /// Sets the profile finishing allowance for selected shapes.
+    pub fn set_selected_finishing_allowance(&mut self, allowance: f64) {
+        let allowance = allowance.max(0.0);
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            if (obj.finishing_allowance - allowance).abs() > f64::EPSILON {
+                let mut new_obj = obj.clone();
+                new_obj.finishing_allowance = allowance;
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Change Finishing Allowance".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
+    /// Sets holding tab parameters for selected shapes. Changing the tab
+    /// count discards manually placed positions so tabs are re-spaced.
+    pub fn set_selected_tabs(
+        &mut self,
+        enabled: bool,
+        count: u32,
+        width: f64,
+        height: f64,
+        shape: TabShape,
+    ) {
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            let mut tabs = TabSettings {
+                enabled,
+                count: count.max(1),
+                width: width.max(0.0),
+                height: height.max(0.0),
+                shape,
+                positions: obj.tabs.positions.clone(),
+            };
+            if tabs.count != obj.tabs.count {
+                tabs.positions.clear();
+            }
+            if tabs != obj.tabs {
+                let mut new_obj = obj.clone();
+                new_obj.tabs = tabs;
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Change Tabs".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
+    /// Discards manually placed tab positions on selected shapes so the
+    /// tabs are spaced automatically again.
+    pub fn reset_selected_tab_positions(&mut self) {
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            if obj.tabs.is_manual() {
+                let mut new_obj = obj.clone();
+                new_obj.tabs.positions.clear();
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Reset Tab Positions".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
+    /// Sets lead-in/out parameters for selected shapes. `start_position` is
+    /// a fraction of the outline length, or `None` for the natural start.
+    pub fn set_selected_leads(
+        &mut self,
+        lead_in: LeadType,
+        lead_out: LeadType,
+        length: f64,
+        radius: f64,
+        overlap: f64,
+        start_position: Option<f64>,
+    ) {
+        let leads = LeadSettings {
+            lead_in,
+            lead_out,
+            length: length.max(0.0),
+            radius: radius.max(0.0),
+            overlap: overlap.max(0.0),
+            start_position: start_position.map(|p| p.clamp(0.0, 1.0)),
+        };
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            if obj.leads != leads {
+                let mut new_obj = obj.clone();
+                new_obj.leads = leads.clone();
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Change Leads".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
+    /// Sets the V-bit angle used to V-carve selected shapes.
+    pub fn set_selected_vcarve(&mut self, tip_angle: f64) {
+        let vcarve = VCarveSettings {
+            tip_angle: tip_angle.clamp(1.0, 179.0),
+        };
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            if obj.vcarve != vcarve {
+                let mut new_obj = obj.clone();
+                new_obj.vcarve = vcarve.clone();
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Change V-Carve".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
+    /// Sets the peck drilling options of selected shapes. A peck depth of 0
+    /// drills in one plunge.
+    pub fn set_selected_drill(&mut self, peck_depth: f64, peck_retract: f64) {
+        let drill = DrillSettings {
+            peck_depth: peck_depth.max(0.0),
+            peck_retract: peck_retract.max(0.0),
+        };
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            if obj.drill != drill {
+                let mut new_obj = obj.clone();
+                new_obj.drill = drill.clone();
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Change Drilling".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
+    /// Assigns a library tool to selected shapes, or the designer's tool
+    /// settings when `None`. Each shape keeps its rest machining choice.
+    pub fn set_selected_tool(&mut self, tool: Option<AssignedTool>) {
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            let new_tool = tool.clone().map(|mut t| {
+                t.rest_machining = obj.tool.as_ref().is_some_and(|old| old.rest_machining);
+                t
+            });
+            if obj.tool != new_tool {
+                let mut new_obj = obj.clone();
+                new_obj.tool = new_tool;
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Change Tool".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
+    /// Sets rest machining on selected shapes that have a library tool.
+    pub fn set_selected_rest_machining(&mut self, rest_machining: bool) {
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            let Some(tool) = &obj.tool else {
+                continue;
+            };
+            if tool.rest_machining != rest_machining {
+                let mut new_obj = obj.clone();
+                if let Some(new_tool) = new_obj.tool.as_mut() {
+                    new_tool.rest_machining = rest_machining;
+                }
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Change Rest Machining".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
+
     /// Converts selected shapes to a single bounding rectangle.
     pub fn convert_selected_to_rectangle(&mut self) {
         let selected: Vec<_> = self
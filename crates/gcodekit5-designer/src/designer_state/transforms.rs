@@ -2,6 +2,7 @@
 
 use super::DesignerState;
 use crate::commands::*;
+use crate::dogbone::DogboneSettings;
 use crate::model::DesignerShape;
 use crate::Point;
 
@@ -499,4 +500,29 @@ impl DesignerState {
         });
         self.push_command(cmd);
     }
+
+    /// Sets the dogbone or T-bone corner relief for the selected shapes.
+    pub fn set_dogbone_selected(&mut self, dogbone: DogboneSettings) {
+        let mut commands = Vec::new();
+        for obj in self.canvas.shapes().filter(|s| s.selected) {
+            if obj.dogbone != dogbone {
+                let mut new_obj = obj.clone();
+                new_obj.dogbone = dogbone.clone();
+
+                commands.push(DesignerCommand::ChangeProperty(ChangeProperty {
+                    id: obj.id,
+                    old_state: obj.clone(),
+                    new_state: new_obj,
+                }));
+            }
+        }
+
+        if !commands.is_empty() {
+            let cmd = DesignerCommand::CompositeCommand(CompositeCommand {
+                commands,
+                name: "Set Corner Relief".to_string(),
+            });
+            self.push_command(cmd);
+        }
+    }
 }
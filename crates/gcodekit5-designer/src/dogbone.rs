@@ -0,0 +1,171 @@
+//! Dogbone and T-bone relief for inside corners.
+//!
+//! A round cutter cannot reach into a sharp inside corner: it leaves a
+//! fillet of its own radius, and a square tenon will not seat in the
+//! mortise. Corner relief moves the outline so the cutter's edge passes
+//! through the drawn corner. A dogbone cuts along the corner's bisector; a
+//! T-bone cuts straight into the longer of the two walls, where the relief
+//! is easier to hide.
+//!
+//! Which corners are "inside" depends on where the cutter runs. Pockets and
+//! inside profiles cut within the shape, so the relief goes into the shape's
+//! convex corners. Other cuts run around the outside and relieve the shape's
+//! concave corners.
+
+use crate::model::Point;
+
+/// Added to the relief radius so the offset toolpath can enter the relief
+/// rather than just touching it, in mm.
+pub const RELIEF_CLEARANCE: f64 = 0.05;
+
+/// Number of sides used for a relief circle.
+const RELIEF_SEGMENTS: usize = 32;
+
+/// How an inside corner is relieved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, Default)]
+pub enum DogboneStyle {
+    /// Relief along the corner's bisector.
+    #[default]
+    Dogbone,
+    /// Relief perpendicular to the corner's longer edge.
+    TBone,
+}
+
+impl DogboneStyle {
+    /// Returns the name of the relief style.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DogboneStyle::Dogbone => "Dogbone",
+            DogboneStyle::TBone => "T-Bone",
+        }
+    }
+}
+
+/// Corner relief settings for a shape.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DogboneSettings {
+    pub enabled: bool,
+    pub style: DogboneStyle,
+    /// Diameter of the cutter the relief is sized for, in mm.
+    pub tool_diameter: f64,
+    /// Corners sharper than this, measured on the cutter's side, are
+    /// relieved, in degrees.
+    pub max_angle: f64,
+}
+
+impl Default for DogboneSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: DogboneStyle::Dogbone,
+            tool_diameter: 3.175,
+            max_angle: 120.0,
+        }
+    }
+}
+
+impl DogboneSettings {
+    /// Whether these settings change the shape at all.
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.tool_diameter > 0.0 && self.max_angle > 0.0
+    }
+}
+
+/// Centres of the relief circles for one closed ring of a shape's outline.
+///
+/// `hole` marks a ring bounding a hole in the shape, and `cut_inside` says
+/// the cutter runs within the shape. The ring may wind either way and need
+/// not repeat its first point. Each relief is a circle of the tool's radius
+/// whose edge passes through the corner.
+pub fn relief_centres(
+    ring: &[Point],
+    hole: bool,
+    cut_inside: bool,
+    settings: &DogboneSettings,
+) -> Vec<Point> {
+    let n = ring.len();
+    if n < 3 || !settings.is_active() {
+        return Vec::new();
+    }
+
+    let area: f64 = (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    // The cutter runs on the left of the ring's direction when this is set
+    let cutter_left = ((area > 0.0) != hole) == cut_inside;
+
+    let radius = settings.tool_diameter / 2.0;
+    let cos_limit = settings.max_angle.min(180.0).to_radians().cos();
+    let mut centres = Vec::new();
+
+    for i in 0..n {
+        let corner = ring[i];
+        let prev = ring[(i + n - 1) % n];
+        let next = ring[(i + 1) % n];
+        let (Some(u), Some(v)) = (unit(corner, prev), unit(corner, next)) else {
+            continue;
+        };
+
+        // Left turn bends towards the left side; the corner is sharp on the
+        // side it bends towards.
+        let turn =
+            (corner.x - prev.x) * (next.y - corner.y) - (corner.y - prev.y) * (next.x - corner.x);
+        if turn.abs() < 1e-12 || (turn > 0.0) != cutter_left {
+            continue;
+        }
+
+        let cos_angle = u.0 * v.0 + u.1 * v.1;
+        if cos_angle <= cos_limit {
+            continue;
+        }
+
+        let direction = match settings.style {
+            DogboneStyle::Dogbone => {
+                let (bx, by) = (u.0 + v.0, u.1 + v.1);
+                let len = (bx * bx + by * by).sqrt();
+                if len < 1e-12 {
+                    continue;
+                }
+                (bx / len, by / len)
+            }
+            DogboneStyle::TBone => {
+                if corner.distance_to(&prev) >= corner.distance_to(&next) {
+                    u
+                } else {
+                    v
+                }
+            }
+        };
+
+        centres.push(Point::new(
+            corner.x + direction.0 * radius,
+            corner.y + direction.1 * radius,
+        ));
+    }
+
+    centres
+}
+
+/// Polygon for a relief circle, with its sides outside the circle so a
+/// cutter of `radius` fits inside it.
+pub fn relief_polygon(centre: Point, radius: f64) -> Vec<[f64; 2]> {
+    let step = std::f64::consts::TAU / RELIEF_SEGMENTS as f64;
+    let r = (radius + RELIEF_CLEARANCE) / (step / 2.0).cos();
+    (0..RELIEF_SEGMENTS)
+        .map(|i| {
+            let angle = i as f64 * step;
+            [centre.x + r * angle.cos(), centre.y + r * angle.sin()]
+        })
+        .collect()
+}
+
+/// Unit vector from `from` to `to`, if they are apart.
+fn unit(from: Point, to: Point) -> Option<(f64, f64)> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let len = (dx * dx + dy * dy).sqrt();
+    (len > 1e-9).then(|| (dx / len, dy / len))
+}
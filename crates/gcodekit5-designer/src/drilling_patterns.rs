@@ -2,6 +2,7 @@
 //!
 //! Generates drilling toolpaths for various hole patterns: linear, circular, and grid.
 //! Supports custom hole definitions and automatic pattern generation.
+//! Designer shapes drilled as holes use [`DrillSettings`] for their peck options.
 
 use super::toolpath::{Toolpath, ToolpathSegment, ToolpathSegmentType};
 use crate::Point;
@@ -83,6 +84,33 @@ impl DrillOperation {
     }
 }
 
+/// Drill settings for a designer shape. Circles are drilled at their
+/// centre to the shape's cut depth.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DrillSettings {
+    /// Depth drilled per peck (mm); 0 drills in a single plunge
+    pub peck_depth: f64,
+    /// Distance backed off between pecks to break the chip (mm); 0 retracts
+    /// clear of the hole to clear it
+    pub peck_retract: f64,
+}
+
+impl Default for DrillSettings {
+    fn default() -> Self {
+        Self {
+            peck_depth: 0.0,
+            peck_retract: 0.0,
+        }
+    }
+}
+
+/// Height above the top of the hole that a full peck retract rises to.
+const PECK_CLEARANCE: f64 = 1.0;
+
+/// Height above the previous peck's floor where the next peck starts feeding.
+const PECK_RESUME: f64 = 0.5;
+
 /// Types of drilling patterns.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PatternType {
@@ -227,6 +255,67 @@ impl DrillingPatternGenerator {
         toolpath
     }
 
+    /// Generates a drilling toolpath with real Z moves for G-code output.
+    ///
+    /// Each hole is reached with a rapid, then drilled from `top_z` to the
+    /// operation's depth below it. Between pecks the drill backs off by
+    /// `peck_retract`, or clears the hole when it is 0, and starts the next
+    /// peck just above the previous one.
+    pub fn generate_peck_toolpath(
+        &self,
+        holes: &[Point],
+        top_z: f64,
+        peck_retract: f64,
+    ) -> Toolpath {
+        let bottom_z = top_z - self.operation.depth.abs();
+        let mut toolpath = Toolpath::new(self.operation.drill_diameter, bottom_z);
+        let mut last = Point::new(0.0, 0.0);
+        let plunge = |z_from: f64, z_to: f64, at: Point| {
+            let mut segment = ToolpathSegment::new(
+                ToolpathSegmentType::LinearMove,
+                at,
+                at,
+                self.operation.plunge_rate,
+                self.operation.spindle_speed,
+            );
+            segment.start_z = Some(z_from);
+            segment.z_depth = Some(z_to);
+            segment
+        };
+
+        for hole in holes {
+            toolpath.add_segment(ToolpathSegment::new(
+                ToolpathSegmentType::RapidMove,
+                last,
+                *hole,
+                self.operation.feed_rate,
+                self.operation.spindle_speed,
+            ));
+            last = *hole;
+
+            let peck = self.operation.peck_depth.unwrap_or(f64::INFINITY).max(1e-3);
+            let mut z = top_z;
+            let mut resume = top_z;
+            loop {
+                let next = (z - peck).max(bottom_z);
+                toolpath.add_segment(plunge(resume, next, *hole));
+                z = next;
+                if z <= bottom_z + 1e-9 {
+                    break;
+                }
+                let backoff = if peck_retract > 0.0 {
+                    (z + peck_retract).min(top_z + PECK_CLEARANCE)
+                } else {
+                    top_z + PECK_CLEARANCE
+                };
+                toolpath.add_segment(plunge(z, backoff, *hole));
+                resume = (z + PECK_RESUME).min(backoff);
+            }
+        }
+
+        toolpath
+    }
+
     /// Generates peck drilling segments for a single hole.
     fn generate_peck_drilling(&self, hole_point: Point, peck_depth: f64) -> Vec<ToolpathSegment> {
         let mut segments = Vec::new();
@@ -1,7 +1,7 @@
 //! G-code generation from toolpaths.
 
 use super::toolpath::{Toolpath, ToolpathSegmentType};
-use gcodekit5_core::Units;
+use gcodekit5_core::{apply_post, PostContext, PostDefinition, Units};
 // use crate::model::Point;
 
 /// G-code generator for converting toolpaths to G-code commands.
@@ -14,6 +14,8 @@ pub struct ToolpathToGcode {
     pub num_axes: u8,
     /// Laser Mode 2D (without Z axis)
     pub is_laser_2d: bool,
+    /// Post-processor applied to the finished program (none by default)
+    pub post_processor: Option<PostDefinition>,
 }
 
 impl ToolpathToGcode {
@@ -25,6 +27,7 @@ impl ToolpathToGcode {
             line_numbers_enabled: false,
             num_axes: 3,
             is_laser_2d: false,
+            post_processor: None,
         }
     }
 
@@ -36,6 +39,7 @@ impl ToolpathToGcode {
             line_numbers_enabled: enabled,
             num_axes: 3,
             is_laser_2d: false,
+            post_processor: None,
         }
     }
 
@@ -45,6 +49,17 @@ impl ToolpathToGcode {
         self
     }
 
+    /// Runs the finished program through a post-processor.
+    pub fn with_post_processor(mut self, post: PostDefinition) -> Self {
+        self.post_processor = Some(post);
+        self
+    }
+
+    /// Applies the post-processor, if any, to a complete program.
+    pub fn post_process(&self, gcode: String, context: &PostContext) -> String {
+        apply_post(self.post_processor.as_ref(), gcode, context)
+    }
+
     /// Generates G-code from a toolpath.
     pub fn generate(&self, toolpath: &Toolpath) -> String {
         let mut gcode = String::new();
@@ -71,7 +86,11 @@ impl ToolpathToGcode {
         gcode.push_str(&self.generate_body(toolpath, 10));
         gcode.push_str(&self.generate_footer());
 
-        gcode
+        let context = PostContext::default()
+        .with_safe_z(self.safe_z)
+        .with_spindle_speed(spindle_speed as f64)
+        .with_feed_rate(feed_rate);
+        self.post_process(gcode, &context)
     }
 
     /// Generates the G-code header.
@@ -167,6 +186,20 @@ impl ToolpathToGcode {
                             "{}G00 X{:.3} Y{:.3}   ; Posicionar\n",
                             line_prefix, segment.end.x, segment.end.y
                         ));
+                    } else if has_z {
+                        // Retract before moving so rapids never cross the stock
+                        if current_z < self.safe_z - 0.001 {
+                            gcode.push_str(&format!("{}G00 Z{:.3}\n", line_prefix, self.safe_z));
+                        }
+                        gcode.push_str(&format!(
+                            "{}G00 X{:.3} Y{:.3} Z{:.3}\n",
+                            line_prefix, segment.end.x, segment.end.y, self.safe_z
+                        ));
+                    } else {
+                        gcode.push_str(&format!(
+                            "{}G00 X{:.3} Y{:.3}\n",
+                            line_prefix, segment.end.x, segment.end.y
+                        ));
                     }
 
                     current_z = self.safe_z;
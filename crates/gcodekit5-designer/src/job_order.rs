@@ -0,0 +1,717 @@
+//! Cut order optimisation to minimise rapid travel.
+//!
+//! Each object's toolpaths form one job. Jobs are sequenced within each tool
+//! group by nearest neighbour and then improved with 2-opt. Closed contours
+//! may start anywhere on their loop, and cuts without a cut side may run in
+//! either direction. Some orderings are fixed, whatever the travel:
+//!
+//! - tool groups keep the order they are given in;
+//! - within a group, shallower jobs are cut before deeper ones;
+//! - anything lying inside a profile is cut before that profile, so inner
+//!   contours and pockets come before the outline that frees the part.
+
+use crate::canvas::DrawingObject;
+use crate::model::Point;
+use crate::profile_operations::CutSide;
+use crate::shapes::OperationType;
+use crate::toolpath::geometry::{
+    arc_angles, arc_center, closest_on_line, closest_on_segment, cutting_runs, segment_z,
+    sub_segment,
+};
+use crate::toolpath::{Toolpath, ToolpathSegment, ToolpathSegmentType};
+
+/// Distance below which two points are the same.
+const TOLERANCE: f64 = 1e-6;
+/// Jobs whose depths differ by less than this share a depth tier.
+const DEPTH_TOLERANCE: f64 = 0.01;
+/// Gap below which consecutive moves join up.
+const JOIN_TOLERANCE: f64 = 1e-3;
+/// Loop positions this close to a segment end start at that end.
+const SPLIT_TOLERANCE: f64 = 1e-6;
+/// Points sampled from a job when testing whether it lies inside a profile.
+const MAX_SAMPLES: usize = 400;
+/// Straight segments used per arc when outlining a profile.
+const ARC_STEPS: usize = 8;
+/// Larger tiers are only ordered by nearest neighbour.
+const MAX_TWO_OPT_JOBS: usize = 250;
+/// Rounds of 2-opt and start point refinement.
+const MAX_ROUNDS: usize = 10;
+
+/// One object's toolpaths, as the optimiser sees them.
+#[derive(Debug, Clone)]
+pub struct OrderJob {
+    pub toolpaths: Vec<Toolpath>,
+    pub operation: OperationType,
+    /// Closed passes may start anywhere on their loop.
+    pub rotatable: bool,
+    /// The cut is the same when run backwards.
+    pub reversible: bool,
+}
+
+impl OrderJob {
+    /// A job that is cut exactly as generated.
+    pub fn new(toolpaths: Vec<Toolpath>, operation: OperationType) -> Self {
+        Self {
+            toolpaths,
+            operation,
+            rotatable: false,
+            reversible: false,
+        }
+    }
+
+    /// A job for an object's toolpaths, free to move where its settings allow.
+    pub fn for_object(object: &DrawingObject, toolpaths: Vec<Toolpath>) -> Self {
+        let operation = object.operation_type;
+        // Leads fix where a profile is entered
+        let free_start = operation == OperationType::Engrave
+            || (operation == OperationType::Profile && !object.leads.is_active());
+        // Running a profile backwards swaps climb and conventional milling
+        let on_line = operation == OperationType::Engrave
+            || (operation == OperationType::Profile && object.cut_side == CutSide::OnLine);
+        Self {
+            toolpaths,
+            operation,
+            rotatable: free_start,
+            reversible: free_start && on_line,
+        }
+    }
+}
+
+/// Rapid travel before and after optimisation.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OrderReport {
+    /// Number of jobs ordered.
+    pub jobs: usize,
+    pub rapid_before: f64,
+    pub rapid_after: f64,
+}
+
+impl OrderReport {
+    /// Rapid distance saved (negative if the order constraints cost travel).
+    pub fn saved(&self) -> f64 {
+        self.rapid_before - self.rapid_after
+    }
+}
+
+/// Total length of the rapid moves in `toolpaths`, starting from `start`.
+pub fn rapid_distance<'a>(toolpaths: impl IntoIterator<Item = &'a Toolpath>, start: Point) -> f64 {
+    let mut position = start;
+    let mut distance = 0.0;
+    for seg in toolpaths.into_iter().flat_map(|tp| &tp.segments) {
+        if seg.segment_type == ToolpathSegmentType::RapidMove {
+            distance += position.distance_to(&seg.end);
+        }
+        position = seg.end;
+    }
+    distance
+}
+
+/// Orders the jobs in each group, starting from `start`.
+///
+/// Groups are cut in the order given (one per tool); the jobs within each are
+/// reordered and their start points and directions chosen.
+pub fn optimize_order<T>(
+    groups: Vec<Vec<(T, OrderJob)>>,
+    start: Point,
+) -> (Vec<Vec<(T, OrderJob)>>, OrderReport) {
+    let mut report = OrderReport {
+        jobs: groups.iter().map(Vec::len).sum(),
+        rapid_before: rapid_distance(
+            groups.iter().flatten().flat_map(|(_, job)| &job.toolpaths),
+            start,
+        ),
+        rapid_after: 0.0,
+    };
+
+    let mut position = start;
+    let ordered: Vec<Vec<(T, OrderJob)>> = groups
+        .into_iter()
+        .map(|group| order_group(group, &mut position))
+        .collect();
+
+    report.rapid_after = rapid_distance(
+        ordered.iter().flatten().flat_map(|(_, job)| &job.toolpaths),
+        start,
+    );
+    (ordered, report)
+}
+
+/// Where a job is entered.
+#[derive(Debug, Clone, Copy)]
+enum Entry {
+    Forward,
+    Reversed,
+    /// At this point on the job's closed loop.
+    At(Point),
+}
+
+/// What the optimiser knows about one job.
+struct Plan {
+    entry: Point,
+    exit: Point,
+    /// Entry and exit when every pass runs backwards.
+    reversed: (Point, Point),
+    /// The loop every pass follows, if the job may start anywhere on it.
+    closed_loop: Option<Vec<ToolpathSegment>>,
+    reversible: bool,
+    depth: f64,
+    samples: Vec<Point>,
+    /// Outline of a profile, for the jobs that must be cut before it.
+    outline: Option<Vec<Point>>,
+    area: f64,
+    tool_radius: f64,
+}
+
+impl Plan {
+    fn new(job: &OrderJob) -> Option<Self> {
+        let first = job.toolpaths.iter().find(|tp| !tp.segments.is_empty())?;
+        let last = job
+            .toolpaths
+            .iter()
+            .rev()
+            .find(|tp| !tp.segments.is_empty())?;
+        let exit = last.segments.last()?.end;
+
+        let depth = job
+            .toolpaths
+            .iter()
+            .flat_map(|tp| {
+                tp.segments
+                    .iter()
+                    .filter(|seg| seg.segment_type != ToolpathSegmentType::RapidMove)
+                    .map(|seg| {
+                        let (z0, z1) = segment_z(seg, tp.depth);
+                        z0.min(z1)
+                    })
+            })
+            .fold(f64::INFINITY, f64::min);
+
+        let outline = if job.operation == OperationType::Profile {
+            profile_outline(last)
+        } else {
+            None
+        };
+
+        Some(Self {
+            entry: start_point(first)?,
+            exit,
+            reversed: (first.segments.last()?.end, start_point(last)?),
+            closed_loop: if job.rotatable {
+                closed_loop(&job.toolpaths)
+            } else {
+                None
+            },
+            reversible: job.reversible && job.toolpaths.iter().all(is_flat),
+            depth: if depth.is_finite() { depth } else { 0.0 },
+            samples: samples(&job.toolpaths),
+            area: outline.as_deref().map(polygon_area).unwrap_or(0.0),
+            outline,
+            tool_radius: first.tool_diameter / 2.0,
+        })
+    }
+
+    /// Entry and exit points when entered as `entry`.
+    fn ends(&self, entry: Entry) -> (Point, Point) {
+        match entry {
+            Entry::Forward => (self.entry, self.exit),
+            Entry::Reversed => self.reversed,
+            Entry::At(p) => (p, p),
+        }
+    }
+
+    /// Travel into the job from `from` and on to `to`.
+    fn link_cost(&self, entry: Entry, from: Point, to: Option<Point>) -> f64 {
+        let (a, b) = self.ends(entry);
+        from.distance_to(&a) + to.map_or(0.0, |q| b.distance_to(&q))
+    }
+
+    /// The cheapest way in after `from`, heading to `to` next.
+    fn best_entry(&self, from: Point, to: Option<Point>) -> Entry {
+        if let Some(closed_loop) = &self.closed_loop {
+            let mut candidates: Vec<Point> = closed_loop.iter().map(|seg| seg.start).collect();
+            candidates.push(closest_on_loop(closed_loop, from).0);
+            if let Some(q) = to {
+                candidates.push(closest_on_loop(closed_loop, q).0);
+            }
+            return candidates
+                .into_iter()
+                .map(Entry::At)
+                .min_by(|a, b| {
+                    self.link_cost(*a, from, to)
+                        .total_cmp(&self.link_cost(*b, from, to))
+                })
+                .unwrap_or(Entry::Forward);
+        }
+        if self.reversible
+            && self.link_cost(Entry::Reversed, from, to)
+                < self.link_cost(Entry::Forward, from, to) - TOLERANCE
+        {
+            Entry::Reversed
+        } else {
+            Entry::Forward
+        }
+    }
+}
+
+/// Orders one tool group, leaving `position` where its last job ends.
+fn order_group<T>(group: Vec<(T, OrderJob)>, position: &mut Point) -> Vec<(T, OrderJob)> {
+    let (indices, plans): (Vec<usize>, Vec<Plan>) = group
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, job))| Plan::new(job).map(|plan| (i, plan)))
+        .unzip();
+    let before = precedence(&plans);
+
+    let mut route = Vec::with_capacity(plans.len());
+    for tier in depth_tiers(&plans, &before) {
+        let mut tier_route = nearest_neighbour(&tier, &plans, &before, *position);
+        improve(&mut tier_route, &plans, &before, *position);
+        if let Some(&(i, entry)) = tier_route.last() {
+            *position = plans[i].ends(entry).1;
+        }
+        route.extend(tier_route);
+    }
+
+    // Jobs without moves only carry comments, so they keep the front
+    let mut slots: Vec<Option<(T, OrderJob)>> = group.into_iter().map(Some).collect();
+    let mut ordered: Vec<(T, OrderJob)> = Vec::with_capacity(slots.len());
+    for (i, slot) in slots.iter_mut().enumerate() {
+        if indices.binary_search(&i).is_err() {
+            ordered.extend(slot.take());
+        }
+    }
+    for (i, entry) in route {
+        if let Some((item, mut job)) = slots[indices[i]].take() {
+            job.toolpaths = match entry {
+                Entry::Forward => job.toolpaths,
+                Entry::Reversed => job.toolpaths.iter().map(reverse_toolpath).collect(),
+                Entry::At(p) => job
+                    .toolpaths
+                    .iter()
+                    .map(|tp| rotate_toolpath(tp, p))
+                    .collect(),
+            };
+            ordered.push((item, job));
+        }
+    }
+    ordered
+}
+
+/// `before[a][b]` when job `a` must be cut before job `b`.
+fn precedence(plans: &[Plan]) -> Vec<Vec<bool>> {
+    let mut before = vec![vec![false; plans.len()]; plans.len()];
+    for (b, outer) in plans.iter().enumerate() {
+        let Some(outline) = &outer.outline else {
+            continue;
+        };
+        let margin = outer.tool_radius + DEPTH_TOLERANCE;
+        let limits = grow(bounds(outline), margin);
+        for (a, inner) in plans.iter().enumerate() {
+            // Of two profiles only the smaller can be inside, so no cycles
+            if a == b
+                || inner.samples.is_empty()
+                || (inner.outline.is_some() && inner.area >= outer.area - TOLERANCE)
+                || !within(bounds(&inner.samples), limits)
+            {
+                continue;
+            }
+            before[a][b] = inner.samples.iter().all(|p| inside(*p, outline, margin));
+        }
+    }
+    before
+}
+
+/// Splits the jobs into depth tiers, shallowest first.
+///
+/// A job that must follow a deeper one moves down to its tier.
+fn depth_tiers(plans: &[Plan], before: &[Vec<bool>]) -> Vec<Vec<usize>> {
+    let mut depths: Vec<f64> = plans.iter().map(|plan| plan.depth).collect();
+    depths.sort_by(|a, b| b.total_cmp(a));
+    let mut levels: Vec<f64> = Vec::new();
+    for depth in depths {
+        if levels
+            .last()
+            .is_none_or(|level| level - depth > DEPTH_TOLERANCE)
+        {
+            levels.push(depth);
+        }
+    }
+    let mut tier: Vec<usize> = plans
+        .iter()
+        .map(|plan| {
+            levels
+                .iter()
+                .position(|level| level - plan.depth <= DEPTH_TOLERANCE)
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for _ in 0..plans.len() {
+        let mut changed = false;
+        for a in 0..plans.len() {
+            for b in 0..plans.len() {
+                if before[a][b] && tier[b] < tier[a] {
+                    tier[b] = tier[a];
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut tiers = vec![Vec::new(); levels.len()];
+    for (i, t) in tier.into_iter().enumerate() {
+        tiers[t].push(i);
+    }
+    tiers.retain(|t| !t.is_empty());
+    tiers
+}
+
+/// Visits the closest job whose inner jobs are all cut.
+fn nearest_neighbour(
+    tier: &[usize],
+    plans: &[Plan],
+    before: &[Vec<bool>],
+    start: Point,
+) -> Vec<(usize, Entry)> {
+    let mut remaining = tier.to_vec();
+    let mut waiting: Vec<usize> = remaining
+        .iter()
+        .map(|&b| remaining.iter().filter(|&&a| before[a][b]).count())
+        .collect();
+    let mut route = Vec::with_capacity(remaining.len());
+    let mut position = start;
+
+    while !remaining.is_empty() {
+        let k = (0..remaining.len())
+            .filter(|&k| waiting[k] == 0)
+            .min_by(|&x, &y| {
+                let cost = |k: usize| {
+                    let plan = &plans[remaining[k]];
+                    plan.link_cost(plan.best_entry(position, None), position, None)
+                };
+                cost(x).total_cmp(&cost(y))
+            })
+            .unwrap_or(0);
+        let i = remaining.remove(k);
+        waiting.remove(k);
+        for (w, &b) in waiting.iter_mut().zip(&remaining) {
+            if before[i][b] {
+                *w = w.saturating_sub(1);
+            }
+        }
+        let entry = plans[i].best_entry(position, None);
+        position = plans[i].ends(entry).1;
+        route.push((i, entry));
+    }
+    route
+}
+
+/// Improves a route with 2-opt moves and better start points.
+fn improve(route: &mut [(usize, Entry)], plans: &[Plan], before: &[Vec<bool>], start: Point) {
+    let mut cost = route_cost(route, plans, start);
+    for _ in 0..MAX_ROUNDS {
+        if route.len() <= MAX_TWO_OPT_JOBS {
+            two_opt(route, plans, before, start);
+        }
+        let refined = refine_entries(route, plans, start);
+        if refined >= cost - TOLERANCE {
+            break;
+        }
+        cost = refined;
+    }
+}
+
+/// One sweep of 2-opt, keeping every reversal that shortens the route.
+fn two_opt(route: &mut [(usize, Entry)], plans: &[Plan], before: &[Vec<bool>], start: Point) {
+    let mut cost = route_cost(route, plans, start);
+    for i in 0..route.len() {
+        for j in i + 1..route.len() {
+            // Reversing would cut a job before one that lies inside it
+            if route[i..j].iter().any(|&(a, _)| before[a][route[j].0]) {
+                break;
+            }
+            reverse_span(&mut route[i..=j], plans);
+            let candidate = route_cost(route, plans, start);
+            if candidate < cost - TOLERANCE {
+                cost = candidate;
+            } else {
+                reverse_span(&mut route[i..=j], plans);
+            }
+        }
+    }
+}
+
+fn reverse_span(span: &mut [(usize, Entry)], plans: &[Plan]) {
+    span.reverse();
+    for (i, entry) in span.iter_mut() {
+        *entry = match *entry {
+            Entry::Forward if plans[*i].reversible => Entry::Reversed,
+            Entry::Reversed => Entry::Forward,
+            other => other,
+        };
+    }
+}
+
+/// Picks each job's start point and direction given its neighbours.
+fn refine_entries(route: &mut [(usize, Entry)], plans: &[Plan], start: Point) -> f64 {
+    for k in 0..route.len() {
+        let from = match k {
+            0 => start,
+            _ => plans[route[k - 1].0].ends(route[k - 1].1).1,
+        };
+        let to = route.get(k + 1).map(|&(i, entry)| plans[i].ends(entry).0);
+        let plan = &plans[route[k].0];
+        let best = plan.best_entry(from, to);
+        if plan.link_cost(best, from, to) < plan.link_cost(route[k].1, from, to) - TOLERANCE {
+            route[k].1 = best;
+        }
+    }
+    route_cost(route, plans, start)
+}
+
+/// Travel between jobs along a route.
+fn route_cost(route: &[(usize, Entry)], plans: &[Plan], start: Point) -> f64 {
+    let mut position = start;
+    let mut cost = 0.0;
+    for &(i, entry) in route {
+        let (entry, exit) = plans[i].ends(entry);
+        cost += position.distance_to(&entry);
+        position = exit;
+    }
+    cost
+}
+
+/// Where the tool first goes in a toolpath.
+fn start_point(toolpath: &Toolpath) -> Option<Point> {
+    let seg = toolpath.segments.first()?;
+    Some(match seg.segment_type {
+        ToolpathSegmentType::RapidMove => seg.end,
+        _ => seg.start,
+    })
+}
+
+/// Whether a toolpath starts with a rapid and cuts at one depth throughout.
+fn is_flat(toolpath: &Toolpath) -> bool {
+    if toolpath
+        .segments
+        .first()
+        .is_some_and(|seg| seg.segment_type != ToolpathSegmentType::RapidMove)
+    {
+        return false;
+    }
+    let mut depths = toolpath
+        .segments
+        .iter()
+        .filter(|seg| seg.segment_type != ToolpathSegmentType::RapidMove)
+        .map(|seg| segment_z(seg, toolpath.depth));
+    let Some((z, _)) = depths.next() else {
+        return true;
+    };
+    depths
+        .chain(std::iter::once((z, z)))
+        .all(|(z0, z1)| (z0 - z).abs() < TOLERANCE && (z1 - z).abs() < TOLERANCE)
+}
+
+/// The closed loop shared by every pass, each a rapid then the loop at one depth.
+fn closed_loop(toolpaths: &[Toolpath]) -> Option<Vec<ToolpathSegment>> {
+    let mut shared: Option<&[ToolpathSegment]> = None;
+    for toolpath in toolpaths {
+        let (_, cuts) = toolpath.segments.split_first()?;
+        let closed = is_flat(toolpath)
+            && !cuts.is_empty()
+            && cuts
+                .iter()
+                .all(|seg| seg.segment_type != ToolpathSegmentType::RapidMove)
+            && cuts
+                .windows(2)
+                .all(|w| w[0].end.distance_to(&w[1].start) < JOIN_TOLERANCE)
+            && cuts[cuts.len() - 1].end.distance_to(&cuts[0].start) < JOIN_TOLERANCE;
+        if !closed {
+            return None;
+        }
+        match shared {
+            None => shared = Some(cuts),
+            Some(first) => {
+                let same = first.len() == cuts.len()
+                    && first
+                        .iter()
+                        .zip(cuts)
+                        .all(|(a, b)| a.start.distance_to(&b.start) < JOIN_TOLERANCE);
+                if !same {
+                    return None;
+                }
+            }
+        }
+    }
+    shared.map(<[ToolpathSegment]>::to_vec)
+}
+
+/// Closest point on a loop, with its segment index and parameter.
+fn closest_on_loop(closed_loop: &[ToolpathSegment], p: Point) -> (Point, usize, f64) {
+    let mut best = (p, 0, 0.0);
+    let mut best_distance = f64::INFINITY;
+    for (k, seg) in closed_loop.iter().enumerate() {
+        let (q, t) = closest_on_segment(p, seg);
+        let distance = q.distance_to(&p);
+        if distance < best_distance {
+            best_distance = distance;
+            best = (q, k, t);
+        }
+    }
+    best
+}
+
+/// Starts a closed-loop pass at the loop point closest to `at`.
+fn rotate_toolpath(toolpath: &Toolpath, at: Point) -> Toolpath {
+    let Some((rapid, cuts)) = toolpath.segments.split_first() else {
+        return toolpath.clone();
+    };
+    let (_, k, t) = closest_on_loop(cuts, at);
+    let (k, t) = if t >= 1.0 - SPLIT_TOLERANCE {
+        ((k + 1) % cuts.len(), 0.0)
+    } else if t <= SPLIT_TOLERANCE {
+        (k, 0.0)
+    } else {
+        (k, t)
+    };
+
+    let mut segments = Vec::with_capacity(cuts.len() + 2);
+    let mut lead = rapid.clone();
+    if t > 0.0 {
+        let head = sub_segment(&cuts[k], t, 1.0);
+        lead.end = head.start;
+        segments.push(lead);
+        segments.push(head);
+        segments.extend_from_slice(&cuts[k + 1..]);
+        segments.extend_from_slice(&cuts[..k]);
+        segments.push(sub_segment(&cuts[k], 0.0, t));
+    } else {
+        lead.end = cuts[k].start;
+        segments.push(lead);
+        segments.extend_from_slice(&cuts[k..]);
+        segments.extend_from_slice(&cuts[..k]);
+    }
+
+    let mut rotated = Toolpath::new(toolpath.tool_diameter, toolpath.depth);
+    rotated.segments = segments;
+    rotated
+}
+
+/// Runs a flat toolpath backwards, run by run.
+fn reverse_toolpath(toolpath: &Toolpath) -> Toolpath {
+    let mut reversed = Toolpath::new(toolpath.tool_diameter, toolpath.depth);
+    let Some(rapid) = toolpath.segments.first() else {
+        return reversed;
+    };
+    for run in cutting_runs(&toolpath.segments).into_iter().rev() {
+        let cuts = &toolpath.segments[run];
+        let mut lead = rapid.clone();
+        lead.end = cuts[cuts.len() - 1].end;
+        reversed.add_segment(lead);
+        for seg in cuts.iter().rev() {
+            let mut back = seg.clone();
+            back.start = seg.end;
+            back.end = seg.start;
+            back.segment_type = match seg.segment_type {
+                ToolpathSegmentType::ArcCW => ToolpathSegmentType::ArcCCW,
+                ToolpathSegmentType::ArcCCW => ToolpathSegmentType::ArcCW,
+                other => other,
+            };
+            reversed.add_segment(back);
+        }
+    }
+    reversed
+}
+
+/// The largest closed run of a profile pass, as a polygon.
+fn profile_outline(toolpath: &Toolpath) -> Option<Vec<Point>> {
+    cutting_runs(&toolpath.segments)
+        .into_iter()
+        .map(|run| &toolpath.segments[run])
+        .filter(|cuts| cuts[cuts.len() - 1].end.distance_to(&cuts[0].start) < JOIN_TOLERANCE)
+        .map(|cuts| {
+            cuts.iter()
+                .flat_map(|seg| segment_points(seg, ARC_STEPS))
+                .collect::<Vec<Point>>()
+        })
+        .filter(|polygon| polygon.len() >= 3)
+        .max_by(|a, b| polygon_area(a).total_cmp(&polygon_area(b)))
+}
+
+/// Points along a segment, its end excluded.
+fn segment_points(seg: &ToolpathSegment, arc_steps: usize) -> Vec<Point> {
+    let Some(center) = arc_center(seg) else {
+        return vec![seg.start];
+    };
+    let (a0, sweep) = arc_angles(seg, center);
+    let r = seg.start.distance_to(&center);
+    (0..arc_steps)
+        .map(|s| {
+            let angle = a0 + sweep * s as f64 / arc_steps as f64;
+            Point::new(center.x + r * angle.cos(), center.y + r * angle.sin())
+        })
+        .collect()
+}
+
+/// Points along a job's cuts, thinned to at most `MAX_SAMPLES`.
+fn samples(toolpaths: &[Toolpath]) -> Vec<Point> {
+    let points: Vec<Point> = toolpaths
+        .iter()
+        .flat_map(|tp| &tp.segments)
+        .filter(|seg| seg.segment_type != ToolpathSegmentType::RapidMove)
+        .flat_map(|seg| {
+            let mut points = segment_points(seg, 2);
+            points.push(seg.end);
+            points
+        })
+        .collect();
+    let stride = points.len().div_ceil(MAX_SAMPLES).max(1);
+    points.into_iter().step_by(stride).collect()
+}
+
+fn polygon_area(polygon: &[Point]) -> f64 {
+    let mut twice = 0.0;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        twice += a.x * b.y - b.x * a.y;
+    }
+    (twice / 2.0).abs()
+}
+
+/// Whether `p` is inside `polygon` or within `margin` of its boundary.
+fn inside(p: Point, polygon: &[Point], margin: f64) -> bool {
+    let mut inside = false;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        if closest_on_line(p, *a, b).0.distance_to(&p) <= margin {
+            return true;
+        }
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn bounds(points: &[Point]) -> (f64, f64, f64, f64) {
+    points.iter().fold(
+        (
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ),
+        |(x0, y0, x1, y1), p| (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y)),
+    )
+}
+
+fn grow((x0, y0, x1, y1): (f64, f64, f64, f64), margin: f64) -> (f64, f64, f64, f64) {
+    (x0 - margin, y0 - margin, x1 + margin, y1 + margin)
+}
+
+fn within(inner: (f64, f64, f64, f64), outer: (f64, f64, f64, f64)) -> bool {
+    inner.0 >= outer.0 && inner.1 >= outer.1 && inner.2 <= outer.2 && inner.3 <= outer.3
+}
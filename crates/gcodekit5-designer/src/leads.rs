@@ -0,0 +1,558 @@
+//! Lead-in and lead-out moves for profile operations.
+//!
+//! A profile that plunges straight down onto its contour leaves a witness
+//! mark where it starts and ends. Leads move the plunge off the part: the
+//! cutter enters on the waste side along a tangent arc or a perpendicular
+//! line, or ramps down along the contour itself, and leaves the same way.
+//! An optional overlap re-cuts the start of a closed contour so the seam is
+//! clean. The start point is a fraction of the length of the shape's drawn
+//! outline, like tab positions, and is projected onto the generated
+//! toolpath.
+
+use crate::model::{Point, Shape};
+use crate::tabs::Outline;
+use crate::toolpath::geometry::{
+    arc_center, closest_on_segment, cutting_runs, segment_length, segment_z, sub_segment,
+    CLOSE_TOLERANCE,
+};
+use crate::toolpath::{Toolpath, ToolpathSegment, ToolpathSegmentType};
+
+/// Number of chords used per arc when measuring contour winding.
+const ARC_SAMPLES: usize = 8;
+
+/// How the cutter enters or leaves a profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, Default)]
+pub enum LeadType {
+    /// Plunge and lift on the contour.
+    #[default]
+    None,
+    /// Quarter arc tangent to the contour, on the waste side.
+    Arc,
+    /// Straight line perpendicular to the contour, on the waste side.
+    Line,
+    /// Ramp down (or up) along the contour itself.
+    Ramp,
+}
+
+impl LeadType {
+    /// Returns the name of the lead type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LeadType::None => "None",
+            LeadType::Arc => "Arc",
+            LeadType::Line => "Line",
+            LeadType::Ramp => "Ramp",
+        }
+    }
+}
+
+/// Lead-in and lead-out settings for a profile.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LeadSettings {
+    pub lead_in: LeadType,
+    pub lead_out: LeadType,
+    /// Length of line and ramp leads, in mm.
+    pub length: f64,
+    /// Radius of arc leads, in mm.
+    pub radius: f64,
+    /// Distance a closed contour is cut past its start point, in mm.
+    pub overlap: f64,
+    /// Start point as a fraction (0-1) of the outline length. `None` keeps
+    /// the contour's own start.
+    pub start_position: Option<f64>,
+}
+
+impl Default for LeadSettings {
+    fn default() -> Self {
+        Self {
+            lead_in: LeadType::None,
+            lead_out: LeadType::None,
+            length: 5.0,
+            radius: 3.0,
+            overlap: 0.0,
+            start_position: None,
+        }
+    }
+}
+
+impl LeadSettings {
+    /// Whether these settings change the toolpath at all.
+    pub fn is_active(&self) -> bool {
+        self.lead_in != LeadType::None
+            || self.lead_out != LeadType::None
+            || self.overlap > 0.0
+            || self.start_position.is_some()
+    }
+}
+
+/// The chosen start point on the outline of `shape`, if one is set.
+pub fn lead_start_point(shape: &Shape, settings: &LeadSettings) -> Option<Point> {
+    let fraction = settings.start_position?;
+    Outline::from_shape(shape).point_at(fraction)
+}
+
+/// Adds lead moves and overlap to every cutting run of `toolpath`.
+///
+/// The run nearest `start` is started there. `waste_outside` says which side
+/// of an outer contour is waste; it is reversed for contours nested inside
+/// another (holes). Ramps descend from `ramp_top_z`, normally the depth of
+/// the previous pass, to the run's cutting depth.
+pub fn apply_leads(
+    toolpath: &Toolpath,
+    settings: &LeadSettings,
+    start: Option<Point>,
+    waste_outside: bool,
+    ramp_top_z: f64,
+) -> Toolpath {
+    if !settings.is_active() {
+        return toolpath.clone();
+    }
+    let runs = cutting_runs(&toolpath.segments);
+    let loops: Vec<Option<Vec<Point>>> = runs
+        .iter()
+        .map(|run| {
+            let segments = &toolpath.segments[run.clone()];
+            is_closed(segments).then(|| flatten(segments))
+        })
+        .collect();
+    let start_run = start.and_then(|p| nearest_run(&toolpath.segments, &runs, p));
+
+    let mut result = toolpath.clone();
+    result.segments.clear();
+    let mut next = 0;
+    for (index, run) in runs.iter().enumerate() {
+        result
+            .segments
+            .extend(toolpath.segments[next..run.start].iter().cloned());
+        next = run.end;
+
+        let mut base = toolpath.segments[run.clone()].to_vec();
+        // Only flat loops can be restarted or extended; a run that descends
+        // as it goes round must keep its own start
+        let closed = loops[index].is_some() && is_flat(&base, toolpath.depth);
+        let mut overlap = 0.0;
+        if closed {
+            if let (Some(p), Some(run_index)) = (start, start_run) {
+                if run_index == index {
+                    base = rotate_to(&base, p);
+                }
+            }
+            overlap = settings.overlap.clamp(0.0, run_length(&base));
+        }
+        let mut segments = base.clone();
+        segments.extend(slice(&base, 0.0, overlap));
+
+        // Waste is on the left of the direction of travel when `side` is 1
+        let side = match &loops[index] {
+            Some(points) => {
+                let outside_is_left = signed_area(points) < 0.0;
+                let nested = loops.iter().enumerate().any(|(other, polygon)| {
+                    other != index
+                        && polygon
+                            .as_ref()
+                            .is_some_and(|polygon| contains(polygon, points[0]))
+                });
+                if outside_is_left == (waste_outside != nested) {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            None if waste_outside => 1.0,
+            None => -1.0,
+        };
+
+        let contour = Contour {
+            base: &base,
+            closed,
+            overlap,
+            side,
+            depth: toolpath.depth,
+        };
+        let lead_in = lead_in_moves(&contour, settings, ramp_top_z);
+        let lead_out = lead_out_moves(&contour, &segments, settings, ramp_top_z);
+
+        // Send the approach rapid to wherever the cut now begins
+        let entry = lead_in.first().or(segments.first()).map(|s| s.start);
+        if let (Some(entry), Some(rapid)) = (entry, result.segments.last_mut()) {
+            if rapid.segment_type == ToolpathSegmentType::RapidMove {
+                rapid.end = entry;
+            }
+        }
+        result.segments.extend(lead_in);
+        result.segments.extend(segments);
+        result.segments.extend(lead_out);
+        if let (Some(last), Some(rapid)) = (
+            result.segments.last().map(|s| s.end),
+            toolpath.segments.get(run.end),
+        ) {
+            if rapid.segment_type == ToolpathSegmentType::RapidMove {
+                let mut rapid = rapid.clone();
+                rapid.start = last;
+                result.segments.push(rapid);
+                next = run.end + 1;
+            }
+        }
+    }
+    result
+        .segments
+        .extend(toolpath.segments[next..].iter().cloned());
+    result
+}
+
+/// One cutting run, as cut before lead moves are added.
+struct Contour<'a> {
+    /// The run once, from its start point.
+    base: &'a [ToolpathSegment],
+    /// Whether `base` is a flat closed loop.
+    closed: bool,
+    /// Length of `base` cut again after the loop.
+    overlap: f64,
+    /// 1 when the waste is on the left of the direction of travel, -1 when
+    /// it is on the right.
+    side: f64,
+    depth: f64,
+}
+
+impl Contour<'_> {
+    /// Unit normal pointing into the waste, given the direction of travel.
+    fn waste_normal(&self, t: Point) -> Point {
+        Point::new(-t.y * self.side, t.x * self.side)
+    }
+}
+
+/// Moves that bring the cutter onto the start of the contour.
+fn lead_in_moves(
+    contour: &Contour,
+    settings: &LeadSettings,
+    ramp_top_z: f64,
+) -> Vec<ToolpathSegment> {
+    let Some(first) = contour.base.first() else {
+        return Vec::new();
+    };
+    let (z, _) = segment_z(first, contour.depth);
+    let s = first.start;
+    let t = start_tangent(first);
+    let n = contour.waste_normal(t);
+    match settings.lead_in {
+        LeadType::None => Vec::new(),
+        LeadType::Line if settings.length > 0.0 => {
+            let from = Point::new(s.x + n.x * settings.length, s.y + n.y * settings.length);
+            vec![at_z(line(first, from, s), z, z)]
+        }
+        LeadType::Arc if settings.radius > 0.0 => {
+            let r = settings.radius;
+            let center = Point::new(s.x + n.x * r, s.y + n.y * r);
+            let from = Point::new(center.x - t.x * r, center.y - t.y * r);
+            vec![at_z(arc(first, from, s, center, contour.side), z, z)]
+        }
+        LeadType::Ramp if settings.length > 0.0 => {
+            let total = run_length(contour.base);
+            let length = settings.length.min(total);
+            let path = if contour.closed {
+                // Come down along the end of the loop, arriving at its start
+                slice(contour.base, total - length, total)
+            } else {
+                // Come down backwards along the start of the run
+                reverse(&slice(contour.base, 0.0, length))
+            };
+            ramp(path, ramp_top_z.max(z), z)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Moves that take the cutter off the end of `segments`, the contour as
+/// cut including any overlap.
+fn lead_out_moves(
+    contour: &Contour,
+    segments: &[ToolpathSegment],
+    settings: &LeadSettings,
+    ramp_top_z: f64,
+) -> Vec<ToolpathSegment> {
+    let Some(last) = segments.last() else {
+        return Vec::new();
+    };
+    let (_, z) = segment_z(last, contour.depth);
+    let e = last.end;
+    let t = end_tangent(last);
+    let n = contour.waste_normal(t);
+    match settings.lead_out {
+        LeadType::None => Vec::new(),
+        LeadType::Line if settings.length > 0.0 => {
+            let to = Point::new(e.x + n.x * settings.length, e.y + n.y * settings.length);
+            vec![at_z(line(last, e, to), z, z)]
+        }
+        LeadType::Arc if settings.radius > 0.0 => {
+            let r = settings.radius;
+            let center = Point::new(e.x + n.x * r, e.y + n.y * r);
+            let to = Point::new(center.x + t.x * r, center.y + t.y * r);
+            vec![at_z(arc(last, e, to, center, contour.side), z, z)]
+        }
+        LeadType::Ramp if settings.length > 0.0 => {
+            let total = run_length(contour.base);
+            let length = settings.length.min(total);
+            let path = if contour.closed {
+                // Carry on round the loop from where the cut ended
+                let end = contour.overlap.rem_euclid(total.max(f64::EPSILON));
+                let mut path = slice(contour.base, end, end + length);
+                let cut = run_length(&path);
+                if cut < length - 1e-6 {
+                    path.extend(slice(contour.base, 0.0, length - cut));
+                }
+                path
+            } else {
+                // Go back up along the end of the run
+                reverse(&slice(contour.base, total - length, total))
+            };
+            ramp(path, z, ramp_top_z.max(z))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Sets a straight Z ramp from `from_z` to `to_z` along `path`.
+fn ramp(path: Vec<ToolpathSegment>, from_z: f64, to_z: f64) -> Vec<ToolpathSegment> {
+    let total = run_length(&path);
+    let mut along = 0.0;
+    path.into_iter()
+        .map(|seg| {
+            let length = segment_length(&seg);
+            let z_at = |s: f64| {
+                if total <= 0.0 {
+                    to_z
+                } else {
+                    from_z + (to_z - from_z) * s / total
+                }
+            };
+            let (z0, z1) = (z_at(along), z_at(along + length));
+            along += length;
+            // Never go below the contour's own Z, which includes any tabs
+            let (own0, own1) = (seg.start_z, seg.z_depth);
+            at_z(
+                seg,
+                own0.map_or(z0, |z| z.max(z0)),
+                own1.map_or(z1, |z| z.max(z1)),
+            )
+        })
+        .collect()
+}
+
+fn at_z(mut seg: ToolpathSegment, start_z: f64, end_z: f64) -> ToolpathSegment {
+    seg.start_z = Some(start_z);
+    seg.z_depth = Some(end_z);
+    seg
+}
+
+fn line(like: &ToolpathSegment, from: Point, to: Point) -> ToolpathSegment {
+    ToolpathSegment::new(
+        ToolpathSegmentType::LinearMove,
+        from,
+        to,
+        like.feed_rate,
+        like.spindle_speed,
+    )
+}
+
+/// Quarter arc turning towards the contour: counter-clockwise when the
+/// waste is on the left (`side` 1), clockwise otherwise.
+fn arc(
+    like: &ToolpathSegment,
+    from: Point,
+    to: Point,
+    center: Point,
+    side: f64,
+) -> ToolpathSegment {
+    let segment_type = if side > 0.0 {
+        ToolpathSegmentType::ArcCCW
+    } else {
+        ToolpathSegmentType::ArcCW
+    };
+    ToolpathSegment::new_arc(
+        segment_type,
+        from,
+        to,
+        center,
+        like.feed_rate,
+        like.spindle_speed,
+    )
+}
+
+/// Unit direction of travel at the start of `seg`.
+fn start_tangent(seg: &ToolpathSegment) -> Point {
+    tangent_at(seg, seg.start, seg.end)
+}
+
+/// Unit direction of travel at the end of `seg`.
+fn end_tangent(seg: &ToolpathSegment) -> Point {
+    tangent_at(seg, seg.end, seg.start)
+}
+
+fn tangent_at(seg: &ToolpathSegment, at: Point, other: Point) -> Point {
+    let (dx, dy) = match arc_center(seg) {
+        Some(c) => {
+            let (vx, vy) = (at.x - c.x, at.y - c.y);
+            if seg.segment_type == ToolpathSegmentType::ArcCCW {
+                (-vy, vx)
+            } else {
+                (vy, -vx)
+            }
+        }
+        None if at.distance_to(&seg.start) <= f64::EPSILON => (other.x - at.x, other.y - at.y),
+        None => (at.x - other.x, at.y - other.y),
+    };
+    let length = dx.hypot(dy);
+    if length <= f64::EPSILON {
+        Point::new(1.0, 0.0)
+    } else {
+        Point::new(dx / length, dy / length)
+    }
+}
+
+fn is_closed(segments: &[ToolpathSegment]) -> bool {
+    match (segments.first(), segments.last()) {
+        (Some(first), Some(last)) => first.start.distance_to(&last.end) <= CLOSE_TOLERANCE,
+        _ => false,
+    }
+}
+
+/// Whether the run ends at the Z it starts at, so it can be rotated and
+/// extended without breaking a descending ramp.
+fn is_flat(segments: &[ToolpathSegment], depth: f64) -> bool {
+    match (segments.first(), segments.last()) {
+        (Some(first), Some(last)) => {
+            (segment_z(first, depth).0 - segment_z(last, depth).1).abs() < 1e-6
+        }
+        _ => false,
+    }
+}
+
+fn run_length(segments: &[ToolpathSegment]) -> f64 {
+    segments.iter().map(segment_length).sum()
+}
+
+/// The part of a run between distances `from` and `to` along it.
+fn slice(segments: &[ToolpathSegment], from: f64, to: f64) -> Vec<ToolpathSegment> {
+    let mut out = Vec::new();
+    let mut along = 0.0;
+    for seg in segments {
+        let length = segment_length(seg);
+        let (a, b) = (from.max(along), to.min(along + length));
+        if b - a > 1e-6 && length > 0.0 {
+            let piece = sub_segment(seg, (a - along) / length, (b - along) / length);
+            let (z0, z1) = (seg.start_z, seg.z_depth);
+            let mut piece = piece;
+            if let (Some(z0), Some(z1)) = (z0, z1) {
+                piece.start_z = Some(z0 + (z1 - z0) * (a - along) / length);
+                piece.z_depth = Some(z0 + (z1 - z0) * (b - along) / length);
+            }
+            out.push(piece);
+        }
+        along += length;
+    }
+    out
+}
+
+fn reverse(segments: &[ToolpathSegment]) -> Vec<ToolpathSegment> {
+    segments
+        .iter()
+        .rev()
+        .map(|seg| {
+            let mut seg = seg.clone();
+            std::mem::swap(&mut seg.start, &mut seg.end);
+            std::mem::swap(&mut seg.start_z, &mut seg.z_depth);
+            seg.segment_type = match seg.segment_type {
+                ToolpathSegmentType::ArcCW => ToolpathSegmentType::ArcCCW,
+                ToolpathSegmentType::ArcCCW => ToolpathSegmentType::ArcCW,
+                other => other,
+            };
+            seg
+        })
+        .collect()
+}
+
+/// Restarts a closed run at the point on it nearest `p`.
+fn rotate_to(segments: &[ToolpathSegment], p: Point) -> Vec<ToolpathSegment> {
+    let total = run_length(segments);
+    let mut best = (f64::INFINITY, 0.0);
+    let mut along = 0.0;
+    for seg in segments {
+        let (q, t) = closest_on_segment(p, seg);
+        let length = segment_length(seg);
+        let d = q.distance_to(&p);
+        if d < best.0 {
+            best = (d, along + t * length);
+        }
+        along += length;
+    }
+    let s = best.1;
+    if s <= 1e-6 || s >= total - 1e-6 {
+        return segments.to_vec();
+    }
+    let mut rotated = slice(segments, s, total);
+    rotated.extend(slice(segments, 0.0, s));
+    rotated
+}
+
+/// Index of the run passing nearest `p`.
+fn nearest_run(
+    segments: &[ToolpathSegment],
+    runs: &[std::ops::Range<usize>],
+    p: Point,
+) -> Option<usize> {
+    runs.iter()
+        .enumerate()
+        .filter_map(|(index, run)| {
+            segments[run.clone()]
+                .iter()
+                .map(|seg| closest_on_segment(p, seg).0.distance_to(&p))
+                .min_by(|a, b| a.total_cmp(b))
+                .map(|d| (d, index))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, index)| index)
+}
+
+/// Points along a run, with arcs split into chords.
+fn flatten(segments: &[ToolpathSegment]) -> Vec<Point> {
+    let mut points = Vec::new();
+    for seg in segments {
+        if points.is_empty() {
+            points.push(seg.start);
+        }
+        if arc_center(seg).is_some() {
+            for i in 1..=ARC_SAMPLES {
+                let t = i as f64 / ARC_SAMPLES as f64;
+                points.push(sub_segment(seg, 0.0, t).end);
+            }
+        } else {
+            points.push(seg.end);
+        }
+    }
+    points
+}
+
+/// Signed area of a closed polygon, positive when counter-clockwise.
+fn signed_area(points: &[Point]) -> f64 {
+    points
+        .windows(2)
+        .map(|w| w[0].x * w[1].y - w[1].x * w[0].y)
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Even-odd point in polygon test.
+fn contains(polygon: &[Point], p: Point) -> bool {
+    let mut inside = false;
+    for w in polygon.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        if (a.y > p.y) != (b.y > p.y) {
+            let x = a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if p.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
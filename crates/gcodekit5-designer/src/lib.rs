@@ -14,11 +14,21 @@
 //!
 //! ### CAM Operations Integration
 //! - **Pocket Operations**: Hollow out areas with tool compensation
-//! - **Drilling Patterns**: Generate hole drilling sequences
+//! - **Profile Operations**: Cut outside, inside or on the line, climb or conventional
+//! - **Holding Tabs**: Leave bridges on through-cut profiles so parts stay in place
+//! - **Lead-In/Out**: Enter and leave profiles off the part to avoid witness marks
+//! - **Drilling Patterns**: Generate hole drilling sequences, with peck drilling at circle centres
 //! - **Multipass**: Cut thick materials in multiple depths
 //! - **Adaptive**: Optimize toolpath load for better cutting
-//! - **V-Carving**: Advanced angle-based cutting
+//! - **Engraving**: Follow a shape's outline at a fixed depth
+//! - **V-Carving**: Variable-depth V-bit carving along the shape's medial axis
+//! - **Tool Changes**: Per-object library tools, ordered by tool, with rest machining
+//! - **Cut Order**: Sequence operations to minimise rapid travel
+//! - **Corner Relief**: Dogbone and T-bone relief so square parts fit together
 //! - **Arrays**: Create repetitive patterns
+//! - **Nesting**: Pack parts onto stock sheets by their true shape
+//! - **3D Finishing**: Drop-cutter parallel and waterline passes over STL meshes
+//! - **Relief Carving**: Carve grayscale images as 2.5D reliefs
 //! - **Parametric**: Generate designs from parameters
 //!
 //! ### Advanced Features
@@ -66,6 +76,7 @@ pub mod adaptive;
 pub mod arrays;
 pub mod canvas;
 pub mod commands;
+pub mod dogbone;
 pub mod drilling_patterns;
 pub mod dxf_parser;
 pub mod error;
@@ -74,13 +85,18 @@ pub mod gcode_gen;
 pub mod helpers;
 pub mod history;
 pub mod import;
+pub mod job_order;
+pub mod leads;
 pub mod model;
 pub mod model3d;
 pub mod multipass;
+pub mod nesting;
 pub mod ops;
 pub mod parametric;
 pub mod parametric_shapes;
 pub mod pocket_operations;
+pub mod profile_operations;
+pub mod relief;
 pub mod render_optimizer;
 pub mod renderer;
 pub mod selection_manager;
@@ -92,8 +108,11 @@ pub mod slice_toolpath;
 pub mod spatial_index;
 pub mod spatial_manager;
 pub mod stock_removal;
+pub mod surface_finishing;
 pub mod svg_renderer;
+pub mod tabs;
 pub mod templates;
+pub mod tool_changes;
 pub mod tool_library;
 pub mod toolpath;
 pub mod toolpath_simulation;
@@ -117,19 +136,25 @@ pub use arrays::{
 };
 pub use canvas::{Canvas, CanvasPoint, DrawingMode};
 pub use commands::DesignerCommand;
+pub use dogbone::{DogboneSettings, DogboneStyle};
 pub use drilling_patterns::*;
 pub use dxf_parser::{DxfEntity, DxfFile, DxfHeader, DxfParser};
 pub use gcode_gen::ToolpathToGcode;
 pub use history::{ActionType, HistoryAction, HistoryTransaction, UndoRedoManager};
 pub use import::{DxfImporter, FileFormat, ImportedDesign, StlImporter, SvgImporter};
+pub use job_order::{OrderJob, OrderReport};
+pub use leads::{LeadSettings, LeadType};
 pub use model::{
     DesignCircle as Circle, DesignEllipse as Ellipse, DesignLine as Line, DesignPath as PathShape,
     DesignRectangle as Rectangle, DesignText as TextShape, Point, Shape, ShapeType,
 };
 pub use model3d::{Mesh3D, Model3DFormat, Model3DImporter, ProjectionParams, Triangle3D};
 pub use multipass::{DepthStrategy, MultiPassConfig, MultiPassToolpathGenerator};
+pub use nesting::{NestPart, NestResult, NestRotation, NestSettings, Placement, SheetReport};
 pub use parametric::ParametricGenerator;
 pub use pocket_operations::{Island, PocketGenerator, PocketOperation};
+pub use profile_operations::{CutDirection, CutSide};
+pub use relief::{ReliefCarver, ReliefJob, ReliefMap, ReliefPass, ReliefSettings};
 pub use render_optimizer::{RenderOptimizer, RenderStats};
 pub use shadow_projection::{
     BatchProjector, ProjectionMethod, ShadowProjectionParams, ShadowProjector, SliceLayer,
@@ -140,11 +165,14 @@ pub use slice_toolpath::{
 };
 pub use spatial_index::{Bounds, SpatialIndex, SpatialIndexStats};
 pub use stock_removal::{HeightMap2D, SimulationResult, StockMaterial};
+pub use surface_finishing::{DropCutter, FinishStrategy, FinishingParams, SurfaceFinisher};
+pub use tabs::{TabSettings, TabShape};
 pub use templates::*;
+pub use tool_changes::{AssignedTool, ToolChangeSettings};
 pub use tool_library::{CoolantType, MaterialProfile, Tool, ToolLibrary, ToolType};
 pub use toolpath::{Toolpath, ToolpathGenerator, ToolpathSegment, ToolpathSegmentType};
 pub use toolpath_simulation::{SimulationState, ToolPosition, ToolpathAnalyzer, ToolpathSimulator};
-pub use vcarve::VCarveGenerator;
+pub use vcarve::{VCarveGenerator, VCarveSettings};
 pub use viewport::Viewport;
 
 // State and integration
@@ -3,6 +3,7 @@
 //! Implements depth ramping and stepping for multi-pass cutting operations,
 //! enabling deep cuts while maintaining tool safety and surface finish quality.
 
+use super::tabs::{apply_tabs, TabSettings};
 use super::toolpath::{Toolpath, ToolpathSegment, ToolpathSegmentType};
 use crate::Point;
 
@@ -151,12 +152,25 @@ impl MultiPassConfig {
 /// Manages multi-pass toolpath generation.
 pub struct MultiPassToolpathGenerator {
     config: MultiPassConfig,
+    tab_settings: TabSettings,
+    tab_points: Vec<Point>,
 }
 
 impl MultiPassToolpathGenerator {
     /// Creates a new multi-pass toolpath generator.
     pub fn new(config: MultiPassConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            tab_settings: TabSettings::default(),
+            tab_points: Vec::new(),
+        }
+    }
+
+    /// Sets holding tabs centred at `points`. Passes that reach below the
+    /// top of the tabs lift over them; shallower passes are unchanged.
+    pub fn set_tabs(&mut self, settings: TabSettings, points: Vec<Point>) {
+        self.tab_settings = settings;
+        self.tab_points = points;
     }
 
     /// Generates a multi-pass toolpath from a single-pass toolpath.
@@ -167,9 +181,21 @@ impl MultiPassToolpathGenerator {
         for pass in 1..=passes {
             let pass_depth = self.config.calculate_pass_depth(pass);
 
+            let mut pass_toolpath = Toolpath::new(base_toolpath.tool_diameter, pass_depth);
             for segment in &base_toolpath.segments {
                 let adjusted_segment = self.adjust_segment_depth(segment, pass_depth);
-                multi_pass.add_segment(adjusted_segment);
+                pass_toolpath.add_segment(adjusted_segment);
+            }
+            if self.tab_settings.enabled && !self.tab_points.is_empty() {
+                pass_toolpath = apply_tabs(
+                    &pass_toolpath,
+                    &self.tab_points,
+                    &self.tab_settings,
+                    self.config.total_depth,
+                );
+            }
+            for segment in pass_toolpath.segments {
+                multi_pass.add_segment(segment);
             }
         }
 
@@ -179,7 +205,10 @@ impl MultiPassToolpathGenerator {
     /// Adjusts a toolpath segment to the specified depth.
     fn adjust_segment_depth(&self, segment: &ToolpathSegment, depth: f64) -> ToolpathSegment {
         let mut adjusted = segment.clone();
-        adjusted.end.y = segment.end.y + depth - segment.end.y;
+        if segment.segment_type != ToolpathSegmentType::RapidMove {
+            adjusted.start_z = Some(depth);
+            adjusted.z_depth = Some(depth);
+        }
         adjusted
     }
 
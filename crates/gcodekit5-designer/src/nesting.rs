@@ -0,0 +1,576 @@
+//! Automatic nesting of parts on stock sheets.
+//!
+//! Parts are placed by their true shape rather than their bounding box. Each
+//! orientation of a part is rasterised with the even-odd rule, so holes stay
+//! free and smaller parts can be placed inside them, and grown by the part
+//! spacing when testing for room. Parts are placed largest first, each at the
+//! lowest and then leftmost position where it fits on the first sheet with
+//! room, trying every allowed rotation. A part that fits nowhere opens a new
+//! sheet, up to the sheet limit.
+
+use crate::model::Point;
+
+/// Most raster cells along a sheet edge; larger sheets use coarser cells.
+const MAX_CELLS: f64 = 2000.0;
+
+/// How parts may be turned when nesting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NestRotation {
+    /// Parts keep their drawn orientation.
+    Fixed,
+    /// Parts may be turned a quarter turn, keeping the grain along an axis.
+    #[default]
+    Orthogonal,
+    /// Parts may be turned by any multiple of the angle step.
+    Free,
+}
+
+impl NestRotation {
+    /// Returns the name of the rotation mode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NestRotation::Fixed => "None",
+            NestRotation::Orthogonal => "0/90",
+            NestRotation::Free => "Free",
+        }
+    }
+}
+
+/// Nesting settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestSettings {
+    /// Clearance kept between parts, in mm.
+    pub spacing: f64,
+    /// Clearance kept from the sheet edges, in mm.
+    pub margin: f64,
+    pub rotation: NestRotation,
+    /// Angle between the orientations tried with free rotation, in degrees.
+    pub angle_step: f64,
+    /// Most sheets to fill.
+    pub max_sheets: usize,
+    /// Gap between sheets laid out side by side on the canvas, in mm.
+    pub sheet_gap: f64,
+    /// Raster cell size, in mm. Large sheets use coarser cells.
+    pub resolution: f64,
+}
+
+impl Default for NestSettings {
+    fn default() -> Self {
+        Self {
+            spacing: 6.0,
+            margin: 10.0,
+            rotation: NestRotation::Orthogonal,
+            angle_step: 15.0,
+            max_sheets: 10,
+            sheet_gap: 50.0,
+            resolution: 1.0,
+        }
+    }
+}
+
+impl NestSettings {
+    /// Orientations tried for each part, in degrees.
+    pub fn angles(&self) -> Vec<f64> {
+        match self.rotation {
+            NestRotation::Fixed => vec![0.0],
+            NestRotation::Orthogonal => vec![0.0, 90.0],
+            NestRotation::Free => {
+                let step = self.angle_step.clamp(1.0, 360.0);
+                let count = (360.0 / step - 1e-9).ceil() as usize;
+                (0..count).map(|i| i as f64 * step).collect()
+            }
+        }
+    }
+}
+
+/// A part to nest: its closed outline rings, filled with the even-odd rule,
+/// and the number of copies wanted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestPart {
+    pub rings: Vec<Vec<Point>>,
+    pub quantity: usize,
+}
+
+impl NestPart {
+    pub fn new(rings: Vec<Vec<Point>>, quantity: usize) -> Self {
+        Self { rings, quantity }
+    }
+
+    /// Filled area of the part, with holes taken out.
+    pub fn area(&self) -> f64 {
+        let mut area = 0.0;
+        for (i, ring) in self.rings.iter().enumerate() {
+            let Some(&first) = ring.first() else {
+                continue;
+            };
+            let depth = self
+                .rings
+                .iter()
+                .enumerate()
+                .filter(|&(j, other)| j != i && contains(other, first))
+                .count();
+            let ring_area = signed_area(ring).abs();
+            area += if depth % 2 == 0 {
+                ring_area
+            } else {
+                -ring_area
+            };
+        }
+        area.max(0.0)
+    }
+}
+
+/// Where one copy of a part goes: turned by `angle` degrees about the origin,
+/// then moved by `offset`, in sheet coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// Index of the part in the nested list.
+    pub part: usize,
+    pub sheet: usize,
+    pub angle: f64,
+    pub offset: Point,
+}
+
+impl Placement {
+    /// Maps a point of the part to the sheet.
+    pub fn apply(&self, p: Point) -> Point {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        Point::new(
+            p.x * cos - p.y * sin + self.offset.x,
+            p.x * sin + p.y * cos + self.offset.y,
+        )
+    }
+}
+
+/// Result for one sheet.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SheetReport {
+    /// Copies placed on the sheet.
+    pub parts: usize,
+    /// Area covered by the placed parts, in mm².
+    pub part_area: f64,
+    /// Fraction (0-1) of the sheet covered by parts.
+    pub utilisation: f64,
+}
+
+/// Result of nesting.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NestResult {
+    pub placements: Vec<Placement>,
+    /// One report per sheet used, in order.
+    pub sheets: Vec<SheetReport>,
+    /// Copies of each part that did not fit, by part index.
+    pub unplaced: Vec<usize>,
+}
+
+impl NestResult {
+    /// Number of copies placed.
+    pub fn placed(&self) -> usize {
+        self.placements.len()
+    }
+
+    /// Number of copies that did not fit.
+    pub fn unplaced_count(&self) -> usize {
+        self.unplaced.iter().sum()
+    }
+
+    /// Fraction (0-1) of the used sheets covered by parts.
+    pub fn utilisation(&self) -> f64 {
+        if self.sheets.is_empty() {
+            return 0.0;
+        }
+        self.sheets.iter().map(|s| s.utilisation).sum::<f64>() / self.sheets.len() as f64
+    }
+}
+
+/// Nests `parts` on sheets of `width` x `height` mm.
+pub fn nest(parts: &[NestPart], width: f64, height: f64, settings: &NestSettings) -> NestResult {
+    let mut result = NestResult {
+        unplaced: vec![0; parts.len()],
+        ..Default::default()
+    };
+    if width <= 0.0 || height <= 0.0 {
+        for (count, part) in result.unplaced.iter_mut().zip(parts) {
+            *count = part.quantity;
+        }
+        return result;
+    }
+
+    let cell = settings
+        .resolution
+        .max(width.max(height) / MAX_CELLS)
+        .max(1e-3);
+    let grow = if settings.spacing > 0.0 {
+        (settings.spacing / cell + std::f64::consts::SQRT_2).ceil() as i64
+    } else {
+        0
+    };
+    let cols = (width / cell).ceil() as usize;
+    let rows = (height / cell).ceil() as usize;
+    let margin = settings.margin.max(0.0);
+    let angles = settings.angles();
+
+    let areas: Vec<f64> = parts.iter().map(NestPart::area).collect();
+    let footprints: Vec<Vec<Footprint>> = parts
+        .iter()
+        .zip(&areas)
+        .map(|(part, &area)| {
+            if area <= 0.0 || part.quantity == 0 {
+                return Vec::new();
+            }
+            angles
+                .iter()
+                .filter_map(|&angle| Footprint::new(&part.rings, angle, cell, grow))
+                .collect()
+        })
+        .collect();
+
+    // Largest parts first, so smaller ones can fill the gaps and holes
+    let mut order: Vec<usize> = (0..parts.len()).collect();
+    order.sort_by(|&a, &b| areas[b].total_cmp(&areas[a]));
+
+    let mut sheets: Vec<Sheet> = Vec::new();
+    for part in order {
+        let mut fits_a_sheet = !footprints[part].is_empty();
+        for _ in 0..parts[part].quantity {
+            if !fits_a_sheet {
+                result.unplaced[part] += 1;
+                continue;
+            }
+
+            let mut placed = None;
+            for (index, sheet) in sheets.iter().enumerate() {
+                if let Some(spot) = sheet.best_spot(&footprints[part], width, height, margin) {
+                    placed = Some((index, spot));
+                    break;
+                }
+            }
+            if placed.is_none() && sheets.len() < settings.max_sheets {
+                let sheet = Sheet::new(cols, rows, cell);
+                match sheet.best_spot(&footprints[part], width, height, margin) {
+                    Some(spot) => {
+                        sheets.push(sheet);
+                        placed = Some((sheets.len() - 1, spot));
+                    }
+                    // Too big for an empty sheet, so every copy is
+                    None => fits_a_sheet = false,
+                }
+            }
+
+            let Some((index, (fp, i, j))) = placed else {
+                result.unplaced[part] += 1;
+                continue;
+            };
+            let fp = &footprints[part][fp];
+            sheets[index].fill(fp, i, j, areas[part]);
+            result.placements.push(Placement {
+                part,
+                sheet: index,
+                angle: fp.angle,
+                offset: Point::new(i as f64 * cell + fp.shift.x, j as f64 * cell + fp.shift.y),
+            });
+        }
+    }
+
+    result.sheets = sheets
+        .iter()
+        .map(|sheet| SheetReport {
+            parts: sheet.parts,
+            part_area: sheet.part_area,
+            utilisation: sheet.part_area / (width * height),
+        })
+        .collect();
+    result
+}
+
+/// One orientation of a part on the raster.
+struct Footprint {
+    angle: f64,
+    /// Moves the turned part so its bounding box starts at the origin.
+    shift: Point,
+    width: f64,
+    height: f64,
+    /// Cells the part covers, as inclusive column spans per row.
+    rows: Vec<Vec<(i64, i64)>>,
+    /// Row of `grown[0]` relative to `rows[0]`.
+    grown_start: i64,
+    /// Cells within the spacing of the part.
+    grown: Vec<Vec<(i64, i64)>>,
+}
+
+impl Footprint {
+    fn new(rings: &[Vec<Point>], angle: f64, cell: f64, grow: i64) -> Option<Self> {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let turned: Vec<Vec<Point>> = rings
+            .iter()
+            .filter(|ring| ring.len() >= 3)
+            .map(|ring| {
+                ring.iter()
+                    .map(|p| Point::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos))
+                    .collect()
+            })
+            .collect();
+
+        let points = turned.iter().flatten();
+        let min_x = points.clone().map(|p| p.x).fold(f64::INFINITY, f64::min);
+        let min_y = points.clone().map(|p| p.y).fold(f64::INFINITY, f64::min);
+        let max_x = points
+            .clone()
+            .map(|p| p.x)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let max_y = points.map(|p| p.y).fold(f64::NEG_INFINITY, f64::max);
+        if !min_x.is_finite() || !min_y.is_finite() {
+            return None;
+        }
+        let turned: Vec<Vec<Point>> = turned
+            .into_iter()
+            .map(|ring| {
+                ring.into_iter()
+                    .map(|p| Point::new(p.x - min_x, p.y - min_y))
+                    .collect()
+            })
+            .collect();
+        let width = max_x - min_x;
+        let height = max_y - min_y;
+
+        let ncols = (width / cell).floor() as i64 + 1;
+        let nrows = (height / cell).floor() as i64 + 1;
+        let rows: Vec<Vec<(i64, i64)>> = (0..nrows)
+            .map(|r| {
+                let y0 = r as f64 * cell;
+                let y1 = ((r + 1) as f64 * cell).min(height);
+                let spans = band_intervals(&turned, y0, y1)
+                    .into_iter()
+                    .map(|(x0, x1)| {
+                        (
+                            ((x0 / cell).floor() as i64).clamp(0, ncols - 1),
+                            ((x1 / cell).floor() as i64).clamp(0, ncols - 1),
+                        )
+                    })
+                    .collect();
+                merge_spans(spans)
+            })
+            .collect();
+
+        // Grow by a disc of `grow` cells
+        let mut grown = vec![Vec::new(); (nrows + 2 * grow) as usize];
+        for (r, spans) in rows.iter().enumerate() {
+            for dy in -grow..=grow {
+                let reach = ((grow * grow - dy * dy) as f64).sqrt().floor() as i64;
+                let target = &mut grown[(r as i64 + dy + grow) as usize];
+                target.extend(spans.iter().map(|&(a, b)| (a - reach, b + reach)));
+            }
+        }
+        let grown = grown.into_iter().map(merge_spans).collect();
+
+        Some(Self {
+            angle,
+            shift: Point::new(-min_x, -min_y),
+            width,
+            height,
+            rows,
+            grown_start: -grow,
+            grown,
+        })
+    }
+}
+
+/// Occupied cells of one sheet.
+struct Sheet {
+    cell: f64,
+    cols: i64,
+    bits: Vec<Vec<u64>>,
+    parts: usize,
+    part_area: f64,
+}
+
+impl Sheet {
+    fn new(cols: usize, rows: usize, cell: f64) -> Self {
+        Self {
+            cell,
+            cols: cols as i64,
+            bits: vec![vec![0; cols.div_ceil(64)]; rows],
+            parts: 0,
+            part_area: 0.0,
+        }
+    }
+
+    /// Lowest, then leftmost, spot for any of the footprints, as the index
+    /// of the footprint and its cell position.
+    fn best_spot(
+        &self,
+        footprints: &[Footprint],
+        width: f64,
+        height: f64,
+        margin: f64,
+    ) -> Option<(usize, i64, i64)> {
+        let cell = self.cell;
+        let first = |limit: f64| (limit / cell - 1e-9).ceil() as i64;
+        let last = |limit: f64| (limit / cell + 1e-9).floor() as i64;
+
+        let mut best: Option<(f64, i64, usize, i64, i64)> = None;
+        for (index, fp) in footprints.iter().enumerate() {
+            let (i_min, i_max) = (first(margin), last(width - margin - fp.width));
+            let (j_min, mut j_max) = (first(margin), last(height - margin - fp.height));
+            if let Some((top, ..)) = best {
+                // Only a lower top can beat the best so far
+                j_max = j_max.min(last(top - fp.height) - 1);
+            }
+
+            'rows: for j in j_min..=j_max {
+                let mut i = i_min;
+                while i <= i_max {
+                    match self.collision(fp, i, j) {
+                        Some(next) => i = next,
+                        None => {
+                            let top = j as f64 * cell + fp.height;
+                            if best.is_none_or(|(t, x, ..)| (top, i) < (t, x)) {
+                                best = Some((top, i, index, i, j));
+                            }
+                            break 'rows;
+                        }
+                    }
+                }
+            }
+        }
+        best.map(|(_, _, index, i, j)| (index, i, j))
+    }
+
+    /// When `fp` at cell (i, j) overlaps a placed part, the next column
+    /// worth trying.
+    fn collision(&self, fp: &Footprint, i: i64, j: i64) -> Option<i64> {
+        for (offset, spans) in fp.grown.iter().enumerate() {
+            let row = j + fp.grown_start + offset as i64;
+            for &(a, b) in spans {
+                if let Some(c) = self.last_set(row, i + a, i + b) {
+                    return Some(c - a + 1);
+                }
+            }
+        }
+        None
+    }
+
+    /// Rightmost occupied cell in columns `a..=b` of `row`.
+    fn last_set(&self, row: i64, a: i64, b: i64) -> Option<i64> {
+        if row < 0 || row >= self.bits.len() as i64 {
+            return None;
+        }
+        let (a, b) = (a.max(0), b.min(self.cols - 1));
+        if a > b {
+            return None;
+        }
+        let words = &self.bits[row as usize];
+        let mut word = b / 64;
+        let mut mask = u64::MAX >> (63 - b % 64);
+        while word >= a / 64 {
+            if word == a / 64 {
+                mask &= u64::MAX << (a % 64);
+            }
+            let bits = words[word as usize] & mask;
+            if bits != 0 {
+                return Some(word * 64 + 63 - bits.leading_zeros() as i64);
+            }
+            word -= 1;
+            mask = u64::MAX;
+        }
+        None
+    }
+
+    fn fill(&mut self, fp: &Footprint, i: i64, j: i64, area: f64) {
+        for (r, spans) in fp.rows.iter().enumerate() {
+            let row = j + r as i64;
+            if row < 0 || row >= self.bits.len() as i64 {
+                continue;
+            }
+            for &(a, b) in spans {
+                for c in (i + a).max(0)..=(i + b).min(self.cols - 1) {
+                    self.bits[row as usize][(c / 64) as usize] |= 1 << (c % 64);
+                }
+            }
+        }
+        self.parts += 1;
+        self.part_area += area;
+    }
+}
+
+/// X intervals covered by the even-odd fill of `rings` anywhere between
+/// heights `y0` and `y1`: the crossings at both edges of the band plus the
+/// outline inside it.
+fn band_intervals(rings: &[Vec<Point>], y0: f64, y1: f64) -> Vec<(f64, f64)> {
+    let mut intervals = scanline(rings, y0);
+    intervals.extend(scanline(rings, y1));
+    for ring in rings {
+        for (k, &a) in ring.iter().enumerate() {
+            let b = ring[(k + 1) % ring.len()];
+            let (lo, hi) = if a.y <= b.y { (a, b) } else { (b, a) };
+            if hi.y < y0 || lo.y > y1 {
+                continue;
+            }
+            let x_at = |y: f64| {
+                if hi.y - lo.y < 1e-12 {
+                    lo.x
+                } else {
+                    lo.x + (hi.x - lo.x) * ((y - lo.y) / (hi.y - lo.y)).clamp(0.0, 1.0)
+                }
+            };
+            let (xa, xb) = (x_at(y0.max(lo.y)), x_at(y1.min(hi.y)));
+            let (xa, xb) = if hi.y - lo.y < 1e-12 {
+                (a.x, b.x)
+            } else {
+                (xa, xb)
+            };
+            intervals.push((xa.min(xb), xa.max(xb)));
+        }
+    }
+    intervals
+}
+
+/// Inside intervals of the even-odd fill of `rings` along height `y`.
+fn scanline(rings: &[Vec<Point>], y: f64) -> Vec<(f64, f64)> {
+    let mut xs = Vec::new();
+    for ring in rings {
+        for (k, &a) in ring.iter().enumerate() {
+            let b = ring[(k + 1) % ring.len()];
+            if (a.y <= y) != (b.y <= y) {
+                xs.push(a.x + (b.x - a.x) * (y - a.y) / (b.y - a.y));
+            }
+        }
+    }
+    xs.sort_by(f64::total_cmp);
+    xs.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+/// Sorts and joins overlapping or touching spans.
+fn merge_spans(mut spans: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    spans.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(spans.len());
+    for (a, b) in spans {
+        match merged.last_mut() {
+            Some(last) if a <= last.1 + 1 => last.1 = last.1.max(b),
+            _ => merged.push((a, b)),
+        }
+    }
+    merged
+}
+
+fn signed_area(ring: &[Point]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+fn contains(ring: &[Point], p: Point) -> bool {
+    let mut inside = false;
+    for (k, &a) in ring.iter().enumerate() {
+        let b = ring[(k + 1) % ring.len()];
+        if (a.y <= p.y) != (b.y <= p.y) && p.x < a.x + (b.x - a.x) * (p.y - a.y) / (b.y - a.y) {
+            inside = !inside;
+        }
+    }
+    inside
+}
@@ -1,4 +1,5 @@
-use crate::model::{DesignPath, DesignerShape, Shape};
+use crate::dogbone::{relief_centres, relief_polygon, DogboneSettings};
+use crate::model::{DesignPath, DesignerShape, Point, Shape};
 use cavalier_contours::polyline::{PlineSource, PlineSourceMut, PlineVertex, Polyline};
 use csgrs::traits::CSG;
 use std::panic;
@@ -233,3 +234,44 @@ pub fn perform_chamfer(shape: &Shape, distance: f64) -> Shape {
 
     Shape::Path(DesignPath::from_csg(result_sketch))
 }
+
+pub fn perform_dogbone(shape: &Shape, settings: &DogboneSettings, cut_inside: bool) -> Shape {
+    // Relieve corners on the cutter's side: add the reliefs to the shape when
+    // cutting inside it, remove them from it when cutting around it
+    let sketch = shape.as_csg();
+    let mp = sketch.to_multipolygon();
+    let radius = settings.tool_diameter / 2.0;
+
+    let mut reliefs = csgrs::sketch::Sketch::new();
+    for poly in mp.0 {
+        let rings = std::iter::once((poly.exterior(), false))
+            .chain(poly.interiors().iter().map(|interior| (interior, true)));
+
+        for (ring, hole) in rings {
+            let mut pline = Polyline::new();
+            for coord in ring.0.iter() {
+                pline.add_vertex(PlineVertex::new(coord.x, coord.y, 0.0));
+            }
+            pline.set_is_closed(true);
+            let pline = clean_polyline(pline);
+
+            let points: Vec<Point> = pline
+                .vertex_data
+                .iter()
+                .map(|v| Point::new(v.x, v.y))
+                .collect();
+            for centre in relief_centres(&points, hole, cut_inside, settings) {
+                let pts = relief_polygon(centre, radius);
+                reliefs = reliefs.union(&csgrs::sketch::Sketch::polygon(&pts, None));
+            }
+        }
+    }
+
+    let result_sketch = if cut_inside {
+        sketch.union(&reliefs)
+    } else {
+        sketch.difference(&reliefs)
+    };
+
+    Shape::Path(DesignPath::from_csg(result_sketch))
+}
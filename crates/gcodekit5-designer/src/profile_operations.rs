@@ -0,0 +1,369 @@
+//! Profile operations for CAM toolpath generation.
+//!
+//! Implements tool-radius compensation for profile cuts: closed contours are
+//! offset to the chosen side of the drawn line and ordered for climb or
+//! conventional milling. Holes and islands are found by nesting, so text
+//! counters and paths with holes are compensated the right way.
+
+use super::toolpath::geometry::CLOSE_TOLERANCE;
+use super::toolpath::{ToolpathSegment, ToolpathSegmentType};
+use crate::model::Point;
+use crate::ops::clean_polyline;
+use cavalier_contours::polyline::{PlineSource, PlineSourceMut, PlineVertex, Polyline};
+use std::f64::consts::PI;
+use std::panic;
+
+/// Side of the drawn line a profile is cut on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, Default)]
+pub enum CutSide {
+    /// Cut around the outside, leaving the shape at its drawn size (parts).
+    Outside,
+    /// Cut inside, leaving an opening at its drawn size (holes and cut-outs).
+    Inside,
+    /// Cut centred on the line, without compensation.
+    #[default]
+    OnLine,
+}
+
+impl CutSide {
+    /// Returns the name of the cut side.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CutSide::Outside => "Outside",
+            CutSide::Inside => "Inside",
+            CutSide::OnLine => "On Line",
+        }
+    }
+}
+
+/// Direction the cutter travels along a compensated profile, for a
+/// clockwise (M3) spindle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, Default)]
+pub enum CutDirection {
+    /// Material on the right of the cutter; better finish on most machines.
+    #[default]
+    Climb,
+    /// Material on the left of the cutter; kinder to machines with backlash.
+    Conventional,
+}
+
+impl CutDirection {
+    /// Returns the name of the cut direction.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CutDirection::Climb => "Climb",
+            CutDirection::Conventional => "Conventional",
+        }
+    }
+}
+
+/// A run of cutting segments started by a rapid move.
+struct Contour {
+    segments: Vec<ToolpathSegment>,
+}
+
+impl Contour {
+    fn is_closed(&self) -> bool {
+        match (self.segments.first(), self.segments.last()) {
+            (Some(first), Some(last)) => first.start.distance_to(&last.end) <= CLOSE_TOLERANCE,
+            _ => false,
+        }
+    }
+}
+
+/// Offsets the closed contours of a profile by `distance` to `side` and
+/// orders them for `direction`.
+///
+/// Contours are the runs of cutting moves between rapid moves. Open contours
+/// (lines, open paths) have no side and are kept as they are, as are all
+/// contours when cutting on the line. A contour nested inside an odd number
+/// of others is a hole of the shape, and is offset the other way. Contours
+/// that vanish when offset inward (a hole smaller than the tool) are dropped.
+pub fn compensate_profile(
+    segments: &[ToolpathSegment],
+    side: CutSide,
+    direction: CutDirection,
+    distance: f64,
+) -> Vec<ToolpathSegment> {
+    if side == CutSide::OnLine {
+        return segments.to_vec();
+    }
+
+    let contours = split_contours(segments);
+    let loops: Vec<Option<Vec<PlineVertex>>> = contours
+        .iter()
+        .map(|c| c.is_closed().then(|| contour_vertices(&c.segments)))
+        .collect();
+
+    let mut result = Vec::new();
+    let mut current = Point::new(0.0, 0.0);
+    for (index, contour) in contours.iter().enumerate() {
+        let Some(first) = contour.segments.first() else {
+            continue;
+        };
+        let (feed_rate, spindle_speed) = (first.feed_rate, first.spindle_speed);
+
+        let Some(vertices) = &loops[index] else {
+            push_rapid(&mut result, current, first.start, feed_rate, spindle_speed);
+            result.extend(contour.segments.iter().cloned());
+            current = contour.segments.last().map_or(current, |s| s.end);
+            continue;
+        };
+
+        let depth = loops
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .filter_map(|(_, other)| other.as_ref())
+            .filter(|other| point_in_loop(Point::new(vertices[0].x, vertices[0].y), other))
+            .count();
+        // Offset away from the material: outward around parts and islands,
+        // inward into holes and cut-outs
+        let grow = (side == CutSide::Outside) == (depth % 2 == 0);
+        // Climb milling with a clockwise spindle keeps the material on the
+        // right, which is clockwise around material inside the loop
+        let want_ccw = grow != (direction == CutDirection::Climb);
+
+        let ccw = if signed_area(vertices) < 0.0 {
+            reverse_vertices(vertices)
+        } else {
+            vertices.clone()
+        };
+        let mut pline = Polyline::new();
+        for v in &ccw {
+            pline.add_vertex(*v);
+        }
+        pline.set_is_closed(true);
+        let pline = clean_polyline(pline);
+        if pline.vertex_count() < 2 {
+            continue;
+        }
+
+        // A positive offset goes to the left, which is inside a CCW loop
+        let offset = if grow { -distance } else { distance };
+        let offsets = if offset.abs() < 1e-9 {
+            vec![pline]
+        } else {
+            panic::catch_unwind(panic::AssertUnwindSafe(|| pline.parallel_offset(offset)))
+                .unwrap_or_default()
+        };
+
+        for offset_pline in offsets {
+            let mut vertices = offset_pline.vertex_data.clone();
+            if vertices.len() < 2 {
+                continue;
+            }
+            if (signed_area(&vertices) > 0.0) != want_ccw {
+                vertices = reverse_vertices(&vertices);
+            }
+            let start = Point::new(vertices[0].x, vertices[0].y);
+            push_rapid(&mut result, current, start, feed_rate, spindle_speed);
+            current = push_loop(&mut result, &vertices, feed_rate, spindle_speed);
+        }
+    }
+    result
+}
+
+/// Splits segments into contours at each rapid move.
+fn split_contours(segments: &[ToolpathSegment]) -> Vec<Contour> {
+    let mut contours = Vec::new();
+    let mut current = Vec::new();
+    for seg in segments {
+        if seg.segment_type == ToolpathSegmentType::RapidMove {
+            if !current.is_empty() {
+                contours.push(Contour {
+                    segments: std::mem::take(&mut current),
+                });
+            }
+        } else {
+            current.push(seg.clone());
+        }
+    }
+    if !current.is_empty() {
+        contours.push(Contour { segments: current });
+    }
+    contours
+}
+
+/// Vertices of a closed contour, with arcs as bulges.
+fn contour_vertices(segments: &[ToolpathSegment]) -> Vec<PlineVertex> {
+    let mut vertices = Vec::with_capacity(segments.len());
+    for seg in segments {
+        let ccw = seg.segment_type == ToolpathSegmentType::ArcCCW;
+        match (seg.segment_type, seg.center) {
+            (ToolpathSegmentType::ArcCW | ToolpathSegmentType::ArcCCW, Some(center)) => {
+                let a0 = (seg.start.y - center.y).atan2(seg.start.x - center.x);
+                let a1 = (seg.end.y - center.y).atan2(seg.end.x - center.x);
+                let mut sweep = if ccw { a1 - a0 } else { a0 - a1 };
+                while sweep <= 1e-9 {
+                    sweep += 2.0 * PI;
+                }
+                let sign = if ccw { 1.0 } else { -1.0 };
+                if sweep > PI + 1e-9 {
+                    // Split at the midpoint so no bulge exceeds a half circle
+                    let mid_angle = a0 + sign * sweep / 2.0;
+                    let r = seg.start.distance_to(&center);
+                    let bulge = sign * (sweep / 8.0).tan();
+                    vertices.push(PlineVertex::new(seg.start.x, seg.start.y, bulge));
+                    vertices.push(PlineVertex::new(
+                        center.x + r * mid_angle.cos(),
+                        center.y + r * mid_angle.sin(),
+                        bulge,
+                    ));
+                } else {
+                    let bulge = sign * (sweep / 4.0).tan();
+                    vertices.push(PlineVertex::new(seg.start.x, seg.start.y, bulge));
+                }
+            }
+            _ => vertices.push(PlineVertex::new(seg.start.x, seg.start.y, 0.0)),
+        }
+    }
+    vertices
+}
+
+/// Signed area of a closed loop, positive when counter-clockwise.
+fn signed_area(vertices: &[PlineVertex]) -> f64 {
+    let n = vertices.len();
+    let mut area = 0.0;
+    for i in 0..n {
+        let a = vertices[i];
+        let b = vertices[(i + 1) % n];
+        area += (a.x * b.y - b.x * a.y) / 2.0;
+        if a.bulge.abs() > 1e-12 {
+            let chord = ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+            let sweep = 4.0 * a.bulge.atan();
+            let r = chord / (2.0 * (sweep / 2.0).sin().abs());
+            if r.is_finite() {
+                area += r * r / 2.0 * (sweep - sweep.sin());
+            }
+        }
+    }
+    area
+}
+
+/// The same loop traversed the other way.
+fn reverse_vertices(vertices: &[PlineVertex]) -> Vec<PlineVertex> {
+    let n = vertices.len();
+    (0..n)
+        .map(|k| {
+            let p = vertices[n - 1 - k];
+            // The segment into p becomes the segment out of it
+            let bulge = vertices[(2 * n - 2 - k) % n].bulge;
+            PlineVertex::new(p.x, p.y, -bulge)
+        })
+        .collect()
+}
+
+/// Ray-casting containment test against a loop, with arcs flattened.
+fn point_in_loop(p: Point, vertices: &[PlineVertex]) -> bool {
+    let points = flatten_loop(vertices);
+    let n = points.len();
+    let mut inside = false;
+    let mut j = n - 1;
+    for i in 0..n {
+        let (a, b) = (points[i], points[j]);
+        if (a.y > p.y) != (b.y > p.y) {
+            let x = a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if p.x < x {
+                inside = !inside;
+            }
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Points along a closed loop, with each arc split into short chords.
+fn flatten_loop(vertices: &[PlineVertex]) -> Vec<Point> {
+    const ARC_STEPS: usize = 16;
+    let n = vertices.len();
+    let mut points = Vec::with_capacity(n * ARC_STEPS);
+    for i in 0..n {
+        let a = vertices[i];
+        let b = vertices[(i + 1) % n];
+        points.push(Point::new(a.x, a.y));
+        if a.bulge.abs() < 1e-9 {
+            continue;
+        }
+        let center = bulge_center(Point::new(a.x, a.y), Point::new(b.x, b.y), a.bulge);
+        let r = ((a.x - center.x).powi(2) + (a.y - center.y).powi(2)).sqrt();
+        let a0 = (a.y - center.y).atan2(a.x - center.x);
+        let sweep = 4.0 * a.bulge.atan();
+        for step in 1..ARC_STEPS {
+            let angle = a0 + sweep * step as f64 / ARC_STEPS as f64;
+            points.push(Point::new(
+                center.x + r * angle.cos(),
+                center.y + r * angle.sin(),
+            ));
+        }
+    }
+    points
+}
+
+/// Centre of the arc from `start` to `end` with the given bulge.
+fn bulge_center(start: Point, end: Point, bulge: f64) -> Point {
+    // Centre lies off the chord midpoint along its left normal
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let k = (1.0 - bulge * bulge) / (4.0 * bulge);
+    Point::new(
+        (start.x + end.x) / 2.0 - dy * k,
+        (start.y + end.y) / 2.0 + dx * k,
+    )
+}
+
+fn push_rapid(
+    segments: &mut Vec<ToolpathSegment>,
+    from: Point,
+    to: Point,
+    feed_rate: f64,
+    spindle_speed: u32,
+) {
+    segments.push(ToolpathSegment::new(
+        ToolpathSegmentType::RapidMove,
+        from,
+        to,
+        feed_rate,
+        spindle_speed,
+    ));
+}
+
+/// Appends a closed loop as line and arc segments, returning its end.
+fn push_loop(
+    segments: &mut Vec<ToolpathSegment>,
+    vertices: &[PlineVertex],
+    feed_rate: f64,
+    spindle_speed: u32,
+) -> Point {
+    let n = vertices.len();
+    for i in 0..n {
+        let a = vertices[i];
+        let b = vertices[(i + 1) % n];
+        let start = Point::new(a.x, a.y);
+        let end = Point::new(b.x, b.y);
+        if a.bulge.abs() < 1e-9 {
+            segments.push(ToolpathSegment::new(
+                ToolpathSegmentType::LinearMove,
+                start,
+                end,
+                feed_rate,
+                spindle_speed,
+            ));
+            continue;
+        }
+        let center = bulge_center(start, end, a.bulge);
+        let segment_type = if a.bulge > 0.0 {
+            ToolpathSegmentType::ArcCCW
+        } else {
+            ToolpathSegmentType::ArcCW
+        };
+        segments.push(ToolpathSegment::new_arc(
+            segment_type,
+            start,
+            end,
+            center,
+            feed_rate,
+            spindle_speed,
+        ));
+    }
+    Point::new(vertices[0].x, vertices[0].y)
+}
@@ -0,0 +1,508 @@
+//! # Relief Carving Module
+//!
+//! Carves a grayscale image as a 2.5D relief, with the brightness of each
+//! pixel setting its depth below the stock top. White pixels sit at the
+//! minimum depth and black pixels at the maximum, unless inverted.
+//!
+//! An optional roughing pass clears the bulk of the material in flat levels,
+//! leaving an allowance on the relief, and a finishing pass then rasters the
+//! surface with a ball nose. Both drop the cutter onto the height map at
+//! every sample, so neither cuts below the relief.
+
+use crate::gcode_gen::ToolpathToGcode;
+use crate::stock_removal::{HeightMap2D, StockMaterial, StockSimulator2D};
+use crate::surface_finishing::{passes_to_toolpath, simplify};
+use crate::tool_changes::{AssignedTool, ToolChangeSettings};
+use crate::toolpath::Toolpath;
+use anyhow::{bail, Result};
+use gcodekit5_core::data::tools::ToolProfile;
+use gcodekit5_core::PostContext;
+use image::DynamicImage;
+use tracing::info;
+
+/// The tool and step sizes of one relief pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReliefPass {
+    /// Library tool, for its number, name, feed rate and spindle speed.
+    pub tool: AssignedTool,
+    pub cutter: ToolProfile,
+    /// Distance between raster passes, in mm.
+    pub stepover: f64,
+    /// Depth of each roughing level, in mm. Finishing follows the surface
+    /// in one pass.
+    pub step_down: f64,
+    /// Material left on the relief by roughing, in mm.
+    pub stock_to_leave: f64,
+}
+
+impl ReliefPass {
+    /// A pass with a library tool's stepover and depth per pass, if it is
+    /// a flat, ball or bull-nose cutter.
+    pub fn from_library(tool: &gcodekit5_core::data::tools::Tool) -> Option<Self> {
+        let cutter = tool.profile();
+        if !matches!(
+            cutter,
+            ToolProfile::Flat { .. } | ToolProfile::Ball { .. } | ToolProfile::BullNose { .. }
+        ) {
+            return None;
+        }
+        Some(Self {
+            tool: AssignedTool::from_library(tool),
+            cutter,
+            stepover: (tool.diameter * tool.params.stepover_percent / 100.0) as f64,
+            step_down: tool.params.depth_per_pass as f64,
+            stock_to_leave: 0.0,
+        })
+    }
+
+    fn with_cutter(cutter: ToolProfile, stepover: f64) -> Self {
+        Self {
+            tool: AssignedTool {
+                diameter: cutter.diameter() as f64,
+                ..Default::default()
+            },
+            cutter,
+            stepover,
+            step_down: 1.0,
+            stock_to_leave: 0.0,
+        }
+    }
+}
+
+/// Parameters for relief carving.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReliefSettings {
+    /// Width of the carving in mm; the height follows the image.
+    pub width: f64,
+    /// Distance between height map samples, in mm.
+    pub resolution: f64,
+    /// Depth of white pixels, in mm.
+    pub min_depth: f64,
+    /// Depth of black pixels, in mm.
+    pub max_depth: f64,
+    /// Brightness is raised to this power before it sets the depth. Above 1
+    /// pushes the mid-tones deeper, below 1 keeps them shallower.
+    pub gamma: f64,
+    /// Blur radius applied to the image before carving, in mm. Zero keeps
+    /// every detail.
+    pub smoothing: f64,
+    /// Carve light pixels deep and dark pixels shallow.
+    pub invert: bool,
+    /// Bottom-left corner of the carving.
+    pub offset_x: f64,
+    pub offset_y: f64,
+    /// Points within this distance of the line through their neighbours are
+    /// dropped from the output, in mm.
+    pub tolerance: f64,
+    /// Clears the bulk of the material in levels before finishing.
+    pub roughing: Option<ReliefPass>,
+    pub finishing: ReliefPass,
+}
+
+impl Default for ReliefSettings {
+    fn default() -> Self {
+        Self {
+            width: 100.0,
+            resolution: 0.2,
+            min_depth: 0.0,
+            max_depth: 5.0,
+            gamma: 1.0,
+            smoothing: 0.0,
+            invert: false,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            tolerance: 0.01,
+            roughing: Some(ReliefPass {
+                step_down: 1.5,
+                stock_to_leave: 0.3,
+                ..ReliefPass::with_cutter(ToolProfile::Flat { diameter: 6.0 }, 2.4)
+            }),
+            finishing: ReliefPass::with_cutter(ToolProfile::Ball { diameter: 3.175 }, 0.3),
+        }
+    }
+}
+
+/// Relief surface sampled on a regular grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReliefMap {
+    pub columns: usize,
+    pub rows: usize,
+    /// Distance between samples, in mm.
+    pub pixel: f64,
+    /// Bottom-left corner of the carving.
+    pub origin: (f64, f64),
+    /// Surface Z of each sample below the stock top, row by row from the
+    /// bottom.
+    pub surface: Vec<f64>,
+}
+
+impl ReliefMap {
+    /// Samples `image` at the settings' size and resolution and maps its
+    /// brightness to depth.
+    pub fn from_image(image: &DynamicImage, settings: &ReliefSettings) -> Result<Self> {
+        if image.width() == 0 || image.height() == 0 {
+            bail!("Image is empty");
+        }
+        if settings.width <= 0.0 || settings.resolution <= 0.0 {
+            bail!("Width and resolution must be positive");
+        }
+        if settings.min_depth < 0.0 || settings.max_depth < settings.min_depth {
+            bail!("Maximum depth must be at least the minimum depth");
+        }
+
+        let columns = (settings.width / settings.resolution).round().max(1.0) as usize;
+        let aspect = image.height() as f64 / image.width() as f64;
+        let rows = (columns as f64 * aspect).round().max(1.0) as usize;
+
+        let mut gray = image::imageops::resize(
+            &image.to_luma8(),
+            columns as u32,
+            rows as u32,
+            image::imageops::FilterType::Lanczos3,
+        );
+        if settings.smoothing > 0.0 {
+            let sigma = (settings.smoothing / settings.resolution) as f32;
+            gray = image::imageops::blur(&gray, sigma);
+        }
+
+        let gamma = settings.gamma.max(0.01);
+        let range = settings.max_depth - settings.min_depth;
+        let mut surface = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            // Image rows run down from the top
+            let y = (rows - 1 - row) as u32;
+            for column in 0..columns {
+                let mut brightness = gray.get_pixel(column as u32, y).0[0] as f64 / 255.0;
+                if settings.invert {
+                    brightness = 1.0 - brightness;
+                }
+                let depth = settings.min_depth + range * (1.0 - brightness.powf(gamma));
+                surface.push(-depth);
+            }
+        }
+
+        Ok(Self {
+            columns,
+            rows,
+            pixel: settings.resolution,
+            origin: (settings.offset_x, settings.offset_y),
+            surface,
+        })
+    }
+
+    /// Size of the carving in mm.
+    pub fn size(&self) -> (f64, f64) {
+        (
+            self.columns as f64 * self.pixel,
+            self.rows as f64 * self.pixel,
+        )
+    }
+
+    /// Centre of a sample.
+    pub fn point(&self, column: usize, row: usize) -> (f64, f64) {
+        (
+            self.origin.0 + (column as f64 + 0.5) * self.pixel,
+            self.origin.1 + (row as f64 + 0.5) * self.pixel,
+        )
+    }
+
+    /// Surface Z of a sample; outside the carving the stock top is uncut.
+    pub fn z(&self, column: isize, row: isize) -> f64 {
+        if column < 0 || row < 0 || column >= self.columns as isize || row >= self.rows as isize {
+            return 0.0;
+        }
+        self.surface[row as usize * self.columns + column as usize]
+    }
+}
+
+/// Roughing and finishing toolpaths for a relief.
+#[derive(Debug, Clone)]
+pub struct ReliefJob {
+    pub roughing: Option<Toolpath>,
+    pub finishing: Toolpath,
+}
+
+/// Generates relief carving toolpaths.
+pub struct ReliefCarver {
+    settings: ReliefSettings,
+}
+
+impl ReliefCarver {
+    pub fn new(settings: ReliefSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Get settings
+    pub fn settings(&self) -> &ReliefSettings {
+        &self.settings
+    }
+
+    /// Roughing and finishing toolpaths for `map`.
+    pub fn generate(&self, map: &ReliefMap) -> ReliefJob {
+        let roughing = self
+            .settings
+            .roughing
+            .as_ref()
+            .map(|pass| self.roughing_toolpath(map, pass));
+        let finishing = self.finishing_toolpath(map, &self.settings.finishing);
+        info!(
+            "Generated relief of {}x{} samples: {} roughing and {} finishing segments",
+            map.columns,
+            map.rows,
+            roughing.as_ref().map_or(0, |tp| tp.segments.len()),
+            finishing.segments.len()
+        );
+        ReliefJob {
+            roughing,
+            finishing,
+        }
+    }
+
+    /// G-code for the job, changing tools before each pass.
+    pub fn to_gcode(
+        &self,
+        job: &ReliefJob,
+        gcode_gen: &ToolpathToGcode,
+        tool_change: &ToolChangeSettings,
+    ) -> String {
+        let passes: Vec<(&str, &ReliefPass, &Toolpath)> = self
+            .settings
+            .roughing
+            .iter()
+            .zip(&job.roughing)
+            .map(|(pass, toolpath)| ("Roughing", pass, toolpath))
+            .chain([("Finishing", &self.settings.finishing, &job.finishing)])
+            .collect();
+
+        let (_, first, _) = passes[0];
+        let total_length = passes.iter().map(|(_, _, tp)| tp.total_length()).sum();
+        let deepest = passes.iter().map(|(_, _, tp)| tp.depth).fold(0.0, f64::min);
+        let mut gcode = gcode_gen.generate_header(
+            first.tool.spindle_speed,
+            first.tool.feed_rate,
+            first.cutter.diameter() as f64,
+            deepest,
+            total_length,
+        );
+        gcode.push_str(&format!(
+            "; Relief: {:.3}mm wide, Depth: {:.3}mm to {:.3}mm, Gamma: {:.2}\n",
+            self.settings.width,
+            self.settings.min_depth,
+            self.settings.max_depth,
+            self.settings.gamma
+        ));
+
+        let mut line_number = 10;
+        for (name, pass, toolpath) in passes {
+            let tool = &pass.tool;
+            gcode.push_str(&format!(
+                "\n; Tool change: T{} {} ({:.3}mm)\n",
+                tool.number,
+                tool.name,
+                pass.cutter.diameter()
+            ));
+            gcode.push_str(&tool_change.expand(tool, gcode_gen.safe_z));
+            gcode.push_str(&format!(
+                "; {}: {} {:.3}mm, Stepover: {:.3}mm\n",
+                name,
+                pass.cutter.name(),
+                pass.cutter.diameter(),
+                pass.stepover
+            ));
+            // The tool change leaves the spindle at the safe height
+            gcode.push_str(&gcode_gen.generate_body(toolpath, line_number));
+            line_number += toolpath.segments.len() as u32 * 10;
+        }
+        gcode.push_str(&gcode_gen.generate_footer());
+
+        let context = PostContext::new("Relief Carving")
+            .with_safe_z(gcode_gen.safe_z)
+            .with_spindle_speed(first.tool.spindle_speed as f64)
+            .with_feed_rate(first.tool.feed_rate);
+        gcode_gen.post_process(gcode, &context)
+    }
+
+    /// Simulates the job on a block the size of the carving and as thick as
+    /// its deepest point, at `resolution` mm per pixel.
+    pub fn simulate(&self, map: &ReliefMap, job: &ReliefJob, resolution: f32) -> HeightMap2D {
+        let (width, height) = map.size();
+        let thickness = self.settings.max_depth.max(0.1) as f32;
+        let stock = StockMaterial::new(
+            width as f32,
+            height as f32,
+            thickness,
+            (map.origin.0 as f32, map.origin.1 as f32, 0.0),
+        );
+
+        let mut simulator =
+            StockSimulator2D::with_tool_profile(stock, resolution, self.settings.finishing.cutter);
+        let passes = self
+            .settings
+            .roughing
+            .iter()
+            .zip(&job.roughing)
+            .chain([(&self.settings.finishing, &job.finishing)]);
+        for (pass, toolpath) in passes {
+            simulator.tool_profile = pass.cutter;
+            simulator.tool_radius = simulator.tool_profile.radius();
+            simulator.simulate_toolpath(&toolpath.segments);
+        }
+        simulator.get_simulation_result().height_map
+    }
+
+    /// Zigzag rows over the whole carving, linked along its edges.
+    fn finishing_toolpath(&self, map: &ReliefMap, pass: &ReliefPass) -> Toolpath {
+        let dropper = HeightDropper::new(map, pass.cutter, 0.0);
+        let rows = raster_rows(map, pass.stepover);
+
+        let mut run: Vec<[f64; 3]> = Vec::new();
+        for (k, &row) in rows.iter().enumerate() {
+            let heights = dropper.row(row);
+            let columns: Vec<usize> = if k % 2 == 0 {
+                (0..map.columns).collect()
+            } else {
+                (0..map.columns).rev().collect()
+            };
+
+            // Link up the edge from the previous row
+            if let Some(&previous) = k.checked_sub(1).map(|i| &rows[i]) {
+                let column = columns[0];
+                for between in previous + 1..row {
+                    let (x, y) = map.point(column, between);
+                    run.push([x, y, dropper.at(column, between)]);
+                }
+            }
+            run.extend(columns.into_iter().map(|column| {
+                let (x, y) = map.point(column, row);
+                [x, y, heights[column]]
+            }));
+        }
+
+        let passes = vec![simplify(&run, self.settings.tolerance)];
+        passes_to_toolpath(
+            &passes,
+            pass.cutter.diameter() as f64,
+            pass.tool.feed_rate,
+            pass.tool.spindle_speed,
+            0.0,
+        )
+    }
+
+    /// Level by level from the top, cutting only where the level before
+    /// left material above the roughing allowance.
+    fn roughing_toolpath(&self, map: &ReliefMap, pass: &ReliefPass) -> Toolpath {
+        let dropper = HeightDropper::new(map, pass.cutter, pass.stock_to_leave);
+        let rows = raster_rows(map, pass.stepover);
+        let heights: Vec<Vec<f64>> = rows.iter().map(|&row| dropper.row(row)).collect();
+        let lowest = heights.iter().flatten().copied().fold(0.0, f64::min);
+
+        let step = pass.step_down.max(0.01);
+        let mut passes = Vec::new();
+        let mut above = 0.0;
+        while above > lowest + 1e-6 {
+            let level = (above - step).max(lowest);
+            for (k, (&row, row_heights)) in rows.iter().zip(&heights).enumerate() {
+                let columns: Vec<usize> = if k % 2 == 0 {
+                    (0..map.columns).collect()
+                } else {
+                    (0..map.columns).rev().collect()
+                };
+                let material: Vec<bool> = columns
+                    .iter()
+                    .map(|&column| row_heights[column] < above - 1e-6)
+                    .collect();
+                let mut cut: Vec<[f64; 3]> = Vec::new();
+                for (i, &column) in columns.iter().enumerate() {
+                    // Cut over material, running on one sample either side
+                    let near = material[i.saturating_sub(1)..(i + 2).min(columns.len())]
+                        .iter()
+                        .any(|&m| m);
+                    if near {
+                        let (x, y) = map.point(column, row);
+                        cut.push([x, y, row_heights[column].max(level)]);
+                    } else if !cut.is_empty() {
+                        passes.push(simplify(&cut, self.settings.tolerance));
+                        cut.clear();
+                    }
+                }
+                if !cut.is_empty() {
+                    passes.push(simplify(&cut, self.settings.tolerance));
+                }
+            }
+            above = level;
+        }
+
+        passes_to_toolpath(
+            &passes,
+            pass.cutter.diameter() as f64,
+            pass.tool.feed_rate,
+            pass.tool.spindle_speed,
+            0.0,
+        )
+    }
+}
+
+/// Rows of the map no further apart than `stepover`, including the first
+/// and last.
+fn raster_rows(map: &ReliefMap, stepover: f64) -> Vec<usize> {
+    let last = map.rows - 1;
+    let span = last as f64 * map.pixel;
+    let count = (span / stepover.max(map.pixel) - 1e-9).ceil().max(0.0) as usize;
+    if count == 0 {
+        return vec![0];
+    }
+    let mut rows: Vec<usize> = (0..=count)
+        .map(|i| ((i * last) as f64 / count as f64).round() as usize)
+        .collect();
+    rows.dedup();
+    rows
+}
+
+/// Drops a cutter onto the samples of a relief map.
+struct HeightDropper<'a> {
+    map: &'a ReliefMap,
+    /// Sample offsets under the cutter and the cutter's height above its tip
+    /// there.
+    footprint: Vec<(isize, isize, f64)>,
+    lift: f64,
+}
+
+impl<'a> HeightDropper<'a> {
+    fn new(map: &'a ReliefMap, cutter: ToolProfile, stock_to_leave: f64) -> Self {
+        let lift = stock_to_leave.max(0.0);
+        let cutter = cutter.grown(lift as f32);
+        let radius = cutter.radius() as f64;
+        let reach = (radius / map.pixel).floor() as isize;
+        let mut footprint = Vec::new();
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let r = (dx as f64).hypot(dy as f64) * map.pixel;
+                if let Some(h) = cutter.height_at(r as f32) {
+                    footprint.push((dx, dy, h as f64));
+                }
+            }
+        }
+        Self {
+            map,
+            footprint,
+            lift,
+        }
+    }
+
+    /// Tip Z of the cutter over a sample, never above the stock top.
+    fn at(&self, column: usize, row: usize) -> f64 {
+        let (c, r) = (column as isize, row as isize);
+        let tip = self
+            .footprint
+            .iter()
+            .map(|&(dx, dy, h)| self.map.z(c + dx, r + dy) - h)
+            .fold(f64::NEG_INFINITY, f64::max);
+        (tip + self.lift).min(0.0)
+    }
+
+    fn row(&self, row: usize) -> Vec<f64> {
+        (0..self.map.columns)
+            .map(|column| self.at(column, row))
+            .collect()
+    }
+}
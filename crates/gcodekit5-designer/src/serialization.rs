@@ -15,7 +15,14 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 use super::canvas::DrawingObject;
+use super::dogbone::DogboneSettings;
+use super::drilling_patterns::DrillSettings;
+use super::leads::LeadSettings;
 use super::pocket_operations::PocketStrategy;
+use super::profile_operations::{CutDirection, CutSide};
+use super::tabs::TabSettings;
+use super::tool_changes::{AssignedTool, ToolChangeSettings};
+use super::vcarve::VCarveSettings;
 use crate::model::*;
 
 /// Design file format version
@@ -107,6 +114,22 @@ pub struct ShapeData {
     #[serde(default = "default_raster_fill_ratio")]
     pub raster_fill_ratio: f64,
     #[serde(default)]
+    pub cut_side: CutSide,
+    #[serde(default)]
+    pub cut_direction: CutDirection,
+    #[serde(default)]
+    pub finishing_allowance: f64,
+    #[serde(default)]
+    pub tabs: TabSettings,
+    #[serde(default)]
+    pub leads: LeadSettings,
+    #[serde(default)]
+    pub vcarve: VCarveSettings,
+    #[serde(default)]
+    pub drill: DrillSettings,
+    #[serde(default)]
+    pub tool: Option<AssignedTool>,
+    #[serde(default)]
     pub sides: u32,
     #[serde(default)]
     pub teeth: usize,
@@ -130,6 +153,8 @@ pub struct ShapeData {
     pub fillet: f64,
     #[serde(default)]
     pub chamfer: f64,
+    #[serde(default)]
+    pub dogbone: DogboneSettings,
     #[serde(default = "default_lock_aspect_ratio")]
     pub lock_aspect_ratio: bool,
 }
@@ -161,6 +186,10 @@ pub struct ToolpathParameters {
     pub stock_thickness: f32,
     #[serde(default = "default_safe_z_height")]
     pub safe_z_height: f32,
+    #[serde(default)]
+    pub tool_change: ToolChangeSettings,
+    #[serde(default = "default_optimize_order")]
+    pub optimize_order: bool,
 }
 
 fn default_feed_rate() -> f64 {
@@ -187,6 +216,9 @@ fn default_stock_thickness() -> f32 {
 fn default_safe_z_height() -> f32 {
     10.0
 }
+fn default_optimize_order() -> bool {
+    true
+}
 
 impl Default for ToolpathParameters {
     fn default() -> Self {
@@ -199,6 +231,8 @@ impl Default for ToolpathParameters {
             stock_height: default_stock_height(),
             stock_thickness: default_stock_thickness(),
             safe_z_height: default_safe_z_height(),
+            tool_change: ToolChangeSettings::default(),
+            optimize_order: default_optimize_order(),
         }
     }
 }
@@ -388,6 +422,9 @@ impl DesignFile {
             operation_type: match obj.operation_type {
                 OperationType::Profile => "profile".to_string(),
                 OperationType::Pocket => "pocket".to_string(),
+                OperationType::Engrave => "engrave".to_string(),
+                OperationType::VCarve => "vcarve".to_string(),
+                OperationType::Drill => "drill".to_string(),
             },
             pocket_depth: obj.pocket_depth,
             start_depth: obj.start_depth,
@@ -406,6 +443,14 @@ impl DesignFile {
             ramp_angle: obj.ramp_angle,
             pocket_strategy: obj.pocket_strategy,
             raster_fill_ratio: obj.raster_fill_ratio,
+            cut_side: obj.cut_side,
+            cut_direction: obj.cut_direction,
+            finishing_allowance: obj.finishing_allowance,
+            tabs: obj.tabs.clone(),
+            leads: obj.leads.clone(),
+            vcarve: obj.vcarve.clone(),
+            drill: obj.drill.clone(),
+            tool: obj.tool.clone(),
             sides,
             teeth,
             module,
@@ -418,6 +463,7 @@ impl DesignFile {
             offset: obj.offset,
             fillet: obj.fillet,
             chamfer: obj.chamfer,
+            dogbone: obj.dogbone.clone(),
             lock_aspect_ratio: obj.lock_aspect_ratio,
         }
     }
@@ -523,6 +569,9 @@ impl DesignFile {
 
         let operation_type = match data.operation_type.as_str() {
             "pocket" => OperationType::Pocket,
+            "engrave" => OperationType::Engrave,
+            "vcarve" => OperationType::VCarve,
+            "drill" => OperationType::Drill,
             _ => OperationType::Profile,
         };
 
@@ -559,9 +608,18 @@ impl DesignFile {
             ramp_angle: data.ramp_angle,
             pocket_strategy: data.pocket_strategy,
             raster_fill_ratio: data.raster_fill_ratio,
+            cut_side: data.cut_side,
+            cut_direction: data.cut_direction,
+            finishing_allowance: data.finishing_allowance,
+            tabs: data.tabs.clone(),
+            leads: data.leads.clone(),
+            vcarve: data.vcarve.clone(),
+            drill: data.drill.clone(),
+            tool: data.tool.clone(),
             offset: data.offset,
             fillet: data.fillet,
             chamfer: data.chamfer,
+            dogbone: data.dogbone.clone(),
             lock_aspect_ratio: data.lock_aspect_ratio,
         })
     }
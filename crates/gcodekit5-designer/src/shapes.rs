@@ -7,4 +7,10 @@ pub enum OperationType {
     #[default]
     Profile,
     Pocket,
+    /// Follow the outline itself at a fixed depth
+    Engrave,
+    /// Carve with a V-bit, deeper where the shape is wider
+    VCarve,
+    /// Drill holes at circle centres
+    Drill,
 }
@@ -4,6 +4,7 @@
 //! during CNC machining operations. It supports both 2D height-map based simulation
 //! and 3D voxel-based simulation.
 
+use gcodekit5_core::data::tools::ToolProfile;
 use tracing::debug;
 
 /// Represents the stock material dimensions and position
@@ -235,18 +236,32 @@ pub struct StockSimulator2D {
     pub stock: StockMaterial,
     /// Height map tracking the material surface
     pub height_map: HeightMap2D,
-    /// Tool radius in mm
+    /// Tool radius in mm (outer radius of `tool_profile`)
     pub tool_radius: f32,
+    /// Shape of the cutter swept along each move
+    pub tool_profile: ToolProfile,
 }
 
 impl StockSimulator2D {
-    /// Create a new 2D stock simulator
+    /// Create a new 2D stock simulator cutting with a flat end mill
     pub fn new(stock: StockMaterial, resolution: f32, tool_radius: f32) -> Self {
+        Self::with_tool_profile(
+            stock,
+            resolution,
+            ToolProfile::Flat {
+                diameter: tool_radius * 2.0,
+            },
+        )
+    }
+
+    /// Create a new 2D stock simulator cutting with the given tool profile
+    pub fn with_tool_profile(stock: StockMaterial, resolution: f32, tool: ToolProfile) -> Self {
         let height_map = HeightMap2D::new(&stock, resolution);
         Self {
             stock,
             height_map,
-            tool_radius,
+            tool_radius: tool.radius(),
+            tool_profile: tool,
         }
     }
 
@@ -305,48 +320,24 @@ impl StockSimulator2D {
         );
     }
 
+    /// Convert a segment depth (negative = below stock top) to a height in the stock
+    fn depth_to_height(&self, depth: Option<f64>) -> f32 {
+        // Stock top is at thickness, Z=0 means cutting to stock top
+        self.stock.thickness - (depth.unwrap_or(0.0) as f32).abs()
+    }
+
     /// Simulate a linear cutting move
     fn simulate_linear_move(&mut self, segment: &crate::toolpath::ToolpathSegment) {
-        let start = &segment.start;
-        let end = &segment.end;
-        // Convert Z depth to height in stock (stock top is at thickness, Z=0 means cutting to stock top)
-        let z_depth = segment.z_depth.unwrap_or(0.0) as f32;
-        let z = self.stock.thickness - z_depth.abs();
-
-        // Debug first few moves
-        static MOVE_COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
-        let count = MOVE_COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        if count < 5 {
-            debug!(
-                move_num = count,
-                z_depth,
-                z,
-                stock_thickness = self.stock.thickness,
-                "linear move"
-            );
-        }
-
-        let dx = end.x - start.x;
-        let dy = end.y - start.y;
-        let distance = (dx * dx + dy * dy).sqrt();
-
-        if distance < 0.001 {
-            // Very short move, just process as single point
-            self.apply_tool_footprint(end.x as f32, end.y as f32, z);
-            return;
-        }
-
-        // Interpolate along the line with step size equal to half the resolution
-        let step_size = self.height_map.resolution * 0.5;
-        let num_steps = (distance / step_size as f64).ceil() as usize + 1;
-
-        for i in 0..num_steps {
-            let t = (i as f64) / (num_steps - 1) as f64;
-            let x = start.x + dx * t;
-            let y = start.y + dy * t;
+        let end_z = self.depth_to_height(segment.z_depth);
+        let start_z = match segment.start_z {
+            Some(_) => self.depth_to_height(segment.start_z),
+            None => end_z,
+        };
 
-            self.apply_tool_footprint(x as f32, y as f32, z);
-        }
+        self.apply_tool_sweep(
+            (segment.start.x as f32, segment.start.y as f32, start_z),
+            (segment.end.x as f32, segment.end.y as f32, end_z),
+        );
     }
 
     /// Simulate an arc move
@@ -358,9 +349,11 @@ impl StockSimulator2D {
 
         let start = &segment.start;
         let end = &segment.end;
-        // Convert Z depth to height in stock (stock top is at thickness, Z=0 means cutting to stock top)
-        let z_depth = segment.z_depth.unwrap_or(0.0) as f32;
-        let z = self.stock.thickness - z_depth.abs();
+        let end_z = self.depth_to_height(segment.z_depth);
+        let start_z = match segment.start_z {
+            Some(_) => self.depth_to_height(segment.start_z),
+            None => end_z,
+        };
 
         // Calculate arc parameters
         let radius = ((start.x - center.x).powi(2) + (start.y - center.y).powi(2)).sqrt();
@@ -380,73 +373,64 @@ impl StockSimulator2D {
             }
         }
 
-        let arc_length = radius * angle_diff.abs();
-        let step_size = self.height_map.resolution as f64 * 0.5;
-        let num_steps = (arc_length / step_size).ceil() as usize + 1;
+        // Split the arc into chords that stay within a quarter pixel of the true arc
+        let tolerance = self.height_map.resolution as f64 * 0.25;
+        let max_step = if radius > tolerance {
+            2.0 * (1.0 - tolerance / radius).acos()
+        } else {
+            std::f64::consts::FRAC_PI_2
+        };
+        let num_steps = ((angle_diff.abs() / max_step.max(1e-3)).ceil() as usize).max(1);
 
-        for i in 0..num_steps {
-            let t = (i as f64) / (num_steps - 1) as f64;
+        let mut previous = (start.x as f32, start.y as f32, start_z);
+        for i in 1..=num_steps {
+            let t = (i as f64) / num_steps as f64;
             let angle = start_angle + angle_diff * t;
             let x = center.x + radius * angle.cos();
             let y = center.y + radius * angle.sin();
+            let z = start_z + (end_z - start_z) * t as f32;
 
-            self.apply_tool_footprint(x as f32, y as f32, z);
+            let point = (x as f32, y as f32, z);
+            self.apply_tool_sweep(previous, point);
+            previous = point;
         }
     }
 
-    /// Apply the circular tool footprint at a given position
-    /// Updates all pixels within the tool radius to the cutting depth
-    fn apply_tool_footprint(&mut self, cx: f32, cy: f32, cz: f32) {
-        let radius_px = (self.tool_radius / self.height_map.resolution).ceil() as isize;
-        let (center_px, center_py) = self.height_map.world_to_pixel(cx, cy);
-
-        // Debug first few footprint calls
-        static FOOTPRINT_COUNT: std::sync::atomic::AtomicUsize =
-            std::sync::atomic::AtomicUsize::new(0);
-        let count = FOOTPRINT_COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        if count < 5 {
-            debug!(
-                footprint_num = count,
-                pos_x = cx,
-                pos_y = cy,
-                z = cz,
-                tool_radius = self.tool_radius,
-                radius_px,
-                center_px,
-                center_py,
-                "apply tool footprint"
-            );
-        }
-
-        // Iterate over a square bounding box around the tool
-        for py in (center_py - radius_px)..=(center_py + radius_px) {
-            for px in (center_px - radius_px)..=(center_px + radius_px) {
-                if px < 0
-                    || py < 0
-                    || px >= self.height_map.width_px as isize
-                    || py >= self.height_map.height_px as isize
-                {
-                    continue;
-                }
-
-                // Get world coordinates of this pixel
+    /// Sweep the tool profile along a straight move
+    /// Lowers every pixel under the swept cutter to the deepest point the cutter reaches there
+    fn apply_tool_sweep(&mut self, start: (f32, f32, f32), end: (f32, f32, f32)) {
+        let radius = self.tool_profile.radius();
+        let (min_px, min_py) = self
+            .height_map
+            .world_to_pixel(start.0.min(end.0) - radius, start.1.min(end.1) - radius);
+        let (max_px, max_py) = self
+            .height_map
+            .world_to_pixel(start.0.max(end.0) + radius, start.1.max(end.1) + radius);
+
+        let min_px = min_px.max(0);
+        let min_py = min_py.max(0);
+        let max_px = max_px.min(self.height_map.width_px as isize - 1);
+        let max_py = max_py.min(self.height_map.height_px as isize - 1);
+
+        for py in min_py..=max_py {
+            // Only the pixels within the tool radius of the move on this row
+            let (_, row_y) = self.height_map.pixel_to_world(0, py as usize);
+            let Some((left, right)) = self.tool_profile.sweep_span(row_y, start, end) else {
+                continue;
+            };
+            let (first_px, _) = self.height_map.world_to_pixel(left, row_y);
+            let (last_px, _) = self.height_map.world_to_pixel(right, row_y);
+            for px in first_px.max(min_px)..=last_px.min(max_px) {
                 let (world_x, world_y) = self.height_map.pixel_to_world(px as usize, py as usize);
+                let Some(bottom) = self.tool_profile.swept_bottom(world_x, world_y, start, end)
+                else {
+                    continue;
+                };
 
-                // Check if pixel is within tool radius
-                let dx = world_x - cx;
-                let dy = world_y - cy;
-                let distance = (dx * dx + dy * dy).sqrt();
-
-                if distance <= self.tool_radius {
-                    // Update height to minimum of current height and cutting depth
-                    let current_height = self
-                        .height_map
-                        .get_height_at_pixel(px as usize, py as usize)
-                        .unwrap_or(cz);
-                    let new_height = current_height.min(cz);
-                    self.height_map
-                        .set_height_at_pixel(px as usize, py as usize, new_height);
-                }
+                // Update height to minimum of current height and cutting depth
+                let index = (py as usize) * self.height_map.width_px + (px as usize);
+                let current = self.height_map.heights[index];
+                self.height_map.heights[index] = current.min(bottom);
             }
         }
     }
@@ -741,6 +725,38 @@ mod tests {
         assert!(height_at_end.expect("height not found") <= 5.0);
     }
 
+    #[test]
+    fn test_vbit_profile_simulation() {
+        use crate::toolpath::{ToolpathSegment, ToolpathSegmentType};
+        use crate::Point;
+
+        let stock = StockMaterial::new(40.0, 40.0, 10.0, (0.0, 0.0, 0.0));
+        let tool = ToolProfile::VBit {
+            diameter: 6.0,
+            tip_angle: 90.0,
+        };
+        let mut simulator = StockSimulator2D::with_tool_profile(stock, 0.5, tool);
+        assert_eq!(simulator.tool_radius, 3.0);
+
+        let segment = ToolpathSegment::new(
+            ToolpathSegmentType::LinearMove,
+            Point { x: 5.0, y: 20.25 },
+            Point { x: 35.0, y: 20.25 },
+            100.0,
+            10000,
+        )
+        .with_z_depth(2.0);
+        simulator.simulate_toolpath(&[segment]);
+
+        // Full depth on the centre line, rising with the 45 degree flank
+        let centre = simulator.height_map.get_height(20.0, 20.0).expect("in map");
+        assert!((centre - 8.0).abs() < 1e-3);
+        let flank = simulator.height_map.get_height(20.0, 21.0).expect("in map");
+        assert!((flank - 9.0).abs() < 1e-3);
+        let outside = simulator.height_map.get_height(20.0, 24.0).expect("in map");
+        assert_eq!(outside, 10.0);
+    }
+
     #[test]
     fn test_visualization_contours() {
         use visualization::generate_2d_contours;
@@ -0,0 +1,765 @@
+//! # 3D Surface Finishing Module
+//!
+//! Generates finishing toolpaths that follow the surface of a triangle mesh,
+//! rather than stepping down through flat slices.
+//!
+//! ## Strategies
+//! - **Parallel**: zigzag raster passes at any angle. At each point along a
+//!   pass the cutter is dropped along its axis until it touches the mesh
+//!   (drop-cutter), so the tip follows the surface without gouging it.
+//! - **Waterline**: closed contours at constant Z, traced where the dropped
+//!   cutter just touches the surface. Best on steep walls, where raster
+//!   passes spread apart.
+//!
+//! The cutter is a [`ToolProfile`], the same geometry the stock simulator
+//! cuts with. Stock to leave offsets the whole surface by growing the
+//! cutter, and the stepover can be derived from the scallop height left
+//! between passes.
+//!
+//! Z values are measured down from the top of the model, which is placed at
+//! the top of the stock, so the toolpath feeds straight into the G-code
+//! generator and the stock simulator.
+
+use crate::model::Point;
+use crate::model3d::Mesh3D;
+use crate::toolpath::{Toolpath, ToolpathSegment, ToolpathSegmentType};
+use gcodekit5_core::data::tools::ToolProfile;
+use std::collections::HashMap;
+use tracing::info;
+
+/// Iterations of the golden-section search for edge contacts.
+const EDGE_SEARCH_STEPS: usize = 40;
+
+/// Iterations refining each waterline crossing between grid points.
+const WATERLINE_REFINE_STEPS: usize = 6;
+
+/// Most triangle buckets along each side of the drop-cutter index.
+const MAX_BUCKETS: f64 = 256.0;
+
+/// Surface finishing strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FinishStrategy {
+    /// Zigzag raster passes at a fixed angle.
+    #[default]
+    Parallel,
+    /// Constant-Z contours.
+    Waterline,
+}
+
+impl FinishStrategy {
+    /// Returns the name of the strategy.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FinishStrategy::Parallel => "Parallel",
+            FinishStrategy::Waterline => "Waterline",
+        }
+    }
+}
+
+/// Parameters for surface finishing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinishingParams {
+    pub strategy: FinishStrategy,
+    pub cutter: ToolProfile,
+    /// Distance between raster passes, in mm.
+    pub stepover: f64,
+    /// When set, the stepover and the waterline step-down are chosen to
+    /// leave scallops no higher than this, in mm. Flat and conical cutters
+    /// keep the set stepover and step-down.
+    pub scallop_height: Option<f64>,
+    /// Direction of the raster passes, in degrees from the X axis.
+    pub angle: f64,
+    /// Z distance between waterlines, in mm.
+    pub step_down: f64,
+    /// Material left on the surface, in mm.
+    pub stock_to_leave: f64,
+    /// Distance between the points tested along a pass, in mm.
+    pub sample_step: f64,
+    /// Points closer than this to the line through their neighbours are
+    /// dropped from the output, in mm.
+    pub tolerance: f64,
+    /// Feed rate in mm/min.
+    pub feed_rate: f64,
+    /// Spindle speed in RPM.
+    pub spindle_speed: u32,
+}
+
+impl Default for FinishingParams {
+    fn default() -> Self {
+        Self {
+            strategy: FinishStrategy::Parallel,
+            cutter: ToolProfile::Ball { diameter: 3.175 },
+            stepover: 0.3,
+            scallop_height: None,
+            angle: 0.0,
+            step_down: 0.3,
+            stock_to_leave: 0.0,
+            sample_step: 0.2,
+            tolerance: 0.01,
+            feed_rate: 1000.0,
+            spindle_speed: 18000,
+        }
+    }
+}
+
+impl FinishingParams {
+    /// Distance between raster passes, from the scallop height when set.
+    pub fn effective_stepover(&self) -> f64 {
+        let rc = self.cutter.corner_radius() as f64;
+        let diameter = self.cutter.diameter() as f64;
+        match self.scallop_height {
+            Some(h) if h > 0.0 && rc > 0.0 => {
+                let h = h.min(rc);
+                (diameter - 2.0 * rc + 2.0 * (h * (2.0 * rc - h)).sqrt()).min(diameter)
+            }
+            _ => self.stepover,
+        }
+        .max(0.01)
+    }
+
+    /// Z distance between waterlines, from the scallop height when set.
+    pub fn effective_step_down(&self) -> f64 {
+        let rc = self.cutter.corner_radius() as f64;
+        match self.scallop_height {
+            Some(h) if h > 0.0 && rc > 0.0 => {
+                let h = h.min(rc);
+                2.0 * (h * (2.0 * rc - h)).sqrt()
+            }
+            _ => self.step_down,
+        }
+        .max(0.01)
+    }
+}
+
+/// Finds the height of a cutter dropped onto a mesh.
+pub struct DropCutter {
+    triangles: Vec<[[f64; 3]; 3]>,
+    cutter: ToolProfile,
+    /// The cutter's tip sits this far above the grown cutter's tip.
+    lift: f64,
+    floor: f64,
+    origin: (f64, f64),
+    bucket: f64,
+    columns: usize,
+    rows: usize,
+    buckets: Vec<Vec<usize>>,
+}
+
+impl DropCutter {
+    /// Prepares `mesh` for dropping `cutter`, keeping `stock_to_leave` off
+    /// the surface. Where the cutter misses the mesh it drops to the bottom
+    /// of the mesh bounds.
+    pub fn new(mesh: &Mesh3D, cutter: ToolProfile, stock_to_leave: f64) -> Self {
+        let leave = stock_to_leave.max(0.0);
+        let cutter = cutter.grown(leave as f32);
+        let radius = cutter.radius() as f64;
+
+        let triangles: Vec<[[f64; 3]; 3]> = mesh
+            .triangles
+            .iter()
+            .map(|t| t.vertices.map(|v| [v.x as f64, v.y as f64, v.z as f64]))
+            .collect();
+
+        let (min, max) = (mesh.bounds_min, mesh.bounds_max);
+        let origin = (min.x as f64 - radius, min.y as f64 - radius);
+        let width = (max.x - min.x) as f64 + 2.0 * radius;
+        let height = (max.y - min.y) as f64 + 2.0 * radius;
+        let bucket = radius.max(width.max(height) / MAX_BUCKETS).max(1e-3);
+        let columns = (width / bucket).ceil().max(1.0) as usize;
+        let rows = (height / bucket).ceil().max(1.0) as usize;
+
+        let mut buckets = vec![Vec::new(); columns * rows];
+        let cell =
+            |v: f64, o: f64, n: usize| (((v - o) / bucket).floor().max(0.0) as usize).min(n - 1);
+        for (index, tri) in triangles.iter().enumerate() {
+            let lo_x = tri.iter().map(|v| v[0]).fold(f64::INFINITY, f64::min) - radius;
+            let hi_x = tri.iter().map(|v| v[0]).fold(f64::NEG_INFINITY, f64::max) + radius;
+            let lo_y = tri.iter().map(|v| v[1]).fold(f64::INFINITY, f64::min) - radius;
+            let hi_y = tri.iter().map(|v| v[1]).fold(f64::NEG_INFINITY, f64::max) + radius;
+            for row in cell(lo_y, origin.1, rows)..=cell(hi_y, origin.1, rows) {
+                for column in cell(lo_x, origin.0, columns)..=cell(hi_x, origin.0, columns) {
+                    buckets[row * columns + column].push(index);
+                }
+            }
+        }
+
+        Self {
+            triangles,
+            cutter,
+            lift: leave,
+            floor: min.z as f64,
+            origin,
+            bucket,
+            columns,
+            rows,
+            buckets,
+        }
+    }
+
+    /// Z of the cutter tip dropped at `(x, y)`.
+    pub fn height(&self, x: f64, y: f64) -> f64 {
+        let mut best = self.floor - self.lift;
+        let column = ((x - self.origin.0) / self.bucket).floor();
+        let row = ((y - self.origin.1) / self.bucket).floor();
+        if column >= 0.0
+            && row >= 0.0
+            && (column as usize) < self.columns
+            && (row as usize) < self.rows
+        {
+            for &index in &self.buckets[row as usize * self.columns + column as usize] {
+                best = best.max(self.touch_triangle(&self.triangles[index], x, y, best));
+            }
+        }
+        best + self.lift
+    }
+
+    /// Highest tip Z at which the cutter at `(x, y)` touches `tri`, or
+    /// `best` when it cannot beat it.
+    fn touch_triangle(&self, tri: &[[f64; 3]; 3], x: f64, y: f64, best: f64) -> f64 {
+        let radius = self.cutter.radius() as f64;
+        let top = tri[0][2].max(tri[1][2]).max(tri[2][2]);
+        if top <= best {
+            return best;
+        }
+        let lo_x = tri[0][0].min(tri[1][0]).min(tri[2][0]);
+        let hi_x = tri[0][0].max(tri[1][0]).max(tri[2][0]);
+        let lo_y = tri[0][1].min(tri[1][1]).min(tri[2][1]);
+        let hi_y = tri[0][1].max(tri[1][1]).max(tri[2][1]);
+        if x < lo_x - radius || x > hi_x + radius || y < lo_y - radius || y > hi_y + radius {
+            return best;
+        }
+
+        let mut best = best;
+        for v in tri {
+            let r = (v[0] - x).hypot(v[1] - y);
+            if r <= radius {
+                best = best.max(v[2] - self.cutter_height(r));
+            }
+        }
+        if let Some(z) = self.touch_facet(tri, x, y) {
+            best = best.max(z);
+        }
+        for k in 0..3 {
+            best = best.max(self.touch_edge(tri[k], tri[(k + 1) % 3], x, y, best));
+        }
+        best
+    }
+
+    /// Tip Z where the cutter rests on the inside of the triangle's plane.
+    fn touch_facet(&self, tri: &[[f64; 3]; 3], x: f64, y: f64) -> Option<f64> {
+        let [a, b, c] = *tri;
+        let (u, v) = (sub(b, a), sub(c, a));
+        let mut n = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+        if len < 1e-12 {
+            return None;
+        }
+        if n[2] < 0.0 {
+            n = [-n[0], -n[1], -n[2]];
+        }
+        n = [n[0] / len, n[1] / len, n[2] / len];
+        if n[2] < 1e-9 {
+            return None;
+        }
+
+        // The cutter touches the plane on its uphill side
+        let slope = n[0].hypot(n[1]);
+        let (dx, dy) = if slope > 1e-12 {
+            (n[0] / slope, n[1] / slope)
+        } else {
+            (0.0, 0.0)
+        };
+        let r = self.cutter.contact_radius((slope / n[2]) as f32) as f64;
+        let contact = (x - r * dx, y - r * dy);
+        if !in_triangle(tri, contact.0, contact.1) {
+            return None;
+        }
+        let plane = a[2] - (n[0] * (contact.0 - a[0]) + n[1] * (contact.1 - a[1])) / n[2];
+        Some(plane - self.cutter_height(r))
+    }
+
+    /// Highest tip Z at which the cutter touches the edge from `p` to `q`,
+    /// or `best` when it cannot beat it.
+    fn touch_edge(&self, p: [f64; 3], q: [f64; 3], x: f64, y: f64, best: f64) -> f64 {
+        if p[2].max(q[2]) <= best {
+            return best;
+        }
+        let radius = self.cutter.radius() as f64;
+        let (ax, ay) = (p[0] - x, p[1] - y);
+        let (dx, dy) = (q[0] - p[0], q[1] - p[1]);
+        let len_sq = dx * dx + dy * dy;
+        if len_sq < 1e-18 {
+            // Vertical edge: its top end is the vertex test
+            return best;
+        }
+
+        // Part of the edge under the cutter
+        let half_b = ax * dx + ay * dy;
+        let disc = half_b * half_b - len_sq * (ax * ax + ay * ay - radius * radius);
+        if disc < 0.0 {
+            return best;
+        }
+        let root = disc.sqrt();
+        let t0 = ((-half_b - root) / len_sq).max(0.0);
+        let t1 = ((-half_b + root) / len_sq).min(1.0);
+        if t0 > t1 {
+            return best;
+        }
+
+        // The tip height is concave along the edge
+        let height = |t: f64| {
+            let r = (ax + dx * t).hypot(ay + dy * t).min(radius);
+            p[2] + (q[2] - p[2]) * t - self.cutter_height(r)
+        };
+        const INV_PHI: f64 = 0.618_033_988_749_895;
+        let (mut lo, mut hi) = (t0, t1);
+        let mut m1 = hi - (hi - lo) * INV_PHI;
+        let mut m2 = lo + (hi - lo) * INV_PHI;
+        let (mut f1, mut f2) = (height(m1), height(m2));
+        for _ in 0..EDGE_SEARCH_STEPS {
+            if f1 >= f2 {
+                hi = m2;
+                m2 = m1;
+                f2 = f1;
+                m1 = hi - (hi - lo) * INV_PHI;
+                f1 = height(m1);
+            } else {
+                lo = m1;
+                m1 = m2;
+                f1 = f2;
+                m2 = lo + (hi - lo) * INV_PHI;
+                f2 = height(m2);
+            }
+        }
+        best.max(f1).max(f2).max(height(t0)).max(height(t1))
+    }
+
+    /// Height of the cutting surface above the tip at distance `r` from the
+    /// axis, within the cutter radius.
+    fn cutter_height(&self, r: f64) -> f64 {
+        let r = (r as f32).min(self.cutter.radius());
+        self.cutter.height_at(r).unwrap_or(0.0) as f64
+    }
+}
+
+/// Generates surface finishing toolpaths from meshes.
+pub struct SurfaceFinisher {
+    params: FinishingParams,
+}
+
+impl Default for SurfaceFinisher {
+    fn default() -> Self {
+        Self::new(FinishingParams::default())
+    }
+}
+
+impl SurfaceFinisher {
+    pub fn new(params: FinishingParams) -> Self {
+        Self { params }
+    }
+
+    /// Get parameters
+    pub fn params(&self) -> &FinishingParams {
+        &self.params
+    }
+
+    /// Set parameters
+    pub fn set_params(&mut self, params: FinishingParams) {
+        self.params = params;
+    }
+
+    /// Generates the finishing toolpath for `mesh`.
+    pub fn generate(&self, mesh: &Mesh3D) -> Toolpath {
+        if mesh.triangles.is_empty() {
+            return Toolpath::new(self.params.cutter.diameter() as f64, 0.0);
+        }
+        let dropper = DropCutter::new(mesh, self.params.cutter, self.params.stock_to_leave);
+        let top = mesh.bounds_max.z as f64;
+        let passes = match self.params.strategy {
+            FinishStrategy::Parallel => self.parallel_passes(mesh, &dropper),
+            FinishStrategy::Waterline => self.waterline_passes(mesh, &dropper),
+        };
+        info!(
+            "Generated {} {} finishing passes over {} triangles",
+            passes.len(),
+            self.params.strategy.name(),
+            mesh.triangles.len()
+        );
+        passes_to_toolpath(
+            &passes,
+            self.params.cutter.diameter() as f64,
+            self.params.feed_rate,
+            self.params.spindle_speed,
+            top,
+        )
+    }
+
+    /// Zigzag raster passes, joined along the boundary into one run.
+    fn parallel_passes(&self, mesh: &Mesh3D, dropper: &DropCutter) -> Vec<Vec<[f64; 3]>> {
+        let (lo, hi) = (mesh.bounds_min, mesh.bounds_max);
+        let (min_x, min_y) = (lo.x as f64, lo.y as f64);
+        let (max_x, max_y) = (hi.x as f64, hi.y as f64);
+        let (sin, cos) = self.params.angle.to_radians().sin_cos();
+        let corners = [
+            (min_x, min_y),
+            (max_x, min_y),
+            (max_x, max_y),
+            (min_x, max_y),
+        ];
+
+        // Passes run along (cos, sin) and step along the normal (-sin, cos)
+        let across = |p: &(f64, f64)| -p.0 * sin + p.1 * cos;
+        let lo_n = corners.iter().map(across).fold(f64::INFINITY, f64::min);
+        let hi_n = corners.iter().map(across).fold(f64::NEG_INFINITY, f64::max);
+        let count = ((hi_n - lo_n) / self.params.effective_stepover() - 1e-9)
+            .ceil()
+            .max(0.0) as usize;
+        let step = if count > 0 {
+            (hi_n - lo_n) / count as f64
+        } else {
+            0.0
+        };
+
+        let mut run: Vec<[f64; 3]> = Vec::new();
+        for k in 0..=count {
+            let offset = lo_n + k as f64 * step;
+            let Some((s0, s1)) = clip_line(offset, sin, cos, (min_x, min_y, max_x, max_y)) else {
+                continue;
+            };
+            let (s0, s1) = if k % 2 == 0 { (s0, s1) } else { (s1, s0) };
+            let start = (s0 * cos - offset * sin, s0 * sin + offset * cos);
+            let end = (s1 * cos - offset * sin, s1 * sin + offset * cos);
+
+            // Link from the previous pass along the surface
+            if let Some(&last) = run.last() {
+                run.extend(
+                    self.sample(dropper, (last[0], last[1]), start)
+                        .into_iter()
+                        .skip(1),
+                );
+            }
+            let pass = self.sample(dropper, start, end);
+            let skip = usize::from(!run.is_empty());
+            run.extend(pass.into_iter().skip(skip));
+        }
+
+        if run.is_empty() {
+            Vec::new()
+        } else {
+            vec![simplify(&run, self.params.tolerance)]
+        }
+    }
+
+    /// Dropped cutter points from `start` to `end`, no further apart than
+    /// the sample step.
+    fn sample(&self, dropper: &DropCutter, start: (f64, f64), end: (f64, f64)) -> Vec<[f64; 3]> {
+        let length = (end.0 - start.0).hypot(end.1 - start.1);
+        let steps = (length / self.params.sample_step.max(0.01)).ceil().max(1.0) as usize;
+        (0..=steps)
+            .map(|i| {
+                let t = i as f64 / steps as f64;
+                let x = start.0 + (end.0 - start.0) * t;
+                let y = start.1 + (end.1 - start.1) * t;
+                [x, y, dropper.height(x, y)]
+            })
+            .collect()
+    }
+
+    /// Closed contours at each waterline, from the top down.
+    fn waterline_passes(&self, mesh: &Mesh3D, dropper: &DropCutter) -> Vec<Vec<[f64; 3]>> {
+        let cell = self.params.sample_step.max(0.01);
+        let margin = dropper.cutter.radius() as f64 + 2.0 * cell;
+        let (x0, y0) = (
+            mesh.bounds_min.x as f64 - margin,
+            mesh.bounds_min.y as f64 - margin,
+        );
+        let nx = (((mesh.bounds_max.x - mesh.bounds_min.x) as f64 + 2.0 * margin) / cell).ceil()
+            as usize
+            + 1;
+        let ny = (((mesh.bounds_max.y - mesh.bounds_min.y) as f64 + 2.0 * margin) / cell).ceil()
+            as usize
+            + 1;
+
+        let grid = Grid {
+            x0,
+            y0,
+            cell,
+            nx,
+            ny,
+            heights: (0..ny)
+                .flat_map(|j| (0..nx).map(move |i| (i, j)))
+                .map(|(i, j)| dropper.height(x0 + i as f64 * cell, y0 + j as f64 * cell))
+                .collect(),
+        };
+
+        let floor = dropper.floor;
+        let top = grid.heights.iter().copied().fold(floor, f64::max);
+        let step = self.params.effective_step_down();
+        let mut passes = Vec::new();
+        let mut level = top - step;
+        while level > floor + 1e-6 {
+            for contour in grid.contours(level, dropper) {
+                let mut points: Vec<[f64; 3]> =
+                    contour.into_iter().map(|(x, y)| [x, y, level]).collect();
+                points.push(points[0]);
+                passes.push(simplify(&points, self.params.tolerance));
+            }
+            level -= step;
+        }
+        passes
+    }
+}
+
+/// Turns cutter tip paths into a toolpath, with Z measured down from `top`.
+/// Each pass starts with a rapid move and is then cut point to point.
+pub(crate) fn passes_to_toolpath(
+    passes: &[Vec<[f64; 3]>],
+    diameter: f64,
+    feed: f64,
+    speed: u32,
+    top: f64,
+) -> Toolpath {
+    let depth = |z: f64| (z - top).min(0.0);
+    let lowest = passes
+        .iter()
+        .flatten()
+        .map(|p| depth(p[2]))
+        .fold(0.0, f64::min);
+
+    let mut toolpath = Toolpath::new(diameter, lowest);
+    let mut position = Point::new(0.0, 0.0);
+    for pass in passes.iter().filter(|pass| pass.len() >= 2) {
+        let first = Point::new(pass[0][0], pass[0][1]);
+        toolpath.add_segment(ToolpathSegment::new(
+            ToolpathSegmentType::RapidMove,
+            position,
+            first,
+            feed,
+            speed,
+        ));
+        for pair in pass.windows(2) {
+            let mut segment = ToolpathSegment::new(
+                ToolpathSegmentType::LinearMove,
+                Point::new(pair[0][0], pair[0][1]),
+                Point::new(pair[1][0], pair[1][1]),
+                feed,
+                speed,
+            )
+            .with_z_depth(depth(pair[1][2]));
+            segment.start_z = Some(depth(pair[0][2]));
+            toolpath.add_segment(segment);
+        }
+        let last = pass[pass.len() - 1];
+        position = Point::new(last[0], last[1]);
+    }
+    toolpath
+}
+
+/// Dropped cutter heights on a regular grid.
+struct Grid {
+    x0: f64,
+    y0: f64,
+    cell: f64,
+    nx: usize,
+    ny: usize,
+    heights: Vec<f64>,
+}
+
+impl Grid {
+    fn at(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.nx + i]
+    }
+
+    fn point(&self, i: usize, j: usize) -> (f64, f64) {
+        (
+            self.x0 + i as f64 * self.cell,
+            self.y0 + j as f64 * self.cell,
+        )
+    }
+
+    /// Closed contours where the dropped cutter height crosses `level`,
+    /// with the higher side on the right.
+    fn contours(&self, level: f64, dropper: &DropCutter) -> Vec<Vec<(f64, f64)>> {
+        let inside = |i: usize, j: usize| self.at(i, j) > level;
+        let mut crossings: HashMap<usize, (f64, f64)> = HashMap::new();
+        let mut next: HashMap<usize, usize> = HashMap::new();
+
+        for j in 0..self.ny - 1 {
+            for i in 0..self.nx - 1 {
+                // Corners and edges counter-clockwise from the bottom left
+                let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+                let edges = [
+                    2 * (j * self.nx + i),
+                    2 * ((j * self.nx) + i + 1) + 1,
+                    2 * ((j + 1) * self.nx + i),
+                    2 * (j * self.nx + i) + 1,
+                ];
+                let states = corners.map(|(ci, cj)| inside(ci, cj));
+                let entering: Vec<usize> = (0..4)
+                    .filter(|&k| !states[k] && states[(k + 1) % 4])
+                    .collect();
+                if entering.is_empty() {
+                    continue;
+                }
+                let centre_inside =
+                    corners.iter().map(|&(ci, cj)| self.at(ci, cj)).sum::<f64>() / 4.0 > level;
+
+                for &k in &entering {
+                    // Pair with the edge where the contour leaves the higher side
+                    let leaving = if entering.len() == 2 && centre_inside {
+                        (1..4)
+                            .map(|d| (k + 4 - d) % 4)
+                            .find(|&e| states[e] && !states[(e + 1) % 4])
+                    } else {
+                        (1..4)
+                            .map(|d| (k + d) % 4)
+                            .find(|&e| states[e] && !states[(e + 1) % 4])
+                    };
+                    let Some(leaving) = leaving else {
+                        continue;
+                    };
+                    for (e, (a, b)) in [
+                        (k, (corners[k], corners[(k + 1) % 4])),
+                        (leaving, (corners[leaving], corners[(leaving + 1) % 4])),
+                    ] {
+                        crossings
+                            .entry(edges[e])
+                            .or_insert_with(|| self.crossing(a, b, level, dropper));
+                    }
+                    next.insert(edges[k], edges[leaving]);
+                }
+            }
+        }
+
+        let mut contours = Vec::new();
+        while let Some(&start) = next.keys().next() {
+            let mut contour = Vec::new();
+            let mut edge = start;
+            while let Some(following) = next.remove(&edge) {
+                contour.push(crossings[&edge]);
+                edge = following;
+            }
+            if contour.len() >= 3 {
+                contours.push(contour);
+            }
+        }
+        contours
+    }
+
+    /// Point between grid points `a` and `b` where the dropped cutter
+    /// height reaches `level`.
+    fn crossing(
+        &self,
+        a: (usize, usize),
+        b: (usize, usize),
+        level: f64,
+        dropper: &DropCutter,
+    ) -> (f64, f64) {
+        let (pa, pb) = (self.point(a.0, a.1), self.point(b.0, b.1));
+        let (mut ta, mut tb) = (0.0, 1.0);
+        let (mut ha, mut hb) = (self.at(a.0, a.1) - level, self.at(b.0, b.1) - level);
+        let at = |t: f64| (pa.0 + (pb.0 - pa.0) * t, pa.1 + (pb.1 - pa.1) * t);
+        for _ in 0..WATERLINE_REFINE_STEPS {
+            let t = (ta + (tb - ta) * ha / (ha - hb)).clamp(ta, tb);
+            let (x, y) = at(t);
+            let h = dropper.height(x, y) - level;
+            if (h > 0.0) == (ha > 0.0) {
+                ta = t;
+                ha = h;
+            } else {
+                tb = t;
+                hb = h;
+            }
+            if (tb - ta) * self.cell < 1e-4 {
+                break;
+            }
+        }
+        at(ta + (tb - ta) * ha / (ha - hb))
+    }
+}
+
+/// Range along the direction `(cos, sin)` of the line `offset` across it
+/// that lies within the rectangle.
+fn clip_line(
+    offset: f64,
+    sin: f64,
+    cos: f64,
+    (min_x, min_y, max_x, max_y): (f64, f64, f64, f64),
+) -> Option<(f64, f64)> {
+    // Points are s * (cos, sin) + offset * (-sin, cos)
+    let (mut lo, mut hi) = (f64::NEG_INFINITY, f64::INFINITY);
+    for (base, dir, min, max) in [
+        (-offset * sin, cos, min_x, max_x),
+        (offset * cos, sin, min_y, max_y),
+    ] {
+        if dir.abs() < 1e-12 {
+            if base < min - 1e-9 || base > max + 1e-9 {
+                return None;
+            }
+            continue;
+        }
+        let (a, b) = ((min - base) / dir, (max - base) / dir);
+        lo = lo.max(a.min(b));
+        hi = hi.min(a.max(b));
+    }
+    (lo <= hi).then_some((lo, hi))
+}
+
+/// Drops points that lie within `tolerance` of the straight line between
+/// the points kept either side of them.
+pub(crate) fn simplify(points: &[[f64; 3]], tolerance: f64) -> Vec<[f64; 3]> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut kept = vec![points[0]];
+    let mut anchor = 0;
+    for i in 1..points.len() - 1 {
+        // Keep point i when any point since the anchor strays from the
+        // line from the anchor to the point after i
+        let end = points[i + 1];
+        let strays = (anchor + 1..=i)
+            .any(|k| distance_to_segment(points[k], points[anchor], end) > tolerance);
+        if strays {
+            kept.push(points[i]);
+            anchor = i;
+        }
+    }
+    kept.push(points[points.len() - 1]);
+    kept
+}
+
+fn distance_to_segment(p: [f64; 3], a: [f64; 3], b: [f64; 3]) -> f64 {
+    let ab = sub(b, a);
+    let ap = sub(p, a);
+    let len_sq = dot(ab, ab);
+    let t = if len_sq > 1e-18 {
+        (dot(ap, ab) / len_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let d = [ap[0] - ab[0] * t, ap[1] - ab[1] * t, ap[2] - ab[2] * t];
+    dot(d, d).sqrt()
+}
+
+fn in_triangle(tri: &[[f64; 3]; 3], x: f64, y: f64) -> bool {
+    let side = |a: [f64; 3], b: [f64; 3]| (b[0] - a[0]) * (y - a[1]) - (b[1] - a[1]) * (x - a[0]);
+    let (d0, d1, d2) = (
+        side(tri[0], tri[1]),
+        side(tri[1], tri[2]),
+        side(tri[2], tri[0]),
+    );
+    let eps = 1e-12;
+    (d0 >= -eps && d1 >= -eps && d2 >= -eps) || (d0 <= eps && d1 <= eps && d2 <= eps)
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
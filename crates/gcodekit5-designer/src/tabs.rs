@@ -0,0 +1,503 @@
+//! Holding tabs for profile operations.
+//!
+//! Tabs leave small bridges of material on through-cut profiles so parts stay
+//! attached to the stock instead of being thrown by the cutter. Tab positions
+//! are fractions of the length of the shape's drawn outline, so they follow
+//! the shape when it is moved or resized, and are projected onto the
+//! (possibly compensated) toolpath when it is generated. Only the passes that
+//! reach below the top of the tabs are lifted over them.
+
+use crate::model::{DesignerShape, Point, Shape};
+use crate::toolpath::geometry::{
+    closest_on_line, closest_on_segment, cutting_runs, segment_length, segment_z, sub_segment,
+    CLOSE_TOLERANCE,
+};
+use crate::toolpath::{Toolpath, ToolpathSegment, ToolpathSegmentType};
+use lyon::path::iterator::PathIterator;
+use std::f64::consts::PI;
+
+/// Tolerance used to flatten curved outlines.
+const OUTLINE_TOLERANCE: f32 = 0.1;
+
+/// Turn angle above which an outline vertex counts as a corner.
+const CORNER_ANGLE: f64 = PI / 6.0;
+
+/// Cross-section of a holding tab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, Default)]
+pub enum TabShape {
+    /// Square-sided bridge; the cutter lifts straight up and over.
+    #[default]
+    Rectangular,
+    /// Ramped bridge; the cutter climbs over at 45 degrees, which avoids
+    /// plunging and leaves a tab that is easier to break out.
+    Triangular,
+}
+
+impl TabShape {
+    /// Returns the name of the tab shape.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TabShape::Rectangular => "Rectangular",
+            TabShape::Triangular => "Triangular",
+        }
+    }
+}
+
+/// Holding tab settings for a profile.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TabSettings {
+    pub enabled: bool,
+    /// Number of tabs placed automatically on each closed contour.
+    pub count: u32,
+    /// Width of the bridge left on the part, along the contour, in mm.
+    pub width: f64,
+    /// Height of the bridge above the bottom of the cut, in mm.
+    pub height: f64,
+    pub shape: TabShape,
+    /// Manually placed tabs as fractions (0-1) of the outline length.
+    /// Empty when tabs are placed automatically.
+    pub positions: Vec<f64>,
+}
+
+impl Default for TabSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            count: 4,
+            width: 5.0,
+            height: 2.0,
+            shape: TabShape::Rectangular,
+            positions: Vec::new(),
+        }
+    }
+}
+
+impl TabSettings {
+    /// Whether the tabs have been placed by hand.
+    pub fn is_manual(&self) -> bool {
+        !self.positions.is_empty()
+    }
+
+    /// Tab positions along `outline`, placing them automatically unless
+    /// they were placed by hand.
+    pub fn resolve_positions(&self, outline: &Outline) -> Vec<f64> {
+        if self.is_manual() {
+            self.positions.clone()
+        } else {
+            outline.auto_positions(self.count, self.width)
+        }
+    }
+}
+
+/// Points at the centre of each tab on the outline of `shape`.
+pub fn tab_points(shape: &Shape, settings: &TabSettings) -> Vec<Point> {
+    if !settings.enabled {
+        return Vec::new();
+    }
+    let outline = Outline::from_shape(shape);
+    settings
+        .resolve_positions(&outline)
+        .into_iter()
+        .filter_map(|fraction| outline.point_at(fraction))
+        .collect()
+}
+
+/// A shape's outline flattened to polylines, measured along its length.
+#[derive(Debug, Clone, Default)]
+pub struct Outline {
+    contours: Vec<Vec<Point>>,
+    closed: Vec<bool>,
+    lengths: Vec<f64>,
+    total: f64,
+}
+
+impl Outline {
+    /// Flattens the outline of `shape`.
+    pub fn from_shape(shape: &Shape) -> Self {
+        let mut outline = Self::default();
+        let mut current: Vec<Point> = Vec::new();
+        for event in shape.render().iter().flattened(OUTLINE_TOLERANCE) {
+            match event {
+                lyon::path::Event::Begin { at } => {
+                    current = vec![Point::new(at.x as f64, at.y as f64)];
+                }
+                lyon::path::Event::Line { to, .. } => {
+                    current.push(Point::new(to.x as f64, to.y as f64));
+                }
+                lyon::path::Event::End { close, .. } => {
+                    outline.push_contour(std::mem::take(&mut current), close);
+                }
+                _ => {}
+            }
+        }
+        outline
+    }
+
+    fn push_contour(&mut self, mut points: Vec<Point>, close: bool) {
+        if points.len() < 2 {
+            return;
+        }
+        let first = points[0];
+        let meets = points
+            .last()
+            .is_some_and(|last| last.distance_to(&first) <= CLOSE_TOLERANCE);
+        let closed = close || meets;
+        if closed && !meets {
+            points.push(first);
+        }
+        let length = polyline_length(&points);
+        if length <= CLOSE_TOLERANCE {
+            return;
+        }
+        self.total += length;
+        self.contours.push(points);
+        self.closed.push(closed);
+        self.lengths.push(length);
+    }
+
+    /// The flattened contours and whether each is closed. Closed contours
+    /// end with their first point.
+    pub fn contours(&self) -> impl Iterator<Item = (&[Point], bool)> + '_ {
+        self.contours
+            .iter()
+            .map(Vec::as_slice)
+            .zip(self.closed.iter().copied())
+    }
+
+    /// Total length of all contours.
+    pub fn length(&self) -> f64 {
+        self.total
+    }
+
+    /// Point at `fraction` of the total length.
+    pub fn point_at(&self, fraction: f64) -> Option<Point> {
+        if self.total <= 0.0 {
+            return None;
+        }
+        let mut remaining = fraction.clamp(0.0, 1.0) * self.total;
+        for (points, length) in self.contours.iter().zip(&self.lengths) {
+            if remaining <= *length {
+                return Some(point_along(points, remaining));
+            }
+            remaining -= length;
+        }
+        self.contours
+            .last()
+            .and_then(|points| points.last().copied())
+    }
+
+    /// Fraction of the total length at the outline point nearest `p`.
+    pub fn project(&self, p: Point) -> Option<f64> {
+        let mut best: Option<(f64, f64)> = None;
+        let mut offset = 0.0;
+        for (points, length) in self.contours.iter().zip(&self.lengths) {
+            let mut along = 0.0;
+            for pair in points.windows(2) {
+                let (q, t) = closest_on_line(p, pair[0], pair[1]);
+                let seg_len = pair[0].distance_to(&pair[1]);
+                let distance = p.distance_to(&q);
+                if best.is_none_or(|(d, _)| distance < d) {
+                    best = Some((distance, offset + along + t * seg_len));
+                }
+                along += seg_len;
+            }
+            offset += length;
+        }
+        best.map(|(_, s)| s / self.total)
+    }
+
+    /// Evenly spaced tab positions, `count` on each closed contour, moved
+    /// along the contour where needed to keep them clear of corners.
+    pub fn auto_positions(&self, count: u32, width: f64) -> Vec<f64> {
+        let mut positions = Vec::new();
+        if count == 0 || self.total <= 0.0 {
+            return positions;
+        }
+        let mut offset = 0.0;
+        for ((points, length), closed) in self.contours.iter().zip(&self.lengths).zip(&self.closed)
+        {
+            let length = *length;
+            if *closed && length > 2.0 * width {
+                let corners = corner_positions(points);
+                let spacing = length / count as f64;
+                for i in 0..count {
+                    let s = clear_of_corners(
+                        (i as f64 + 0.5) * spacing,
+                        &corners,
+                        width,
+                        spacing / 2.0,
+                        length,
+                    );
+                    positions.push((offset + s) / self.total);
+                }
+            }
+            offset += length;
+        }
+        positions
+    }
+}
+
+/// Lifts the passes of `toolpath` that reach below the top of the tabs over
+/// each tab at `tabs`.
+///
+/// `bottom_z` is the final depth of the cut; tabs stand `settings.height`
+/// above it. Each tab is assigned to the nearest contour of the toolpath
+/// (runs of cutting moves between rapids) within a tool diameter, and the
+/// cutter is lifted for the tab width plus the tool diameter so the bridge
+/// left on the part is `settings.width` wide.
+pub fn apply_tabs(
+    toolpath: &Toolpath,
+    tabs: &[Point],
+    settings: &TabSettings,
+    bottom_z: f64,
+) -> Toolpath {
+    let top_z = bottom_z + settings.height;
+    if !settings.enabled || tabs.is_empty() || settings.height <= 0.0 || settings.width <= 0.0 {
+        return toolpath.clone();
+    }
+    let lowest = toolpath
+        .segments
+        .iter()
+        .filter(|s| s.segment_type != ToolpathSegmentType::RapidMove)
+        .map(|s| {
+            let (a, b) = segment_z(s, toolpath.depth);
+            a.min(b)
+        })
+        .fold(f64::INFINITY, f64::min);
+    if lowest >= top_z - 1e-9 {
+        return toolpath.clone();
+    }
+
+    let runs = cutting_runs(&toolpath.segments);
+    let reach = toolpath.tool_diameter.max(1.0);
+    let mut run_tabs: Vec<Vec<f64>> = vec![Vec::new(); runs.len()];
+    for tab in tabs {
+        let mut best: Option<(f64, usize, f64)> = None;
+        for (run_index, run) in runs.iter().enumerate() {
+            let mut along = 0.0;
+            for seg in &toolpath.segments[run.clone()] {
+                let (q, t) = closest_on_segment(*tab, seg);
+                let distance = tab.distance_to(&q);
+                if best.is_none_or(|(d, _, _)| distance < d) {
+                    best = Some((distance, run_index, along + t * segment_length(seg)));
+                }
+                along += segment_length(seg);
+            }
+        }
+        if let Some((distance, run_index, s)) = best {
+            if distance <= reach {
+                run_tabs[run_index].push(s);
+            }
+        }
+    }
+
+    let profile = TabProfile {
+        half: settings.width / 2.0 + toolpath.tool_diameter / 2.0,
+        ramp: match settings.shape {
+            TabShape::Rectangular => 0.0,
+            TabShape::Triangular => settings
+                .height
+                .min(settings.width / 2.0 + toolpath.tool_diameter / 2.0),
+        },
+        bottom_z,
+        top_z,
+    };
+
+    let mut result = toolpath.clone();
+    result.segments.clear();
+    let mut next = 0;
+    for (run, centers) in runs.iter().zip(&run_tabs) {
+        result
+            .segments
+            .extend(toolpath.segments[next..run.start].iter().cloned());
+        next = run.end;
+        let segments = &toolpath.segments[run.clone()];
+        if centers.is_empty() {
+            result.segments.extend(segments.iter().cloned());
+            continue;
+        }
+        lift_run(
+            &mut result.segments,
+            segments,
+            centers,
+            &profile,
+            toolpath.depth,
+        );
+    }
+    result
+        .segments
+        .extend(toolpath.segments[next..].iter().cloned());
+    result
+}
+
+/// Height of the tabs along a contour.
+struct TabProfile {
+    /// Half the length the cutter is lifted for.
+    half: f64,
+    /// Length of the ramp at each side of a triangular tab; 0 for
+    /// rectangular tabs.
+    ramp: f64,
+    bottom_z: f64,
+    top_z: f64,
+}
+
+impl TabProfile {
+    /// Lowest Z the cutter may reach at `distance` from a tab centre.
+    fn floor(&self, distance: f64) -> Option<f64> {
+        if distance >= self.half {
+            return None;
+        }
+        let from_edge = self.half - distance;
+        if self.ramp <= 0.0 || from_edge >= self.ramp {
+            Some(self.top_z)
+        } else {
+            Some(self.bottom_z + (self.top_z - self.bottom_z) * from_edge / self.ramp)
+        }
+    }
+}
+
+/// Appends one contour, split at the tab edges and lifted over the tabs.
+fn lift_run(
+    out: &mut Vec<ToolpathSegment>,
+    segments: &[ToolpathSegment],
+    centers: &[f64],
+    profile: &TabProfile,
+    depth: f64,
+) {
+    let lengths: Vec<f64> = segments.iter().map(segment_length).collect();
+    let total: f64 = lengths.iter().sum();
+    let closed = match (segments.first(), segments.last()) {
+        (Some(first), Some(last)) => first.start.distance_to(&last.end) <= CLOSE_TOLERANCE,
+        _ => false,
+    };
+    let distance = |s: f64, c: f64| {
+        let d = (s - c).abs();
+        if closed {
+            d.min(total - d)
+        } else {
+            d
+        }
+    };
+    let floor = |s: f64| {
+        centers
+            .iter()
+            .filter_map(|c| profile.floor(distance(s, *c)))
+            .fold(None, |acc: Option<f64>, z| {
+                Some(acc.map_or(z, |a| a.max(z)))
+            })
+    };
+
+    let mut breaks: Vec<f64> = Vec::new();
+    for c in centers {
+        let mut edges = vec![c - profile.half, c + profile.half];
+        if profile.ramp > 0.0 {
+            edges.push(c - profile.half + profile.ramp);
+            edges.push(c + profile.half - profile.ramp);
+        }
+        for e in edges {
+            let e = if closed { e.rem_euclid(total) } else { e };
+            if e > 0.0 && e < total {
+                breaks.push(e);
+            }
+        }
+    }
+    breaks.sort_by(|a, b| a.total_cmp(b));
+
+    let mut along = 0.0;
+    for (seg, length) in segments.iter().zip(&lengths) {
+        let (z0, z1) = segment_z(seg, depth);
+        let mut cuts = vec![0.0];
+        cuts.extend(
+            breaks
+                .iter()
+                .filter(|b| **b > along + 1e-6 && **b < along + length - 1e-6)
+                .map(|b| (b - along) / length),
+        );
+        cuts.push(1.0);
+        for pair in cuts.windows(2) {
+            let (t0, t1) = (pair[0], pair[1]);
+            let mut piece = sub_segment(seg, t0, t1);
+            let (a, b) = (along + t0 * length, along + t1 * length);
+            let (floor_a, floor_b) = if profile.ramp > 0.0 {
+                (floor(a), floor(b))
+            } else {
+                let mid = floor((a + b) / 2.0);
+                (mid, mid)
+            };
+            let za = z0 + (z1 - z0) * t0;
+            let zb = z0 + (z1 - z0) * t1;
+            piece.start_z = Some(floor_a.map_or(za, |f| za.max(f)));
+            piece.z_depth = Some(floor_b.map_or(zb, |f| zb.max(f)));
+            out.push(piece);
+        }
+        along += length;
+    }
+}
+
+fn polyline_length(points: &[Point]) -> f64 {
+    points.windows(2).map(|w| w[0].distance_to(&w[1])).sum()
+}
+
+fn point_along(points: &[Point], distance: f64) -> Point {
+    let mut remaining = distance;
+    for pair in points.windows(2) {
+        let length = pair[0].distance_to(&pair[1]);
+        if remaining <= length && length > 0.0 {
+            let t = remaining / length;
+            return Point::new(
+                pair[0].x + (pair[1].x - pair[0].x) * t,
+                pair[0].y + (pair[1].y - pair[0].y) * t,
+            );
+        }
+        remaining -= length;
+    }
+    points.last().copied().unwrap_or(Point::new(0.0, 0.0))
+}
+
+/// Positions along a closed polyline where it turns sharply.
+fn corner_positions(points: &[Point]) -> Vec<f64> {
+    let n = points.len();
+    let mut corners = Vec::new();
+    if n < 3 {
+        return corners;
+    }
+    let mut along = 0.0;
+    for i in 0..n - 1 {
+        // The closing point repeats the first, so the vertex before the
+        // first is the second to last
+        let prev = if i == 0 { points[n - 2] } else { points[i - 1] };
+        let (here, next) = (points[i], points[i + 1]);
+        let a_in = (here.y - prev.y).atan2(here.x - prev.x);
+        let a_out = (next.y - here.y).atan2(next.x - here.x);
+        let turn = (a_out - a_in + PI).rem_euclid(2.0 * PI) - PI;
+        if turn.abs() > CORNER_ANGLE {
+            corners.push(along);
+        }
+        along += here.distance_to(&next);
+    }
+    corners
+}
+
+/// Moves `s` up to `max_shift` along a closed contour of `length` until it
+/// is at least `width` from every corner. Returns `s` unchanged when there
+/// is no clear position.
+fn clear_of_corners(s: f64, corners: &[f64], width: f64, max_shift: f64, length: f64) -> f64 {
+    let is_clear = |s: f64| {
+        corners.iter().all(|c| {
+            let d = (s - c).rem_euclid(length);
+            d.min(length - d) >= width
+        })
+    };
+    const STEPS: usize = 40;
+    for step in 0..=STEPS {
+        let shift = max_shift * step as f64 / STEPS as f64;
+        for candidate in [s + shift, s - shift] {
+            let candidate = candidate.rem_euclid(length);
+            if is_clear(candidate) {
+                return candidate;
+            }
+        }
+    }
+    s
+}
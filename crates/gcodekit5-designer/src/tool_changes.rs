@@ -0,0 +1,390 @@
+//! Multiple tools per design.
+//!
+//! A drawing object can name a tool from the tool library; objects without
+//! one are cut with the designer's tool settings. Generation groups the
+//! objects by tool and cuts the unassigned objects first (with whatever tool
+//! is in the spindle), then the library tools from largest to smallest, with
+//! a tool-change macro between groups. A tool set to rest machining only cuts
+//! where the larger tools before it could not reach: its toolpath is trimmed
+//! to the moves that touch material not already swept by an earlier cutter
+//! at the same depth or deeper.
+
+use crate::model::Point;
+use crate::toolpath::geometry::{closest_on_segment, segment_length, segment_z, sub_segment};
+use crate::toolpath::{Toolpath, ToolpathSegment, ToolpathSegmentType};
+use std::collections::HashMap;
+
+/// Depth difference below which an earlier cut counts as reaching a later one.
+const Z_TOLERANCE: f64 = 0.01;
+
+/// Number of points around the cutter tested for uncut material.
+const REST_PROBES: usize = 8;
+
+/// Fraction of the cutter radius at which uncut material is probed, so that
+/// material only grazed by the cutter edge is not worth a pass.
+const REST_PROBE_RADIUS: f64 = 0.9;
+
+/// Pieces of a rest-machining path shorter than this are dropped, in mm.
+const MIN_REST_PIECE: f64 = 0.05;
+
+/// A library tool assigned to a drawing object.
+///
+/// The cutting parameters are copied from the library so a design still
+/// generates the same G-code when the library entry changes or is missing.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AssignedTool {
+    /// Tool library identifier.
+    pub id: String,
+    /// Tool number used in the `Tn M6` tool change.
+    pub number: u32,
+    pub name: String,
+    /// Cutting diameter in mm.
+    pub diameter: f64,
+    /// Feed rate in mm/min.
+    pub feed_rate: f64,
+    /// Spindle speed in RPM.
+    pub spindle_speed: u32,
+    /// Only cut where larger tools earlier in the job could not reach.
+    pub rest_machining: bool,
+}
+
+impl Default for AssignedTool {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            number: 1,
+            name: String::new(),
+            diameter: 3.175,
+            feed_rate: 1000.0,
+            spindle_speed: 12000,
+            rest_machining: false,
+        }
+    }
+}
+
+impl AssignedTool {
+    /// Copies a tool from the tool library.
+    pub fn from_library(tool: &gcodekit5_core::data::tools::Tool) -> Self {
+        Self {
+            id: tool.id.0.clone(),
+            number: tool.number,
+            name: tool.name.clone(),
+            diameter: tool.diameter as f64,
+            feed_rate: tool.params.feed_rate as f64,
+            spindle_speed: tool.params.rpm,
+            rest_machining: false,
+        }
+    }
+
+    /// Whether two assignments load the same tool into the spindle.
+    pub fn same_tool(&self, other: &AssignedTool) -> bool {
+        self.id == other.id && self.number == other.number
+    }
+}
+
+/// How tool changes are written to the G-code.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ToolChangeSettings {
+    /// G-code emitted at each tool change. `{tool}`, `{name}`, `{diameter}`,
+    /// `{spindle}` and `{safe_z}` are replaced with the new tool's values.
+    pub macro_template: String,
+}
+
+impl Default for ToolChangeSettings {
+    fn default() -> Self {
+        Self {
+            macro_template: "M5\nG00 Z{safe_z}\nT{tool} M6\nM3 S{spindle}".to_string(),
+        }
+    }
+}
+
+impl ToolChangeSettings {
+    /// The tool-change G-code for `tool`, ending with a newline.
+    pub fn expand(&self, tool: &AssignedTool, safe_z: f64) -> String {
+        let mut gcode = self
+            .macro_template
+            .replace("{tool}", &tool.number.to_string())
+            .replace("{name}", &tool.name)
+            .replace("{diameter}", &format!("{:.3}", tool.diameter))
+            .replace("{spindle}", &tool.spindle_speed.to_string())
+            .replace("{safe_z}", &format!("{:.3}", safe_z));
+        if !gcode.is_empty() && !gcode.ends_with('\n') {
+            gcode.push('\n');
+        }
+        gcode
+    }
+}
+
+/// The objects cut with one tool, in job order.
+#[derive(Debug, Clone)]
+pub struct ToolGroup<T> {
+    /// `None` for objects cut with the designer's tool settings.
+    pub tool: Option<AssignedTool>,
+    pub jobs: Vec<(T, Vec<Toolpath>)>,
+}
+
+/// Groups per-object toolpaths by tool, orders the groups and applies rest
+/// machining.
+///
+/// Objects keep their relative order within a group. Unassigned objects come
+/// first, then tools from the largest diameter to the smallest, ties broken
+/// by tool number. The toolpaths of objects whose tool is set to rest
+/// machining are trimmed against everything cut by the groups before them.
+pub fn group_by_tool<T>(jobs: Vec<(Option<AssignedTool>, T, Vec<Toolpath>)>) -> Vec<ToolGroup<T>> {
+    let mut groups: Vec<ToolGroup<T>> = Vec::new();
+    let mut rest_flags: Vec<Vec<bool>> = Vec::new();
+    for (tool, item, toolpaths) in jobs {
+        let rest = tool.as_ref().is_some_and(|t| t.rest_machining);
+        let index = groups.iter().position(|g| match (&g.tool, &tool) {
+            (None, None) => true,
+            (Some(a), Some(b)) => a.same_tool(b),
+            _ => false,
+        });
+        let index = index.unwrap_or_else(|| {
+            groups.push(ToolGroup {
+                tool: tool.clone(),
+                jobs: Vec::new(),
+            });
+            rest_flags.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[index].jobs.push((item, toolpaths));
+        rest_flags[index].push(rest);
+    }
+
+    let mut order: Vec<usize> = (0..groups.len()).collect();
+    order.sort_by(|&a, &b| match (&groups[a].tool, &groups[b].tool) {
+        (None, None) => std::cmp::Ordering::Equal,
+        (None, Some(_)) => std::cmp::Ordering::Less,
+        (Some(_), None) => std::cmp::Ordering::Greater,
+        (Some(ta), Some(tb)) => tb
+            .diameter
+            .total_cmp(&ta.diameter)
+            .then(ta.number.cmp(&tb.number)),
+    });
+    let mut slots: Vec<Option<(ToolGroup<T>, Vec<bool>)>> =
+        groups.into_iter().zip(rest_flags).map(Some).collect();
+
+    let mut cleared = ClearedArea::default();
+    let mut ordered = Vec::with_capacity(slots.len());
+    for index in order {
+        let Some((mut group, rest)) = slots[index].take() else {
+            continue;
+        };
+        // Trim against earlier groups only, so a group never trims itself
+        if !cleared.is_empty() {
+            for ((_, toolpaths), &is_rest) in group.jobs.iter_mut().zip(&rest) {
+                if !is_rest {
+                    continue;
+                }
+                *toolpaths = toolpaths
+                    .iter()
+                    .map(|tp| cleared.rest_toolpath(tp))
+                    .filter(|tp| !tp.segments.is_empty())
+                    .collect();
+            }
+        }
+        for (_, toolpaths) in &group.jobs {
+            for toolpath in toolpaths {
+                cleared.add(toolpath);
+            }
+        }
+        ordered.push(group);
+    }
+    ordered
+}
+
+/// Keeps only the parts of `toolpath` that cut material not already removed
+/// by `cleared`.
+pub fn apply_rest_machining(toolpath: &Toolpath, cleared: &[Toolpath]) -> Toolpath {
+    let mut area = ClearedArea::default();
+    for tp in cleared {
+        area.add(tp);
+    }
+    area.rest_toolpath(toolpath)
+}
+
+/// A cutting move of an earlier tool and the disc it sweeps.
+struct ClearedMove {
+    segment: ToolpathSegment,
+    radius: f64,
+    /// Shallowest Z along the move; everything above it is cleared.
+    z: f64,
+}
+
+/// Cutting moves of earlier tools, bucketed on a grid for point queries.
+#[derive(Default)]
+struct ClearedArea {
+    moves: Vec<ClearedMove>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl ClearedArea {
+    /// Grid cell size in mm.
+    const CELL: f64 = 2.0;
+
+    fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    fn cell(p: Point) -> (i64, i64) {
+        (
+            (p.x / Self::CELL).floor() as i64,
+            (p.y / Self::CELL).floor() as i64,
+        )
+    }
+
+    fn add(&mut self, toolpath: &Toolpath) {
+        let radius = toolpath.tool_diameter / 2.0;
+        for seg in &toolpath.segments {
+            if seg.segment_type == ToolpathSegmentType::RapidMove {
+                continue;
+            }
+            let (z0, z1) = segment_z(seg, toolpath.depth);
+            let index = self.moves.len();
+            self.moves.push(ClearedMove {
+                segment: seg.clone(),
+                radius,
+                z: z0.max(z1),
+            });
+
+            let (min, max) = swept_bounds(seg, radius);
+            let (c0, c1) = (Self::cell(min), Self::cell(max));
+            for cx in c0.0..=c1.0 {
+                for cy in c0.1..=c1.1 {
+                    self.cells.entry((cx, cy)).or_default().push(index);
+                }
+            }
+        }
+    }
+
+    /// Whether the point `p` has been cut at depth `z` or deeper.
+    fn covers(&self, p: Point, z: f64) -> bool {
+        let Some(candidates) = self.cells.get(&Self::cell(p)) else {
+            return false;
+        };
+        candidates.iter().any(|&i| {
+            let m = &self.moves[i];
+            m.z <= z + Z_TOLERANCE
+                && closest_on_segment(p, &m.segment).0.distance_to(&p) <= m.radius + 1e-6
+        })
+    }
+
+    /// Whether a cutter of `radius` centred at `p` and cutting at `z` would
+    /// remove any material that is left.
+    fn has_material(&self, p: Point, radius: f64, z: f64) -> bool {
+        if !self.covers(p, z) {
+            return true;
+        }
+        let probe = radius * REST_PROBE_RADIUS;
+        (0..REST_PROBES).any(|k| {
+            let angle = 2.0 * std::f64::consts::PI * k as f64 / REST_PROBES as f64;
+            let q = Point::new(p.x + probe * angle.cos(), p.y + probe * angle.sin());
+            !self.covers(q, z)
+        })
+    }
+
+    /// Parameter ranges of `seg` where the cutter still meets material.
+    fn needed_ranges(&self, seg: &ToolpathSegment, radius: f64, depth: f64) -> Vec<(f64, f64)> {
+        let (z0, z1) = segment_z(seg, depth);
+        let length = segment_length(seg);
+        let samples = ((length / (radius / 2.0).max(0.05)).ceil() as usize).max(1);
+        let needed: Vec<bool> = (0..=samples)
+            .map(|i| {
+                let t = i as f64 / samples as f64;
+                let p = sub_segment(seg, 0.0, t).end;
+                self.has_material(p, radius, z0 + (z1 - z0) * t)
+            })
+            .collect();
+
+        let mut ranges: Vec<(f64, f64)> = Vec::new();
+        for i in 0..samples {
+            if !(needed[i] || needed[i + 1]) {
+                continue;
+            }
+            let (t0, t1) = (i as f64 / samples as f64, (i + 1) as f64 / samples as f64);
+            match ranges.last_mut() {
+                Some(last) if (last.1 - t0).abs() < 1e-12 => last.1 = t1,
+                _ => ranges.push((t0, t1)),
+            }
+        }
+        ranges.retain(|&(t0, t1)| (t1 - t0) * length >= MIN_REST_PIECE || length < MIN_REST_PIECE);
+        ranges
+    }
+
+    /// The rest-machining version of `toolpath`.
+    fn rest_toolpath(&self, toolpath: &Toolpath) -> Toolpath {
+        let radius = toolpath.tool_diameter / 2.0;
+        let mut out = Toolpath::new(toolpath.tool_diameter, toolpath.depth);
+        // Where the cutter is, and whether it is still down in the cut there
+        let mut position: Option<Point> = None;
+        let mut in_cut = false;
+        let mut pending_rapid: Option<ToolpathSegment> = None;
+
+        for seg in &toolpath.segments {
+            if seg.segment_type == ToolpathSegmentType::RapidMove {
+                pending_rapid = Some(seg.clone());
+                in_cut = false;
+                continue;
+            }
+
+            let (z0, z1) = segment_z(seg, toolpath.depth);
+            let has_z = seg.start_z.is_some() || seg.z_depth.is_some();
+            for (t0, t1) in self.needed_ranges(seg, radius, toolpath.depth) {
+                let whole = t0 <= 0.0 && t1 >= 1.0;
+                let mut piece = if whole {
+                    seg.clone()
+                } else {
+                    sub_segment(seg, t0, t1)
+                };
+                let joined = in_cut && t0 <= 0.0;
+                if !joined {
+                    // Travel to the piece above the stock and plunge into it
+                    let mut rapid = pending_rapid.take().unwrap_or_else(|| {
+                        ToolpathSegment::new(
+                            ToolpathSegmentType::RapidMove,
+                            piece.start,
+                            piece.start,
+                            seg.feed_rate,
+                            seg.spindle_speed,
+                        )
+                    });
+                    rapid.start = position.unwrap_or(rapid.start);
+                    rapid.end = piece.start;
+                    out.add_segment(rapid);
+                }
+                if has_z && !(whole && joined) {
+                    piece.start_z = Some(z0 + (z1 - z0) * t0);
+                    piece.z_depth = Some(z0 + (z1 - z0) * t1);
+                }
+                position = Some(piece.end);
+                in_cut = t1 >= 1.0;
+                out.add_segment(piece);
+            }
+            if position.is_none_or(|p| p.distance_to(&seg.end) > 1e-9) {
+                in_cut = false;
+            }
+        }
+        out
+    }
+}
+
+/// Bounding box of the area swept by a cutter of `radius` along `seg`.
+fn swept_bounds(seg: &ToolpathSegment, radius: f64) -> (Point, Point) {
+    let mut min = Point::new(seg.start.x.min(seg.end.x), seg.start.y.min(seg.end.y));
+    let mut max = Point::new(seg.start.x.max(seg.end.x), seg.start.y.max(seg.end.y));
+    if let (ToolpathSegmentType::ArcCW | ToolpathSegmentType::ArcCCW, Some(center)) =
+        (seg.segment_type, seg.center)
+    {
+        // The full circle bounds any arc on it
+        let r = seg.start.distance_to(&center);
+        min = Point::new(min.x.min(center.x - r), min.y.min(center.y - r));
+        max = Point::new(max.x.max(center.x + r), max.y.max(center.y + r));
+    }
+    (
+        Point::new(min.x - radius, min.y - radius),
+        Point::new(max.x + radius, max.y + radius),
+    )
+}
@@ -18,6 +18,13 @@ pub struct ToolpathGenerator {
     pocket_strategy: PocketStrategy,
     ramp_angle: f64,
     raster_fill_ratio: f64,
+    cut_side: CutSide,
+    cut_direction: CutDirection,
+    finishing_allowance: f64,
+    tab_settings: TabSettings,
+    tab_points: Vec<Point>,
+    lead_settings: LeadSettings,
+    lead_start: Option<Point>,
 }
 
 impl ToolpathGenerator {
@@ -33,6 +40,13 @@ impl ToolpathGenerator {
             pocket_strategy: PocketStrategy::ContourParallel,
             ramp_angle: 0.0,
             raster_fill_ratio: 0.5,
+            cut_side: CutSide::OnLine,
+            cut_direction: CutDirection::Climb,
+            finishing_allowance: 0.0,
+            tab_settings: TabSettings::default(),
+            tab_points: Vec::new(),
+            lead_settings: LeadSettings::default(),
+            lead_start: None,
         }
     }
 
@@ -55,6 +69,21 @@ impl ToolpathGenerator {
         self.feed_rate = feed_rate;
     }
 
+    /// Feed rate in mm/min.
+    pub fn feed_rate(&self) -> f64 {
+        self.feed_rate
+    }
+
+    /// Spindle speed in RPM.
+    pub fn spindle_speed(&self) -> u32 {
+        self.spindle_speed
+    }
+
+    /// Tool diameter in mm.
+    pub fn tool_diameter(&self) -> f64 {
+        self.tool_diameter
+    }
+
     /// Sets the spindle speed in RPM.
     pub fn set_spindle_speed(&mut self, speed: u32) {
         self.spindle_speed = speed;
@@ -96,6 +125,41 @@ impl ToolpathGenerator {
         self.raster_fill_ratio
     }
 
+    /// Sets which side of the line profile contours are cut on.
+    pub fn set_cut_side(&mut self, side: CutSide) {
+        self.cut_side = side;
+    }
+
+    /// Sets the milling direction for compensated profile contours.
+    pub fn set_cut_direction(&mut self, direction: CutDirection) {
+        self.cut_direction = direction;
+    }
+
+    /// Sets the material left by the roughing passes of a compensated
+    /// profile, removed by a final full-depth pass. Negative values are
+    /// clamped to 0.
+    pub fn set_finishing_allowance(&mut self, allowance: f64) {
+        self.finishing_allowance = if allowance.is_finite() {
+            allowance.max(0.0)
+        } else {
+            0.0
+        };
+    }
+
+    /// Sets the holding tabs for profile contours, centred at `points` on
+    /// the shape's outline. Only passes below the top of the tabs lift over them.
+    pub fn set_tabs(&mut self, settings: TabSettings, points: Vec<Point>) {
+        self.tab_settings = settings;
+        self.tab_points = points;
+    }
+
+    /// Sets lead-in/out moves for profiles, starting closed contours at the
+    /// point nearest `start` when one is given.
+    pub fn set_leads(&mut self, settings: LeadSettings, start: Option<Point>) {
+        self.lead_settings = settings;
+        self.lead_start = start;
+    }
+
     /// Creates an empty toolpath with current settings.
     pub fn empty_toolpath(&self) -> Toolpath {
         Toolpath::new(self.tool_diameter, self.start_depth - self.cut_depth.abs())
@@ -322,7 +386,83 @@ impl ToolpathGenerator {
              */
         }
 
-        self.create_multipass_toolpaths(segments, step_down)
+        self.create_profile_toolpaths(segments, step_down)
+    }
+
+    /// Helper to create the depth passes of a profile contour, offset by the
+    /// tool radius to the cut side. With a finishing allowance the roughing
+    /// passes stay clear of the line by that much and a final pass at full
+    /// depth cuts to size. Holding tabs are left on the deepest passes.
+    fn create_profile_toolpaths(
+        &self,
+        segments: Vec<ToolpathSegment>,
+        step_down: f64,
+    ) -> Vec<Toolpath> {
+        let toolpaths = if self.cut_side == CutSide::OnLine {
+            self.create_multipass_toolpaths(segments, step_down)
+        } else {
+            self.create_compensated_toolpaths(segments, step_down)
+        };
+
+        let toolpaths: Vec<Toolpath> =
+            if self.tab_settings.enabled && !self.tab_points.is_empty() {
+                let bottom_z = self.start_depth - self.cut_depth.abs();
+                toolpaths
+                    .iter()
+                    .map(|tp| apply_tabs(tp, &self.tab_points, &self.tab_settings, bottom_z))
+                    .collect()
+            } else {
+                toolpaths
+            };
+
+        if !self.lead_settings.is_active() {
+            return toolpaths;
+        }
+        // Ramps come down from the floor left by the previous pass
+        let waste_outside = self.cut_side != CutSide::Inside;
+        let mut previous_z = self.start_depth;
+        toolpaths
+            .iter()
+            .map(|tp| {
+                let ramp_top_z = previous_z.max(tp.depth);
+                previous_z = tp.depth;
+                apply_leads(
+                    tp,
+                    &self.lead_settings,
+                    self.lead_start,
+                    waste_outside,
+                    ramp_top_z,
+                )
+            })
+            .collect()
+    }
+
+    /// Helper to create the depth passes of a profile offset to the cut side.
+    fn create_compensated_toolpaths(
+        &self,
+        segments: Vec<ToolpathSegment>,
+        step_down: f64,
+    ) -> Vec<Toolpath> {
+        let radius = self.tool_diameter / 2.0;
+        let roughing = compensate_profile(
+            &segments,
+            self.cut_side,
+            self.cut_direction,
+            radius + self.finishing_allowance,
+        );
+        let mut toolpaths = self.create_multipass_toolpaths(roughing, step_down);
+
+        if self.finishing_allowance > 0.0 {
+            let finishing =
+                compensate_profile(&segments, self.cut_side, self.cut_direction, radius);
+            if let Some(pass) = self
+                .create_multipass_toolpaths(finishing, step_down)
+                .pop()
+            {
+                toolpaths.push(pass);
+            }
+        }
+        toolpaths
     }
 
     /// Helper to create multiple toolpaths from segments based on depth settings
@@ -534,7 +674,7 @@ impl ToolpathGenerator {
          *            self.spindle_speed,
          *        ));
          */
-        self.create_multipass_toolpaths(segments, step_down)
+        self.create_profile_toolpaths(segments, step_down)
     }
 
     // ---
@@ -602,7 +742,7 @@ pub fn generate_ellipse_contour(&self, ellipse: &Ellipse, step_down: f64) -> Vec
         ));
     }
 
-    self.create_multipass_toolpaths(segments, step_down)
+    self.create_profile_toolpaths(segments, step_down)
 }
 
 /// Generates a pocket toolpath for an ellipse.
@@ -669,7 +809,7 @@ pub fn generate_ellipse_pocket(
              */
         ];
 
-        self.create_multipass_toolpaths(segments, step_down)
+        self.create_profile_toolpaths(segments, step_down)
     }
 
     /// Generates a contour toolpath for a polyline (with bulges for arcs)
@@ -757,7 +897,7 @@ pub fn generate_ellipse_pocket(
             }
         }
 
-        self.create_multipass_toolpaths(segments, step_down)
+        self.create_profile_toolpaths(segments, step_down)
     }
 
     /// Converts a DXF bulge to an arc and steering center
@@ -989,7 +1129,7 @@ pub fn generate_ellipse_pocket(
             self.spindle_speed,
         ));
 
-        self.create_multipass_toolpaths(segments, step_down)
+        self.create_profile_toolpaths(segments, step_down)
     }
 
     /// Generates a contour toolpath for a polygon.
@@ -1040,7 +1180,7 @@ pub fn generate_ellipse_pocket(
             ));
         }
 
-        self.create_multipass_toolpaths(segments, step_down)
+        self.create_profile_toolpaths(segments, step_down)
     }
 
     /// Generates a pocket toolpath for a triangle.
@@ -1251,7 +1391,7 @@ pub fn generate_ellipse_pocket(
             }
         }
 
-        self.create_multipass_toolpaths(segments, step_down)
+        self.create_profile_toolpaths(segments, step_down)
     }
 
     fn try_convert_quadratic_to_arc(&self, start: Point, end: Point, ctrl: Point) -> Option<(Point, bool)> {
@@ -1698,7 +1838,7 @@ pub fn generate_ellipse_pocket(
     /// Generates a contour (profile) toolpath for text.
     pub fn generate_text_toolpath(&self, text_shape: &TextShape, step_down: f64) -> Vec<Toolpath> {
         let segments = self.build_text_outline_segments(text_shape);
-        self.create_multipass_toolpaths(segments, step_down)
+        self.create_profile_toolpaths(segments, step_down)
     }
 
     /// Generates a contour toolpath for a gear.
@@ -1744,6 +1884,125 @@ pub fn generate_ellipse_pocket(
         let path_shape = PathShape::from_lyon_path(&path);
         self.generate_path_pocket(&path_shape, pocket_depth, step_down, step_in)
     }
+
+    /// Generates a V-carve of a shape's closed outlines.
+    /// The V-bit follows the medial axis, going deeper where the shape is wider. The tool
+    /// diameter is the bit's diameter and the cut depth limits how deep it goes, so areas
+    /// wider than either allows are only outlined at full depth.
+    pub fn generate_vcarve(
+        &self,
+        shape: &Shape,
+        settings: &VCarveSettings,
+        step_down: f64,
+    ) -> Vec<Toolpath> {
+        let rings: Vec<Vec<Point>> = match shape {
+            Shape::Text(text) => {
+                contours_from_outline_segments(&self.build_text_outline_segments(text))
+            }
+            _ => Outline::from_shape(shape)
+                .contours()
+                .filter(|(_, closed)| *closed)
+                .map(|(points, _)| points.to_vec())
+                .collect(),
+        };
+
+        let max_depth = self.cut_depth.abs();
+        let tool = VBitTool::new(settings.tip_angle, self.tool_diameter, max_depth);
+        if rings.is_empty() || !tool.is_valid() {
+            return Vec::new();
+        }
+        let max_radius = tool.radius_at_depth(max_depth).min(self.tool_diameter / 2.0);
+
+        // Sample finely, but keep the boundary to a few thousand points
+        let perimeter: f64 = rings
+            .iter()
+            .flat_map(|ring| ring.windows(2))
+            .map(|w| w[0].distance_to(&w[1]))
+            .sum();
+        let spacing = (perimeter / 3000.0).max(0.05);
+        let chains: Vec<Vec<MedialPoint>> = medial_axis(&rings, spacing, max_radius)
+            .iter()
+            .map(|chain| simplify_chain(chain, spacing / 4.0))
+            .collect();
+
+        let deepest = chains
+            .iter()
+            .flatten()
+            .map(|p| tool.calculate_depth(2.0 * p.radius))
+            .fold(0.0, f64::max)
+            .min(max_depth);
+        if deepest <= 1e-9 {
+            return Vec::new();
+        }
+        let passes = if step_down > 0.0 {
+            (deepest / step_down).ceil().max(1.0) as usize
+        } else {
+            1
+        };
+
+        let mut toolpaths = Vec::new();
+        for pass in 1..=passes {
+            let floor = self.start_depth - (deepest * pass as f64 / passes as f64);
+            let mut toolpath = Toolpath::new(self.tool_diameter, floor);
+            let mut last = Point::new(0.0, 0.0);
+            for chain in &chains {
+                let z = |p: &MedialPoint| {
+                    (self.start_depth - tool.calculate_depth(2.0 * p.radius)).max(floor)
+                };
+                toolpath.add_segment(ToolpathSegment::new(
+                    ToolpathSegmentType::RapidMove,
+                    last,
+                    chain[0].center,
+                    self.feed_rate,
+                    self.spindle_speed,
+                ));
+                for pair in chain.windows(2) {
+                    let mut segment = ToolpathSegment::new(
+                        ToolpathSegmentType::LinearMove,
+                        pair[0].center,
+                        pair[1].center,
+                        self.feed_rate,
+                        self.spindle_speed,
+                    );
+                    segment.start_z = Some(z(&pair[0]));
+                    segment.z_depth = Some(z(&pair[1]));
+                    toolpath.add_segment(segment);
+                }
+                last = chain[chain.len() - 1].center;
+            }
+            toolpaths.push(toolpath);
+        }
+        toolpaths
+    }
+
+    /// Generates a drilling toolpath for a shape, pecking if the settings ask for it.
+    /// Circles are drilled at their centre; other shapes at the centre of their bounds.
+    pub fn generate_drill(&self, shape: &Shape, settings: &DrillSettings) -> Vec<Toolpath> {
+        let (center, hole_diameter) = match shape {
+            Shape::Circle(circle) => (circle.center, circle.radius * 2.0),
+            _ => {
+                let (x1, y1, x2, y2) = shape.bounds();
+                (Point::new((x1 + x2) / 2.0, (y1 + y2) / 2.0), self.tool_diameter)
+            }
+        };
+
+        let mut operation = DrillOperation::new(
+            "designer".to_string(),
+            hole_diameter.max(self.tool_diameter),
+            self.tool_diameter,
+            -self.cut_depth.abs(),
+        );
+        operation.set_parameters(self.feed_rate, self.feed_rate, self.spindle_speed);
+        if settings.peck_depth > 0.0 {
+            operation.set_peck_drilling(settings.peck_depth);
+        }
+
+        vec![DrillingPatternGenerator::new(operation).generate_peck_toolpath(
+            &[center],
+            self.start_depth,
+            settings.peck_retract,
+        )]
+    }
 }
 
 fn contours_from_outline_segments(segments: &[ToolpathSegment]) -> Vec<Vec<Point>> {
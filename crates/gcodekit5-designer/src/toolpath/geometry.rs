@@ -0,0 +1,124 @@
+//! Geometry helpers shared by toolpath post-processing (tabs, leads).
+
+use super::*;
+use std::f64::consts::PI;
+
+/// Distance below which a contour's end is taken to meet its start.
+pub(crate) const CLOSE_TOLERANCE: f64 = 1e-3;
+
+/// Index ranges of the runs of cutting moves between rapids.
+pub(crate) fn cutting_runs(segments: &[ToolpathSegment]) -> Vec<std::ops::Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, seg) in segments.iter().enumerate() {
+        match (seg.segment_type == ToolpathSegmentType::RapidMove, start) {
+            (true, Some(s)) => {
+                runs.push(s..i);
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        runs.push(s..segments.len());
+    }
+    runs
+}
+
+/// Start and end Z of a cutting segment, as the G-code generator reads them.
+pub(crate) fn segment_z(seg: &ToolpathSegment, depth: f64) -> (f64, f64) {
+    match (seg.start_z, seg.z_depth) {
+        (Some(start), Some(end)) => (start, end),
+        (Some(start), None) => (start, start),
+        (None, Some(end)) => (end, end),
+        (None, None) => (depth, depth),
+    }
+}
+
+/// Start angle and signed sweep of an arc segment.
+pub(crate) fn arc_angles(seg: &ToolpathSegment, center: Point) -> (f64, f64) {
+    let a0 = (seg.start.y - center.y).atan2(seg.start.x - center.x);
+    let a1 = (seg.end.y - center.y).atan2(seg.end.x - center.x);
+    let ccw = seg.segment_type == ToolpathSegmentType::ArcCCW;
+    let mut sweep = if ccw { a1 - a0 } else { a0 - a1 };
+    while sweep <= 1e-9 {
+        sweep += 2.0 * PI;
+    }
+    (a0, if ccw { sweep } else { -sweep })
+}
+
+pub(crate) fn arc_center(seg: &ToolpathSegment) -> Option<Point> {
+    match seg.segment_type {
+        ToolpathSegmentType::ArcCW | ToolpathSegmentType::ArcCCW => seg.center,
+        _ => None,
+    }
+}
+
+pub(crate) fn segment_length(seg: &ToolpathSegment) -> f64 {
+    match arc_center(seg) {
+        Some(center) => {
+            let (_, sweep) = arc_angles(seg, center);
+            seg.start.distance_to(&center) * sweep.abs()
+        }
+        None => seg.start.distance_to(&seg.end),
+    }
+}
+
+/// The part of `seg` between parameters `t0` and `t1`.
+pub(crate) fn sub_segment(seg: &ToolpathSegment, t0: f64, t1: f64) -> ToolpathSegment {
+    let mut piece = seg.clone();
+    match arc_center(seg) {
+        Some(center) => {
+            let (a0, sweep) = arc_angles(seg, center);
+            let r = seg.start.distance_to(&center);
+            let at = |t: f64| {
+                let angle = a0 + sweep * t;
+                Point::new(center.x + r * angle.cos(), center.y + r * angle.sin())
+            };
+            piece.start = at(t0);
+            piece.end = at(t1);
+        }
+        None => {
+            let at = |t: f64| {
+                Point::new(
+                    seg.start.x + (seg.end.x - seg.start.x) * t,
+                    seg.start.y + (seg.end.y - seg.start.y) * t,
+                )
+            };
+            piece.start = at(t0);
+            piece.end = at(t1);
+        }
+    }
+    piece
+}
+
+/// Closest point to `p` on `seg`, with its parameter along the segment.
+pub(crate) fn closest_on_segment(p: Point, seg: &ToolpathSegment) -> (Point, f64) {
+    let Some(center) = arc_center(seg) else {
+        return closest_on_line(p, seg.start, seg.end);
+    };
+    let (a0, sweep) = arc_angles(seg, center);
+    let r = seg.start.distance_to(&center);
+    let angle = (p.y - center.y).atan2(p.x - center.x);
+    let delta = ((angle - a0) * sweep.signum()).rem_euclid(2.0 * PI);
+    if delta <= sweep.abs() {
+        let q = Point::new(center.x + r * angle.cos(), center.y + r * angle.sin());
+        return (q, delta / sweep.abs());
+    }
+    if p.distance_to(&seg.start) <= p.distance_to(&seg.end) {
+        (seg.start, 0.0)
+    } else {
+        (seg.end, 1.0)
+    }
+}
+
+pub(crate) fn closest_on_line(p: Point, a: Point, b: Point) -> (Point, f64) {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len_sq = dx * dx + dy * dy;
+    if len_sq <= f64::EPSILON {
+        return (a, 0.0);
+    }
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / len_sq).clamp(0.0, 1.0);
+    (Point::new(a.x + dx * t, a.y + dy * t), t)
+}
@@ -1,14 +1,20 @@
 //! Toolpath generation from design shapes.
 
 mod generator;
+pub(crate) mod geometry;
 mod segment;
 
+use super::drilling_patterns::{DrillOperation, DrillSettings, DrillingPatternGenerator};
+use super::leads::{apply_leads, LeadSettings};
 use super::pocket_operations::{PocketGenerator, PocketOperation, PocketStrategy};
+use super::profile_operations::{compensate_profile, CutDirection, CutSide};
+use super::tabs::{apply_tabs, Outline, TabSettings};
+use super::vcarve::{medial_axis, simplify_chain, MedialPoint, VBitTool, VCarveSettings};
 use crate::font_manager;
 use crate::model::{
     rotate_point, DesignCircle as Circle, DesignGear, DesignLine as Line, DesignPath as PathShape,
     DesignPolygon as Polygon, DesignRectangle as Rectangle, DesignSprocket,
-    DesignText as TextShape, DesignTriangle as Triangle, DesignerShape, Point,
+    DesignText as TextShape, DesignTriangle as Triangle, DesignerShape, Point, Shape,
 };
 
 pub use generator::ToolpathGenerator;
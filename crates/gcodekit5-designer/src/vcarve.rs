@@ -12,6 +12,7 @@
 //! - Path offset for tool diameter compensation
 //! - Multi-pass cutting for deeper designs
 //! - Toolpath generation and optimization
+//! - Medial-axis carving of closed shapes, where the depth follows the width
 
 use crate::Point;
 use anyhow::Result;
@@ -297,3 +298,290 @@ impl VCarveGenerator {
         Ok(())
     }
 }
+
+/// V-carve settings for a designer shape. The V-bit's diameter is the
+/// current tool diameter, and the carve never goes deeper than the shape's
+/// cut depth.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct VCarveSettings {
+    /// Included angle of the V-bit in degrees
+    pub tip_angle: f64,
+}
+
+impl Default for VCarveSettings {
+    fn default() -> Self {
+        Self { tip_angle: 90.0 }
+    }
+}
+
+/// A point on the medial axis with the radius of the largest circle centred
+/// there that still fits inside the shape.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MedialPoint {
+    pub center: Point,
+    pub radius: f64,
+}
+
+/// Consecutive circles further apart than this many sample spacings (after
+/// allowing for their change in radius) are on different branches.
+const BRANCH_JUMP: f64 = 3.0;
+
+/// Approximates the medial axis of the region bounded by the closed `rings`
+/// using the shrinking-ball method. Rings nested inside another ring are
+/// holes.
+///
+/// The boundary is sampled every `spacing`; for each sample the largest
+/// empty circle touching the boundary there from inside is found, capped at
+/// `max_radius`. The circle centres are returned as chains in boundary
+/// order, split where the axis branches.
+pub fn medial_axis(rings: &[Vec<Point>], spacing: f64, max_radius: f64) -> Vec<Vec<MedialPoint>> {
+    let rings: Vec<Vec<Point>> = rings.iter().filter_map(|r| clean_ring(r)).collect();
+    if rings.is_empty() || spacing <= 0.0 || max_radius <= 0.0 {
+        return Vec::new();
+    }
+    let fan_step = (spacing / max_radius).clamp(0.02, 0.25);
+
+    let sampled: Vec<Vec<(Point, (f64, f64))>> = rings
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            let depth = rings
+                .iter()
+                .enumerate()
+                .filter(|(j, other)| *j != i && contains(other, ring[0]))
+                .count();
+            let inward_left = (signed_area(ring) > 0.0) == (depth % 2 == 0);
+            sample_ring(ring, inward_left, spacing, fan_step)
+        })
+        .collect();
+    let boundary: Vec<Point> = sampled.iter().flatten().map(|(p, _)| *p).collect();
+
+    let mut chains = Vec::new();
+    for samples in &sampled {
+        let points: Vec<MedialPoint> = samples
+            .iter()
+            .map(|(p, n)| {
+                let radius = inscribed_radius(*p, *n, &boundary, max_radius);
+                MedialPoint {
+                    center: Point::new(p.x + n.0 * radius, p.y + n.1 * radius),
+                    radius,
+                }
+            })
+            .collect();
+        chains.extend(split_branches(points, spacing));
+    }
+    chains
+}
+
+/// Removes points of `chain` that lie within `tolerance` of the straight
+/// line between their neighbours, treating the radius as a third axis.
+pub fn simplify_chain(chain: &[MedialPoint], tolerance: f64) -> Vec<MedialPoint> {
+    if chain.len() < 3 {
+        return chain.to_vec();
+    }
+    let mut out = vec![chain[0]];
+    let mut anchor = 0;
+    for i in 1..chain.len() - 1 {
+        // Keep the point if dropping it would move any skipped point too far
+        let next = chain[i + 1];
+        let fits = chain[anchor + 1..=i]
+            .iter()
+            .all(|p| deviation(chain[anchor], next, *p) <= tolerance);
+        if !fits {
+            out.push(chain[i]);
+            anchor = i;
+        }
+    }
+    out.push(chain[chain.len() - 1]);
+    out
+}
+
+/// Distance of `p` from the segment `a`-`b` in (x, y, radius) space.
+fn deviation(a: MedialPoint, b: MedialPoint, p: MedialPoint) -> f64 {
+    let d = (
+        b.center.x - a.center.x,
+        b.center.y - a.center.y,
+        b.radius - a.radius,
+    );
+    let v = (
+        p.center.x - a.center.x,
+        p.center.y - a.center.y,
+        p.radius - a.radius,
+    );
+    let len2 = d.0 * d.0 + d.1 * d.1 + d.2 * d.2;
+    let t = if len2 > 0.0 {
+        ((v.0 * d.0 + v.1 * d.1 + v.2 * d.2) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let e = (v.0 - d.0 * t, v.1 - d.1 * t, v.2 - d.2 * t);
+    (e.0 * e.0 + e.1 * e.1 + e.2 * e.2).sqrt()
+}
+
+/// Drops the closing duplicate and zero-length edges of a ring.
+fn clean_ring(ring: &[Point]) -> Option<Vec<Point>> {
+    let mut points: Vec<Point> = Vec::with_capacity(ring.len());
+    for p in ring {
+        if points.last().is_none_or(|last| last.distance_to(p) > 1e-9) {
+            points.push(*p);
+        }
+    }
+    while let (Some(first), Some(last)) = (points.first(), points.last()) {
+        if points.len() <= 1 || first.distance_to(last) > 1e-9 {
+            break;
+        }
+        points.pop();
+    }
+    (points.len() >= 3 && signed_area(&points).abs() > 1e-12).then_some(points)
+}
+
+/// Boundary samples with their inward unit normals. Concave corners are
+/// fanned so the circle centres sweep round them instead of jumping.
+fn sample_ring(
+    ring: &[Point],
+    inward_left: bool,
+    spacing: f64,
+    fan_step: f64,
+) -> Vec<(Point, (f64, f64))> {
+    let n = ring.len();
+    let normal = |a: Point, b: Point| {
+        let len = a.distance_to(&b);
+        let (dx, dy) = ((b.x - a.x) / len, (b.y - a.y) / len);
+        if inward_left {
+            (-dy, dx)
+        } else {
+            (dy, -dx)
+        }
+    };
+
+    let mut samples = Vec::new();
+    for i in 0..n {
+        let (prev, here, next) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+        let n_in = normal(prev, here);
+        let n_out = normal(here, next);
+        let turn = n_in.0 * n_out.1 - n_in.1 * n_out.0;
+        let dot = n_in.0 * n_out.0 + n_in.1 * n_out.1;
+        // The outgoing edge heads away from the inside at a concave corner
+        let concave = (next.x - here.x) * n_in.0 + (next.y - here.y) * n_in.1 < -1e-12;
+        if concave {
+            let angle = turn.atan2(dot);
+            let steps = (angle.abs() / fan_step).ceil().max(1.0) as usize;
+            for k in 0..=steps {
+                let a = angle * k as f64 / steps as f64;
+                let (sin, cos) = a.sin_cos();
+                samples.push((
+                    here,
+                    (n_in.0 * cos - n_in.1 * sin, n_in.0 * sin + n_in.1 * cos),
+                ));
+            }
+        } else {
+            let (x, y) = (n_in.0 + n_out.0, n_in.1 + n_out.1);
+            let len = x.hypot(y);
+            samples.push((
+                here,
+                if len > 1e-9 {
+                    (x / len, y / len)
+                } else {
+                    n_out
+                },
+            ));
+        }
+
+        let steps = (here.distance_to(&next) / spacing).ceil() as usize;
+        for j in 1..steps {
+            let t = j as f64 / steps as f64;
+            samples.push((
+                Point::new(
+                    here.x + (next.x - here.x) * t,
+                    here.y + (next.y - here.y) * t,
+                ),
+                n_out,
+            ));
+        }
+    }
+    samples
+}
+
+/// Shrinks a circle touching the boundary at `p` with inward normal `n`
+/// until no boundary sample lies inside it.
+fn inscribed_radius(p: Point, n: (f64, f64), boundary: &[Point], max_radius: f64) -> f64 {
+    let mut radius = max_radius;
+    for _ in 0..64 {
+        let center = Point::new(p.x + n.0 * radius, p.y + n.1 * radius);
+        let limit = radius * radius * (1.0 - 1e-9);
+        let nearest = boundary
+            .iter()
+            .map(|q| ((q.x - center.x).powi(2) + (q.y - center.y).powi(2), q))
+            .filter(|(d2, _)| *d2 < limit)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let Some((_, q)) = nearest else {
+            break;
+        };
+        // Circle through q tangent to the boundary at p
+        let (dx, dy) = (q.x - p.x, q.y - p.y);
+        let along = 2.0 * (dx * n.0 + dy * n.1);
+        if along <= 1e-12 {
+            break;
+        }
+        let next = (dx * dx + dy * dy) / along;
+        if next >= radius {
+            break;
+        }
+        radius = next;
+    }
+    radius
+}
+
+/// Splits a ring's circle centres where consecutive circles jump to another
+/// branch of the axis. Unbroken rings are closed back to their start.
+fn split_branches(points: Vec<MedialPoint>, spacing: f64) -> Vec<Vec<MedialPoint>> {
+    let n = points.len();
+    let limit = (BRANCH_JUMP * spacing).powi(2);
+    let breaks = |i: usize| {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        let dc2 = (a.center.x - b.center.x).powi(2) + (a.center.y - b.center.y).powi(2);
+        dc2 - (a.radius - b.radius).powi(2) > limit
+    };
+
+    let Some(first_break) = (0..n).find(|&i| breaks(i)) else {
+        let mut closed = points.clone();
+        closed.extend(points.first().copied());
+        return vec![closed];
+    };
+
+    let mut chains = Vec::new();
+    let mut current = Vec::new();
+    for k in 1..=n {
+        let i = (first_break + k) % n;
+        current.push(points[i]);
+        if breaks(i) {
+            chains.push(std::mem::take(&mut current));
+        }
+    }
+    chains.retain(|c| c.len() > 1);
+    chains
+}
+
+fn signed_area(ring: &[Point]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+fn contains(ring: &[Point], p: Point) -> bool {
+    let n = ring.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (a, b) = (ring[i], ring[(i + 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+            inside = !inside;
+        }
+    }
+    inside
+}
@@ -1,7 +1,10 @@
 // Designer state manager integration tests
 
 use gcodekit5_designer::model::DesignerShape;
-use gcodekit5_designer::{DesignerState, DrawingMode, PathShape, Point};
+use gcodekit5_designer::{
+    AssignedTool, DesignerState, DogboneSettings, DogboneStyle, DrawingMode, NestSettings,
+    PathShape, Point,
+};
 
 #[test]
 fn test_designer_state_complete_workflow() {
@@ -148,3 +151,103 @@ fn test_designer_state_polyline_update() {
     // Note: The position/size update behavior for polylines may differ from rectangles
     // This test verifies the API works, not the exact positioning behavior
 }
+
+#[test]
+fn test_designer_state_tool_changes() {
+    let mut state = DesignerState::new();
+    let small = state.canvas.add_rectangle(0.0, 0.0, 20.0, 20.0);
+    let big = state.canvas.add_rectangle(40.0, 0.0, 20.0, 20.0);
+
+    state.canvas.select_shape(small, false);
+    state.set_selected_tool(Some(AssignedTool {
+        id: "small".to_string(),
+        number: 2,
+        name: "1mm End Mill".to_string(),
+        diameter: 1.0,
+        ..Default::default()
+    }));
+    state.canvas.select_shape(big, false);
+    state.set_selected_tool(Some(AssignedTool {
+        id: "big".to_string(),
+        number: 1,
+        name: "6mm End Mill".to_string(),
+        diameter: 6.0,
+        ..Default::default()
+    }));
+
+    let gcode = state.generate_gcode();
+    let first = gcode.find("T1 M6").expect("missing change to T1");
+    let second = gcode.find("T2 M6").expect("missing change to T2");
+    assert!(first < second, "larger tool should cut first");
+    assert_eq!(gcode.matches(" M6").count(), 2);
+}
+
+#[test]
+fn test_designer_state_cut_order() {
+    let mut state = DesignerState::new();
+    state.canvas.add_rectangle(0.0, 0.0, 10.0, 10.0);
+    state.canvas.add_rectangle(200.0, 0.0, 10.0, 10.0);
+    state.canvas.add_rectangle(20.0, 0.0, 10.0, 10.0);
+    state.canvas.add_rectangle(220.0, 0.0, 10.0, 10.0);
+
+    let gcode = state.generate_gcode();
+    assert!(gcode.contains("; Cut order optimized"));
+    assert_eq!(state.order_report.jobs, 4);
+    assert!(state.order_report.saved() > 0.0);
+
+    state.set_optimize_order(false);
+    let gcode = state.generate_gcode();
+    assert!(!gcode.contains("; Cut order optimized"));
+    assert_eq!(state.order_report.jobs, 0);
+}
+
+#[test]
+fn test_designer_state_dogbone() {
+    let mut state = DesignerState::new();
+    let id = state.canvas.add_rectangle(0.0, 0.0, 40.0, 20.0);
+    state.canvas.select_shape(id, false);
+
+    state.set_dogbone_selected(DogboneSettings {
+        enabled: true,
+        style: DogboneStyle::TBone,
+        tool_diameter: 6.0,
+        max_angle: 120.0,
+    });
+    let dogbone = &state.canvas.get_shape(id).unwrap().dogbone;
+    assert!(dogbone.is_active());
+    assert_eq!(dogbone.style, DogboneStyle::TBone);
+
+    let gcode = state.generate_gcode();
+    assert!(gcode.contains("; Corner relief: T-Bone, Tool: 6.000mm"));
+
+    state.undo();
+    assert!(!state.canvas.get_shape(id).unwrap().dogbone.is_active());
+}
+
+#[test]
+fn test_designer_state_nest_selected() {
+    let mut state = DesignerState::new();
+    let square = state.canvas.add_rectangle(500.0, 500.0, 80.0, 80.0);
+    let circle = state.canvas.add_circle(Point::new(-100.0, -100.0), 15.0);
+    state.canvas.select_shape(square, false);
+    state.canvas.select_shape(circle, true);
+    assert_eq!(state.selected_nest_parts().len(), 2);
+
+    let result = state
+        .nest_selected(&[3, 2], &NestSettings::default())
+        .unwrap();
+    assert_eq!(result.placed(), 5);
+    assert_eq!(result.sheets.len(), 1);
+    assert_eq!(state.canvas.shape_count(), 5);
+
+    // Everything lands on the 200 x 200 stock, inside the 10mm margin
+    for obj in state.canvas.shapes() {
+        let (x1, y1, x2, y2) = obj.shape.bounds();
+        assert!(x1 >= 9.9 && y1 >= 9.9 && x2 <= 190.1 && y2 <= 190.1);
+    }
+
+    state.undo();
+    assert_eq!(state.canvas.shape_count(), 2);
+    let (x1, y1, ..) = state.canvas.get_shape(square).unwrap().shape.bounds();
+    assert!((x1 - 500.0).abs() < 1e-6 && (y1 - 500.0).abs() < 1e-6);
+}
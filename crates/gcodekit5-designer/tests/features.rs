@@ -2,20 +2,40 @@
 mod adaptive;
 #[path = "features/arrays.rs"]
 mod arrays;
+#[path = "features/common.rs"]
+mod common;
+#[path = "features/dogbone.rs"]
+mod dogbone;
 #[path = "features/drilling_patterns.rs"]
 mod drilling_patterns;
 #[path = "features/gcode_snapshots.rs"]
 mod gcode_snapshots;
+#[path = "features/job_order.rs"]
+mod job_order;
+#[path = "features/leads.rs"]
+mod leads;
 #[path = "features/multipass.rs"]
 mod multipass;
+#[path = "features/nesting.rs"]
+mod nesting;
 #[path = "features/parametric.rs"]
 mod parametric;
 #[path = "features/parser_fuzz.rs"]
 mod parser_fuzz;
 #[path = "features/pocket_operations.rs"]
 mod pocket_operations;
+#[path = "features/profile_operations.rs"]
+mod profile_operations;
+#[path = "features/relief.rs"]
+mod relief;
+#[path = "features/surface_finishing.rs"]
+mod surface_finishing;
+#[path = "features/tabs.rs"]
+mod tabs;
 #[path = "features/templates.rs"]
 mod templates;
+#[path = "features/tool_changes.rs"]
+mod tool_changes;
 #[path = "features/tool_library.rs"]
 mod tool_library;
 #[path = "features/toolpath_shape_combos.rs"]
@@ -0,0 +1,51 @@
+//! Toolpath builders shared by the feature tests
+
+use gcodekit5_designer::toolpath::Toolpath;
+use gcodekit5_designer::{Point, ToolpathSegment, ToolpathSegmentType};
+
+/// Appends a rapid to the first corner and a closed loop through `corners`.
+pub fn add_loop(toolpath: &mut Toolpath, corners: &[(f64, f64)]) {
+    toolpath.add_segment(segment(
+        ToolpathSegmentType::RapidMove,
+        (0.0, 0.0),
+        corners[0],
+        100.0,
+        1000,
+    ));
+    for i in 0..corners.len() {
+        toolpath.add_segment(segment(
+            ToolpathSegmentType::LinearMove,
+            corners[i],
+            corners[(i + 1) % corners.len()],
+            100.0,
+            1000,
+        ));
+    }
+}
+
+/// A 40 x 20 counter-clockwise rectangular profile cut at `depth` with a 2mm tool.
+pub fn rectangle_pass(depth: f64) -> Toolpath {
+    let mut toolpath = Toolpath::new(2.0, depth);
+    add_loop(
+        &mut toolpath,
+        &[(0.0, 0.0), (40.0, 0.0), (40.0, 20.0), (0.0, 20.0)],
+    );
+    toolpath
+}
+
+/// A straight segment from `from` to `to`.
+pub fn segment(
+    kind: ToolpathSegmentType,
+    from: (f64, f64),
+    to: (f64, f64),
+    feed: f64,
+    spindle: u32,
+) -> ToolpathSegment {
+    ToolpathSegment::new(
+        kind,
+        Point::new(from.0, from.1),
+        Point::new(to.0, to.1),
+        feed,
+        spindle,
+    )
+}
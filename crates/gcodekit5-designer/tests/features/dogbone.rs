@@ -0,0 +1,157 @@
+use gcodekit5_designer::canvas::DrawingObject;
+use gcodekit5_designer::dogbone::{relief_centres, DogboneSettings, DogboneStyle};
+use gcodekit5_designer::model::DesignerShape;
+use gcodekit5_designer::profile_operations::CutSide;
+use gcodekit5_designer::shapes::OperationType;
+use gcodekit5_designer::{Point, Rectangle, Shape};
+
+fn settings(style: DogboneStyle) -> DogboneSettings {
+    DogboneSettings {
+        enabled: true,
+        style,
+        tool_diameter: 6.0,
+        max_angle: 120.0,
+    }
+}
+
+fn ring(points: &[(f64, f64)]) -> Vec<Point> {
+    points.iter().map(|&(x, y)| Point::new(x, y)).collect()
+}
+
+fn contains(centres: &[Point], x: f64, y: f64) -> bool {
+    centres
+        .iter()
+        .any(|c| c.distance_to(&Point::new(x, y)) < 1e-6)
+}
+
+/// A 40 x 20 rectangle, counter-clockwise.
+fn rectangle() -> Vec<Point> {
+    ring(&[(0.0, 0.0), (40.0, 0.0), (40.0, 20.0), (0.0, 20.0)])
+}
+
+/// An L-shaped part with its one concave corner at (20, 10).
+fn l_shape() -> Vec<Point> {
+    ring(&[
+        (0.0, 0.0),
+        (40.0, 0.0),
+        (40.0, 10.0),
+        (20.0, 10.0),
+        (20.0, 30.0),
+        (0.0, 30.0),
+    ])
+}
+
+#[test]
+fn test_pocket_relieves_every_corner_along_bisector() {
+    let centres = relief_centres(&rectangle(), false, true, &settings(DogboneStyle::Dogbone));
+    assert_eq!(centres.len(), 4);
+
+    let d = 3.0 / 2f64.sqrt();
+    assert!(contains(&centres, d, d));
+    assert!(contains(&centres, 40.0 - d, d));
+    assert!(contains(&centres, 40.0 - d, 20.0 - d));
+    assert!(contains(&centres, d, 20.0 - d));
+
+    // The relief's edge passes through the drawn corner
+    for centre in &centres {
+        let corner = rectangle()
+            .into_iter()
+            .map(|p| p.distance_to(centre))
+            .fold(f64::INFINITY, f64::min);
+        assert!((corner - 3.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_winding_does_not_change_reliefs() {
+    let mut reversed = rectangle();
+    reversed.reverse();
+    let forward = relief_centres(&rectangle(), false, true, &settings(DogboneStyle::Dogbone));
+    let backward = relief_centres(&reversed, false, true, &settings(DogboneStyle::Dogbone));
+    assert_eq!(backward.len(), forward.len());
+    for centre in &forward {
+        assert!(contains(&backward, centre.x, centre.y));
+    }
+}
+
+#[test]
+fn test_outside_cut_relieves_only_concave_corners() {
+    let settings = settings(DogboneStyle::Dogbone);
+    assert!(relief_centres(&rectangle(), false, false, &settings).is_empty());
+
+    let centres = relief_centres(&l_shape(), false, false, &settings);
+    assert_eq!(centres.len(), 1);
+    // Points out of the part, into the waste
+    let d = 3.0 / 2f64.sqrt();
+    assert!(contains(&centres, 20.0 + d, 10.0 + d));
+}
+
+#[test]
+fn test_holes_are_relieved_when_cutting_around_the_shape() {
+    let hole = ring(&[(10.0, 5.0), (10.0, 15.0), (30.0, 15.0), (30.0, 5.0)]);
+    let settings = settings(DogboneStyle::Dogbone);
+
+    // An outside cut also clears the hole, so its corners are inside corners
+    assert_eq!(relief_centres(&hole, true, false, &settings).len(), 4);
+    // Cutting within the shape, the hole's corners point into the cutter
+    assert!(relief_centres(&hole, true, true, &settings).is_empty());
+}
+
+#[test]
+fn test_tbone_relieves_into_the_longer_wall() {
+    let centres = relief_centres(&rectangle(), false, true, &settings(DogboneStyle::TBone));
+    assert_eq!(centres.len(), 4);
+
+    // Centres lie on the 40mm walls, one radius from each corner
+    assert!(contains(&centres, 3.0, 0.0));
+    assert!(contains(&centres, 37.0, 0.0));
+    assert!(contains(&centres, 37.0, 20.0));
+    assert!(contains(&centres, 3.0, 20.0));
+}
+
+#[test]
+fn test_corners_wider_than_max_angle_are_left() {
+    // Hexagon: every corner is 120 degrees
+    let hexagon: Vec<Point> = (0..6)
+        .map(|i| {
+            let a = i as f64 * std::f64::consts::PI / 3.0;
+            Point::new(10.0 * a.cos(), 10.0 * a.sin())
+        })
+        .collect();
+
+    let mut narrow = settings(DogboneStyle::Dogbone);
+    narrow.max_angle = 100.0;
+    assert!(relief_centres(&hexagon, false, true, &narrow).is_empty());
+
+    let mut wide = settings(DogboneStyle::Dogbone);
+    wide.max_angle = 130.0;
+    assert_eq!(relief_centres(&hexagon, false, true, &wide).len(), 6);
+}
+
+#[test]
+fn test_disabled_relief_does_nothing() {
+    let mut disabled = settings(DogboneStyle::Dogbone);
+    disabled.enabled = false;
+    assert!(!disabled.is_active());
+    assert!(relief_centres(&rectangle(), false, true, &disabled).is_empty());
+    assert!(!DogboneSettings::default().is_active());
+}
+
+#[test]
+fn test_pocket_effect_cuts_past_the_drawn_corners() {
+    let mut obj = DrawingObject::new(1, Shape::Rectangle(Rectangle::new(0.0, 0.0, 40.0, 20.0)));
+    obj.operation_type = OperationType::Pocket;
+    obj.dogbone = settings(DogboneStyle::Dogbone);
+
+    // The reliefs reach past every wall of the pocket
+    let (x1, y1, x2, y2) = obj.get_effective_shape().bounds();
+    assert!(x1 < -0.5 && y1 < -0.5);
+    assert!(x2 > 40.5 && y2 > 20.5);
+
+    // Cut around the outside, a rectangle has no inside corners
+    obj.operation_type = OperationType::Profile;
+    obj.cut_side = CutSide::Outside;
+    let (x1, y1, x2, y2) = obj.get_effective_shape().bounds();
+    assert!(x1.abs() < 1e-3 && y1.abs() < 1e-3);
+    assert!((x2 - 40.0).abs() < 1e-3 && (y2 - 20.0).abs() < 1e-3);
+}
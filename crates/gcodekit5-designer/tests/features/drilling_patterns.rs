@@ -83,3 +83,42 @@ fn test_drilling_pattern_type_names() {
     assert_eq!(PatternType::Circular.name(), "Circular");
     assert_eq!(PatternType::Grid.name(), "Grid");
 }
+
+#[test]
+fn test_peck_toolpath_clears_chips_between_pecks() {
+    let mut op = DrillOperation::new("drill1".to_string(), 3.0, 3.0, -6.0);
+    op.set_peck_drilling(2.0);
+    let gen = DrillingPatternGenerator::new(op);
+
+    let toolpath = gen.generate_peck_toolpath(&[Point::new(5.0, 5.0)], 0.0, 0.0);
+    let moves: Vec<(f64, f64)> = toolpath
+        .segments
+        .iter()
+        .filter_map(|s| Some((s.start_z?, s.z_depth?)))
+        .collect();
+
+    // Three pecks of 2mm, retracting clear of the hole between them
+    assert_eq!(
+        moves,
+        vec![
+            (0.0, -2.0),
+            (-2.0, 1.0),
+            (-1.5, -4.0),
+            (-4.0, 1.0),
+            (-3.5, -6.0)
+        ]
+    );
+    assert_eq!(toolpath.depth, -6.0);
+}
+
+#[test]
+fn test_peck_toolpath_short_retract() {
+    let mut op = DrillOperation::new("drill1".to_string(), 3.0, 3.0, -4.0);
+    op.set_peck_drilling(2.0);
+    let gen = DrillingPatternGenerator::new(op);
+
+    let toolpath = gen.generate_peck_toolpath(&[Point::new(0.0, 0.0)], 0.0, 0.5);
+    let retract = &toolpath.segments[2];
+    assert_eq!(retract.start_z, Some(-2.0));
+    assert_eq!(retract.z_depth, Some(-1.5));
+}
@@ -4,7 +4,7 @@
 //! deterministic, expected output. If the output format changes,
 //! update the expected strings to match.
 
-use gcodekit5_core::Units;
+use gcodekit5_core::{PostDefinition, Units};
 use gcodekit5_designer::gcode_gen::ToolpathToGcode;
 use gcodekit5_designer::model::Point;
 use gcodekit5_designer::toolpath::{Toolpath, ToolpathSegment, ToolpathSegmentType};
@@ -291,3 +291,19 @@ fn snapshot_mixed_segment_types() {
     assert!(output.contains("G02"));
     assert!(output.contains("G03"));
 }
+
+#[test]
+fn snapshot_rectangle_linuxcnc_post() {
+    let tp = rectangle_toolpath();
+    let post = PostDefinition::builtin("LinuxCNC").unwrap();
+    let gen = ToolpathToGcode::new(Units::MM, 5.0).with_post_processor(post);
+    let output = gen.generate(&tp);
+
+    assert!(output.starts_with("%\n(Untitled)\n"));
+    assert!(output.contains("(Generated G-code from Designer tool)"));
+    assert!(output.contains(" S12000\nG4 P2\n"));
+    assert!(output.contains("G01 X50 Y10 F500"));
+    assert!(!output.contains(';'));
+    assert!(!output.contains("M30"));
+    assert!(output.ends_with("M2\n%\n"));
+}
@@ -0,0 +1,230 @@
+use crate::common::segment;
+use gcodekit5_designer::job_order::{optimize_order, rapid_distance, OrderJob};
+use gcodekit5_designer::model::Point;
+use gcodekit5_designer::shapes::OperationType;
+use gcodekit5_designer::toolpath::{Toolpath, ToolpathSegment, ToolpathSegmentType};
+
+const FEED: f64 = 500.0;
+const SPINDLE: u32 = 10000;
+
+/// One counter-clockwise square pass per depth, starting at the lower-left corner.
+fn square(x: f64, y: f64, size: f64, depths: &[f64]) -> Vec<Toolpath> {
+    let corners = [(x, y), (x + size, y), (x + size, y + size), (x, y + size)];
+    depths
+        .iter()
+        .map(|&depth| {
+            let mut tp = Toolpath::new(3.0, depth);
+            tp.add_segment(segment(
+                ToolpathSegmentType::RapidMove,
+                (0.0, 0.0),
+                corners[0],
+                FEED,
+                SPINDLE,
+            ));
+            for i in 0..4 {
+                tp.add_segment(segment(
+                    ToolpathSegmentType::LinearMove,
+                    corners[i],
+                    corners[(i + 1) % 4],
+                    FEED,
+                    SPINDLE,
+                ));
+            }
+            tp
+        })
+        .collect()
+}
+
+fn profile(toolpaths: Vec<Toolpath>) -> OrderJob {
+    OrderJob {
+        toolpaths,
+        operation: OperationType::Profile,
+        rotatable: true,
+        reversible: false,
+    }
+}
+
+fn pocket(toolpaths: Vec<Toolpath>) -> OrderJob {
+    OrderJob::new(toolpaths, OperationType::Pocket)
+}
+
+fn names<T: Copy>(group: &[(T, OrderJob)]) -> Vec<T> {
+    group.iter().map(|(name, _)| *name).collect()
+}
+
+#[test]
+fn test_scattered_jobs_are_visited_nearest_first() {
+    let positions = [
+        (80.0, 0.0),
+        (0.0, 80.0),
+        (40.0, 0.0),
+        (80.0, 80.0),
+        (0.0, 40.0),
+        (40.0, 80.0),
+        (80.0, 40.0),
+        (40.0, 40.0),
+        (0.0, 0.0),
+    ];
+    let jobs = positions
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, y))| (i, pocket(square(x, y, 10.0, &[-1.0]))))
+        .collect();
+
+    let origin = Point::new(0.0, 0.0);
+    let (groups, report) = optimize_order(vec![jobs], origin);
+    let order = names(&groups[0]);
+    assert_eq!(order.len(), positions.len());
+    assert_eq!(order[0], 8);
+    assert!(report.rapid_after <= 9.0 * 40.0 + 1e-6);
+    assert!(report.saved() > 0.0);
+
+    let measured = rapid_distance(
+        groups.iter().flatten().flat_map(|(_, job)| &job.toolpaths),
+        origin,
+    );
+    assert!((measured - report.rapid_after).abs() < 1e-9);
+}
+
+#[test]
+fn test_inner_contours_before_outer() {
+    let jobs = vec![
+        ("outer", profile(square(0.0, 0.0, 100.0, &[-5.0]))),
+        ("hole", profile(square(80.0, 80.0, 10.0, &[-5.0]))),
+        ("other", profile(square(200.0, 0.0, 10.0, &[-5.0]))),
+    ];
+    let (groups, _) = optimize_order(vec![jobs], Point::new(0.0, 0.0));
+    let order = names(&groups[0]);
+    let hole = order.iter().position(|n| *n == "hole").unwrap();
+    let outer = order.iter().position(|n| *n == "outer").unwrap();
+    assert!(hole < outer, "{order:?}");
+}
+
+#[test]
+fn test_pocket_before_profile_on_same_part() {
+    let jobs = vec![
+        ("profile", profile(square(10.0, 10.0, 20.0, &[-3.0]))),
+        ("pocket", pocket(square(10.0, 10.0, 20.0, &[-3.0]))),
+    ];
+    let (groups, _) = optimize_order(vec![jobs], Point::new(0.0, 0.0));
+    assert_eq!(names(&groups[0]), vec!["pocket", "profile"]);
+}
+
+#[test]
+fn test_shallower_jobs_first() {
+    let jobs = vec![
+        ("deep", pocket(square(0.0, 0.0, 10.0, &[-2.0, -4.0]))),
+        (
+            "shallow",
+            OrderJob::new(square(100.0, 100.0, 10.0, &[-0.5]), OperationType::Engrave),
+        ),
+        ("deep2", pocket(square(20.0, 0.0, 10.0, &[-4.0]))),
+    ];
+    let (groups, _) = optimize_order(vec![jobs], Point::new(0.0, 0.0));
+    assert_eq!(names(&groups[0]), vec!["shallow", "deep2", "deep"]);
+}
+
+#[test]
+fn test_tool_groups_keep_their_order() {
+    let first = vec![(1, pocket(square(100.0, 0.0, 10.0, &[-1.0])))];
+    let second = vec![
+        (2, pocket(square(0.0, 0.0, 10.0, &[-1.0]))),
+        (3, pocket(square(120.0, 0.0, 10.0, &[-1.0]))),
+    ];
+    let (groups, _) = optimize_order(vec![first, second], Point::new(0.0, 0.0));
+    assert_eq!(names(&groups[0]), vec![1]);
+    assert_eq!(names(&groups[1]), vec![3, 2]);
+}
+
+#[test]
+fn test_closed_contour_starts_nearest_the_tool() {
+    let jobs = vec![(0, profile(square(0.0, 0.0, 10.0, &[-1.0, -2.0])))];
+    let (groups, report) = optimize_order(vec![jobs], Point::new(5.0, 20.0));
+
+    for tp in &groups[0][0].1.toolpaths {
+        assert_eq!(tp.segments[0].segment_type, ToolpathSegmentType::RapidMove);
+        assert!(tp.segments[0].end.distance_to(&Point::new(5.0, 10.0)) < 1e-9);
+
+        // Same loop and direction, split where it is entered
+        let cuts = &tp.segments[1..];
+        assert_eq!(cuts.len(), 5);
+        for pair in cuts.windows(2) {
+            assert!(pair[0].end.distance_to(&pair[1].start) < 1e-9);
+        }
+        assert!(cuts[4].end.distance_to(&cuts[0].start) < 1e-9);
+        assert!(cuts[0].end.distance_to(&Point::new(0.0, 10.0)) < 1e-9);
+        let length: f64 = cuts.iter().map(|s| s.start.distance_to(&s.end)).sum();
+        assert!((length - 40.0).abs() < 1e-9);
+    }
+    assert!((report.rapid_after - 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_arc_contour_is_split_on_the_arc() {
+    let (cx, cy, r) = (50.0, 50.0, 10.0);
+    let points = [(cx + r, cy), (cx, cy + r), (cx - r, cy), (cx, cy - r)];
+    let mut tp = Toolpath::new(3.0, -1.0);
+    tp.add_segment(segment(
+        ToolpathSegmentType::RapidMove,
+        (0.0, 0.0),
+        points[0],
+        FEED,
+        SPINDLE,
+    ));
+    for i in 0..4 {
+        let (a, b) = (points[i], points[(i + 1) % 4]);
+        tp.add_segment(ToolpathSegment::new_arc(
+            ToolpathSegmentType::ArcCCW,
+            Point::new(a.0, a.1),
+            Point::new(b.0, b.1),
+            Point::new(cx, cy),
+            500.0,
+            10000,
+        ));
+    }
+
+    let (groups, _) = optimize_order(vec![vec![(0, profile(vec![tp]))]], Point::new(30.0, 90.0));
+    let rotated = &groups[0][0].1.toolpaths[0];
+    assert_eq!(rotated.segments.len(), 6);
+    let entry = rotated.segments[0].end;
+    assert!((entry.distance_to(&Point::new(cx, cy)) - r).abs() < 1e-6);
+    assert!(entry.x < cx && entry.y > cy);
+}
+
+#[test]
+fn test_open_cut_runs_backwards_only_when_allowed() {
+    let line = || {
+        let mut tp = Toolpath::new(3.0, -0.5);
+        tp.add_segment(segment(
+            ToolpathSegmentType::RapidMove,
+            (0.0, 0.0),
+            (0.0, 0.0),
+            FEED,
+            SPINDLE,
+        ));
+        tp.add_segment(segment(
+            ToolpathSegmentType::LinearMove,
+            (0.0, 0.0),
+            (50.0, 0.0),
+            FEED,
+            SPINDLE,
+        ));
+        vec![tp]
+    };
+    let start = Point::new(60.0, 0.0);
+
+    let mut engrave = OrderJob::new(line(), OperationType::Engrave);
+    engrave.reversible = true;
+    let (groups, report) = optimize_order(vec![vec![(0, engrave)]], start);
+    let tp = &groups[0][0].1.toolpaths[0];
+    assert_eq!(tp.segments[0].end, Point::new(50.0, 0.0));
+    assert_eq!(tp.segments[1].end, Point::new(0.0, 0.0));
+    assert!((report.rapid_after - 10.0).abs() < 1e-9);
+
+    let fixed = OrderJob::new(line(), OperationType::Engrave);
+    let (groups, _) = optimize_order(vec![vec![(0, fixed)]], start);
+    assert_eq!(
+        groups[0][0].1.toolpaths[0].segments[0].end,
+        Point::new(0.0, 0.0)
+    );
+}
@@ -0,0 +1,165 @@
+use crate::common::{add_loop, rectangle_pass};
+use gcodekit5_designer::leads::{apply_leads, lead_start_point, LeadSettings, LeadType};
+use gcodekit5_designer::tabs::Outline;
+use gcodekit5_designer::toolpath::Toolpath;
+use gcodekit5_designer::{Point, Rectangle, Shape, ToolpathGenerator, ToolpathSegmentType};
+
+fn near(p: Point, x: f64, y: f64) -> bool {
+    p.distance_to(&Point::new(x, y)) < 1e-6
+}
+
+#[test]
+fn test_line_lead_in_approaches_from_waste_side() {
+    let settings = LeadSettings {
+        lead_in: LeadType::Line,
+        length: 5.0,
+        ..Default::default()
+    };
+
+    // Outside cut: the lead comes up from below the bottom edge
+    let outside = apply_leads(&rectangle_pass(-2.0), &settings, None, true, 0.0);
+    assert!(near(outside.segments[0].end, 0.0, -5.0));
+    assert!(near(outside.segments[1].start, 0.0, -5.0));
+    assert!(near(outside.segments[1].end, 0.0, 0.0));
+
+    // Inside cut: the lead comes from inside the part outline
+    let inside = apply_leads(&rectangle_pass(-2.0), &settings, None, false, 0.0);
+    assert!(near(inside.segments[1].start, 0.0, 5.0));
+}
+
+#[test]
+fn test_arc_leads_are_tangent_quarter_arcs() {
+    let settings = LeadSettings {
+        lead_in: LeadType::Arc,
+        lead_out: LeadType::Arc,
+        radius: 3.0,
+        ..Default::default()
+    };
+    let out = apply_leads(&rectangle_pass(-2.0), &settings, None, true, 0.0);
+
+    let lead_in = &out.segments[1];
+    assert_eq!(lead_in.segment_type, ToolpathSegmentType::ArcCW);
+    assert!(near(lead_in.start, -3.0, -3.0));
+    assert!(near(lead_in.end, 0.0, 0.0));
+
+    let lead_out = out.segments.last().unwrap();
+    assert_eq!(lead_out.segment_type, ToolpathSegmentType::ArcCW);
+    assert!(near(lead_out.start, 0.0, 0.0));
+    assert!((lead_out.end.distance_to(&Point::new(0.0, 0.0)) - 3.0 * 2f64.sqrt()).abs() < 1e-6);
+}
+
+#[test]
+fn test_start_position_and_overlap() {
+    let settings = LeadSettings {
+        overlap: 5.0,
+        start_position: Some(0.5),
+        ..Default::default()
+    };
+    let out = apply_leads(
+        &rectangle_pass(-2.0),
+        &settings,
+        Some(Point::new(20.0, 21.0)),
+        true,
+        0.0,
+    );
+
+    // The loop is rotated to start on the top edge and runs 5mm past it
+    assert!(near(out.segments[0].end, 20.0, 20.0));
+    assert!(near(out.segments[1].start, 20.0, 20.0));
+    assert!(near(out.segments.last().unwrap().end, 15.0, 20.0));
+    let cut: f64 = out.segments[1..]
+        .iter()
+        .map(|s| s.start.distance_to(&s.end))
+        .sum();
+    assert!((cut - 125.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_ramp_leads_descend_from_previous_pass() {
+    let settings = LeadSettings {
+        lead_in: LeadType::Ramp,
+        lead_out: LeadType::Ramp,
+        length: 10.0,
+        overlap: 2.0,
+        ..Default::default()
+    };
+    let out = apply_leads(&rectangle_pass(-4.0), &settings, None, true, -2.0);
+
+    // Ramp in along the tail of the loop, from the previous floor
+    let ramp_in = &out.segments[1];
+    assert!(near(ramp_in.start, 0.0, 10.0));
+    assert_eq!(ramp_in.start_z, Some(-2.0));
+
+    // Ramp out climbs back up after the overlap
+    let ramp_out = out.segments.last().unwrap();
+    assert!(near(ramp_out.end, 12.0, 0.0));
+    assert_eq!(ramp_out.z_depth, Some(-2.0));
+}
+
+#[test]
+fn test_holes_lead_in_from_inside_the_hole() {
+    let mut toolpath = Toolpath::new(2.0, -2.0);
+    add_loop(
+        &mut toolpath,
+        &[(0.0, 0.0), (40.0, 0.0), (40.0, 40.0), (0.0, 40.0)],
+    );
+    add_loop(
+        &mut toolpath,
+        &[(10.0, 10.0), (10.0, 30.0), (30.0, 30.0), (30.0, 10.0)],
+    );
+    let settings = LeadSettings {
+        lead_in: LeadType::Line,
+        length: 5.0,
+        ..Default::default()
+    };
+    let out = apply_leads(&toolpath, &settings, None, true, 0.0);
+
+    assert!(near(out.segments[1].start, 0.0, -5.0));
+    assert!(near(out.segments[7].start, 15.0, 10.0));
+}
+
+#[test]
+fn test_start_point_follows_outline() {
+    let shape = Shape::Rectangle(Rectangle::new(0.0, 0.0, 40.0, 20.0));
+    let fraction = Outline::from_shape(&shape)
+        .project(Point::new(30.0, 21.0))
+        .unwrap();
+    let settings = LeadSettings {
+        start_position: Some(fraction),
+        ..Default::default()
+    };
+
+    let start = lead_start_point(&shape, &settings).unwrap();
+    assert!(start.distance_to(&Point::new(30.0, 20.0)) < 1e-6);
+    assert!(lead_start_point(&shape, &LeadSettings::default()).is_none());
+}
+
+#[test]
+fn test_generator_adds_leads_to_every_pass() {
+    let rect = Rectangle::new(0.0, 0.0, 40.0, 20.0);
+    let settings = LeadSettings {
+        lead_in: LeadType::Line,
+        lead_out: LeadType::Line,
+        length: 5.0,
+        ..Default::default()
+    };
+
+    let mut gen = ToolpathGenerator::new();
+    gen.set_tool_diameter(2.0);
+    gen.set_start_depth(0.0);
+    gen.set_cut_depth(6.0);
+    gen.set_leads(settings, None);
+
+    let passes = gen.generate_rectangle_contour(&rect, 2.0);
+    assert_eq!(passes.len(), 3);
+    for pass in &passes {
+        let plain = rectangle_pass(pass.depth);
+        let cutting = |tp: &Toolpath| {
+            tp.segments
+                .iter()
+                .filter(|s| s.segment_type != ToolpathSegmentType::RapidMove)
+                .count()
+        };
+        assert!(cutting(pass) >= cutting(&plain) + 2);
+    }
+}
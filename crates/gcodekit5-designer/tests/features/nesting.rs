@@ -0,0 +1,167 @@
+use gcodekit5_designer::nesting::{nest, NestPart, NestResult, NestRotation, NestSettings};
+use gcodekit5_designer::Point;
+
+fn rect(x: f64, y: f64, w: f64, h: f64) -> Vec<Point> {
+    vec![
+        Point::new(x, y),
+        Point::new(x + w, y),
+        Point::new(x + w, y + h),
+        Point::new(x, y + h),
+    ]
+}
+
+fn fixed(spacing: f64, margin: f64) -> NestSettings {
+    NestSettings {
+        spacing,
+        margin,
+        rotation: NestRotation::Fixed,
+        ..Default::default()
+    }
+}
+
+fn segment_distance(p: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+    Point::new(a.x + t * dx, a.y + t * dy).distance_to(&p)
+}
+
+fn outline_distance(a: &[Vec<Point>], b: &[Vec<Point>]) -> f64 {
+    let mut distance = f64::INFINITY;
+    for (from, to) in [(a, b), (b, a)] {
+        for &p in from.iter().flatten() {
+            for ring in to {
+                for k in 0..ring.len() {
+                    let d = segment_distance(p, ring[k], ring[(k + 1) % ring.len()]);
+                    distance = distance.min(d);
+                }
+            }
+        }
+    }
+    distance
+}
+
+/// Checks every placed part lies within the margin and keeps the spacing
+/// from the other parts on its sheet.
+fn assert_valid(result: &NestResult, parts: &[NestPart], w: f64, h: f64, s: &NestSettings) {
+    let placed: Vec<(usize, Vec<Vec<Point>>)> = result
+        .placements
+        .iter()
+        .map(|p| {
+            let rings = parts[p.part].rings.iter();
+            let rings = rings.map(|ring| ring.iter().map(|&q| p.apply(q)).collect());
+            (p.sheet, rings.collect())
+        })
+        .collect();
+
+    for q in placed.iter().flat_map(|(_, rings)| rings.iter().flatten()) {
+        assert!(q.x >= s.margin - 1e-6 && q.x <= w - s.margin + 1e-6);
+        assert!(q.y >= s.margin - 1e-6 && q.y <= h - s.margin + 1e-6);
+    }
+    for (i, (sheet_a, a)) in placed.iter().enumerate() {
+        for (sheet_b, b) in &placed[i + 1..] {
+            if sheet_a == sheet_b {
+                assert!(outline_distance(a, b) >= s.spacing - 1e-6);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_part_area_excludes_holes() {
+    let part = NestPart::new(
+        vec![rect(0.0, 0.0, 100.0, 100.0), rect(20.0, 20.0, 60.0, 60.0)],
+        1,
+    );
+    assert!((part.area() - 6400.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_squares_fill_one_sheet() {
+    let settings = fixed(5.0, 10.0);
+    let parts = [NestPart::new(vec![rect(0.0, 0.0, 40.0, 40.0)], 9)];
+    let result = nest(&parts, 200.0, 200.0, &settings);
+
+    assert_eq!(result.placed(), 9);
+    assert_eq!(result.sheets.len(), 1);
+    assert!((result.sheets[0].utilisation - 9.0 * 1600.0 / 40000.0).abs() < 1e-9);
+    assert_valid(&result, &parts, 200.0, 200.0, &settings);
+}
+
+#[test]
+fn test_overflow_opens_new_sheets() {
+    let parts = [NestPart::new(vec![rect(0.0, 0.0, 80.0, 80.0)], 5)];
+    let result = nest(&parts, 200.0, 200.0, &fixed(6.0, 10.0));
+    assert_eq!(result.placed(), 5);
+    assert_eq!(result.sheets.len(), 2);
+    assert_eq!(result.sheets[0].parts, 4);
+    assert_eq!(result.sheets[1].parts, 1);
+
+    let one_sheet = NestSettings {
+        max_sheets: 1,
+        ..fixed(6.0, 10.0)
+    };
+    let result = nest(&parts, 200.0, 200.0, &one_sheet);
+    assert_eq!(result.placed(), 4);
+    assert_eq!(result.unplaced, vec![1]);
+}
+
+#[test]
+fn test_oversized_part_is_reported_unplaced() {
+    let parts = [NestPart::new(vec![rect(0.0, 0.0, 300.0, 10.0)], 3)];
+    let result = nest(&parts, 200.0, 200.0, &fixed(6.0, 10.0));
+    assert_eq!(result.unplaced, vec![3]);
+    assert_eq!(result.unplaced_count(), 3);
+    assert!(result.sheets.is_empty());
+}
+
+#[test]
+fn test_quarter_turn_fits_long_part() {
+    let parts = [NestPart::new(vec![rect(0.0, 0.0, 10.0, 250.0)], 1)];
+    assert_eq!(
+        nest(&parts, 300.0, 100.0, &fixed(6.0, 10.0)).unplaced,
+        vec![1]
+    );
+
+    let result = nest(&parts, 300.0, 100.0, &NestSettings::default());
+    assert_eq!(result.placed(), 1);
+    assert_eq!(result.placements[0].angle, 90.0);
+}
+
+#[test]
+fn test_small_part_nests_in_hole() {
+    let settings = fixed(2.0, 0.0);
+    let parts = [
+        NestPart::new(
+            vec![rect(0.0, 0.0, 100.0, 100.0), rect(10.0, 10.0, 80.0, 80.0)],
+            1,
+        ),
+        NestPart::new(vec![rect(0.0, 0.0, 30.0, 30.0)], 1),
+    ];
+    let result = nest(&parts, 100.0, 100.0, &settings);
+
+    assert_eq!(result.placed(), 2);
+    assert_eq!(result.sheets.len(), 1);
+    assert_valid(&result, &parts, 100.0, 100.0, &settings);
+}
+
+#[test]
+fn test_free_rotation_keeps_spacing() {
+    let triangle = vec![
+        Point::new(0.0, 0.0),
+        Point::new(60.0, 0.0),
+        Point::new(0.0, 60.0),
+    ];
+    let settings = NestSettings {
+        spacing: 2.0,
+        margin: 5.0,
+        rotation: NestRotation::Free,
+        angle_step: 45.0,
+        ..Default::default()
+    };
+    assert_eq!(settings.angles().len(), 8);
+
+    let parts = [NestPart::new(vec![triangle], 6)];
+    let result = nest(&parts, 200.0, 150.0, &settings);
+    assert_eq!(result.placed(), 6);
+    assert_valid(&result, &parts, 200.0, 150.0, &settings);
+}
@@ -0,0 +1,152 @@
+use gcodekit5_designer::profile_operations::{compensate_profile, CutDirection, CutSide};
+use gcodekit5_designer::toolpath::Toolpath;
+use gcodekit5_designer::{
+    Point, Rectangle, ToolpathGenerator, ToolpathSegment, ToolpathSegmentType,
+};
+
+fn generator(side: CutSide, direction: CutDirection) -> ToolpathGenerator {
+    let mut gen = ToolpathGenerator::new();
+    gen.set_tool_diameter(2.0);
+    gen.set_cut_depth(3.0);
+    gen.set_start_depth(0.0);
+    gen.set_cut_side(side);
+    gen.set_cut_direction(direction);
+    gen
+}
+
+/// Bounding box (min x, min y, max x, max y) of the cutting moves.
+fn cut_bounds(toolpath: &Toolpath) -> (f64, f64, f64, f64) {
+    toolpath
+        .segments
+        .iter()
+        .filter(|s| s.segment_type != ToolpathSegmentType::RapidMove)
+        .flat_map(|s| [s.start, s.end])
+        .fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(x0, y0, x1, y1), p| (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y)),
+        )
+}
+
+/// Signed area of the cutting moves' end points, positive when counter-clockwise.
+fn signed_area(toolpath: &Toolpath) -> f64 {
+    toolpath
+        .segments
+        .iter()
+        .filter(|s| s.segment_type != ToolpathSegmentType::RapidMove)
+        .map(|s| (s.start.x * s.end.y - s.end.x * s.start.y) / 2.0)
+        .sum()
+}
+
+fn assert_bounds_near(actual: (f64, f64, f64, f64), expected: (f64, f64, f64, f64)) {
+    let pairs = [
+        (actual.0, expected.0),
+        (actual.1, expected.1),
+        (actual.2, expected.2),
+        (actual.3, expected.3),
+    ];
+    for (a, e) in pairs {
+        assert!(
+            (a - e).abs() < 1e-3,
+            "bounds {:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_on_line_profile_is_uncompensated() {
+    let rect = Rectangle::new(0.0, 0.0, 20.0, 10.0);
+    let gen = generator(CutSide::OnLine, CutDirection::Climb);
+    let toolpaths = gen.generate_rectangle_contour(&rect, 0.0);
+    let default = ToolpathGenerator::new().generate_rectangle_contour(&rect, 0.0);
+    assert_eq!(cut_bounds(&toolpaths[0]), cut_bounds(&default[0]));
+}
+
+#[test]
+fn test_outside_profile_grows_by_tool_radius() {
+    let rect = Rectangle::new(0.0, 0.0, 20.0, 10.0);
+    let drawn = cut_bounds(
+        &generator(CutSide::OnLine, CutDirection::Climb).generate_rectangle_contour(&rect, 0.0)[0],
+    );
+    let toolpaths =
+        generator(CutSide::Outside, CutDirection::Climb).generate_rectangle_contour(&rect, 0.0);
+    assert_bounds_near(
+        cut_bounds(&toolpaths[0]),
+        (drawn.0 - 1.0, drawn.1 - 1.0, drawn.2 + 1.0, drawn.3 + 1.0),
+    );
+}
+
+#[test]
+fn test_inside_profile_shrinks_by_tool_radius() {
+    let rect = Rectangle::new(0.0, 0.0, 20.0, 10.0);
+    let drawn = cut_bounds(
+        &generator(CutSide::OnLine, CutDirection::Climb).generate_rectangle_contour(&rect, 0.0)[0],
+    );
+    let toolpaths =
+        generator(CutSide::Inside, CutDirection::Climb).generate_rectangle_contour(&rect, 0.0);
+    assert_bounds_near(
+        cut_bounds(&toolpaths[0]),
+        (drawn.0 + 1.0, drawn.1 + 1.0, drawn.2 - 1.0, drawn.3 - 1.0),
+    );
+}
+
+#[test]
+fn test_cut_direction_sets_loop_orientation() {
+    let rect = Rectangle::new(0.0, 0.0, 20.0, 10.0);
+    let climb =
+        generator(CutSide::Outside, CutDirection::Climb).generate_rectangle_contour(&rect, 0.0);
+    let conventional = generator(CutSide::Outside, CutDirection::Conventional)
+        .generate_rectangle_contour(&rect, 0.0);
+    // Climb milling around a part keeps the material on the right: clockwise
+    assert!(signed_area(&climb[0]) < 0.0);
+    assert!(signed_area(&conventional[0]) > 0.0);
+
+    // Inside a cut-out the material is outside the loop, so climb is counter-clockwise
+    let inside =
+        generator(CutSide::Inside, CutDirection::Climb).generate_rectangle_contour(&rect, 0.0);
+    assert!(signed_area(&inside[0]) > 0.0);
+}
+
+#[test]
+fn test_finishing_allowance_adds_final_pass() {
+    let rect = Rectangle::new(0.0, 0.0, 20.0, 10.0);
+    let drawn = cut_bounds(
+        &generator(CutSide::OnLine, CutDirection::Climb).generate_rectangle_contour(&rect, 0.0)[0],
+    );
+    let mut gen = generator(CutSide::Outside, CutDirection::Climb);
+    gen.set_finishing_allowance(0.5);
+    let toolpaths = gen.generate_rectangle_contour(&rect, 1.0);
+
+    // Three roughing passes at 1mm plus the finishing pass
+    assert_eq!(toolpaths.len(), 4);
+    assert_bounds_near(
+        cut_bounds(&toolpaths[0]),
+        (drawn.0 - 1.5, drawn.1 - 1.5, drawn.2 + 1.5, drawn.3 + 1.5),
+    );
+    let finish = toolpaths.last().unwrap();
+    assert!((finish.depth - (-3.0)).abs() < 1e-9);
+    assert_bounds_near(
+        cut_bounds(finish),
+        (drawn.0 - 1.0, drawn.1 - 1.0, drawn.2 + 1.0, drawn.3 + 1.0),
+    );
+}
+
+#[test]
+fn test_open_contours_are_not_compensated() {
+    let line = vec![ToolpathSegment::new(
+        ToolpathSegmentType::LinearMove,
+        Point::new(0.0, 0.0),
+        Point::new(10.0, 0.0),
+        100.0,
+        1000,
+    )];
+    let result = compensate_profile(&line, CutSide::Outside, CutDirection::Climb, 1.0);
+    let cuts: Vec<_> = result
+        .iter()
+        .filter(|s| s.segment_type != ToolpathSegmentType::RapidMove)
+        .collect();
+    assert_eq!(cuts.len(), 1);
+    assert_eq!(cuts[0].start, Point::new(0.0, 0.0));
+    assert_eq!(cuts[0].end, Point::new(10.0, 0.0));
+}
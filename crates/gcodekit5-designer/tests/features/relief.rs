@@ -0,0 +1,227 @@
+use gcodekit5_core::data::tools::{Tool, ToolId, ToolProfile, ToolType};
+use gcodekit5_core::Units;
+use gcodekit5_designer::gcode_gen::ToolpathToGcode;
+use gcodekit5_designer::relief::{ReliefCarver, ReliefMap, ReliefPass, ReliefSettings};
+use gcodekit5_designer::tool_changes::ToolChangeSettings;
+use image::{DynamicImage, GrayImage, Luma};
+
+fn gray(width: u32, height: u32, pixels: &[u8]) -> DynamicImage {
+    DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, y| {
+        Luma([pixels[(y * width + x) as usize]])
+    }))
+}
+
+/// A map sampled from `surface(x, y)` at the sample centres.
+fn map_from(size: usize, pixel: f64, surface: impl Fn(f64, f64) -> f64) -> ReliefMap {
+    let mut map = ReliefMap {
+        columns: size,
+        rows: size,
+        pixel,
+        origin: (0.0, 0.0),
+        surface: Vec::new(),
+    };
+    for row in 0..size {
+        for column in 0..size {
+            let (x, y) = map.point(column, row);
+            map.surface.push(surface(x, y));
+        }
+    }
+    map
+}
+
+fn finishing_only(cutter: ToolProfile, stepover: f64) -> ReliefSettings {
+    ReliefSettings {
+        max_depth: 4.0,
+        roughing: None,
+        finishing: ReliefPass {
+            cutter,
+            stepover,
+            ..ReliefSettings::default().finishing
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_brightness_sets_depth() {
+    // Image rows run down from the top; map rows run up from the bottom
+    let image = gray(3, 2, &[255, 0, 64, 128, 128, 128]);
+    let settings = ReliefSettings {
+        width: 3.0,
+        resolution: 1.0,
+        min_depth: 1.0,
+        max_depth: 5.0,
+        ..Default::default()
+    };
+    let map = ReliefMap::from_image(&image, &settings).unwrap();
+    assert_eq!((map.columns, map.rows), (3, 2));
+    assert!((map.z(0, 1) + 1.0).abs() < 1e-9);
+    assert!((map.z(1, 1) + 5.0).abs() < 1e-9);
+    assert!((map.z(0, 0) + (1.0 + 4.0 * (1.0 - 128.0 / 255.0))).abs() < 1e-9);
+    // Outside the carving the stock top is left alone
+    assert_eq!(map.z(-1, 0), 0.0);
+
+    let gamma = ReliefSettings {
+        gamma: 2.0,
+        ..settings.clone()
+    };
+    let map = ReliefMap::from_image(&image, &gamma).unwrap();
+    let brightness: f64 = 128.0 / 255.0;
+    assert!((map.z(0, 0) + (1.0 + 4.0 * (1.0 - brightness.powi(2)))).abs() < 1e-9);
+
+    let inverted = ReliefSettings {
+        invert: true,
+        ..settings
+    };
+    let map = ReliefMap::from_image(&image, &inverted).unwrap();
+    assert!((map.z(0, 1) + 5.0).abs() < 1e-9);
+    assert!((map.z(1, 1) + 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_relief_rejects_bad_settings() {
+    let image = gray(2, 2, &[0, 255, 255, 0]);
+    let shallow = ReliefSettings {
+        min_depth: 3.0,
+        max_depth: 2.0,
+        ..Default::default()
+    };
+    assert!(ReliefMap::from_image(&image, &shallow).is_err());
+
+    let narrow = ReliefSettings {
+        width: 0.0,
+        ..Default::default()
+    };
+    assert!(ReliefMap::from_image(&image, &narrow).is_err());
+}
+
+#[test]
+fn test_pass_from_library_tool() {
+    let ball = Tool::new(
+        ToolId("ball".into()),
+        2,
+        "Ball 3mm".into(),
+        ToolType::EndMillBall,
+        3.0,
+        40.0,
+    );
+    let pass = ReliefPass::from_library(&ball).unwrap();
+    assert_eq!(pass.cutter, ToolProfile::Ball { diameter: 3.0 });
+    assert_eq!(pass.tool.number, 2);
+    assert!((pass.stepover - 3.0 * ball.params.stepover_percent as f64 / 100.0).abs() < 1e-6);
+
+    let vbit = Tool::new(
+        ToolId("vbit".into()),
+        3,
+        "V-bit".into(),
+        ToolType::VBit,
+        6.0,
+        40.0,
+    );
+    assert!(ReliefPass::from_library(&vbit).is_none());
+}
+
+#[test]
+fn test_finishing_follows_relief_without_gouging() {
+    // A dome rising from 4mm deep at the edges to 1mm deep in the middle
+    let dome = |x: f64, y: f64| {
+        let r = (x - 20.0).hypot(y - 20.0);
+        -4.0 + 3.0 * (1.0 - (r / 20.0).powi(2)).max(0.0)
+    };
+    let map = map_from(80, 0.5, dome);
+    let carver = ReliefCarver::new(finishing_only(ToolProfile::Ball { diameter: 3.0 }, 0.5));
+    let job = carver.generate(&map);
+    assert!(job.roughing.is_none());
+    assert!((job.finishing.depth + 4.0).abs() < 0.1);
+
+    let result = carver.simulate(&map, &job, 0.5);
+    for row in (10..70).step_by(3) {
+        for column in (10..70).step_by(3) {
+            let (x, y) = map.point(column, row);
+            let cut = result.get_height(x as f32, y as f32).unwrap() as f64;
+            let surface = 4.0 + dome(x, y);
+            // Between samples the ball can clip the steepest slopes slightly
+            assert!(
+                cut >= surface - 0.05,
+                "gouge at ({x}, {y}): {cut} {surface}"
+            );
+            assert!(
+                cut <= surface + 0.1,
+                "material left at ({x}, {y}): {cut} {surface}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_roughing_steps_down_to_allowance() {
+    // A square pocket 4mm deep in the middle of the carving
+    let pocket = |x: f64, y: f64| {
+        if (x - 15.0).abs() < 8.0 && (y - 15.0).abs() < 8.0 {
+            -4.0
+        } else {
+            0.0
+        }
+    };
+    let map = map_from(60, 0.5, pocket);
+    let settings = ReliefSettings {
+        roughing: Some(ReliefPass {
+            cutter: ToolProfile::Flat { diameter: 4.0 },
+            stepover: 1.6,
+            step_down: 1.5,
+            stock_to_leave: 0.5,
+            ..ReliefSettings::default().finishing
+        }),
+        ..finishing_only(ToolProfile::Ball { diameter: 2.0 }, 0.4)
+    };
+    let carver = ReliefCarver::new(settings);
+    let job = carver.generate(&map);
+    let roughing = job.roughing.as_ref().unwrap();
+
+    let mut depths: Vec<i64> = roughing
+        .segments
+        .iter()
+        .filter_map(|segment| segment.z_depth)
+        .map(|z| (z * 1000.0).round() as i64)
+        .collect();
+    depths.sort_unstable();
+    depths.dedup();
+    assert_eq!(depths.first(), Some(&-3500));
+    for level in [-1500, -3000, -3500] {
+        assert!(depths.contains(&level), "no cut at {level}");
+    }
+    assert!((roughing.depth + 3.5).abs() < 1e-9);
+
+    // Roughing only cuts inside the pocket, clear of its walls
+    for segment in &roughing.segments {
+        if segment.z_depth.is_some_and(|z| z < -1e-6) {
+            for p in [segment.start, segment.end] {
+                assert!((p.x - 15.0).abs() < 8.0 - 2.0 && (p.y - 15.0).abs() < 8.0 - 2.0);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_relief_gcode_changes_tools() {
+    let map = map_from(20, 0.5, |x, _| -0.2 * x);
+    let mut settings = ReliefSettings::default();
+    settings.roughing.as_mut().unwrap().tool.number = 4;
+    settings.finishing.tool.number = 7;
+    let carver = ReliefCarver::new(settings);
+    let job = carver.generate(&map);
+
+    let gcode = carver.to_gcode(
+        &job,
+        &ToolpathToGcode::new(Units::MM, 5.0),
+        &ToolChangeSettings::default(),
+    );
+    let roughing = gcode.find("T4 M6").expect("roughing tool change");
+    let finishing = gcode.find("T7 M6").expect("finishing tool change");
+    assert!(roughing < finishing);
+    assert!(gcode.contains("; Roughing: Flat"));
+    assert!(gcode.contains("; Finishing: Ball"));
+    assert!(gcode
+        .lines()
+        .any(|line| line.starts_with("G01 X") && line.contains(" Z-")));
+}
@@ -0,0 +1,288 @@
+use gcodekit5_core::data::tools::ToolProfile;
+use gcodekit5_core::Units;
+use gcodekit5_designer::gcode_gen::ToolpathToGcode;
+use gcodekit5_designer::model3d::{Mesh3D, Triangle3D};
+use gcodekit5_designer::stock_removal::{StockMaterial, StockSimulator2D};
+use gcodekit5_designer::surface_finishing::{
+    DropCutter, FinishStrategy, FinishingParams, SurfaceFinisher,
+};
+use gcodekit5_designer::toolpath::{Toolpath, ToolpathSegmentType};
+use nalgebra::Point3;
+
+fn p(x: f32, y: f32, z: f32) -> Point3<f32> {
+    Point3::new(x, y, z)
+}
+
+/// Square plane from -half to half, tilted so z = slope * x.
+fn sloped_plane(half: f32, slope: f32) -> Mesh3D {
+    let z = |x: f32| slope * x;
+    Mesh3D::new(vec![
+        Triangle3D::new(
+            p(-half, -half, z(-half)),
+            p(half, -half, z(half)),
+            p(half, half, z(half)),
+        ),
+        Triangle3D::new(
+            p(-half, -half, z(-half)),
+            p(half, half, z(half)),
+            p(-half, half, z(-half)),
+        ),
+    ])
+}
+
+/// Square pyramid on the XY plane with its apex at (0, 0, height).
+fn pyramid(half: f32, height: f32) -> Mesh3D {
+    let corners = [
+        p(-half, -half, 0.0),
+        p(half, -half, 0.0),
+        p(half, half, 0.0),
+        p(-half, half, 0.0),
+    ];
+    let apex = p(0.0, 0.0, height);
+    Mesh3D::new(
+        (0..4)
+            .map(|k| Triangle3D::new(corners[k], corners[(k + 1) % 4], apex))
+            .collect(),
+    )
+}
+
+fn pyramid_height(x: f64, y: f64, half: f64, height: f64) -> f64 {
+    (height * (1.0 - x.abs().max(y.abs()) / half)).max(0.0)
+}
+
+/// Cutting passes as lists of (x, y, z) points.
+fn passes(toolpath: &Toolpath) -> Vec<Vec<(f64, f64, f64)>> {
+    let mut passes: Vec<Vec<(f64, f64, f64)>> = Vec::new();
+    for segment in &toolpath.segments {
+        match segment.segment_type {
+            ToolpathSegmentType::RapidMove => passes.push(Vec::new()),
+            _ => {
+                let pass = passes.last_mut().expect("cut before the first rapid");
+                if pass.is_empty() {
+                    let z = segment.start_z.expect("start z");
+                    pass.push((segment.start.x, segment.start.y, z));
+                }
+                let z = segment.z_depth.expect("z depth");
+                pass.push((segment.end.x, segment.end.y, z));
+            }
+        }
+    }
+    passes
+}
+
+#[test]
+fn test_drop_cutter_on_slope_matches_cutter_shape() {
+    let slope = 0.5_f64;
+    let mesh = sloped_plane(20.0, slope as f32);
+    let secant = (1.0 + slope * slope).sqrt();
+
+    // The tip rests where the cutter touches the plane on its uphill side
+    let cases = [
+        (ToolProfile::Flat { diameter: 6.0 }, 3.0 * slope),
+        (ToolProfile::Ball { diameter: 6.0 }, 3.0 * secant - 3.0),
+        (
+            ToolProfile::BullNose {
+                diameter: 6.0,
+                corner_radius: 1.0,
+            },
+            2.0 * slope + secant - 1.0,
+        ),
+        (
+            ToolProfile::VBit {
+                diameter: 6.0,
+                tip_angle: 150.0,
+            },
+            3.0 * slope - 3.0 * 15f64.to_radians().tan(),
+        ),
+    ];
+    for (cutter, lift) in cases {
+        let dropper = DropCutter::new(&mesh, cutter, 0.0);
+        for x in [-5.0, 0.0, 7.5] {
+            let expected = slope * x + lift;
+            assert!(
+                (dropper.height(x, 2.0) - expected).abs() < 1e-4,
+                "{} cutter at x = {x}",
+                cutter.name()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_drop_cutter_rests_on_vertices_and_edges() {
+    let mesh = pyramid(10.0, 10.0);
+
+    // A flat cutter anywhere within its radius of the apex sits on it
+    let flat = DropCutter::new(&mesh, ToolProfile::Flat { diameter: 4.0 }, 0.0);
+    assert!((flat.height(1.5, 0.5) - 10.0).abs() < 1e-4);
+
+    // A ball over the apex touches it at the side of the ball
+    let ball = DropCutter::new(&mesh, ToolProfile::Ball { diameter: 4.0 }, 0.0);
+    let r: f64 = 1.0;
+    let expected = 10.0 - (2.0 - (4.0 - r * r).sqrt());
+    assert!(ball.height(r, 0.0) <= 10.0);
+    assert!(ball.height(r, 0.0) >= expected - 1e-4);
+
+    // Beyond the mesh the cutter drops to the bottom of the model
+    assert!(flat.height(30.0, 30.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_stock_to_leave_offsets_surface() {
+    let slope = 0.5_f64;
+    let mesh = sloped_plane(20.0, slope as f32);
+    let secant = (1.0 + slope * slope).sqrt();
+
+    for cutter in [
+        ToolProfile::Ball { diameter: 6.0 },
+        ToolProfile::BullNose {
+            diameter: 6.0,
+            corner_radius: 1.0,
+        },
+    ] {
+        let exact = DropCutter::new(&mesh, cutter, 0.0);
+        let offset = DropCutter::new(&mesh, cutter, 0.5);
+        let lift = offset.height(1.0, 1.0) - exact.height(1.0, 1.0);
+        assert!((lift - 0.5 * secant).abs() < 1e-4, "{}", cutter.name());
+    }
+}
+
+#[test]
+fn test_scallop_height_sets_stepover() {
+    let ball = FinishingParams {
+        cutter: ToolProfile::Ball { diameter: 6.0 },
+        scallop_height: Some(0.01),
+        ..Default::default()
+    };
+    let expected = 2.0 * (0.01_f64 * (6.0 - 0.01)).sqrt();
+    assert!((ball.effective_stepover() - expected).abs() < 1e-9);
+    assert!((ball.effective_step_down() - expected).abs() < 1e-9);
+
+    // The flat bottom of a bull nose adds to the stepover
+    let bull = FinishingParams {
+        cutter: ToolProfile::BullNose {
+            diameter: 6.0,
+            corner_radius: 1.0,
+        },
+        ..ball.clone()
+    };
+    let expected = 4.0 + 2.0 * (0.01_f64 * (2.0 - 0.01)).sqrt();
+    assert!((bull.effective_stepover() - expected).abs() < 1e-9);
+
+    let flat = FinishingParams {
+        cutter: ToolProfile::Flat { diameter: 6.0 },
+        stepover: 1.5,
+        ..ball
+    };
+    assert_eq!(flat.effective_stepover(), 1.5);
+}
+
+#[test]
+fn test_parallel_passes_follow_angle() {
+    let mesh = sloped_plane(10.0, 0.0);
+    for (angle, along_x) in [(0.0, true), (90.0, false)] {
+        let params = FinishingParams {
+            cutter: ToolProfile::Ball { diameter: 3.0 },
+            stepover: 1.0,
+            angle,
+            ..Default::default()
+        };
+        let toolpath = SurfaceFinisher::new(params).generate(&mesh);
+        let cuts = passes(&toolpath);
+        assert_eq!(cuts.len(), 1);
+
+        // One straight move per pass and one link between passes
+        let points = &cuts[0];
+        assert_eq!(points.len(), 2 * 21);
+        for pair in points.chunks(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if along_x {
+                assert!((a.1 - b.1).abs() < 1e-9 && (a.0 - b.0).abs() > 19.9);
+            } else {
+                assert!((a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() > 19.9);
+            }
+        }
+        assert!(points.iter().all(|q| q.2.abs() < 1e-9));
+    }
+}
+
+#[test]
+fn test_waterline_traces_climb_contours() {
+    let mesh = pyramid(20.0, 10.0);
+    let params = FinishingParams {
+        strategy: FinishStrategy::Waterline,
+        cutter: ToolProfile::Flat { diameter: 4.0 },
+        step_down: 2.0,
+        sample_step: 0.5,
+        ..Default::default()
+    };
+    let toolpath = SurfaceFinisher::new(params).generate(&mesh);
+    let loops = passes(&toolpath);
+    assert_eq!(loops.len(), 4);
+
+    for (k, contour) in loops.iter().enumerate() {
+        let depth = -2.0 * (k + 1) as f64;
+        // The flat cutter's edge rides on the slice of the pyramid at its tip
+        let half = 2.0 * -depth;
+        let mut area = 0.0;
+        for pair in contour.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert!((a.2 - depth).abs() < 1e-9);
+            let outside = (a.0.abs() - half)
+                .max(0.0)
+                .hypot((a.1.abs() - half).max(0.0));
+            assert!((outside - 2.0).abs() < 0.05);
+            area += a.0 * b.1 - b.0 * a.1;
+        }
+        assert_eq!(contour.first(), contour.last());
+        // Clockwise round the island keeps the material on the right
+        assert!(area < 0.0);
+    }
+}
+
+#[test]
+fn test_finishing_gcode_and_simulation_follow_surface() {
+    let (half, height) = (15.0, 6.0);
+    let mesh = pyramid(half as f32, height as f32);
+    let cutter = ToolProfile::Ball { diameter: 3.0 };
+    let params = FinishingParams {
+        cutter,
+        stepover: 0.5,
+        angle: 30.0,
+        tolerance: 0.01,
+        ..Default::default()
+    };
+    let toolpath = SurfaceFinisher::new(params).generate(&mesh);
+    let lowest = toolpath
+        .segments
+        .iter()
+        .filter_map(|segment| segment.z_depth)
+        .fold(0.0, f64::min);
+    assert_eq!(toolpath.depth, lowest);
+    assert!(lowest > -height && lowest < 0.0);
+
+    let gcode = ToolpathToGcode::new(Units::MM, 5.0).generate(&toolpath);
+    assert!(gcode.contains("G01 Z"));
+    assert!(gcode
+        .lines()
+        .any(|line| line.starts_with("G01 X") && line.contains(" Z-")));
+
+    let stock = StockMaterial::new(30.0, 30.0, height as f32, (-15.0, -15.0, 0.0));
+    let mut simulator = StockSimulator2D::with_tool_profile(stock, 0.25, cutter);
+    simulator.simulate_toolpath(&toolpath.segments);
+    let map = simulator.get_simulation_result().height_map;
+
+    for py in 0..map.height_px {
+        for px in 0..map.width_px {
+            let (x, y) = map.pixel_to_world(px, py);
+            if x.abs().max(y.abs()) > 12.0 {
+                continue;
+            }
+            let surface = pyramid_height(x as f64, y as f64, half, height);
+            let cut = map.get_height_at_pixel(px, py).unwrap() as f64;
+            // Moves may cut across ridges by up to the tolerance
+            assert!(cut >= surface - 0.02, "gouge at ({x}, {y})");
+            assert!(cut <= surface + 0.1, "material left at ({x}, {y})");
+        }
+    }
+}
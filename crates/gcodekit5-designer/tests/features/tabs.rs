@@ -0,0 +1,151 @@
+use crate::common::rectangle_pass;
+use gcodekit5_designer::tabs::{apply_tabs, tab_points, Outline, TabSettings, TabShape};
+use gcodekit5_designer::toolpath::Toolpath;
+use gcodekit5_designer::{
+    Point, Rectangle, Shape, ToolpathGenerator, ToolpathSegment, ToolpathSegmentType,
+};
+
+fn settings(count: u32, width: f64, height: f64, shape: TabShape) -> TabSettings {
+    TabSettings {
+        enabled: true,
+        count,
+        width,
+        height,
+        shape,
+        positions: Vec::new(),
+    }
+}
+
+fn lifted(toolpath: &Toolpath, z: f64) -> Vec<&ToolpathSegment> {
+    toolpath
+        .segments
+        .iter()
+        .filter(|s| s.start_z == Some(z) && s.z_depth == Some(z))
+        .collect()
+}
+
+#[test]
+fn test_auto_tabs_keep_clear_of_corners() {
+    let shape = Shape::Rectangle(Rectangle::new(0.0, 0.0, 40.0, 20.0));
+    let tabs = settings(4, 5.0, 2.0, TabShape::Rectangular);
+
+    let points = tab_points(&shape, &tabs);
+    assert_eq!(points.len(), 4);
+    for p in &points {
+        for (cx, cy) in [(0.0, 0.0), (40.0, 0.0), (40.0, 20.0), (0.0, 20.0)] {
+            assert!(p.distance_to(&Point::new(cx, cy)) >= 5.0 - 1e-6);
+        }
+        let on_edge = p.x.abs() < 1e-6
+            || (p.x - 40.0).abs() < 1e-6
+            || p.y.abs() < 1e-6
+            || (p.y - 20.0).abs() < 1e-6;
+        assert!(on_edge, "tab {:?} is off the outline", p);
+    }
+}
+
+#[test]
+fn test_disabled_tabs_have_no_points() {
+    let shape = Shape::Rectangle(Rectangle::new(0.0, 0.0, 40.0, 20.0));
+    assert!(tab_points(&shape, &TabSettings::default()).is_empty());
+}
+
+#[test]
+fn test_tabs_lift_only_passes_below_tab_top() {
+    let tabs = settings(1, 4.0, 2.0, TabShape::Rectangular);
+    let points = [Point::new(20.0, 0.0)];
+
+    let shallow = apply_tabs(&rectangle_pass(-1.0), &points, &tabs, -6.0);
+    assert_eq!(shallow.segments.len(), 5);
+    assert!(shallow.segments.iter().all(|s| s.start_z.is_none()));
+
+    // The bridge is 4mm wide plus the 2mm tool: 17..23 along the bottom edge
+    let deep = apply_tabs(&rectangle_pass(-6.0), &points, &tabs, -6.0);
+    let over = lifted(&deep, -4.0);
+    assert_eq!(over.len(), 1);
+    assert!((over[0].start.x - 17.0).abs() < 1e-9);
+    assert!((over[0].end.x - 23.0).abs() < 1e-9);
+    assert_eq!(deep.segments.len(), 7);
+}
+
+#[test]
+fn test_triangular_tabs_ramp_over_the_bridge() {
+    let tabs = settings(1, 4.0, 2.0, TabShape::Triangular);
+    let deep = apply_tabs(&rectangle_pass(-6.0), &[Point::new(20.0, 0.0)], &tabs, -6.0);
+
+    let ramps = deep
+        .segments
+        .iter()
+        .filter(|s| matches!((s.start_z, s.z_depth), (Some(a), Some(b)) if (a - b).abs() > 1e-9))
+        .count();
+    assert_eq!(ramps, 2);
+    let top = deep
+        .segments
+        .iter()
+        .filter_map(|s| s.z_depth)
+        .fold(f64::MIN, f64::max);
+    assert!((top + 4.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_tab_splits_arc() {
+    let center = Point::new(0.0, 0.0);
+    let mut circle = Toolpath::new(2.0, -6.0);
+    circle.add_segment(ToolpathSegment::new(
+        ToolpathSegmentType::RapidMove,
+        center,
+        Point::new(10.0, 0.0),
+        100.0,
+        1000,
+    ));
+    circle.add_segment(ToolpathSegment::new_arc(
+        ToolpathSegmentType::ArcCW,
+        Point::new(10.0, 0.0),
+        Point::new(10.0, 0.0),
+        center,
+        100.0,
+        1000,
+    ));
+
+    let tabs = settings(1, 4.0, 2.0, TabShape::Rectangular);
+    let out = apply_tabs(&circle, &[Point::new(0.0, -10.5)], &tabs, -6.0);
+    let over = lifted(&out, -4.0);
+    assert_eq!(over.len(), 1);
+    assert_eq!(over[0].segment_type, ToolpathSegmentType::ArcCW);
+    let sweep = over[0].start.x.atan2(-over[0].start.y) - over[0].end.x.atan2(-over[0].end.y);
+    assert!((10.0 * sweep.abs() - 6.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_manual_positions_follow_outline() {
+    let shape = Shape::Rectangle(Rectangle::new(0.0, 0.0, 40.0, 20.0));
+    let outline = Outline::from_shape(&shape);
+    assert!((outline.length() - 120.0).abs() < 1e-6);
+
+    let fraction = outline.project(Point::new(30.0, 21.0)).unwrap();
+    let mut tabs = settings(4, 5.0, 2.0, TabShape::Rectangular);
+    tabs.positions = vec![fraction];
+    assert!(tabs.is_manual());
+
+    let points = tab_points(&shape, &tabs);
+    assert_eq!(points.len(), 1);
+    assert!(points[0].distance_to(&Point::new(30.0, 20.0)) < 1e-6);
+}
+
+#[test]
+fn test_generator_tabs_only_on_final_pass() {
+    let rect = Rectangle::new(0.0, 0.0, 40.0, 20.0);
+    let tabs = settings(4, 5.0, 2.0, TabShape::Rectangular);
+    let points = tab_points(&Shape::Rectangle(rect.clone()), &tabs);
+
+    let mut gen = ToolpathGenerator::new();
+    gen.set_tool_diameter(2.0);
+    gen.set_start_depth(0.0);
+    gen.set_cut_depth(6.0);
+    gen.set_tabs(tabs, points);
+
+    let passes = gen.generate_rectangle_contour(&rect, 2.0);
+    assert_eq!(passes.len(), 3);
+    assert!(lifted(&passes[0], -4.0).is_empty());
+    assert!(lifted(&passes[1], -4.0).is_empty());
+    assert_eq!(lifted(&passes[2], -4.0).len(), 4);
+}
@@ -0,0 +1,192 @@
+use crate::common::segment;
+use gcodekit5_designer::tool_changes::{
+    apply_rest_machining, group_by_tool, AssignedTool, ToolChangeSettings,
+};
+use gcodekit5_designer::toolpath::{Toolpath, ToolpathSegmentType};
+
+const FEED: f64 = 100.0;
+const SPINDLE: u32 = 1000;
+
+fn tool(id: &str, number: u32, diameter: f64, rest_machining: bool) -> AssignedTool {
+    AssignedTool {
+        id: id.to_string(),
+        number,
+        name: id.to_string(),
+        diameter,
+        rest_machining,
+        ..Default::default()
+    }
+}
+
+/// Zig-zag raster over a `size` square for a tool of `diameter`.
+fn raster(size: f64, diameter: f64, step: f64, depth: f64) -> Toolpath {
+    let r = diameter / 2.0;
+    let mut toolpath = Toolpath::new(diameter, depth);
+    let mut y = r;
+    let mut last: Option<(f64, f64)> = None;
+    let mut forward = true;
+    while y <= size - r + 1e-9 {
+        let (a, b) = if forward {
+            ((r, y), (size - r, y))
+        } else {
+            ((size - r, y), (r, y))
+        };
+        match last {
+            None => toolpath.add_segment(segment(
+                ToolpathSegmentType::RapidMove,
+                (0.0, 0.0),
+                a,
+                FEED,
+                SPINDLE,
+            )),
+            Some(prev) => toolpath.add_segment(segment(
+                ToolpathSegmentType::LinearMove,
+                prev,
+                a,
+                FEED,
+                SPINDLE,
+            )),
+        }
+        toolpath.add_segment(segment(
+            ToolpathSegmentType::LinearMove,
+            a,
+            b,
+            FEED,
+            SPINDLE,
+        ));
+        last = Some(b);
+        forward = !forward;
+        y += step;
+    }
+    toolpath
+}
+
+fn cut_length(toolpath: &Toolpath) -> f64 {
+    toolpath
+        .segments
+        .iter()
+        .filter(|s| s.segment_type != ToolpathSegmentType::RapidMove)
+        .map(|s| s.start.distance_to(&s.end))
+        .sum()
+}
+
+#[test]
+fn test_groups_run_largest_tool_first() {
+    let jobs = vec![
+        (Some(tool("small", 2, 1.0, false)), "a", vec![]),
+        (None, "b", vec![]),
+        (Some(tool("big", 5, 6.0, false)), "c", vec![]),
+        (Some(tool("small", 2, 1.0, false)), "d", vec![]),
+    ];
+    let groups = group_by_tool(jobs);
+    let order: Vec<Vec<&str>> = groups
+        .iter()
+        .map(|g| g.jobs.iter().map(|(name, _)| *name).collect())
+        .collect();
+
+    assert_eq!(order, vec![vec!["b"], vec!["c"], vec!["a", "d"]]);
+    assert!(groups[0].tool.is_none());
+    assert_eq!(groups[1].tool.as_ref().map(|t| t.number), Some(5));
+}
+
+#[test]
+fn test_rest_machining_keeps_only_uncut_material() {
+    let big = raster(20.0, 6.0, 2.4, -2.0);
+    let small = raster(20.0, 1.0, 0.4, -2.0);
+
+    let rest = apply_rest_machining(&small, std::slice::from_ref(&big));
+    let kept = cut_length(&rest);
+    assert!(kept > 0.0);
+    assert!(kept < cut_length(&small) * 0.4);
+
+    // Every cutting piece starts where the previous move ended
+    for pair in rest.segments.windows(2) {
+        assert!(pair[0].end.distance_to(&pair[1].start) < 1e-9);
+    }
+}
+
+#[test]
+fn test_rest_machining_ignores_shallower_cuts() {
+    let shallow = raster(20.0, 6.0, 2.4, -1.0);
+    let small = raster(20.0, 1.0, 0.4, -2.0);
+
+    let rest = apply_rest_machining(&small, &[shallow]);
+    assert!((cut_length(&rest) - cut_length(&small)).abs() < 1e-6);
+}
+
+#[test]
+fn test_rest_machining_splits_partially_cleared_move() {
+    let mut big = Toolpath::new(4.0, -2.0);
+    big.add_segment(segment(
+        ToolpathSegmentType::RapidMove,
+        (0.0, 0.0),
+        (0.0, 0.0),
+        FEED,
+        SPINDLE,
+    ));
+    big.add_segment(segment(
+        ToolpathSegmentType::LinearMove,
+        (0.0, 0.0),
+        (10.0, 0.0),
+        FEED,
+        SPINDLE,
+    ));
+
+    let mut cut = segment(
+        ToolpathSegmentType::LinearMove,
+        (0.0, 0.0),
+        (20.0, 0.0),
+        FEED,
+        SPINDLE,
+    );
+    cut.start_z = Some(-2.0);
+    cut.z_depth = Some(-2.0);
+    let mut small = Toolpath::new(1.0, -2.0);
+    small.add_segment(segment(
+        ToolpathSegmentType::RapidMove,
+        (0.0, 0.0),
+        (0.0, 0.0),
+        FEED,
+        SPINDLE,
+    ));
+    small.add_segment(cut);
+
+    let rest = apply_rest_machining(&small, &[big]);
+    assert_eq!(rest.segments.len(), 2);
+    assert_eq!(
+        rest.segments[0].segment_type,
+        ToolpathSegmentType::RapidMove
+    );
+    assert!(rest.segments[1].start.x > 11.0 && rest.segments[1].start.x < 12.5);
+    assert_eq!(rest.segments[1].start_z, Some(-2.0));
+}
+
+#[test]
+fn test_group_applies_rest_machining_from_earlier_tools() {
+    let big = raster(20.0, 6.0, 2.4, -2.0);
+    let small = raster(20.0, 1.0, 0.4, -2.0);
+    let full = cut_length(&small);
+
+    let groups = group_by_tool(vec![
+        (Some(tool("small", 2, 1.0, true)), 1, vec![small]),
+        (Some(tool("big", 1, 6.0, false)), 2, vec![big]),
+    ]);
+
+    assert_eq!(groups[0].jobs[0].0, 2);
+    assert!(cut_length(&groups[1].jobs[0].1[0]) < full);
+}
+
+#[test]
+fn test_tool_change_macro_expansion() {
+    let settings = ToolChangeSettings::default();
+    let gcode = settings.expand(&tool("endmill", 3, 1.0, false), 10.0);
+    assert_eq!(gcode, "M5\nG00 Z10.000\nT3 M6\nM3 S12000\n");
+
+    let custom = ToolChangeSettings {
+        macro_template: "T{tool} M6 ; {name} {diameter}".to_string(),
+    };
+    assert_eq!(
+        custom.expand(&tool("endmill", 7, 3.175, false), 5.0),
+        "T7 M6 ; endmill 3.175\n"
+    );
+}
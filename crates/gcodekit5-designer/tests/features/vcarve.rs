@@ -1,5 +1,9 @@
 use gcodekit5_designer::model::Point;
-use gcodekit5_designer::vcarve::{VBitTool, VCarveGenerator, VCarveParams, VCarveSegment};
+use gcodekit5_designer::vcarve::{
+    medial_axis, MedialPoint, VBitTool, VCarveGenerator, VCarveParams, VCarveSegment,
+    VCarveSettings,
+};
+use gcodekit5_designer::{Rectangle, Shape, ToolpathGenerator};
 
 #[test]
 fn test_vbit_creation() {
@@ -229,3 +233,90 @@ fn test_validate_params_invalid_width() {
     let result = VCarveGenerator::validate_params(&params);
     assert!(result.is_err());
 }
+
+fn rect_ring(x: f64, y: f64, w: f64, h: f64) -> Vec<Point> {
+    vec![
+        Point::new(x, y),
+        Point::new(x + w, y),
+        Point::new(x + w, y + h),
+        Point::new(x, y + h),
+        Point::new(x, y),
+    ]
+}
+
+#[test]
+fn test_medial_axis_follows_strip_centre_line() {
+    let chains = medial_axis(&[rect_ring(0.0, 0.0, 40.0, 4.0)], 0.2, 10.0);
+    let points: Vec<&MedialPoint> = chains.iter().flatten().collect();
+    assert!(!points.is_empty());
+
+    let max_radius = points.iter().map(|p| p.radius).fold(0.0, f64::max);
+    assert!((max_radius - 2.0).abs() < 0.05);
+    for p in points
+        .iter()
+        .filter(|p| p.center.x > 5.0 && p.center.x < 35.0)
+    {
+        assert!((p.center.y - 2.0).abs() < 0.05);
+    }
+}
+
+#[test]
+fn test_medial_axis_caps_radius_and_skips_holes() {
+    let rings = [
+        rect_ring(0.0, 0.0, 20.0, 20.0),
+        rect_ring(8.0, 8.0, 4.0, 4.0),
+    ];
+    let chains = medial_axis(&rings, 0.2, 2.0);
+    assert!(!chains.is_empty());
+    for p in chains.iter().flatten() {
+        assert!(p.radius <= 2.0 + 1e-9);
+        let in_hole =
+            p.center.x > 8.0 && p.center.x < 12.0 && p.center.y > 8.0 && p.center.y < 12.0;
+        assert!(!in_hole);
+    }
+}
+
+#[test]
+fn test_medial_axis_concave_corner() {
+    let l_shape = vec![
+        Point::new(0.0, 0.0),
+        Point::new(20.0, 0.0),
+        Point::new(20.0, 4.0),
+        Point::new(4.0, 4.0),
+        Point::new(4.0, 20.0),
+        Point::new(0.0, 20.0),
+    ];
+    let chains = medial_axis(&[l_shape], 0.2, 10.0);
+
+    // The largest inscribed circle sits in the elbow: r = 4 / (1 + 1/sqrt(2))
+    let elbow = 4.0 * 2f64.sqrt() / (1.0 + 2f64.sqrt());
+    let max_radius = chains
+        .iter()
+        .flatten()
+        .map(|p| p.radius)
+        .fold(0.0, f64::max);
+    assert!((max_radius - elbow).abs() < 0.02);
+}
+
+#[test]
+fn test_generate_vcarve_depth_follows_width() {
+    let mut gen = ToolpathGenerator::new();
+    gen.set_tool_diameter(12.0);
+    gen.set_start_depth(0.0);
+    gen.set_cut_depth(5.0);
+
+    let shape = Shape::Rectangle(Rectangle::new(0.0, 0.0, 40.0, 4.0));
+    let passes = gen.generate_vcarve(&shape, &VCarveSettings::default(), 0.0);
+    assert_eq!(passes.len(), 1);
+
+    // A 90 degree bit cuts a 4mm wide channel 2mm deep
+    let deepest = passes[0]
+        .segments
+        .iter()
+        .filter_map(|s| s.z_depth)
+        .fold(0.0, f64::min);
+    assert!((deepest + 2.0).abs() < 0.05);
+
+    let stepped = gen.generate_vcarve(&shape, &VCarveSettings::default(), 0.5);
+    assert_eq!(stepped.len(), 4);
+}
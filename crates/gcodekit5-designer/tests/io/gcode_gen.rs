@@ -1,6 +1,9 @@
 use gcodekit5_core::Units;
 use gcodekit5_designer::gcode_gen::ToolpathToGcode;
-use gcodekit5_designer::toolpath::{Toolpath, ToolpathGenerator};
+use gcodekit5_designer::model::Point;
+use gcodekit5_designer::toolpath::{
+    Toolpath, ToolpathGenerator, ToolpathSegment, ToolpathSegmentType,
+};
 use gcodekit5_designer::Rectangle;
 
 #[test]
@@ -70,3 +73,38 @@ fn test_rapid_moves_retract_before_xy() {
     let prev_line = lines[origin_idx - 1];
     assert!(prev_line.contains("G00 Z5.000"));
 }
+
+#[test]
+fn test_rapid_between_contours_retracts_then_moves() {
+    let mut toolpath = Toolpath::new(3.175, -1.0);
+    for (x, y) in [(0.0, 0.0), (20.0, 0.0)] {
+        toolpath.add_segment(ToolpathSegment::new(
+            ToolpathSegmentType::RapidMove,
+            Point::new(x, y),
+            Point::new(x, y),
+            200.0,
+            1000,
+        ));
+        toolpath.add_segment(ToolpathSegment::new(
+            ToolpathSegmentType::LinearMove,
+            Point::new(x, y),
+            Point::new(x + 10.0, y),
+            200.0,
+            1000,
+        ));
+    }
+
+    let gcode_gen = ToolpathToGcode::new(Units::MM, 5.0);
+    let body = gcode_gen.generate_body(&toolpath, 10);
+    // The first rapid starts at safe height; the second lifts off the cut
+    // before moving to the next contour
+    let expected = "\
+G00 X0.000 Y0.000 Z5.000\n\
+G01 Z-1.000 F200\n\
+G01 X10.000 Y0.000 F200\n\
+G00 Z5.000\n\
+G00 X20.000 Y0.000 Z5.000\n\
+G01 Z-1.000 F200\n\
+G01 X30.000 Y0.000 F200\n";
+    assert_eq!(body, expected);
+}
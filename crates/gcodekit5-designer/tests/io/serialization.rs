@@ -1,5 +1,11 @@
+use gcodekit5_designer::dogbone::DogboneSettings;
+use gcodekit5_designer::drilling_patterns::DrillSettings;
+use gcodekit5_designer::leads::LeadSettings;
 use gcodekit5_designer::pocket_operations::PocketStrategy;
+use gcodekit5_designer::profile_operations::{CutDirection, CutSide};
 use gcodekit5_designer::serialization::{DesignFile, ShapeData};
+use gcodekit5_designer::tabs::TabSettings;
+use gcodekit5_designer::vcarve::VCarveSettings;
 use tempfile::TempDir;
 
 #[test]
@@ -45,6 +51,14 @@ fn test_save_and_load() {
         ramp_angle: 0.0,
         pocket_strategy: PocketStrategy::ContourParallel,
         raster_fill_ratio: 0.5,
+        cut_side: CutSide::OnLine,
+        cut_direction: CutDirection::Climb,
+        finishing_allowance: 0.0,
+        tabs: TabSettings::default(),
+        leads: LeadSettings::default(),
+        vcarve: VCarveSettings::default(),
+        drill: DrillSettings::default(),
+        tool: None,
         sides: 0,
         teeth: 0,
         module: 0.0,
@@ -57,6 +71,7 @@ fn test_save_and_load() {
         offset: 0.0,
         fillet: 0.0,
         chamfer: 0.0,
+        dogbone: DogboneSettings::default(),
         lock_aspect_ratio: true,
     });
 
@@ -194,6 +209,14 @@ fn create_test_shape(id: i32, shape_type: &str) -> ShapeData {
         ramp_angle: 0.0,
         pocket_strategy: PocketStrategy::ContourParallel,
         raster_fill_ratio: 0.5,
+        cut_side: CutSide::OnLine,
+        cut_direction: CutDirection::Climb,
+        finishing_allowance: 0.0,
+        tabs: TabSettings::default(),
+        leads: LeadSettings::default(),
+        vcarve: VCarveSettings::default(),
+        drill: DrillSettings::default(),
+        tool: None,
         sides: if shape_type == "polygon" { 6 } else { 0 },
         teeth: 0,
         module: 0.0,
@@ -206,6 +229,7 @@ fn create_test_shape(id: i32, shape_type: &str) -> ShapeData {
         offset: 0.0,
         fillet: 0.0,
         chamfer: 0.0,
+        dogbone: DogboneSettings::default(),
         lock_aspect_ratio: true,
     }
 }
@@ -6,6 +6,9 @@ pub mod ui_integration;
 
 pub use error::{DeviceError, DeviceResult, ProfileError, ProfileResult};
 pub use manager::DeviceManager;
-pub use model::{AxisLimits, ControllerType, DeviceProfile, DeviceType};
+pub use model::{
+    AxisBacklash, AxisLimits, ControllerType, DeviceProfile, DeviceType, LaserPowerScaling,
+    LaserProcessing,
+};
 pub use traits::DeviceProfileProvider;
 pub use ui_integration::{DeviceProfileUiModel, DeviceUiController};
@@ -66,6 +66,79 @@ impl Default for AxisLimits {
     }
 }
 
+/// Lost motion per axis when it reverses, in millimetres.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AxisBacklash {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl AxisBacklash {
+    /// Backlash as an `[x, y, z]` array.
+    pub fn as_array(&self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    /// Whether any axis has backlash to compensate.
+    pub fn is_set(&self) -> bool {
+        self.as_array().iter().any(|b| *b > 0.0)
+    }
+}
+
+/// How laser power is scaled when jobs are streamed.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LaserPowerScaling {
+    #[default]
+    None,
+    /// Full power at the reference feed, proportionally less below it
+    ByFeed,
+    /// Power looked up from the material power curve
+    Curve,
+}
+
+impl std::fmt::Display for LaserPowerScaling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::ByFeed => write!(f, "By Feed"),
+            Self::Curve => write!(f, "Power Curve"),
+        }
+    }
+}
+
+/// Processing applied to jobs streamed to a device with a laser.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LaserProcessing {
+    /// Convert M3 (constant power) to M4 (dynamic power)
+    pub dynamic_power: bool,
+    /// Overscan on each end of raster lines in millimetres; zero disables it
+    pub overscan: f64,
+    pub power_scaling: LaserPowerScaling,
+    /// Feed in mm/min that gets full power when scaling by feed; zero uses
+    /// the device's max feed rate
+    pub reference_feed: f64,
+    /// Material power curve as (feed in mm/min, percent of power) points
+    pub power_curve: Vec<(f64, f64)>,
+    /// Drop S words that repeat the modal power
+    pub strip_redundant_power: bool,
+}
+
+impl Default for LaserProcessing {
+    fn default() -> Self {
+        Self {
+            dynamic_power: true,
+            overscan: 0.0,
+            power_scaling: LaserPowerScaling::None,
+            reference_feed: 0.0,
+            power_curve: Vec::new(),
+            strip_redundant_power: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct DeviceProfile {
@@ -81,6 +154,9 @@ pub struct DeviceProfile {
     pub z_axis: AxisLimits,
     pub a_axis: AxisLimits, // Rotary/Aux
 
+    /// Backlash compensated when streaming jobs; zero disables it.
+    pub backlash: AxisBacklash,
+
     // Capabilities
     pub num_axes: u8,
     pub has_spindle: bool,
@@ -93,6 +169,8 @@ pub struct DeviceProfile {
     // Power
     pub cnc_spindle_watts: f64,
     pub laser_watts: f64,
+    /// Laser processing applied to jobs when `has_laser` is set.
+    pub laser: LaserProcessing,
 
     // Connection Settings
     pub connection_type: String,
@@ -106,6 +184,10 @@ pub struct DeviceProfile {
     /// Last known GRBL settings (from `$$`) for this profile (u16 to support grblHAL extended settings up to $680).
     #[serde(default)]
     pub grbl_settings: std::collections::HashMap<u16, String>,
+
+    /// Post-processor definition name; empty picks one for the controller type
+    /// and `"None"` keeps the generic output.
+    pub post_processor: String,
 }
 
 impl Default for DeviceProfile {
@@ -128,6 +210,7 @@ impl Default for DeviceProfile {
                 max: 360.0,
                 enabled: false,
             },
+            backlash: AxisBacklash::default(),
             num_axes: 3,
             has_spindle: true,
             has_laser: false,
@@ -137,6 +220,7 @@ impl Default for DeviceProfile {
             max_spindle_speed_rpm: 12000,
             cnc_spindle_watts: 500.0,
             laser_watts: 5.0,
+            laser: LaserProcessing::default(),
             connection_type: "Serial".to_string(),
             baud_rate: 115200,
             port: "Auto".to_string(),
@@ -145,6 +229,25 @@ impl Default for DeviceProfile {
             timeout_ms: 5000,
             auto_reconnect: false,
             grbl_settings: std::collections::HashMap::new(),
+            post_processor: String::new(),
+        }
+    }
+}
+
+impl DeviceProfile {
+    /// Name of the post-processor definition used for this device.
+    pub fn post_processor_name(&self) -> &str {
+        if !self.post_processor.is_empty() {
+            return &self.post_processor;
+        }
+        match self.controller_type {
+            ControllerType::GrblHal => "grblHAL",
+            ControllerType::Smoothieware => "Smoothieware",
+            ControllerType::Marlin => "Marlin",
+            ControllerType::Grbl
+            | ControllerType::TinyG
+            | ControllerType::G2Core
+            | ControllerType::FluidNC => "GRBL",
         }
     }
 }
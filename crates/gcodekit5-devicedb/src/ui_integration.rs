@@ -1,5 +1,5 @@
 use crate::manager::DeviceManager;
-use crate::model::{ControllerType, DeviceProfile, DeviceType};
+use crate::model::{AxisBacklash, ControllerType, DeviceProfile, DeviceType, LaserPowerScaling};
 use anyhow::Context;
 use std::sync::Arc;
 
@@ -16,6 +16,9 @@ pub struct DeviceProfileUiModel {
     pub y_max: String,
     pub z_min: String,
     pub z_max: String,
+    pub backlash_x: String,
+    pub backlash_y: String,
+    pub backlash_z: String,
     pub num_axes: String,
     pub has_spindle: bool,
     pub has_laser: bool,
@@ -25,12 +28,21 @@ pub struct DeviceProfileUiModel {
     pub max_spindle_speed_rpm: String,
     pub cnc_spindle_watts: String,
     pub laser_watts: String,
+    pub laser_dynamic_power: bool,
+    pub laser_overscan: String,
+    pub laser_power_scaling: String,
+    pub laser_reference_feed: String,
+    /// Power curve as `feed:percent` pairs separated by commas
+    pub laser_power_curve: String,
+    pub laser_strip_redundant_power: bool,
     pub connection_type: String,
     pub baud_rate: String,
     pub port: String,
     pub tcp_port: String,
     pub timeout_ms: String,
     pub auto_reconnect: bool,
+    /// Post-processor name; empty uses the controller default
+    pub post_processor: String,
     pub is_active: bool,
 }
 
@@ -48,6 +60,9 @@ impl From<DeviceProfile> for DeviceProfileUiModel {
             y_max: format!("{:.2}", p.y_axis.max),
             z_min: format!("{:.2}", p.z_axis.min),
             z_max: format!("{:.2}", p.z_axis.max),
+            backlash_x: format!("{:.3}", p.backlash.x),
+            backlash_y: format!("{:.3}", p.backlash.y),
+            backlash_z: format!("{:.3}", p.backlash.z),
             num_axes: p.num_axes.to_string(),
             has_spindle: p.has_spindle,
             has_laser: p.has_laser,
@@ -57,12 +72,19 @@ impl From<DeviceProfile> for DeviceProfileUiModel {
             max_spindle_speed_rpm: p.max_spindle_speed_rpm.to_string(),
             cnc_spindle_watts: format!("{:.0}", p.cnc_spindle_watts),
             laser_watts: format!("{:.0}", p.laser_watts),
+            laser_dynamic_power: p.laser.dynamic_power,
+            laser_overscan: format!("{:.2}", p.laser.overscan),
+            laser_power_scaling: p.laser.power_scaling.to_string(),
+            laser_reference_feed: format!("{:.0}", p.laser.reference_feed),
+            laser_power_curve: format_power_curve(&p.laser.power_curve),
+            laser_strip_redundant_power: p.laser.strip_redundant_power,
             connection_type: p.connection_type,
             baud_rate: p.baud_rate.to_string(),
             port: p.port,
             tcp_port: p.tcp_port.to_string(),
             timeout_ms: p.timeout_ms.to_string(),
             auto_reconnect: p.auto_reconnect,
+            post_processor: p.post_processor,
             is_active: false, // Set separately
         }
     }
@@ -169,6 +191,21 @@ impl DeviceUiController {
         profile.z_axis.min = z_min;
         profile.z_axis.max = z_max;
 
+        for (axis, text, value) in [
+            ("X", &ui_model.backlash_x, &mut profile.backlash.x),
+            ("Y", &ui_model.backlash_y, &mut profile.backlash.y),
+            ("Z", &ui_model.backlash_z, &mut profile.backlash.z),
+        ] {
+            let backlash: f64 = text
+                .trim()
+                .parse()
+                .with_context(|| format!("{} Backlash must be a number (got {})", axis, text))?;
+            if backlash < 0.0 {
+                anyhow::bail!("{} Backlash cannot be negative (got {})", axis, text);
+            }
+            *value = backlash;
+        }
+
         profile.has_spindle = ui_model.has_spindle;
         profile.has_laser = ui_model.has_laser;
         profile.has_coolant = ui_model.has_coolant;
@@ -218,6 +255,47 @@ impl DeviceUiController {
             )
         })?;
 
+        profile.laser.dynamic_power = ui_model.laser_dynamic_power;
+        profile.laser.strip_redundant_power = ui_model.laser_strip_redundant_power;
+        profile.laser.overscan = ui_model.laser_overscan.trim().parse().with_context(|| {
+            format!(
+                "Laser overscan must be a number (got {})",
+                ui_model.laser_overscan
+            )
+        })?;
+        if profile.laser.overscan < 0.0 {
+            anyhow::bail!(
+                "Laser overscan cannot be negative (got {})",
+                ui_model.laser_overscan
+            );
+        }
+        profile.laser.power_scaling = match ui_model.laser_power_scaling.as_str() {
+            "None" | "" => LaserPowerScaling::None,
+            "By Feed" => LaserPowerScaling::ByFeed,
+            "Power Curve" => LaserPowerScaling::Curve,
+            _ => anyhow::bail!(
+                "Unknown laser power scaling: {}",
+                ui_model.laser_power_scaling
+            ),
+        };
+        profile.laser.reference_feed =
+            ui_model
+                .laser_reference_feed
+                .trim()
+                .parse()
+                .with_context(|| {
+                    format!(
+                        "Laser reference feed must be a number (got {})",
+                        ui_model.laser_reference_feed
+                    )
+                })?;
+        profile.laser.power_curve = parse_power_curve(&ui_model.laser_power_curve)?;
+        if profile.laser.power_scaling == LaserPowerScaling::Curve
+            && profile.laser.power_curve.is_empty()
+        {
+            anyhow::bail!("Power curve scaling needs at least one feed:percent point");
+        }
+
         profile.connection_type = ui_model.connection_type;
         profile.baud_rate = ui_model.baud_rate.trim().parse().with_context(|| {
             format!("Baud Rate must be an integer (got {})", ui_model.baud_rate)
@@ -232,6 +310,7 @@ impl DeviceUiController {
                 format!("Timeout must be an integer (got {})", ui_model.timeout_ms)
             })?;
         profile.auto_reconnect = ui_model.auto_reconnect;
+        profile.post_processor = ui_model.post_processor.trim().to_string();
 
         self.manager.save_profile(profile)
     }
@@ -250,4 +329,54 @@ impl DeviceUiController {
     pub fn set_active_profile(&self, id: &str) -> anyhow::Result<()> {
         self.manager.set_active_profile(id)
     }
+
+    /// Post-processor name of the active profile, if there is one
+    pub fn active_post_processor_name(&self) -> Option<String> {
+        self.manager
+            .get_active_profile()
+            .map(|p| p.post_processor_name().to_string())
+    }
+
+    /// Backlash of the active profile, if there is one
+    pub fn active_backlash(&self) -> Option<AxisBacklash> {
+        self.manager.get_active_profile().map(|p| p.backlash)
+    }
+
+    /// The active profile, if there is one
+    pub fn active_profile(&self) -> Option<DeviceProfile> {
+        self.manager.get_active_profile()
+    }
+}
+
+fn format_power_curve(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(feed, percent)| format!("{}:{}", feed, percent))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse `feed:percent` pairs separated by commas
+fn parse_power_curve(text: &str) -> anyhow::Result<Vec<(f64, f64)>> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|point| !point.is_empty())
+        .map(|point| {
+            let (feed, percent) = point.split_once(':').with_context(|| {
+                format!("Power curve point must be feed:percent (got {})", point)
+            })?;
+            let feed: f64 = feed
+                .trim()
+                .parse()
+                .with_context(|| format!("Power curve feed must be a number (got {})", point))?;
+            let percent: f64 = percent
+                .trim()
+                .parse()
+                .with_context(|| format!("Power curve percent must be a number (got {})", point))?;
+            if feed < 0.0 || !(0.0..=100.0).contains(&percent) {
+                anyhow::bail!("Power curve point out of range (got {})", point);
+            }
+            Ok((feed, percent))
+        })
+        .collect()
 }
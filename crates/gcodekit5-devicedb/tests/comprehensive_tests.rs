@@ -1,7 +1,9 @@
 use gcodekit5_devicedb::{
     ControllerType, DeviceManager, DeviceProfile, DeviceProfileUiModel, DeviceType,
+    DeviceUiController, LaserPowerScaling,
 };
 use std::path::PathBuf;
+use std::sync::Arc;
 
 fn temp_path(name: &str) -> PathBuf {
     std::env::temp_dir()
@@ -46,6 +48,84 @@ fn test_device_profile_serialization() {
     assert_eq!(deser.num_axes, profile.num_axes);
 }
 
+#[test]
+fn test_device_profile_post_processor() {
+    let mut profile = DeviceProfile::default();
+    assert_eq!(profile.post_processor_name(), "GRBL");
+    profile.controller_type = ControllerType::Marlin;
+    assert_eq!(profile.post_processor_name(), "Marlin");
+    profile.post_processor = "LinuxCNC".to_string();
+    assert_eq!(profile.post_processor_name(), "LinuxCNC");
+
+    // Profiles saved before the field existed load with the default
+    let mut json: serde_json::Value = serde_json::to_value(&profile).unwrap();
+    json.as_object_mut().unwrap().remove("post_processor");
+    let deser: DeviceProfile = serde_json::from_value(json).unwrap();
+    assert!(deser.post_processor.is_empty());
+}
+
+#[test]
+fn test_device_profile_backlash() {
+    let mut profile = DeviceProfile::default();
+    assert!(!profile.backlash.is_set());
+    profile.backlash.x = 0.05;
+    profile.backlash.z = 0.12;
+    assert!(profile.backlash.is_set());
+    assert_eq!(profile.backlash.as_array(), [0.05, 0.0, 0.12]);
+
+    let ui_model: DeviceProfileUiModel = profile.clone().into();
+    assert_eq!(ui_model.backlash_x, "0.050");
+    assert_eq!(ui_model.backlash_y, "0.000");
+
+    // Profiles saved before the field existed load without compensation
+    let mut json: serde_json::Value = serde_json::to_value(&profile).unwrap();
+    json.as_object_mut().unwrap().remove("backlash");
+    let deser: DeviceProfile = serde_json::from_value(json).unwrap();
+    assert!(!deser.backlash.is_set());
+}
+
+#[test]
+fn test_device_profile_laser_processing() {
+    let manager = Arc::new(setup_manager("laser_processing"));
+    let profile = DeviceProfile::default();
+    assert!(profile.laser.dynamic_power);
+    assert!(profile.laser.strip_redundant_power);
+    assert_eq!(profile.laser.power_scaling, LaserPowerScaling::None);
+    let id = profile.id.clone();
+    manager.save_profile(profile).unwrap();
+
+    let controller = DeviceUiController::new(manager.clone());
+    let mut ui_model: DeviceProfileUiModel = manager.get_profile(&id).unwrap().into();
+    assert_eq!(ui_model.laser_power_scaling, "None");
+    ui_model.has_laser = true;
+    ui_model.laser_overscan = "2.5".to_string();
+    ui_model.laser_power_scaling = "Power Curve".to_string();
+    ui_model.laser_power_curve = "1000:40, 3000 : 100".to_string();
+    controller.update_profile_from_ui(ui_model.clone()).unwrap();
+
+    let saved = manager.get_profile(&id).unwrap();
+    assert_eq!(saved.laser.overscan, 2.5);
+    assert_eq!(saved.laser.power_scaling, LaserPowerScaling::Curve);
+    assert_eq!(
+        saved.laser.power_curve,
+        vec![(1000.0, 40.0), (3000.0, 100.0)]
+    );
+    let round_trip: DeviceProfileUiModel = saved.into();
+    assert_eq!(round_trip.laser_power_curve, "1000:40, 3000:100");
+
+    ui_model.laser_power_curve = "1000:140".to_string();
+    assert!(controller.update_profile_from_ui(ui_model.clone()).is_err());
+    ui_model.laser_power_curve = String::new();
+    assert!(controller.update_profile_from_ui(ui_model).is_err());
+
+    // Profiles saved before the field existed load with the defaults
+    let mut json: serde_json::Value = serde_json::to_value(DeviceProfile::default()).unwrap();
+    json.as_object_mut().unwrap().remove("laser");
+    let deser: DeviceProfile = serde_json::from_value(json).unwrap();
+    assert!(deser.laser.dynamic_power);
+    assert_eq!(deser.laser.overscan, 0.0);
+}
+
 #[test]
 fn test_device_type_display() {
     assert_eq!(DeviceType::CncMill.to_string(), "CNC Mill");
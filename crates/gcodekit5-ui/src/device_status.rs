@@ -3,7 +3,9 @@
 use gcodekit5_communication::firmware::grbl::status_parser::{
     BufferRxState, FeedSpindleState, MachinePosition, WorkCoordinateOffset, WorkPosition,
 };
-use gcodekit5_core::{thread_safe_rw, ThreadSafeRw};
+use gcodekit5_core::{thread_safe_rw, PostDefinition, PostLibrary, ThreadSafeRw};
+use gcodekit5_devicedb::{AxisBacklash, DeviceProfile, LaserPowerScaling};
+use gcodekit5_visualizer::{LaserSettings, PowerCurve, PowerScaling};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU8, Ordering};
@@ -81,6 +83,96 @@ pub fn set_active_num_axes(n: u8) {
     ACTIVE_NUM_AXES.store(n, Ordering::Relaxed);
 }
 
+/// Post-processor of the active device (`None` keeps the generic output).
+static ACTIVE_POST_PROCESSOR: Lazy<ThreadSafeRw<Option<PostDefinition>>> =
+    Lazy::new(|| thread_safe_rw(None));
+
+/// Built-in post-processors plus the user's definitions in
+/// `<config>/gcodekit5/post_processors`.
+pub fn post_library() -> PostLibrary {
+    let mut library = PostLibrary::with_builtins();
+    let dir = dirs::config_dir()
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .join("gcodekit5")
+        .join("post_processors");
+    if dir.is_dir() {
+        match library.load_dir(&dir) {
+            Ok(failures) => {
+                for (path, e) in failures {
+                    tracing::warn!("Skipping post-processor {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to read {}: {}", dir.display(), e),
+        }
+    }
+    library
+}
+
+/// Returns the post-processor for the active device.
+pub fn get_active_post_processor() -> Option<PostDefinition> {
+    ACTIVE_POST_PROCESSOR.read().clone()
+}
+
+/// Selects the active post-processor by name; unknown names (or "None") clear it.
+pub fn set_active_post_processor(name: &str) {
+    *ACTIVE_POST_PROCESSOR.write() = post_library().get(name).cloned();
+}
+
+/// Backlash of the active device, compensated when streaming jobs.
+static ACTIVE_BACKLASH: Lazy<ThreadSafeRw<AxisBacklash>> =
+    Lazy::new(|| thread_safe_rw(AxisBacklash::default()));
+
+/// Returns the backlash configured on the active device.
+pub fn get_active_backlash() -> AxisBacklash {
+    *ACTIVE_BACKLASH.read()
+}
+
+/// Sets the backlash of the active device.
+pub fn set_active_backlash(backlash: AxisBacklash) {
+    *ACTIVE_BACKLASH.write() = backlash;
+}
+
+/// Laser processing of the active device, `None` when it has no laser.
+static ACTIVE_LASER: Lazy<ThreadSafeRw<Option<LaserSettings>>> = Lazy::new(|| thread_safe_rw(None));
+
+/// Returns the laser processing applied to jobs on the active device.
+pub fn get_active_laser() -> Option<LaserSettings> {
+    ACTIVE_LASER.read().clone()
+}
+
+/// Selects the laser processing of the active device from its profile.
+pub fn set_active_laser(profile: &DeviceProfile) {
+    *ACTIVE_LASER.write() = laser_settings(profile);
+}
+
+/// Laser processing a device profile asks for; `None` without a laser.
+pub fn laser_settings(profile: &DeviceProfile) -> Option<LaserSettings> {
+    if !profile.has_laser {
+        return None;
+    }
+    let laser = &profile.laser;
+    let power_scaling = match laser.power_scaling {
+        LaserPowerScaling::None => None,
+        LaserPowerScaling::ByFeed => Some(PowerScaling::ByFeed {
+            reference_feed: if laser.reference_feed > 0.0 {
+                laser.reference_feed
+            } else {
+                profile.max_feed_rate
+            },
+        }),
+        LaserPowerScaling::Curve => Some(PowerScaling::Curve(PowerCurve::new(
+            laser.power_curve.iter().copied(),
+        ))),
+    };
+    Some(LaserSettings {
+        dynamic_power: laser.dynamic_power,
+        overscan: laser.overscan,
+        power_scaling,
+        max_power: Some(profile.max_s_value),
+        strip_redundant_power: laser.strip_redundant_power,
+    })
+}
+
 /// Update the machine state
 pub fn update_state(state: String) {
     {
@@ -6,12 +6,19 @@ use crate::ui::gtk::device_console::DeviceConsoleView;
 use crate::device_status;
 use crate::i18n;
 use crate::t;
+use crate::ui::gtk::auto_level::show_auto_level_dialog;
+use crate::ui::gtk::backlash_wizard::show_backlash_wizard;
+use crate::ui::gtk::gcode_compare::show_compare_dialog;
+use crate::ui::gtk::gcode_transform::show_transform_dialog;
+use crate::ui::gtk::job_report::export_job_report;
 use crate::ui::gtk::device_manager::DeviceManagerWindow;
 use crate::ui::gtk::editor::GcodeEditor;
 use crate::ui::gtk::machine_control::MachineControlView;
 use crate::ui::gtk::materials_manager::MaterialsManagerView;
+use crate::ui::gtk::recent_files::show_recent_files_dialog;
 use crate::ui::gtk::settings::SettingsWindow;
 use crate::ui::gtk::status_bar::StatusBar;
+use crate::ui::gtk::thumbnails;
 use crate::ui::gtk::tools_manager::ToolsManagerView;
 use crate::ui::gtk::visualizer::GcodeVisualizer;
 use gcodekit5_communication::Communicator;
@@ -24,7 +31,7 @@ use gtk4::{
 };
 use std::cell::RefCell;
 use std::rc::Rc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 pub fn main() {
     let app = Application::builder()
@@ -113,6 +120,9 @@ pub fn main() {
         // Sync active device num_axes to global state
         if let Some(profile) = device_manager.get_active_profile() {
             crate::device_status::set_active_num_axes(profile.num_axes);
+            crate::device_status::set_active_post_processor(profile.post_processor_name());
+            crate::device_status::set_active_backlash(profile.backlash);
+            crate::device_status::set_active_laser(&profile);
         }
         let device_controller = Rc::new(gcodekit5_devicedb::DeviceUiController::new(
             device_manager.clone(),
@@ -138,11 +148,13 @@ pub fn main() {
         let file_menu = gio::Menu::new();
         file_menu.append(Some(&t!("New")), Some("app.file_new"));
         file_menu.append(Some(&t!("Open")), Some("app.file_open"));
+        file_menu.append(Some(&t!("Open Recent...")), Some("app.file_open_recent"));
         file_menu.append(Some(&t!("Save")), Some("app.file_save"));
         file_menu.append(Some(&t!("Save As...")), Some("app.file_save_as"));
         file_menu.append(Some(&t!("Import")), Some("app.file_import"));
         file_menu.append(Some(&t!("Export G-Code...")), Some("app.file_export_gcode"));
         file_menu.append(Some(&t!("Export SVG...")), Some("app.file_export_svg"));
+        file_menu.append(Some(&t!("Export Job Report...")), Some("app.file_export_report"));
         file_menu.append(Some(&t!("Run")), Some("app.file_run"));
         file_menu.append(Some(&t!("Quit")), Some("app.quit"));
         menu_bar_model.append_submenu(Some(&t!("File")), &file_menu);
@@ -153,6 +165,14 @@ pub fn main() {
         edit_menu.append(Some(&t!("Cut")), Some("app.edit_cut"));
         edit_menu.append(Some(&t!("Copy")), Some("app.edit_copy"));
         edit_menu.append(Some(&t!("Paste")), Some("app.edit_paste"));
+        edit_menu.append(
+            Some(&t!("Transform G-code...")),
+            Some("app.edit_transform_gcode"),
+        );
+        edit_menu.append(
+            Some(&t!("Compare G-code...")),
+            Some("app.edit_compare_gcode"),
+        );
         edit_menu.append(Some(&t!("Preferences")), Some("app.preferences"));
         menu_bar_model.append_submenu(Some(&t!("Edit")), &edit_menu);
 
@@ -161,6 +181,11 @@ pub fn main() {
         machine_menu.append(Some(&t!("Disconnect")), Some("app.machine_disconnect"));
         machine_menu.append(Some(&t!("Home")), Some("app.machine_home"));
         machine_menu.append(Some(&t!("Reset")), Some("app.machine_reset"));
+        machine_menu.append(Some(&t!("Auto-Level...")), Some("app.machine_auto_level"));
+        machine_menu.append(
+            Some(&t!("Measure Backlash...")),
+            Some("app.machine_measure_backlash"),
+        );
         menu_bar_model.append_submenu(Some(&t!("Machine")), &machine_menu);
 
         let help_menu = gio::Menu::new();
@@ -274,6 +299,40 @@ pub fn main() {
             vis_clone.set_gcode(&text);
         });
 
+        // Show collision check results from the visualizer as editor marks
+        let editor_issues = editor.clone();
+        visualizer.set_on_collision_issues(move |issues| {
+            let issues: Vec<_> = issues
+                .iter()
+                .map(|issue| issue.to_validation_issue())
+                .collect();
+            editor_issues.set_validation_issues(&issues);
+        });
+
+        // Keep the visualizer timeline and the editor cursor on the same line
+        let editor_timeline = editor.clone();
+        visualizer.set_on_timeline_line(move |line| editor_timeline.goto_line(line));
+        let vis_timeline = visualizer.clone();
+        editor.connect_cursor_line_changed(move |line| vis_timeline.select_line(line));
+
+        // Remember opened G-code files and refresh their thumbnails
+        let persistence_recent = settings_persistence.clone();
+        editor.set_on_file_opened(move |path, content| {
+            let mut persistence = persistence_recent.borrow_mut();
+            persistence.config_mut().add_recent_file(path.to_path_buf());
+            if let Ok(config_path) = gcodekit5_settings::SettingsManager::config_file_path() {
+                if let Err(e) = persistence.save_to_file(&config_path) {
+                    warn!("Failed to save recent files: {}", e);
+                }
+            }
+
+            let path = path.to_path_buf();
+            let content = content.to_string();
+            std::thread::spawn(move || {
+                thumbnails::write_thumbnail(&path, &content);
+            });
+        });
+
         // 5. CAM Tools
         // First create the designer view so we can pass it to CAM tools
         let designer = DesignerView::new(
@@ -487,6 +546,34 @@ pub fn main() {
         });
         app.add_action(&run_action);
 
+        // Auto-level the editor program from a probed height map
+        let auto_level_action = gio::SimpleAction::new("machine_auto_level", None);
+        let machine_control_level = machine_control.clone();
+        let editor_level = editor.clone();
+        let window_level = window.clone();
+        auto_level_action.connect_activate(move |_, _| {
+            show_auto_level_dialog(
+                Some(window_level.upcast_ref()),
+                &machine_control_level,
+                editor_level.clone(),
+            );
+        });
+        app.add_action(&auto_level_action);
+
+        // Measure backlash with a dial indicator and save it to the active device
+        let backlash_action = gio::SimpleAction::new("machine_measure_backlash", None);
+        let machine_control_backlash = machine_control.clone();
+        let device_manager_backlash = device_manager.clone();
+        let window_backlash = window.clone();
+        backlash_action.connect_activate(move |_, _| {
+            show_backlash_wizard(
+                Some(window_backlash.upcast_ref()),
+                &machine_control_backlash,
+                device_manager_backlash.clone(),
+            );
+        });
+        app.add_action(&backlash_action);
+
         // Generate Frame
         let editor_frame = editor.clone();
         let designer_frame = designer.clone();
@@ -539,6 +626,22 @@ pub fn main() {
         });
         app.add_action(&open_action);
 
+        let stack_clone = stack.clone();
+        let editor_clone = editor.clone();
+        let persistence_recent = settings_persistence.clone();
+        let window_recent = window.clone();
+        let open_recent_action = gio::SimpleAction::new("file_open_recent", None);
+        open_recent_action.connect_activate(move |_, _| {
+            let files = persistence_recent.borrow().config().recent_files.clone();
+            let stack = stack_clone.clone();
+            let editor = editor_clone.clone();
+            show_recent_files_dialog(Some(window_recent.upcast_ref()), &files, move |path| {
+                stack.set_visible_child_name("editor");
+                editor.open_path(path);
+            });
+        });
+        app.add_action(&open_recent_action);
+
         let stack_clone = stack.clone();
         let designer_clone = designer.clone();
         let editor_clone = editor.clone();
@@ -605,6 +708,15 @@ pub fn main() {
         });
         app.add_action(&export_svg_action);
 
+        // Analysis report of the editor program
+        let editor_report = editor.clone();
+        let window_report = window.clone();
+        let export_report_action = gio::SimpleAction::new("file_export_report", None);
+        export_report_action.connect_activate(move |_, _| {
+            export_job_report(Some(window_report.upcast_ref()), &editor_report);
+        });
+        app.add_action(&export_report_action);
+
         // About Dialog Action
         let app_clone = app.clone();
         let about_action = gio::SimpleAction::new("about", None);
@@ -737,6 +849,32 @@ pub fn main() {
         });
         app.add_action(&paste_action);
 
+        // Translate/rotate/mirror/scale the editor program
+        let editor_transform = editor.clone();
+        let window_transform = window.clone();
+        let transform_action = gio::SimpleAction::new("edit_transform_gcode", None);
+        transform_action.connect_activate(move |_, _| {
+            show_transform_dialog(
+                Some(window_transform.upcast_ref()),
+                editor_transform.clone(),
+            );
+        });
+        app.add_action(&transform_action);
+
+        // Geometric comparison against another program
+        let editor_compare = editor.clone();
+        let visualizer_compare = visualizer.clone();
+        let window_compare = window.clone();
+        let compare_action = gio::SimpleAction::new("edit_compare_gcode", None);
+        compare_action.connect_activate(move |_, _| {
+            show_compare_dialog(
+                Some(window_compare.upcast_ref()),
+                editor_compare.clone(),
+                visualizer_compare.clone(),
+            );
+        });
+        app.add_action(&compare_action);
+
         // Placeholder Actions for remaining items
         let action_names = vec![
             "quit",
@@ -790,6 +928,8 @@ pub fn main() {
                 set_enabled("edit_cut", is_designer || is_editor);
                 set_enabled("edit_copy", is_designer || is_editor);
                 set_enabled("edit_paste", is_designer || is_editor);
+                set_enabled("edit_transform_gcode", is_editor);
+                set_enabled("edit_compare_gcode", is_editor);
 
                 // File actions
                 set_enabled("file_new", is_designer || is_editor);
@@ -799,6 +939,7 @@ pub fn main() {
                 set_enabled("file_import", is_designer);
                 set_enabled("file_export_gcode", is_designer);
                 set_enabled("file_export_svg", is_designer);
+                set_enabled("file_export_report", is_editor);
             }
         });
 
@@ -0,0 +1,337 @@
+//! Auto-level dialog.
+//!
+//! Probes a height map over the program loaded in the editor, saves and
+//! loads meshes, and rewrites the program so Z follows the probed surface.
+
+use super::editor::GcodeEditor;
+use super::file_dialog;
+use super::machine_control::MachineControlView;
+use crate::t;
+use gcodekit5_communication::Communicator;
+use gcodekit5_visualizer::{AutoLeveler, ProbeGrid, ProbeMesh};
+use gtk4::prelude::*;
+use gtk4::{
+    glib, Align, Box, Button, FileFilter, Grid, Label, Orientation, ResponseType, SpinButton,
+    Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Show the auto-level dialog for the editor program
+pub fn show_auto_level_dialog(
+    parent: Option<&Window>,
+    machine_control: &MachineControlView,
+    editor: Rc<GcodeEditor>,
+) {
+    let window = Window::builder()
+        .title(t!("Auto-Level"))
+        .modal(true)
+        .default_width(420)
+        .build();
+    if let Some(parent) = parent {
+        window.set_transient_for(Some(parent));
+    }
+
+    let content = Box::new(Orientation::Vertical, 12);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let grid = Grid::new();
+    grid.set_row_spacing(6);
+    grid.set_column_spacing(12);
+    let mut row = 0;
+    let mut add_spin = |label: &str, tooltip: &str, min: f64, max: f64, step: f64, value: f64| {
+        let spin = SpinButton::with_range(min, max, step);
+        spin.set_digits(if step < 1.0 { 2 } else { 1 });
+        spin.set_value(value);
+        spin.set_hexpand(true);
+        spin.set_tooltip_text(Some(tooltip));
+        let label = Label::new(Some(label));
+        label.set_halign(Align::Start);
+        grid.attach(&label, 0, row, 1, 1);
+        grid.attach(&spin, 1, row, 1, 1);
+        row += 1;
+        spin
+    };
+    let spacing = add_spin(
+        &t!("Grid spacing (mm)"),
+        &t!("Largest distance between probe points"),
+        1.0,
+        200.0,
+        1.0,
+        10.0,
+    );
+    let margin = add_spin(
+        &t!("Margin (mm)"),
+        &t!("Extend the probed area beyond the cutting moves"),
+        0.0,
+        50.0,
+        0.5,
+        2.0,
+    );
+    let clearance = add_spin(
+        &t!("Clearance Z (mm)"),
+        &t!("Height for moves between probe points"),
+        0.5,
+        50.0,
+        0.5,
+        2.0,
+    );
+    let probe_depth = add_spin(
+        &t!("Probe to Z (mm)"),
+        &t!("Lowest Z the probe may reach before the cycle fails"),
+        -50.0,
+        -0.1,
+        0.5,
+        -5.0,
+    );
+    let probe_feed = add_spin(
+        &t!("Probe feed (mm/min)"),
+        &t!("Feed rate for the probing moves"),
+        1.0,
+        1000.0,
+        5.0,
+        50.0,
+    );
+    let segment = add_spin(
+        &t!("Segment length (mm)"),
+        &t!("Feed moves are split into segments of at most this length"),
+        0.1,
+        20.0,
+        0.1,
+        1.0,
+    );
+    content.append(&grid);
+
+    let status = Label::new(Some(&t!(
+        "Set work Z zero on the surface at X0 Y0 and attach the probe."
+    )));
+    status.set_wrap(true);
+    status.set_halign(Align::Start);
+    status.add_css_class("dim-label");
+    content.append(&status);
+
+    let buttons = Box::new(Orientation::Horizontal, 6);
+    buttons.set_halign(Align::End);
+    let load_btn = Button::with_label(&t!("Load Mesh..."));
+    let save_btn = Button::with_label(&t!("Save Mesh..."));
+    let probe_btn = Button::with_label(&t!("Probe"));
+    let apply_btn = Button::with_label(&t!("Apply to Program"));
+    apply_btn.add_css_class("suggested-action");
+    let close_btn = Button::with_label(&t!("Close"));
+    save_btn.set_sensitive(false);
+    apply_btn.set_sensitive(false);
+    for button in [&load_btn, &save_btn, &probe_btn, &apply_btn, &close_btn] {
+        buttons.append(button);
+    }
+    content.append(&buttons);
+    window.set_child(Some(&content));
+
+    let mesh: Rc<RefCell<Option<ProbeMesh>>> = Rc::new(RefCell::new(None));
+    let set_mesh = {
+        let mesh = mesh.clone();
+        let status = status.clone();
+        let save_btn = save_btn.clone();
+        let apply_btn = apply_btn.clone();
+        Rc::new(move |new_mesh: ProbeMesh| {
+            let (count, z_min, z_max) = new_mesh.stats();
+            status.set_text(&format!(
+                "{} {} ({} {:.3} .. {:.3} mm)",
+                count,
+                t!("probe points"),
+                t!("Z range"),
+                z_min,
+                z_max
+            ));
+            *mesh.borrow_mut() = Some(new_mesh);
+            save_btn.set_sensitive(true);
+            apply_btn.set_sensitive(true);
+        })
+    };
+
+    // Probe the grid over the editor program
+    let mc = machine_control.clone();
+    let editor_probe = editor.clone();
+    let window_probe = window.clone();
+    let status_probe = status.clone();
+    let set_mesh_probe = set_mesh.clone();
+    probe_btn.connect_clicked(move |probe_btn| {
+        let error = |message: String| {
+            file_dialog::show_error_dialog(&t!("Auto-Level"), &message, Some(&window_probe));
+        };
+        if !mc.communicator.lock().is_connected() {
+            error(t!("Connect to the machine before probing."));
+            return;
+        }
+        if *mc.is_streaming.lock() {
+            error(t!("A job is already running."));
+            return;
+        }
+        let Some(probe_grid) =
+            ProbeGrid::for_program(&editor_probe.get_text(), spacing.value(), margin.value())
+        else {
+            error(t!("The program has no cutting moves to probe."));
+            return;
+        };
+        let probe_grid =
+            probe_grid.with_probe(clearance.value(), probe_depth.value(), probe_feed.value());
+
+        mc.probing.lock().results.clear();
+        mc.start_job(&probe_grid.to_gcode());
+        probe_btn.set_sensitive(false);
+        status_probe.set_text(&format!(
+            "{} 0 / {}",
+            t!("Probing"),
+            probe_grid.point_count()
+        ));
+
+        let mc = mc.clone();
+        let status = status_probe.clone();
+        let probe_btn = probe_btn.clone();
+        let set_mesh = set_mesh_probe.clone();
+        let window = window_probe.clone();
+        glib::timeout_add_local(Duration::from_millis(200), move || {
+            let total = probe_grid.point_count();
+            let (count, failed) = {
+                let probing = mc.probing.lock();
+                (
+                    probing.results.len(),
+                    probing.results.iter().any(|r| !r.success),
+                )
+            };
+            status.set_text(&format!("{} {} / {}", t!("Probing"), count, total));
+
+            let finished = count >= total;
+            let stopped = !*mc.is_streaming.lock() && !finished;
+            if failed || stopped {
+                if failed {
+                    // GRBL raises an alarm; reset it and drop the rest of the grid
+                    mc.stop_btn.emit_clicked();
+                }
+                probe_btn.set_sensitive(true);
+                file_dialog::show_error_dialog(
+                    &t!("Auto-Level"),
+                    &format!(
+                        "{} {} / {}",
+                        t!("Probing stopped after"),
+                        count.min(total),
+                        total
+                    ),
+                    Some(&window),
+                );
+                return glib::ControlFlow::Break;
+            }
+            if !finished {
+                return glib::ControlFlow::Continue;
+            }
+
+            probe_btn.set_sensitive(true);
+            let heights = mc
+                .probing
+                .lock()
+                .generate_mesh(probe_grid.cols, probe_grid.rows);
+            match probe_grid.build_mesh(&heights) {
+                Ok(mut mesh) => {
+                    // Heights are machine Z; make them relative to work zero
+                    mesh.normalize_to(0.0, 0.0);
+                    set_mesh(mesh);
+                }
+                Err(e) => {
+                    file_dialog::show_error_dialog(&t!("Auto-Level"), &e.to_string(), Some(&window))
+                }
+            }
+            glib::ControlFlow::Break
+        });
+    });
+
+    // Save and load meshes as JSON
+    let json_filter = || {
+        let filter = FileFilter::new();
+        filter.set_name(Some(&t!("Height Maps (*.json)")));
+        filter.add_pattern("*.json");
+        filter
+    };
+
+    let mesh_save = mesh.clone();
+    let window_save = window.clone();
+    save_btn.connect_clicked(move |_| {
+        let dialog = file_dialog::save_dialog(&t!("Save Height Map"), Some(&window_save));
+        dialog.add_filter(&json_filter());
+        dialog.set_current_name("heightmap.json");
+        let mesh = mesh_save.clone();
+        let window = window_save.clone();
+        dialog.connect_response(move |dialog, response| {
+            if response == ResponseType::Accept {
+                if let (Some(path), Some(mesh)) =
+                    (dialog.file().and_then(|f| f.path()), mesh.borrow().as_ref())
+                {
+                    if let Err(e) = mesh.save_to_file(&path) {
+                        file_dialog::show_error_dialog(
+                            &t!("Save Height Map"),
+                            &e.to_string(),
+                            Some(&window),
+                        );
+                    }
+                }
+            }
+            dialog.destroy();
+        });
+        dialog.show();
+    });
+
+    let window_load = window.clone();
+    load_btn.connect_clicked(move |_| {
+        let dialog = file_dialog::open_dialog(&t!("Load Height Map"), Some(&window_load));
+        dialog.add_filter(&json_filter());
+        let set_mesh = set_mesh.clone();
+        let window = window_load.clone();
+        dialog.connect_response(move |dialog, response| {
+            if response == ResponseType::Accept {
+                if let Some(path) = dialog.file().and_then(|f| f.path()) {
+                    match ProbeMesh::load_from_file(&path) {
+                        Ok(mesh) => set_mesh(mesh),
+                        Err(e) => file_dialog::show_error_dialog(
+                            &t!("Load Height Map"),
+                            &e.to_string(),
+                            Some(&window),
+                        ),
+                    }
+                }
+            }
+            dialog.destroy();
+        });
+        dialog.show();
+    });
+
+    // Replace the editor program with the leveled one
+    let window_apply = window.clone();
+    apply_btn.connect_clicked(move |_| {
+        let Some(mesh) = mesh.borrow().clone() else {
+            return;
+        };
+        let result = AutoLeveler::new(&mesh).and_then(|leveler| {
+            leveler
+                .with_segment_length(segment.value())
+                .apply(&editor.get_text())
+        });
+        match result {
+            Ok(gcode) => {
+                editor.set_text(&gcode);
+                window_apply.close();
+            }
+            Err(e) => file_dialog::show_error_dialog(
+                &t!("Auto-Level"),
+                &e.to_string(),
+                Some(&window_apply),
+            ),
+        }
+    });
+
+    let window_close = window.clone();
+    close_btn.connect_clicked(move |_| window_close.close());
+
+    window.present();
+}
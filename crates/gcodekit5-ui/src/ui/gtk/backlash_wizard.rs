@@ -0,0 +1,348 @@
+//! Measure Backlash wizard.
+//!
+//! Walks the operator through measuring each axis's backlash with a dial
+//! indicator and saves the readings to the active device profile, where job
+//! streaming picks them up for compensation.
+
+use super::file_dialog;
+use super::machine_control::MachineControlView;
+use crate::device_status;
+use crate::t;
+use gcodekit5_communication::Communicator;
+use gcodekit5_devicedb::DeviceManager;
+use gcodekit5_visualizer::utils::{BacklashProcedure, CalibrationWizard};
+use gtk4::prelude::*;
+use gtk4::{glib, Align, Box, Button, CheckButton, Grid, Label, Orientation, SpinButton, Window};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+const AXES: [&str; 3] = ["X", "Y", "Z"];
+
+/// Show the backlash measurement wizard
+pub fn show_backlash_wizard(
+    parent: Option<&Window>,
+    machine_control: &MachineControlView,
+    device_manager: Arc<DeviceManager>,
+) {
+    let window = Window::builder()
+        .title(t!("Measure Backlash"))
+        .modal(true)
+        .default_width(460)
+        .build();
+    if let Some(parent) = parent {
+        window.set_transient_for(Some(parent));
+    }
+
+    let content = Box::new(Orientation::Vertical, 12);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let instructions = Label::new(Some(&t!(
+        "Jog each axis to a spot with room to move both ways, then mount a dial indicator against the carriage, in line with the axis. The wizard moves the axis so the indicator can read the play lost when it reverses."
+    )));
+    instructions.set_wrap(true);
+    instructions.set_halign(Align::Start);
+    instructions.set_xalign(0.0);
+    content.append(&instructions);
+
+    // Setup: axes, travel and feed
+    let grid = Grid::new();
+    grid.set_row_spacing(6);
+    grid.set_column_spacing(12);
+    let axes_box = Box::new(Orientation::Horizontal, 12);
+    let current = device_status::get_active_backlash();
+    let axis_checks: Vec<CheckButton> = AXES
+        .iter()
+        .enumerate()
+        .map(|(i, axis)| {
+            let check = CheckButton::with_label(axis);
+            check.set_active(i < 2 || current.z > 0.0);
+            axes_box.append(&check);
+            check
+        })
+        .collect();
+    let axes_label = Label::new(Some(&t!("Axes")));
+    axes_label.set_halign(Align::Start);
+    grid.attach(&axes_label, 0, 0, 1, 1);
+    grid.attach(&axes_box, 1, 0, 1, 1);
+
+    let defaults = BacklashProcedure::new('X');
+    let travel = SpinButton::with_range(0.5, 20.0, 0.5);
+    travel.set_digits(1);
+    travel.set_value(defaults.travel);
+    travel.set_hexpand(true);
+    travel.set_tooltip_text(Some(&t!(
+        "Distance moved each way; keep it well above the expected backlash"
+    )));
+    let travel_label = Label::new(Some(&t!("Travel (mm)")));
+    travel_label.set_halign(Align::Start);
+    grid.attach(&travel_label, 0, 1, 1, 1);
+    grid.attach(&travel, 1, 1, 1, 1);
+
+    let feed = SpinButton::with_range(10.0, 2000.0, 10.0);
+    feed.set_digits(0);
+    feed.set_value(defaults.feed_rate);
+    let feed_label = Label::new(Some(&t!("Feed rate (mm/min)")));
+    feed_label.set_halign(Align::Start);
+    grid.attach(&feed_label, 0, 2, 1, 1);
+    grid.attach(&feed, 1, 2, 1, 1);
+    content.append(&grid);
+
+    // Measurement step
+    let step_title = Label::new(None);
+    step_title.add_css_class("heading");
+    step_title.set_halign(Align::Start);
+    let step_text = Label::new(None);
+    step_text.set_wrap(true);
+    step_text.set_halign(Align::Start);
+    step_text.set_xalign(0.0);
+    let moves = Box::new(Orientation::Horizontal, 6);
+    let preload_btn = Button::with_label(&t!("1. Preload"));
+    preload_btn.set_tooltip_text(Some(&t!(
+        "Move away and back so the axis arrives travelling positive, then zero the indicator"
+    )));
+    let reverse_btn = Button::with_label(&t!("2. Reverse"));
+    reverse_btn.set_tooltip_text(Some(&t!(
+        "Move on and back to the same point from the other side, then read the indicator"
+    )));
+    moves.append(&preload_btn);
+    moves.append(&reverse_btn);
+    let reading_row = Box::new(Orientation::Horizontal, 6);
+    let reading = SpinButton::with_range(0.0, 5.0, 0.001);
+    reading.set_digits(3);
+    reading.set_hexpand(true);
+    let record_btn = Button::with_label(&t!("Record"));
+    reading_row.append(&Label::new(Some(&t!("Indicator reading (mm)"))));
+    reading_row.append(&reading);
+    reading_row.append(&record_btn);
+    let step_box = Box::new(Orientation::Vertical, 6);
+    step_box.append(&step_title);
+    step_box.append(&step_text);
+    step_box.append(&moves);
+    step_box.append(&reading_row);
+    step_box.set_visible(false);
+    content.append(&step_box);
+
+    let results = Label::new(None);
+    results.set_halign(Align::Start);
+    results.set_xalign(0.0);
+    results.set_visible(false);
+    content.append(&results);
+
+    let buttons = Box::new(Orientation::Horizontal, 6);
+    buttons.set_halign(Align::End);
+    let start_btn = Button::with_label(&t!("Start"));
+    start_btn.add_css_class("suggested-action");
+    let save_btn = Button::with_label(&t!("Save to Device Profile"));
+    save_btn.add_css_class("suggested-action");
+    save_btn.set_visible(false);
+    let close_btn = Button::with_label(&t!("Close"));
+    buttons.append(&close_btn);
+    buttons.append(&start_btn);
+    buttons.append(&save_btn);
+    content.append(&buttons);
+    window.set_child(Some(&content));
+
+    let wizard: Rc<RefCell<Option<CalibrationWizard>>> = Rc::new(RefCell::new(None));
+
+    let procedure = {
+        let wizard = wizard.clone();
+        let travel = travel.clone();
+        let feed = feed.clone();
+        Rc::new(move || {
+            let axis = wizard
+                .borrow()
+                .as_ref()
+                .and_then(|w| w.current_step().map(|s| s.axis.clone()))?;
+            let mut procedure = BacklashProcedure::new(axis.chars().next()?);
+            procedure.travel = travel.value();
+            procedure.feed_rate = feed.value();
+            Some(procedure)
+        })
+    };
+
+    // Show the current step, or the results once every axis is measured
+    let show_step = {
+        let wizard = wizard.clone();
+        let step_box = step_box.clone();
+        let results = results.clone();
+        let save_btn = save_btn.clone();
+        let reading = reading.clone();
+        Rc::new(move || {
+            let wizard = wizard.borrow();
+            let Some(wizard) = wizard.as_ref() else {
+                return;
+            };
+            if let Some(step) = wizard.current_step() {
+                step_title.set_text(&format!(
+                    "{} {} ({} / {})",
+                    t!("Axis"),
+                    step.axis,
+                    wizard.results().len() + 1,
+                    wizard.steps().len()
+                ));
+                step_text.set_text(&format!(
+                    "{} {} {}",
+                    t!("Set the indicator against the"),
+                    step.axis,
+                    t!("carriage. Run Preload and zero the indicator, then run Reverse and enter the reading.")
+                ));
+                reading.set_value(0.0);
+                return;
+            }
+
+            step_box.set_visible(false);
+            let mut text = t!("Measured backlash:");
+            for result in wizard.results() {
+                text.push_str(&format!(
+                    "\n{}: {:.3} mm{}",
+                    result.axis,
+                    result.measured_value,
+                    if result.passed {
+                        String::new()
+                    } else {
+                        format!(" ({})", t!("worth compensating"))
+                    }
+                ));
+            }
+            results.set_text(&text);
+            results.set_visible(true);
+            save_btn.set_visible(true);
+        })
+    };
+
+    let start_window = window.clone();
+    let start_wizard = wizard.clone();
+    let start_show = show_step.clone();
+    let setup = grid.clone();
+    let start_step_box = step_box.clone();
+    start_btn.connect_clicked(move |start_btn| {
+        let axes: String = AXES
+            .iter()
+            .zip(&axis_checks)
+            .filter(|(_, check)| check.is_active())
+            .map(|(axis, _)| *axis)
+            .collect();
+        if axes.is_empty() {
+            file_dialog::show_error_dialog(
+                &t!("Measure Backlash"),
+                &t!("Select at least one axis to measure."),
+                Some(&start_window),
+            );
+            return;
+        }
+        *start_wizard.borrow_mut() = Some(CalibrationWizard::backlash_measurement(&axes));
+        setup.set_sensitive(false);
+        start_btn.set_visible(false);
+        start_step_box.set_visible(true);
+        start_show();
+    });
+
+    // Send the measurement moves as written: compensating them would hide
+    // the play being measured
+    let run_moves = {
+        let mc = machine_control.clone();
+        let window = window.clone();
+        let preload_btn = preload_btn.clone();
+        let reverse_btn = reverse_btn.clone();
+        Rc::new(move |gcode: String| {
+            if !mc.communicator.lock().is_connected() {
+                file_dialog::show_error_dialog(
+                    &t!("Measure Backlash"),
+                    &t!("Connect to the machine before measuring."),
+                    Some(&window),
+                );
+                return;
+            }
+            if *mc.is_streaming.lock() {
+                file_dialog::show_error_dialog(
+                    &t!("Measure Backlash"),
+                    &t!("A job is already running."),
+                    Some(&window),
+                );
+                return;
+            }
+            mc.stream_job(&gcode);
+            preload_btn.set_sensitive(false);
+            reverse_btn.set_sensitive(false);
+
+            let mc = mc.clone();
+            let preload_btn = preload_btn.clone();
+            let reverse_btn = reverse_btn.clone();
+            glib::timeout_add_local(Duration::from_millis(200), move || {
+                if *mc.is_streaming.lock() {
+                    return glib::ControlFlow::Continue;
+                }
+                preload_btn.set_sensitive(true);
+                reverse_btn.set_sensitive(true);
+                glib::ControlFlow::Break
+            });
+        })
+    };
+
+    let preload_procedure = procedure.clone();
+    let preload_run = run_moves.clone();
+    preload_btn.connect_clicked(move |_| {
+        if let Some(procedure) = preload_procedure() {
+            preload_run(procedure.preload_gcode());
+        }
+    });
+    let reverse_procedure = procedure.clone();
+    let reverse_run = run_moves.clone();
+    reverse_btn.connect_clicked(move |_| {
+        if let Some(procedure) = reverse_procedure() {
+            reverse_run(procedure.reversal_gcode());
+        }
+    });
+
+    let record_wizard = wizard.clone();
+    let record_show = show_step.clone();
+    record_btn.connect_clicked(move |_| {
+        if let Some(wizard) = record_wizard.borrow_mut().as_mut() {
+            // The carriage stops short on either side; only the size matters
+            let _ = wizard.record_measurement(reading.value().abs());
+        }
+        record_show();
+    });
+
+    let save_window = window.clone();
+    let save_wizard = wizard.clone();
+    save_btn.connect_clicked(move |_| {
+        let wizard = save_wizard.borrow();
+        let Some(wizard) = wizard.as_ref() else {
+            return;
+        };
+        let error = |message: String| {
+            file_dialog::show_error_dialog(&t!("Measure Backlash"), &message, Some(&save_window));
+        };
+        let Some(mut profile) = device_manager.get_active_profile() else {
+            error(t!("There is no active device profile to save to."));
+            return;
+        };
+        for (axis, value) in AXES.iter().zip([
+            &mut profile.backlash.x,
+            &mut profile.backlash.y,
+            &mut profile.backlash.z,
+        ]) {
+            if let Some(measured) = wizard.measured(axis) {
+                *value = measured;
+            }
+        }
+        let backlash = profile.backlash;
+        if let Err(e) = device_manager.save_profile(profile) {
+            error(e.to_string());
+            return;
+        }
+        device_status::set_active_backlash(backlash);
+        save_window.close();
+    });
+
+    let window_close = window.clone();
+    close_btn.connect_clicked(move |_| window_close.close());
+
+    window.present();
+}
@@ -504,7 +504,12 @@ impl BitmapEngravingTool {
 
             // Spawn background thread for generation
             std::thread::spawn(move || {
+                let post = crate::device_status::get_active_post_processor();
                 let result = BitmapImageEngraver::from_file(&image_path_thread, params)
+                    .map(|engraver| match post {
+                        Some(post) => engraver.with_post_processor(post),
+                        None => engraver,
+                    })
                     .and_then(|engraver| {
                         engraver.generate_gcode_with_progress(|progress| {
                             // Check for cancellation
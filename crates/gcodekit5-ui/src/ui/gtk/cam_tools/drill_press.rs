@@ -384,7 +384,10 @@ impl DrillPressTool {
                 y: units::parse_length(&w_run.y.text(), system).unwrap_or(0.0) as f64,
             };
 
-            let generator = DrillPressGenerator::new(params);
+            let mut generator = DrillPressGenerator::new(params);
+            if let Some(post) = crate::device_status::get_active_post_processor() {
+                generator = generator.with_post_processor(post);
+            }
             match generator.generate() {
                 Ok(mut gcode) => {
                     gcode = gcode.replace("$H\n", "").replace("$H", "");
@@ -457,7 +457,8 @@ impl GerberTool {
                 params.layer_type, params
             );
 
-            match GerberConverter::generate(&params, &content) {
+            let post = crate::device_status::get_active_post_processor();
+            match GerberConverter::generate_with_post(&params, &content, post.as_ref()) {
                 Ok(gcode) => {
                     warn!("Generated {} bytes of G-Code", gcode.len());
                     on_gen(gcode)
@@ -346,6 +346,9 @@ impl JigsawTool {
                         return Err("Cancelled by user".to_string());
                     }
                     let mut maker = JigsawPuzzleMaker::new(params)?;
+                    if let Some(post) = crate::device_status::get_active_post_processor() {
+                        maker = maker.with_post_processor(post);
+                    }
                     maker.generate()?;
                     let mut gcode = maker.to_gcode(500.0, 1.0);
 
@@ -8,6 +8,7 @@ mod common;
 mod drill_press;
 mod gerber;
 mod jigsaw;
+mod relief_carving;
 mod speeds_feeds;
 mod spoilboard_grid;
 mod spoilboard_surfacing;
@@ -19,6 +20,7 @@ pub use bitmap_engraving::BitmapEngravingTool;
 pub use drill_press::DrillPressTool;
 pub use gerber::GerberTool;
 pub use jigsaw::JigsawTool;
+pub use relief_carving::ReliefCarvingTool;
 pub use speeds_feeds::SpeedsFeedsTool;
 pub use spoilboard_grid::SpoilboardGridTool;
 pub use spoilboard_surfacing::SpoilboardSurfacingTool;
@@ -89,6 +91,10 @@ impl CamToolsView {
         let vector_tool = VectorEngravingTool::new(&stack, settings.clone(), on_generate.clone());
         stack.add_named(vector_tool.widget(), Some("laser_vector"));
 
+        // Relief Carving Tool
+        let relief_tool = ReliefCarvingTool::new(&stack, settings.clone(), on_generate.clone());
+        stack.add_named(relief_tool.widget(), Some("relief"));
+
         // Speeds & Feeds Calculator
         let feeds_tool = SpeedsFeedsTool::new(&stack, settings.clone());
         stack.add_named(feeds_tool.widget(), Some("feeds"));
@@ -163,6 +169,13 @@ impl CamToolsView {
                 icon: "insert-image-symbolic",
                 category: "engraving",
             },
+            Tool {
+                page: "relief",
+                title: "Relief Carving",
+                desc: "Carve grayscale images as 2.5D reliefs with roughing and finishing",
+                icon: "image-x-generic-symbolic",
+                category: "engraving",
+            },
             Tool {
                 page: "feeds",
                 title: "Speeds and Feeds Calculator",
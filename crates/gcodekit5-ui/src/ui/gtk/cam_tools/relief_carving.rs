@@ -0,0 +1,871 @@
+//! Relief Carving Tool
+
+use gtk4::prelude::*;
+use gtk4::{
+    Align, Box, Button, CheckButton, ComboBoxText, Entry, FileChooserAction, FileChooserDialog,
+    Label, Orientation, Overlay, Paned, ResponseType, ScrolledWindow, Stack,
+};
+use libadwaita::prelude::*;
+use libadwaita::{ActionRow, PreferencesGroup};
+use std::cell::Cell;
+use std::fs;
+use std::rc::Rc;
+
+use super::common::{create_dimension_row, set_paned_initial_fraction};
+use super::CamToolsView;
+use crate::ui::gtk::help_browser;
+use crate::ui::tools_manager_backend::ToolsManagerBackend;
+use gcodekit5_core::{units, Units};
+use gcodekit5_designer::{
+    HeightMap2D, ReliefCarver, ReliefJob, ReliefMap, ReliefPass, ReliefSettings,
+    ToolChangeSettings, ToolpathToGcode,
+};
+use gcodekit5_settings::SettingsController;
+
+/// Longest side of the simulated preview, in pixels.
+const PREVIEW_PIXELS: f64 = 400.0;
+
+struct ReliefCarvingWidgets {
+    image_path: Entry,
+    width: Entry,
+    resolution: Entry,
+    min_depth: Entry,
+    max_depth: Entry,
+    gamma: Entry,
+    smoothing: Entry,
+    invert: CheckButton,
+    roughing_enabled: CheckButton,
+    roughing_tool: ComboBoxText,
+    roughing_stepover: Entry,
+    roughing_step_down: Entry,
+    stock_to_leave: Entry,
+    finishing_tool: ComboBoxText,
+    finishing_stepover: Entry,
+    offset_x: Entry,
+    offset_y: Entry,
+    safe_z: Entry,
+    home_before: CheckButton,
+    preview_image: gtk4::Picture,
+    preview_spinner: gtk4::Spinner,
+    library: Vec<ReliefPass>,
+}
+
+pub struct ReliefCarvingTool {
+    content: Box,
+}
+
+impl ReliefCarvingTool {
+    pub fn new<F: Fn(String) + 'static>(
+        stack: &Stack,
+        settings: Rc<SettingsController>,
+        on_generate: Rc<F>,
+    ) -> Self {
+        let content_box = Box::new(Orientation::Vertical, 0);
+
+        // Header
+        let header = Box::new(Orientation::Horizontal, 12);
+        header.set_margin_top(12);
+        header.set_margin_bottom(12);
+        header.set_margin_start(12);
+        header.set_margin_end(12);
+
+        let back_btn = Button::builder().icon_name("go-previous-symbolic").build();
+        let stack_clone = stack.clone();
+        back_btn.connect_clicked(move |_| {
+            stack_clone.set_visible_child_name("dashboard");
+        });
+        header.append(&back_btn);
+
+        let title = Label::builder()
+            .label("Relief Carving")
+            .css_classes(vec!["title-2"])
+            .build();
+        title.set_hexpand(true);
+        title.set_halign(Align::Start);
+        header.append(&title);
+
+        header.append(&help_browser::make_help_button("relief_carving"));
+
+        content_box.append(&header);
+
+        // Paned Layout
+        let paned = Paned::new(Orientation::Horizontal);
+        paned.set_hexpand(true);
+        paned.set_vexpand(true);
+        content_box.append(&paned);
+
+        // Sidebar with Preview (40%)
+        let sidebar = Box::new(Orientation::Vertical, 12);
+        sidebar.add_css_class("sidebar");
+        sidebar.set_margin_top(24);
+        sidebar.set_margin_bottom(24);
+        sidebar.set_margin_start(24);
+        sidebar.set_margin_end(24);
+
+        let title_label = Label::builder()
+            .label("2.5D Relief")
+            .css_classes(vec!["title-3"])
+            .halign(Align::Start)
+            .build();
+        sidebar.append(&title_label);
+
+        let desc = Label::builder()
+            .label("Carve a grayscale image as a relief. Dark pixels are cut deepest; a roughing pass clears the bulk before a ball nose finishes the surface. Preview shows the simulated result.")
+            .css_classes(vec!["body"])
+            .wrap(true)
+            .halign(Align::Start)
+            .build();
+        sidebar.append(&desc);
+
+        // Preview Image with spinner overlay
+        let preview_overlay = Overlay::new();
+        let preview_image = gtk4::Picture::new();
+        preview_image.set_can_shrink(true);
+        preview_image.set_vexpand(true);
+        preview_image.set_hexpand(true);
+        preview_overlay.set_child(Some(&preview_image));
+
+        // Loading spinner
+        let preview_spinner = gtk4::Spinner::new();
+        preview_spinner.set_halign(Align::Center);
+        preview_spinner.set_valign(Align::Center);
+        preview_spinner.set_size_request(48, 48);
+        preview_spinner.set_visible(false);
+        preview_overlay.add_overlay(&preview_spinner);
+
+        sidebar.append(&preview_overlay);
+
+        // Content Area
+        let right_panel = Box::new(Orientation::Vertical, 0);
+        let scroll_content = Box::new(Orientation::Vertical, 0);
+        let scrolled = ScrolledWindow::builder()
+            .hscrollbar_policy(gtk4::PolicyType::Never)
+            .vexpand(true)
+            .child(&scroll_content)
+            .build();
+
+        // Library tools the relief can cut with
+        let mut library: Vec<ReliefPass> = ToolsManagerBackend::new()
+            .get_all_tools()
+            .into_iter()
+            .filter_map(ReliefPass::from_library)
+            .collect();
+        library.sort_by(|a, b| {
+            a.tool
+                .number
+                .cmp(&b.tool.number)
+                .then(a.tool.name.cmp(&b.tool.name))
+        });
+        let defaults = ReliefSettings::default();
+        let default_roughing = defaults
+            .roughing
+            .clone()
+            .unwrap_or_else(|| defaults.finishing.clone());
+
+        // Create Widgets
+        let image_path = Entry::builder()
+            .placeholder_text("No image selected")
+            .valign(Align::Center)
+            .build();
+        let (width_row, width, width_unit) =
+            create_dimension_row("Width:", defaults.width, &settings);
+        let (resolution_row, resolution, resolution_unit) =
+            create_dimension_row("Resolution:", defaults.resolution, &settings);
+        let (min_depth_row, min_depth, min_depth_unit) =
+            create_dimension_row("Min Depth:", defaults.min_depth, &settings);
+        let (max_depth_row, max_depth, max_depth_unit) =
+            create_dimension_row("Max Depth:", defaults.max_depth, &settings);
+        let gamma = Entry::builder()
+            .text(defaults.gamma.to_string())
+            .valign(Align::Center)
+            .build();
+        let (smoothing_row, smoothing, smoothing_unit) =
+            create_dimension_row("Smoothing:", defaults.smoothing, &settings);
+        let invert = CheckButton::builder()
+            .active(false)
+            .valign(Align::Center)
+            .build();
+
+        let roughing_enabled = CheckButton::builder()
+            .active(true)
+            .valign(Align::Center)
+            .build();
+        let roughing_tool = Self::create_tool_combo(&library);
+        let (roughing_stepover_row, roughing_stepover, roughing_stepover_unit) =
+            create_dimension_row("Stepover:", default_roughing.stepover, &settings);
+        let (roughing_step_down_row, roughing_step_down, roughing_step_down_unit) =
+            create_dimension_row("Step Down:", default_roughing.step_down, &settings);
+        let (stock_to_leave_row, stock_to_leave, stock_to_leave_unit) = create_dimension_row(
+            "Stock to Leave:",
+            default_roughing.stock_to_leave,
+            &settings,
+        );
+
+        let finishing_tool = Self::create_tool_combo(&library);
+        let (finishing_stepover_row, finishing_stepover, finishing_stepover_unit) =
+            create_dimension_row("Stepover:", defaults.finishing.stepover, &settings);
+
+        let (offset_x_row, offset_x, offset_x_unit) =
+            create_dimension_row("Offset X:", defaults.offset_x, &settings);
+        let (offset_y_row, offset_y, offset_y_unit) =
+            create_dimension_row("Offset Y:", defaults.offset_y, &settings);
+        let (safe_z_row, safe_z, safe_z_unit) = create_dimension_row("Safe Z:", 5.0, &settings);
+        let home_before = CheckButton::builder()
+            .active(false)
+            .valign(Align::Center)
+            .build();
+
+        // Groups
+        let image_group = PreferencesGroup::builder().title("Image File").build();
+        let image_row = ActionRow::builder().title("Image Path:").build();
+        let image_box = Box::new(Orientation::Horizontal, 6);
+        image_box.append(&image_path);
+        let load_image_btn = Button::builder().label("Browse...").build();
+        image_box.append(&load_image_btn);
+        image_row.add_suffix(&image_box);
+        image_group.add(&image_row);
+        scroll_content.append(&image_group);
+
+        let relief_group = PreferencesGroup::builder().title("Relief").build();
+        relief_group.add(&width_row);
+        relief_group.add(&resolution_row);
+        relief_group.add(&min_depth_row);
+        relief_group.add(&max_depth_row);
+        relief_group.add(&Self::create_row("Gamma:", &gamma));
+        relief_group.add(&smoothing_row);
+        relief_group.add(&Self::create_row("Invert:", &invert));
+        scroll_content.append(&relief_group);
+
+        let roughing_group = PreferencesGroup::builder().title("Roughing").build();
+        roughing_group.add(&Self::create_row("Enabled:", &roughing_enabled));
+        roughing_group.add(&Self::create_row("Tool:", &roughing_tool));
+        roughing_group.add(&roughing_stepover_row);
+        roughing_group.add(&roughing_step_down_row);
+        roughing_group.add(&stock_to_leave_row);
+        scroll_content.append(&roughing_group);
+
+        let finishing_group = PreferencesGroup::builder().title("Finishing").build();
+        finishing_group.add(&Self::create_row("Tool:", &finishing_tool));
+        finishing_group.add(&finishing_stepover_row);
+        scroll_content.append(&finishing_group);
+
+        let offset_group = PreferencesGroup::builder().title("Work Offsets").build();
+        offset_group.add(&offset_x_row);
+        offset_group.add(&offset_y_row);
+        offset_group.add(&safe_z_row);
+        let home_row = ActionRow::builder()
+            .title("Home Device Before Start")
+            .build();
+        home_row.add_suffix(&home_before);
+        offset_group.add(&home_row);
+        scroll_content.append(&offset_group);
+
+        right_panel.append(&scrolled);
+
+        // Actions
+        let action_box = Box::new(Orientation::Horizontal, 12);
+        action_box.set_margin_top(12);
+        action_box.set_margin_bottom(12);
+        action_box.set_margin_end(12);
+        action_box.set_halign(Align::End);
+
+        let load_btn = Button::with_label("Load");
+        let save_btn = Button::with_label("Save");
+        let cancel_btn = Button::with_label("Cancel");
+        let preview_btn = Button::with_label("Preview");
+        let generate_btn = Button::with_label("Generate");
+        generate_btn.add_css_class("suggested-action");
+        action_box.append(&load_btn);
+        action_box.append(&save_btn);
+        action_box.append(&cancel_btn);
+        action_box.append(&preview_btn);
+        action_box.append(&generate_btn);
+
+        right_panel.append(&action_box);
+
+        paned.set_start_child(Some(&sidebar));
+        paned.set_end_child(Some(&right_panel));
+        // Initial ratio only; do not fight user resizing.
+        set_paned_initial_fraction(&paned, 0.40);
+
+        let widgets = Rc::new(ReliefCarvingWidgets {
+            image_path,
+            width,
+            resolution,
+            min_depth,
+            max_depth,
+            gamma,
+            smoothing,
+            invert,
+            roughing_enabled,
+            roughing_tool,
+            roughing_stepover,
+            roughing_step_down,
+            stock_to_leave,
+            finishing_tool,
+            finishing_stepover,
+            offset_x,
+            offset_y,
+            safe_z,
+            home_before,
+            preview_image: preview_image.clone(),
+            preview_spinner: preview_spinner.clone(),
+            library,
+        });
+
+        // A new tool brings its own stepover and depth per pass
+        {
+            let settings_clone = settings.clone();
+            let w = widgets.clone();
+            widgets.roughing_tool.connect_changed(move |combo| {
+                if let Some(pass) = Self::selected_pass(combo, &w.library) {
+                    let system = settings_clone
+                        .persistence
+                        .borrow()
+                        .config()
+                        .ui
+                        .measurement_system;
+                    w.roughing_stepover
+                        .set_text(&units::format_length(pass.stepover as f32, system));
+                    w.roughing_step_down
+                        .set_text(&units::format_length(pass.step_down as f32, system));
+                }
+            });
+        }
+        {
+            let settings_clone = settings.clone();
+            let w = widgets.clone();
+            widgets.finishing_tool.connect_changed(move |combo| {
+                if let Some(pass) = Self::selected_pass(combo, &w.library) {
+                    let system = settings_clone
+                        .persistence
+                        .borrow()
+                        .config()
+                        .ui
+                        .measurement_system;
+                    w.finishing_stepover
+                        .set_text(&units::format_length(pass.stepover as f32, system));
+                }
+            });
+        }
+
+        // Pick the first flat and ball cutters in the library
+        Self::select_tool(&widgets.roughing_tool, &widgets.library, |pass| {
+            pass.cutter.corner_radius() == 0.0
+        });
+        Self::select_tool(&widgets.finishing_tool, &widgets.library, |pass| {
+            pass.cutter.corner_radius() >= pass.cutter.radius()
+        });
+
+        // Unit update listener
+        {
+            let settings_clone = settings.clone();
+            let w = widgets.clone();
+
+            let last_system = Rc::new(Cell::new(
+                settings.persistence.borrow().config().ui.measurement_system,
+            ));
+
+            settings.on_setting_changed(move |key, _| {
+                if key == "measurement_system" {
+                    let new_system = settings_clone
+                        .persistence
+                        .borrow()
+                        .config()
+                        .ui
+                        .measurement_system;
+                    let old_system = last_system.get();
+
+                    if new_system != old_system {
+                        let unit_label = units::get_unit_label(new_system);
+
+                        let update_entry = |entry: &Entry, label: &Label| {
+                            if let Ok(val_mm) = units::parse_length(&entry.text(), old_system) {
+                                entry.set_text(&units::format_length(val_mm, new_system));
+                            }
+                            label.set_text(unit_label);
+                        };
+
+                        update_entry(&w.width, &width_unit);
+                        update_entry(&w.resolution, &resolution_unit);
+                        update_entry(&w.min_depth, &min_depth_unit);
+                        update_entry(&w.max_depth, &max_depth_unit);
+                        update_entry(&w.smoothing, &smoothing_unit);
+                        update_entry(&w.roughing_stepover, &roughing_stepover_unit);
+                        update_entry(&w.roughing_step_down, &roughing_step_down_unit);
+                        update_entry(&w.stock_to_leave, &stock_to_leave_unit);
+                        update_entry(&w.finishing_stepover, &finishing_stepover_unit);
+                        update_entry(&w.offset_x, &offset_x_unit);
+                        update_entry(&w.offset_y, &offset_y_unit);
+                        update_entry(&w.safe_z, &safe_z_unit);
+
+                        last_system.set(new_system);
+                    }
+                }
+            });
+        }
+
+        // Load Image Button
+        let w_load_image = widgets.clone();
+        load_image_btn.connect_clicked(move |_| {
+            let dialog = FileChooserDialog::new(
+                Some("Select Image"),
+                None::<&gtk4::Window>,
+                FileChooserAction::Open,
+                &[
+                    ("Cancel", ResponseType::Cancel),
+                    ("Open", ResponseType::Accept),
+                ],
+            );
+            dialog.set_default_size(900, 700);
+
+            let filter = gtk4::FileFilter::new();
+            filter.set_name(Some("Image Files"));
+            filter.add_mime_type("image/png");
+            filter.add_mime_type("image/jpeg");
+            filter.add_mime_type("image/bmp");
+            filter.add_mime_type("image/gif");
+            filter.add_mime_type("image/tiff");
+            dialog.add_filter(&filter);
+
+            let w_clone = w_load_image.clone();
+            dialog.connect_response(move |d, response| {
+                if response == ResponseType::Accept {
+                    if let Some(file) = d.file() {
+                        if let Some(path) = file.path() {
+                            let path = path.display().to_string();
+                            w_clone.image_path.set_text(&path);
+                            Self::load_image_preview(&w_clone, path);
+                        }
+                    }
+                }
+                d.close();
+            });
+
+            dialog.show();
+        });
+
+        // Preview the simulated carving
+        let w_preview = widgets.clone();
+        let settings_preview = settings.clone();
+        preview_btn.connect_clicked(move |_| {
+            let Some((image_path, relief)) = Self::collect_settings(&w_preview, &settings_preview)
+            else {
+                return;
+            };
+
+            w_preview.preview_spinner.set_visible(true);
+            w_preview.preview_spinner.start();
+            let preview_img = w_preview.preview_image.clone();
+            let spinner = w_preview.preview_spinner.clone();
+
+            let (tx, rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                let result = Self::plan(&image_path, relief).map(|(carver, map, job)| {
+                    let (width, height) = map.size();
+                    let resolution = (width.max(height) / PREVIEW_PIXELS).max(map.pixel);
+                    let result = carver.simulate(&map, &job, resolution as f32);
+                    Self::render_height_map(&result, carver.settings().max_depth as f32)
+                });
+                let _ = tx.send(result);
+            });
+
+            glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+                if let Ok(result) = rx.try_recv() {
+                    spinner.stop();
+                    spinner.set_visible(false);
+
+                    match result {
+                        Ok((width, height, pixels)) => {
+                            let buffer = glib::Bytes::from(&pixels);
+                            let texture = gtk4::gdk::MemoryTexture::new(
+                                width as i32,
+                                height as i32,
+                                gtk4::gdk::MemoryFormat::R8g8b8,
+                                &buffer,
+                                width * 3,
+                            );
+                            preview_img.set_paintable(Some(&texture));
+                        }
+                        Err(e) => {
+                            CamToolsView::show_error_dialog(
+                                "Relief Preview Failed",
+                                &format!("Failed to preview relief: {}", e),
+                            );
+                        }
+                    }
+                    glib::ControlFlow::Break
+                } else {
+                    glib::ControlFlow::Continue
+                }
+            });
+        });
+
+        // Connect Generate
+        let w_gen = widgets.clone();
+        let settings_gen = settings.clone();
+        let on_gen = on_generate.clone();
+        generate_btn.connect_clicked(move |_| {
+            let Some((image_path, relief)) = Self::collect_settings(&w_gen, &settings_gen) else {
+                return;
+            };
+            let system = settings_gen
+                .persistence
+                .borrow()
+                .config()
+                .ui
+                .measurement_system;
+            let safe_z = units::parse_length(&w_gen.safe_z.text(), system).unwrap_or(5.0) as f64;
+            let home_before = w_gen.home_before.is_active();
+
+            w_gen.preview_spinner.set_visible(true);
+            w_gen.preview_spinner.start();
+            let spinner = w_gen.preview_spinner.clone();
+            let on_gen_clone = on_gen.clone();
+
+            let (tx, rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                let result = Self::plan(&image_path, relief).map(|(carver, _, job)| {
+                    let mut gcode_gen = ToolpathToGcode::new(Units::MM, safe_z);
+                    if let Some(post) = crate::device_status::get_active_post_processor() {
+                        gcode_gen = gcode_gen.with_post_processor(post);
+                    }
+                    let gcode = carver.to_gcode(&job, &gcode_gen, &ToolChangeSettings::default());
+                    if home_before {
+                        format!("$H\n{}", gcode)
+                    } else {
+                        gcode
+                    }
+                });
+                let _ = tx.send(result);
+            });
+
+            glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+                if let Ok(result) = rx.try_recv() {
+                    spinner.stop();
+                    spinner.set_visible(false);
+
+                    match result {
+                        Ok(gcode) => {
+                            on_gen_clone(gcode);
+                        }
+                        Err(e) => {
+                            CamToolsView::show_error_dialog(
+                                "Relief Generation Failed",
+                                &format!("Failed to generate relief: {}", e),
+                            );
+                        }
+                    }
+                    glib::ControlFlow::Break
+                } else {
+                    glib::ControlFlow::Continue
+                }
+            });
+        });
+
+        // Save params
+        let w_save = widgets.clone();
+        save_btn.connect_clicked(move |_| {
+            let dialog = FileChooserDialog::new(
+                Some("Save Parameters"),
+                None::<&gtk4::Window>,
+                FileChooserAction::Save,
+                &[
+                    ("Cancel", ResponseType::Cancel),
+                    ("Save", ResponseType::Accept),
+                ],
+            );
+            dialog.set_default_size(900, 700);
+            dialog.set_current_name("relief_params.json");
+
+            let w_clone = w_save.clone();
+            dialog.connect_response(move |d, response| {
+                if response == ResponseType::Accept {
+                    if let Some(file) = d.file() {
+                        if let Some(path) = file.path() {
+                            let params = Self::collect_params_for_save(&w_clone);
+                            if let Ok(json) = serde_json::to_string_pretty(&params) {
+                                let _ = fs::write(path, json);
+                            }
+                        }
+                    }
+                }
+                d.close();
+            });
+
+            dialog.show();
+        });
+
+        // Load params
+        let w_load = widgets.clone();
+        load_btn.connect_clicked(move |_| {
+            let dialog = FileChooserDialog::new(
+                Some("Load Parameters"),
+                None::<&gtk4::Window>,
+                FileChooserAction::Open,
+                &[
+                    ("Cancel", ResponseType::Cancel),
+                    ("Open", ResponseType::Accept),
+                ],
+            );
+            dialog.set_default_size(900, 700);
+
+            let w_clone = w_load.clone();
+            dialog.connect_response(move |d, response| {
+                if response == ResponseType::Accept {
+                    if let Some(file) = d.file() {
+                        if let Some(path) = file.path() {
+                            if let Ok(content) = fs::read_to_string(path) {
+                                if let Ok(params) =
+                                    serde_json::from_str::<serde_json::Value>(&content)
+                                {
+                                    Self::apply_params(&w_clone, &params);
+                                }
+                            }
+                        }
+                    }
+                }
+                d.close();
+            });
+
+            dialog.show();
+        });
+
+        // Cancel
+        let stack_clone_cancel = stack.clone();
+        cancel_btn.connect_clicked(move |_| {
+            stack_clone_cancel.set_visible_child_name("dashboard");
+        });
+
+        Self {
+            content: content_box,
+        }
+    }
+
+    pub fn widget(&self) -> &Box {
+        &self.content
+    }
+
+    fn create_row(title: &str, widget: &impl IsA<gtk4::Widget>) -> ActionRow {
+        let row = ActionRow::builder().title(title).build();
+        row.add_suffix(widget);
+        row
+    }
+
+    fn create_tool_combo(library: &[ReliefPass]) -> ComboBoxText {
+        let combo = ComboBoxText::new();
+        for (index, pass) in library.iter().enumerate() {
+            let label = format!(
+                "T{} {} ({} {:.2}mm)",
+                pass.tool.number,
+                pass.tool.name,
+                pass.cutter.name(),
+                pass.cutter.diameter()
+            );
+            combo.append(Some(index.to_string().as_str()), &label);
+        }
+        combo.set_valign(Align::Center);
+        combo
+    }
+
+    fn select_tool(combo: &ComboBoxText, library: &[ReliefPass], prefer: fn(&ReliefPass) -> bool) {
+        let index = library.iter().position(prefer).unwrap_or(0);
+        if index < library.len() {
+            combo.set_active_id(Some(index.to_string().as_str()));
+        }
+    }
+
+    fn selected_pass(combo: &ComboBoxText, library: &[ReliefPass]) -> Option<ReliefPass> {
+        let index: usize = combo.active_id()?.parse().ok()?;
+        library.get(index).cloned()
+    }
+
+    /// Reads the form into relief settings, reporting anything missing.
+    fn collect_settings(
+        w: &ReliefCarvingWidgets,
+        settings: &Rc<SettingsController>,
+    ) -> Option<(String, ReliefSettings)> {
+        let image_path = w.image_path.text().to_string();
+        if image_path.is_empty() {
+            CamToolsView::show_error_dialog(
+                "No Image Selected",
+                "Please select an image file first.",
+            );
+            return None;
+        }
+        let Some(finishing) = Self::selected_pass(&w.finishing_tool, &w.library) else {
+            CamToolsView::show_error_dialog(
+                "No Finishing Tool",
+                "Please add a flat, ball or bull-nose end mill to the tool library.",
+            );
+            return None;
+        };
+
+        let system = settings.persistence.borrow().config().ui.measurement_system;
+        let length = |entry: &Entry, default: f64| {
+            units::parse_length(&entry.text(), system)
+                .map(|v| v as f64)
+                .unwrap_or(default)
+        };
+        let defaults = ReliefSettings::default();
+
+        let roughing = if w.roughing_enabled.is_active() {
+            Self::selected_pass(&w.roughing_tool, &w.library).map(|pass| ReliefPass {
+                stepover: length(&w.roughing_stepover, pass.stepover),
+                step_down: length(&w.roughing_step_down, pass.step_down),
+                stock_to_leave: length(&w.stock_to_leave, 0.0),
+                ..pass
+            })
+        } else {
+            None
+        };
+        let finishing = ReliefPass {
+            stepover: length(&w.finishing_stepover, finishing.stepover),
+            ..finishing
+        };
+
+        let relief = ReliefSettings {
+            width: length(&w.width, defaults.width),
+            resolution: length(&w.resolution, defaults.resolution),
+            min_depth: length(&w.min_depth, defaults.min_depth),
+            max_depth: length(&w.max_depth, defaults.max_depth),
+            gamma: w.gamma.text().parse().unwrap_or(defaults.gamma),
+            smoothing: length(&w.smoothing, defaults.smoothing),
+            invert: w.invert.is_active(),
+            offset_x: length(&w.offset_x, defaults.offset_x),
+            offset_y: length(&w.offset_y, defaults.offset_y),
+            roughing,
+            finishing,
+            ..defaults
+        };
+        Some((image_path, relief))
+    }
+
+    fn plan(
+        image_path: &str,
+        settings: ReliefSettings,
+    ) -> anyhow::Result<(ReliefCarver, ReliefMap, ReliefJob)> {
+        let image = image::open(image_path)?;
+        let map = ReliefMap::from_image(&image, &settings)?;
+        let carver = ReliefCarver::new(settings);
+        let job = carver.generate(&map);
+        Ok((carver, map, job))
+    }
+
+    /// Shades the simulated stock from black at full depth to white at the
+    /// top, with the top row of the image at the back of the stock.
+    fn render_height_map(map: &HeightMap2D, thickness: f32) -> (usize, usize, Vec<u8>) {
+        let mut pixels = Vec::with_capacity(map.width_px * map.height_px * 3);
+        for py in (0..map.height_px).rev() {
+            for px in 0..map.width_px {
+                let height = map.get_height_at_pixel(px, py).unwrap_or(thickness);
+                let shade = (height / thickness.max(1e-3)).clamp(0.0, 1.0);
+                let value = (shade * 255.0).round() as u8;
+                pixels.extend_from_slice(&[value, value, value]);
+            }
+        }
+        (map.width_px, map.height_px, pixels)
+    }
+
+    fn load_image_preview(w: &ReliefCarvingWidgets, path: String) {
+        // Show spinner and load preview in background
+        w.preview_spinner.set_visible(true);
+        w.preview_spinner.start();
+
+        let preview_img = w.preview_image.clone();
+        let spinner = w.preview_spinner.clone();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let file = gtk4::gio::File::for_path(&path);
+            let texture_result = gtk4::gdk::Texture::from_file(&file);
+            let _ = tx.send(texture_result);
+        });
+
+        glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+            if let Ok(texture_result) = rx.try_recv() {
+                spinner.stop();
+                spinner.set_visible(false);
+
+                if let Ok(texture) = texture_result {
+                    preview_img.set_paintable(Some(&texture));
+                }
+                glib::ControlFlow::Break
+            } else {
+                glib::ControlFlow::Continue
+            }
+        });
+    }
+
+    fn collect_params_for_save(w: &ReliefCarvingWidgets) -> serde_json::Value {
+        serde_json::json!({
+            "image_path": w.image_path.text().to_string(),
+            "width": w.width.text().to_string(),
+            "resolution": w.resolution.text().to_string(),
+            "min_depth": w.min_depth.text().to_string(),
+            "max_depth": w.max_depth.text().to_string(),
+            "gamma": w.gamma.text().to_string(),
+            "smoothing": w.smoothing.text().to_string(),
+            "invert": w.invert.is_active(),
+            "roughing_enabled": w.roughing_enabled.is_active(),
+            "roughing_tool": Self::selected_pass(&w.roughing_tool, &w.library)
+                .map(|pass| pass.tool.id)
+                .unwrap_or_default(),
+            "roughing_stepover": w.roughing_stepover.text().to_string(),
+            "roughing_step_down": w.roughing_step_down.text().to_string(),
+            "stock_to_leave": w.stock_to_leave.text().to_string(),
+            "finishing_tool": Self::selected_pass(&w.finishing_tool, &w.library)
+                .map(|pass| pass.tool.id)
+                .unwrap_or_default(),
+            "finishing_stepover": w.finishing_stepover.text().to_string(),
+            "offset_x": w.offset_x.text().to_string(),
+            "offset_y": w.offset_y.text().to_string(),
+            "safe_z": w.safe_z.text().to_string(),
+        })
+    }
+
+    fn apply_params(w: &ReliefCarvingWidgets, params: &serde_json::Value) {
+        if let Some(image_path) = params.get("image_path").and_then(|v| v.as_str()) {
+            w.image_path.set_text(image_path);
+            Self::load_image_preview(w, image_path.to_string());
+        }
+        // Tools first, so their defaults do not overwrite the saved steps
+        let select = |combo: &ComboBoxText, key: &str| {
+            if let Some(id) = params.get(key).and_then(|v| v.as_str()) {
+                if let Some(index) = w.library.iter().position(|pass| pass.tool.id == id) {
+                    combo.set_active_id(Some(index.to_string().as_str()));
+                }
+            }
+        };
+        select(&w.roughing_tool, "roughing_tool");
+        select(&w.finishing_tool, "finishing_tool");
+
+        let entries = [
+            ("width", &w.width),
+            ("resolution", &w.resolution),
+            ("min_depth", &w.min_depth),
+            ("max_depth", &w.max_depth),
+            ("gamma", &w.gamma),
+            ("smoothing", &w.smoothing),
+            ("roughing_stepover", &w.roughing_stepover),
+            ("roughing_step_down", &w.roughing_step_down),
+            ("stock_to_leave", &w.stock_to_leave),
+            ("finishing_stepover", &w.finishing_stepover),
+            ("offset_x", &w.offset_x),
+            ("offset_y", &w.offset_y),
+            ("safe_z", &w.safe_z),
+        ];
+        for (key, entry) in entries {
+            if let Some(v) = params.get(key).and_then(|v| v.as_str()) {
+                entry.set_text(v);
+            }
+        }
+        if let Some(v) = params.get("invert").and_then(|v| v.as_bool()) {
+            w.invert.set_active(v);
+        }
+        if let Some(v) = params.get("roughing_enabled").and_then(|v| v.as_bool()) {
+            w.roughing_enabled.set_active(v);
+        }
+    }
+}
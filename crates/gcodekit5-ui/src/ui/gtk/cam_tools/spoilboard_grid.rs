@@ -250,7 +250,10 @@ impl SpoilboardGridTool {
                 laser_mode: laser_mode_str,
             };
 
-            let generator = SpoilboardGridGenerator::new(params);
+            let mut generator = SpoilboardGridGenerator::new(params);
+            if let Some(post) = crate::device_status::get_active_post_processor() {
+                generator = generator.with_post_processor(post);
+            }
             match generator.generate() {
                 Ok(mut gcode) => {
                     gcode = gcode.replace("$H\n", "").replace("$H", "");
@@ -272,7 +272,10 @@ impl SpoilboardSurfacingTool {
                 safe_z: units::parse_length(&w_gen.safe_z.text(), system).unwrap_or(5.0) as f64,
             };
 
-            let generator = SpoilboardSurfacingGenerator::new(params);
+            let mut generator = SpoilboardSurfacingGenerator::new(params);
+            if let Some(post) = crate::device_status::get_active_post_processor() {
+                generator = generator.with_post_processor(post);
+            }
             match generator.generate() {
                 Ok(mut gcode) => {
                     gcode = gcode.replace("$H\n", "").replace("$H", "");
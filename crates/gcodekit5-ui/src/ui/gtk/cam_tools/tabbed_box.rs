@@ -430,6 +430,9 @@ impl TabbedBoxMaker {
                         return Err("Cancelled by user".to_string());
                     }
                     let mut generator = Generator::new(params)?;
+                    if let Some(post) = crate::device_status::get_active_post_processor() {
+                        generator = generator.with_post_processor(post);
+                    }
                     generator.generate()?;
                     let mut gcode = generator.to_gcode();
 
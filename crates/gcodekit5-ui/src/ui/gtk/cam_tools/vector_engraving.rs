@@ -479,7 +479,12 @@ impl VectorEngravingTool {
 
             // Spawn background thread
             std::thread::spawn(move || {
+                let post = crate::device_status::get_active_post_processor();
                 let result = VectorEngraver::from_file(&vector_path, params)
+                    .map(|engraver| match post {
+                        Some(post) => engraver.with_post_processor(post),
+                        None => engraver,
+                    })
                     .and_then(|engraver| {
                         engraver.generate_gcode_with_progress(|progress| {
                             if cancel_rx.try_recv().is_ok() {
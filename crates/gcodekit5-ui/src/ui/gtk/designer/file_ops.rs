@@ -87,6 +87,8 @@ impl DesignerView {
                                 state.tool_settings.spindle_speed = design.toolpath_params.spindle_speed as u32;
                                 state.tool_settings.tool_diameter = design.toolpath_params.tool_diameter;
                                 state.tool_settings.cut_depth = design.toolpath_params.cut_depth;
+                                state.tool_change = design.toolpath_params.tool_change.clone();
+                                state.optimize_order = design.toolpath_params.optimize_order;
 
                                 state.stock_material = Some(StockMaterial {
                                     width: design.toolpath_params.stock_width,
@@ -503,6 +505,8 @@ impl DesignerView {
                         state.tool_settings.spindle_speed as f64;
                         design.toolpath_params.tool_diameter = state.tool_settings.tool_diameter;
                         design.toolpath_params.cut_depth = state.tool_settings.cut_depth;
+                        design.toolpath_params.tool_change = state.tool_change.clone();
+                        design.toolpath_params.optimize_order = state.optimize_order;
 
                         // Stock and toolpath parameters
                         if let Some(ref stock) = state.stock_material {
@@ -570,6 +574,8 @@ impl DesignerView {
         design.toolpath_params.spindle_speed = state.tool_settings.spindle_speed as f64;
         design.toolpath_params.tool_diameter = state.tool_settings.tool_diameter;
         design.toolpath_params.cut_depth = state.tool_settings.cut_depth;
+        design.toolpath_params.tool_change = state.tool_change.clone();
+        design.toolpath_params.optimize_order = state.optimize_order;
 
         // Stock and toolpath parameters
         if let Some(ref stock) = state.stock_material {
@@ -686,6 +692,8 @@ impl DesignerView {
                         state.toolpath_generator.set_start_depth(start_depth);
                         state.toolpath_generator.set_step_in(tool_diameter * 0.4); // Default stepover
 
+                        state.post_processor =
+                            crate::device_status::get_active_post_processor();
                         let gcode = state.generate_gcode();
 
                         match std::fs::write(&path, gcode) {
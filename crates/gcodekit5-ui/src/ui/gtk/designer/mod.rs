@@ -510,10 +510,21 @@ impl DesignerView {
             state.toolpath_generator.set_start_depth(start_depth);
             state.toolpath_generator.set_step_in(tool_diameter * 0.4); // Default stepover
 
+            state.post_processor = crate::device_status::get_active_post_processor();
             let gcode = state.generate_gcode();
+            let order_report = state.order_report;
             drop(state);
 
-            status_label_gen.set_text(&t!("G-Code generated"));
+            if order_report.jobs > 1 && order_report.saved() > 0.05 {
+                status_label_gen.set_text(&format!(
+                    "{} ({} {:.1} mm)",
+                    t!("G-Code generated"),
+                    t!("rapid travel saved:"),
+                    order_report.saved()
+                ));
+            } else {
+                status_label_gen.set_text(&t!("G-Code generated"));
+            }
 
             if let Some(callback) = on_gen.borrow().as_ref() {
                 callback(gcode);
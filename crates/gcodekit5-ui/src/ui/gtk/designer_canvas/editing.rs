@@ -4,6 +4,7 @@ use super::*;
 use gcodekit5_designer::canvas::DrawingObject;
 use gcodekit5_designer::commands::{DesignerCommand, PasteShapes, RemoveShape};
 use gcodekit5_designer::model::DesignerShape;
+use gcodekit5_designer::nesting::NestSettings;
 
 impl DesignerCanvas {
     pub fn delete_selected(&self) {
@@ -520,6 +521,75 @@ impl DesignerCanvas {
         self.widget.queue_draw();
     }
 
+    pub fn nest_selected(&self, quantities: &[usize], settings: &NestSettings) {
+        let result = self.state.borrow_mut().nest_selected(quantities, settings);
+        let Some(result) = result else {
+            return;
+        };
+
+        if let Some(layers_panel) = self.layers.borrow().as_ref() {
+            layers_panel.refresh(&self.state);
+        }
+        if let Some(ref props) = *self.properties.borrow() {
+            props.update_from_selection();
+        }
+
+        // Update toolpaths if enabled
+        let show_toolpaths = self.state.borrow().show_toolpaths;
+        if show_toolpaths {
+            self.generate_preview_toolpaths();
+        }
+
+        self.widget.queue_draw();
+
+        // Report the utilisation of each sheet
+        let mut report: Vec<String> = result
+            .sheets
+            .iter()
+            .enumerate()
+            .map(|(i, sheet)| {
+                format!(
+                    "{} {}: {} {}, {:.1}% {}",
+                    t!("Sheet"),
+                    i + 1,
+                    sheet.parts,
+                    t!("parts"),
+                    sheet.utilisation * 100.0,
+                    t!("used")
+                )
+            })
+            .collect();
+        if result.unplaced_count() > 0 {
+            report.push(format!(
+                "{} {}",
+                result.unplaced_count(),
+                t!("parts did not fit")
+            ));
+        }
+
+        let Some(window) = self.widget.root().and_downcast::<gtk4::Window>() else {
+            return;
+        };
+        let dialog = gtk4::MessageDialog::builder()
+            .transient_for(&window)
+            .modal(true)
+            .message_type(gtk4::MessageType::Info)
+            .buttons(gtk4::ButtonsType::Ok)
+            .text(format!(
+                "{} {} {} {} {:.1}% {}",
+                result.placed(),
+                t!("parts nested on"),
+                result.sheets.len(),
+                t!("sheets,"),
+                result.utilisation() * 100.0,
+                t!("used")
+            ))
+            .secondary_text(report.join("\n"))
+            .build();
+        dialog.connect_response(|d, _| d.close());
+        dialog.present();
+    }
+
     pub fn undo(&self) {
         let mut state = self.state.borrow_mut();
         state.undo();
@@ -7,6 +7,8 @@ use gcodekit5_designer::model::{
     DesignPolygon as Polygon, DesignRectangle as Rectangle, DesignText as TextShape,
     DesignTriangle as Triangle, Point, Shape,
 };
+use gcodekit5_designer::nesting::{NestRotation, NestSettings};
+use gcodekit5_designer::tabs::Outline;
 use gtk4::prelude::*;
 use gtk4::{
     Box, Button, CheckButton, Dialog, DropDown, Entry, Grid, Label, Orientation, Popover,
@@ -212,6 +214,7 @@ impl DesignerCanvas {
         let can_ungroup = state_borrow.can_ungroup();
         let can_align = selected_count >= 2;
         let can_boolean = selected_count >= 2;
+        let can_nest = state_borrow.stock_material.is_some();
         drop(state_borrow);
 
         let menu = Popover::new();
@@ -330,6 +333,7 @@ impl DesignerCanvas {
                     "convert_to_rectangle" => canvas.convert_to_rectangle(),
                     "mirror_x" => canvas.mirror_x(),
                     "mirror_y" => canvas.mirror_y(),
+                    "nest_parts" => canvas.open_nest_dialog(),
                     _ => {}
                 }
             });
@@ -483,6 +487,10 @@ impl DesignerCanvas {
         vbox.append(&Separator::new(Orientation::Horizontal));
         vbox.append(&create_item("Convert to Path", "convert_to_path"));
         vbox.append(&create_item("Convert to Rectangle", "convert_to_rectangle"));
+        if can_nest {
+            vbox.append(&Separator::new(Orientation::Horizontal));
+            vbox.append(&create_item("Nest Parts…", "nest_parts"));
+        }
 
         menu.set_child(Some(&vbox));
         menu.popup();
@@ -679,6 +687,159 @@ impl DesignerCanvas {
         }
     }
 
+    fn open_nest_dialog(&self) {
+        let (parts, sheet) = {
+            let state = self.state.borrow();
+            let Some(stock) = state.stock_material.as_ref() else {
+                return;
+            };
+            (state.selected_nest_parts(), (stock.width, stock.height))
+        };
+        if parts.is_empty() {
+            return;
+        }
+
+        let dialog = Dialog::builder()
+            .title(t!("Nest Parts"))
+            .modal(true)
+            .resizable(false)
+            .build();
+        dialog.add_button(&t!("Cancel"), ResponseType::Cancel);
+        dialog.add_button(&t!("Nest"), ResponseType::Ok);
+        dialog.set_default_response(ResponseType::Ok);
+        if let Some(root) = self.widget.root() {
+            if let Ok(win) = root.downcast::<gtk4::Window>() {
+                dialog.set_transient_for(Some(&win));
+            }
+        }
+
+        let content = Box::new(Orientation::Vertical, 10);
+        content.set_margin_top(12);
+        content.set_margin_bottom(12);
+        content.set_margin_start(12);
+        content.set_margin_end(12);
+
+        let sheet_label = Label::new(Some(&format!(
+            "{} {:.1} x {:.1} mm",
+            t!("Sheet (stock):"),
+            sheet.0,
+            sheet.1
+        )));
+        sheet_label.set_halign(gtk4::Align::Start);
+        content.append(&sheet_label);
+
+        let defaults = NestSettings::default();
+        let grid = Grid::builder().row_spacing(8).column_spacing(8).build();
+        let add_row = |row: i32, label: &str, value: String, unit: &str| {
+            let label = Label::new(Some(label));
+            label.set_halign(gtk4::Align::Start);
+            let entry = Entry::new();
+            entry.set_text(&value);
+            entry.set_hexpand(true);
+            entry.set_activates_default(true);
+            let unit = Label::new(Some(unit));
+            unit.set_width_chars(4);
+            unit.set_xalign(1.0);
+            grid.attach(&label, 0, row, 1, 1);
+            grid.attach(&entry, 1, row, 1, 1);
+            grid.attach(&unit, 2, row, 1, 1);
+            entry
+        };
+        let spacing_entry = add_row(0, &t!("Spacing:"), format!("{}", defaults.spacing), "mm");
+        let margin_entry = add_row(1, &t!("Edge Margin:"), format!("{}", defaults.margin), "mm");
+
+        let rotation_label = Label::new(Some(&t!("Rotation:")));
+        rotation_label.set_halign(gtk4::Align::Start);
+        let rotations = [
+            NestRotation::Fixed,
+            NestRotation::Orthogonal,
+            NestRotation::Free,
+        ];
+        let rotation_names: Vec<&str> = rotations.iter().map(|r| r.name()).collect();
+        let rotation_combo = DropDown::from_strings(&rotation_names);
+        rotation_combo.set_selected(1);
+        grid.attach(&rotation_label, 0, 2, 1, 1);
+        grid.attach(&rotation_combo, 1, 2, 2, 1);
+
+        let step_entry = add_row(
+            3,
+            &t!("Angle Step:"),
+            format!("{}", defaults.angle_step),
+            "°",
+        );
+        let sheets_entry = add_row(
+            4,
+            &t!("Max Sheets:"),
+            format!("{}", defaults.max_sheets),
+            "",
+        );
+        step_entry.set_sensitive(false);
+        let step_clone = step_entry.clone();
+        rotation_combo.connect_selected_notify(move |combo| {
+            step_clone.set_sensitive(combo.selected() == 2);
+        });
+        content.append(&grid);
+
+        let header = Label::new(Some(&t!("Quantity")));
+        header.add_css_class("title-4");
+        header.set_halign(gtk4::Align::Start);
+        content.append(&header);
+
+        let parts_grid = Grid::builder().row_spacing(4).column_spacing(8).build();
+        let quantity_entries: Vec<Entry> = parts
+            .iter()
+            .enumerate()
+            .map(|(row, name)| {
+                let label = Label::new(Some(name));
+                label.set_halign(gtk4::Align::Start);
+                label.set_hexpand(true);
+                let entry = Entry::new();
+                entry.set_text("1");
+                entry.set_width_chars(5);
+                entry.set_activates_default(true);
+                parts_grid.attach(&label, 0, row as i32, 1, 1);
+                parts_grid.attach(&entry, 1, row as i32, 1, 1);
+                entry
+            })
+            .collect();
+        content.append(&parts_grid);
+
+        dialog.content_area().append(&content);
+
+        let canvas = self.clone();
+        dialog.connect_response(move |d, resp| {
+            if resp == ResponseType::Ok {
+                let number = |entry: &Entry, default: f64| {
+                    entry
+                        .text()
+                        .trim()
+                        .parse::<f64>()
+                        .unwrap_or(default)
+                        .max(0.0)
+                };
+                let settings = NestSettings {
+                    spacing: number(&spacing_entry, defaults.spacing),
+                    margin: number(&margin_entry, defaults.margin),
+                    rotation: rotations
+                        .get(rotation_combo.selected() as usize)
+                        .copied()
+                        .unwrap_or_default(),
+                    angle_step: number(&step_entry, defaults.angle_step),
+                    max_sheets: number(&sheets_entry, defaults.max_sheets as f64) as usize,
+                    ..defaults.clone()
+                };
+                let quantities: Vec<usize> = quantity_entries
+                    .iter()
+                    .map(|entry| number(entry, 1.0) as usize)
+                    .collect();
+                canvas.nest_selected(&quantities, &settings);
+            }
+            d.close();
+        });
+
+        dialog.present();
+    }
+
     pub(super) fn handle_click(&self, x: f64, y: f64, ctrl_pressed_arg: bool, n_press: i32) {
         // Combine gesture modifier state with tracked keyboard state for reliability
         let ctrl_pressed = ctrl_pressed_arg || *self.ctrl_pressed.borrow();
@@ -1001,6 +1162,34 @@ impl DesignerCanvas {
                     }
                 };
 
+                // Grabbing a holding tab marker moves the tab along the outline
+                let tab_hit = {
+                    let state = self.state.borrow();
+                    Self::tab_marker_at(&state, canvas_x, canvas_y, zoom).and_then(|(id, index)| {
+                        state
+                            .canvas
+                            .get_shape(id)
+                            .map(|obj| (id, index, obj.clone()))
+                    })
+                };
+                if let Some(hit) = tab_hit {
+                    *self.active_tab_drag.borrow_mut() = Some(hit);
+                    *self.creation_start.borrow_mut() = Some((canvas_x, canvas_y));
+                    return;
+                }
+
+                // Grabbing the lead start marker moves where the profile is entered
+                let lead_hit = {
+                    let state = self.state.borrow();
+                    Self::lead_marker_at(&state, canvas_x, canvas_y, zoom)
+                        .and_then(|id| state.canvas.get_shape(id).map(|obj| (id, obj.clone())))
+                };
+                if let Some(hit) = lead_hit {
+                    *self.active_lead_drag.borrow_mut() = Some(hit);
+                    *self.creation_start.borrow_mut() = Some((canvas_x, canvas_y));
+                    return;
+                }
+
                 if let (Some(selected_id), Some(bounds)) = (selected_id_opt, bounds_opt) {
                     if let Some(handle) =
                         self.get_resize_handle_at(canvas_x, canvas_y, &bounds, zoom)
@@ -1112,8 +1301,28 @@ impl DesignerCanvas {
 
             // If in select mode, handle resizing or moving
             if tool == DesignerTool::Select {
-                // Check if we're resizing
-                if let Some((handle, shape_id)) = *self.active_resize_handle.borrow() {
+                let tab_drag = self
+                    .active_tab_drag
+                    .borrow()
+                    .as_ref()
+                    .map(|(id, index, _)| (*id, *index));
+                let lead_drag = self.active_lead_drag.borrow().as_ref().map(|(id, _)| *id);
+                // Check if we're moving a tab, the lead start or resizing
+                if let Some(shape_id) = lead_drag {
+                    self.move_lead_start(
+                        shape_id,
+                        start.0 + canvas_offset_x,
+                        start.1 - canvas_offset_y,
+                    );
+                } else if let Some((shape_id, index)) = tab_drag {
+                    // Follow the pointer exactly; tabs are not snapped to the grid
+                    self.move_tab(
+                        shape_id,
+                        index,
+                        start.0 + canvas_offset_x,
+                        start.1 - canvas_offset_y,
+                    );
+                } else if let Some((handle, shape_id)) = *self.active_resize_handle.borrow() {
                     self.apply_resize(handle, shape_id, current_x, current_y, shift_pressed);
                 } else {
                     let mut state = self.state.borrow_mut();
@@ -1251,6 +1460,12 @@ impl DesignerCanvas {
             let end_y = start.1 - canvas_offset_y; // Flip Y offset
 
             match tool {
+                DesignerTool::Select if self.active_tab_drag.borrow().is_some() => {
+                    self.finish_tab_drag();
+                }
+                DesignerTool::Select if self.active_lead_drag.borrow().is_some() => {
+                    self.finish_lead_drag();
+                }
                 DesignerTool::Select => {
                     // Check if we were resizing and need to create undo command
                     let was_resizing = self.active_resize_handle.borrow().is_some();
@@ -1403,6 +1618,76 @@ impl DesignerCanvas {
         }
     }
 
+    /// Moves a holding tab to the point on the shape's outline nearest
+    /// `(x, y)`. Automatically spaced tabs become manual on first move.
+    fn move_tab(&self, shape_id: u64, index: usize, x: f64, y: f64) {
+        let mut state = self.state.borrow_mut();
+        if let Some(obj) = state.canvas.get_shape_mut(shape_id) {
+            let outline = Outline::from_shape(&obj.get_effective_shape());
+            if !obj.tabs.is_manual() {
+                obj.tabs.positions = obj.tabs.resolve_positions(&outline);
+            }
+            if let (Some(fraction), Some(slot)) = (
+                outline.project(Point::new(x, y)),
+                obj.tabs.positions.get_mut(index),
+            ) {
+                *slot = fraction;
+            }
+        }
+    }
+
+    /// Ends a tab drag, recording the move for undo.
+    fn finish_tab_drag(&self) {
+        let Some((id, _, old_state)) = self.active_tab_drag.borrow_mut().take() else {
+            return;
+        };
+        let mut state = self.state.borrow_mut();
+        let new_state = state.canvas.get_shape(id).cloned();
+        if let Some(new_state) = new_state.filter(|obj| obj.tabs != old_state.tabs) {
+            state.record_command(
+                gcodekit5_designer::commands::DesignerCommand::ChangeProperty(
+                    gcodekit5_designer::commands::ChangeProperty {
+                        id,
+                        old_state,
+                        new_state,
+                    },
+                ),
+            );
+        }
+    }
+
+    /// Moves the lead start of a profile to the point on its outline
+    /// nearest `(x, y)`.
+    fn move_lead_start(&self, shape_id: u64, x: f64, y: f64) {
+        let mut state = self.state.borrow_mut();
+        if let Some(obj) = state.canvas.get_shape_mut(shape_id) {
+            let outline = Outline::from_shape(&obj.get_effective_shape());
+            if let Some(fraction) = outline.project(Point::new(x, y)) {
+                obj.leads.start_position = Some(fraction);
+            }
+        }
+    }
+
+    /// Ends a lead start drag, recording the move for undo.
+    fn finish_lead_drag(&self) {
+        let Some((id, old_state)) = self.active_lead_drag.borrow_mut().take() else {
+            return;
+        };
+        let mut state = self.state.borrow_mut();
+        let new_state = state.canvas.get_shape(id).cloned();
+        if let Some(new_state) = new_state.filter(|obj| obj.leads != old_state.leads) {
+            state.record_command(
+                gcodekit5_designer::commands::DesignerCommand::ChangeProperty(
+                    gcodekit5_designer::commands::ChangeProperty {
+                        id,
+                        old_state,
+                        new_state,
+                    },
+                ),
+            );
+        }
+    }
+
     fn create_shape(&self, tool: DesignerTool, start: (f64, f64), end: (f64, f64)) {
         // Scope the borrow to release it before queue_draw
         {
@@ -18,6 +18,7 @@ use crate::ui::gtk::designer_properties::PropertiesPanel;
 use crate::ui::gtk::designer_toolbox::{DesignerTool, DesignerToolbox};
 use gcodekit5_core::constants as core_constants;
 use gcodekit5_core::{shared, shared_none, Shared, SharedOption, SharedVec};
+use gcodekit5_designer::canvas::DrawingObject;
 use gcodekit5_designer::designer_state::DesignerState;
 use gcodekit5_designer::model::{DesignPath as PathShape, Point, Shape};
 use gcodekit5_designer::toolpath::Toolpath;
@@ -107,6 +108,10 @@ pub struct DesignerCanvas {
     pub(crate) active_resize_handle: SharedOption<(ResizeHandle, u64)>, // (handle, shape_id)
     pub(crate) resize_original_bounds: SharedOption<(f64, f64, f64, f64)>, // (x, y, width, height)
     pub(crate) resize_original_shapes: SharedOption<Vec<(u64, Shape)>>,
+    // Holding tab drag state: (shape_id, tab index, object before the drag)
+    pub(crate) active_tab_drag: SharedOption<(u64, usize, DrawingObject)>,
+    // Lead start drag state: (shape_id, object before the drag)
+    pub(crate) active_lead_drag: SharedOption<(u64, DrawingObject)>,
     // Scroll adjustments
     pub(crate) hadjustment: SharedOption<gtk4::Adjustment>,
     pub(crate) vadjustment: SharedOption<gtk4::Adjustment>,
@@ -227,6 +232,8 @@ impl DesignerCanvas {
             active_resize_handle: shared_none(),
             resize_original_bounds: shared_none(),
             resize_original_shapes: shared_none(),
+            active_tab_drag: shared_none(),
+            active_lead_drag: shared_none(),
             hadjustment: shared_none(),
             vadjustment: shared_none(),
             shift_pressed: shared(false),
@@ -3,6 +3,9 @@
 use super::*;
 use gcodekit5_designer::designer_state::DesignerState;
 use gcodekit5_designer::model::{DesignerShape, Point, Shape};
+use gcodekit5_designer::shapes::OperationType;
+use gcodekit5_designer::tabs;
+use gcodekit5_designer::tabs::Outline;
 use gcodekit5_designer::toolpath::{Toolpath, ToolpathSegmentType};
 
 impl DesignerCanvas {
@@ -238,7 +241,8 @@ impl DesignerCanvas {
             let _ = cr.restore();
 
             // 2. Draw Effective Shape (Yellow Overlay) if modified
-            if obj.offset.abs() > 1e-6 || obj.fillet.abs() > 1e-6 || obj.chamfer.abs() > 1e-6 {
+            if obj.offset.abs() > 1e-6 || obj.fillet.abs() > 1e-6 || obj.chamfer.abs() > 1e-6
+                || obj.dogbone.is_active() {
                 let _ = cr.save();
                 cr.set_source_rgba(
                     warning_color.red() as f64,
@@ -250,6 +254,19 @@ impl DesignerCanvas {
                 Self::draw_shape_geometry(cr, &obj.get_effective_shape());
                 let _ = cr.restore();
             }
+
+            // 3. Draw holding tab markers on profiles
+            if obj.operation_type == OperationType::Profile && obj.tabs.enabled {
+                Self::draw_tab_markers(cr, obj, zoom, &warning_color);
+            }
+
+            // 4. Draw the lead start marker on selected profiles
+            if obj.selected
+                && obj.operation_type == OperationType::Profile
+                && obj.leads.is_active()
+            {
+                Self::draw_lead_start_marker(cr, obj, zoom, &accent_color);
+            }
         }
 
         // Draw Preview Shapes (e.g. for offset/fillet) in yellow
@@ -1032,6 +1049,123 @@ impl DesignerCanvas {
         }
     }
 
+    /// Draws a marker at each holding tab, sized to the tab width but never
+    /// smaller than a grabbable handle.
+    fn draw_tab_markers(
+        cr: &gtk4::cairo::Context,
+        obj: &DrawingObject,
+        zoom: f64,
+        color: &gtk4::gdk::RGBA,
+    ) {
+        let radius = (obj.tabs.width / 2.0).max(4.0 / zoom);
+        let points = tabs::tab_points(&obj.get_effective_shape(), &obj.tabs);
+
+        let _ = cr.save();
+        for p in points {
+            cr.arc(p.x, p.y, radius, 0.0, 2.0 * std::f64::consts::PI);
+            cr.set_source_rgba(
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+                0.5,
+            );
+            let _ = cr.fill_preserve();
+            cr.set_source_rgba(
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+                1.0,
+            );
+            cr.set_line_width(1.5 / zoom);
+            let _ = cr.stroke();
+        }
+        let _ = cr.restore();
+    }
+
+    /// Finds the holding tab marker under `(x, y)` on a selected profile,
+    /// returning the shape id and the tab's index.
+    pub(super) fn tab_marker_at(
+        state: &DesignerState,
+        x: f64,
+        y: f64,
+        zoom: f64,
+    ) -> Option<(u64, usize)> {
+        let zoom = zoom.max(1e-6);
+        state
+            .canvas
+            .shapes()
+            .filter(|o| {
+                o.selected && o.operation_type == OperationType::Profile && o.tabs.enabled
+            })
+            .find_map(|obj| {
+                let tolerance = (obj.tabs.width / 2.0).max(6.0 / zoom);
+                tabs::tab_points(&obj.get_effective_shape(), &obj.tabs)
+                    .iter()
+                    .position(|p| (p.x - x).hypot(p.y - y) <= tolerance)
+                    .map(|index| (obj.id, index))
+            })
+    }
+
+    /// Where the lead moves of `obj` start on its outline; the outline's own
+    /// start when no position has been chosen.
+    fn lead_start_marker_point(obj: &DrawingObject) -> Option<Point> {
+        Outline::from_shape(&obj.get_effective_shape())
+            .point_at(obj.leads.start_position.unwrap_or(0.0))
+    }
+
+    /// Draws a diamond at the point where the profile's lead-in starts.
+    fn draw_lead_start_marker(
+        cr: &gtk4::cairo::Context,
+        obj: &DrawingObject,
+        zoom: f64,
+        color: &gtk4::gdk::RGBA,
+    ) {
+        let Some(p) = Self::lead_start_marker_point(obj) else {
+            return;
+        };
+        let size = 6.0 / zoom;
+
+        let _ = cr.save();
+        cr.move_to(p.x, p.y + size);
+        cr.line_to(p.x + size, p.y);
+        cr.line_to(p.x, p.y - size);
+        cr.line_to(p.x - size, p.y);
+        cr.close_path();
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        let _ = cr.fill_preserve();
+        cr.set_source_rgba(
+            color.red() as f64,
+            color.green() as f64,
+            color.blue() as f64,
+            1.0,
+        );
+        cr.set_line_width(2.0 / zoom);
+        let _ = cr.stroke();
+        let _ = cr.restore();
+    }
+
+    /// Finds the lead start marker under `(x, y)` on a selected profile,
+    /// returning the shape id.
+    pub(super) fn lead_marker_at(
+        state: &DesignerState,
+        x: f64,
+        y: f64,
+        zoom: f64,
+    ) -> Option<u64> {
+        let tolerance = 8.0 / zoom.max(1e-6);
+        state
+            .canvas
+            .shapes()
+            .filter(|o| {
+                o.selected && o.operation_type == OperationType::Profile && o.leads.is_active()
+            })
+            .find(|obj| {
+                Self::lead_start_marker_point(obj)
+                    .is_some_and(|p| (p.x - x).hypot(p.y - y) <= tolerance)
+            })
+            .map(|obj| obj.id)
+    }
+
     fn draw_resize_handles(
         cr: &gtk4::cairo::Context,
         bounds: &(f64, f64, f64, f64),
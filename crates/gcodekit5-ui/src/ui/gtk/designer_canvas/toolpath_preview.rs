@@ -1,8 +1,11 @@
 //! Toolpath preview generation for the designer canvas
 
 use super::*;
+use gcodekit5_designer::job_order::{optimize_order, OrderJob};
 use gcodekit5_designer::model::{DesignCircle as Circle, DesignerShape, Point, Shape};
+use gcodekit5_designer::profile_operations::CutSide;
 use gcodekit5_designer::shapes::OperationType;
+use gcodekit5_designer::tool_changes::group_by_tool;
 use gcodekit5_designer::toolpath::Toolpath;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -22,7 +25,7 @@ impl DesignerCanvas {
 
         let started_at = std::time::Instant::now();
 
-        let (shapes, feed_rate, spindle_speed, tool_diameter, cut_depth) = {
+        let (shapes, feed_rate, spindle_speed, tool_diameter, cut_depth, optimize) = {
             let state = self.state.borrow();
             (
                 state.canvas.shapes().cloned().collect::<Vec<_>>(),
@@ -30,6 +33,7 @@ impl DesignerCanvas {
                 state.tool_settings.spindle_speed,
                 state.tool_settings.tool_diameter,
                 state.tool_settings.cut_depth,
+                state.optimize_order,
             )
         };
 
@@ -63,125 +67,194 @@ impl DesignerCanvas {
             gen.set_cut_depth(cut_depth);
             gen.set_step_in(tool_diameter * 0.4);
 
-            let mut toolpaths = Vec::new();
+            let mut jobs = Vec::new();
             for shape in shapes {
                 if cancel.load(Ordering::SeqCst) {
                     return;
                 }
 
+                // Library tools override the designer's tool settings
+                match &shape.tool {
+                    Some(tool) => {
+                        gen.set_tool_diameter(tool.diameter);
+                        gen.set_feed_rate(tool.feed_rate);
+                        gen.set_spindle_speed(tool.spindle_speed);
+                    }
+                    None => {
+                        gen.set_tool_diameter(tool_diameter);
+                        gen.set_feed_rate(feed_rate);
+                        gen.set_spindle_speed(spindle_speed);
+                    }
+                }
                 gen.set_pocket_strategy(shape.pocket_strategy);
                 gen.set_start_depth(shape.start_depth);
                 gen.set_cut_depth(shape.pocket_depth);
                 gen.set_step_in(shape.step_in as f64);
                 gen.set_raster_fill_ratio(shape.raster_fill_ratio);
+                // Engraving follows the drawn line itself
+                let is_engrave = shape.operation_type == OperationType::Engrave;
+                gen.set_cut_side(if is_engrave {
+                    CutSide::OnLine
+                } else {
+                    shape.cut_side
+                });
+                gen.set_cut_direction(shape.cut_direction);
+                gen.set_finishing_allowance(if is_engrave {
+                    0.0
+                } else {
+                    shape.finishing_allowance
+                });
 
                 let effective_shape = shape.get_effective_shape();
-                let shape_toolpaths = match &effective_shape {
-                    Shape::Rectangle(rect) => {
-                        if shape.operation_type == OperationType::Pocket {
-                            gen.generate_rectangle_pocket(
-                                rect,
-                                shape.pocket_depth,
-                                shape.step_down as f64,
-                                shape.step_in as f64,
-                            )
-                        } else {
-                            gen.generate_rectangle_contour(rect, shape.step_down as f64)
-                        }
+                let tab_points = if shape.operation_type == OperationType::Profile {
+                    gcodekit5_designer::tabs::tab_points(&effective_shape, &shape.tabs)
+                } else {
+                    Vec::new()
+                };
+                gen.set_tabs(shape.tabs.clone(), tab_points);
+                if shape.operation_type == OperationType::Profile {
+                    gen.set_leads(
+                        shape.leads.clone(),
+                        gcodekit5_designer::leads::lead_start_point(&effective_shape, &shape.leads),
+                    );
+                } else {
+                    gen.set_leads(Default::default(), None);
+                }
+                let shape_toolpaths = match shape.operation_type {
+                    OperationType::VCarve => {
+                        gen.generate_vcarve(&effective_shape, &shape.vcarve, shape.step_down as f64)
                     }
-                    Shape::Circle(circle) => {
-                        if shape.operation_type == OperationType::Pocket {
-                            gen.generate_circle_pocket(
-                                circle,
-                                shape.pocket_depth,
-                                shape.step_down as f64,
-                                shape.step_in as f64,
-                            )
-                        } else {
-                            gen.generate_circle_contour(circle, shape.step_down as f64)
+                    OperationType::Drill => gen.generate_drill(&effective_shape, &shape.drill),
+                    _ => match &effective_shape {
+                        Shape::Rectangle(rect) => {
+                            if shape.operation_type == OperationType::Pocket {
+                                gen.generate_rectangle_pocket(
+                                    rect,
+                                    shape.pocket_depth,
+                                    shape.step_down as f64,
+                                    shape.step_in as f64,
+                                )
+                            } else {
+                                gen.generate_rectangle_contour(rect, shape.step_down as f64)
+                            }
                         }
-                    }
-                    Shape::Line(line) => gen.generate_line_contour(line, shape.step_down as f64),
-                    Shape::Ellipse(ellipse) => {
-                        let (x1, y1, x2, y2) = ellipse.bounds();
-                        let cx = (x1 + x2) / 2.0;
-                        let cy = (y1 + y2) / 2.0;
-                        let radius = ((x2 - x1).abs().max((y2 - y1).abs())) / 2.0;
-                        let circle = Circle::new(Point::new(cx, cy), radius);
-                        gen.generate_circle_contour(&circle, shape.step_down as f64)
-                    }
-                    Shape::Path(path_shape) => {
-                        if shape.operation_type == OperationType::Pocket {
-                            gen.generate_path_pocket(
-                                path_shape,
-                                shape.pocket_depth,
-                                shape.step_down as f64,
-                                shape.step_in as f64,
-                            )
-                        } else {
-                            gen.generate_path_contour(path_shape, shape.step_down as f64)
+                        Shape::Circle(circle) => {
+                            if shape.operation_type == OperationType::Pocket {
+                                gen.generate_circle_pocket(
+                                    circle,
+                                    shape.pocket_depth,
+                                    shape.step_down as f64,
+                                    shape.step_in as f64,
+                                )
+                            } else {
+                                gen.generate_circle_contour(circle, shape.step_down as f64)
+                            }
                         }
-                    }
-                    Shape::Text(text) => {
-                        if shape.operation_type == OperationType::Pocket {
-                            gen.generate_text_pocket_toolpath(text, shape.step_down as f64)
-                        } else {
-                            gen.generate_text_toolpath(text, shape.step_down as f64)
+                        Shape::Line(line) => {
+                            gen.generate_line_contour(line, shape.step_down as f64)
                         }
-                    }
-                    Shape::Triangle(triangle) => {
-                        if shape.operation_type == OperationType::Pocket {
-                            gen.generate_triangle_pocket(
-                                triangle,
-                                shape.pocket_depth,
-                                shape.step_down as f64,
-                                shape.step_in as f64,
-                            )
-                        } else {
-                            gen.generate_triangle_contour(triangle, shape.step_down as f64)
+                        Shape::Ellipse(ellipse) => {
+                            let (x1, y1, x2, y2) = ellipse.bounds();
+                            let cx = (x1 + x2) / 2.0;
+                            let cy = (y1 + y2) / 2.0;
+                            let radius = ((x2 - x1).abs().max((y2 - y1).abs())) / 2.0;
+                            let circle = Circle::new(Point::new(cx, cy), radius);
+                            gen.generate_circle_contour(&circle, shape.step_down as f64)
                         }
-                    }
-                    Shape::Polygon(polygon) => {
-                        if shape.operation_type == OperationType::Pocket {
-                            gen.generate_polygon_pocket(
-                                polygon,
-                                shape.pocket_depth,
-                                shape.step_down as f64,
-                                shape.step_in as f64,
-                            )
-                        } else {
-                            gen.generate_polygon_contour(polygon, shape.step_down as f64)
+                        Shape::Path(path_shape) => {
+                            if shape.operation_type == OperationType::Pocket {
+                                gen.generate_path_pocket(
+                                    path_shape,
+                                    shape.pocket_depth,
+                                    shape.step_down as f64,
+                                    shape.step_in as f64,
+                                )
+                            } else {
+                                gen.generate_path_contour(path_shape, shape.step_down as f64)
+                            }
                         }
-                    }
-                    Shape::Gear(gear) => {
-                        if shape.operation_type == OperationType::Pocket {
-                            gen.generate_gear_pocket(
-                                gear,
-                                shape.pocket_depth,
-                                shape.step_down as f64,
-                                shape.step_in as f64,
-                            )
-                        } else {
-                            gen.generate_gear_contour(gear, shape.step_down as f64)
+                        Shape::Text(text) => {
+                            if shape.operation_type == OperationType::Pocket {
+                                gen.generate_text_pocket_toolpath(text, shape.step_down as f64)
+                            } else {
+                                gen.generate_text_toolpath(text, shape.step_down as f64)
+                            }
                         }
-                    }
-                    Shape::Sprocket(sprocket) => {
-                        if shape.operation_type == OperationType::Pocket {
-                            gen.generate_sprocket_pocket(
-                                sprocket,
-                                shape.pocket_depth,
-                                shape.step_down as f64,
-                                shape.step_in as f64,
-                            )
-                        } else {
-                            gen.generate_sprocket_contour(sprocket, shape.step_down as f64)
+                        Shape::Triangle(triangle) => {
+                            if shape.operation_type == OperationType::Pocket {
+                                gen.generate_triangle_pocket(
+                                    triangle,
+                                    shape.pocket_depth,
+                                    shape.step_down as f64,
+                                    shape.step_in as f64,
+                                )
+                            } else {
+                                gen.generate_triangle_contour(triangle, shape.step_down as f64)
+                            }
                         }
-                    }
+                        Shape::Polygon(polygon) => {
+                            if shape.operation_type == OperationType::Pocket {
+                                gen.generate_polygon_pocket(
+                                    polygon,
+                                    shape.pocket_depth,
+                                    shape.step_down as f64,
+                                    shape.step_in as f64,
+                                )
+                            } else {
+                                gen.generate_polygon_contour(polygon, shape.step_down as f64)
+                            }
+                        }
+                        Shape::Gear(gear) => {
+                            if shape.operation_type == OperationType::Pocket {
+                                gen.generate_gear_pocket(
+                                    gear,
+                                    shape.pocket_depth,
+                                    shape.step_down as f64,
+                                    shape.step_in as f64,
+                                )
+                            } else {
+                                gen.generate_gear_contour(gear, shape.step_down as f64)
+                            }
+                        }
+                        Shape::Sprocket(sprocket) => {
+                            if shape.operation_type == OperationType::Pocket {
+                                gen.generate_sprocket_pocket(
+                                    sprocket,
+                                    shape.pocket_depth,
+                                    shape.step_down as f64,
+                                    shape.step_in as f64,
+                                )
+                            } else {
+                                gen.generate_sprocket_contour(sprocket, shape.step_down as f64)
+                            }
+                        }
+                    },
                 };
-                toolpaths.extend(shape_toolpaths);
+                jobs.push((shape.tool.clone(), shape, shape_toolpaths));
                 done_shapes_thread.fetch_add(1, Ordering::Relaxed);
             }
 
+            // Show what each tool actually cuts, after rest machining, in cut order
+            let groups: Vec<Vec<((), OrderJob)>> = group_by_tool(jobs)
+                .into_iter()
+                .map(|group| {
+                    group
+                        .jobs
+                        .into_iter()
+                        .map(|(shape, toolpaths)| ((), OrderJob::for_object(&shape, toolpaths)))
+                        .collect()
+                })
+                .collect();
+            let groups = if optimize {
+                optimize_order(groups, Point::new(0.0, 0.0)).0
+            } else {
+                groups
+            };
+            let toolpaths: Vec<Toolpath> = groups
+                .into_iter()
+                .flatten()
+                .flat_map(|(_, job)| job.toolpaths)
+                .collect();
             *result_arc_thread.lock() = Some(toolpaths);
         });
 
@@ -493,6 +493,9 @@ impl LayersPanel {
             let (op_icon, op_tooltip) = match operation_type {
                 OperationType::Pocket => ("selection-mode-symbolic", t!("Pocket operation")),
                 OperationType::Profile => ("emblem-documents-symbolic", t!("Profile operation")),
+                OperationType::Engrave => ("document-edit-symbolic", t!("Engrave operation")),
+                OperationType::VCarve => ("go-down-symbolic", t!("V-Carve operation")),
+                OperationType::Drill => ("media-record-symbolic", t!("Drill operation")),
             };
             let op_image = Image::from_icon_name(op_icon);
             op_image.set_tooltip_text(Some(&op_tooltip));
@@ -343,8 +343,20 @@ impl PropertiesPanel {
         )
     }
 
-    pub(crate) fn build_geometry_ops_section() -> (Frame, Entry, Entry, Entry, Label, Label, Label)
-    {
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn build_geometry_ops_section() -> (
+        Frame,
+        Entry,
+        Entry,
+        Entry,
+        DropDown,
+        Entry,
+        Entry,
+        Label,
+        Label,
+        Label,
+        Label,
+    ) {
         let frame = Self::create_section(&t!("Geometry Operations"));
         let grid = gtk4::Grid::builder()
             .row_spacing(8)
@@ -386,15 +398,51 @@ impl PropertiesPanel {
         grid.attach(&chamfer_entry, 1, 2, 1, 1);
         grid.attach(&chamfer_unit_label, 2, 2, 1, 1);
 
+        // Dogbone / T-bone relief for inside corners
+        let relief_label = Label::new(Some(&t!("Corner Relief:")));
+        relief_label.set_halign(gtk4::Align::Start);
+        let relief_model = StringList::new(&[]);
+        relief_model.append(&t!("None"));
+        relief_model.append(&t!("Dogbone"));
+        relief_model.append(&t!("T-Bone"));
+        let dogbone_style_combo = DropDown::new(Some(relief_model), None::<Expression>);
+        dogbone_style_combo.set_hexpand(true);
+
+        let relief_tool_label = Label::new(Some(&t!("Tool Dia:")));
+        relief_tool_label.set_halign(gtk4::Align::Start);
+        let dogbone_tool_entry = Entry::new();
+        dogbone_tool_entry.set_hexpand(true);
+        let dogbone_tool_unit_label = Label::new(Some("mm"));
+
+        // Corners sharper than this are relieved
+        let relief_angle_label = Label::new(Some(&t!("Max Angle:")));
+        relief_angle_label.set_halign(gtk4::Align::Start);
+        let dogbone_angle_entry = Entry::new();
+        dogbone_angle_entry.set_hexpand(true);
+        let relief_angle_unit_label = Label::new(Some("°"));
+
+        grid.attach(&relief_label, 0, 3, 1, 1);
+        grid.attach(&dogbone_style_combo, 1, 3, 1, 1);
+        grid.attach(&relief_tool_label, 0, 4, 1, 1);
+        grid.attach(&dogbone_tool_entry, 1, 4, 1, 1);
+        grid.attach(&dogbone_tool_unit_label, 2, 4, 1, 1);
+        grid.attach(&relief_angle_label, 0, 5, 1, 1);
+        grid.attach(&dogbone_angle_entry, 1, 5, 1, 1);
+        grid.attach(&relief_angle_unit_label, 2, 5, 1, 1);
+
         frame.set_child(Some(&grid));
         (
             frame,
             offset_entry,
             fillet_entry,
             chamfer_entry,
+            dogbone_style_combo,
+            dogbone_tool_entry,
+            dogbone_angle_entry,
             offset_unit_label,
             fillet_unit_label,
             chamfer_unit_label,
+            dogbone_tool_unit_label,
         )
     }
 
@@ -408,6 +456,10 @@ impl PropertiesPanel {
         Entry,
         DropDown,
         Entry,
+        DropDown,
+        DropDown,
+        Entry,
+        Label,
         Label,
         Label,
         Label,
@@ -428,6 +480,9 @@ impl PropertiesPanel {
         let op_model = StringList::new(&[]);
         op_model.append(&t!("Profile"));
         op_model.append(&t!("Pocket"));
+        op_model.append(&t!("Engrave"));
+        op_model.append(&t!("V-Carve"));
+        op_model.append(&t!("Drill"));
         let op_type_combo = DropDown::new(Some(op_model), None::<Expression>);
         op_type_combo.set_hexpand(true);
 
@@ -490,6 +545,35 @@ impl PropertiesPanel {
         raster_fill_hint.add_css_class("dim-label");
         raster_fill_hint.set_halign(gtk4::Align::Start);
 
+        // Profile Cut Side
+        let cut_side_label = Label::new(Some(&t!("Cut Side:")));
+        cut_side_label.set_halign(gtk4::Align::Start);
+        let cut_side_model = StringList::new(&[]);
+        cut_side_model.append(&t!("Outside"));
+        cut_side_model.append(&t!("Inside"));
+        cut_side_model.append(&t!("On Line"));
+        let cut_side_combo = DropDown::new(Some(cut_side_model), None::<Expression>);
+        cut_side_combo.set_hexpand(true);
+
+        // Profile Cut Direction
+        let cut_direction_label = Label::new(Some(&t!("Direction:")));
+        cut_direction_label.set_halign(gtk4::Align::Start);
+        let cut_direction_model = StringList::new(&[]);
+        cut_direction_model.append(&t!("Climb"));
+        cut_direction_model.append(&t!("Conventional"));
+        let cut_direction_combo = DropDown::new(Some(cut_direction_model), None::<Expression>);
+        cut_direction_combo.set_hexpand(true);
+
+        // Finishing Allowance (left by roughing, removed by a final pass)
+        let finishing_allowance_label = Label::new(Some(&t!("Finish Allowance:")));
+        finishing_allowance_label.set_halign(gtk4::Align::Start);
+        let finishing_allowance_entry = Entry::new();
+        finishing_allowance_entry.set_hexpand(true);
+        let finishing_allowance_unit_label = Label::new(Some("mm"));
+        finishing_allowance_unit_label.set_width_chars(4);
+        finishing_allowance_unit_label.set_halign(gtk4::Align::End);
+        finishing_allowance_unit_label.set_xalign(1.0);
+
         grid.attach(&op_label, 0, 0, 1, 1);
         grid.attach(&op_type_combo, 1, 0, 1, 1);
         grid.attach(&depth_label, 0, 1, 1, 1);
@@ -509,6 +593,13 @@ impl PropertiesPanel {
         grid.attach(&raster_fill_label, 0, 6, 1, 1);
         grid.attach(&raster_fill_entry, 1, 6, 1, 1);
         grid.attach(&raster_fill_hint, 0, 7, 3, 1);
+        grid.attach(&cut_side_label, 0, 8, 1, 1);
+        grid.attach(&cut_side_combo, 1, 8, 1, 1);
+        grid.attach(&cut_direction_label, 0, 9, 1, 1);
+        grid.attach(&cut_direction_combo, 1, 9, 1, 1);
+        grid.attach(&finishing_allowance_label, 0, 10, 1, 1);
+        grid.attach(&finishing_allowance_entry, 1, 10, 1, 1);
+        grid.attach(&finishing_allowance_unit_label, 2, 10, 1, 1);
 
         frame.set_child(Some(&grid));
         (
@@ -520,9 +611,344 @@ impl PropertiesPanel {
             ramp_angle_entry,
             strategy_combo,
             raster_fill_entry,
+            cut_side_combo,
+            cut_direction_combo,
+            finishing_allowance_entry,
             depth_unit_label,
             step_down_unit_label,
             step_in_unit_label,
+            finishing_allowance_unit_label,
+        )
+    }
+
+    pub(crate) fn build_tool_section(tools: &[AssignedTool]) -> (Frame, DropDown, CheckButton) {
+        let frame = Self::create_section(&t!("Tool"));
+        let grid = gtk4::Grid::builder()
+            .row_spacing(8)
+            .column_spacing(8)
+            .margin_start(8)
+            .margin_end(8)
+            .margin_top(8)
+            .margin_bottom(8)
+            .build();
+
+        // First entry cuts with the designer's tool settings
+        let tool_label = Label::new(Some(&t!("Tool:")));
+        tool_label.set_halign(gtk4::Align::Start);
+        let tool_model = StringList::new(&[]);
+        tool_model.append(&t!("Tool Settings"));
+        for tool in tools {
+            tool_model.append(&format!("T{} {}", tool.number, tool.name));
+        }
+        let tool_combo = DropDown::new(Some(tool_model), None::<Expression>);
+        tool_combo.set_hexpand(true);
+
+        let rest_label = Label::new(Some(&t!("Rest Machining:")));
+        rest_label.set_halign(gtk4::Align::Start);
+        let rest_machining_check = CheckButton::new();
+
+        let hint = Label::new(Some("Only cuts where larger tools could not reach"));
+        hint.add_css_class("dim-label");
+        hint.set_halign(gtk4::Align::Start);
+
+        grid.attach(&tool_label, 0, 0, 1, 1);
+        grid.attach(&tool_combo, 1, 0, 2, 1);
+        grid.attach(&rest_label, 0, 1, 1, 1);
+        grid.attach(&rest_machining_check, 1, 1, 1, 1);
+        grid.attach(&hint, 0, 2, 3, 1);
+
+        frame.set_child(Some(&grid));
+        (frame, tool_combo, rest_machining_check)
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn build_tabs_section() -> (
+        Frame,
+        CheckButton,
+        Entry,
+        Entry,
+        Entry,
+        DropDown,
+        Button,
+        Label,
+        Label,
+    ) {
+        let frame = Self::create_section(&t!("Holding Tabs"));
+        let grid = gtk4::Grid::builder()
+            .row_spacing(8)
+            .column_spacing(8)
+            .margin_start(8)
+            .margin_end(8)
+            .margin_top(8)
+            .margin_bottom(8)
+            .build();
+
+        let enabled_label = Label::new(Some(&t!("Enabled:")));
+        enabled_label.set_halign(gtk4::Align::Start);
+        let tabs_enabled_check = CheckButton::new();
+
+        let count_label = Label::new(Some(&t!("Count:")));
+        count_label.set_halign(gtk4::Align::Start);
+        let tab_count_entry = Entry::new();
+        tab_count_entry.set_hexpand(true);
+
+        let width_label = Label::new(Some(&t!("Width:")));
+        width_label.set_halign(gtk4::Align::Start);
+        let tab_width_entry = Entry::new();
+        tab_width_entry.set_hexpand(true);
+        let tab_width_unit_label = Label::new(Some("mm"));
+        tab_width_unit_label.set_width_chars(4);
+        tab_width_unit_label.set_halign(gtk4::Align::End);
+        tab_width_unit_label.set_xalign(1.0);
+
+        let height_label = Label::new(Some(&t!("Height:")));
+        height_label.set_halign(gtk4::Align::Start);
+        let tab_height_entry = Entry::new();
+        tab_height_entry.set_hexpand(true);
+        let tab_height_unit_label = Label::new(Some("mm"));
+        tab_height_unit_label.set_width_chars(4);
+        tab_height_unit_label.set_halign(gtk4::Align::End);
+        tab_height_unit_label.set_xalign(1.0);
+
+        let shape_label = Label::new(Some(&t!("Shape:")));
+        shape_label.set_halign(gtk4::Align::Start);
+        let shape_model = StringList::new(&[]);
+        shape_model.append(&t!("Rectangular"));
+        shape_model.append(&t!("Triangular"));
+        let tab_shape_combo = DropDown::new(Some(shape_model), None::<Expression>);
+        tab_shape_combo.set_hexpand(true);
+
+        // Manually dragged tabs stay put until re-spaced
+        let tab_auto_place_button = Button::with_label(&t!("Auto Place"));
+        tab_auto_place_button.set_tooltip_text(Some(&t!(
+            "Discard dragged tab positions and space tabs evenly"
+        )));
+
+        grid.attach(&enabled_label, 0, 0, 1, 1);
+        grid.attach(&tabs_enabled_check, 1, 0, 1, 1);
+        grid.attach(&count_label, 0, 1, 1, 1);
+        grid.attach(&tab_count_entry, 1, 1, 1, 1);
+        grid.attach(&width_label, 0, 2, 1, 1);
+        grid.attach(&tab_width_entry, 1, 2, 1, 1);
+        grid.attach(&tab_width_unit_label, 2, 2, 1, 1);
+        grid.attach(&height_label, 0, 3, 1, 1);
+        grid.attach(&tab_height_entry, 1, 3, 1, 1);
+        grid.attach(&tab_height_unit_label, 2, 3, 1, 1);
+        grid.attach(&shape_label, 0, 4, 1, 1);
+        grid.attach(&tab_shape_combo, 1, 4, 1, 1);
+        grid.attach(&tab_auto_place_button, 0, 5, 3, 1);
+
+        frame.set_child(Some(&grid));
+        (
+            frame,
+            tabs_enabled_check,
+            tab_count_entry,
+            tab_width_entry,
+            tab_height_entry,
+            tab_shape_combo,
+            tab_auto_place_button,
+            tab_width_unit_label,
+            tab_height_unit_label,
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn build_leads_section() -> (
+        Frame,
+        DropDown,
+        DropDown,
+        Entry,
+        Entry,
+        Entry,
+        Entry,
+        Label,
+        Label,
+        Label,
+    ) {
+        let frame = Self::create_section(&t!("Lead-In / Lead-Out"));
+        let grid = gtk4::Grid::builder()
+            .row_spacing(8)
+            .column_spacing(8)
+            .margin_start(8)
+            .margin_end(8)
+            .margin_top(8)
+            .margin_bottom(8)
+            .build();
+
+        let lead_model = || {
+            let model = StringList::new(&[]);
+            model.append(&t!("None"));
+            model.append(&t!("Arc"));
+            model.append(&t!("Line"));
+            model.append(&t!("Ramp"));
+            model
+        };
+
+        let lead_in_label = Label::new(Some(&t!("Lead In:")));
+        lead_in_label.set_halign(gtk4::Align::Start);
+        let lead_in_combo = DropDown::new(Some(lead_model()), None::<Expression>);
+        lead_in_combo.set_hexpand(true);
+
+        let lead_out_label = Label::new(Some(&t!("Lead Out:")));
+        lead_out_label.set_halign(gtk4::Align::Start);
+        let lead_out_combo = DropDown::new(Some(lead_model()), None::<Expression>);
+        lead_out_combo.set_hexpand(true);
+
+        // Length of line and ramp leads
+        let length_label = Label::new(Some(&t!("Length:")));
+        length_label.set_halign(gtk4::Align::Start);
+        let lead_length_entry = Entry::new();
+        lead_length_entry.set_hexpand(true);
+        let lead_length_unit_label = Label::new(Some("mm"));
+        lead_length_unit_label.set_width_chars(4);
+        lead_length_unit_label.set_halign(gtk4::Align::End);
+        lead_length_unit_label.set_xalign(1.0);
+
+        // Radius of arc leads
+        let radius_label = Label::new(Some(&t!("Radius:")));
+        radius_label.set_halign(gtk4::Align::Start);
+        let lead_radius_entry = Entry::new();
+        lead_radius_entry.set_hexpand(true);
+        let lead_radius_unit_label = Label::new(Some("mm"));
+        lead_radius_unit_label.set_width_chars(4);
+        lead_radius_unit_label.set_halign(gtk4::Align::End);
+        lead_radius_unit_label.set_xalign(1.0);
+
+        let overlap_label = Label::new(Some(&t!("Overlap:")));
+        overlap_label.set_halign(gtk4::Align::Start);
+        let lead_overlap_entry = Entry::new();
+        lead_overlap_entry.set_hexpand(true);
+        let lead_overlap_unit_label = Label::new(Some("mm"));
+        lead_overlap_unit_label.set_width_chars(4);
+        lead_overlap_unit_label.set_halign(gtk4::Align::End);
+        lead_overlap_unit_label.set_xalign(1.0);
+
+        // Start point along the outline; empty keeps the contour's own start
+        let start_label = Label::new(Some(&t!("Start (%):")));
+        start_label.set_halign(gtk4::Align::Start);
+        let lead_start_entry = Entry::new();
+        lead_start_entry.set_hexpand(true);
+        lead_start_entry.set_placeholder_text(Some(&t!("Auto")));
+        let start_hint = Label::new(Some("Drag the start marker on the canvas"));
+        start_hint.add_css_class("dim-label");
+        start_hint.set_halign(gtk4::Align::Start);
+
+        grid.attach(&lead_in_label, 0, 0, 1, 1);
+        grid.attach(&lead_in_combo, 1, 0, 1, 1);
+        grid.attach(&lead_out_label, 0, 1, 1, 1);
+        grid.attach(&lead_out_combo, 1, 1, 1, 1);
+        grid.attach(&length_label, 0, 2, 1, 1);
+        grid.attach(&lead_length_entry, 1, 2, 1, 1);
+        grid.attach(&lead_length_unit_label, 2, 2, 1, 1);
+        grid.attach(&radius_label, 0, 3, 1, 1);
+        grid.attach(&lead_radius_entry, 1, 3, 1, 1);
+        grid.attach(&lead_radius_unit_label, 2, 3, 1, 1);
+        grid.attach(&overlap_label, 0, 4, 1, 1);
+        grid.attach(&lead_overlap_entry, 1, 4, 1, 1);
+        grid.attach(&lead_overlap_unit_label, 2, 4, 1, 1);
+        grid.attach(&start_label, 0, 5, 1, 1);
+        grid.attach(&lead_start_entry, 1, 5, 1, 1);
+        grid.attach(&start_hint, 0, 6, 3, 1);
+
+        frame.set_child(Some(&grid));
+        (
+            frame,
+            lead_in_combo,
+            lead_out_combo,
+            lead_length_entry,
+            lead_radius_entry,
+            lead_overlap_entry,
+            lead_start_entry,
+            lead_length_unit_label,
+            lead_radius_unit_label,
+            lead_overlap_unit_label,
+        )
+    }
+
+    pub(crate) fn build_vcarve_section() -> (Frame, Entry) {
+        let frame = Self::create_section(&t!("V-Carve"));
+        let grid = gtk4::Grid::builder()
+            .row_spacing(8)
+            .column_spacing(8)
+            .margin_start(8)
+            .margin_end(8)
+            .margin_top(8)
+            .margin_bottom(8)
+            .build();
+
+        let angle_label = Label::new(Some(&t!("V-Bit Angle:")));
+        angle_label.set_halign(gtk4::Align::Start);
+        let vcarve_angle_entry = Entry::new();
+        vcarve_angle_entry.set_hexpand(true);
+        let angle_unit_label = Label::new(Some("deg"));
+        angle_unit_label.set_width_chars(4);
+        angle_unit_label.set_halign(gtk4::Align::End);
+        angle_unit_label.set_xalign(1.0);
+
+        // The bit's diameter comes from the tool, its depth limit from Depth
+        let hint = Label::new(Some("Uses the tool diameter; Depth limits the carve"));
+        hint.add_css_class("dim-label");
+        hint.set_halign(gtk4::Align::Start);
+
+        grid.attach(&angle_label, 0, 0, 1, 1);
+        grid.attach(&vcarve_angle_entry, 1, 0, 1, 1);
+        grid.attach(&angle_unit_label, 2, 0, 1, 1);
+        grid.attach(&hint, 0, 1, 3, 1);
+
+        frame.set_child(Some(&grid));
+        (frame, vcarve_angle_entry)
+    }
+
+    pub(crate) fn build_drill_section() -> (Frame, Entry, Entry, Label, Label) {
+        let frame = Self::create_section(&t!("Drilling"));
+        let grid = gtk4::Grid::builder()
+            .row_spacing(8)
+            .column_spacing(8)
+            .margin_start(8)
+            .margin_end(8)
+            .margin_top(8)
+            .margin_bottom(8)
+            .build();
+
+        // Depth per peck; 0 drills in a single plunge
+        let peck_label = Label::new(Some(&t!("Peck Depth:")));
+        peck_label.set_halign(gtk4::Align::Start);
+        let peck_depth_entry = Entry::new();
+        peck_depth_entry.set_hexpand(true);
+        let peck_depth_unit_label = Label::new(Some("mm"));
+        peck_depth_unit_label.set_width_chars(4);
+        peck_depth_unit_label.set_halign(gtk4::Align::End);
+        peck_depth_unit_label.set_xalign(1.0);
+
+        // Chip-break back-off; 0 clears the hole between pecks
+        let retract_label = Label::new(Some(&t!("Peck Retract:")));
+        retract_label.set_halign(gtk4::Align::Start);
+        let peck_retract_entry = Entry::new();
+        peck_retract_entry.set_hexpand(true);
+        let peck_retract_unit_label = Label::new(Some("mm"));
+        peck_retract_unit_label.set_width_chars(4);
+        peck_retract_unit_label.set_halign(gtk4::Align::End);
+        peck_retract_unit_label.set_xalign(1.0);
+
+        let hint = Label::new(Some("Circles are drilled at their centre"));
+        hint.add_css_class("dim-label");
+        hint.set_halign(gtk4::Align::Start);
+
+        grid.attach(&peck_label, 0, 0, 1, 1);
+        grid.attach(&peck_depth_entry, 1, 0, 1, 1);
+        grid.attach(&peck_depth_unit_label, 2, 0, 1, 1);
+        grid.attach(&retract_label, 0, 1, 1, 1);
+        grid.attach(&peck_retract_entry, 1, 1, 1, 1);
+        grid.attach(&peck_retract_unit_label, 2, 1, 1, 1);
+        grid.attach(&hint, 0, 2, 3, 1);
+
+        frame.set_child(Some(&grid));
+        (
+            frame,
+            peck_depth_entry,
+            peck_retract_entry,
+            peck_depth_unit_label,
+            peck_retract_unit_label,
         )
     }
 }
@@ -1,12 +1,30 @@
-//! CAM property handlers (operation type, depth, step down, step in, ramp angle, strategy, raster fill).
+//! CAM property handlers (operation type, depth, step down, step in, ramp angle, strategy, raster fill,
+//! cut side, cut direction, finishing allowance, tool, holding tabs, lead-in/out, V-carve, drilling).
 
 use gcodekit5_core::units;
-use gcodekit5_core::Shared;
+use gcodekit5_core::{Shared, SharedOption};
 use gcodekit5_designer::designer_state::DesignerState;
+use gcodekit5_designer::leads::LeadType;
 use gcodekit5_designer::pocket_operations::PocketStrategy;
+use gcodekit5_designer::profile_operations::{CutDirection, CutSide};
+use gcodekit5_designer::shapes::OperationType;
+use gcodekit5_designer::tabs::TabShape;
+use gcodekit5_designer::tool_changes::AssignedTool;
 use gcodekit5_settings::SettingsPersistence;
 use gtk4::prelude::*;
-use gtk4::{DropDown, Entry};
+use gtk4::{Button, CheckButton, DropDown, Entry};
+use std::rc::Rc;
+
+/// Operation for the selected entry of the operation dropdown.
+fn selected_operation(combo: &DropDown) -> OperationType {
+    match combo.selected() {
+        1 => OperationType::Pocket,
+        2 => OperationType::Engrave,
+        3 => OperationType::VCarve,
+        4 => OperationType::Drill,
+        _ => OperationType::Profile,
+    }
+}
 
 /// Setup operation type dropdown handler
 pub fn setup_operation_type_handler(
@@ -19,14 +37,14 @@ pub fn setup_operation_type_handler(
             return;
         }
         let mut designer_state = state.borrow_mut();
-        let is_pocket = combo.selected() == 1;
+        let operation = selected_operation(combo);
         let depth = designer_state
             .canvas
             .shapes()
             .find(|s| s.selected)
             .map(|s| s.pocket_depth)
             .unwrap_or(0.0);
-        designer_state.set_selected_pocket_properties(is_pocket, depth);
+        designer_state.set_selected_operation(operation, depth);
     });
 }
 
@@ -48,8 +66,7 @@ pub fn setup_depth_handler(
         if let Ok(val) = units::parse_length(&entry.text(), system) {
             entry.remove_css_class("entry-invalid");
             let mut designer_state = state.borrow_mut();
-            let is_pocket = op_combo.selected() == 1;
-            designer_state.set_selected_pocket_properties(is_pocket, val as f64);
+            designer_state.set_selected_operation(selected_operation(&op_combo), val as f64);
         } else {
             entry.add_css_class("entry-invalid");
         }
@@ -164,3 +181,381 @@ pub fn setup_strategy_handler(
         designer_state.set_selected_pocket_strategy(strategy);
     });
 }
+
+/// Setup cut side dropdown handler
+pub fn setup_cut_side_handler(
+    cut_side_combo: &DropDown,
+    state: Shared<DesignerState>,
+    updating: Shared<bool>,
+) {
+    cut_side_combo.connect_selected_notify(move |combo| {
+        if *updating.borrow() {
+            return;
+        }
+        let mut designer_state = state.borrow_mut();
+        let side = match combo.selected() {
+            0 => CutSide::Outside,
+            1 => CutSide::Inside,
+            _ => CutSide::OnLine,
+        };
+        designer_state.set_selected_cut_side(side);
+    });
+}
+
+/// Setup cut direction dropdown handler
+pub fn setup_cut_direction_handler(
+    cut_direction_combo: &DropDown,
+    state: Shared<DesignerState>,
+    updating: Shared<bool>,
+) {
+    cut_direction_combo.connect_selected_notify(move |combo| {
+        if *updating.borrow() {
+            return;
+        }
+        let mut designer_state = state.borrow_mut();
+        let direction = match combo.selected() {
+            1 => CutDirection::Conventional,
+            _ => CutDirection::Climb,
+        };
+        designer_state.set_selected_cut_direction(direction);
+    });
+}
+
+/// Setup finishing allowance entry handler
+pub fn setup_finishing_allowance_handler(
+    finishing_allowance_entry: &Entry,
+    state: Shared<DesignerState>,
+    settings: Shared<SettingsPersistence>,
+    updating: Shared<bool>,
+) {
+    finishing_allowance_entry.connect_changed(move |entry| {
+        if *updating.borrow() {
+            return;
+        }
+        let system = settings.borrow().config().ui.measurement_system;
+        match units::parse_length(&entry.text(), system) {
+            Ok(val) if val >= 0.0 => {
+                entry.remove_css_class("entry-invalid");
+                let mut designer_state = state.borrow_mut();
+                designer_state.set_selected_finishing_allowance(val as f64);
+            }
+            _ => entry.add_css_class("entry-invalid"),
+        }
+    });
+}
+
+/// Setup holding tab handlers. Every tab widget applies the full set of
+/// tab settings so the selection always matches what the panel shows.
+#[allow(clippy::too_many_arguments)]
+pub fn setup_tabs_handlers(
+    tabs_enabled_check: &CheckButton,
+    tab_count_entry: &Entry,
+    tab_width_entry: &Entry,
+    tab_height_entry: &Entry,
+    tab_shape_combo: &DropDown,
+    tab_auto_place_button: &Button,
+    state: Shared<DesignerState>,
+    settings: Shared<SettingsPersistence>,
+    redraw_callback: SharedOption<Rc<dyn Fn()>>,
+    updating: Shared<bool>,
+) {
+    let apply: Rc<dyn Fn()> = {
+        let enabled_check = tabs_enabled_check.clone();
+        let count_entry = tab_count_entry.clone();
+        let width_entry = tab_width_entry.clone();
+        let height_entry = tab_height_entry.clone();
+        let shape_combo = tab_shape_combo.clone();
+        let state = state.clone();
+        let redraw_callback = redraw_callback.clone();
+        let updating = updating.clone();
+        Rc::new(move || {
+            if *updating.borrow() {
+                return;
+            }
+            let system = settings.borrow().config().ui.measurement_system;
+
+            let count = match count_entry.text().trim().parse::<u32>() {
+                Ok(val) if val >= 1 => {
+                    count_entry.remove_css_class("entry-invalid");
+                    Some(val)
+                }
+                _ => {
+                    count_entry.add_css_class("entry-invalid");
+                    None
+                }
+            };
+            let parse_positive = |entry: &Entry| match units::parse_length(&entry.text(), system) {
+                Ok(val) if val > 0.0 => {
+                    entry.remove_css_class("entry-invalid");
+                    Some(val as f64)
+                }
+                _ => {
+                    entry.add_css_class("entry-invalid");
+                    None
+                }
+            };
+            let width = parse_positive(&width_entry);
+            let height = parse_positive(&height_entry);
+
+            let (Some(count), Some(width), Some(height)) = (count, width, height) else {
+                return;
+            };
+            let shape = match shape_combo.selected() {
+                1 => TabShape::Triangular,
+                _ => TabShape::Rectangular,
+            };
+
+            state.borrow_mut().set_selected_tabs(
+                enabled_check.is_active(),
+                count,
+                width,
+                height,
+                shape,
+            );
+            if let Some(ref cb) = *redraw_callback.borrow() {
+                cb();
+            }
+        })
+    };
+
+    let on_toggle = apply.clone();
+    tabs_enabled_check.connect_toggled(move |_| on_toggle());
+
+    for entry in [tab_count_entry, tab_width_entry, tab_height_entry] {
+        let on_change = apply.clone();
+        entry.connect_changed(move |_| on_change());
+    }
+
+    let on_select = apply;
+    tab_shape_combo.connect_selected_notify(move |_| on_select());
+
+    tab_auto_place_button.connect_clicked(move |_| {
+        if *updating.borrow() {
+            return;
+        }
+        state.borrow_mut().reset_selected_tab_positions();
+        if let Some(ref cb) = *redraw_callback.borrow() {
+            cb();
+        }
+    });
+}
+
+/// Setup tool and rest machining handlers
+pub fn setup_tool_handlers(
+    tool_combo: &DropDown,
+    rest_machining_check: &CheckButton,
+    tool_choices: Shared<Vec<AssignedTool>>,
+    state: Shared<DesignerState>,
+    redraw_callback: SharedOption<Rc<dyn Fn()>>,
+    updating: Shared<bool>,
+) {
+    {
+        let state = state.clone();
+        let redraw_callback = redraw_callback.clone();
+        let updating = updating.clone();
+        let rest_check = rest_machining_check.clone();
+        tool_combo.connect_selected_notify(move |combo| {
+            if *updating.borrow() {
+                return;
+            }
+            // Entry 0 is the designer's tool settings
+            let tool = (combo.selected() as usize)
+                .checked_sub(1)
+                .and_then(|i| tool_choices.borrow().get(i).cloned());
+            rest_check.set_sensitive(tool.is_some());
+            state.borrow_mut().set_selected_tool(tool);
+            if let Some(ref cb) = *redraw_callback.borrow() {
+                cb();
+            }
+        });
+    }
+
+    rest_machining_check.connect_toggled(move |check| {
+        if *updating.borrow() {
+            return;
+        }
+        state
+            .borrow_mut()
+            .set_selected_rest_machining(check.is_active());
+        if let Some(ref cb) = *redraw_callback.borrow() {
+            cb();
+        }
+    });
+}
+
+/// Setup lead-in/out handlers. Like the tab handlers, every widget applies
+/// the full set of lead settings.
+#[allow(clippy::too_many_arguments)]
+pub fn setup_leads_handlers(
+    lead_in_combo: &DropDown,
+    lead_out_combo: &DropDown,
+    lead_length_entry: &Entry,
+    lead_radius_entry: &Entry,
+    lead_overlap_entry: &Entry,
+    lead_start_entry: &Entry,
+    state: Shared<DesignerState>,
+    settings: Shared<SettingsPersistence>,
+    redraw_callback: SharedOption<Rc<dyn Fn()>>,
+    updating: Shared<bool>,
+) {
+    let apply: Rc<dyn Fn()> = {
+        let in_combo = lead_in_combo.clone();
+        let out_combo = lead_out_combo.clone();
+        let length_entry = lead_length_entry.clone();
+        let radius_entry = lead_radius_entry.clone();
+        let overlap_entry = lead_overlap_entry.clone();
+        let start_entry = lead_start_entry.clone();
+        Rc::new(move || {
+            if *updating.borrow() {
+                return;
+            }
+            let system = settings.borrow().config().ui.measurement_system;
+
+            let parse_non_negative =
+                |entry: &Entry| match units::parse_length(&entry.text(), system) {
+                    Ok(val) if val >= 0.0 => {
+                        entry.remove_css_class("entry-invalid");
+                        Some(val as f64)
+                    }
+                    _ => {
+                        entry.add_css_class("entry-invalid");
+                        None
+                    }
+                };
+            let length = parse_non_negative(&length_entry);
+            let radius = parse_non_negative(&radius_entry);
+            let overlap = parse_non_negative(&overlap_entry);
+
+            // Empty start means the contour's own start point
+            let start_text = start_entry.text();
+            let start = if start_text.trim().is_empty() {
+                start_entry.remove_css_class("entry-invalid");
+                Some(None)
+            } else {
+                match start_text.trim().parse::<f64>() {
+                    Ok(val) if (0.0..=100.0).contains(&val) => {
+                        start_entry.remove_css_class("entry-invalid");
+                        Some(Some(val / 100.0))
+                    }
+                    _ => {
+                        start_entry.add_css_class("entry-invalid");
+                        None
+                    }
+                }
+            };
+
+            let (Some(length), Some(radius), Some(overlap), Some(start)) =
+                (length, radius, overlap, start)
+            else {
+                return;
+            };
+            let lead_type = |combo: &DropDown| match combo.selected() {
+                1 => LeadType::Arc,
+                2 => LeadType::Line,
+                3 => LeadType::Ramp,
+                _ => LeadType::None,
+            };
+
+            state.borrow_mut().set_selected_leads(
+                lead_type(&in_combo),
+                lead_type(&out_combo),
+                length,
+                radius,
+                overlap,
+                start,
+            );
+            if let Some(ref cb) = *redraw_callback.borrow() {
+                cb();
+            }
+        })
+    };
+
+    for combo in [lead_in_combo, lead_out_combo] {
+        let on_select = apply.clone();
+        combo.connect_selected_notify(move |_| on_select());
+    }
+
+    for entry in [
+        lead_length_entry,
+        lead_radius_entry,
+        lead_overlap_entry,
+        lead_start_entry,
+    ] {
+        let on_change = apply.clone();
+        entry.connect_changed(move |_| on_change());
+    }
+}
+
+/// Setup V-carve bit angle handler
+pub fn setup_vcarve_handler(
+    vcarve_angle_entry: &Entry,
+    state: Shared<DesignerState>,
+    redraw_callback: SharedOption<Rc<dyn Fn()>>,
+    updating: Shared<bool>,
+) {
+    vcarve_angle_entry.connect_changed(move |entry| {
+        if *updating.borrow() {
+            return;
+        }
+        match entry.text().trim().parse::<f64>() {
+            Ok(val) if val > 0.0 && val < 180.0 => {
+                entry.remove_css_class("entry-invalid");
+                state.borrow_mut().set_selected_vcarve(val);
+                if let Some(ref cb) = *redraw_callback.borrow() {
+                    cb();
+                }
+            }
+            _ => entry.add_css_class("entry-invalid"),
+        }
+    });
+}
+
+/// Setup peck drilling handlers
+pub fn setup_drill_handlers(
+    peck_depth_entry: &Entry,
+    peck_retract_entry: &Entry,
+    state: Shared<DesignerState>,
+    settings: Shared<SettingsPersistence>,
+    redraw_callback: SharedOption<Rc<dyn Fn()>>,
+    updating: Shared<bool>,
+) {
+    let apply: Rc<dyn Fn()> = {
+        let depth_entry = peck_depth_entry.clone();
+        let retract_entry = peck_retract_entry.clone();
+        Rc::new(move || {
+            if *updating.borrow() {
+                return;
+            }
+            let system = settings.borrow().config().ui.measurement_system;
+            let parse_non_negative =
+                |entry: &Entry| match units::parse_length(&entry.text(), system) {
+                    Ok(val) if val >= 0.0 => {
+                        entry.remove_css_class("entry-invalid");
+                        Some(val as f64)
+                    }
+                    _ => {
+                        entry.add_css_class("entry-invalid");
+                        None
+                    }
+                };
+            let (Some(peck_depth), Some(peck_retract)) = (
+                parse_non_negative(&depth_entry),
+                parse_non_negative(&retract_entry),
+            ) else {
+                return;
+            };
+
+            state
+                .borrow_mut()
+                .set_selected_drill(peck_depth, peck_retract);
+            if let Some(ref cb) = *redraw_callback.borrow() {
+                cb();
+            }
+        })
+    };
+
+    for entry in [peck_depth_entry, peck_retract_entry] {
+        let on_change = apply.clone();
+        entry.connect_changed(move |_| on_change());
+    }
+}
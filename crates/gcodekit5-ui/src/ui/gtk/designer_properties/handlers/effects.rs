@@ -1,10 +1,13 @@
-//! Effects property handlers (offset, fillet, chamfer with live preview).
+//! Effects property handlers (offset, fillet, chamfer with live preview, corner relief).
 
+use gcodekit5_core::units;
 use gcodekit5_core::{Shared, SharedOption, SharedVec};
 use gcodekit5_designer::designer_state::DesignerState;
+use gcodekit5_designer::dogbone::{DogboneSettings, DogboneStyle};
 use gcodekit5_designer::model::Shape;
+use gcodekit5_settings::SettingsPersistence;
 use gtk4::prelude::*;
-use gtk4::{Entry, EventControllerFocus};
+use gtk4::{DropDown, Entry, EventControllerFocus};
 use std::rc::Rc;
 
 /// Setup offset entry handler with preview
@@ -291,3 +294,74 @@ pub fn setup_chamfer_handler(
     });
     chamfer_entry.add_controller(focus_controller);
 }
+
+/// Setup dogbone / T-bone corner relief handlers. Every widget applies the
+/// full set of relief settings.
+pub fn setup_dogbone_handlers(
+    dogbone_style_combo: &DropDown,
+    dogbone_tool_entry: &Entry,
+    dogbone_angle_entry: &Entry,
+    state: Shared<DesignerState>,
+    settings: Shared<SettingsPersistence>,
+    redraw_callback: SharedOption<Rc<dyn Fn()>>,
+    updating: Shared<bool>,
+) {
+    let apply: Rc<dyn Fn()> = {
+        let style_combo = dogbone_style_combo.clone();
+        let tool_entry = dogbone_tool_entry.clone();
+        let angle_entry = dogbone_angle_entry.clone();
+        Rc::new(move || {
+            if *updating.borrow() {
+                return;
+            }
+            let system = settings.borrow().config().ui.measurement_system;
+
+            let tool_diameter = match units::parse_length(&tool_entry.text(), system) {
+                Ok(val) if val > 0.0 => {
+                    tool_entry.remove_css_class("entry-invalid");
+                    Some(val as f64)
+                }
+                _ => {
+                    tool_entry.add_css_class("entry-invalid");
+                    None
+                }
+            };
+            let max_angle = match angle_entry.text().trim().parse::<f64>() {
+                Ok(val) if val > 0.0 && val <= 180.0 => {
+                    angle_entry.remove_css_class("entry-invalid");
+                    Some(val)
+                }
+                _ => {
+                    angle_entry.add_css_class("entry-invalid");
+                    None
+                }
+            };
+            let (Some(tool_diameter), Some(max_angle)) = (tool_diameter, max_angle) else {
+                return;
+            };
+
+            let (enabled, style) = match style_combo.selected() {
+                1 => (true, DogboneStyle::Dogbone),
+                2 => (true, DogboneStyle::TBone),
+                _ => (false, DogboneStyle::Dogbone),
+            };
+            state.borrow_mut().set_dogbone_selected(DogboneSettings {
+                enabled,
+                style,
+                tool_diameter,
+                max_angle,
+            });
+            if let Some(ref cb) = *redraw_callback.borrow() {
+                cb();
+            }
+        })
+    };
+
+    let on_select = apply.clone();
+    dogbone_style_combo.connect_selected_notify(move |_| on_select());
+
+    for entry in [dogbone_tool_entry, dogbone_angle_entry] {
+        let on_change = apply.clone();
+        entry.connect_changed(move |_| on_change());
+    }
+}
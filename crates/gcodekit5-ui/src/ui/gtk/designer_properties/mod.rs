@@ -10,18 +10,24 @@ mod handlers;
 mod update;
 
 use crate::t;
+use crate::ui::tools_manager_backend::ToolsManagerBackend;
 use gcodekit5_core::units;
 use gcodekit5_core::{shared, shared_none, Shared, SharedOption, SharedVec};
 use gcodekit5_designer::designer_state::DesignerState;
+use gcodekit5_designer::dogbone::DogboneStyle;
 use gcodekit5_designer::font_manager;
+use gcodekit5_designer::leads::LeadType;
 use gcodekit5_designer::model::{DesignerShape, Shape};
 use gcodekit5_designer::pocket_operations::PocketStrategy;
+use gcodekit5_designer::profile_operations::{CutDirection, CutSide};
 use gcodekit5_designer::shapes::OperationType;
+use gcodekit5_designer::tabs::TabShape;
+use gcodekit5_designer::tool_changes::AssignedTool;
 use gcodekit5_settings::SettingsPersistence;
 use gtk4::prelude::*;
 use gtk4::{
-    Box, CheckButton, DropDown, Entry, EventControllerFocus, Expression, Frame, Label, Orientation,
-    ScrolledWindow, StringList,
+    Box, Button, CheckButton, DropDown, Entry, EventControllerFocus, Expression, Frame, Label,
+    Orientation, ScrolledWindow, StringList,
 };
 use std::rc::Rc;
 
@@ -52,7 +58,12 @@ pub struct PropertiesPanel {
     pub(crate) corner_frame: Frame,
     pub(crate) text_frame: Frame,
     pub(crate) cam_frame: Frame,
+    pub(crate) tool_frame: Frame,
     pub(crate) ops_frame: Frame,
+    pub(crate) tabs_frame: Frame,
+    pub(crate) leads_frame: Frame,
+    pub(crate) vcarve_frame: Frame,
+    pub(crate) drill_frame: Frame,
     pub(crate) empty_label: Label,
 
     // Property widgets
@@ -95,11 +106,43 @@ pub struct PropertiesPanel {
     pub(crate) ramp_angle_entry: Entry,
     pub(crate) strategy_combo: DropDown,
     pub(crate) raster_fill_entry: Entry,
+    pub(crate) cut_side_combo: DropDown,
+    pub(crate) cut_direction_combo: DropDown,
+    pub(crate) finishing_allowance_entry: Entry,
+
+    // Tool widgets; the dropdown lists the designer's settings, then `tool_choices`
+    pub(crate) tool_combo: DropDown,
+    pub(crate) rest_machining_check: CheckButton,
+    pub(crate) tool_choices: Shared<Vec<AssignedTool>>,
+
+    // Holding tab widgets
+    pub(crate) tabs_enabled_check: CheckButton,
+    pub(crate) tab_count_entry: Entry,
+    pub(crate) tab_width_entry: Entry,
+    pub(crate) tab_height_entry: Entry,
+    pub(crate) tab_shape_combo: DropDown,
+    pub(crate) tab_auto_place_button: Button,
+
+    // Lead-in/out widgets
+    pub(crate) lead_in_combo: DropDown,
+    pub(crate) lead_out_combo: DropDown,
+    pub(crate) lead_length_entry: Entry,
+    pub(crate) lead_radius_entry: Entry,
+    pub(crate) lead_overlap_entry: Entry,
+    pub(crate) lead_start_entry: Entry,
+
+    // V-carve and drilling widgets
+    pub(crate) vcarve_angle_entry: Entry,
+    pub(crate) peck_depth_entry: Entry,
+    pub(crate) peck_retract_entry: Entry,
 
     // Geometry Ops widgets
     pub(crate) offset_entry: Entry,
     pub(crate) fillet_entry: Entry,
     pub(crate) chamfer_entry: Entry,
+    pub(crate) dogbone_style_combo: DropDown,
+    pub(crate) dogbone_tool_entry: Entry,
+    pub(crate) dogbone_angle_entry: Entry,
 
     // Unit Labels
     pub(crate) x_unit_label: Label,
@@ -111,9 +154,18 @@ pub struct PropertiesPanel {
     pub(crate) depth_unit_label: Label,
     pub(crate) step_down_unit_label: Label,
     pub(crate) step_in_unit_label: Label,
+    pub(crate) finishing_allowance_unit_label: Label,
+    pub(crate) tab_width_unit_label: Label,
+    pub(crate) tab_height_unit_label: Label,
+    pub(crate) lead_length_unit_label: Label,
+    pub(crate) lead_radius_unit_label: Label,
+    pub(crate) lead_overlap_unit_label: Label,
+    pub(crate) peck_depth_unit_label: Label,
+    pub(crate) peck_retract_unit_label: Label,
     pub(crate) offset_unit_label: Label,
     pub(crate) fillet_unit_label: Label,
     pub(crate) chamfer_unit_label: Label,
+    pub(crate) dogbone_tool_unit_label: Label,
     // Redraw callback
     pub(crate) redraw_callback: SharedOption<Rc<dyn Fn()>>,
     // Flag to prevent feedback loops during updates
@@ -204,9 +256,13 @@ impl PropertiesPanel {
             offset_entry,
             fillet_entry,
             chamfer_entry,
+            dogbone_style_combo,
+            dogbone_tool_entry,
+            dogbone_angle_entry,
             offset_unit_label,
             fillet_unit_label,
             chamfer_unit_label,
+            dogbone_tool_unit_label,
         ) = Self::build_geometry_ops_section();
         content.append(&ops_frame);
 
@@ -219,12 +275,65 @@ impl PropertiesPanel {
             ramp_angle_entry,
             strategy_combo,
             raster_fill_entry,
+            cut_side_combo,
+            cut_direction_combo,
+            finishing_allowance_entry,
             depth_unit_label,
             step_down_unit_label,
             step_in_unit_label,
+            finishing_allowance_unit_label,
         ) = Self::build_cam_section();
         content.append(&cam_frame);
 
+        let mut library_tools: Vec<AssignedTool> = ToolsManagerBackend::new()
+            .get_all_tools()
+            .into_iter()
+            .map(AssignedTool::from_library)
+            .collect();
+        library_tools.sort_by(|a, b| a.number.cmp(&b.number).then(a.name.cmp(&b.name)));
+        let (tool_frame, tool_combo, rest_machining_check) =
+            Self::build_tool_section(&library_tools);
+        content.append(&tool_frame);
+
+        let (
+            tabs_frame,
+            tabs_enabled_check,
+            tab_count_entry,
+            tab_width_entry,
+            tab_height_entry,
+            tab_shape_combo,
+            tab_auto_place_button,
+            tab_width_unit_label,
+            tab_height_unit_label,
+        ) = Self::build_tabs_section();
+        content.append(&tabs_frame);
+
+        let (
+            leads_frame,
+            lead_in_combo,
+            lead_out_combo,
+            lead_length_entry,
+            lead_radius_entry,
+            lead_overlap_entry,
+            lead_start_entry,
+            lead_length_unit_label,
+            lead_radius_unit_label,
+            lead_overlap_unit_label,
+        ) = Self::build_leads_section();
+        content.append(&leads_frame);
+
+        let (vcarve_frame, vcarve_angle_entry) = Self::build_vcarve_section();
+        content.append(&vcarve_frame);
+
+        let (
+            drill_frame,
+            peck_depth_entry,
+            peck_retract_entry,
+            peck_depth_unit_label,
+            peck_retract_unit_label,
+        ) = Self::build_drill_section();
+        content.append(&drill_frame);
+
         // Empty state message
         let empty_label = Label::new(Some(&t!("Select a shape to edit its properties")));
         empty_label.add_css_class("dim-label");
@@ -249,7 +358,12 @@ impl PropertiesPanel {
             gear_frame,
             sprocket_frame,
             cam_frame,
+            tool_frame,
             ops_frame,
+            tabs_frame,
+            leads_frame,
+            vcarve_frame,
+            drill_frame,
             empty_label,
             pos_x_entry,
             pos_y_entry,
@@ -277,9 +391,33 @@ impl PropertiesPanel {
             ramp_angle_entry,
             strategy_combo,
             raster_fill_entry,
+            cut_side_combo,
+            cut_direction_combo,
+            finishing_allowance_entry,
+            tool_combo,
+            rest_machining_check,
+            tool_choices: shared(library_tools),
+            tabs_enabled_check,
+            tab_count_entry,
+            tab_width_entry,
+            tab_height_entry,
+            tab_shape_combo,
+            tab_auto_place_button,
+            lead_in_combo,
+            lead_out_combo,
+            lead_length_entry,
+            lead_radius_entry,
+            lead_overlap_entry,
+            lead_start_entry,
+            vcarve_angle_entry,
+            peck_depth_entry,
+            peck_retract_entry,
             offset_entry,
             fillet_entry,
             chamfer_entry,
+            dogbone_style_combo,
+            dogbone_tool_entry,
+            dogbone_angle_entry,
             header,
             x_unit_label,
             y_unit_label,
@@ -290,9 +428,18 @@ impl PropertiesPanel {
             depth_unit_label,
             step_down_unit_label,
             step_in_unit_label,
+            finishing_allowance_unit_label,
+            tab_width_unit_label,
+            tab_height_unit_label,
+            lead_length_unit_label,
+            lead_radius_unit_label,
+            lead_overlap_unit_label,
+            peck_depth_unit_label,
+            peck_retract_unit_label,
             offset_unit_label,
             fillet_unit_label,
             chamfer_unit_label,
+            dogbone_tool_unit_label,
             lock_aspect_ratio,
             redraw_callback: shared_none(),
             updating: shared(false),
@@ -522,6 +669,76 @@ impl PropertiesPanel {
             self.updating.clone(),
         );
 
+        handlers::cam::setup_cut_side_handler(
+            &self.cut_side_combo,
+            self.state.clone(),
+            self.updating.clone(),
+        );
+
+        handlers::cam::setup_cut_direction_handler(
+            &self.cut_direction_combo,
+            self.state.clone(),
+            self.updating.clone(),
+        );
+
+        handlers::cam::setup_finishing_allowance_handler(
+            &self.finishing_allowance_entry,
+            self.state.clone(),
+            self.settings.clone(),
+            self.updating.clone(),
+        );
+
+        handlers::cam::setup_tool_handlers(
+            &self.tool_combo,
+            &self.rest_machining_check,
+            self.tool_choices.clone(),
+            self.state.clone(),
+            self.redraw_callback.clone(),
+            self.updating.clone(),
+        );
+
+        handlers::cam::setup_tabs_handlers(
+            &self.tabs_enabled_check,
+            &self.tab_count_entry,
+            &self.tab_width_entry,
+            &self.tab_height_entry,
+            &self.tab_shape_combo,
+            &self.tab_auto_place_button,
+            self.state.clone(),
+            self.settings.clone(),
+            self.redraw_callback.clone(),
+            self.updating.clone(),
+        );
+
+        handlers::cam::setup_leads_handlers(
+            &self.lead_in_combo,
+            &self.lead_out_combo,
+            &self.lead_length_entry,
+            &self.lead_radius_entry,
+            &self.lead_overlap_entry,
+            &self.lead_start_entry,
+            self.state.clone(),
+            self.settings.clone(),
+            self.redraw_callback.clone(),
+            self.updating.clone(),
+        );
+
+        handlers::cam::setup_vcarve_handler(
+            &self.vcarve_angle_entry,
+            self.state.clone(),
+            self.redraw_callback.clone(),
+            self.updating.clone(),
+        );
+
+        handlers::cam::setup_drill_handlers(
+            &self.peck_depth_entry,
+            &self.peck_retract_entry,
+            self.state.clone(),
+            self.settings.clone(),
+            self.redraw_callback.clone(),
+            self.updating.clone(),
+        );
+
         // Gear/Sprocket handlers
         handlers::gear_sprocket::setup_gear_module_handler(
             &self.gear_module_entry,
@@ -592,5 +809,15 @@ impl PropertiesPanel {
             self.updating.clone(),
             self.has_focus.clone(),
         );
+
+        handlers::effects::setup_dogbone_handlers(
+            &self.dogbone_style_combo,
+            &self.dogbone_tool_entry,
+            &self.dogbone_angle_entry,
+            self.state.clone(),
+            self.settings.clone(),
+            self.redraw_callback.clone(),
+            self.updating.clone(),
+        );
     }
 }
@@ -39,9 +39,18 @@ impl PropertiesPanel {
         self.depth_unit_label.set_text(unit_label);
         self.step_down_unit_label.set_text(unit_label);
         self.step_in_unit_label.set_text(unit_label);
+        self.finishing_allowance_unit_label.set_text(unit_label);
+        self.tab_width_unit_label.set_text(unit_label);
+        self.tab_height_unit_label.set_text(unit_label);
+        self.lead_length_unit_label.set_text(unit_label);
+        self.lead_radius_unit_label.set_text(unit_label);
+        self.lead_overlap_unit_label.set_text(unit_label);
+        self.peck_depth_unit_label.set_text(unit_label);
+        self.peck_retract_unit_label.set_text(unit_label);
         self.offset_unit_label.set_text(unit_label);
         self.fillet_unit_label.set_text(unit_label);
         self.chamfer_unit_label.set_text(unit_label);
+        self.dogbone_tool_unit_label.set_text(unit_label);
 
         // Extract data first to avoid holding the borrow while updating widgets
         let selection_data = {
@@ -68,9 +77,18 @@ impl PropertiesPanel {
                     obj.ramp_angle,
                     obj.pocket_strategy,
                     obj.raster_fill_ratio,
+                    obj.cut_side,
+                    obj.cut_direction,
+                    obj.finishing_allowance,
+                    obj.tabs.clone(),
+                    obj.leads.clone(),
+                    obj.vcarve.clone(),
+                    obj.drill.clone(),
+                    obj.tool.clone(),
                     obj.offset,
                     obj.fillet,
                     obj.chamfer,
+                    obj.dogbone.clone(),
                     any_not_text,
                     obj.lock_aspect_ratio,
                 ))
@@ -88,9 +106,18 @@ impl PropertiesPanel {
                     obj.ramp_angle,
                     obj.pocket_strategy,
                     obj.raster_fill_ratio,
+                    obj.cut_side,
+                    obj.cut_direction,
+                    obj.finishing_allowance,
+                    obj.tabs.clone(),
+                    obj.leads.clone(),
+                    obj.vcarve.clone(),
+                    obj.drill.clone(),
+                    obj.tool.clone(),
                     obj.offset,
                     obj.fillet,
                     obj.chamfer,
+                    obj.dogbone.clone(),
                     any_not_text,
                     false, // Multi-selection: don't lock aspect ratio
                 ))
@@ -107,9 +134,18 @@ impl PropertiesPanel {
             ramp_angle,
             strategy,
             raster_fill,
+            cut_side,
+            cut_direction,
+            finishing_allowance,
+            tabs,
+            leads,
+            vcarve,
+            drill,
+            tool,
             offset,
             fillet,
             chamfer,
+            dogbone,
             any_not_text,
             lock_aspect,
         )) = selection_data
@@ -287,12 +323,13 @@ impl PropertiesPanel {
             }
 
             // Update CAM properties (common to all shapes)
-            self.op_type_combo
-                .set_selected(if op_type == OperationType::Pocket {
-                    1
-                } else {
-                    0
-                });
+            self.op_type_combo.set_selected(match op_type {
+                OperationType::Profile => 0,
+                OperationType::Pocket => 1,
+                OperationType::Engrave => 2,
+                OperationType::VCarve => 3,
+                OperationType::Drill => 4,
+            });
             self.set_entry_text_if_changed(&self.depth_entry, depth as f32, system);
             self.set_entry_text_if_changed(&self.step_down_entry, step_down, system);
             self.set_entry_text_if_changed(&self.step_in_entry, step_in, system);
@@ -308,10 +345,59 @@ impl PropertiesPanel {
             self.raster_fill_entry
                 .set_text(&format!("{:.0}", raster_fill * 100.0));
 
+            self.cut_side_combo.set_selected(match cut_side {
+                CutSide::Outside => 0,
+                CutSide::Inside => 1,
+                CutSide::OnLine => 2,
+            });
+            self.cut_direction_combo.set_selected(match cut_direction {
+                CutDirection::Climb => 0,
+                CutDirection::Conventional => 1,
+            });
+            self.set_entry_text_if_changed(
+                &self.finishing_allowance_entry,
+                finishing_allowance as f32,
+                system,
+            );
+
+            // Library tool, adding it to the list if the library no longer has it
+            self.tool_frame.set_visible(true);
+            let tool_index = tool.as_ref().map(|tool| {
+                let mut choices = self.tool_choices.borrow_mut();
+                let index = match choices.iter().position(|t| t.same_tool(tool)) {
+                    Some(index) => index,
+                    None => {
+                        choices.push(tool.clone());
+                        if let Some(model) = self.tool_combo.model().and_downcast::<StringList>() {
+                            model.append(&format!("T{} {}", tool.number, tool.name));
+                        }
+                        choices.len() - 1
+                    }
+                };
+                index as u32 + 1
+            });
+            self.tool_combo.set_selected(tool_index.unwrap_or(0));
+            self.rest_machining_check.set_sensitive(tool.is_some());
+            self.rest_machining_check
+                .set_active(tool.as_ref().is_some_and(|t| t.rest_machining));
+
             // Update geometry ops values
             self.offset_entry.set_text(&format!("{:.2}", offset));
             self.fillet_entry.set_text(&format!("{:.2}", fillet));
             self.chamfer_entry.set_text(&format!("{:.2}", chamfer));
+            self.dogbone_style_combo
+                .set_selected(match (dogbone.enabled, dogbone.style) {
+                    (false, _) => 0,
+                    (true, DogboneStyle::Dogbone) => 1,
+                    (true, DogboneStyle::TBone) => 2,
+                });
+            self.set_entry_text_if_changed(
+                &self.dogbone_tool_entry,
+                dogbone.tool_diameter as f32,
+                system,
+            );
+            self.dogbone_angle_entry
+                .set_text(&format!("{:.1}", dogbone.max_angle));
 
             // Enable/disable pocket-specific controls
             let is_pocket = op_type == OperationType::Pocket;
@@ -319,6 +405,58 @@ impl PropertiesPanel {
             self.step_in_entry.set_sensitive(is_pocket);
             self.raster_fill_entry.set_sensitive(is_pocket);
 
+            // Cut side and direction only apply to profiles
+            let is_profile = op_type == OperationType::Profile;
+            self.cut_side_combo.set_sensitive(is_profile);
+            self.cut_direction_combo.set_sensitive(is_profile);
+            self.finishing_allowance_entry.set_sensitive(is_profile);
+
+            // Holding tabs only apply to profiles
+            self.tabs_frame.set_visible(is_profile);
+            self.tabs_enabled_check.set_active(tabs.enabled);
+            self.tab_count_entry.set_text(&tabs.count.to_string());
+            self.set_entry_text_if_changed(&self.tab_width_entry, tabs.width as f32, system);
+            self.set_entry_text_if_changed(&self.tab_height_entry, tabs.height as f32, system);
+            self.tab_shape_combo.set_selected(match tabs.shape {
+                TabShape::Rectangular => 0,
+                TabShape::Triangular => 1,
+            });
+            self.tab_auto_place_button.set_sensitive(tabs.is_manual());
+
+            // Lead moves only apply to profiles
+            self.leads_frame.set_visible(is_profile);
+            let lead_index = |lead: LeadType| match lead {
+                LeadType::None => 0,
+                LeadType::Arc => 1,
+                LeadType::Line => 2,
+                LeadType::Ramp => 3,
+            };
+            self.lead_in_combo.set_selected(lead_index(leads.lead_in));
+            self.lead_out_combo.set_selected(lead_index(leads.lead_out));
+            self.set_entry_text_if_changed(&self.lead_length_entry, leads.length as f32, system);
+            self.set_entry_text_if_changed(&self.lead_radius_entry, leads.radius as f32, system);
+            self.set_entry_text_if_changed(&self.lead_overlap_entry, leads.overlap as f32, system);
+            self.lead_start_entry.set_text(
+                &leads
+                    .start_position
+                    .map(|p| format!("{:.1}", p * 100.0))
+                    .unwrap_or_default(),
+            );
+
+            self.vcarve_frame
+                .set_visible(op_type == OperationType::VCarve);
+            self.vcarve_angle_entry
+                .set_text(&format!("{:.1}", vcarve.tip_angle));
+
+            self.drill_frame
+                .set_visible(op_type == OperationType::Drill);
+            self.set_entry_text_if_changed(&self.peck_depth_entry, drill.peck_depth as f32, system);
+            self.set_entry_text_if_changed(
+                &self.peck_retract_entry,
+                drill.peck_retract as f32,
+                system,
+            );
+
             *self.updating.borrow_mut() = false;
         } else {
             // Nothing selected - show empty state
@@ -332,7 +470,12 @@ impl PropertiesPanel {
             self.gear_frame.set_visible(false);
             self.sprocket_frame.set_visible(false);
             self.cam_frame.set_visible(false);
+            self.tool_frame.set_visible(false);
             self.ops_frame.set_visible(false);
+            self.tabs_frame.set_visible(false);
+            self.leads_frame.set_visible(false);
+            self.vcarve_frame.set_visible(false);
+            self.drill_frame.set_visible(false);
             self.header.set_text(&t!("Properties"));
 
             // Clear entries
@@ -356,8 +499,24 @@ impl PropertiesPanel {
             self.ramp_angle_entry.set_sensitive(false);
             self.strategy_combo.set_sensitive(false);
             self.raster_fill_entry.set_sensitive(false);
+            self.cut_side_combo.set_sensitive(false);
+            self.cut_direction_combo.set_sensitive(false);
+            self.finishing_allowance_entry.set_sensitive(false);
 
             self.raster_fill_entry.set_text("");
+            self.finishing_allowance_entry.set_text("");
+            self.tab_count_entry.set_text("");
+            self.tab_width_entry.set_text("");
+            self.tab_height_entry.set_text("");
+            self.lead_length_entry.set_text("");
+            self.lead_radius_entry.set_text("");
+            self.lead_overlap_entry.set_text("");
+            self.lead_start_entry.set_text("");
+            self.vcarve_angle_entry.set_text("");
+            self.peck_depth_entry.set_text("");
+            self.peck_retract_entry.set_text("");
+            self.dogbone_tool_entry.set_text("");
+            self.dogbone_angle_entry.set_text("");
             *self.updating.borrow_mut() = false;
         }
     }
@@ -377,6 +536,17 @@ impl PropertiesPanel {
             &self.step_in_entry,
             &self.ramp_angle_entry,
             &self.raster_fill_entry,
+            &self.finishing_allowance_entry,
+            &self.tab_count_entry,
+            &self.tab_width_entry,
+            &self.tab_height_entry,
+            &self.lead_length_entry,
+            &self.lead_radius_entry,
+            &self.lead_overlap_entry,
+            &self.lead_start_entry,
+            &self.vcarve_angle_entry,
+            &self.peck_depth_entry,
+            &self.peck_retract_entry,
             &self.sides_entry,
             &self.gear_module_entry,
             &self.gear_teeth_entry,
@@ -387,6 +557,8 @@ impl PropertiesPanel {
             &self.offset_entry,
             &self.fillet_entry,
             &self.chamfer_entry,
+            &self.dogbone_tool_entry,
+            &self.dogbone_angle_entry,
         ];
 
         for entry in entries {
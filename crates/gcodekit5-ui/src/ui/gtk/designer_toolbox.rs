@@ -7,7 +7,7 @@ use gcodekit5_settings::controller::SettingsController;
 use gtk4::prelude::*;
 use gtk4::{
     Align, Box, Button, Dialog, Entry, Frame, Grid, Image, Label, Orientation, PolicyType,
-    ResponseType, ScrolledWindow,
+    ResponseType, ScrolledWindow, TextView, WrapMode,
 };
 use std::cell::Cell;
 use std::rc::Rc;
@@ -588,6 +588,41 @@ impl DesignerToolbox {
         tool_frame.set_child(Some(&settings_box));
         tool_dialog_content.append(&tool_frame);
 
+        // Tool change macro, emitted between operations that use different library tools
+        let tool_change_frame = Frame::new(Some(&t!("Tool Change Macro")));
+        let tool_change_box = Box::new(Orientation::Vertical, 6);
+        tool_change_box.set_margin_start(8);
+        tool_change_box.set_margin_end(8);
+        tool_change_box.set_margin_top(8);
+        tool_change_box.set_margin_bottom(8);
+        let tool_change_view = TextView::new();
+        tool_change_view.set_monospace(true);
+        tool_change_view.set_wrap_mode(WrapMode::None);
+        tool_change_view.set_height_request(90);
+        tool_change_box.append(&tool_change_view);
+        let tool_change_hint = Label::new(Some(&t!(
+            "Placeholders: {tool}, {name}, {diameter}, {spindle}, {safe_z}"
+        )));
+        tool_change_hint.add_css_class("dim-label");
+        tool_change_hint.set_halign(Align::Start);
+        tool_change_hint.set_wrap(true);
+        tool_change_box.append(&tool_change_hint);
+        tool_change_frame.set_child(Some(&tool_change_box));
+        tool_dialog_content.append(&tool_change_frame);
+
+        let tool_change_loading = Rc::new(Cell::new(false));
+        {
+            let state_setter = state.clone();
+            let loading = tool_change_loading.clone();
+            tool_change_view.buffer().connect_changed(move |buffer| {
+                if loading.get() {
+                    return;
+                }
+                let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+                state_setter.borrow_mut().set_tool_change_macro(text.to_string());
+            });
+        }
+
         let tool_scroller = ScrolledWindow::builder()
         .hscrollbar_policy(PolicyType::Never)
         .vscrollbar_policy(PolicyType::Automatic)
@@ -600,7 +635,16 @@ impl DesignerToolbox {
         {
             let dlg = tool_settings_dialog.clone();
             let btn = tool_settings_btn.clone();
+            let state_getter = state.clone();
+            let view = tool_change_view.clone();
+            let loading = tool_change_loading.clone();
             tool_settings_btn.connect_clicked(move |_| {
+                // Pick up macros loaded from a design file
+                loading.set(true);
+                view.buffer()
+                .set_text(&state_getter.borrow().tool_change.macro_template);
+                loading.set(false);
+
                 if let Some(root) = btn.root() {
                     if let Ok(win) = root.downcast::<gtk4::Window>() {
                         dlg.set_transient_for(Some(&win));
@@ -952,6 +996,20 @@ impl DesignerToolbox {
 
         content_box.append(&stock_settings_btn);
 
+        // Cut order optimisation
+        let optimize_order_check = gtk4::CheckButton::with_label(&t!("Optimize Cut Order"));
+        optimize_order_check.set_tooltip_text(Some(&t!(
+            "Reorder operations and pick start points to cut down rapid travel"
+        )));
+        optimize_order_check.set_margin_top(6);
+        optimize_order_check.set_margin_start(5);
+        optimize_order_check.set_active(state.borrow().optimize_order);
+        let state_optimize = state.clone();
+        optimize_order_check.connect_toggled(move |cb| {
+            state_optimize.borrow_mut().set_optimize_order(cb.is_active());
+        });
+        content_box.append(&optimize_order_check);
+
         // Generate G-Code Button
         let generate_btn = Button::with_label(&t!("Generate G-Code"));
         generate_btn.add_css_class("suggested-action");
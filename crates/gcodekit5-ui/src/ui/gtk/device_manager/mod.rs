@@ -37,6 +37,7 @@ pub struct DeviceManagerWindow {
     pub(crate) edit_description: Entry,
     pub(crate) edit_device_type: ComboBoxText,
     pub(crate) edit_controller_type: ComboBoxText,
+    pub(crate) edit_post_processor: ComboBoxText,
     pub(crate) edit_connection_type: ComboBoxText,
     pub(crate) edit_port: Entry,
     pub(crate) edit_baud_rate: ComboBoxText,
@@ -55,6 +56,12 @@ pub struct DeviceManagerWindow {
     pub(crate) edit_y_max_unit: Label,
     pub(crate) edit_z_min_unit: Label,
     pub(crate) edit_z_max_unit: Label,
+    pub(crate) edit_backlash_x: Entry,
+    pub(crate) edit_backlash_y: Entry,
+    pub(crate) edit_backlash_z: Entry,
+    pub(crate) edit_backlash_x_unit: Label,
+    pub(crate) edit_backlash_y_unit: Label,
+    pub(crate) edit_backlash_z_unit: Label,
     pub(crate) edit_has_spindle: CheckButton,
     pub(crate) edit_has_laser: CheckButton,
     pub(crate) edit_has_coolant: CheckButton,
@@ -65,6 +72,14 @@ pub struct DeviceManagerWindow {
     pub(crate) edit_spindle_watts: Entry,
     pub(crate) edit_max_spindle_speed_rpm: Entry,
     pub(crate) edit_laser_watts: Entry,
+    pub(crate) edit_laser_dynamic_power: CheckButton,
+    pub(crate) edit_laser_overscan: Entry,
+    pub(crate) edit_laser_overscan_unit: Label,
+    pub(crate) edit_laser_power_scaling: ComboBoxText,
+    pub(crate) edit_laser_reference_feed: Entry,
+    pub(crate) edit_laser_reference_feed_unit: Label,
+    pub(crate) edit_laser_power_curve: Entry,
+    pub(crate) edit_laser_strip_redundant_power: CheckButton,
 
     // State
     pub(crate) selected_device: SharedOption<DeviceProfileUiModel>,
@@ -195,8 +210,14 @@ impl DeviceManagerWindow {
         stack.set_vexpand(true);
 
         // Create tab pages
-        let (general_page, edit_name, edit_description, edit_device_type, edit_controller_type) =
-            Self::create_general_tab();
+        let (
+            general_page,
+            edit_name,
+            edit_description,
+            edit_device_type,
+            edit_controller_type,
+            edit_post_processor,
+        ) = Self::create_general_tab();
         let (
             connection_page,
             edit_connection_type,
@@ -220,6 +241,12 @@ impl DeviceManagerWindow {
             edit_y_max_unit,
             edit_z_min_unit,
             edit_z_max_unit,
+            edit_backlash_x,
+            edit_backlash_y,
+            edit_backlash_z,
+            edit_backlash_x_unit,
+            edit_backlash_y_unit,
+            edit_backlash_z_unit,
         ) = Self::create_dimensions_tab(*current_units.borrow());
         let (
             capabilities_page,
@@ -234,11 +261,23 @@ impl DeviceManagerWindow {
             edit_max_spindle_speed_rpm,
             edit_laser_watts,
         ) = Self::create_capabilities_tab(*current_feed_units.borrow());
+        let (
+            laser_page,
+            edit_laser_dynamic_power,
+            edit_laser_overscan,
+            edit_laser_overscan_unit,
+            edit_laser_power_scaling,
+            edit_laser_reference_feed,
+            edit_laser_reference_feed_unit,
+            edit_laser_power_curve,
+            edit_laser_strip_redundant_power,
+        ) = Self::create_laser_tab(*current_units.borrow(), *current_feed_units.borrow());
 
         stack.add_titled(&general_page, Some("general"), "General");
         stack.add_titled(&connection_page, Some("connection"), "Connection");
         stack.add_titled(&dimensions_page, Some("dimensions"), "Dimensions");
         stack.add_titled(&capabilities_page, Some("capabilities"), "Capabilities");
+        stack.add_titled(&laser_page, Some("laser"), "Laser");
 
         let switcher = StackSwitcher::new();
         switcher.set_stack(Some(&stack));
@@ -293,6 +332,7 @@ impl DeviceManagerWindow {
             edit_description,
             edit_device_type,
             edit_controller_type,
+            edit_post_processor,
             edit_connection_type,
             edit_port,
             edit_baud_rate,
@@ -311,6 +351,12 @@ impl DeviceManagerWindow {
             edit_y_max_unit,
             edit_z_min_unit,
             edit_z_max_unit,
+            edit_backlash_x,
+            edit_backlash_y,
+            edit_backlash_z,
+            edit_backlash_x_unit,
+            edit_backlash_y_unit,
+            edit_backlash_z_unit,
             edit_has_spindle,
             edit_has_laser,
             edit_has_coolant,
@@ -321,6 +367,14 @@ impl DeviceManagerWindow {
             edit_spindle_watts,
             edit_max_spindle_speed_rpm,
             edit_laser_watts,
+            edit_laser_dynamic_power,
+            edit_laser_overscan,
+            edit_laser_overscan_unit,
+            edit_laser_power_scaling,
+            edit_laser_reference_feed,
+            edit_laser_reference_feed_unit,
+            edit_laser_power_curve,
+            edit_laser_strip_redundant_power,
             selected_device: shared_none(),
             save_btn,
             cancel_btn,
@@ -382,9 +436,15 @@ impl DeviceManagerWindow {
         self.edit_y_max_unit.set_text(unit_label);
         self.edit_z_min_unit.set_text(unit_label);
         self.edit_z_max_unit.set_text(unit_label);
+        self.edit_backlash_x_unit.set_text(unit_label);
+        self.edit_backlash_y_unit.set_text(unit_label);
+        self.edit_backlash_z_unit.set_text(unit_label);
+        self.edit_laser_overscan_unit.set_text(unit_label);
 
         self.edit_max_feed_rate_unit
             .set_text(&feed_units.to_string());
+        self.edit_laser_reference_feed_unit
+            .set_text(&feed_units.to_string());
 
         let model_opt = self.selected_device.borrow().clone();
         if let Some(profile) = model_opt {
@@ -412,11 +472,26 @@ impl DeviceManagerWindow {
                 profile.z_max.parse::<f32>().unwrap_or(100.0),
                 units,
             ));
+            for (entry, value) in [
+                (&self.edit_backlash_x, &profile.backlash_x),
+                (&self.edit_backlash_y, &profile.backlash_y),
+                (&self.edit_backlash_z, &profile.backlash_z),
+            ] {
+                entry.set_text(&format_length(value.parse::<f32>().unwrap_or(0.0), units));
+            }
+            self.edit_laser_overscan.set_text(&format_length(
+                profile.laser_overscan.parse::<f32>().unwrap_or(0.0),
+                units,
+            ));
 
             self.edit_max_feed_rate.set_text(&format_feed_rate(
                 profile.max_feed_rate.parse::<f32>().unwrap_or(1000.0),
                 feed_units,
             ));
+            self.edit_laser_reference_feed.set_text(&format_feed_rate(
+                profile.laser_reference_feed.parse::<f32>().unwrap_or(0.0),
+                feed_units,
+            ));
         }
     }
 }
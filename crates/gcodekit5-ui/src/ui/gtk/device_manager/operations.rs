@@ -57,6 +57,9 @@ impl DeviceManagerWindow {
         let view = self.clone();
         self.edit_has_laser
             .connect_toggled(move |_| view.update_capabilities_field_sensitivity());
+        let view = self.clone();
+        self.edit_laser_power_scaling
+            .connect_changed(move |_| view.update_capabilities_field_sensitivity());
 
         // List selection
         let view = self.clone();
@@ -176,6 +179,12 @@ impl DeviceManagerWindow {
                 .set_active_id(Some(profile.device_type.as_str()));
             self.edit_controller_type
                 .set_active_id(Some(profile.controller_type.as_str()));
+            if !self
+                .edit_post_processor
+                .set_active_id(Some(profile.post_processor.as_str()))
+            {
+                self.edit_post_processor.set_active_id(Some(""));
+            }
 
             self.edit_connection_type
                 .set_active_id(Some(profile.connection_type.as_str()));
@@ -198,6 +207,14 @@ impl DeviceManagerWindow {
             self.edit_max_spindle_speed_rpm
                 .set_text(profile.max_spindle_speed_rpm.trim());
             self.edit_laser_watts.set_text(profile.laser_watts.trim());
+            self.edit_laser_dynamic_power
+                .set_active(profile.laser_dynamic_power);
+            self.edit_laser_strip_redundant_power
+                .set_active(profile.laser_strip_redundant_power);
+            self.edit_laser_power_scaling
+                .set_active_id(Some(profile.laser_power_scaling.as_str()));
+            self.edit_laser_power_curve
+                .set_text(&profile.laser_power_curve);
 
             self.update_connection_field_sensitivity();
             self.update_capabilities_field_sensitivity();
@@ -248,6 +265,16 @@ impl DeviceManagerWindow {
 
         let has_laser = self.edit_has_laser.is_active();
         self.edit_laser_watts.set_sensitive(has_laser);
+        self.edit_laser_dynamic_power.set_sensitive(has_laser);
+        self.edit_laser_strip_redundant_power
+            .set_sensitive(has_laser);
+        self.edit_laser_overscan.set_sensitive(has_laser);
+        self.edit_laser_power_scaling.set_sensitive(has_laser);
+        let scaling = self.edit_laser_power_scaling.active_id();
+        self.edit_laser_reference_feed
+            .set_sensitive(has_laser && scaling.as_deref() == Some("By Feed"));
+        self.edit_laser_power_curve
+            .set_sensitive(has_laser && scaling.as_deref() == Some("Power Curve"));
     }
 
     pub(crate) fn show_error_dialog(&self, title: &str, details: &str) {
@@ -269,6 +296,11 @@ impl DeviceManagerWindow {
             if let Some(txt) = self.edit_controller_type.active_text() {
                 model.controller_type = txt.to_string();
             }
+            model.post_processor = self
+                .edit_post_processor
+                .active_id()
+                .map(|id| id.to_string())
+                .unwrap_or_default();
 
             // Connection
             if let Some(txt) = self.edit_connection_type.active_text() {
@@ -349,6 +381,36 @@ impl DeviceManagerWindow {
             model.z_min = format!("{:.2}", z_min_mm);
             model.z_max = format!("{:.2}", z_max_mm);
 
+            for (entry, value, title) in [
+                (
+                    &self.edit_backlash_x,
+                    &mut model.backlash_x,
+                    "Invalid X Backlash",
+                ),
+                (
+                    &self.edit_backlash_y,
+                    &mut model.backlash_y,
+                    "Invalid Y Backlash",
+                ),
+                (
+                    &self.edit_backlash_z,
+                    &mut model.backlash_z,
+                    "Invalid Z Backlash",
+                ),
+            ] {
+                match parse_length(&entry.text(), units) {
+                    Ok(v) if v >= 0.0 => *value = format!("{:.4}", v),
+                    Ok(_) => {
+                        self.show_error_dialog(title, "Backlash cannot be negative");
+                        return;
+                    }
+                    Err(e) => {
+                        self.show_error_dialog(title, &e);
+                        return;
+                    }
+                }
+            }
+
             // Capabilities
             model.has_spindle = self.edit_has_spindle.is_active();
             model.has_laser = self.edit_has_laser.is_active();
@@ -430,12 +492,52 @@ impl DeviceManagerWindow {
             model.cnc_spindle_watts = format!("{:.0}", spindle_watts);
             model.laser_watts = format!("{:.0}", laser_watts);
 
+            // Laser processing
+            model.laser_dynamic_power = self.edit_laser_dynamic_power.is_active();
+            model.laser_strip_redundant_power = self.edit_laser_strip_redundant_power.is_active();
+            match parse_length(&self.edit_laser_overscan.text(), units) {
+                Ok(v) if v >= 0.0 => model.laser_overscan = format!("{:.4}", v),
+                Ok(_) => {
+                    self.show_error_dialog("Invalid Overscan", "Overscan cannot be negative");
+                    return;
+                }
+                Err(e) => {
+                    self.show_error_dialog("Invalid Overscan", &e);
+                    return;
+                }
+            }
+            match parse_feed_rate(&self.edit_laser_reference_feed.text(), feed_units) {
+                Ok(v) => model.laser_reference_feed = format!("{:.0}", v),
+                Err(e) => {
+                    self.show_error_dialog("Invalid Full Power Feed", &e);
+                    return;
+                }
+            }
+            model.laser_power_scaling = self
+                .edit_laser_power_scaling
+                .active_id()
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| "None".to_string());
+            model.laser_power_curve = self.edit_laser_power_curve.text().to_string();
+
             // Save
+            let is_active = model.is_active;
             if let Err(e) = self.controller.update_profile_from_ui(model) {
                 error!("Failed to save device profile: {}", e);
                 self.show_error_dialog("Failed to save device", &e.to_string());
                 return;
             }
+            if is_active {
+                if let Some(name) = self.controller.active_post_processor_name() {
+                    device_status::set_active_post_processor(&name);
+                }
+                if let Some(backlash) = self.controller.active_backlash() {
+                    device_status::set_active_backlash(backlash);
+                }
+                if let Some(profile) = self.controller.active_profile() {
+                    device_status::set_active_laser(&profile);
+                }
+            }
 
             self.load_devices();
             self.cancel_edit();
@@ -484,6 +586,15 @@ impl DeviceManagerWindow {
                 let num: u8 = profile.num_axes.trim().parse().unwrap_or(3);
                 crate::device_status::set_active_num_axes(num);
             }
+            if let Some(name) = self.controller.active_post_processor_name() {
+                crate::device_status::set_active_post_processor(&name);
+            }
+            if let Some(backlash) = self.controller.active_backlash() {
+                crate::device_status::set_active_backlash(backlash);
+            }
+            if let Some(profile) = self.controller.active_profile() {
+                crate::device_status::set_active_laser(&profile);
+            }
             self.load_devices();
             self.cancel_edit();
         }
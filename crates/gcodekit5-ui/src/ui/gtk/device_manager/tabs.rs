@@ -3,8 +3,14 @@
 use super::*;
 
 impl DeviceManagerWindow {
-    pub(crate) fn create_general_tab() -> (ScrolledWindow, Entry, Entry, ComboBoxText, ComboBoxText)
-    {
+    pub(crate) fn create_general_tab() -> (
+        ScrolledWindow,
+        Entry,
+        Entry,
+        ComboBoxText,
+        ComboBoxText,
+        ComboBoxText,
+    ) {
         let scroll = ScrolledWindow::new();
         scroll.set_policy(PolicyType::Never, PolicyType::Automatic);
 
@@ -63,6 +69,24 @@ impl DeviceManagerWindow {
         edit_controller_type.set_active_id(Some("GRBL"));
         grid.attach(&ctrl_label, 0, row, 1, 1);
         grid.attach(&edit_controller_type, 1, row, 1, 1);
+        row += 1;
+
+        // Post-processor
+        let post_label = Label::new(Some("Post-processor:"));
+        post_label.set_halign(Align::Start);
+        let edit_post_processor = ComboBoxText::new();
+        edit_post_processor.append(Some(""), "Controller default");
+        edit_post_processor.append(Some("None"), "None (generic G-code)");
+        for name in device_status::post_library().names() {
+            edit_post_processor.append(Some(name), name);
+        }
+        edit_post_processor.set_active_id(Some(""));
+        edit_post_processor.set_tooltip_text(Some(
+            "Formats generated G-code for this controller. \
+             Custom definitions are read from the post_processors config folder.",
+        ));
+        grid.attach(&post_label, 0, row, 1, 1);
+        grid.attach(&edit_post_processor, 1, row, 1, 1);
 
         scroll.set_child(Some(&grid));
         (
@@ -71,6 +95,7 @@ impl DeviceManagerWindow {
             edit_description,
             edit_device_type,
             edit_controller_type,
+            edit_post_processor,
         )
     }
 
@@ -185,6 +210,12 @@ impl DeviceManagerWindow {
         Label,
         Label,
         Label,
+        Entry,
+        Entry,
+        Entry,
+        Label,
+        Label,
+        Label,
     ) {
         let scroll = ScrolledWindow::new();
         scroll.set_policy(PolicyType::Never, PolicyType::Automatic);
@@ -299,6 +330,30 @@ impl DeviceManagerWindow {
         grid.attach(&edit_z_max, 5, row, 1, 1);
         grid.attach(&edit_z_max_unit, 6, row, 1, 1);
 
+        // Backlash, next to each axis's limits
+        let backlash_field = |row: i32| {
+            let label = Label::new(Some("Backlash:"));
+            label.set_margin_start(20);
+            let entry = Entry::new();
+            entry.set_input_purpose(gtk4::InputPurpose::Number);
+            entry.set_width_chars(6);
+            entry.set_tooltip_text(Some(
+                "Lost motion when the axis reverses, made up for when streaming jobs. \
+                 0 turns compensation off; Machine → Measure Backlash helps measure it.",
+            ));
+            let unit = Label::new(Some(unit_label));
+            unit.set_width_chars(4);
+            unit.set_halign(Align::End);
+            unit.set_xalign(1.0);
+            grid.attach(&label, 7, row, 1, 1);
+            grid.attach(&entry, 8, row, 1, 1);
+            grid.attach(&unit, 9, row, 1, 1);
+            (entry, unit)
+        };
+        let (edit_backlash_x, edit_backlash_x_unit) = backlash_field(row - 2);
+        let (edit_backlash_y, edit_backlash_y_unit) = backlash_field(row - 1);
+        let (edit_backlash_z, edit_backlash_z_unit) = backlash_field(row);
+
         scroll.set_child(Some(&grid));
         (
             scroll,
@@ -314,6 +369,12 @@ impl DeviceManagerWindow {
             edit_y_max_unit,
             edit_z_min_unit,
             edit_z_max_unit,
+            edit_backlash_x,
+            edit_backlash_y,
+            edit_backlash_z,
+            edit_backlash_x_unit,
+            edit_backlash_y_unit,
+            edit_backlash_z_unit,
         )
     }
 
@@ -460,4 +521,119 @@ impl DeviceManagerWindow {
             edit_laser_watts,
         )
     }
+
+    pub(crate) fn create_laser_tab(
+        units: MeasurementSystem,
+        feed_units: FeedRateUnits,
+    ) -> (
+        ScrolledWindow,
+        CheckButton,
+        Entry,
+        Label,
+        ComboBoxText,
+        Entry,
+        Label,
+        Entry,
+        CheckButton,
+    ) {
+        let scroll = ScrolledWindow::new();
+        scroll.set_policy(PolicyType::Never, PolicyType::Automatic);
+
+        let vbox = Box::new(Orientation::Vertical, 15);
+        vbox.set_margin_top(10);
+        vbox.set_margin_bottom(10);
+        vbox.set_margin_start(10);
+        vbox.set_margin_end(10);
+
+        let title = Label::new(Some("Job Processing"));
+        title.set_css_classes(&["title-4"]);
+        title.set_halign(Align::Start);
+        vbox.append(&title);
+
+        let hint = Label::new(Some(
+            "Applied to jobs streamed to this device when it has a laser.",
+        ));
+        hint.add_css_class("dim-label");
+        hint.set_halign(Align::Start);
+        vbox.append(&hint);
+
+        let edit_laser_dynamic_power = CheckButton::with_label("Dynamic Power (M3 → M4)");
+        edit_laser_dynamic_power.set_tooltip_text(Some(
+            "Scale power with the actual speed of the head so corners do not burn",
+        ));
+        vbox.append(&edit_laser_dynamic_power);
+
+        let edit_laser_strip_redundant_power = CheckButton::with_label("Remove Repeated S Words");
+        vbox.append(&edit_laser_strip_redundant_power);
+
+        let grid = Grid::new();
+        grid.set_column_spacing(10);
+        grid.set_row_spacing(10);
+
+        let overscan_label = Label::new(Some("Overscan:"));
+        overscan_label.set_halign(Align::Start);
+        let edit_laser_overscan = Entry::new();
+        edit_laser_overscan.set_input_purpose(gtk4::InputPurpose::Number);
+        edit_laser_overscan.set_tooltip_text(Some(
+            "Laser-off run-up added to each end of raster lines; 0 turns it off",
+        ));
+        let edit_laser_overscan_unit = Label::new(Some(get_unit_label(units)));
+        edit_laser_overscan_unit.set_width_chars(6);
+        edit_laser_overscan_unit.set_halign(Align::End);
+        edit_laser_overscan_unit.set_xalign(1.0);
+        grid.attach(&overscan_label, 0, 0, 1, 1);
+        grid.attach(&edit_laser_overscan, 1, 0, 1, 1);
+        grid.attach(&edit_laser_overscan_unit, 2, 0, 1, 1);
+
+        let scaling_label = Label::new(Some("Power Scaling:"));
+        scaling_label.set_halign(Align::Start);
+        let edit_laser_power_scaling = ComboBoxText::new();
+        for option in ["None", "By Feed", "Power Curve"] {
+            edit_laser_power_scaling.append(Some(option), option);
+        }
+        edit_laser_power_scaling.set_active_id(Some("None"));
+        grid.attach(&scaling_label, 0, 1, 1, 1);
+        grid.attach(&edit_laser_power_scaling, 1, 1, 1, 1);
+
+        let reference_label = Label::new(Some("Full Power Feed:"));
+        reference_label.set_halign(Align::Start);
+        let edit_laser_reference_feed = Entry::new();
+        edit_laser_reference_feed.set_input_purpose(gtk4::InputPurpose::Number);
+        edit_laser_reference_feed.set_tooltip_text(Some(
+            "Feed that gets full power when scaling by feed; 0 uses the max feed rate",
+        ));
+        let edit_laser_reference_feed_unit = Label::new(Some(&feed_units.to_string()));
+        edit_laser_reference_feed_unit.set_width_chars(6);
+        edit_laser_reference_feed_unit.set_halign(Align::End);
+        edit_laser_reference_feed_unit.set_xalign(1.0);
+        grid.attach(&reference_label, 0, 2, 1, 1);
+        grid.attach(&edit_laser_reference_feed, 1, 2, 1, 1);
+        grid.attach(&edit_laser_reference_feed_unit, 2, 2, 1, 1);
+
+        let curve_label = Label::new(Some("Power Curve:"));
+        curve_label.set_halign(Align::Start);
+        let edit_laser_power_curve = Entry::new();
+        edit_laser_power_curve.set_placeholder_text(Some("500:40, 1500:70, 3000:100"));
+        edit_laser_power_curve.set_tooltip_text(Some(
+            "Material power curve as feed:percent points, feeds in mm/min",
+        ));
+        edit_laser_power_curve.set_hexpand(true);
+        grid.attach(&curve_label, 0, 3, 1, 1);
+        grid.attach(&edit_laser_power_curve, 1, 3, 2, 1);
+
+        vbox.append(&grid);
+
+        scroll.set_child(Some(&vbox));
+        (
+            scroll,
+            edit_laser_dynamic_power,
+            edit_laser_overscan,
+            edit_laser_overscan_unit,
+            edit_laser_power_scaling,
+            edit_laser_reference_feed,
+            edit_laser_reference_feed_unit,
+            edit_laser_power_curve,
+            edit_laser_strip_redundant_power,
+        )
+    }
 }
@@ -1,5 +1,6 @@
 use crate::ui::gtk::status_bar::StatusBar;
-use gcodekit5_core::{shared_none, SharedOption};
+use gcodekit5_core::{shared, shared_none, Shared, SharedOption};
+use gcodekit5_visualizer::{ValidationIssue, ValidationSeverity};
 use glib;
 use gtk4::prelude::*;
 use gtk4::{
@@ -7,10 +8,12 @@ use gtk4::{
 };
 use sourceview5::prelude::*;
 use sourceview5::{
-    Buffer, LanguageManager, SearchContext, SearchSettings, StyleSchemeManager, View,
+    Buffer, LanguageManager, MarkAttributes, SearchContext, SearchSettings, StyleSchemeManager,
+    View,
 };
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::mpsc;
 use std::thread;
@@ -25,8 +28,17 @@ pub struct GcodeEditor {
     _search_context: SearchContext,
     _search_settings: SearchSettings,
     _status_bar: Option<StatusBar>,
+    // Tooltip text of validation marks, keyed by 0-based line
+    issue_messages: Shared<HashMap<i32, String>>,
+    on_file_opened: SharedOption<FileOpenedCallback>,
 }
 
+/// Called with the path and contents of a file after it is loaded
+type FileOpenedCallback = std::boxed::Box<dyn Fn(&Path, &str)>;
+
+/// Source mark categories used for validation issues
+const ISSUE_CATEGORIES: [&str; 3] = ["issue-error", "issue-warning", "issue-info"];
+
 impl GcodeEditor {
     pub fn new(status_bar: Option<StatusBar>) -> Self {
         let buffer = Buffer::new(None);
@@ -40,6 +52,48 @@ impl GcodeEditor {
         view.set_show_right_margin(true);
         view.set_right_margin_position(80);
 
+        // Validation issues are shown as gutter marks with the message as tooltip
+        let issue_messages: Shared<HashMap<i32, String>> = shared(HashMap::new());
+        view.set_show_line_marks(true);
+        for (category, icon, rgba, priority) in [
+            (
+                ISSUE_CATEGORIES[0],
+                "dialog-error-symbolic",
+                (0.88, 0.11, 0.14),
+                3,
+            ),
+            (
+                ISSUE_CATEGORIES[1],
+                "dialog-warning-symbolic",
+                (0.96, 0.76, 0.07),
+                2,
+            ),
+            (
+                ISSUE_CATEGORIES[2],
+                "dialog-information-symbolic",
+                (0.21, 0.52, 0.89),
+                1,
+            ),
+        ] {
+            let attributes = MarkAttributes::new();
+            attributes.set_icon_name(icon);
+            attributes.set_background(&gtk4::gdk::RGBA::new(rgba.0, rgba.1, rgba.2, 0.15));
+            let messages = issue_messages.clone();
+            attributes.connect_query_tooltip_text(move |_, mark| {
+                let line = mark
+                    .buffer()
+                    .map(|buffer| buffer.iter_at_mark(mark).line())
+                    .unwrap_or(-1);
+                messages
+                    .borrow()
+                    .get(&line)
+                    .cloned()
+                    .unwrap_or_default()
+                    .into()
+            });
+            view.set_mark_attributes(category, &attributes, priority);
+        }
+
         // Try to set a dark style scheme if available, matching the app's dark theme
         let scheme_manager = StyleSchemeManager::default();
 
@@ -494,6 +548,8 @@ impl GcodeEditor {
             _search_context: search_context,
             _search_settings: search_settings,
             _status_bar: status_bar,
+            issue_messages,
+            on_file_opened: shared_none(),
         };
 
         // Update line counter when cursor moves
@@ -550,6 +606,11 @@ impl GcodeEditor {
         self.buffer.text(&start, &end, true).to_string()
     }
 
+    /// Path of the file being edited, if it has been opened or saved
+    pub fn current_file(&self) -> Option<PathBuf> {
+        self.current_file.borrow().clone()
+    }
+
     pub fn grab_focus(&self) {
         self.view.grab_focus();
     }
@@ -558,6 +619,75 @@ impl GcodeEditor {
         self.buffer.connect_changed(f);
     }
 
+    /// 1-based line holding the cursor
+    pub fn cursor_line(&self) -> u32 {
+        let iter = self.buffer.iter_at_mark(&self.buffer.get_insert());
+        iter.line() as u32 + 1
+    }
+
+    /// Move the cursor to the start of 1-based `line` and scroll it into view
+    pub fn goto_line(&self, line: u32) {
+        let Some(mut iter) = self.buffer.iter_at_line(line.saturating_sub(1) as i32) else {
+            return;
+        };
+        self.buffer.place_cursor(&iter);
+        self.view.scroll_to_iter(&mut iter, 0.1, false, 0.0, 0.0);
+    }
+
+    /// Call `f` with the 1-based line whenever the cursor moves to another line
+    pub fn connect_cursor_line_changed<F: Fn(u32) + 'static>(&self, f: F) {
+        let last_line = std::rc::Rc::new(std::cell::Cell::new(self.cursor_line()));
+        self.buffer.connect_mark_set(move |_, iter, mark| {
+            if mark.name().as_deref() != Some("insert") {
+                return;
+            }
+            let line = iter.line() as u32 + 1;
+            if last_line.replace(line) != line {
+                f(line);
+            }
+        });
+    }
+
+    /// Mark the lines of validation issues in the gutter, replacing any
+    /// previously shown issues. Hovering a mark shows the message.
+    pub fn set_validation_issues(&self, issues: &[ValidationIssue]) {
+        let (start, end) = self.buffer.bounds();
+        for category in ISSUE_CATEGORIES {
+            self.buffer
+                .remove_source_marks(&start, &end, Some(category));
+        }
+
+        let mut messages = self.issue_messages.borrow_mut();
+        messages.clear();
+        let mut marked = std::collections::HashSet::new();
+        for issue in issues {
+            let Some(line) = issue.line_number.checked_sub(1).map(|l| l as i32) else {
+                continue;
+            };
+            let Some(iter) = self.buffer.iter_at_line(line) else {
+                continue;
+            };
+            let category = match issue.severity {
+                ValidationSeverity::Error => ISSUE_CATEGORIES[0],
+                ValidationSeverity::Warning => ISSUE_CATEGORIES[1],
+                ValidationSeverity::Info => ISSUE_CATEGORIES[2],
+            };
+            if marked.insert((line, category)) {
+                self.buffer.create_source_mark(None, category, &iter);
+            }
+
+            let text = messages.entry(line).or_default();
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&issue.message);
+            if let Some(suggestion) = &issue.suggestion {
+                text.push_str(" – ");
+                text.push_str(suggestion);
+            }
+        }
+    }
+
     pub fn undo(&self) {
         if self.buffer.can_undo() {
             self.buffer.undo();
@@ -609,30 +739,12 @@ impl GcodeEditor {
 
         let buffer = self.buffer.clone();
         let current_file = self.current_file.clone();
+        let on_file_opened = self.on_file_opened.clone();
 
         dialog.connect_response(move |dialog, response| {
             if response == ResponseType::Accept {
-                if let Some(file) = dialog.file() {
-                    if let Some(path) = file.path() {
-                        match fs::read_to_string(&path) {
-                            Ok(content) => {
-                                buffer.set_text(&content);
-                                *current_file.borrow_mut() = Some(path);
-                                // Move cursor to start
-                                let start_iter = buffer.start_iter();
-                                buffer.place_cursor(&start_iter);
-                            }
-                            Err(e) => {
-                                error!("Error reading file {}: {}", path.display(), e);
-                                let parent = super::file_dialog::parent_window(dialog);
-                                super::file_dialog::show_error_dialog(
-                                    "Error Reading File",
-                                    &format!("Could not open '{}'.\n\n{}", path.display(), e),
-                                    parent.as_ref(),
-                                );
-                            }
-                        }
-                    }
+                if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                    Self::load_path(&buffer, &current_file, &on_file_opened, &path, dialog);
                 }
             }
             dialog.destroy();
@@ -641,6 +753,53 @@ impl GcodeEditor {
         dialog.show();
     }
 
+    /// Open a file without showing the file chooser, e.g. from the recent
+    /// files list
+    pub fn open_path(&self, path: &Path) {
+        Self::load_path(
+            &self.buffer,
+            &self.current_file,
+            &self.on_file_opened,
+            path,
+            &self.widget,
+        );
+    }
+
+    /// Register a callback run after a file has been loaded into the editor
+    pub fn set_on_file_opened<F: Fn(&Path, &str) + 'static>(&self, callback: F) {
+        *self.on_file_opened.borrow_mut() = Some(std::boxed::Box::new(callback));
+    }
+
+    fn load_path(
+        buffer: &Buffer,
+        current_file: &SharedOption<PathBuf>,
+        on_file_opened: &SharedOption<FileOpenedCallback>,
+        path: &Path,
+        widget: &impl IsA<gtk4::Widget>,
+    ) {
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                buffer.set_text(&content);
+                *current_file.borrow_mut() = Some(path.to_path_buf());
+                // Move cursor to start
+                let start_iter = buffer.start_iter();
+                buffer.place_cursor(&start_iter);
+                if let Some(callback) = on_file_opened.borrow().as_ref() {
+                    callback(path, &content);
+                }
+            }
+            Err(e) => {
+                error!("Error reading file {}: {}", path.display(), e);
+                let parent = super::file_dialog::parent_window(widget);
+                super::file_dialog::show_error_dialog(
+                    "Error Reading File",
+                    &format!("Could not open '{}'.\n\n{}", path.display(), e),
+                    parent.as_ref(),
+                );
+            }
+        }
+    }
+
     pub fn save_file(&self) {
         let current_path = self.current_file.borrow().clone();
 
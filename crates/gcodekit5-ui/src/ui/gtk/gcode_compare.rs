@@ -0,0 +1,284 @@
+//! Compare G-code dialog.
+//!
+//! Compares the editor program against another file by the material each
+//! one removes, rather than line by line, and overlays the differences on
+//! the visualizer.
+
+use super::editor::GcodeEditor;
+use super::file_dialog;
+use super::visualizer::GcodeVisualizer;
+use crate::t;
+use gcodekit5_visualizer::{DiffKind, DiffSettings, ToolpathDiff};
+use gtk4::prelude::*;
+use gtk4::{
+    Align, Box, Button, FileFilter, Grid, Label, ListBox, Orientation, PolicyType, ResponseType,
+    ScrolledWindow, SpinButton, Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Ask for the original program, then show how the editor program differs
+pub fn show_compare_dialog(
+    parent: Option<&Window>,
+    editor: Rc<GcodeEditor>,
+    visualizer: Rc<GcodeVisualizer>,
+) {
+    let dialog = file_dialog::open_dialog(&t!("Compare With"), parent);
+    let filter = FileFilter::new();
+    filter.set_name(Some(&t!("G-code Files")));
+    for pattern in ["*.gcode", "*.nc", "*.ngc", "*.tap", "*.txt"] {
+        filter.add_pattern(pattern);
+    }
+    dialog.add_filter(&filter);
+    let all = FileFilter::new();
+    all.set_name(Some(&t!("All Files")));
+    all.add_pattern("*");
+    dialog.add_filter(&all);
+
+    let window = parent.cloned();
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            if let Some(path) = dialog.file().and_then(|f| f.path()) {
+                match std::fs::read_to_string(&path) {
+                    Ok(original) => {
+                        let name = path
+                            .file_name()
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        show_results(
+                            window.as_ref(),
+                            &name,
+                            original,
+                            editor.clone(),
+                            visualizer.clone(),
+                        );
+                    }
+                    Err(e) => file_dialog::show_error_dialog(
+                        &t!("Compare G-code"),
+                        &e.to_string(),
+                        window.as_ref(),
+                    ),
+                }
+            }
+        }
+        dialog.destroy();
+    });
+    dialog.show();
+}
+
+fn format_time(seconds: f64) -> String {
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    let seconds = seconds.abs().round() as u64;
+    format!(
+        "{}{}:{:02}:{:02}",
+        sign,
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+fn format_z(z: Option<f32>) -> String {
+    z.map_or_else(|| "-".to_string(), |z| format!("{:.3}", z))
+}
+
+/// Summary rows: label, original, modified, change
+fn summary_rows(diff: &ToolpathDiff) -> Vec<[String; 4]> {
+    let (a, b) = (&diff.original, &diff.modified);
+    vec![
+        [
+            t!("Run time"),
+            format_time(a.run_time),
+            format_time(b.run_time),
+            format_time(diff.time_change()),
+        ],
+        [
+            t!("Deepest cut (mm)"),
+            format_z(a.min_cut_z),
+            format_z(b.min_cut_z),
+            format_z(diff.depth_change()),
+        ],
+        [
+            t!("Lowest rapid (mm)"),
+            format_z(a.min_rapid_z),
+            format_z(b.min_rapid_z),
+            String::new(),
+        ],
+        [
+            t!("Average feed (mm/min)"),
+            format!("{:.0}", a.avg_feed),
+            format!("{:.0}", b.avg_feed),
+            format!("{:+.0}", diff.feed_change()),
+        ],
+        [
+            t!("Cut distance (mm)"),
+            format!("{:.1}", a.cut_distance),
+            format!("{:.1}", b.cut_distance),
+            format!("{:+.1}", b.cut_distance - a.cut_distance),
+        ],
+    ]
+}
+
+fn show_results(
+    parent: Option<&Window>,
+    original_name: &str,
+    original: String,
+    editor: Rc<GcodeEditor>,
+    visualizer: Rc<GcodeVisualizer>,
+) {
+    let window = Window::builder()
+        .title(format!("{} — {}", t!("Compare G-code"), original_name))
+        .default_width(520)
+        .default_height(560)
+        .build();
+    if let Some(parent) = parent {
+        window.set_transient_for(Some(parent));
+    }
+
+    let content = Box::new(Orientation::Vertical, 12);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let tolerance_row = Box::new(Orientation::Horizontal, 6);
+    let tolerance_label = Label::new(Some(&t!("Tolerance (mm)")));
+    let tolerance = SpinButton::with_range(0.001, 10.0, 0.01);
+    tolerance.set_digits(3);
+    tolerance.set_value(DiffSettings::default().tolerance as f64);
+    tolerance.set_tooltip_text(Some(&t!(
+        "Cuts closer than this to the other program count as the same"
+    )));
+    tolerance_row.append(&tolerance_label);
+    tolerance_row.append(&tolerance);
+    content.append(&tolerance_row);
+
+    let verdict = Label::new(None);
+    verdict.set_halign(Align::Start);
+    verdict.set_wrap(true);
+    content.append(&verdict);
+
+    let grid = Grid::new();
+    grid.set_row_spacing(4);
+    grid.set_column_spacing(18);
+    content.append(&grid);
+
+    let regions = ListBox::new();
+    let scrolled = ScrolledWindow::builder()
+        .hscrollbar_policy(PolicyType::Never)
+        .vexpand(true)
+        .child(&regions)
+        .build();
+    content.append(&scrolled);
+
+    let hint = Label::new(Some(&t!(
+        "Green cuts are only in the editor program, red cuts only in the original. Select a change to show it in the editor."
+    )));
+    hint.set_wrap(true);
+    hint.set_halign(Align::Start);
+    hint.add_css_class("dim-label");
+    content.append(&hint);
+
+    let buttons = Box::new(Orientation::Horizontal, 6);
+    buttons.set_halign(Align::End);
+    let close_btn = Button::with_label(&t!("Close"));
+    buttons.append(&close_btn);
+    content.append(&buttons);
+    window.set_child(Some(&content));
+
+    // Region shown on each list row, by row index
+    let listed: Rc<RefCell<Vec<(DiffKind, u32)>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let refresh = {
+        let editor = editor.clone();
+        let visualizer = visualizer.clone();
+        let listed = listed.clone();
+        let tolerance = tolerance.clone();
+        Rc::new(move || {
+            let settings = DiffSettings {
+                tolerance: tolerance.value() as f32,
+                ..DiffSettings::default()
+            };
+            let diff = ToolpathDiff::compare(&original, &editor.get_text(), settings);
+
+            verdict.set_text(&if diff.is_equivalent() {
+                t!("Both programs cut the same toolpath.")
+            } else if diff.cuts_new_material() {
+                t!("The editor program cuts material the original does not.")
+            } else {
+                t!("The editor program only leaves out cuts of the original.")
+            });
+
+            while let Some(child) = grid.first_child() {
+                grid.remove(&child);
+            }
+            let header = [String::new(), t!("Original"), t!("Editor"), t!("Change")];
+            for (row, cells) in std::iter::once(header)
+                .chain(summary_rows(&diff))
+                .enumerate()
+            {
+                for (col, text) in cells.iter().enumerate() {
+                    let label = Label::new(Some(text));
+                    label.set_halign(if col == 0 { Align::Start } else { Align::End });
+                    if row == 0 {
+                        label.add_css_class("heading");
+                    }
+                    grid.attach(&label, col as i32, row as i32, 1, 1);
+                }
+            }
+
+            while let Some(child) = regions.first_child() {
+                regions.remove(&child);
+            }
+            let mut rows = listed.borrow_mut();
+            rows.clear();
+            for region in &diff.regions {
+                let (kind, css) = match region.kind {
+                    DiffKind::Added => (t!("Added"), "success"),
+                    DiffKind::Removed => (t!("Removed"), "error"),
+                };
+                let lines = if region.first_line == region.last_line {
+                    format!("{} {}", t!("Line"), region.first_line)
+                } else {
+                    format!("{} {}–{}", t!("Lines"), region.first_line, region.last_line)
+                };
+                let label = Label::new(Some(&format!(
+                    "{}: {}, {:.2} mm, {} {:.3} mm, Z {:.3}",
+                    kind,
+                    lines,
+                    region.length,
+                    t!("off by"),
+                    region.max_deviation,
+                    region.min_z
+                )));
+                label.set_halign(Align::Start);
+                label.add_css_class(css);
+                regions.append(&label);
+                rows.push((region.kind, region.first_line));
+            }
+
+            visualizer.set_comparison(Some(diff));
+        })
+    };
+    refresh();
+
+    let refresh_tolerance = refresh.clone();
+    tolerance.connect_value_changed(move |_| refresh_tolerance());
+
+    // Line numbers of removed regions refer to the original file
+    regions.connect_row_activated(move |_, row| {
+        if let Some(&(DiffKind::Added, line)) = listed.borrow().get(row.index() as usize) {
+            editor.goto_line(line);
+        }
+    });
+
+    let visualizer_close = visualizer.clone();
+    window.connect_close_request(move |_| {
+        visualizer_close.set_comparison(None);
+        gtk4::glib::Propagation::Proceed
+    });
+    let window_close = window.clone();
+    close_btn.connect_clicked(move |_| window_close.close());
+
+    window.present();
+}
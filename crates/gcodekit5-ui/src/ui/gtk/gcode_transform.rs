@@ -0,0 +1,195 @@
+//! Transform G-code dialog.
+//!
+//! Translates, rotates, mirrors or scales the program loaded in the editor.
+
+use super::editor::GcodeEditor;
+use super::file_dialog;
+use crate::t;
+use gcodekit5_visualizer::{MirrorAxis, TransformProcessor, Visualizer};
+use gtk4::prelude::*;
+use gtk4::{Align, Box, Button, ComboBoxText, Grid, Label, Orientation, SpinButton, Window};
+use std::rc::Rc;
+
+/// Show the transform dialog for the editor program
+pub fn show_transform_dialog(parent: Option<&Window>, editor: Rc<GcodeEditor>) {
+    let window = Window::builder()
+        .title(t!("Transform G-code"))
+        .modal(true)
+        .default_width(380)
+        .build();
+    if let Some(parent) = parent {
+        window.set_transient_for(Some(parent));
+    }
+
+    let content = Box::new(Orientation::Vertical, 12);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let grid = Grid::new();
+    grid.set_row_spacing(6);
+    grid.set_column_spacing(12);
+    let row = std::cell::Cell::new(0);
+    let attach = |label: &str, widget: &gtk4::Widget| {
+        let label = Label::new(Some(label));
+        label.set_halign(Align::Start);
+        grid.attach(&label, 0, row.get(), 1, 1);
+        grid.attach(widget, 1, row.get(), 1, 1);
+        row.set(row.get() + 1);
+    };
+    let spin = |min: f64, max: f64, step: f64, value: f64| {
+        let spin = SpinButton::with_range(min, max, step);
+        spin.set_digits(3);
+        spin.set_value(value);
+        spin.set_hexpand(true);
+        spin
+    };
+
+    let operation = ComboBoxText::new();
+    operation.append(Some("translate"), &t!("Translate"));
+    operation.append(Some("rotate"), &t!("Rotate"));
+    operation.append(Some("mirror"), &t!("Mirror"));
+    operation.append(Some("scale"), &t!("Scale"));
+    operation.set_active_id(Some("translate"));
+    attach(&t!("Operation"), operation.upcast_ref());
+
+    let dx = spin(-10000.0, 10000.0, 1.0, 0.0);
+    let dy = spin(-10000.0, 10000.0, 1.0, 0.0);
+    let dz = spin(-1000.0, 1000.0, 0.1, 0.0);
+    attach(&t!("Offset X (mm)"), dx.upcast_ref());
+    attach(&t!("Offset Y (mm)"), dy.upcast_ref());
+    attach(&t!("Offset Z (mm)"), dz.upcast_ref());
+
+    let angle = spin(-360.0, 360.0, 15.0, 90.0);
+    angle.set_tooltip_text(Some(&t!("Counter-clockwise, in degrees")));
+    attach(&t!("Angle (°)"), angle.upcast_ref());
+
+    let mirror_axis = ComboBoxText::new();
+    mirror_axis.append(Some("x"), &t!("Flip X"));
+    mirror_axis.append(Some("y"), &t!("Flip Y"));
+    mirror_axis.set_active_id(Some("x"));
+    attach(&t!("Mirror"), mirror_axis.upcast_ref());
+
+    let sx = spin(-100.0, 100.0, 0.1, 1.0);
+    let sy = spin(-100.0, 100.0, 0.1, 1.0);
+    let sz = spin(-100.0, 100.0, 0.1, 1.0);
+    attach(&t!("Scale X"), sx.upcast_ref());
+    attach(&t!("Scale Y"), sy.upcast_ref());
+    attach(&t!("Scale Z"), sz.upcast_ref());
+
+    let about = ComboBoxText::new();
+    about.append(Some("origin"), &t!("Origin"));
+    about.append(Some("center"), &t!("Job center"));
+    about.append(Some("custom"), &t!("Custom point"));
+    about.set_active_id(Some("origin"));
+    about.set_tooltip_text(Some(&t!("Fixed point for rotate, mirror and scale")));
+    attach(&t!("About"), about.upcast_ref());
+
+    let cx = spin(-10000.0, 10000.0, 1.0, 0.0);
+    let cy = spin(-10000.0, 10000.0, 1.0, 0.0);
+    attach(&t!("Point X (mm)"), cx.upcast_ref());
+    attach(&t!("Point Y (mm)"), cy.upcast_ref());
+    content.append(&grid);
+
+    let note = Label::new(Some(&t!(
+        "Machine coordinate moves (G53) are left unchanged. Arcs need uniform XY scaling."
+    )));
+    note.set_wrap(true);
+    note.set_halign(Align::Start);
+    note.add_css_class("dim-label");
+    content.append(&note);
+
+    let buttons = Box::new(Orientation::Horizontal, 6);
+    buttons.set_halign(Align::End);
+    let cancel_btn = Button::with_label(&t!("Cancel"));
+    let apply_btn = Button::with_label(&t!("Apply"));
+    apply_btn.add_css_class("suggested-action");
+    buttons.append(&cancel_btn);
+    buttons.append(&apply_btn);
+    content.append(&buttons);
+    window.set_child(Some(&content));
+
+    // Only the fields of the chosen operation are editable
+    let update_sensitivity = {
+        let operation = operation.clone();
+        let about = about.clone();
+        let (dx, dy, dz, angle, mirror_axis) = (
+            dx.clone(),
+            dy.clone(),
+            dz.clone(),
+            angle.clone(),
+            mirror_axis.clone(),
+        );
+        let (sx, sy, sz, cx, cy) = (sx.clone(), sy.clone(), sz.clone(), cx.clone(), cy.clone());
+        Rc::new(move || {
+            let op = operation.active_id().unwrap_or_default();
+            let op = op.as_str();
+            for w in [&dx, &dy, &dz] {
+                w.set_sensitive(op == "translate");
+            }
+            angle.set_sensitive(op == "rotate");
+            mirror_axis.set_sensitive(op == "mirror");
+            for w in [&sx, &sy, &sz] {
+                w.set_sensitive(op == "scale");
+            }
+            about.set_sensitive(op != "translate");
+            let custom = op != "translate" && about.active_id().as_deref() == Some("custom");
+            cx.set_sensitive(custom);
+            cy.set_sensitive(custom);
+        })
+    };
+    update_sensitivity();
+    let update = update_sensitivity.clone();
+    operation.connect_changed(move |_| update());
+    about.connect_changed(move |_| update_sensitivity());
+
+    let window_apply = window.clone();
+    apply_btn.connect_clicked(move |_| {
+        let gcode = editor.get_text();
+        let center = match about.active_id().as_deref() {
+            Some("center") => {
+                let mut visualizer = Visualizer::new();
+                visualizer.parse_gcode(&gcode);
+                match visualizer.get_cutting_bounds() {
+                    Some((min_x, max_x, min_y, max_y, _, _)) => (
+                        (min_x as f64 + max_x as f64) / 2.0,
+                        (min_y as f64 + max_y as f64) / 2.0,
+                    ),
+                    None => (0.0, 0.0),
+                }
+            }
+            Some("custom") => (cx.value(), cy.value()),
+            _ => (0.0, 0.0),
+        };
+
+        let processor = match operation.active_id().as_deref() {
+            Some("rotate") => TransformProcessor::rotate(angle.value(), center),
+            Some("mirror") => {
+                let (axis, about) = if mirror_axis.active_id().as_deref() == Some("y") {
+                    (MirrorAxis::Y, center.1)
+                } else {
+                    (MirrorAxis::X, center.0)
+                };
+                TransformProcessor::mirror(axis, about)
+            }
+            Some("scale") => TransformProcessor::scale(sx.value(), sy.value(), sz.value(), center),
+            _ => TransformProcessor::translate(dx.value(), dy.value(), dz.value()),
+        };
+
+        match processor.apply_to_program(&gcode) {
+            Ok(transformed) => {
+                editor.set_text(&transformed);
+                window_apply.close();
+            }
+            Err(e) => {
+                file_dialog::show_error_dialog(&t!("Transform G-code"), &e, Some(&window_apply))
+            }
+        }
+    });
+
+    let window_cancel = window.clone();
+    cancel_btn.connect_clicked(move |_| window_cancel.close());
+
+    window.present();
+}
@@ -0,0 +1,97 @@
+//! Export Job Report action.
+//!
+//! Writes an HTML, JSON or CSV analysis of the program loaded in the editor,
+//! including the G-code validator's findings, for quotes and job travellers.
+
+use super::editor::GcodeEditor;
+use super::file_dialog;
+use crate::t;
+use gcodekit5_camtools::validator::{GCodeValidator, ValidatorConfig};
+use gcodekit5_visualizer::{
+    JobReport, ReportFormat, ReportOptions, ValidationIssue, ValidationSeverity,
+};
+use gtk4::prelude::*;
+use gtk4::{FileFilter, ResponseType, Window};
+use std::path::Path;
+
+/// Validator findings for `gcode` as report warnings
+fn validator_issues(gcode: &str) -> Vec<ValidationIssue> {
+    let lines: Vec<String> = gcode.lines().map(str::to_string).collect();
+    match GCodeValidator::new(ValidatorConfig::default()).validate(&lines) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .into_iter()
+            .map(|e| {
+                ValidationIssue::new(e.line as u32 + 1, ValidationSeverity::Warning, e.message)
+            })
+            .collect(),
+    }
+}
+
+/// Ask where to save a report for the editor program, then write it
+pub fn export_job_report(parent: Option<&Window>, editor: &GcodeEditor) {
+    let gcode = editor.get_text();
+    if gcode.trim().is_empty() {
+        file_dialog::show_error_dialog(
+            &t!("Export Job Report"),
+            &t!("There is no G-code to report on."),
+            parent,
+        );
+        return;
+    }
+
+    let name = editor
+        .current_file()
+        .and_then(|path| path.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| t!("Untitled"));
+    let stem = Path::new(&name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| name.clone());
+
+    let dialog = file_dialog::save_dialog(&t!("Export Job Report"), parent);
+    let filters: Vec<(FileFilter, ReportFormat)> = [
+        (t!("HTML Report (*.html)"), ReportFormat::Html),
+        (t!("JSON Data (*.json)"), ReportFormat::Json),
+        (t!("CSV Tables (*.csv)"), ReportFormat::Csv),
+    ]
+    .into_iter()
+    .map(|(label, format)| {
+        let filter = FileFilter::new();
+        filter.set_name(Some(&label));
+        filter.add_pattern(&format!("*.{}", format.extension()));
+        dialog.add_filter(&filter);
+        (filter, format)
+    })
+    .collect();
+    dialog.set_current_name(&format!("{}-report.html", stem));
+
+    let window = parent.cloned();
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            if let Some(mut path) = dialog.file().and_then(|f| f.path()) {
+                // Use the selected filter's format when no extension was typed
+                if ReportFormat::from_path(&path).is_none() {
+                    let format = dialog
+                        .filter()
+                        .and_then(|selected| filters.iter().find(|(f, _)| *f == selected))
+                        .map_or(ReportFormat::Html, |(_, format)| *format);
+                    path.set_extension(format.extension());
+                }
+
+                let result = JobReport::generate(name.as_str(), &gcode, &ReportOptions::default())
+                    .with_issues(validator_issues(&gcode))
+                    .save(&path);
+                if let Err(e) = result {
+                    file_dialog::show_error_dialog(
+                        &t!("Export Job Report"),
+                        &format!("{:#}", e),
+                        window.as_ref(),
+                    );
+                }
+            }
+        }
+        dialog.destroy();
+    });
+    dialog.show();
+}
@@ -0,0 +1,81 @@
+//! Parametric jobs expanded while they stream
+//!
+//! Programs that read the probe result or the current position cannot be
+//! expanded up front. A [`LiveJob`] expands them one line at a time as the
+//! controller acknowledges each command, so a probe result reported with
+//! `[PRB:...]` is known before the lines that use it are expanded.
+
+use std::collections::VecDeque;
+
+use gcodekit5_visualizer::{
+    ExpansionStep, GcodeCommand, GcodeState, ParametricExpander, ProcessorPipeline,
+};
+
+pub struct LiveJob {
+    expander: ParametricExpander,
+    /// Laser and backlash processing applied to each expanded line
+    pipeline: ProcessorPipeline,
+    state: GcodeState,
+    finished: bool,
+}
+
+impl LiveJob {
+    pub fn new(expander: ParametricExpander, pipeline: ProcessorPipeline) -> Self {
+        Self {
+            expander,
+            pipeline,
+            state: GcodeState::new(),
+            finished: false,
+        }
+    }
+
+    /// Queue expanded lines until at least one is ready to send, the
+    /// program waits for a probe result or it ends
+    ///
+    /// Returns the number of lines queued.
+    pub fn refill(&mut self, queue: &mut VecDeque<String>) -> Result<usize, String> {
+        let before = queue.len();
+        while queue.len() == before && !self.finished {
+            match self.expander.next_step().map_err(|e| e.to_string())? {
+                ExpansionStep::Line(line) => {
+                    let processed = self
+                        .pipeline
+                        .process_command(&GcodeCommand::new(&line.text), &self.state)
+                        .map_err(|e| format!("Line {}: {}", line.source_line, e))?;
+                    push_commands(queue, processed);
+                }
+                ExpansionStep::WaitingForProbe => {
+                    // Release anything held back so the probe move is sent
+                    let flushed = self.pipeline.finish(&self.state)?;
+                    push_commands(queue, flushed);
+                    break;
+                }
+                ExpansionStep::Finished => {
+                    let flushed = self.pipeline.finish(&self.state)?;
+                    push_commands(queue, flushed);
+                    self.finished = true;
+                }
+            }
+        }
+        Ok(queue.len() - before)
+    }
+
+    /// Record a probe result, in work coordinates
+    pub fn record_probe(&mut self, position: [f64; 3], success: bool) {
+        self.expander.record_probe(position, success);
+    }
+
+    /// Whether the job stopped for a probe result that was never reported
+    pub fn is_waiting_for_probe(&self) -> bool {
+        !self.finished && self.expander.is_waiting_for_probe()
+    }
+}
+
+fn push_commands(queue: &mut VecDeque<String>, commands: Vec<GcodeCommand>) {
+    for command in commands {
+        let line = command.command.trim();
+        if !line.is_empty() && !line.starts_with(';') && !line.starts_with('(') {
+            queue.push_back(line.to_string());
+        }
+    }
+}
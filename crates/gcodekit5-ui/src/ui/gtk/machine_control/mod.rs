@@ -1,8 +1,10 @@
 #![allow(deprecated)]
 
+use gcodekit5_camtools::advanced_features::{ProbeResult as CamProbeResult, ProbingSystem};
 use gcodekit5_communication::firmware::grbl::status_parser::{
     FeedSpindleState, OverrideState, StatusParser,
 };
+use gcodekit5_communication::firmware::grbl::{GrblResponse, GrblResponseParser};
 use gcodekit5_communication::{
     Communicator, ConnectionDriver, ConnectionParams, SerialCommunicator,
 };
@@ -154,6 +156,10 @@ pub struct MachineControlView {
     pub current_units: ThreadSafe<MeasurementSystem>,
     pub last_overrides: ThreadSafe<OverrideState>,
     pub job_start_time: ThreadSafeOption<std::time::Instant>,
+    /// Results of `G38.x` probing cycles, in the order they were reported
+    pub probing: ThreadSafe<ProbingSystem>,
+    /// Parametric job that reads machine state, expanded as it streams
+    pub live_job: ThreadSafeOption<LiveJob>,
 }
 
 impl MachineControlView {
@@ -1158,6 +1164,8 @@ impl MachineControlView {
                 spindle: 100,
             }),
             job_start_time: thread_safe_none(),
+            probing: thread_safe(ProbingSystem::new()),
+            live_job: thread_safe_none(),
         };
 
         // Keep internal jog values in base units (mm, mm/min)
@@ -1401,6 +1409,7 @@ impl MachineControlView {
             let send_queue = view.send_queue.clone();
             let status_bar = view.status_bar.clone();
             let job_start_time = view.job_start_time.clone();
+            let live_job = view.live_job.clone();
             let console = view.device_console.clone();
             view.stop_btn.connect_clicked(move |_| {
                 if let Some(c) = console.as_ref() {
@@ -1416,6 +1425,7 @@ impl MachineControlView {
                 *waiting_for_ack.lock() = false;
                 *job_start_time.lock() = None;
                 send_queue.lock().clear();
+                *live_job.lock() = None;
 
                 // Reset progress
                 if let Some(sb) = status_bar.as_ref() {
@@ -1904,6 +1914,8 @@ impl MachineControlView {
                             let last_overrides_poll = view_clone.last_overrides.clone();
                             let widget_poll = view_clone.widget.clone();
                             let job_start_time_poll = view_clone.job_start_time.clone();
+                            let probing_poll = view_clone.probing.clone();
+                            let live_job_poll = view_clone.live_job.clone();
 
                             let mut query_counter = 0u32;
                             let mut response_buffer = String::new();
@@ -1971,6 +1983,24 @@ impl MachineControlView {
                                                     }
                                                 }
 
+                                                // Record probe cycle results ([PRB:x,y,z:1])
+                                                if line.starts_with("[PRB:") {
+                                                    if let Some(GrblResponse::ProbeResult { position, success }) = GrblResponseParser::new().parse(&line) {
+                                                        let result = if success {
+                                                            CamProbeResult::success(position.z as f32)
+                                                        } else {
+                                                            CamProbeResult::failure(t!("Probe did not make contact"))
+                                                        };
+                                                        probing_poll.lock().add_result(result);
+
+                                                        // Probe results are reported in machine coordinates
+                                                        if let Some(job) = live_job_poll.lock().as_mut() {
+                                                            let (wx, wy, wz) = last_wco.map(|w| (w.x, w.y, w.z)).unwrap_or_default();
+                                                            job.record_probe([position.x - wx, position.y - wy, position.z - wz], success);
+                                                        }
+                                                    }
+                                                }
+
                                                 // Handle 'ok' or 'error' for streaming
                                                 let is_ack = line == "ok";
                                                 let lower = line.to_ascii_lowercase();
@@ -2028,6 +2058,30 @@ impl MachineControlView {
                                                      if *is_streaming_poll.lock()
                                                          && !*is_paused_poll.lock() {
                                                               let mut queue = send_queue_poll.lock();
+
+                                                              // Expand more of a live parametric job
+                                                              if queue.is_empty() {
+                                                                  let mut live_job = live_job_poll.lock();
+                                                                  if let Some(job) = live_job.as_mut() {
+                                                                      let error = match job.refill(&mut queue) {
+                                                                          Ok(_) if job.is_waiting_for_probe() && queue.is_empty() => {
+                                                                              Some(t!("No probe result was reported"))
+                                                                          }
+                                                                          Ok(added) => {
+                                                                              *total_lines_poll.lock() += added;
+                                                                              None
+                                                                          }
+                                                                          Err(e) => Some(e),
+                                                                      };
+                                                                      if let Some(e) = error {
+                                                                          if let Some(c) = device_console_poll.as_ref() {
+                                                                              c.append_log(&format!("{} {}\n", t!("Parametric expansion stopped:"), e));
+                                                                          }
+                                                                          *live_job = None;
+                                                                      }
+                                                                  }
+                                                              }
+
                                                               let total_lines_val = *total_lines_poll.lock();
                                                               let remaining = queue.len();
                                                               let sent = total_lines_val - remaining;
@@ -2078,6 +2132,7 @@ impl MachineControlView {
                                                               } else {
                                                                    // Done streaming
                                                                    *is_streaming_poll.lock() = false;
+                                                                   *live_job_poll.lock() = None;
                                                                    *is_paused_poll.lock() = false;
 
                                                                    // Don't clear job_start_time yet - wait for machine to be Idle
@@ -2412,5 +2467,8 @@ impl MachineControlView {
     }
 }
 
+mod live_job;
 mod operations;
 mod overrides;
+
+use live_job::LiveJob;
@@ -2,6 +2,10 @@
 #![allow(deprecated)]
 
 use super::*;
+use gcodekit5_visualizer::{
+    BacklashCompensator, CommandProcessor, ParametricExpander, ParametricProgram, ProcessorPipeline,
+};
+use std::sync::Arc;
 
 impl MachineControlView {
     pub fn refresh_ports(&self) {
@@ -28,10 +32,168 @@ impl MachineControlView {
         *self.jog_step_mm.lock() as f64
     }
 
+    /// Stream a job, applying the active device's laser processing and
+    /// compensating its backlash
     pub fn start_job(&self, content: &str) {
         if *self.is_streaming.lock() {
             return;
         }
+        let log = |message: String| {
+            if let Some(c) = self.device_console.as_ref() {
+                c.append_log(&message);
+            }
+        };
+
+        let mut program = std::borrow::Cow::Borrowed(content);
+        if ParametricProgram::is_parametric(&program) {
+            let parsed = match ParametricProgram::parse(&program) {
+                Ok(parsed) => parsed,
+                Err(errors) => {
+                    log(format!(
+                        "{}\n",
+                        t!("Job not started, the parametric program has errors:")
+                    ));
+                    for e in errors {
+                        log(format!("{}\n", e));
+                    }
+                    return;
+                }
+            };
+            if parsed.reads_machine_state() {
+                // Probe results and positions are only known while running
+                self.start_live_job(parsed);
+                return;
+            }
+            match ParametricExpander::new(parsed).expand_all() {
+                Ok(lines) => {
+                    log(format!(
+                        "{} {}\n",
+                        t!("Parametric expansion: lines generated:"),
+                        lines.len()
+                    ));
+                    let expanded: Vec<String> = lines.into_iter().map(|l| l.text).collect();
+                    program = std::borrow::Cow::Owned(expanded.join("\n"));
+                }
+                Err(e) => {
+                    log(format!(
+                        "{} {}\n",
+                        t!("Job not started, parametric expansion failed:"),
+                        e
+                    ));
+                    return;
+                }
+            }
+        }
+
+        if let Some(laser) = device_status::get_active_laser() {
+            match laser.apply_to_program(&program) {
+                Ok(processed) => {
+                    let names: Vec<String> = laser
+                        .processors()
+                        .iter()
+                        .map(|p| p.name().to_string())
+                        .collect();
+                    log(format!(
+                        "{} {}\n",
+                        t!("Laser processing:"),
+                        names.join(", ")
+                    ));
+                    program = std::borrow::Cow::Owned(processed);
+                }
+                Err(e) => {
+                    log(format!(
+                        "{} {}\n",
+                        t!("Job not started, laser processing failed:"),
+                        e
+                    ));
+                    return;
+                }
+            }
+        }
+
+        let backlash = device_status::get_active_backlash();
+        if backlash.is_set() {
+            let compensator = BacklashCompensator::new(backlash.x, backlash.y, backlash.z);
+            match compensator.apply_to_program(&program) {
+                Ok(compensated) => {
+                    log(format!(
+                        "{} {}\n",
+                        t!("Backlash compensation: take-up moves inserted:"),
+                        compensator.moves_inserted()
+                    ));
+                    program = std::borrow::Cow::Owned(compensated);
+                }
+                Err(e) => {
+                    log(format!(
+                        "{} {}\n",
+                        t!("Job not started, backlash compensation failed:"),
+                        e
+                    ));
+                    return;
+                }
+            }
+        }
+        self.stream_job(&program);
+    }
+
+    /// Stream a parametric job that reads machine state, expanding it as the
+    /// controller acknowledges each line
+    fn start_live_job(&self, program: ParametricProgram) {
+        let log = |message: String| {
+            if let Some(c) = self.device_console.as_ref() {
+                c.append_log(&message);
+            }
+        };
+
+        let mut pipeline = ProcessorPipeline::new();
+        if let Some(laser) = device_status::get_active_laser() {
+            pipeline.register_all(laser.processors());
+        }
+        let backlash = device_status::get_active_backlash();
+        if backlash.is_set() {
+            pipeline.register(Arc::new(BacklashCompensator::new(
+                backlash.x, backlash.y, backlash.z,
+            )));
+        }
+        let names: Vec<&str> = pipeline
+            .list_processors()
+            .into_iter()
+            .map(|(name, _, _)| name)
+            .collect();
+        log(format!(
+            "{} {}\n",
+            t!("Parametric expansion: expanding while streaming, processing:"),
+            names.join(", ")
+        ));
+
+        let mut expander = ParametricExpander::live(program);
+        if let Some(wpos) = device_status::get_status().work_position {
+            expander.set_position([wpos.x, wpos.y, wpos.z]);
+        }
+        let mut job = LiveJob::new(expander, pipeline);
+        let mut first = std::collections::VecDeque::new();
+        if let Err(e) = job.refill(&mut first) {
+            log(format!(
+                "{} {}\n",
+                t!("Job not started, parametric expansion failed:"),
+                e
+            ));
+            return;
+        }
+        if first.is_empty() {
+            log(format!("{}\n", t!("No valid G-Code lines found.")));
+            return;
+        }
+        *self.live_job.lock() = Some(job);
+        let first: Vec<String> = first.into_iter().collect();
+        self.stream_job(&first.join("\n"));
+    }
+
+    /// Stream a job as written, without backlash compensation
+    pub fn stream_job(&self, content: &str) {
+        if *self.is_streaming.lock() {
+            return;
+        }
 
         let lines: Vec<String> = content
             .lines()
@@ -124,6 +286,7 @@ impl MachineControlView {
         *self.waiting_for_ack.lock() = false;
         *self.job_start_time.lock() = None;
         self.send_queue.lock().clear();
+        *self.live_job.lock() = None;
 
         // Reset progress
         if let Some(sb) = self.status_bar.as_ref() {
@@ -1,5 +1,7 @@
 #![allow(deprecated)]
 
+pub mod auto_level;
+pub mod backlash_wizard;
 pub mod cam_tools;
 pub mod command_history;
 pub mod designer;
@@ -12,15 +14,20 @@ pub mod device_manager;
 pub mod editor;
 pub mod fast_shape_gallery;
 pub mod file_dialog;
+pub mod gcode_compare;
+pub mod gcode_transform;
 pub mod help_browser;
+pub mod job_report;
 pub mod machine_control;
 pub mod nav_cube;
 pub mod osd_format;
+pub mod recent_files;
 pub mod renderer_3d;
 pub mod settings;
 pub mod shaders;
 pub mod status_bar;
 pub mod stock_texture;
+pub mod thumbnails;
 pub mod visualizer;
 //pub mod designer_file_ops; // Temporarily disabled - needs shape struct updates
 pub mod config_settings;
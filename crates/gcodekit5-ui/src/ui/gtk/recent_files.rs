@@ -0,0 +1,161 @@
+//! Recent files dialog with toolpath thumbnails.
+
+use super::thumbnails;
+use crate::t;
+use gtk4::prelude::*;
+use gtk4::{
+    glib, Align, Box, Button, Label, ListBox, Orientation, Picture, PolicyType, ScrolledWindow,
+    SelectionMode, Window,
+};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Show the recent files list; `on_open` is called with the chosen file.
+///
+/// Cached thumbnails are shown straight away, missing or stale ones are
+/// rendered in the background.
+pub fn show_recent_files_dialog<F: Fn(&Path) + 'static>(
+    parent: Option<&Window>,
+    files: &[PathBuf],
+    on_open: F,
+) {
+    let window = Window::builder()
+        .title(t!("Open Recent"))
+        .modal(true)
+        .default_width(520)
+        .default_height(600)
+        .build();
+    if let Some(parent) = parent {
+        window.set_transient_for(Some(parent));
+    }
+
+    let content = Box::new(Orientation::Vertical, 6);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let list = ListBox::new();
+    list.set_selection_mode(SelectionMode::Single);
+    list.add_css_class("boxed-list");
+
+    let files: Vec<PathBuf> = files.iter().filter(|p| p.exists()).cloned().collect();
+    let mut pending = Vec::new();
+    for path in &files {
+        let row = Box::new(Orientation::Horizontal, 12);
+        row.set_margin_top(6);
+        row.set_margin_bottom(6);
+        row.set_margin_start(6);
+        row.set_margin_end(6);
+
+        let picture = Picture::new();
+        let size = thumbnails::THUMBNAIL_SIZE as i32 / 2;
+        picture.set_size_request(size, size);
+        match thumbnails::load_texture(path) {
+            Some(texture) => picture.set_paintable(Some(&texture)),
+            None => pending.push((path.clone(), picture.clone())),
+        }
+        row.append(&picture);
+
+        let text = Box::new(Orientation::Vertical, 2);
+        text.set_valign(Align::Center);
+        let name = Label::new(path.file_name().and_then(|n| n.to_str()));
+        name.set_halign(Align::Start);
+        name.add_css_class("heading");
+        let dir = Label::new(path.parent().and_then(|p| p.to_str()));
+        dir.set_halign(Align::Start);
+        dir.add_css_class("dim-label");
+        dir.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+        text.append(&name);
+        text.append(&dir);
+        row.append(&text);
+
+        list.append(&row);
+    }
+
+    if files.is_empty() {
+        let empty = Label::new(Some(&t!("No recent files")));
+        empty.add_css_class("dim-label");
+        empty.set_margin_top(24);
+        empty.set_margin_bottom(24);
+        list.set_placeholder(Some(&empty));
+    }
+
+    let scrolled = ScrolledWindow::builder()
+        .hscrollbar_policy(PolicyType::Never)
+        .vexpand(true)
+        .child(&list)
+        .build();
+    content.append(&scrolled);
+
+    let buttons = Box::new(Orientation::Horizontal, 6);
+    buttons.set_halign(Align::End);
+    let cancel = Button::with_label(&t!("Cancel"));
+    let open = Button::with_label(&t!("Open"));
+    open.add_css_class("suggested-action");
+    open.set_sensitive(false);
+    buttons.append(&cancel);
+    buttons.append(&open);
+    content.append(&buttons);
+    window.set_child(Some(&content));
+
+    let on_open = Rc::new(on_open);
+    let files = Rc::new(files);
+
+    let open_clone = open.clone();
+    list.connect_row_selected(move |_, row| open_clone.set_sensitive(row.is_some()));
+
+    let window_clone = window.clone();
+    let files_clone = files.clone();
+    let on_open_clone = on_open.clone();
+    list.connect_row_activated(move |_, row| {
+        if let Some(path) = files_clone.get(row.index() as usize) {
+            window_clone.close();
+            on_open_clone(path);
+        }
+    });
+
+    let window_clone = window.clone();
+    let list_clone = list.clone();
+    open.connect_clicked(move |_| {
+        let selected = list_clone.selected_row().map(|row| row.index() as usize);
+        if let Some(path) = selected.and_then(|i| files.get(i)) {
+            window_clone.close();
+            on_open(path);
+        }
+    });
+
+    let window_clone = window.clone();
+    cancel.connect_clicked(move |_| window_clone.close());
+
+    // Render missing thumbnails off the main thread
+    if !pending.is_empty() {
+        let paths: Vec<PathBuf> = pending.iter().map(|(p, _)| p.clone()).collect();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for (index, path) in paths.iter().enumerate() {
+                if thumbnails::ensure_thumbnail(path).is_some() && sender.send(index).is_err() {
+                    break;
+                }
+            }
+        });
+
+        glib::timeout_add_local(Duration::from_millis(100), move || loop {
+            match receiver.try_recv() {
+                Ok(index) => {
+                    let (path, picture) = &pending[index];
+                    if let Some(texture) = thumbnails::load_texture(path) {
+                        picture.set_paintable(Some(&texture));
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => break glib::ControlFlow::Continue,
+                Err(mpsc::TryRecvError::Disconnected) => break glib::ControlFlow::Break,
+            }
+        });
+    }
+
+    window.present();
+}
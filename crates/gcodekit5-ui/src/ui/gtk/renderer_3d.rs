@@ -176,6 +176,9 @@ impl Drop for RenderBuffers {
     }
 }
 
+/// Zoom used to pick the level of detail for 3D vertex data
+const VERTEX_PIXELS_PER_MM: f32 = 500.0;
+
 pub fn generate_vertex_data(visualizer: &Visualizer) -> (Vec<f32>, Vec<f32>) {
     let mut rapid_vertices = Vec::new();
     let mut cut_vertices = Vec::new();
@@ -185,15 +188,19 @@ pub fn generate_vertex_data(visualizer: &Visualizer) -> (Vec<f32>, Vec<f32>) {
     let cut_color = [1.0, 1.0, 0.0, 1.0]; // Yellow (matches 2D)
     let arc_color = [1.0, 1.0, 0.0, 1.0]; // Yellow (matches 2D)
 
-    for cmd in visualizer.commands() {
+    // Vertex data is uploaded once and viewed at any zoom, so only use the
+    // visually lossless level that merges collinear moves, minus whatever
+    // the line/Z filter hides
+    let toolpath = visualizer.lod_for_scale(VERTEX_PIXELS_PER_MM);
+    for cmd in visualizer.filter().iter(toolpath) {
         match cmd {
             GCodeCommand::Move {
                 from, to, rapid, ..
             } => {
-                if *rapid {
-                    push_line(&mut rapid_vertices, from, to, rapid_color);
+                if rapid {
+                    push_line(&mut rapid_vertices, &from, &to, rapid_color);
                 } else {
-                    push_line(&mut cut_vertices, from, to, cut_color);
+                    push_line(&mut cut_vertices, &from, &to, cut_color);
                 }
             }
             GCodeCommand::Arc {
@@ -203,7 +210,7 @@ pub fn generate_vertex_data(visualizer: &Visualizer) -> (Vec<f32>, Vec<f32>) {
                 clockwise,
                 ..
             } => {
-                push_arc(&mut cut_vertices, from, to, center, *clockwise, arc_color);
+                push_arc(&mut cut_vertices, &from, &to, &center, clockwise, arc_color);
             }
             GCodeCommand::Dwell { .. } => {
                 // Ignore dwell for now in 3D
@@ -289,6 +296,35 @@ pub fn generate_tool_marker_data() -> Vec<f32> {
     vertices
 }
 
+/// Red crosses along each axis marking collision check results
+pub fn generate_collision_marker_data(positions: &[(f32, f32, f32)], size: f32) -> Vec<f32> {
+    let mut vertices = Vec::new();
+    let color = [1.0, 0.15, 0.15, 1.0];
+
+    for &(x, y, z) in positions {
+        push_line(
+            &mut vertices,
+            &Point3D::new(x - size, y, z),
+            &Point3D::new(x + size, y, z),
+            color,
+        );
+        push_line(
+            &mut vertices,
+            &Point3D::new(x, y - size, z),
+            &Point3D::new(x, y + size, z),
+            color,
+        );
+        push_line(
+            &mut vertices,
+            &Point3D::new(x, y, z - size),
+            &Point3D::new(x, y, z + size),
+            color,
+        );
+    }
+
+    vertices
+}
+
 fn push_triangle(
     vertices: &mut Vec<f32>,
     p1: &Point3D,
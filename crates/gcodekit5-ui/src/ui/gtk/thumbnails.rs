@@ -0,0 +1,109 @@
+//! G-code file thumbnails.
+//!
+//! Thumbnails are written to the freedesktop thumbnail cache
+//! (`~/.cache/thumbnails/normal`) so file managers and the GTK file chooser
+//! can show them too, and are reused by the recent files dialog.
+
+use gcodekit5_visualizer::{PreviewOptions, PreviewRenderer};
+use gtk4::{gdk, gio, glib};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::warn;
+
+/// Edge length of "normal" freedesktop thumbnails
+pub const THUMBNAIL_SIZE: u32 = 128;
+
+/// Location of the cached thumbnail for a file
+pub fn thumbnail_path(file: &Path) -> PathBuf {
+    let uri = gio::File::for_path(file).uri();
+    let hash = glib::compute_checksum_for_string(glib::ChecksumType::Md5, &uri)
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    glib::user_cache_dir()
+        .join("thumbnails")
+        .join("normal")
+        .join(format!("{hash}.png"))
+}
+
+fn modified_secs(file: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(file).ok()?.modified().ok()?;
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+/// Render the thumbnail for a G-code program, replacing any cached copy.
+///
+/// Runs the parser and rasterizer, so call it off the GTK main thread for
+/// large files.
+pub fn write_thumbnail(file: &Path, gcode: &str) -> Option<PathBuf> {
+    let uri = gio::File::for_path(file).uri().to_string();
+    let mtime = modified_secs(file)?.to_string();
+    let renderer = PreviewRenderer::new(PreviewOptions::thumbnail(THUMBNAIL_SIZE));
+
+    let mut visualizer = gcodekit5_visualizer::Visualizer::new();
+    visualizer.parse_gcode(gcode);
+    let png = match renderer.render_png(
+        visualizer.commands(),
+        &[
+            ("Thumb::URI", uri.as_str()),
+            ("Thumb::MTime", mtime.as_str()),
+            ("Software", "GCodeKit5"),
+        ],
+    ) {
+        Ok(png) => png,
+        Err(e) => {
+            warn!("Could not render thumbnail for {}: {}", file.display(), e);
+            return None;
+        }
+    };
+
+    let target = thumbnail_path(file);
+    let dir = target.parent()?;
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        warn!("Could not create {}: {}", dir.display(), e);
+        return None;
+    }
+    // Write to a temporary name first so readers never see a partial file.
+    // The name follows the target so files thumbnailed at the same time
+    // never share it.
+    let tmp = target.with_extension(format!("{}.tmp", std::process::id()));
+    if let Err(e) = std::fs::write(&tmp, png).and_then(|_| std::fs::rename(&tmp, &target)) {
+        warn!("Could not write thumbnail {}: {}", target.display(), e);
+        let _ = std::fs::remove_file(&tmp);
+        return None;
+    }
+    Some(target)
+}
+
+/// Return a thumbnail that matches the file's modification time, rendering
+/// a new one if needed.
+pub fn ensure_thumbnail(file: &Path) -> Option<PathBuf> {
+    let target = thumbnail_path(file);
+    if is_current(&target, file) {
+        return Some(target);
+    }
+    let gcode = std::fs::read_to_string(file).ok()?;
+    write_thumbnail(file, &gcode)
+}
+
+/// The cached thumbnail exists and is at least as new as the file
+fn is_current(thumbnail: &Path, file: &Path) -> bool {
+    match (
+        std::fs::metadata(thumbnail).and_then(|m| m.modified()),
+        std::fs::metadata(file).and_then(|m| m.modified()),
+    ) {
+        (Ok(thumb), Ok(source)) => thumb >= source,
+        _ => false,
+    }
+}
+
+/// Load a cached thumbnail as a texture, if one is up to date
+pub fn load_texture(file: &Path) -> Option<gdk::Texture> {
+    let target = thumbnail_path(file);
+    if !is_current(&target, file) {
+        return None;
+    }
+    gdk::Texture::from_filename(&target).ok()
+}
@@ -1,17 +1,25 @@
 mod gl_loader;
 mod interaction;
 mod rendering;
+mod scrubbing;
+mod stock_analysis;
 
 use gcodekit5_core::constants as core_constants;
+use gcodekit5_core::data::tools::{Tool, ToolProfile};
 use gcodekit5_designer::stock_removal::{SimulationResult, StockMaterial};
-use gcodekit5_devicedb::DeviceManager;
-use gcodekit5_visualizer::visualizer::GCodeCommand;
-use gcodekit5_visualizer::{Camera3D, Visualizer};
+use gcodekit5_devicedb::{AxisLimits, DeviceManager};
+use gcodekit5_visualizer::visualizer::DEFAULT_RAPID_RATE_MM_MIN;
+use gcodekit5_visualizer::visualizer::{GCodeCommand, MachineLimits, Vector3};
+use gcodekit5_visualizer::{
+    Camera3D, CollisionDetector, CollisionIssue, CollisionSettings, ParseEvent, ParseJob,
+    ToolpathDiff, Visualizer,
+};
 // use gcodekit5_designer::stock_removal::visualization::generate_2d_contours;
 use crate::t;
 use crate::ui::gtk::osd_format::format_zoom_center_cursor;
 use crate::ui::gtk::shaders::StockRemovalShaderProgram;
 use crate::ui::gtk::status_bar::StatusBar;
+use crate::ui::tools_manager_backend::ToolsManagerBackend;
 use gcodekit5_settings::controller::SettingsController;
 use gcodekit5_settings::manager::SettingsManager;
 use gcodekit5_visualizer::visualizer::{generate_surface_mesh, StockSimulator3D};
@@ -31,17 +39,19 @@ pub(crate) struct StockRemovalVisualization {
 }
 use crate::ui::gtk::nav_cube::NavCube;
 use crate::ui::gtk::renderer_3d::{
-    generate_axis_data, generate_bounds_data, generate_grid_data, generate_tool_marker_data,
-    generate_vertex_data, RenderBuffers,
+    generate_axis_data, generate_bounds_data, generate_collision_marker_data, generate_grid_data,
+    generate_tool_marker_data, generate_vertex_data, RenderBuffers,
 };
 use crate::ui::gtk::shaders::ShaderProgram;
 use glow::HasContext;
 use gtk4::gdk::Key;
 use gtk4::prelude::*;
 use gtk4::{EventControllerKey, GestureClick, Popover, Separator};
-use tracing::debug;
+use tracing::{debug, warn};
 
 use gl_loader::load_gl_func;
+use scrubbing::{FilterControls, TimelineBar};
+use stock_analysis::StockAnalysisControls;
 
 use gcodekit5_core::{shared, shared_none, thread_safe_none, Shared, SharedOption};
 use gtk4::prelude::{BoxExt, ButtonExt, CheckButtonExt, WidgetExt};
@@ -55,6 +65,9 @@ use gtk4::{
 use std::rc::Rc;
 use std::sync::Arc;
 
+/// G-code larger than this is parsed on a worker thread
+const BACKGROUND_PARSE_THRESHOLD_BYTES: usize = 4 * 1024 * 1024;
+
 // Phase 4: Render cache for expensive computations
 #[derive(Clone)]
 pub(crate) struct RenderCache {
@@ -76,6 +89,7 @@ pub(crate) struct RendererState {
     bounds_buffers: RenderBuffers,
     stock_removal_shader: Option<StockRemovalShaderProgram>,
     stock_removal_buffers: Option<RenderBuffers>,
+    collision_buffers: Option<RenderBuffers>,
 }
 
 impl Default for RenderCache {
@@ -121,9 +135,19 @@ pub struct GcodeVisualizer {
     pub(crate) _simulation_visualization: SharedOption<StockRemovalVisualization>,
     pub(crate) _simulation_resolution: Shared<f32>,
     pub(crate) _simulation_running: Shared<bool>,
+    // Cutter used by stock removal: a library tool, or a flat end mill of the entered diameter
+    pub(crate) stock_tool_profile: SharedOption<ToolProfile>,
+    pub(crate) stock_tool_diameter: Shared<f32>,
+    // Problems found while simulating stock removal, and who to tell about them
+    pub(crate) collision_issues: Shared<Vec<CollisionIssue>>,
+    pub(crate) on_collision_issues: SharedOption<std::boxed::Box<dyn Fn(&[CollisionIssue])>>,
+    // Geometric comparison drawn over the toolpath
+    pub(crate) comparison: SharedOption<ToolpathDiff>,
     // Stock removal simulation (3D)
     pub(crate) _stock_simulator_3d: SharedOption<StockSimulator3D>,
     pub(crate) _stock_simulation_3d_pending: Shared<bool>,
+    // STL export and deviation from a reference model
+    pub(crate) _stock_analysis: Rc<StockAnalysisControls>,
     // Scrollbars
     pub(crate) hadjustment: Adjustment,
     pub(crate) vadjustment: Adjustment,
@@ -141,6 +165,11 @@ pub struct GcodeVisualizer {
     #[allow(dead_code)]
     pub(crate) status_bar: Option<StatusBar>,
     pub(crate) current_pos: Shared<(f32, f32, f32)>,
+    // Background parse of large G-code, cancelled when replaced
+    pub(crate) parse_job: SharedOption<ParseJob>,
+    // Line/Z filter and timeline scrubbing
+    pub(crate) filter_controls: Rc<FilterControls>,
+    pub(crate) timeline_bar: Rc<TimelineBar>,
 }
 
 impl GcodeVisualizer {
@@ -218,6 +247,67 @@ impl GcodeVisualizer {
         }
     }
 
+    /// Axis limits of the active device profile; disabled axes are unbounded
+    fn machine_limits(device_manager: &Option<Arc<DeviceManager>>) -> Option<MachineLimits> {
+        let profile = device_manager.as_ref()?.get_active_profile()?;
+        let range = |axis: &AxisLimits| {
+            if axis.enabled {
+                (axis.min as f32, axis.max as f32)
+            } else {
+                (f32::MIN, f32::MAX)
+            }
+        };
+        let (x, y, z) = (
+            range(&profile.x_axis),
+            range(&profile.y_axis),
+            range(&profile.z_axis),
+        );
+        Some(MachineLimits::new(
+            Vector3::new(x.0, y.0, z.0),
+            Vector3::new(x.1, y.1, z.1),
+        ))
+    }
+
+    /// Called with the collision check results whenever the stock removal
+    /// simulation finishes or its results are cleared
+    pub fn set_on_collision_issues<F: Fn(&[CollisionIssue]) + 'static>(&self, f: F) {
+        *self.on_collision_issues.borrow_mut() = Some(std::boxed::Box::new(f));
+    }
+
+    /// Rapid rate of the active device profile, for timeline estimates
+    fn rapid_rate(device_manager: &Option<Arc<DeviceManager>>) -> f32 {
+        device_manager
+            .as_ref()
+            .and_then(|manager| manager.get_active_profile())
+            .map(|profile| profile.max_feed_rate as f32)
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or(DEFAULT_RAPID_RATE_MM_MIN)
+    }
+
+    /// Called with the source line under the timeline's tool marker
+    /// whenever it moves to another line
+    pub fn set_on_timeline_line<F: Fn(u32) + 'static>(&self, f: F) {
+        self.timeline_bar.set_on_line_changed(f);
+    }
+
+    /// Move the timeline's tool marker to where the job is once `line`
+    /// (1-based) has run
+    pub fn select_line(&self, line: u32) {
+        self.timeline_bar.select_line(line);
+    }
+
+    /// Problems found by the last stock removal simulation
+    pub fn collision_issues(&self) -> Vec<CollisionIssue> {
+        self.collision_issues.borrow().clone()
+    }
+
+    /// Show the other program of a comparison and its added and removed
+    /// cuts over the 2D toolpath, or clear them with `None`
+    pub fn set_comparison(&self, diff: Option<ToolpathDiff>) {
+        *self.comparison.borrow_mut() = diff;
+        self.drawing_area.queue_draw();
+    }
+
     pub fn new(
         device_manager: Option<Arc<DeviceManager>>,
         settings_controller: Rc<SettingsController>,
@@ -424,6 +514,28 @@ impl GcodeVisualizer {
             .text("3.175")
             .build();
 
+        // Tool library cutters for stock removal; the first entry uses the diameter above
+        let stock_tool_combo = ComboBoxText::new();
+        stock_tool_combo.set_tooltip_text(Some(&t!("Tool")));
+        stock_tool_combo.append(Some(""), &t!("Custom Diameter (Flat End Mill)"));
+        let mut stock_tools: Vec<Tool> = ToolsManagerBackend::new()
+            .get_all_tools()
+            .into_iter()
+            .cloned()
+            .collect();
+        stock_tools.sort_by(|a, b| a.name.cmp(&b.name));
+        for tool in &stock_tools {
+            stock_tool_combo.append(Some(&tool.id.0), &tool.name);
+        }
+        stock_tool_combo.set_active_id(Some(""));
+
+        // Holder or collet nut diameter used for the collision check
+        let stock_holder_diameter_entry = gtk4::Entry::builder()
+            .placeholder_text(t!("Holder Diameter"))
+            .tooltip_text(t!("Holder Diameter"))
+            .text("20.0")
+            .build();
+
         // Group toggles into sections
         let toolpath_box = Box::new(Orientation::Vertical, 6);
         toolpath_box.set_margin_start(6);
@@ -445,6 +557,10 @@ impl GcodeVisualizer {
             sidebar_list.append(&row);
         }
 
+        // Filled in once the canvases exist
+        let filter_row = ListBoxRow::new();
+        sidebar_list.append(&filter_row);
+
         let guides_box = Box::new(Orientation::Vertical, 4);
         guides_box.set_margin_start(6);
         guides_box.set_margin_end(6);
@@ -475,7 +591,9 @@ impl GcodeVisualizer {
         stock_box.append(&stock_width_entry);
         stock_box.append(&stock_height_entry);
         stock_box.append(&stock_thickness_entry);
+        stock_box.append(&stock_tool_combo);
         stock_box.append(&stock_tool_diameter_entry);
+        stock_box.append(&stock_holder_diameter_entry);
 
         let stock_revealer = Revealer::new();
         stock_revealer.set_transition_type(gtk4::RevealerTransitionType::SlideDown);
@@ -734,12 +852,38 @@ impl GcodeVisualizer {
         });
         let stock_material = shared(initial_stock);
         let tool_diameter = shared(3.175f32); // Default 1/8" end mill
+        let tool_profile: SharedOption<ToolProfile> = shared_none();
+        let stock_tool: SharedOption<Tool> = shared_none();
+        let holder_diameter = shared(20.0f32);
+        let collision_issues: Shared<Vec<CollisionIssue>> = shared(Vec::new());
+        let on_collision_issues: SharedOption<std::boxed::Box<dyn Fn(&[CollisionIssue])>> =
+            shared_none();
+        let comparison: SharedOption<ToolpathDiff> = shared_none();
         let simulation_result = shared_none();
         let simulation_visualization = shared_none();
         let simulation_resolution = shared(0.1);
         let simulation_running = shared(false);
         let stock_simulator_3d = shared_none();
         let stock_simulation_3d_pending = shared(false);
+        let stock_analysis = StockAnalysisControls::new(
+            stock_simulator_3d.clone(),
+            stock_material.clone(),
+            stock_simulation_3d_pending.clone(),
+            gl_area.clone(),
+        );
+        stock_box.append(&stock_analysis.widget);
+
+        // Line/Z filter and timeline scrubber
+        let filter_controls =
+            FilterControls::new(visualizer.clone(), drawing_area.clone(), gl_area.clone());
+        let filter_expander = Expander::builder()
+            .label(t!("Filter"))
+            .expanded(false)
+            .child(&filter_controls.widget)
+            .build();
+        filter_row.set_child(Some(&filter_expander));
+        let timeline_bar =
+            TimelineBar::new(visualizer.clone(), drawing_area.clone(), gl_area.clone());
 
         // Overlay for floating controls
         let overlay = Overlay::new();
@@ -944,7 +1088,10 @@ impl GcodeVisualizer {
         overlay.add_overlay(&sidebar_show_panel);
         overlay.add_overlay(&sim_panel);
 
-        container.set_end_child(Some(&overlay));
+        let canvas_box = Box::new(Orientation::Vertical, 0);
+        canvas_box.append(&overlay);
+        canvas_box.append(&timeline_bar.widget);
+        container.set_end_child(Some(&canvas_box));
 
         // Connect NavCube Fit Button
         let fit_btn_3d = nav_cube.fit_btn.clone();
@@ -1066,7 +1213,7 @@ impl GcodeVisualizer {
                 ));
 
                 units_badge.set_text(gcodekit5_core::units::get_unit_label(system));
-                empty_box.set_visible(v.commands().is_empty());
+                empty_box.set_visible(v.get_command_count() == 0);
             }
         });
 
@@ -1742,8 +1889,11 @@ impl GcodeVisualizer {
         let simulation_result_draw = simulation_result.clone();
         let simulation_visualization_draw = simulation_visualization.clone();
         let stock_material_draw = stock_material.clone();
+        let collision_issues_draw = collision_issues.clone();
+        let comparison_draw = comparison.clone();
         let device_manager_draw = device_manager.clone();
         let current_pos_draw = current_pos.clone();
+        let scrub_time_draw = timeline_bar.scrub_time.clone();
         let grid_spacing_draw = grid_spacing_mm.clone();
         let settings_draw = settings_controller.clone();
 
@@ -1751,6 +1901,7 @@ impl GcodeVisualizer {
             let vis = vis_draw.borrow();
             let mut cache = render_cache_draw.borrow_mut();
             let pos = *current_pos_draw.borrow();
+            let scrub_pos = scrub_time_draw.borrow().map(|t| vis.position_at_time(t));
             let style = da.style_context();
             let config = settings_draw.persistence.borrow();
             let grid_major_width = config.config().ui.grid_major_line_width;
@@ -1772,7 +1923,10 @@ impl GcodeVisualizer {
                 &simulation_result_draw.borrow(),
                 &simulation_visualization_draw.borrow(),
                 &stock_material_draw.borrow(),
+                &collision_issues_draw.borrow(),
+                &comparison_draw.borrow(),
                 pos,
+                scrub_pos,
                 &device_manager_draw,
                 grid_spacing_draw.get(),
                 grid_major_width,
@@ -1904,9 +2058,17 @@ impl GcodeVisualizer {
         let _simulation_visualization_stock = simulation_visualization.clone();
         let stock_material_stock = stock_material.clone();
         let tool_diameter_stock = tool_diameter.clone();
+        let tool_profile_stock = tool_profile.clone();
+        let stock_tool_collision = stock_tool.clone();
+        let holder_diameter_stock = holder_diameter.clone();
+        let collision_issues_stock = collision_issues.clone();
+        let on_collision_issues_stock = on_collision_issues.clone();
+        let device_manager_stock = device_manager.clone();
+        let da_collision = drawing_area.clone();
         let simulation_running_flag = simulation_running.clone();
         let stock_simulator_3d_stock = stock_simulator_3d.clone();
         let stock_simulation_3d_pending_toggle = stock_simulation_3d_pending.clone();
+        let stock_analysis_toggle = stock_analysis.clone();
         let sim_panel_toggle = sim_panel.clone();
         let sim_cancel_flag = sim_cancel.clone();
         let sim_progress_flag = sim_progress.clone();
@@ -1946,24 +2108,32 @@ impl GcodeVisualizer {
                 if let Some(stock) = stock_material_stock.borrow().as_ref() {
                     // Run simulation in background thread
                     let stock_clone = stock.clone();
-                    let tool_radius_value = *tool_diameter_stock.borrow() / 2.0;
+                    let tool_profile_value =
+                        (*tool_profile_stock.borrow()).unwrap_or(ToolProfile::Flat {
+                            diameter: *tool_diameter_stock.borrow(),
+                        });
                     let result_3d_ref = stock_simulator_3d_stock.clone();
                     let gl_ref = gl_update.clone();
+                    let da_ref = da_collision.clone();
 
                     // Convert GCode commands to toolpath segments for 3D
                     use gcodekit5_visualizer::{ToolpathSegment, ToolpathSegmentType};
                     let mut toolpath_segments_3d = Vec::new();
+                    // Source line of each segment, for reporting collisions
+                    let mut segment_lines = Vec::new();
 
                     // G-code Z is typically negative when cutting (Z=-5 means 5mm below surface)
                     // Voxel grid expects Z from 0 (bottom) to thickness (top)
                     // So we convert: voxel_z = stock_thickness + gcode_z
                     let stock_thickness = stock_clone.thickness;
 
-                    for cmd in vis.commands() {
+                    for (cmd_idx, cmd) in vis.commands().iter().enumerate() {
+                        let line = vis.command_lines().get(cmd_idx).copied().unwrap_or(0);
                         match cmd {
                             GCodeCommand::Move {
                                 from, to, rapid, ..
                             } => {
+                                segment_lines.push(line);
                                 let seg_type = if *rapid {
                                     ToolpathSegmentType::RapidMove
                                 } else {
@@ -1988,6 +2158,7 @@ impl GcodeVisualizer {
                                 clockwise,
                                 ..
                             } => {
+                                segment_lines.push(line);
                                 let seg_type = if *clockwise {
                                     ToolpathSegmentType::ArcCW
                                 } else {
@@ -2011,6 +2182,24 @@ impl GcodeVisualizer {
                         }
                     }
 
+                    // Tool, holder and machine limits for the collision check.
+                    // Voxel Z 0 is the bottom of the stock, i.e. program Z -thickness.
+                    let mut collision_settings = stock_tool_collision
+                        .borrow()
+                        .as_ref()
+                        .map(CollisionSettings::from_tool)
+                        .unwrap_or_default()
+                        .with_stock_origin((0.0, 0.0, -stock_thickness));
+                    let holder = *holder_diameter_stock.borrow();
+                    if holder > 0.0 {
+                        let stick_out = collision_settings.stick_out;
+                        collision_settings = collision_settings.with_holder(holder, stick_out);
+                    }
+                    if let Some(limits) = Self::machine_limits(&device_manager_stock) {
+                        collision_settings = collision_settings.with_machine_limits(limits);
+                    }
+                    let detector = CollisionDetector::new(collision_settings);
+
                     // Use Arc<Mutex<>> for thread-safe sharing
                     let result_arc = thread_safe_none();
                     let result_arc_clone = result_arc.clone();
@@ -2029,32 +2218,39 @@ impl GcodeVisualizer {
                             resolution,
                         );
 
-                        let mut simulator = StockSimulator3D::new(
+                        let mut simulator = StockSimulator3D::with_tool(
                             stock_clone.width,
                             stock_clone.height,
                             stock_clone.thickness,
                             resolution,
-                            tool_radius_value,
+                            tool_profile_value,
                         );
 
                         let cancel = cancel_thread.clone();
                         let progress = progress_thread.clone();
-                        let _ =
-                            simulator.simulate_toolpath_with_progress(&toolpath_segments_3d, |p| {
-                                if p > 0.0 {
-                                    progress.store(
-                                        (p * 100.0).round() as usize,
-                                        std::sync::atomic::Ordering::Relaxed,
-                                    );
-                                }
-                                !cancel.load(std::sync::atomic::Ordering::SeqCst)
-                            });
+                        // Carves the stock while checking each move for collisions
+                        let issues = detector
+                            .analyze_with_progress(
+                                &mut simulator,
+                                &toolpath_segments_3d,
+                                &segment_lines,
+                                |p| {
+                                    if p > 0.0 {
+                                        progress.store(
+                                            (p * 100.0).round() as usize,
+                                            std::sync::atomic::Ordering::Relaxed,
+                                        );
+                                    }
+                                    !cancel.load(std::sync::atomic::Ordering::SeqCst)
+                                },
+                            )
+                            .unwrap_or_default();
                         progress.store(100, std::sync::atomic::Ordering::Relaxed);
 
                         let result_sim = simulator;
 
                         // Store in Arc
-                        *result_arc_clone.lock() = Some(result_sim);
+                        *result_arc_clone.lock() = Some((result_sim, issues));
                     });
 
                     // Poll for completion on main thread with timeout limit
@@ -2064,11 +2260,14 @@ impl GcodeVisualizer {
                     let sim_running_poll = simulation_running_flag.clone();
 
                     let pending_flag = stock_simulation_3d_pending_toggle.clone();
+                    let stock_analysis_poll = stock_analysis_toggle.clone();
                     let sim_cancel_flag_poll = sim_cancel_flag.clone();
                     let sim_panel_toggle_poll = sim_panel_toggle.clone();
                     let sim_progress_poll = sim_progress_flag.clone();
                     let sim_progress_label_poll = sim_progress_label_toggle.clone();
                     let sb_poll = status_bar_sim.clone();
+                    let collision_issues_poll = collision_issues_stock.clone();
+                    let on_collision_poll = on_collision_issues_stock.clone();
                     glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
                         *poll_count_clone.borrow_mut() += 1;
 
@@ -2103,7 +2302,7 @@ impl GcodeVisualizer {
                         }
 
                         if let Some(mut guard) = result_arc_poll.try_lock() {
-                            if let Some(result_simulator) = guard.take() {
+                            if let Some((result_simulator, issues)) = guard.take() {
                                 if sim_cancel_flag_poll.load(std::sync::atomic::Ordering::SeqCst) {
                                     *sim_running_poll.borrow_mut() = false;
                                     sim_panel_toggle_poll.set_visible(false);
@@ -2116,6 +2315,13 @@ impl GcodeVisualizer {
 
                                 *result_3d_ref.borrow_mut() = Some(result_simulator);
                                 *pending_flag.borrow_mut() = true;
+                                stock_analysis_poll.refresh();
+
+                                if let Some(callback) = on_collision_poll.borrow().as_ref() {
+                                    callback(&issues);
+                                }
+                                *collision_issues_poll.borrow_mut() = issues;
+                                da_ref.queue_draw();
 
                                 *sim_running_poll.borrow_mut() = false;
                                 sim_panel_toggle_poll.set_visible(false);
@@ -2152,6 +2358,12 @@ impl GcodeVisualizer {
                 *stock_simulator_3d_stock.borrow_mut() = None;
                 *simulation_running_flag.borrow_mut() = false;
                 sim_panel_toggle.set_visible(false);
+                stock_analysis_toggle.refresh();
+                collision_issues_stock.borrow_mut().clear();
+                if let Some(callback) = on_collision_issues_stock.borrow().as_ref() {
+                    callback(&[]);
+                }
+                da_collision.queue_draw();
                 gl_update.queue_render();
             }
         });
@@ -2185,13 +2397,37 @@ impl GcodeVisualizer {
             }
         });
 
-        let tool_diameter = tool_diameter.clone();
+        let tool_diameter_entry = tool_diameter.clone();
         stock_tool_diameter_entry.connect_changed(move |entry| {
             if let Ok(diameter) = entry.text().parse::<f32>() {
-                *tool_diameter.borrow_mut() = diameter;
+                *tool_diameter_entry.borrow_mut() = diameter;
             }
         });
 
+        let holder_diameter_entry = holder_diameter.clone();
+        stock_holder_diameter_entry.connect_changed(move |entry| {
+            if let Ok(diameter) = entry.text().parse::<f32>() {
+                *holder_diameter_entry.borrow_mut() = diameter;
+            }
+        });
+
+        {
+            let tool_profile = tool_profile.clone();
+            let stock_tool = stock_tool.clone();
+            let diameter_entry = stock_tool_diameter_entry.clone();
+            stock_tool_combo.connect_changed(move |cb| {
+                let selected = cb.active_id().and_then(|id| {
+                    stock_tools
+                        .iter()
+                        .find(|tool| tool.id.0.as_str() == id.as_str())
+                        .cloned()
+                });
+                diameter_entry.set_sensitive(selected.is_none());
+                *tool_profile.borrow_mut() = selected.as_ref().map(Tool::profile);
+                *stock_tool.borrow_mut() = selected;
+            });
+        }
+
         // Mouse Interaction
         Self::setup_interaction(&drawing_area, &visualizer, update_ui.clone());
 
@@ -2225,10 +2461,13 @@ impl GcodeVisualizer {
         let visualizer_3d = visualizer.clone();
         let camera_3d = camera.clone();
         let current_pos_3d = current_pos.clone();
+        let scrub_time_3d = timeline_bar.scrub_time.clone();
         let device_manager_3d = device_manager.clone();
         let stock_simulator_3d_render = stock_simulator_3d.clone();
         let _stock_material_3d = stock_material.clone();
         let stock_simulation_3d_pending_render = stock_simulation_3d_pending.clone();
+        let stock_analysis_render = stock_analysis.clone();
+        let collision_issues_3d = collision_issues.clone();
 
         // Capture checkbox states
         let show_rapid_3d = show_rapid.clone();
@@ -2289,6 +2528,7 @@ impl GcodeVisualizer {
                             bounds_buffers,
                             stock_removal_shader: None,
                             stock_removal_buffers: None,
+                            collision_buffers: None,
                         });
                     }
                     (shader, rapid, cut, grid, axis, tool, bounds) => {
@@ -2389,10 +2629,18 @@ impl GcodeVisualizer {
                     state.cut_buffers.draw();
                 }
 
-                // Draw Tool Marker
+                // Draw Tool Marker, and the timeline scrubber's marker
+                let mut markers = Vec::new();
                 if show_laser_3d.is_active() {
                     let pos = *current_pos_3d.borrow();
-                    let model = glam::Mat4::from_translation(glam::Vec3::new(pos.0, pos.1, pos.2));
+                    markers.push(glam::Vec3::new(pos.0, pos.1, pos.2));
+                }
+                if let Some(time) = *scrub_time_3d.borrow() {
+                    let pos = visualizer_3d.borrow().position_at_time(time);
+                    markers.push(glam::Vec3::new(pos.x, pos.y, pos.z));
+                }
+                for marker in markers {
+                    let model = glam::Mat4::from_translation(marker);
                     let mvp_tool = proj * view * model;
 
                     if let Some(loc) = state.shader.get_uniform_location("uModelViewProjection") {
@@ -2415,6 +2663,37 @@ impl GcodeVisualizer {
                     }
                 }
 
+                // Draw Collision Markers; rebuilt together with the stock mesh
+                if show_stock_removal_3d.is_active() && !collision_issues_3d.borrow().is_empty() {
+                    if state.collision_buffers.is_none()
+                        || *stock_simulation_3d_pending_render.borrow()
+                    {
+                        let positions: Vec<(f32, f32, f32)> = collision_issues_3d
+                            .borrow()
+                            .iter()
+                            .map(|issue| issue.position)
+                            .collect();
+                        match RenderBuffers::new(gl.clone(), glow::LINES) {
+                            Ok(mut buffers) => {
+                                buffers.update(&generate_collision_marker_data(&positions, 2.0));
+                                state.collision_buffers = Some(buffers);
+                            }
+                            Err(e) => {
+                                tracing::error!(error = %e, "failed to create collision marker buffers")
+                            }
+                        }
+                    }
+
+                    if let Some(loc) = state.shader.get_uniform_location("uModelViewProjection") {
+                        unsafe {
+                            gl.uniform_matrix_4_f32_slice(Some(&loc), false, &mvp.to_cols_array());
+                        }
+                    }
+                    if let Some(buffers) = &state.collision_buffers {
+                        buffers.draw();
+                    }
+                }
+
                 state.shader.unbind();
 
                 // Draw 3D Stock Removal
@@ -2432,7 +2711,9 @@ impl GcodeVisualizer {
                         if state.stock_removal_buffers.is_none()
                             || *stock_simulation_3d_pending_render.borrow()
                         {
-                            let mesh_vertices = generate_surface_mesh(simulator.get_grid());
+                            let mesh_vertices = stock_analysis_render
+                                .deviation_mesh()
+                                .unwrap_or_else(|| generate_surface_mesh(simulator.get_grid()));
                             match RenderBuffers::new(gl.clone(), glow::TRIANGLES) {
                                 Ok(mut buffers) => {
                                     buffers.update_mesh(&mesh_vertices);
@@ -2661,8 +2942,14 @@ impl GcodeVisualizer {
             _simulation_visualization: shared_none(),
             _simulation_resolution: simulation_resolution,
             _simulation_running: simulation_running,
+            stock_tool_profile: tool_profile,
+            stock_tool_diameter: tool_diameter,
+            collision_issues,
+            on_collision_issues,
+            comparison,
             _stock_simulator_3d: stock_simulator_3d,
             _stock_simulation_3d_pending: stock_simulation_3d_pending,
+            _stock_analysis: stock_analysis,
             hadjustment,
             vadjustment,
             hadjustment_3d,
@@ -2677,12 +2964,93 @@ impl GcodeVisualizer {
             settings_controller,
             status_bar,
             current_pos,
+            parse_job: shared_none(),
+            filter_controls,
+            timeline_bar,
+        }
+    }
+
+    pub fn set_gcode(self: &Rc<Self>, gcode: &str) {
+        // Newer content replaces any parse still running
+        if let Some(job) = self.parse_job.borrow_mut().take() {
+            job.cancel();
+            if let Some(sb) = self.status_bar.as_ref() {
+                sb.set_progress(0.0, "", "");
+                sb.set_cancel_action(None);
+            }
         }
+
+        if gcode.len() >= BACKGROUND_PARSE_THRESHOLD_BYTES {
+            self.start_background_parse(ParseJob::spawn_text(gcode.to_string()));
+            return;
+        }
+
+        self.visualizer.borrow_mut().parse_gcode(gcode);
+        self.refresh_after_parse();
+    }
+
+    /// Poll a background parse, showing its progress in the status bar and
+    /// loading the result when it finishes
+    fn start_background_parse(self: &Rc<Self>, job: ParseJob) {
+        *self.parse_job.borrow_mut() = Some(job);
+        if let Some(sb) = self.status_bar.as_ref() {
+            let parse_job = self.parse_job.clone();
+            sb.set_progress(0.1, "", "");
+            sb.set_cancel_action(Some(std::boxed::Box::new(move || {
+                if let Some(job) = parse_job.borrow().as_ref() {
+                    job.cancel();
+                }
+            })));
+        }
+
+        let this = Rc::downgrade(self);
+        gtk4::glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+            let Some(this) = this.upgrade() else {
+                return gtk4::glib::ControlFlow::Break;
+            };
+            loop {
+                let event = match this.parse_job.borrow().as_ref() {
+                    Some(job) => job.try_next(),
+                    // Replaced or dropped by a newer set_gcode
+                    None => return gtk4::glib::ControlFlow::Break,
+                };
+                match event {
+                    None => return gtk4::glib::ControlFlow::Continue,
+                    Some(ParseEvent::Progress(progress)) => {
+                        if let Some(sb) = this.status_bar.as_ref() {
+                            sb.set_progress(progress.percent().max(0.1), "", "");
+                        }
+                    }
+                    Some(event) => {
+                        *this.parse_job.borrow_mut() = None;
+                        if let Some(sb) = this.status_bar.as_ref() {
+                            sb.set_progress(0.0, "", "");
+                            sb.set_cancel_action(None);
+                        }
+                        match event {
+                            ParseEvent::Finished(parsed) => {
+                                if this.visualizer.borrow_mut().apply_parsed(*parsed) {
+                                    this.refresh_after_parse();
+                                }
+                            }
+                            ParseEvent::Failed(e) => {
+                                warn!("Failed to parse G-code for the visualizer: {}", e);
+                            }
+                            _ => {}
+                        }
+                        return gtk4::glib::ControlFlow::Break;
+                    }
+                }
+            }
+        });
     }
 
-    pub fn set_gcode(&self, gcode: &str) {
+    /// Update caches, info labels, view fit and stock simulation for newly
+    /// parsed G-code
+    fn refresh_after_parse(&self) {
         let mut vis = self.visualizer.borrow_mut();
-        vis.parse_gcode(gcode);
+        vis.set_rapid_rate(Self::rapid_rate(&self.device_manager));
+        self.filter_controls.reset_ranges(&mut vis);
 
         // Phase 4: Invalidate render cache when G-code changes
         let mut cache = self.render_cache.borrow_mut();
@@ -2721,14 +3089,14 @@ impl GcodeVisualizer {
         let mut sum_s = 0.0;
         let mut count_s = 0;
 
-        for cmd in vis.commands() {
+        for cmd in vis.buffers().iter() {
             let s = match cmd {
                 GCodeCommand::Move {
                     intensity: Some(s), ..
-                } => Some(*s),
+                } => Some(s),
                 GCodeCommand::Arc {
                     intensity: Some(s), ..
-                } => Some(*s),
+                } => Some(s),
                 _ => None,
             };
 
@@ -2846,10 +3214,13 @@ impl GcodeVisualizer {
                     }
                 }
 
-                // Create simulator with default tool radius
-                let tool_radius = 1.585; // 3.17mm diameter / 2
+                // Create simulator with the selected cutter
+                let tool = (*self.stock_tool_profile.borrow()).unwrap_or(ToolProfile::Flat {
+                    diameter: *self.stock_tool_diameter.borrow(),
+                });
                 let resolution = 0.1; // 0.1mm resolution
-                let mut simulator = StockSimulator2D::new(stock.clone(), tool_radius, resolution);
+                let mut simulator =
+                    StockSimulator2D::with_tool_profile(stock.clone(), resolution, tool);
 
                 // Run simulation
                 simulator.simulate_toolpath(&toolpath_segments);
@@ -2861,6 +3232,7 @@ impl GcodeVisualizer {
         }
 
         drop(vis);
+        self.timeline_bar.reset();
         self.update_scrollbars();
         self.drawing_area.queue_draw();
     }
@@ -3,8 +3,9 @@ use super::*;
 use gcodekit5_core::constants as core_constants;
 use gcodekit5_designer::stock_removal::{SimulationResult, StockMaterial};
 use gcodekit5_devicedb::DeviceManager;
-use gcodekit5_visualizer::visualizer::GCodeCommand;
-use gcodekit5_visualizer::Visualizer;
+use gcodekit5_visualizer::utils::ValidationSeverity;
+use gcodekit5_visualizer::visualizer::{GCodeCommand, Point3D};
+use gcodekit5_visualizer::{CollisionIssue, DiffKind, ToolpathDiff, Visualizer};
 use std::sync::Arc;
 
 impl GcodeVisualizer {
@@ -25,7 +26,10 @@ impl GcodeVisualizer {
         _simulation_result: &Option<SimulationResult>,
         simulation_visualization: &Option<StockRemovalVisualization>,
         _stock_material: &Option<StockMaterial>,
+        collision_issues: &[CollisionIssue],
+        comparison: &Option<ToolpathDiff>,
         current_pos: (f32, f32, f32),
+        scrub_pos: Option<Point3D>,
         device_manager: &Option<Arc<DeviceManager>>,
         grid_spacing_mm: f64,
         grid_major_line_width: f64,
@@ -36,8 +40,15 @@ impl GcodeVisualizer {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
+        // Phase 3: Level of Detail - draw simplified geometry that stays
+        // within half a pixel of the real toolpath
+        let toolpath = vis.lod_for_scale(vis.zoom_scale);
+        let filter = *vis.filter();
+
         let mut hasher = DefaultHasher::new();
-        vis.commands().len().hash(&mut hasher);
+        vis.get_command_count().hash(&mut hasher);
+        toolpath.len().hash(&mut hasher);
+        filter.hash(&mut hasher);
         show_intensity.hash(&mut hasher);
         let new_hash = hasher.finish();
         let fg_color = style_context.color();
@@ -145,18 +156,8 @@ impl GcodeVisualizer {
         // Draw Toolpath - Phase 1, 2 & 3 Optimization: Batched Rendering + Viewport Culling + LOD
         cr.set_line_width(1.5 / vis.zoom_scale as f64);
 
-        // Phase 3: Level of Detail
-        let pixels_per_mm = vis.zoom_scale;
-
-        let lod_level = if pixels_per_mm >= 1.0 {
-            0 // High detail
-        } else if pixels_per_mm >= 0.2 {
-            1 // Medium detail
-        } else if pixels_per_mm >= 0.05 {
-            2 // Low detail
-        } else {
-            3 // Minimal (bounding box)
-        };
+        // At extreme zoom out only the bounding box is drawn
+        let bounds_only = vis.zoom_scale < 0.05;
 
         // Phase 2: Calculate visible viewport bounds in world coordinates
         let half_width_world = (width as f32 / 2.0) / vis.zoom_scale;
@@ -172,7 +173,7 @@ impl GcodeVisualizer {
         let view_max_y = -vis.y_offset + half_height_world + margin_y;
 
         // OPTIMIZATION: Batch rapid moves together (single stroke) + viewport culling + LOD
-        if show_rapid && lod_level < 3 {
+        if show_rapid && !bounds_only {
             cr.new_path();
             cr.set_source_rgba(
                 warning_color.red() as f64,
@@ -181,8 +182,7 @@ impl GcodeVisualizer {
                 0.5,
             );
 
-            let mut line_counter = 0u32;
-            for cmd in vis.commands() {
+            for cmd in filter.iter(toolpath) {
                 if let GCodeCommand::Move {
                     from,
                     to,
@@ -203,21 +203,6 @@ impl GcodeVisualizer {
                         continue;
                     }
 
-                    line_counter += 1;
-                    match lod_level {
-                        1 => {
-                            if !line_counter.is_multiple_of(2) {
-                                continue;
-                            }
-                        }
-                        2 => {
-                            if !line_counter.is_multiple_of(4) {
-                                continue;
-                            }
-                        }
-                        _ => {}
-                    }
-
                     cr.move_to(from.x as f64, from.y as f64);
                     cr.line_to(to.x as f64, to.y as f64);
                 }
@@ -226,7 +211,7 @@ impl GcodeVisualizer {
         }
 
         // OPTIMIZATION: Batch cutting moves by intensity + LOD
-        if show_cut && lod_level < 3 {
+        if show_cut && !bounds_only {
             if show_intensity {
                 const INTENSITY_BUCKETS: usize = 20;
 
@@ -238,7 +223,7 @@ impl GcodeVisualizer {
                     cache.total_lines = 0;
                     cache.cut_lines = 0;
 
-                    for cmd in vis.commands() {
+                    for cmd in filter.iter(toolpath) {
                         cache.total_lines += 1;
                         if let GCodeCommand::Move {
                             from,
@@ -269,8 +254,6 @@ impl GcodeVisualizer {
                     }
                 }
 
-                let mut line_counter = 0u32;
-
                 for (bucket_idx, lines) in cache.intensity_buckets.iter().enumerate() {
                     if lines.is_empty() {
                         continue;
@@ -294,21 +277,6 @@ impl GcodeVisualizer {
                             continue;
                         }
 
-                        line_counter += 1;
-                        match lod_level {
-                            1 => {
-                                if !line_counter.is_multiple_of(2) {
-                                    continue;
-                                }
-                            }
-                            2 => {
-                                if !line_counter.is_multiple_of(4) {
-                                    continue;
-                                }
-                            }
-                            _ => {}
-                        }
-
                         cr.move_to(*fx, *fy);
                         cr.line_to(*tx, *ty);
                     }
@@ -317,7 +285,7 @@ impl GcodeVisualizer {
                 }
 
                 // Draw arcs separately (usually fewer)
-                for cmd in vis.commands() {
+                for cmd in filter.iter(toolpath) {
                     if let GCodeCommand::Arc {
                         from,
                         to,
@@ -352,7 +320,7 @@ impl GcodeVisualizer {
                         let start_angle = (from.y - center.y).atan2(from.x - center.x) as f64;
                         let end_angle = (to.y - center.y).atan2(to.x - center.x) as f64;
 
-                        if *clockwise {
+                        if clockwise {
                             cr.arc_negative(
                                 center.x as f64,
                                 center.y as f64,
@@ -382,8 +350,7 @@ impl GcodeVisualizer {
                     1.0,
                 );
 
-                let mut line_counter = 0u32;
-                for cmd in vis.commands() {
+                for cmd in filter.iter(toolpath) {
                     match cmd {
                         GCodeCommand::Move {
                             from,
@@ -404,21 +371,6 @@ impl GcodeVisualizer {
                                 continue;
                             }
 
-                            line_counter += 1;
-                            match lod_level {
-                                1 => {
-                                    if !line_counter.is_multiple_of(2) {
-                                        continue;
-                                    }
-                                }
-                                2 => {
-                                    if !line_counter.is_multiple_of(4) {
-                                        continue;
-                                    }
-                                }
-                                _ => {}
-                            }
-
                             cr.move_to(from.x as f64, from.y as f64);
                             cr.line_to(to.x as f64, to.y as f64);
                         }
@@ -448,7 +400,7 @@ impl GcodeVisualizer {
                             let start_angle = (from.y - center.y).atan2(from.x - center.x) as f64;
                             let end_angle = (to.y - center.y).atan2(to.x - center.x) as f64;
 
-                            if *clockwise {
+                            if clockwise {
                                 cr.arc_negative(
                                     center.x as f64,
                                     center.y as f64,
@@ -474,7 +426,7 @@ impl GcodeVisualizer {
         }
 
         // Phase 3 + 4: LOD Level 3 (Minimal) - Draw bounding box only at extreme zoom out
-        if lod_level == 3 && show_cut {
+        if bounds_only && show_cut {
             if cache.cutting_bounds.is_none() && cache.needs_rebuild(new_hash) {
                 let mut bounds_min_x = f32::MAX;
                 let mut bounds_max_x = f32::MIN;
@@ -484,7 +436,7 @@ impl GcodeVisualizer {
                 let mut bounds_max_z = f32::MIN;
                 let mut has_bounds = false;
 
-                for cmd in vis.commands() {
+                for cmd in filter.iter(toolpath) {
                     match cmd {
                         GCodeCommand::Move {
                             from,
@@ -556,6 +508,83 @@ impl GcodeVisualizer {
             }
         }
 
+        // Draw the compared program faintly, then the cuts only one of the
+        // two programs makes
+        if let Some(diff) = comparison {
+            cr.set_source_rgba(
+                fg_color.red() as f64,
+                fg_color.green() as f64,
+                fg_color.blue() as f64,
+                0.35,
+            );
+            cr.set_line_width(1.0 / vis.zoom_scale as f64);
+            cr.set_dash(&[4.0 / vis.zoom_scale as f64], 0.0);
+            for path in diff.original_cuts() {
+                Self::trace_points(cr, path);
+            }
+            let _ = cr.stroke();
+            cr.set_dash(&[], 0.0);
+
+            let error_color = style_context
+                .lookup_color("error_color")
+                .unwrap_or(gtk4::gdk::RGBA::new(0.9, 0.1, 0.1, 1.0));
+            cr.set_line_width(3.0 / vis.zoom_scale as f64);
+            for (kind, color) in [
+                (DiffKind::Removed, &error_color),
+                (DiffKind::Added, &success_color),
+            ] {
+                cr.set_source_rgba(
+                    color.red() as f64,
+                    color.green() as f64,
+                    color.blue() as f64,
+                    0.9,
+                );
+                for region in diff.regions_of(kind) {
+                    Self::trace_points(cr, &region.points);
+                    // Single-sample regions still get a visible dot
+                    if region.points.len() == 1 {
+                        let p = region.points[0];
+                        cr.arc(
+                            p.x as f64,
+                            p.y as f64,
+                            2.0 / vis.zoom_scale as f64,
+                            0.0,
+                            2.0 * std::f64::consts::PI,
+                        );
+                    }
+                }
+                let _ = cr.stroke();
+            }
+        }
+
+        // Draw Collision Markers found by the stock removal simulation
+        if show_stock_removal && !collision_issues.is_empty() {
+            let error_color = style_context
+                .lookup_color("error_color")
+                .unwrap_or(gtk4::gdk::RGBA::new(0.9, 0.1, 0.1, 1.0));
+            let size = 6.0 / vis.zoom_scale as f64;
+            cr.set_line_width(2.0 / vis.zoom_scale as f64);
+            for issue in collision_issues {
+                let color = if issue.kind.severity() == ValidationSeverity::Warning {
+                    &warning_color
+                } else {
+                    &error_color
+                };
+                cr.set_source_rgba(
+                    color.red() as f64,
+                    color.green() as f64,
+                    color.blue() as f64,
+                    1.0,
+                );
+                let (x, y) = (issue.position.0 as f64, issue.position.1 as f64);
+                cr.move_to(x - size, y - size);
+                cr.line_to(x + size, y + size);
+                cr.move_to(x - size, y + size);
+                cr.line_to(x + size, y - size);
+                let _ = cr.stroke();
+            }
+        }
+
         // Draw Laser/Spindle Position
         if show_laser {
             cr.set_source_rgb(1.0, 0.0, 0.0);
@@ -570,9 +599,43 @@ impl GcodeVisualizer {
             let _ = cr.fill();
         }
 
+        // Draw the timeline scrubber's tool marker
+        if let Some(pos) = scrub_pos {
+            let radius = 6.0 / vis.zoom_scale as f64;
+            cr.set_source_rgba(
+                accent_color.red() as f64,
+                accent_color.green() as f64,
+                accent_color.blue() as f64,
+                1.0,
+            );
+            cr.set_line_width(2.0 / vis.zoom_scale as f64);
+            cr.arc(
+                pos.x as f64,
+                pos.y as f64,
+                radius,
+                0.0,
+                2.0 * std::f64::consts::PI,
+            );
+            cr.move_to(pos.x as f64 - radius * 1.5, pos.y as f64);
+            cr.line_to(pos.x as f64 + radius * 1.5, pos.y as f64);
+            cr.move_to(pos.x as f64, pos.y as f64 - radius * 1.5);
+            cr.line_to(pos.x as f64, pos.y as f64 + radius * 1.5);
+            let _ = cr.stroke();
+        }
+
         let _ = cr.restore();
     }
 
+    /// Add a polyline to the current path
+    fn trace_points(cr: &gtk4::cairo::Context, points: &[Point3D]) {
+        if let Some((first, rest)) = points.split_first() {
+            cr.move_to(first.x as f64, first.y as f64);
+            for p in rest {
+                cr.line_to(p.x as f64, p.y as f64);
+            }
+        }
+    }
+
     pub(crate) fn draw_grid(
         cr: &gtk4::cairo::Context,
         vis: &Visualizer,
@@ -0,0 +1,445 @@
+//! Line/Z filter controls and the timeline scrubber
+//!
+//! The filter limits what both views draw. The timeline bar under the
+//! canvas moves a tool marker along the toolpath by estimated run time,
+//! plays it back at a chosen speed, and reports the line under the marker
+//! so the editor can follow along.
+
+use crate::t;
+use gcodekit5_core::{shared, shared_none, Shared, SharedOption};
+use gcodekit5_visualizer::{TimelinePlayback, ToolpathFilter, Visualizer};
+use gtk4::prelude::*;
+use gtk4::{
+    accessible::Property as AccessibleProperty, Adjustment, Align, Box, Button, CheckButton,
+    ComboBoxText, DrawingArea, GLArea, Grid, Label, Orientation, Scale, SpinButton,
+};
+use std::rc::{Rc, Weak};
+use std::time::{Duration, Instant};
+
+/// Interval between playback frames
+const PLAYBACK_FRAME: Duration = Duration::from_millis(16);
+
+/// Playback speed multipliers offered in the speed menu
+const PLAYBACK_SPEEDS: [f64; 8] = [0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 25.0, 100.0];
+
+/// Format seconds as MM:SS, or H:MM:SS for long jobs
+fn format_time(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (hours, minutes, secs) = (total / 3600, (total % 3600) / 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{:02}:{:02}", minutes, secs)
+    }
+}
+
+/// Sidebar controls for the line and Z range filter
+pub(crate) struct FilterControls {
+    pub(crate) widget: Box,
+    limit_lines: CheckButton,
+    first_line: SpinButton,
+    last_line: SpinButton,
+    limit_z: CheckButton,
+    z_min: SpinButton,
+    z_max: SpinButton,
+    // Set while ranges are reset from code, so nothing is applied midway
+    updating: Shared<bool>,
+}
+
+impl FilterControls {
+    /// Build the controls; changes are applied to `visualizer` and redrawn
+    pub(crate) fn new(
+        visualizer: Shared<Visualizer>,
+        drawing_area: DrawingArea,
+        gl_area: GLArea,
+    ) -> Rc<Self> {
+        let widget = Box::new(Orientation::Vertical, 4);
+        widget.set_margin_start(6);
+        widget.set_margin_end(6);
+        widget.set_margin_top(6);
+        widget.set_margin_bottom(6);
+
+        let spin = |min: f64, max: f64, step: f64, digits: u32, tooltip: String| {
+            let spin = SpinButton::with_range(min, max, step);
+            spin.set_digits(digits);
+            spin.set_hexpand(true);
+            spin.set_sensitive(false);
+            spin.set_tooltip_text(Some(&tooltip));
+            spin.update_property(&[AccessibleProperty::Label(&tooltip)]);
+            spin
+        };
+
+        let limit_lines = CheckButton::with_label(&t!("Limit Lines"));
+        let first_line = spin(1.0, 1.0, 1.0, 0, t!("First Line").to_string());
+        let last_line = spin(1.0, 1.0, 1.0, 0, t!("Last Line").to_string());
+
+        let limit_z = CheckButton::with_label(&t!("Limit Z"));
+        let z_min = spin(-1000.0, 1000.0, 0.1, 2, t!("Min Z").to_string());
+        let z_max = spin(-1000.0, 1000.0, 0.1, 2, t!("Max Z").to_string());
+
+        let grid = Grid::new();
+        grid.set_row_spacing(4);
+        grid.set_column_spacing(6);
+        grid.attach(&limit_lines, 0, 0, 2, 1);
+        grid.attach(&first_line, 0, 1, 1, 1);
+        grid.attach(&last_line, 1, 1, 1, 1);
+        grid.attach(&limit_z, 0, 2, 2, 1);
+        grid.attach(&z_min, 0, 3, 1, 1);
+        grid.attach(&z_max, 1, 3, 1, 1);
+        widget.append(&grid);
+
+        let controls = Rc::new(Self {
+            widget,
+            limit_lines,
+            first_line,
+            last_line,
+            limit_z,
+            z_min,
+            z_max,
+            updating: shared(false),
+        });
+
+        let apply = {
+            let controls = Rc::downgrade(&controls);
+            Rc::new(move || {
+                let Some(controls) = controls.upgrade() else {
+                    return;
+                };
+                if *controls.updating.borrow() {
+                    return;
+                }
+                let lines = controls.limit_lines.is_active();
+                controls.first_line.set_sensitive(lines);
+                controls.last_line.set_sensitive(lines);
+                let z = controls.limit_z.is_active();
+                controls.z_min.set_sensitive(z);
+                controls.z_max.set_sensitive(z);
+
+                visualizer.borrow_mut().set_filter(controls.filter());
+                drawing_area.queue_draw();
+                gl_area.queue_render();
+            })
+        };
+        for check in [&controls.limit_lines, &controls.limit_z] {
+            let apply = apply.clone();
+            check.connect_toggled(move |_| apply());
+        }
+        for spin in [
+            &controls.first_line,
+            &controls.last_line,
+            &controls.z_min,
+            &controls.z_max,
+        ] {
+            let apply = apply.clone();
+            spin.connect_value_changed(move |_| apply());
+        }
+
+        controls
+    }
+
+    /// Filter described by the current control values
+    pub(crate) fn filter(&self) -> ToolpathFilter {
+        let mut filter = ToolpathFilter::new();
+        if self.limit_lines.is_active() {
+            filter = filter.with_line_range(
+                self.first_line.value() as u32,
+                self.last_line.value() as u32,
+            );
+        }
+        if self.limit_z.is_active() {
+            filter = filter.with_z_range(self.z_min.value() as f32, self.z_max.value() as f32);
+        }
+        filter
+    }
+
+    /// Fit the ranges to a newly loaded toolpath. Ranges the user has
+    /// turned on are kept as far as the new toolpath allows.
+    pub(crate) fn reset_ranges(&self, vis: &mut Visualizer) {
+        *self.updating.borrow_mut() = true;
+        let last = vis.buffers().lines().last().copied().unwrap_or(1).max(1) as f64;
+        self.first_line.set_range(1.0, last);
+        self.last_line.set_range(1.0, last);
+        if !self.limit_lines.is_active() {
+            self.first_line.set_value(1.0);
+            self.last_line.set_value(last);
+        }
+
+        if !self.limit_z.is_active() {
+            let bounds = vis.buffers().bounds();
+            let (min_z, max_z) = if vis.buffers().is_empty() {
+                (0.0, 0.0)
+            } else {
+                (bounds.min_z as f64, bounds.max_z as f64)
+            };
+            self.z_min.set_value(min_z);
+            self.z_max.set_value(max_z);
+        }
+        *self.updating.borrow_mut() = false;
+        vis.set_filter(self.filter());
+    }
+}
+
+/// Called with the source line under the scrubber's tool marker
+type LineCallback = std::boxed::Box<dyn Fn(u32)>;
+
+/// Timeline slider with play/pause and a speed multiplier
+pub(crate) struct TimelineBar {
+    pub(crate) widget: Box,
+    /// Time (s) shown by the scrubber, or None before it is first used
+    pub(crate) scrub_time: Shared<Option<f64>>,
+    scale: Scale,
+    play_btn: Button,
+    time_label: Label,
+    line_label: Label,
+    playback: Shared<TimelinePlayback>,
+    visualizer: Shared<Visualizer>,
+    drawing_area: DrawingArea,
+    gl_area: GLArea,
+    // Set while the slider is moved from code, so it is not taken as a seek
+    updating: Shared<bool>,
+    ticking: Shared<bool>,
+    last_line: Shared<Option<u32>>,
+    on_line_changed: SharedOption<LineCallback>,
+}
+
+impl TimelineBar {
+    pub(crate) fn new(
+        visualizer: Shared<Visualizer>,
+        drawing_area: DrawingArea,
+        gl_area: GLArea,
+    ) -> Rc<Self> {
+        let widget = Box::new(Orientation::Horizontal, 6);
+        widget.add_css_class("visualizer-timeline");
+        widget.set_margin_start(6);
+        widget.set_margin_end(6);
+        widget.set_margin_top(4);
+        widget.set_margin_bottom(4);
+
+        let play_btn = Button::builder()
+            .icon_name("media-playback-start-symbolic")
+            .tooltip_text(t!("Play"))
+            .build();
+        play_btn.update_property(&[AccessibleProperty::Label(&t!("Play"))]);
+
+        let scale = Scale::new(
+            Orientation::Horizontal,
+            Some(&Adjustment::new(0.0, 0.0, 0.0, 0.1, 1.0, 0.0)),
+        );
+        scale.set_hexpand(true);
+        scale.set_draw_value(false);
+        scale.set_tooltip_text(Some(&t!("Timeline")));
+        scale.update_property(&[AccessibleProperty::Label(&t!("Timeline"))]);
+
+        let time_label = Label::builder()
+            .label("00:00 / 00:00")
+            .css_classes(vec!["monospace"])
+            .build();
+        let line_label = Label::builder()
+            .label("")
+            .width_chars(12)
+            .halign(Align::Start)
+            .css_classes(vec!["monospace", "caption"])
+            .build();
+
+        let speed_combo = ComboBoxText::new();
+        for speed in PLAYBACK_SPEEDS {
+            speed_combo.append(Some(speed.to_string().as_str()), &format!("{}×", speed));
+        }
+        speed_combo.set_active_id(Some("1"));
+        speed_combo.set_tooltip_text(Some(&t!("Playback Speed")));
+
+        widget.append(&play_btn);
+        widget.append(&scale);
+        widget.append(&time_label);
+        widget.append(&line_label);
+        widget.append(&speed_combo);
+        widget.set_sensitive(false);
+
+        let bar = Rc::new(Self {
+            widget,
+            scrub_time: shared_none(),
+            scale,
+            play_btn,
+            time_label,
+            line_label,
+            playback: shared(TimelinePlayback::new()),
+            visualizer,
+            drawing_area,
+            gl_area,
+            updating: shared(false),
+            ticking: shared(false),
+            last_line: shared_none(),
+            on_line_changed: shared_none(),
+        });
+
+        {
+            let bar_weak = Rc::downgrade(&bar);
+            bar.scale.connect_value_changed(move |scale| {
+                let Some(bar) = bar_weak.upgrade() else {
+                    return;
+                };
+                if !*bar.updating.borrow() {
+                    bar.seek(scale.value(), true);
+                }
+            });
+        }
+        {
+            let bar_weak = Rc::downgrade(&bar);
+            bar.play_btn.connect_clicked(move |_| {
+                if let Some(bar) = bar_weak.upgrade() {
+                    bar.toggle_playback();
+                }
+            });
+        }
+        {
+            let playback = bar.playback.clone();
+            speed_combo.connect_changed(move |combo| {
+                if let Some(speed) = combo.active_id().and_then(|id| id.parse::<f64>().ok()) {
+                    playback.borrow_mut().set_speed(speed);
+                }
+            });
+        }
+
+        bar
+    }
+
+    /// Register a callback run when the line under the tool marker changes
+    pub(crate) fn set_on_line_changed<F: Fn(u32) + 'static>(&self, callback: F) {
+        *self.on_line_changed.borrow_mut() = Some(std::boxed::Box::new(callback));
+    }
+
+    /// Rewind and resize the timeline after a new toolpath was loaded
+    pub(crate) fn reset(&self) {
+        self.playback.borrow_mut().pause();
+        self.playback.borrow_mut().set_time(0.0);
+        *self.scrub_time.borrow_mut() = None;
+        *self.last_line.borrow_mut() = None;
+
+        let total = self.visualizer.borrow().timeline().total_time();
+        *self.updating.borrow_mut() = true;
+        self.scale.set_range(0.0, total.max(f64::EPSILON));
+        self.scale.set_value(0.0);
+        *self.updating.borrow_mut() = false;
+
+        self.widget.set_sensitive(total > 0.0);
+        self.update_play_button();
+        self.time_label
+            .set_text(&format!("{} / {}", format_time(0.0), format_time(total)));
+        self.line_label.set_text("");
+    }
+
+    /// Move the tool marker to `time` seconds. With `notify`, the line
+    /// under the marker is reported when it changes.
+    pub(crate) fn seek(&self, time: f64, notify: bool) {
+        let vis = self.visualizer.borrow();
+        let timeline = vis.timeline();
+        if timeline.is_empty() {
+            return;
+        }
+        let total = timeline.total_time();
+        let time = time.clamp(0.0, total);
+        let line = timeline.line_at(vis.buffers(), time);
+        drop(vis);
+
+        self.playback.borrow_mut().set_time(time);
+        *self.scrub_time.borrow_mut() = Some(time);
+
+        *self.updating.borrow_mut() = true;
+        self.scale.set_value(time);
+        *self.updating.borrow_mut() = false;
+
+        self.time_label
+            .set_text(&format!("{} / {}", format_time(time), format_time(total)));
+        if let Some(line) = line {
+            self.line_label
+                .set_text(&format!("{} {}", t!("Line"), line));
+        }
+
+        let changed = *self.last_line.borrow() != line;
+        *self.last_line.borrow_mut() = line;
+        if notify && changed {
+            if let (Some(line), Some(callback)) = (line, self.on_line_changed.borrow().as_ref()) {
+                callback(line);
+            }
+        }
+
+        self.drawing_area.queue_draw();
+        self.gl_area.queue_render();
+    }
+
+    /// Move the tool marker to where the job is once `line` has run,
+    /// e.g. when the cursor moves in the editor
+    pub(crate) fn select_line(&self, line: u32) {
+        if *self.last_line.borrow() == Some(line) {
+            return;
+        }
+        let time = {
+            let vis = self.visualizer.borrow();
+            vis.timeline().time_at_line(vis.buffers(), line)
+        };
+        self.seek(time, false);
+    }
+
+    fn toggle_playback(self: &Rc<Self>) {
+        let total = self.visualizer.borrow().timeline().total_time();
+        {
+            let mut playback = self.playback.borrow_mut();
+            if playback.is_playing() {
+                playback.pause();
+            } else {
+                if playback.time() >= total {
+                    playback.set_time(0.0);
+                }
+                playback.play();
+            }
+        }
+        self.update_play_button();
+
+        if self.playback.borrow().is_playing() && !*self.ticking.borrow() {
+            *self.ticking.borrow_mut() = true;
+            Self::start_ticking(Rc::downgrade(self));
+        }
+    }
+
+    /// Advance playback by the wall-clock time between frames, so the
+    /// marker moves at the job's real speed times the multiplier
+    fn start_ticking(bar_weak: Weak<Self>) {
+        let mut last_frame = Instant::now();
+        gtk4::glib::timeout_add_local(PLAYBACK_FRAME, move || {
+            let Some(bar) = bar_weak.upgrade() else {
+                return gtk4::glib::ControlFlow::Break;
+            };
+            let now = Instant::now();
+            let elapsed = now - last_frame;
+            last_frame = now;
+
+            let total = bar.visualizer.borrow().timeline().total_time();
+            let (time, playing) = {
+                let mut playback = bar.playback.borrow_mut();
+                let time = playback.advance(elapsed, total);
+                (time, playback.is_playing())
+            };
+            bar.seek(time, true);
+
+            if playing {
+                gtk4::glib::ControlFlow::Continue
+            } else {
+                *bar.ticking.borrow_mut() = false;
+                bar.update_play_button();
+                gtk4::glib::ControlFlow::Break
+            }
+        });
+    }
+
+    fn update_play_button(&self) {
+        let (icon, label) = if self.playback.borrow().is_playing() {
+            ("media-playback-pause-symbolic", t!("Pause"))
+        } else {
+            ("media-playback-start-symbolic", t!("Play"))
+        };
+        self.play_btn.set_icon_name(icon);
+        self.play_btn.set_tooltip_text(Some(&label));
+        self.play_btn
+            .update_property(&[AccessibleProperty::Label(&label)]);
+    }
+}
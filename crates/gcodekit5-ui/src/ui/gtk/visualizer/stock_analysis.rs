@@ -0,0 +1,239 @@
+//! STL export of the simulated stock and comparison with a reference model
+//!
+//! Both work on the result of the 3D stock removal simulation. The
+//! comparison is kept up to date as the simulation is re-run, and can
+//! colour the stock by how far it is from the model.
+
+use crate::t;
+use crate::ui::gtk::file_dialog;
+use gcodekit5_core::{shared_none, Shared, SharedOption};
+use gcodekit5_designer::model3d::{Mesh3D, Model3DImporter};
+use gcodekit5_designer::stock_removal::StockMaterial;
+use gcodekit5_visualizer::{DeviationMap, DeviationSettings, StockSimulator3D};
+use glam::Vec3;
+use gtk4::prelude::*;
+use gtk4::{Box, Button, CheckButton, FileFilter, GLArea, Label, Orientation, ResponseType};
+use std::rc::Rc;
+
+/// Sidebar controls for exporting and checking the simulated stock
+pub(crate) struct StockAnalysisControls {
+    pub(crate) widget: Box,
+    show_deviation: CheckButton,
+    stats_label: Label,
+    simulator: SharedOption<StockSimulator3D>,
+    stock_material: SharedOption<StockMaterial>,
+    // Set when the stock mesh must be rebuilt
+    mesh_pending: Shared<bool>,
+    gl_area: GLArea,
+    reference: SharedOption<Mesh3D>,
+    deviation: SharedOption<DeviationMap>,
+    settings: DeviationSettings,
+}
+
+impl StockAnalysisControls {
+    pub(crate) fn new(
+        simulator: SharedOption<StockSimulator3D>,
+        stock_material: SharedOption<StockMaterial>,
+        mesh_pending: Shared<bool>,
+        gl_area: GLArea,
+    ) -> Rc<Self> {
+        let widget = Box::new(Orientation::Vertical, 4);
+
+        let export_btn = Button::with_label(&t!("Export Stock STL…"));
+        export_btn.set_tooltip_text(Some(&t!("Save the simulated stock as an STL file")));
+        let compare_btn = Button::with_label(&t!("Compare to Model…"));
+        compare_btn.set_tooltip_text(Some(&t!(
+            "Measure over-cut and under-cut against a reference STL"
+        )));
+        let show_deviation = CheckButton::with_label(&t!("Show Deviation"));
+        show_deviation.set_sensitive(false);
+        let stats_label = Label::new(None);
+        stats_label.set_halign(gtk4::Align::Start);
+        stats_label.set_wrap(true);
+        stats_label.add_css_class("caption");
+        stats_label.set_visible(false);
+
+        widget.append(&export_btn);
+        widget.append(&compare_btn);
+        widget.append(&show_deviation);
+        widget.append(&stats_label);
+
+        let controls = Rc::new(Self {
+            widget,
+            show_deviation,
+            stats_label,
+            simulator,
+            stock_material,
+            mesh_pending,
+            gl_area,
+            reference: shared_none(),
+            deviation: shared_none(),
+            settings: DeviationSettings::default(),
+        });
+
+        let weak = Rc::downgrade(&controls);
+        export_btn.connect_clicked(move |btn| {
+            if let Some(controls) = weak.upgrade() {
+                controls.export_stl(btn);
+            }
+        });
+
+        let weak = Rc::downgrade(&controls);
+        compare_btn.connect_clicked(move |btn| {
+            if let Some(controls) = weak.upgrade() {
+                controls.choose_reference(btn);
+            }
+        });
+
+        let weak = Rc::downgrade(&controls);
+        controls.show_deviation.connect_toggled(move |_| {
+            if let Some(controls) = weak.upgrade() {
+                *controls.mesh_pending.borrow_mut() = true;
+                controls.gl_area.queue_render();
+            }
+        });
+
+        controls
+    }
+
+    /// Where the voxel grid's bottom corner is in program coordinates:
+    /// the stock top sits at Z0
+    fn stock_origin(&self) -> Vec3 {
+        let thickness = self
+            .stock_material
+            .borrow()
+            .as_ref()
+            .map(|stock| stock.thickness)
+            .unwrap_or(0.0);
+        Vec3::new(0.0, 0.0, -thickness)
+    }
+
+    fn stl_filter() -> FileFilter {
+        let filter = FileFilter::new();
+        filter.set_name(Some(&t!("STL Files (*.stl)")));
+        filter.add_pattern("*.stl");
+        filter.add_pattern("*.STL");
+        filter
+    }
+
+    fn export_stl(self: &Rc<Self>, button: &Button) {
+        let window = file_dialog::parent_window(button);
+        if self.simulator.borrow().is_none() {
+            file_dialog::show_error_dialog(
+                &t!("Export Stock STL"),
+                &t!("Run the stock removal simulation first."),
+                window.as_ref(),
+            );
+            return;
+        }
+
+        let dialog = file_dialog::save_dialog(&t!("Export Stock STL"), window.as_ref());
+        dialog.add_filter(&Self::stl_filter());
+        dialog.set_current_name("stock.stl");
+        let controls = Rc::downgrade(self);
+        dialog.connect_response(move |dialog, response| {
+            if response == ResponseType::Accept {
+                if let (Some(path), Some(controls)) =
+                    (dialog.file().and_then(|f| f.path()), controls.upgrade())
+                {
+                    let origin = controls.stock_origin();
+                    let result = match controls.simulator.borrow().as_ref() {
+                        Some(simulator) => simulator.export_stl(&path, origin).map(|_| ()),
+                        None => Ok(()),
+                    };
+                    if let Err(e) = result {
+                        file_dialog::show_error_dialog(
+                            &t!("Export Stock STL"),
+                            &format!("{:#}", e),
+                            window.as_ref(),
+                        );
+                    }
+                }
+            }
+            dialog.destroy();
+        });
+        dialog.show();
+    }
+
+    fn choose_reference(self: &Rc<Self>, button: &Button) {
+        let window = file_dialog::parent_window(button);
+        let dialog = file_dialog::open_dialog(&t!("Compare to Model"), window.as_ref());
+        dialog.add_filter(&Self::stl_filter());
+        let controls = Rc::downgrade(self);
+        dialog.connect_response(move |dialog, response| {
+            if response == ResponseType::Accept {
+                if let (Some(path), Some(controls)) =
+                    (dialog.file().and_then(|f| f.path()), controls.upgrade())
+                {
+                    // The model must already be placed in program coordinates
+                    let imported = Model3DImporter::new()
+                        .with_centering(false)
+                        .import_stl_file(&path.to_string_lossy());
+                    match imported {
+                        Ok(mesh) => {
+                            *controls.reference.borrow_mut() = Some(mesh);
+                            controls.show_deviation.set_sensitive(true);
+                            controls.show_deviation.set_active(true);
+                            controls.refresh();
+                        }
+                        Err(e) => file_dialog::show_error_dialog(
+                            &t!("Compare to Model"),
+                            &e.to_string(),
+                            window.as_ref(),
+                        ),
+                    }
+                }
+            }
+            dialog.destroy();
+        });
+        dialog.show();
+    }
+
+    /// Re-measure the current simulation against the reference model.
+    /// Called whenever the simulated stock changes.
+    pub(crate) fn refresh(&self) {
+        let map = match (
+            self.simulator.borrow().as_ref(),
+            self.reference.borrow().as_ref(),
+        ) {
+            (Some(simulator), Some(reference)) => Some(DeviationMap::compute(
+                simulator.get_grid(),
+                self.stock_origin(),
+                reference,
+                self.settings,
+            )),
+            _ => None,
+        };
+
+        match map.as_ref().map(DeviationMap::stats) {
+            Some(stats) => {
+                self.stats_label.set_text(&format!(
+                    "{} ±{:.2} mm: {:.1}%\n{}: {:.3} mm\n{}: {:.3} mm\n{}: {:.3} mm",
+                    t!("Within"),
+                    self.settings.tolerance,
+                    stats.within_tolerance_percent(),
+                    t!("Max over-cut"),
+                    stats.max_overcut,
+                    t!("Max under-cut"),
+                    stats.max_undercut,
+                    t!("RMS deviation"),
+                    stats.rms,
+                ));
+                self.stats_label.set_visible(true);
+            }
+            None => self.stats_label.set_visible(false),
+        }
+
+        *self.deviation.borrow_mut() = map;
+        *self.mesh_pending.borrow_mut() = true;
+        self.gl_area.queue_render();
+    }
+
+    /// Stock mesh coloured by deviation, when that view is enabled
+    pub(crate) fn deviation_mesh(&self) -> Option<Vec<f32>> {
+        if !self.show_deviation.is_active() {
+            return None;
+        }
+        self.deviation.borrow().as_ref().map(DeviationMap::to_mesh)
+    }
+}
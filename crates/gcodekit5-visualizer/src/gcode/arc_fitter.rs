@@ -0,0 +1,617 @@
+//! Arc fitting processor
+//!
+//! Replaces runs of short linear moves with G2/G3 arcs. Programs generated
+//! from meshes or imported vector art often describe curves with thousands
+//! of tiny G1 segments, which fill the controller's planner buffer and make
+//! the machine stutter. The fitted arcs follow the original polyline within
+//! the configured tolerance at every vertex and segment midpoint.
+
+use std::f64::consts::{PI, TAU};
+use std::sync::Mutex;
+
+use super::{CommandProcessor, GcodeCommand, GcodeState, ProcessorConfig};
+
+/// Moves buffered before a run is fitted regardless of what follows
+const MAX_BUFFERED_MOVES: usize = 2000;
+
+/// Line-count statistics collected by [`ArcFitter`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArcFitStats {
+    /// Commands received
+    pub lines_in: usize,
+    /// Commands emitted
+    pub lines_out: usize,
+    /// G2/G3 commands created
+    pub arcs_created: usize,
+    /// G1 commands replaced by arcs
+    pub segments_replaced: usize,
+}
+
+impl ArcFitStats {
+    /// Number of lines removed from the program
+    pub fn reduction(&self) -> usize {
+        self.lines_in.saturating_sub(self.lines_out)
+    }
+
+    /// Lines removed as a percentage of the input
+    pub fn reduction_percent(&self) -> f64 {
+        if self.lines_in == 0 {
+            0.0
+        } else {
+            self.reduction() as f64 * 100.0 / self.lines_in as f64
+        }
+    }
+}
+
+/// Arc Fitter Processor
+///
+/// Fits consecutive G1 moves into G2/G3 arcs in the active plane (G17, G18
+/// or G19); movement along the plane normal becomes a helical arc. Only
+/// plain absolute-mode G1 lines are candidates: lines with comments, line
+/// numbers or other words are passed through unchanged and end the current
+/// run. Because runs are buffered, [`CommandProcessor::finish`] must be
+/// called at the end of the program, which [`ProcessorPipeline`] does.
+///
+/// Options: `tolerance` (mm, default 0.01), `min_segments` (default 3) and
+/// `max_radius` (mm, default 1000).
+///
+/// [`ProcessorPipeline`]: super::ProcessorPipeline
+#[derive(Debug)]
+pub struct ArcFitter {
+    config: ProcessorConfig,
+    state: Mutex<FitState>,
+    stats: Mutex<ArcFitStats>,
+}
+
+impl ArcFitter {
+    /// Create a new arc fitter with default tolerance (0.01 mm)
+    pub fn new() -> Self {
+        let config = ProcessorConfig::new()
+            .with_option("tolerance", "0.01")
+            .with_option("min_segments", "3")
+            .with_option("max_radius", "1000");
+        Self {
+            config,
+            state: Mutex::new(FitState::default()),
+            stats: Mutex::new(ArcFitStats::default()),
+        }
+    }
+
+    /// Create with a specific tolerance in millimetres
+    pub fn with_tolerance(tolerance: f64) -> Self {
+        let mut fitter = Self::new();
+        fitter.config = fitter
+            .config
+            .with_option("tolerance", tolerance.to_string());
+        fitter
+    }
+
+    /// Statistics since creation or the last [`Self::reset_stats`]
+    pub fn stats(&self) -> ArcFitStats {
+        *self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Clear the collected statistics
+    pub fn reset_stats(&self) {
+        *self.stats.lock().unwrap_or_else(|e| e.into_inner()) = ArcFitStats::default();
+    }
+
+    fn option<T: std::str::FromStr>(&self, key: &str, default: T) -> T {
+        self.config
+            .get_option(key)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn settings(&self, state: &FitState) -> FitSettings {
+        // Tolerances are configured in mm; the program may be in inches
+        let scale = if state.inches { 1.0 / 25.4 } else { 1.0 };
+        FitSettings {
+            tolerance: self.option("tolerance", 0.01_f64).max(1e-6) * scale,
+            min_segments: self.option("min_segments", 3_usize).max(2),
+            max_radius: self.option("max_radius", 1000.0_f64) * scale,
+        }
+    }
+
+    /// Fit and emit all buffered moves
+    fn flush(&self, state: &mut FitState, out: &mut Vec<GcodeCommand>) {
+        if state.run.is_empty() {
+            return;
+        }
+        let settings = self.settings(state);
+        let run = std::mem::take(&mut state.run);
+        let start = state.run_start;
+        let mut points = Vec::with_capacity(run.len() + 1);
+        points.push(start);
+        points.extend(run.iter().map(|m| m.end));
+
+        let (a, b, n) = state.plane.axes();
+        let mut i = 0;
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        while i < run.len() {
+            let mut best = None;
+            let mut j = i + settings.min_segments;
+            while j <= run.len() {
+                match fit_arc(&points[i..=j], (a, b, n), &settings) {
+                    Some(arc) => best = Some((j, arc)),
+                    None => break,
+                }
+                j += 1;
+            }
+
+            match best {
+                Some((end, arc)) => {
+                    let mut command = run[i].command.clone();
+                    let text = self.arc_text(state, &points[i], &points[end], &arc, run[i].feed);
+                    command.command = text.clone();
+                    command.line = text;
+                    out.push(command);
+                    state.emitted_motion = Some(if arc.clockwise { 2 } else { 3 });
+                    stats.arcs_created += 1;
+                    stats.segments_replaced += end - i;
+                    i = end;
+                }
+                None => {
+                    out.push(self.linear_command(state, &run[i].command));
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// G1 command, made explicit when the controller's modal motion differs
+    fn linear_command(&self, state: &mut FitState, original: &GcodeCommand) -> GcodeCommand {
+        let mut command = original.clone();
+        if state.emitted_motion != Some(1) && motion_word(&command.command).is_none() {
+            command.command = format!("G1 {}", command.command.trim());
+            command.line = command.command.clone();
+        }
+        state.emitted_motion = Some(1);
+        command
+    }
+
+    fn arc_text(
+        &self,
+        state: &FitState,
+        start: &[f64; 3],
+        end: &[f64; 3],
+        arc: &FittedArc,
+        feed: Option<f64>,
+    ) -> String {
+        let (a, b, _) = state.plane.axes();
+        let mut text = String::from(if arc.clockwise { "G2" } else { "G3" });
+        for (axis, letter) in ['X', 'Y', 'Z'].into_iter().enumerate() {
+            if (end[axis] - start[axis]).abs() > 1e-9 || axis == a || axis == b {
+                text.push_str(&format!(" {}{}", letter, format_number(end[axis])));
+            }
+        }
+        // Centre offsets use I/J/K for X/Y/Z whatever the plane
+        let offsets = ['I', 'J', 'K'];
+        text.push_str(&format!(
+            " {}{} {}{}",
+            offsets[a],
+            format_number(arc.center.0 - start[a]),
+            offsets[b],
+            format_number(arc.center.1 - start[b])
+        ));
+        if let Some(feed) = feed {
+            text.push_str(&format!(" F{}", format_number(feed)));
+        }
+        text
+    }
+}
+
+impl Default for ArcFitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandProcessor for ArcFitter {
+    fn name(&self) -> &str {
+        "arc_fitter"
+    }
+
+    fn description(&self) -> &str {
+        "Fits runs of linear moves (G01) into arcs (G02/G03) within a tolerance"
+    }
+
+    fn process(
+        &self,
+        command: &GcodeCommand,
+        _state: &GcodeState,
+    ) -> Result<Vec<GcodeCommand>, String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = Vec::new();
+        let line = Line::parse(&command.command);
+
+        if let Some(end) = state.candidate(&line) {
+            // A new feed rate can only start a run
+            if line.feed.is_some() && !state.run.is_empty() {
+                self.flush(&mut state, &mut out);
+            }
+            if state.run.is_empty() {
+                state.run_start = state.known_position().unwrap_or(end);
+            }
+            state.run.push(BufferedMove {
+                command: command.clone(),
+                end,
+                feed: line.feed,
+            });
+            state.program_motion = 1;
+            state.position = end.map(Some);
+            if state.run.len() >= MAX_BUFFERED_MOVES {
+                self.flush(&mut state, &mut out);
+            }
+        } else {
+            self.flush(&mut state, &mut out);
+            let mut passed = command.clone();
+            if line.is_modal_move() && state.emitted_motion != Some(state.program_motion) {
+                // The fitted arcs changed the modal motion the line relies on
+                passed.command = format!("G{} {}", state.program_motion, passed.command.trim());
+                passed.line = passed.command.clone();
+            }
+            state.update(&line);
+            if line.motion.is_some() || line.is_modal_move() {
+                state.emitted_motion = Some(state.program_motion);
+            }
+            out.push(passed);
+        }
+
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.lines_in += 1;
+        stats.lines_out += out.len();
+        Ok(out)
+    }
+
+    fn finish(&self, _state: &GcodeState) -> Result<Vec<GcodeCommand>, String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = Vec::new();
+        self.flush(&mut state, &mut out);
+        *state = FitState::default();
+
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.lines_out += out.len();
+        tracing::debug!(
+            "Arc fitting: {} -> {} lines ({} arcs replaced {} segments)",
+            stats.lines_in,
+            stats.lines_out,
+            stats.arcs_created,
+            stats.segments_replaced
+        );
+        Ok(out)
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+}
+
+struct FitSettings {
+    tolerance: f64,
+    min_segments: usize,
+    max_radius: f64,
+}
+
+/// Arc plane with its two in-plane axes and the normal axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Plane {
+    #[default]
+    XY,
+    ZX,
+    YZ,
+}
+
+impl Plane {
+    /// Indices of the first and second plane axis and the normal axis
+    fn axes(&self) -> (usize, usize, usize) {
+        match self {
+            Self::XY => (0, 1, 2),
+            Self::ZX => (2, 0, 1),
+            Self::YZ => (1, 2, 0),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BufferedMove {
+    command: GcodeCommand,
+    end: [f64; 3],
+    feed: Option<f64>,
+}
+
+/// Modal state and the run being collected
+#[derive(Debug)]
+struct FitState {
+    position: [Option<f64>; 3],
+    relative: bool,
+    inches: bool,
+    plane: Plane,
+    /// Motion mode the program relies on
+    program_motion: u8,
+    /// Motion mode the controller is in after the emitted commands
+    emitted_motion: Option<u8>,
+    run_start: [f64; 3],
+    run: Vec<BufferedMove>,
+}
+
+impl Default for FitState {
+    fn default() -> Self {
+        Self {
+            position: [None; 3],
+            relative: false,
+            inches: false,
+            plane: Plane::XY,
+            program_motion: 0,
+            emitted_motion: None,
+            run_start: [0.0; 3],
+            run: Vec::new(),
+        }
+    }
+}
+
+impl FitState {
+    fn known_position(&self) -> Option<[f64; 3]> {
+        Some([self.position[0]?, self.position[1]?, self.position[2]?])
+    }
+
+    /// End point of a line that can join the current run
+    fn candidate(&self, line: &Line) -> Option<[f64; 3]> {
+        let motion = line.motion.unwrap_or(self.program_motion);
+        if !line.plain || motion != 1 || self.relative || !line.has_axes() {
+            return None;
+        }
+        // The run needs a known start; after that unspecified axes are known
+        let start = self.known_position()?;
+        let mut end = start;
+        for (axis, value) in line.axes.iter().enumerate() {
+            if let Some(v) = value {
+                end[axis] = *v;
+            }
+        }
+        Some(end)
+    }
+
+    /// Track modal state and position for a line passed through unchanged
+    fn update(&mut self, line: &Line) {
+        for g in &line.g_codes {
+            match *g {
+                17 => self.plane = Plane::XY,
+                18 => self.plane = Plane::ZX,
+                19 => self.plane = Plane::YZ,
+                20 => self.inches = true,
+                21 => self.inches = false,
+                90 => self.relative = false,
+                91 => self.relative = true,
+                80 => self.program_motion = 0,
+                _ => {}
+            }
+        }
+        if let Some(motion) = line.motion {
+            self.program_motion = motion;
+        }
+
+        if line.non_modal_axes {
+            // G28/G30/G53/G38 move in other frames, G92 redefines the position
+            for (axis, value) in line.axes.iter().enumerate() {
+                if value.is_some() || line.homes {
+                    self.position[axis] = if line.sets_position { *value } else { None };
+                }
+            }
+            return;
+        }
+        if line.has_axes() {
+            for (axis, value) in line.axes.iter().enumerate() {
+                if let Some(v) = value {
+                    self.position[axis] = if self.relative {
+                        self.position[axis].map(|p| p + v)
+                    } else {
+                        Some(*v)
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Words of one command relevant to arc fitting
+#[derive(Debug, Default)]
+struct Line {
+    /// Whole-number G codes other than motion
+    g_codes: Vec<u32>,
+    motion: Option<u8>,
+    axes: [Option<f64>; 3],
+    feed: Option<f64>,
+    /// Only G1/X/Y/Z/F words and no comments
+    plain: bool,
+    /// Axis words do not describe a move in the motion mode
+    non_modal_axes: bool,
+    /// G92: axis words set the current position
+    sets_position: bool,
+    /// G28/G30: every axis may move
+    homes: bool,
+}
+
+impl Line {
+    fn parse(text: &str) -> Self {
+        let mut line = Line {
+            plain: !text.contains(['(', ';']),
+            ..Default::default()
+        };
+        // Words only; comments are dropped
+        let mut code = String::with_capacity(text.len());
+        let mut in_comment = false;
+        for c in text.chars() {
+            match c {
+                '(' => in_comment = true,
+                ')' => in_comment = false,
+                ';' if !in_comment => break,
+                _ if !in_comment && !c.is_whitespace() => code.push(c.to_ascii_uppercase()),
+                _ => {}
+            }
+        }
+        let mut chars = code.chars().peekable();
+        while let Some(c) = chars.next() {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek() {
+                if d.is_ascii_digit() || matches!(d, '.' | '-' | '+') {
+                    number.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let value = number.parse::<f64>().ok();
+            match (c, value) {
+                ('G', Some(g)) if matches!(g, 0.0 | 1.0 | 2.0 | 3.0) => {
+                    line.motion = Some(g as u8);
+                    line.plain &= g == 1.0;
+                }
+                ('G', Some(g)) => {
+                    line.plain = false;
+                    if g.fract() == 0.0 {
+                        line.g_codes.push(g as u32);
+                    } else if g.floor() == 38.0 {
+                        line.non_modal_axes = true;
+                    } else if g.floor() == 92.0 {
+                        // G92.1/G92.2 reset the offsets
+                        line.non_modal_axes = true;
+                        line.homes = true;
+                    }
+                }
+                ('X', Some(v)) => line.axes[0] = Some(v),
+                ('Y', Some(v)) => line.axes[1] = Some(v),
+                ('Z', Some(v)) => line.axes[2] = Some(v),
+                ('F', Some(v)) => line.feed = Some(v),
+                _ => line.plain = false,
+            }
+        }
+        for g in &line.g_codes {
+            match g {
+                28 | 30 => {
+                    line.non_modal_axes = true;
+                    line.homes = true;
+                }
+                53 => line.non_modal_axes = true,
+                92 => {
+                    line.non_modal_axes = true;
+                    line.sets_position = true;
+                }
+                // Axis words are parameters here, not a move
+                4 | 10 => line.non_modal_axes = true,
+                _ => {}
+            }
+        }
+        line
+    }
+
+    fn has_axes(&self) -> bool {
+        self.axes.iter().any(Option::is_some)
+    }
+
+    /// Move that relies on the modal motion mode
+    fn is_modal_move(&self) -> bool {
+        self.motion.is_none() && self.has_axes() && !self.non_modal_axes
+    }
+}
+
+/// Motion word (G0-G3) in a command, if any
+fn motion_word(text: &str) -> Option<u8> {
+    Line::parse(text).motion
+}
+
+struct FittedArc {
+    /// Centre in plane coordinates
+    center: (f64, f64),
+    clockwise: bool,
+}
+
+/// Fit one arc through all points, or `None` if any point or segment
+/// midpoint is further than the tolerance from it
+fn fit_arc(
+    points: &[[f64; 3]],
+    (a, b, n): (usize, usize, usize),
+    settings: &FitSettings,
+) -> Option<FittedArc> {
+    let plane = |p: &[f64; 3]| (p[a], p[b]);
+    let first = plane(&points[0]);
+    let last = plane(&points[points.len() - 1]);
+    let middle = plane(&points[points.len() / 2]);
+    let (center, radius) = circle_through(first, middle, last)?;
+    if radius > settings.max_radius {
+        return None;
+    }
+
+    let tolerance = settings.tolerance;
+    let distance = |p: (f64, f64)| ((p.0 - center.0).powi(2) + (p.1 - center.1).powi(2)).sqrt();
+    let angle = |p: (f64, f64)| (p.1 - center.1).atan2(p.0 - center.0);
+
+    // Every step must turn the same way by less than half a circle
+    let mut sweeps = Vec::with_capacity(points.len());
+    let mut total = 0.0;
+    for pair in points.windows(2) {
+        let (p, q) = (plane(&pair[0]), plane(&pair[1]));
+        let mid = ((p.0 + q.0) / 2.0, (p.1 + q.1) / 2.0);
+        if (distance(q) - radius).abs() > tolerance || (distance(mid) - radius).abs() > tolerance {
+            return None;
+        }
+        let mut step = angle(q) - angle(p);
+        if step > PI {
+            step -= TAU;
+        } else if step <= -PI {
+            step += TAU;
+        }
+        if step.abs() < 1e-12 || (total != 0.0 && step.signum() != f64::signum(total)) {
+            return None;
+        }
+        total += step;
+        sweeps.push(total);
+    }
+    if total.abs() >= TAU - 1e-6 {
+        return None;
+    }
+
+    // Movement along the normal must be linear in the swept angle (helix)
+    let (start_n, end_n) = (points[0][n], points[points.len() - 1][n]);
+    for (point, sweep) in points[1..].iter().zip(&sweeps) {
+        let expected = start_n + (end_n - start_n) * sweep / total;
+        if (point[n] - expected).abs() > tolerance {
+            return None;
+        }
+    }
+
+    Some(FittedArc {
+        center,
+        clockwise: total < 0.0,
+    })
+}
+
+/// Centre and radius of the circle through three points
+fn circle_through(p1: (f64, f64), p2: (f64, f64), p3: (f64, f64)) -> Option<((f64, f64), f64)> {
+    let (bx, by) = (p2.0 - p1.0, p2.1 - p1.1);
+    let (cx, cy) = (p3.0 - p1.0, p3.1 - p1.1);
+    let d = 2.0 * (bx * cy - by * cx);
+    let scale = (bx * bx + by * by).max(cx * cx + cy * cy);
+    if scale < 1e-18 || d.abs() < 1e-12 * scale {
+        return None;
+    }
+    let b2 = bx * bx + by * by;
+    let c2 = cx * cx + cy * cy;
+    let ux = (cy * b2 - by * c2) / d;
+    let uy = (bx * c2 - cx * b2) / d;
+    Some(((p1.0 + ux, p1.1 + uy), (ux * ux + uy * uy).sqrt()))
+}
+
+/// Format a coordinate with up to 4 decimals and no trailing zeros
+fn format_number(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" | "" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
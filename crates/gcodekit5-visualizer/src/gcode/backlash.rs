@@ -0,0 +1,686 @@
+//! Backlash compensation processor
+//!
+//! Lead-screw machines lose motion when an axis changes direction: the nut
+//! has to cross the play in the thread before the carriage follows again,
+//! and GRBL has no setting to make up for it. This processor tracks the
+//! direction of travel on each axis, inserts a short take-up move when it
+//! reverses, and shifts later absolute coordinates by the play taken up so
+//! the tool lands where the program asked.
+
+use std::f64::consts::{FRAC_PI_2, TAU};
+use std::sync::Mutex;
+
+use super::program::{self, ModalState};
+use super::transform::{format_number, tokenize, Token, Word};
+use super::{CommandProcessor, GcodeCommand, GcodeState, ProcessorConfig};
+
+const EPSILON: f64 = 1e-9;
+const AXES: [char; 3] = ['X', 'Y', 'Z'];
+const ARC_OFFSETS: [char; 3] = ['I', 'J', 'K'];
+/// Angle stepped along an arc to read the direction of travel next to a point
+const ARC_PROBE_ANGLE: f64 = 1e-4;
+
+/// Backlash Compensator
+///
+/// Backlash is given per axis in millimetres; axes without backlash are
+/// left alone. The first move along an axis is assumed to start with the
+/// play already taken up in its direction. From then on every reversal
+/// inserts a take-up move along the reversing axes only, at the modal feed
+/// (as a rapid before rapids and in inverse-time mode), and later absolute
+/// coordinates on those axes are shifted by the play taken up.
+///
+/// Arcs are split where an axis with backlash reaches its extreme, since it
+/// reverses there, except in inverse-time mode. Moves in machine
+/// coordinates (G53), homing, probing and work-offset changes pass through
+/// without updating the tracked direction, as do the holes of canned
+/// cycles, whose coordinates are still shifted.
+#[derive(Debug)]
+pub struct BacklashCompensator {
+    backlash: [f64; 3],
+    config: ProcessorConfig,
+    state: Mutex<BacklashState>,
+}
+
+impl BacklashCompensator {
+    /// Compensate `x`, `y` and `z` millimetres of backlash
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        let backlash = [x, y, z].map(|b| if b.is_finite() { b.max(0.0) } else { 0.0 });
+        let config = ProcessorConfig::new()
+            .with_option("x", backlash[0].to_string())
+            .with_option("y", backlash[1].to_string())
+            .with_option("z", backlash[2].to_string());
+        Self {
+            backlash,
+            config,
+            state: Mutex::new(BacklashState::default()),
+        }
+    }
+
+    /// Backlash per axis (X, Y, Z) in millimetres
+    pub fn backlash(&self) -> [f64; 3] {
+        self.backlash
+    }
+
+    /// Whether any axis is compensated
+    pub fn is_active(&self) -> bool {
+        self.backlash.iter().any(|b| *b > 0.0)
+    }
+
+    /// Take-up moves inserted since creation
+    pub fn moves_inserted(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .inserted
+    }
+
+    /// Compensate a whole program, reporting errors with their line number
+    pub fn apply_to_program(&self, gcode: &str) -> Result<String, String> {
+        program::apply_to_program(gcode, |c, s| self.process(c, s), |s| self.finish(s))
+    }
+
+    fn compensate_line(
+        &self,
+        text: &str,
+        state: &mut BacklashState,
+    ) -> Result<Vec<String>, String> {
+        let tokens = tokenize(text)?;
+        let words = program::words(&tokens);
+        let value = |letter: char| program::value(&words, letter);
+
+        // A take-up before this line runs in the modes set by earlier lines
+        let before = Modes::of(state);
+        let explicit_motion = state.modal.apply(&words);
+        let axes = [value('X'), value('Y'), value('Z')];
+
+        // Machine coordinates, homing, work offsets and dwell stay as they are
+        if state.modal.pass_through(&words) {
+            return Ok(vec![text.to_string()]);
+        }
+        if words
+            .iter()
+            .any(|w| w.letter == 'G' && w.value.floor() == 38.0)
+        {
+            state.modal.forget_named_axes(&words);
+            return Ok(vec![text.to_string()]);
+        }
+
+        // G92 names the current position; the controller's is offset too
+        if program::has_g(&words, 92.0) {
+            let out = self.shifted_axes(state, &tokens, false);
+            for (pos, axis) in state.modal.pos.iter_mut().zip(axes) {
+                if axis.is_some() {
+                    *pos = axis;
+                }
+            }
+            return Ok(vec![rebuild(&tokens, out, None, None)]);
+        }
+
+        let canned = state.modal.is_canned();
+        let arc = state.modal.motion == 20 || state.modal.motion == 30;
+        let has_arc_words = ['I', 'J', 'K', 'R'].iter().any(|l| value(*l).is_some());
+        let linear = state.modal.motion == 0 || state.modal.motion == 10;
+        if !(axes.iter().any(Option::is_some) || (arc && has_arc_words))
+            || !(linear || arc || canned)
+        {
+            return Ok(vec![text.to_string()]);
+        }
+
+        let target: [Option<f64>; 3] = std::array::from_fn(|i| match axes[i] {
+            Some(v) if state.modal.relative => state.modal.pos[i].map(|p| p + v),
+            Some(v) => Some(v),
+            None => state.modal.pos[i],
+        });
+
+        if canned {
+            let out = self.shifted_axes(state, &tokens, state.modal.relative);
+            let retract = self.shifted_center_words(state, &words, true);
+            state.modal.pos = [target[0], target[1], None];
+            return Ok(vec![rebuild(&tokens, out, Some(retract), None)]);
+        }
+
+        if arc {
+            if let Some(lines) = self.compensate_arc(state, before, &tokens, explicit_motion)? {
+                return Ok(lines);
+            }
+        }
+
+        // Straight move, or an arc whose start is unknown
+        let mut directions = [0; 3];
+        for axis in 0..3 {
+            directions[axis] = match (axes[axis], state.modal.pos[axis], target[axis]) {
+                (Some(v), _, _) if state.modal.relative => direction_of(v),
+                (Some(_), Some(from), Some(to)) => direction_of(to - from),
+                _ => 0,
+            };
+        }
+        let mut lines = Vec::new();
+        let rapid = state.modal.motion == 0;
+        if let Some(take_up) = self.take_up(state, before, directions, rapid, feed_word(&words)) {
+            lines.push(take_up);
+        }
+        let motion = (!lines.is_empty()
+            && !explicit_motion
+            && state.modal.motion != take_up_motion(state, rapid))
+        .then(|| motion_word(state.modal.motion));
+        let out = self.shifted_axes(state, &tokens, state.modal.relative);
+        let arc_words = (arc && state.modal.arc_absolute)
+            .then(|| self.shifted_center_words(state, &words, false));
+        lines.push(rebuild(&tokens, out, arc_words, motion));
+        state.modal.pos = target;
+        Ok(lines)
+    }
+
+    /// Compensate an arc; `None` when its start is unknown
+    fn compensate_arc(
+        &self,
+        state: &mut BacklashState,
+        before: Modes,
+        tokens: &[Token],
+        explicit_motion: bool,
+    ) -> Result<Option<Vec<String>>, String> {
+        let words = program::words(tokens);
+        let value = |letter: char| program::value(&words, letter);
+        let (a, b, n) = match state.modal.plane {
+            18 => (2, 0, 1),
+            19 => (1, 2, 0),
+            _ => (0, 1, 2),
+        };
+        // Incremental arcs only need the start for absolute take-ups
+        let known = state.modal.pos.map(|p| p.is_some());
+        if !state.modal.relative
+            && !(known[a] && known[b] && (known[n] || value(AXES[n]).is_none()))
+        {
+            return Ok(None);
+        }
+        if state.modal.relative && state.modal.arc_absolute {
+            return Ok(None);
+        }
+        let start: [f64; 3] = std::array::from_fn(|i| state.modal.pos[i].unwrap_or(0.0));
+        let (sa, sb) = (start[a], start[b]);
+        let mut end = start;
+        for (i, letter) in AXES.into_iter().enumerate() {
+            if let Some(v) = value(letter) {
+                end[i] = if state.modal.relative {
+                    start[i] + v
+                } else {
+                    v
+                };
+            }
+        }
+        let end_pos: [Option<f64>; 3] = std::array::from_fn(|i| known[i].then_some(end[i]));
+        let ccw = state.modal.motion == 30;
+
+        let center = if let Some(r) = value('R') {
+            arc_center_from_radius((sa, sb), (end[a], end[b]), r, ccw)?
+        } else if state.modal.arc_absolute {
+            (
+                value(ARC_OFFSETS[a]).unwrap_or(sa),
+                value(ARC_OFFSETS[b]).unwrap_or(sb),
+            )
+        } else {
+            (
+                sa + value(ARC_OFFSETS[a]).unwrap_or(0.0),
+                sb + value(ARC_OFFSETS[b]).unwrap_or(0.0),
+            )
+        };
+        let radius = (sa - center.0).hypot(sb - center.1);
+        if radius < EPSILON {
+            return Err("arc has zero radius".to_string());
+        }
+        let start_angle = (sb - center.1).atan2(sa - center.0);
+        let end_angle = (end[b] - center.1).atan2(end[a] - center.0);
+        let mut sweep = end_angle - start_angle;
+        if ccw && sweep <= EPSILON {
+            sweep += TAU;
+        } else if !ccw && sweep >= -EPSILON {
+            sweep -= TAU;
+        }
+        let point_at = |t: f64| -> [f64; 3] {
+            let angle = start_angle + sweep * t;
+            let mut p = [0.0; 3];
+            p[a] = center.0 + radius * angle.cos();
+            p[b] = center.1 + radius * angle.sin();
+            p[n] = start[n] + (end[n] - start[n]) * t;
+            p
+        };
+        let plane_directions = |angle: f64| -> [i8; 3] {
+            let angle = angle + ARC_PROBE_ANGLE * sweep.signum();
+            let (sin, cos) = angle.sin_cos();
+            let (da, db) = if ccw { (-sin, cos) } else { (sin, -cos) };
+            let mut d = [0; 3];
+            d[a] = direction_of(da);
+            d[b] = direction_of(db);
+            d[n] = direction_of(end[n] - start[n]);
+            d
+        };
+
+        let mut lines = Vec::new();
+        if let Some(take_up) = self.take_up(
+            state,
+            before,
+            plane_directions(start_angle),
+            false,
+            feed_word(&words),
+        ) {
+            lines.push(take_up);
+        }
+
+        // Axis extremes passed on the way, in travel order
+        let (low, high) = if sweep > 0.0 {
+            (start_angle, start_angle + sweep)
+        } else {
+            (start_angle + sweep, start_angle)
+        };
+        let mut extremes: Vec<(f64, usize, f64)> = Vec::new();
+        if !state.modal.inverse_time {
+            let first = (low / FRAC_PI_2).ceil() as i64;
+            let last = (high / FRAC_PI_2).floor() as i64;
+            for k in first..=last {
+                let angle = k as f64 * FRAC_PI_2;
+                let t = (angle - start_angle) / sweep;
+                let axis = if k.rem_euclid(2) == 0 { a } else { b };
+                if t > EPSILON && t < 1.0 - EPSILON && self.backlash[axis] > 0.0 {
+                    extremes.push((t, axis, angle));
+                }
+            }
+            extremes.sort_by(|x, y| x.0.total_cmp(&y.0));
+        }
+
+        let take_up_first = !lines.is_empty();
+        if extremes.is_empty() {
+            let motion =
+                (take_up_first && !explicit_motion).then(|| motion_word(state.modal.motion));
+            let out = self.shifted_axes(state, tokens, state.modal.relative);
+            let arc_words = state
+                .modal
+                .arc_absolute
+                .then(|| self.shifted_center_words(state, &words, false));
+            lines.push(rebuild(tokens, out, arc_words, motion));
+            state.modal.pos = end_pos;
+            return Ok(Some(lines));
+        }
+
+        let mut from = start;
+        let mut first = true;
+        let segments = extremes
+            .iter()
+            .map(|&(t, axis, angle)| (point_at(t), Some((axis, angle))))
+            .chain(std::iter::once((end, None)));
+        for (to, reversal) in segments {
+            let segment = self.arc_segment(state, (a, b), center, from, to);
+            if first {
+                let out: [Option<String>; 3] = segment.0;
+                let motion =
+                    (take_up_first && !explicit_motion).then(|| motion_word(state.modal.motion));
+                lines.push(rebuild(tokens, out, Some(segment.1), motion));
+                first = false;
+            } else {
+                let mut text = motion_word(state.modal.motion);
+                for word in segment.0.into_iter().flatten().chain(segment.1) {
+                    text.push(' ');
+                    text.push_str(&word);
+                }
+                lines.push(text);
+            }
+            if let Some((axis, angle)) = reversal {
+                let mut directions = [0; 3];
+                directions[axis] = plane_directions(angle)[axis];
+                let modes = Modes::of(state);
+                state.modal.pos = to.map(Some);
+                if let Some(take_up) = self.take_up(state, modes, directions, false, None) {
+                    lines.push(take_up);
+                }
+            }
+            from = to;
+        }
+        state.modal.pos = end_pos;
+        Ok(Some(lines))
+    }
+
+    /// End and centre words for the part of an arc from `from` to `to`
+    fn arc_segment(
+        &self,
+        state: &BacklashState,
+        (a, b): (usize, usize),
+        center: (f64, f64),
+        from: [f64; 3],
+        to: [f64; 3],
+    ) -> ([Option<String>; 3], Vec<String>) {
+        let digits = digits(state.modal.inches);
+        let axes = std::array::from_fn(|i| {
+            if i != a && i != b && (to[i] - from[i]).abs() < EPSILON {
+                return None;
+            }
+            let v = if state.modal.relative {
+                to[i] - from[i]
+            } else {
+                to[i] + state.offset_in_units(i)
+            };
+            Some(format!("{}{}", AXES[i], format_number(v, digits)))
+        });
+        let centre = [(a, center.0), (b, center.1)].map(|(i, c)| {
+            let v = if state.modal.arc_absolute {
+                c + state.offset_in_units(i)
+            } else {
+                c - from[i]
+            };
+            format!("{}{}", ARC_OFFSETS[i], format_number(v, digits))
+        });
+        let mut words = centre.to_vec();
+        words.sort_by_key(|w| w.chars().next());
+        (axes, words)
+    }
+
+    /// Take-up move for axes reversing into `directions`; updates the offsets
+    fn take_up(
+        &self,
+        state: &mut BacklashState,
+        modes: Modes,
+        directions: [i8; 3],
+        rapid: bool,
+        feed: Option<&str>,
+    ) -> Option<String> {
+        let mut words = Vec::new();
+        for (axis, &direction) in directions.iter().enumerate() {
+            if direction == 0 || self.backlash[axis] <= 0.0 {
+                continue;
+            }
+            let previous = std::mem::replace(&mut state.direction[axis], direction);
+            if previous != -direction {
+                continue;
+            }
+            let scale = if modes.inches { 1.0 / 25.4 } else { 1.0 };
+            let old = state.offset[axis];
+            let new = old + direction as f64 * self.backlash[axis];
+            let v = match (modes.relative, state.modal.pos[axis]) {
+                (true, _) => (new - old) * scale,
+                (false, Some(pos)) => pos + new * scale,
+                // Nowhere to write an absolute take-up to
+                (false, None) => continue,
+            };
+            state.offset[axis] = new;
+            words.push(format!(
+                "{}{}",
+                AXES[axis],
+                format_number(v, digits(modes.inches))
+            ));
+        }
+        if words.is_empty() {
+            return None;
+        }
+        state.inserted += 1;
+        let mut text = motion_word(take_up_motion(state, rapid));
+        for word in words {
+            text.push(' ');
+            text.push_str(&word);
+        }
+        if let (Some(feed), 10) = (feed, take_up_motion(state, rapid)) {
+            text.push(' ');
+            text.push_str(feed);
+        }
+        Some(text)
+    }
+
+    /// Axis words, with absolute values shifted by the current offsets
+    fn shifted_axes(
+        &self,
+        state: &BacklashState,
+        tokens: &[Token],
+        relative: bool,
+    ) -> [Option<String>; 3] {
+        let digits = digits(state.modal.inches);
+        std::array::from_fn(|i| {
+            let word = tokens.iter().find_map(|t| match t {
+                Token::Word(w) if w.letter == AXES[i] => Some(w),
+                _ => None,
+            })?;
+            let offset = state.offset_in_units(i);
+            if relative || offset == 0.0 {
+                return Some(word.text.clone());
+            }
+            Some(format!(
+                "{}{}",
+                AXES[i],
+                format_number(word.value + offset, digits)
+            ))
+        })
+    }
+
+    /// I/J/K/R words with the absolute ones shifted by the current offsets:
+    /// arc centres in G90.1, or the retract height of a canned cycle
+    fn shifted_center_words(
+        &self,
+        state: &BacklashState,
+        words: &[&Word],
+        canned: bool,
+    ) -> Vec<String> {
+        words
+            .iter()
+            .filter(|w| matches!(w.letter, 'I' | 'J' | 'K' | 'R'))
+            .map(|w| {
+                let axis = match (w.letter, canned) {
+                    ('R', true) if !state.modal.relative => Some(2),
+                    ('I' | 'J' | 'K', false) => ARC_OFFSETS.iter().position(|l| *l == w.letter),
+                    _ => None,
+                };
+                match axis {
+                    Some(i) if state.offset[i] != 0.0 => format!(
+                        "{}{}",
+                        w.letter,
+                        format_number(
+                            w.value + state.offset_in_units(i),
+                            digits(state.modal.inches)
+                        )
+                    ),
+                    _ => w.text.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl CommandProcessor for BacklashCompensator {
+    fn name(&self) -> &str {
+        "backlash_compensation"
+    }
+
+    fn description(&self) -> &str {
+        "Inserts take-up moves when an axis reverses to make up for backlash"
+    }
+
+    fn process(
+        &self,
+        command: &GcodeCommand,
+        _state: &GcodeState,
+    ) -> Result<Vec<GcodeCommand>, String> {
+        if !self.is_active() {
+            return Ok(vec![command.clone()]);
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let lines = self.compensate_line(&command.command, &mut state)?;
+        if lines.len() == 1 && lines[0] == command.command {
+            return Ok(vec![command.clone()]);
+        }
+        Ok(lines
+            .into_iter()
+            .map(|text| {
+                let mut processed = command.clone();
+                processed.command = text.clone();
+                processed.line = text;
+                processed
+            })
+            .collect())
+    }
+
+    fn finish(&self, _state: &GcodeState) -> Result<Vec<GcodeCommand>, String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state = BacklashState {
+            inserted: state.inserted,
+            ..BacklashState::default()
+        };
+        Ok(vec![])
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+}
+
+/// Distance and unit modes a take-up move is written in
+#[derive(Debug, Clone, Copy)]
+struct Modes {
+    relative: bool,
+    inches: bool,
+}
+
+impl Modes {
+    fn of(state: &BacklashState) -> Self {
+        Self {
+            relative: state.modal.relative,
+            inches: state.modal.inches,
+        }
+    }
+}
+
+/// Modal state, program position and the play taken up on each axis
+#[derive(Debug, Default)]
+struct BacklashState {
+    modal: ModalState,
+    /// Last direction of travel, 0 until the axis first moves
+    direction: [i8; 3],
+    /// Commanded minus program position, in millimetres
+    offset: [f64; 3],
+    inserted: usize,
+}
+
+impl BacklashState {
+    fn offset_in_units(&self, axis: usize) -> f64 {
+        if self.modal.inches {
+            self.offset[axis] / 25.4
+        } else {
+            self.offset[axis]
+        }
+    }
+}
+
+/// Text of the line's F word
+fn feed_word<'a>(words: &[&'a Word]) -> Option<&'a str> {
+    words
+        .iter()
+        .find(|w| w.letter == 'F')
+        .map(|w| w.text.as_str())
+}
+
+fn direction_of(delta: f64) -> i8 {
+    if delta > EPSILON {
+        1
+    } else if delta < -EPSILON {
+        -1
+    } else {
+        0
+    }
+}
+
+fn digits(inches: bool) -> usize {
+    if inches {
+        5
+    } else {
+        4
+    }
+}
+
+/// Motion mode of a take-up move before a move in `state.modal.motion`
+fn take_up_motion(state: &BacklashState, rapid: bool) -> i32 {
+    if rapid || state.modal.inverse_time {
+        0
+    } else {
+        10
+    }
+}
+
+fn motion_word(motion: i32) -> String {
+    format!("G{}", motion / 10)
+}
+
+/// Centre of an R-form arc, as controllers compute it
+fn arc_center_from_radius(
+    start: (f64, f64),
+    end: (f64, f64),
+    r: f64,
+    ccw: bool,
+) -> Result<(f64, f64), String> {
+    let (x, y) = (end.0 - start.0, end.1 - start.1);
+    let chord = x.hypot(y);
+    if chord < EPSILON {
+        return Err("R-form arc needs distinct start and end points".to_string());
+    }
+    let discriminant = 4.0 * r * r - chord * chord;
+    if discriminant < -1e-6 * r * r {
+        return Err(format!("arc radius {} is too small for its end point", r));
+    }
+    let mut h = -discriminant.max(0.0).sqrt() / chord;
+    if ccw {
+        h = -h;
+    }
+    if r < 0.0 {
+        h = -h;
+    }
+    Ok((start.0 + (x - y * h) / 2.0, start.1 + (y + x * h) / 2.0))
+}
+
+/// Rebuild a line with new axis and arc words in place of the old ones
+fn rebuild(
+    tokens: &[Token],
+    axes: [Option<String>; 3],
+    arc_words: Option<Vec<String>>,
+    motion: Option<String>,
+) -> String {
+    let mut parts: Vec<String> = Vec::with_capacity(tokens.len() + 2);
+    let mut axes = Some(axes);
+    let replace_arc_words = arc_words.is_some();
+    let mut arc_words = arc_words;
+    let mut first_comment = None;
+    for token in tokens {
+        let word = match token {
+            Token::Comment(c) => {
+                first_comment.get_or_insert(parts.len());
+                parts.push(c.clone());
+                continue;
+            }
+            Token::Word(w) => w,
+        };
+        match word.letter {
+            'X' | 'Y' | 'Z' => {
+                if let Some(axes) = axes.take() {
+                    parts.extend(axes.into_iter().flatten());
+                }
+            }
+            'I' | 'J' | 'K' | 'R' if replace_arc_words => {
+                if let Some(words) = arc_words.take() {
+                    parts.extend(words);
+                }
+            }
+            _ => parts.push(word.text.clone()),
+        }
+    }
+
+    // Words the original line did not have go before its comments
+    let mut missing: Vec<String> = axes.into_iter().flatten().flatten().collect();
+    missing.extend(arc_words.into_iter().flatten());
+    let at = first_comment.unwrap_or(parts.len());
+    parts.splice(at..at, missing);
+
+    if let Some(motion) = motion {
+        let at = match tokens.first() {
+            Some(Token::Word(w)) if w.letter == 'N' => 1,
+            _ => 0,
+        };
+        parts.insert(at.min(parts.len()), motion);
+    }
+    parts.join(" ")
+}
@@ -0,0 +1,1056 @@
+//! Laser processors
+//!
+//! Vector and raster files from other tools are often written for constant
+//! power (M3), which burns corners and the ends of raster lines where the
+//! head slows down. These processors adapt such programs to a laser: switch
+//! to dynamic power (M4), add overscan to raster lines, scale power by feed
+//! or a material power curve, clamp S to the controller's maximum and drop
+//! S words that repeat the modal power.
+//!
+//! [`LaserSettings`] builds the processors a device profile asks for into a
+//! pipeline.
+
+use std::sync::{Arc, Mutex};
+
+use super::program;
+use super::transform::{format_number, tokenize, Token, Word};
+use super::{
+    CommandProcessor, GcodeCommand, GcodeState, ProcessorConfig, ProcessorHandle, ProcessorPipeline,
+};
+
+const EPSILON: f64 = 1e-9;
+/// Digits kept when writing S words
+const POWER_DIGITS: usize = 3;
+
+/// Laser settings applied to a program before it is sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaserSettings {
+    /// Convert M3 (constant power) to M4 (dynamic power)
+    pub dynamic_power: bool,
+    /// Overscan added to each end of raster lines, in millimetres
+    pub overscan: f64,
+    /// Scale S by feed rate or a power curve
+    pub power_scaling: Option<PowerScaling>,
+    /// Largest S value the controller accepts
+    pub max_power: Option<f64>,
+    /// Drop S words that repeat the modal power
+    pub strip_redundant_power: bool,
+}
+
+impl Default for LaserSettings {
+    fn default() -> Self {
+        Self {
+            dynamic_power: true,
+            overscan: 0.0,
+            power_scaling: None,
+            max_power: None,
+            strip_redundant_power: true,
+        }
+    }
+}
+
+impl LaserSettings {
+    /// Processors for these settings, in the order they run
+    pub fn processors(&self) -> Vec<ProcessorHandle> {
+        let mut processors: Vec<ProcessorHandle> = Vec::new();
+        if self.dynamic_power {
+            processors.push(Arc::new(DynamicPowerProcessor::new()));
+        }
+        if let Some(scaling) = &self.power_scaling {
+            processors.push(Arc::new(PowerScaleProcessor::new(scaling.clone())));
+        }
+        if let Some(max) = self.max_power {
+            processors.push(Arc::new(PowerClampProcessor::new(max)));
+        }
+        if self.overscan > 0.0 {
+            processors.push(Arc::new(OverscanProcessor::new(self.overscan)));
+        }
+        if self.strip_redundant_power {
+            processors.push(Arc::new(RedundantPowerRemover::new()));
+        }
+        processors
+    }
+
+    /// Pipeline running [`Self::processors`]
+    pub fn pipeline(&self) -> ProcessorPipeline {
+        let mut pipeline = ProcessorPipeline::new();
+        pipeline.register_all(self.processors());
+        pipeline
+    }
+
+    /// Process a whole program, reporting errors with their line number
+    pub fn apply_to_program(&self, gcode: &str) -> Result<String, String> {
+        let pipeline = self.pipeline();
+        program::apply_to_program(
+            gcode,
+            |c, s| pipeline.process_command(c, s),
+            |s| pipeline.finish(s),
+        )
+    }
+}
+
+/// Dynamic Power Processor
+///
+/// Replaces M3 with M4 so the controller scales laser power with the actual
+/// speed of the head, which keeps corners and accelerations from burning.
+#[derive(Debug)]
+pub struct DynamicPowerProcessor {
+    config: ProcessorConfig,
+}
+
+impl DynamicPowerProcessor {
+    /// Create a new dynamic power processor
+    pub fn new() -> Self {
+        Self {
+            config: ProcessorConfig::new(),
+        }
+    }
+}
+
+impl Default for DynamicPowerProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandProcessor for DynamicPowerProcessor {
+    fn name(&self) -> &str {
+        "laser_dynamic_power"
+    }
+
+    fn description(&self) -> &str {
+        "Converts constant laser power (M3) to dynamic power (M4)"
+    }
+
+    fn process(
+        &self,
+        command: &GcodeCommand,
+        _state: &GcodeState,
+    ) -> Result<Vec<GcodeCommand>, String> {
+        let mut line = Line::parse(&command.command)?;
+        let mut changed = false;
+        for token in &mut line.tokens {
+            if let Token::Word(word) = token {
+                if word.letter == 'M' && is_code(word.value, 3.0) {
+                    *word = Word {
+                        letter: 'M',
+                        value: 4.0,
+                        text: "M4".to_string(),
+                    };
+                    changed = true;
+                }
+            }
+        }
+        Ok(vec![if changed {
+            with_text(command, line.text())
+        } else {
+            command.clone()
+        }])
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+}
+
+/// How [`PowerScaleProcessor`] scales power
+#[derive(Debug, Clone, PartialEq)]
+pub enum PowerScaling {
+    /// Full power at `reference_feed` (mm/min) and above, proportionally
+    /// less at slower feeds
+    ByFeed { reference_feed: f64 },
+    /// Power looked up from a material power curve
+    Curve(PowerCurve),
+}
+
+impl PowerScaling {
+    /// Fraction of the programmed power used at `feed` mm/min
+    pub fn factor(&self, feed: f64) -> f64 {
+        match self {
+            PowerScaling::ByFeed { reference_feed } => {
+                if *reference_feed > 0.0 {
+                    (feed / reference_feed).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                }
+            }
+            PowerScaling::Curve(curve) => curve.factor(feed),
+        }
+    }
+}
+
+/// Material power curve: percent of the programmed power by feed rate
+///
+/// Points are (feed in mm/min, percent). Feeds between points are
+/// interpolated linearly; feeds outside the curve use its nearest end.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PowerCurve {
+    points: Vec<(f64, f64)>,
+}
+
+impl PowerCurve {
+    /// Create a curve, ignoring points that are not finite
+    pub fn new(points: impl IntoIterator<Item = (f64, f64)>) -> Self {
+        let mut points: Vec<(f64, f64)> = points
+            .into_iter()
+            .filter(|(feed, percent)| feed.is_finite() && percent.is_finite())
+            .map(|(feed, percent)| (feed, percent.max(0.0)))
+            .collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { points }
+    }
+
+    /// Points of the curve, sorted by feed
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    /// Fraction of the programmed power used at `feed` mm/min
+    pub fn factor(&self, feed: f64) -> f64 {
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return 1.0;
+        };
+        if feed <= first.0 {
+            return first.1 / 100.0;
+        }
+        if feed >= last.0 {
+            return last.1 / 100.0;
+        }
+        let percent = self
+            .points
+            .windows(2)
+            .find(|pair| feed <= pair[1].0)
+            .map(|pair| {
+                let (a, b) = (pair[0], pair[1]);
+                if b.0 - a.0 < EPSILON {
+                    b.1
+                } else {
+                    a.1 + (b.1 - a.1) * (feed - a.0) / (b.0 - a.0)
+                }
+            })
+            .unwrap_or(last.1);
+        percent / 100.0
+    }
+}
+
+/// Power Scale Processor
+///
+/// Scales S by the modal feed rate, either proportionally to a reference
+/// feed or through a material power curve. S words are rewritten in place,
+/// and cutting moves get an S word when the feed changes the power they
+/// should run at. Inverse-time feeds are left unscaled.
+#[derive(Debug)]
+pub struct PowerScaleProcessor {
+    scaling: PowerScaling,
+    config: ProcessorConfig,
+    state: Mutex<ScaleState>,
+}
+
+impl PowerScaleProcessor {
+    /// Scale power with `scaling`
+    pub fn new(scaling: PowerScaling) -> Self {
+        let config = match &scaling {
+            PowerScaling::ByFeed { reference_feed } => ProcessorConfig::new()
+                .with_option("mode", "feed")
+                .with_option("reference_feed", reference_feed.to_string()),
+            PowerScaling::Curve(curve) => ProcessorConfig::new()
+                .with_option("mode", "curve")
+                .with_option("points", curve.points.len().to_string()),
+        };
+        Self {
+            scaling,
+            config,
+            state: Mutex::new(ScaleState::default()),
+        }
+    }
+
+    /// Full power at `reference_feed` mm/min, less at slower feeds
+    pub fn by_feed(reference_feed: f64) -> Self {
+        Self::new(PowerScaling::ByFeed { reference_feed })
+    }
+
+    /// Power from a material power curve
+    pub fn with_curve(curve: PowerCurve) -> Self {
+        Self::new(PowerScaling::Curve(curve))
+    }
+
+    /// The scaling applied
+    pub fn scaling(&self) -> &PowerScaling {
+        &self.scaling
+    }
+}
+
+#[derive(Debug, Default)]
+struct ScaleState {
+    modal: LaserModal,
+    /// Power last written to the output
+    written: Option<f64>,
+}
+
+impl CommandProcessor for PowerScaleProcessor {
+    fn name(&self) -> &str {
+        "laser_power_scale"
+    }
+
+    fn description(&self) -> &str {
+        "Scales laser power by feed rate or a material power curve"
+    }
+
+    fn process(
+        &self,
+        command: &GcodeCommand,
+        _state: &GcodeState,
+    ) -> Result<Vec<GcodeCommand>, String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut line = Line::parse(&command.command)?;
+        let step = state.modal.apply(&line);
+        let modal = state.modal;
+
+        let factor = match modal.feed {
+            Some(feed) if !modal.inverse_time => {
+                self.scaling
+                    .factor(if modal.inches { feed * 25.4 } else { feed })
+            }
+            _ => 1.0,
+        };
+        let power = round_power(modal.power * factor);
+
+        if line.value('S').is_some() {
+            state.written = Some(power);
+            if line.value('S') != Some(power) {
+                line.set('S', format_number(power, POWER_DIGITS));
+                return Ok(vec![with_text(command, line.text())]);
+            }
+        } else if step.moved
+            && modal.is_cutting_motion()
+            && state.written.is_some_and(|w| (w - power).abs() > EPSILON)
+        {
+            state.written = Some(power);
+            line.set('S', format_number(power, POWER_DIGITS));
+            return Ok(vec![with_text(command, line.text())]);
+        }
+        Ok(vec![command.clone()])
+    }
+
+    fn finish(&self, _state: &GcodeState) -> Result<Vec<GcodeCommand>, String> {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = ScaleState::default();
+        Ok(vec![])
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+}
+
+/// Power Clamp Processor
+///
+/// Limits S words to the controller's maximum (GRBL `$30`, the device
+/// profile's `max_s_value`) and to zero at the bottom.
+#[derive(Debug)]
+pub struct PowerClampProcessor {
+    max: f64,
+    config: ProcessorConfig,
+}
+
+impl PowerClampProcessor {
+    /// Clamp S to `max`; a maximum that is not positive disables clamping
+    pub fn new(max: f64) -> Self {
+        let max = if max.is_finite() && max > 0.0 {
+            max
+        } else {
+            f64::INFINITY
+        };
+        Self {
+            max,
+            config: ProcessorConfig::new().with_option("max", max.to_string()),
+        }
+    }
+
+    /// Largest S value let through
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+}
+
+impl CommandProcessor for PowerClampProcessor {
+    fn name(&self) -> &str {
+        "laser_power_clamp"
+    }
+
+    fn description(&self) -> &str {
+        "Clamps laser power to the controller's maximum S value"
+    }
+
+    fn process(
+        &self,
+        command: &GcodeCommand,
+        _state: &GcodeState,
+    ) -> Result<Vec<GcodeCommand>, String> {
+        let mut line = Line::parse(&command.command)?;
+        match line.value('S') {
+            Some(s) if s > self.max || s < 0.0 => {
+                line.set('S', format_number(s.clamp(0.0, self.max), POWER_DIGITS));
+                Ok(vec![with_text(command, line.text())])
+            }
+            _ => Ok(vec![command.clone()]),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+}
+
+/// Redundant Power Remover
+///
+/// Drops S words that repeat the modal power, and lines left empty by it.
+/// The modal power is forgotten at program end (M2/M30).
+#[derive(Debug)]
+pub struct RedundantPowerRemover {
+    config: ProcessorConfig,
+    power: Mutex<Option<f64>>,
+}
+
+impl RedundantPowerRemover {
+    /// Create a new redundant power remover
+    pub fn new() -> Self {
+        Self {
+            config: ProcessorConfig::new(),
+            power: Mutex::new(None),
+        }
+    }
+}
+
+impl Default for RedundantPowerRemover {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandProcessor for RedundantPowerRemover {
+    fn name(&self) -> &str {
+        "laser_strip_redundant_s"
+    }
+
+    fn description(&self) -> &str {
+        "Removes S words that repeat the modal laser power"
+    }
+
+    fn process(
+        &self,
+        command: &GcodeCommand,
+        _state: &GcodeState,
+    ) -> Result<Vec<GcodeCommand>, String> {
+        let mut power = self.power.lock().unwrap_or_else(|e| e.into_inner());
+        let mut line = Line::parse(&command.command)?;
+        let ends = line.has('M', 2.0) || line.has('M', 30.0);
+        let Some(s) = line.value('S') else {
+            if ends {
+                *power = None;
+            }
+            return Ok(vec![command.clone()]);
+        };
+
+        let repeated = power.is_some_and(|p| (p - s).abs() < EPSILON);
+        *power = if ends { None } else { Some(s) };
+        if !repeated {
+            return Ok(vec![command.clone()]);
+        }
+        line.remove('S');
+        if line.tokens.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(vec![with_text(command, line.text())])
+    }
+
+    fn finish(&self, _state: &GcodeState) -> Result<Vec<GcodeCommand>, String> {
+        *self.power.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(vec![])
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+}
+
+/// Overscan Processor
+///
+/// Extends raster lines so the head is already up to speed when the laser
+/// starts burning and only slows down after it stops. A raster line is a
+/// run of burning G1 moves along one axis (X or Y) in one direction, reached
+/// by a rapid or laser-off positioning move. The positioning move is pulled
+/// back by the overscan distance, a laser-off lead-in runs up to the start
+/// of the line and a laser-off lead-out carries on past its end.
+///
+/// Lines are held back until their raster line ends, and released in
+/// [`CommandProcessor::finish`] at the end of the program. Relative moves,
+/// arcs and diagonal burns pass through unchanged.
+#[derive(Debug)]
+pub struct OverscanProcessor {
+    distance: f64,
+    config: ProcessorConfig,
+    state: Mutex<OverscanState>,
+}
+
+impl OverscanProcessor {
+    /// Add `distance` millimetres of overscan to each end of raster lines
+    pub fn new(distance: f64) -> Self {
+        let distance = if distance.is_finite() {
+            distance.max(0.0)
+        } else {
+            0.0
+        };
+        Self {
+            distance,
+            config: ProcessorConfig::new().with_option("distance", distance.to_string()),
+            state: Mutex::new(OverscanState::default()),
+        }
+    }
+
+    /// Overscan in millimetres
+    pub fn distance(&self) -> f64 {
+        self.distance
+    }
+
+    /// Raster lines extended since creation
+    pub fn lines_extended(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extended
+    }
+
+    fn overscan_line(&self, text: &str, state: &mut OverscanState) -> Result<Vec<String>, String> {
+        let mut line = Line::parse(text)?;
+        let before = state.modal;
+        let step = state.modal.apply(&line);
+        let after = state.modal;
+        let burning = step.moved && after.is_burning();
+        let segment = raster_axis(&before, &after, &step).filter(|_| burning);
+        let positioning = step.moved
+            && !burning
+            && !after.relative
+            && matches!(after.motion, 0 | 1)
+            && after.pos[0].is_some()
+            && after.pos[1].is_some();
+        let holdable = !step.touches_axes && !changes_laser(&line);
+
+        let mut out = Vec::new();
+        if let Some(mut run) = state.run.take() {
+            if let Some((axis, direction)) = segment {
+                if axis == run.axis && direction == run.direction {
+                    run.end = after.pos[axis].unwrap_or(run.end);
+                    run.last = state.held.len();
+                    run.end_power = after.power;
+                    state.held.push((text.to_string(), true));
+                    state.run = Some(run);
+                    return Ok(out);
+                }
+            }
+            if holdable {
+                run.end_power = after.power;
+                state.held.push((text.to_string(), false));
+                state.run = Some(run);
+                return Ok(out);
+            }
+            if burning {
+                state.flush(&mut out);
+            } else {
+                self.extend_run(run, state, &mut out);
+            }
+        }
+
+        // Bring the machine back in line after a lead-out
+        let mut text = text.to_string();
+        if let Some(displaced) = state.displaced.take() {
+            if step.touches_axes {
+                let letter = RASTER_AXES[displaced.axis];
+                if !after.relative && line.value(letter).is_some() {
+                    // The line moves the axis itself
+                } else if positioning {
+                    line.set(
+                        letter,
+                        format_number(
+                            after.pos[displaced.axis].unwrap_or(0.0),
+                            digits(after.inches),
+                        ),
+                    );
+                    text = line.text();
+                } else if before.relative {
+                    out.push(format!(
+                        "G0 {}{}",
+                        letter,
+                        format_number(-displaced.offset, digits(before.inches))
+                    ));
+                } else {
+                    out.push(format!(
+                        "G0 {}{}",
+                        letter,
+                        format_number(displaced.program, digits(before.inches))
+                    ));
+                }
+            } else {
+                state.displaced = Some(displaced);
+            }
+        }
+
+        if state.positioned {
+            if let Some((axis, direction)) = segment {
+                state.run = Some(Run {
+                    axis,
+                    direction,
+                    start: before.pos[axis].unwrap_or(0.0),
+                    end: after.pos[axis].unwrap_or(0.0),
+                    first: state.held.len(),
+                    last: state.held.len(),
+                    power: after.power,
+                    end_power: after.power,
+                    inches: after.inches,
+                });
+                state.positioned = false;
+                state.held.push((text, true));
+                return Ok(out);
+            }
+            if holdable {
+                state.held.push((text, false));
+                return Ok(out);
+            }
+            state.positioned = false;
+            state.flush(&mut out);
+        }
+
+        if positioning {
+            state.positioned = true;
+            state.held.push((text, false));
+        } else {
+            state.emit(text, burning, &mut out);
+        }
+        Ok(out)
+    }
+
+    /// Release a finished raster line with its lead-in and lead-out
+    fn extend_run(&self, run: Run, state: &mut OverscanState, out: &mut Vec<String>) {
+        let letter = RASTER_AXES[run.axis];
+        let digits = digits(run.inches);
+        let distance = if run.inches {
+            self.distance / 25.4
+        } else {
+            self.distance
+        };
+        let lead_in = run.start - run.direction * distance;
+        let lead_out = run.end + run.direction * distance;
+        let mut held = std::mem::take(&mut state.held);
+
+        // Pull the positioning move back and run up to the start laser-off
+        if let Some((positioning, _)) = held.first_mut() {
+            if let Ok(mut line) = Line::parse(positioning) {
+                line.set(letter, format_number(lead_in, digits));
+                *positioning = line.text();
+            }
+        }
+        let feed = Line::parse(&held[run.first].0)
+            .ok()
+            .and_then(|line| line.word('F').map(|w| w.text.clone()));
+        let mut lead_in_line = format!("G1 {}{} S0", letter, format_number(run.start, digits));
+        if let Some(feed) = feed {
+            lead_in_line.push(' ');
+            lead_in_line.push_str(&feed);
+        }
+        if let Ok(mut first) = Line::parse(&held[run.first].0) {
+            if first.value('S').is_none() {
+                first.set('S', format_number(run.power, POWER_DIGITS));
+                held[run.first].0 = first.text();
+            }
+        }
+
+        let tail = held.split_off(run.last + 1);
+        for (index, (text, _)) in held.into_iter().enumerate() {
+            if index == run.first {
+                out.push(lead_in_line.clone());
+            }
+            out.push(text);
+        }
+        out.push(format!(
+            "G1 {}{} S0",
+            letter,
+            format_number(lead_out, digits)
+        ));
+        state.restore_power = Some(run.end_power);
+        state.displaced = Some(Displaced {
+            axis: run.axis,
+            program: run.end,
+            offset: run.direction * distance,
+        });
+        state.extended += 1;
+        for (text, burning) in tail {
+            state.emit(text, burning, out);
+        }
+    }
+}
+
+impl CommandProcessor for OverscanProcessor {
+    fn name(&self) -> &str {
+        "laser_overscan"
+    }
+
+    fn description(&self) -> &str {
+        "Adds laser-off lead-in and lead-out moves to raster lines"
+    }
+
+    fn process(
+        &self,
+        command: &GcodeCommand,
+        _state: &GcodeState,
+    ) -> Result<Vec<GcodeCommand>, String> {
+        if self.distance <= 0.0 {
+            return Ok(vec![command.clone()]);
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let lines = self.overscan_line(&command.command, &mut state)?;
+        if lines.len() == 1 && lines[0] == command.command {
+            return Ok(vec![command.clone()]);
+        }
+        Ok(lines
+            .into_iter()
+            .map(|text| with_text(command, text))
+            .collect())
+    }
+
+    fn finish(&self, _state: &GcodeState) -> Result<Vec<GcodeCommand>, String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = Vec::new();
+        if let Some(run) = state.run.take() {
+            self.extend_run(run, &mut state, &mut out);
+        } else {
+            state.flush(&mut out);
+        }
+        *state = OverscanState {
+            extended: state.extended,
+            ..OverscanState::default()
+        };
+        Ok(out.into_iter().map(GcodeCommand::new).collect())
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+}
+
+const RASTER_AXES: [char; 2] = ['X', 'Y'];
+
+/// A raster line being collected
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    /// Index into [`RASTER_AXES`]
+    axis: usize,
+    direction: f64,
+    start: f64,
+    end: f64,
+    /// Held lines of the first and last burning moves
+    first: usize,
+    last: usize,
+    /// Power of the first move and at the end of the line
+    power: f64,
+    end_power: f64,
+    inches: bool,
+}
+
+/// Where the machine was left by a lead-out
+#[derive(Debug, Clone, Copy)]
+struct Displaced {
+    axis: usize,
+    /// Program position on the axis
+    program: f64,
+    /// Machine minus program position
+    offset: f64,
+}
+
+#[derive(Debug, Default)]
+struct OverscanState {
+    modal: LaserModal,
+    /// Lines held back with whether they burn; a positioning move first
+    held: Vec<(String, bool)>,
+    /// A positioning move is held and no raster line has started yet
+    positioned: bool,
+    run: Option<Run>,
+    displaced: Option<Displaced>,
+    /// Power to put back on the next burning move after a lead-out
+    restore_power: Option<f64>,
+    extended: usize,
+}
+
+impl OverscanState {
+    fn flush(&mut self, out: &mut Vec<String>) {
+        self.positioned = false;
+        for (text, burning) in std::mem::take(&mut self.held) {
+            self.emit(text, burning, out);
+        }
+    }
+
+    fn emit(&mut self, text: String, burning: bool, out: &mut Vec<String>) {
+        let Ok(mut line) = Line::parse(&text) else {
+            out.push(text);
+            return;
+        };
+        if line.value('S').is_some() {
+            self.restore_power = None;
+        } else if burning {
+            if let Some(power) = self.restore_power.take() {
+                line.set('S', format_number(power, POWER_DIGITS));
+                out.push(line.text());
+                return;
+            }
+        }
+        out.push(text);
+    }
+}
+
+/// Axis and direction of a single-axis G1 move in absolute mode
+fn raster_axis(before: &LaserModal, after: &LaserModal, step: &Step) -> Option<(usize, f64)> {
+    if !step.moved || after.relative || after.motion != 1 || step.moved_z {
+        return None;
+    }
+    let deltas: Vec<f64> = (0..2)
+        .map(|axis| Some(after.pos[axis]? - before.pos[axis]?))
+        .collect::<Option<_>>()?;
+    match (deltas[0].abs() > EPSILON, deltas[1].abs() > EPSILON) {
+        (true, false) => Some((0, deltas[0].signum())),
+        (false, true) => Some((1, deltas[1].signum())),
+        _ => None,
+    }
+}
+
+/// Whether a line switches the laser or ends the program
+fn changes_laser(line: &Line) -> bool {
+    line.words().any(|w| {
+        w.letter == 'M'
+            && [2.0, 3.0, 4.0, 5.0, 30.0]
+                .iter()
+                .any(|c| is_code(w.value, *c))
+    })
+}
+
+fn digits(inches: bool) -> usize {
+    if inches {
+        5
+    } else {
+        4
+    }
+}
+
+fn round_power(power: f64) -> f64 {
+    let scale = 10f64.powi(POWER_DIGITS as i32);
+    (power * scale).round() / scale
+}
+
+fn is_code(value: f64, code: f64) -> bool {
+    (value - code).abs() < 1e-6
+}
+
+fn with_text(command: &GcodeCommand, text: String) -> GcodeCommand {
+    let mut processed = command.clone();
+    processed.command = text.clone();
+    processed.line = text;
+    processed
+}
+
+/// A line split into words and comments that can be edited word by word
+#[derive(Debug)]
+struct Line {
+    tokens: Vec<Token>,
+}
+
+impl Line {
+    fn parse(text: &str) -> Result<Self, String> {
+        Ok(Self {
+            tokens: tokenize(text)?,
+        })
+    }
+
+    fn words(&self) -> impl Iterator<Item = &Word> {
+        self.tokens.iter().filter_map(|t| match t {
+            Token::Word(w) => Some(w),
+            Token::Comment(_) => None,
+        })
+    }
+
+    fn word(&self, letter: char) -> Option<&Word> {
+        self.words().find(|w| w.letter == letter)
+    }
+
+    fn value(&self, letter: char) -> Option<f64> {
+        self.word(letter).map(|w| w.value)
+    }
+
+    fn has(&self, letter: char, code: f64) -> bool {
+        self.words()
+            .any(|w| w.letter == letter && is_code(w.value, code))
+    }
+
+    /// Replace the value of a word, adding it before any comment if missing
+    fn set(&mut self, letter: char, number: String) {
+        let word = Word {
+            letter,
+            value: number.parse().unwrap_or(0.0),
+            text: format!("{}{}", letter, number),
+        };
+        for token in &mut self.tokens {
+            if let Token::Word(w) = token {
+                if w.letter == letter {
+                    *w = word;
+                    return;
+                }
+            }
+        }
+        let at = self
+            .tokens
+            .iter()
+            .position(|t| matches!(t, Token::Comment(_)))
+            .unwrap_or(self.tokens.len());
+        self.tokens.insert(at, Token::Word(word));
+    }
+
+    fn remove(&mut self, letter: char) {
+        self.tokens
+            .retain(|t| !matches!(t, Token::Word(w) if w.letter == letter));
+    }
+
+    fn text(&self) -> String {
+        self.tokens
+            .iter()
+            .map(|t| match t {
+                Token::Word(w) => w.text.as_str(),
+                Token::Comment(c) => c.as_str(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Laser-relevant modal state and program position
+#[derive(Debug, Clone, Copy)]
+struct LaserModal {
+    /// X, Y, Z in program coordinates
+    pos: [Option<f64>; 3],
+    /// Motion mode G0–G3, or -1 for canned cycles and G80
+    motion: i32,
+    /// Programmed S
+    power: f64,
+    feed: Option<f64>,
+    /// Laser switched on with M3 or M4
+    laser_on: bool,
+    relative: bool,
+    inches: bool,
+    inverse_time: bool,
+}
+
+impl Default for LaserModal {
+    fn default() -> Self {
+        Self {
+            pos: [None; 3],
+            motion: 0,
+            power: 0.0,
+            feed: None,
+            laser_on: false,
+            relative: false,
+            inches: false,
+            inverse_time: false,
+        }
+    }
+}
+
+/// What a line did to the position
+#[derive(Debug, Clone, Copy, Default)]
+struct Step {
+    /// A G0–G3 move along X, Y or Z
+    moved: bool,
+    moved_z: bool,
+    /// The line has X, Y or Z words
+    touches_axes: bool,
+}
+
+impl LaserModal {
+    fn is_cutting_motion(&self) -> bool {
+        matches!(self.motion, 1..=3)
+    }
+
+    fn is_burning(&self) -> bool {
+        self.laser_on && self.power > 0.0 && self.is_cutting_motion()
+    }
+
+    fn apply(&mut self, line: &Line) -> Step {
+        let mut non_modal = false;
+        for word in line.words() {
+            match word.letter {
+                'G' => match (word.value * 10.0).round() as i32 {
+                    code @ (0 | 10 | 20 | 30) => self.motion = code / 10,
+                    800..=890 => self.motion = -1,
+                    200 => self.inches = true,
+                    210 => self.inches = false,
+                    900 => self.relative = false,
+                    910 => self.relative = true,
+                    930 => self.inverse_time = true,
+                    940 | 950 => self.inverse_time = false,
+                    40 | 100 | 280 | 300 | 382..=385 | 530 | 920..=923 => non_modal = true,
+                    _ => {}
+                },
+                'M' => match word.value.round() as i32 {
+                    3 | 4 => self.laser_on = true,
+                    5 | 2 | 30 => self.laser_on = false,
+                    _ => {}
+                },
+                'S' => self.power = word.value,
+                'F' => self.feed = Some(word.value),
+                _ => {}
+            }
+        }
+
+        let mut step = Step::default();
+        for (axis, letter) in ['X', 'Y', 'Z'].into_iter().enumerate() {
+            let Some(value) = line.value(letter) else {
+                continue;
+            };
+            step.touches_axes = true;
+            if non_modal || self.motion < 0 {
+                // Cycles, machine coordinates and offsets leave the
+                // program position somewhere this tracker does not follow
+                self.pos[axis] = None;
+                continue;
+            }
+            let to = if self.relative {
+                self.pos[axis].map(|p| p + value)
+            } else {
+                Some(value)
+            };
+            if to
+                .zip(self.pos[axis])
+                .is_none_or(|(to, from)| (to - from).abs() > EPSILON)
+            {
+                step.moved = true;
+                step.moved_z |= axis == 2;
+            }
+            self.pos[axis] = to;
+        }
+        step
+    }
+}
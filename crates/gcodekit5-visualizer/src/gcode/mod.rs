@@ -8,14 +8,26 @@
 //! - Command lifecycle management
 //! - Command listener framework
 //! - Stream management (reading from files or strings)
+//! - Parametric expansion of parameters and O-word control flow
 
+pub mod arc_fitter;
+pub mod backlash;
 pub mod command;
+pub mod laser;
+pub mod parametric;
 pub mod parser;
 pub mod pipeline;
 pub mod processors;
+mod program;
 pub mod stream;
+pub mod transform;
 
+pub use arc_fitter::*;
+pub use backlash::*;
 pub use command::*;
+pub use laser::*;
+pub use parametric::*;
 pub use parser::*;
 pub use pipeline::*;
 pub use processors::*;
+pub use transform::*;
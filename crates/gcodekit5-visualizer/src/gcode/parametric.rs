@@ -0,0 +1,1710 @@
+//! Parametric G-code expansion
+//!
+//! Hand-written probing and fixture programs often use LinuxCNC-style
+//! parameters, expressions and O-word control flow, which GRBL and most
+//! hobby firmwares cannot run. This module interprets that dialect and
+//! emits flat G-code:
+//!
+//! - numbered (`#1`) and named (`#<depth>`) parameters, with `#1`–`#30` and
+//!   names not starting with `_` local to subroutine calls
+//! - expressions in brackets: `+ - * / MOD **`, comparisons (`EQ NE GT GE
+//!   LT LE`), `AND OR XOR` and the functions `SIN COS TAN ASIN ACOS ATAN
+//!   SQRT ABS EXP LN ROUND FIX FUP EXISTS` (angles in degrees)
+//! - O-words: `sub`/`endsub`/`return`/`call`, `if`/`elseif`/`else`/`endif`,
+//!   `while`/`endwhile`, `do`/`while`, `repeat`/`endrepeat`, `break` and
+//!   `continue`
+//! - machine-state parameters: the last probe result (`#5061`–`#5063`, with
+//!   `#5070` set when it touched) and the current position (`#5420`–`#5422`
+//!   or `#<_x>`, `#<_y>`, `#<_z>`)
+//!
+//! [`ParametricExpander`] runs a program one output line at a time, so
+//! machine-state parameters can be resolved live while streaming: a probe
+//! result is recorded with [`ParametricExpander::record_probe`] and the
+//! expander waits for it before running anything that reads it. Expanded
+//! offline, probe moves are assumed to touch at their end point.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use super::transform::format_number;
+use super::{CommandProcessor, GcodeCommand, GcodeState, ProcessorConfig};
+
+/// Statements run before a program is assumed to loop forever
+const MAX_STEPS: usize = 10_000_000;
+/// Deepest subroutine nesting allowed
+const MAX_CALL_DEPTH: usize = 64;
+/// Digits kept when writing evaluated words
+const DIGITS: usize = 4;
+/// Subroutine arguments and locals, `#1`–`#30`
+const LOCALS: usize = 30;
+/// Highest parameter number
+const MAX_PARAMETER: u32 = 5602;
+const PROBE_PARAMETERS: [u32; 3] = [5061, 5062, 5063];
+const PROBE_SUCCESS_PARAMETER: u32 = 5070;
+const POSITION_PARAMETERS: [u32; 3] = [5420, 5421, 5422];
+
+/// An error in a parametric program, with the source line it occurred on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionError {
+    /// 1-based source line
+    pub line: usize,
+    pub message: String,
+}
+
+impl ExpansionError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ExpansionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ExpansionError {}
+
+/// A line of flat G-code and the source line it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpandedLine {
+    pub text: String,
+    /// 1-based source line
+    pub source_line: usize,
+}
+
+/// Result of asking a [`ParametricExpander`] for more output
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpansionStep {
+    Line(ExpandedLine),
+    /// The next statement reads a probe result that has not been recorded
+    WaitingForProbe,
+    Finished,
+}
+
+/// A parsed parametric program
+#[derive(Debug, Clone)]
+pub struct ParametricProgram {
+    statements: Vec<Statement>,
+    /// Subroutine name to the index of its `sub` statement
+    subs: HashMap<String, usize>,
+    reads_machine_state: bool,
+}
+
+impl ParametricProgram {
+    /// Parse a program, collecting the errors of every line
+    pub fn parse(source: &str) -> Result<Self, Vec<ExpansionError>> {
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
+        for (index, text) in source.lines().enumerate() {
+            let line = index + 1;
+            match parse_line(text) {
+                Ok((label, kind)) => statements.push(Statement { line, label, kind }),
+                Err(message) => {
+                    errors.push(ExpansionError::new(line, message));
+                    statements.push(Statement {
+                        line,
+                        label: None,
+                        kind: Kind::Empty,
+                    });
+                }
+            }
+        }
+        let subs = match resolve_blocks(&mut statements) {
+            Ok(subs) => subs,
+            Err(mut block_errors) => {
+                errors.append(&mut block_errors);
+                HashMap::new()
+            }
+        };
+        if !errors.is_empty() {
+            errors.sort_by_key(|e| e.line);
+            return Err(errors);
+        }
+        let reads_machine_state = statements.iter().any(|s| s.kind.reads_machine_state());
+        Ok(Self {
+            statements,
+            subs,
+            reads_machine_state,
+        })
+    }
+
+    /// Whether a program uses parameters or O-word control flow
+    pub fn is_parametric(source: &str) -> bool {
+        source.lines().any(|line| {
+            let code = strip_comments(line);
+            let code = code.trim_start();
+            code.contains('#')
+                || (code.starts_with(['O', 'o'])
+                    && code[1..]
+                        .trim_start()
+                        .starts_with(|c: char| c == '<' || c.is_ascii_digit())
+                    && code.chars().skip(1).any(|c| c.is_ascii_alphabetic()))
+        })
+    }
+
+    /// Whether the program reads the probe result or the current position
+    pub fn reads_machine_state(&self) -> bool {
+        self.reads_machine_state
+    }
+
+    /// Names of the subroutines defined in the program
+    pub fn subroutines(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.subs.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Runs a [`ParametricProgram`], producing flat G-code one line at a time
+#[derive(Debug, Clone)]
+pub struct ParametricExpander {
+    program: ParametricProgram,
+    pc: usize,
+    frames: Vec<Frame>,
+    numbered: HashMap<u32, f64>,
+    named: HashMap<String, f64>,
+    machine: MachineState,
+    live: bool,
+    steps: usize,
+    finished: bool,
+}
+
+impl ParametricExpander {
+    /// Expand offline: probe moves are assumed to touch at their end point
+    pub fn new(program: ParametricProgram) -> Self {
+        Self {
+            program,
+            pc: 0,
+            frames: vec![Frame::new(0)],
+            numbered: HashMap::new(),
+            named: HashMap::new(),
+            machine: MachineState::default(),
+            live: false,
+            steps: 0,
+            finished: false,
+        }
+    }
+
+    /// Expand while streaming: probe results come from
+    /// [`Self::record_probe`], and reading one waits until it is recorded
+    pub fn live(program: ParametricProgram) -> Self {
+        Self {
+            live: true,
+            ..Self::new(program)
+        }
+    }
+
+    /// Set the current work position, e.g. from the machine before starting
+    pub fn set_position(&mut self, position: [f64; 3]) {
+        self.machine.pos = position.map(Some);
+    }
+
+    /// Record the result of the last probe move, in work coordinates
+    pub fn record_probe(&mut self, position: [f64; 3], success: bool) {
+        self.machine.probe = position;
+        self.machine.probe_success = success;
+        self.machine.pos = position.map(Some);
+        self.machine.probe_pending = false;
+    }
+
+    /// Whether a probe move has been sent without its result recorded
+    pub fn is_waiting_for_probe(&self) -> bool {
+        self.machine.probe_pending
+    }
+
+    /// Value of a numbered parameter as the program currently sees it
+    pub fn parameter(&self, number: u32) -> Option<f64> {
+        self.read_numbered(number).ok()
+    }
+
+    /// Run until the next output line, a missing probe result or the end
+    pub fn next_step(&mut self) -> Result<ExpansionStep, ExpansionError> {
+        while !self.finished {
+            let Some(statement) = self.program.statements.get(self.pc) else {
+                self.finished = true;
+                break;
+            };
+            self.steps += 1;
+            if self.steps > MAX_STEPS {
+                return Err(ExpansionError::new(
+                    statement.line,
+                    format!(
+                        "program did not finish after {} statements; check loop conditions",
+                        MAX_STEPS
+                    ),
+                ));
+            }
+            let line = statement.line;
+            match self.execute(self.pc) {
+                Ok(Some(text)) => {
+                    return Ok(ExpansionStep::Line(ExpandedLine {
+                        text,
+                        source_line: line,
+                    }))
+                }
+                Ok(None) => {}
+                Err(Failure::Pending) => {
+                    self.steps -= 1;
+                    return Ok(ExpansionStep::WaitingForProbe);
+                }
+                Err(Failure::Error(message)) => {
+                    self.finished = true;
+                    return Err(ExpansionError::new(line, message));
+                }
+            }
+        }
+        Ok(ExpansionStep::Finished)
+    }
+
+    /// Expand the rest of the program offline
+    pub fn expand_all(&mut self) -> Result<Vec<ExpandedLine>, ExpansionError> {
+        let mut lines = Vec::new();
+        loop {
+            match self.next_step()? {
+                ExpansionStep::Line(line) => lines.push(line),
+                ExpansionStep::Finished => return Ok(lines),
+                ExpansionStep::WaitingForProbe => {
+                    let line = self.program.statements.get(self.pc).map_or(0, |s| s.line);
+                    return Err(ExpansionError::new(
+                        line,
+                        "probe result needed; expand this program while streaming",
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Run the statement at `index`, returning the line it outputs
+    fn execute(&mut self, index: usize) -> Result<Option<String>, Failure> {
+        let kind = &self.program.statements[index].kind;
+        let mut next = index + 1;
+        let mut output = None;
+        match kind {
+            Kind::Empty | Kind::Do | Kind::EndIf => {}
+            Kind::Code(code) => {
+                let code = code.clone();
+                output = self.run_code(&code)?;
+            }
+            Kind::Sub { end, .. } => next = end + 1,
+            Kind::EndSub { value } | Kind::Return { value } => {
+                let value = value.as_ref().map(|v| self.eval(v)).transpose()?;
+                next = self.return_from_sub(value)?;
+            }
+            Kind::Call { name, args } => {
+                let Some(&start) = self.program.subs.get(name) else {
+                    return Err(Failure::error(format!(
+                        "subroutine O{} is not defined",
+                        name
+                    )));
+                };
+                if self.frames.len() > MAX_CALL_DEPTH {
+                    return Err(Failure::error("subroutine calls nested too deeply"));
+                }
+                if args.len() > LOCALS {
+                    return Err(Failure::error(format!(
+                        "a call takes at most {} arguments",
+                        LOCALS
+                    )));
+                }
+                let values = args
+                    .iter()
+                    .map(|a| self.eval(a))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut frame = Frame::new(index + 1);
+                frame.locals[..values.len()].copy_from_slice(&values);
+                self.frames.push(frame);
+                next = start + 1;
+            }
+            Kind::If {
+                condition,
+                next: branch,
+            } => {
+                if !truthy(self.eval(condition)?) {
+                    next = self.take_branch(*branch)?;
+                }
+            }
+            Kind::ElseIf { end, .. } | Kind::Else { end } => next = end + 1,
+            Kind::While { condition, end } => {
+                if !truthy(self.eval(condition)?) {
+                    next = end + 1;
+                }
+            }
+            Kind::EndWhile { start } => next = *start,
+            Kind::DoWhile { condition, start } => {
+                if truthy(self.eval(condition)?) {
+                    next = start + 1;
+                }
+            }
+            Kind::Repeat { count, end } => {
+                let count = self.eval(count)?.floor();
+                if count >= 1.0 {
+                    self.frame_mut().repeats.insert(index, count as u64);
+                } else {
+                    next = end + 1;
+                }
+            }
+            Kind::EndRepeat { start } => {
+                let start = *start;
+                let frame = self.frame_mut();
+                let left = frame.repeats.get(&start).copied().unwrap_or(1) - 1;
+                if left > 0 {
+                    frame.repeats.insert(start, left);
+                    next = start + 1;
+                } else {
+                    frame.repeats.remove(&start);
+                }
+            }
+            Kind::Break { start, end } => {
+                let start = *start;
+                next = end + 1;
+                self.frame_mut().repeats.remove(&start);
+            }
+            Kind::Continue { target } => next = *target,
+        }
+        self.pc = next;
+        Ok(output)
+    }
+
+    /// Index to run after an `if` or `elseif` whose condition was false
+    fn take_branch(&mut self, mut branch: usize) -> Result<usize, Failure> {
+        loop {
+            match &self.program.statements[branch].kind {
+                Kind::ElseIf {
+                    condition, next, ..
+                } => {
+                    let (condition, next) = (condition.clone(), *next);
+                    if truthy(self.eval(&condition)?) {
+                        return Ok(branch + 1);
+                    }
+                    branch = next;
+                }
+                _ => return Ok(branch + 1),
+            }
+        }
+    }
+
+    fn return_from_sub(&mut self, value: Option<f64>) -> Result<usize, Failure> {
+        if self.frames.len() < 2 {
+            return Err(Failure::error("return outside a subroutine"));
+        }
+        let frame = self.frames.pop().unwrap_or_else(|| Frame::new(0));
+        self.named
+            .insert("_value".to_string(), value.unwrap_or(0.0));
+        self.named.insert(
+            "_value_returned".to_string(),
+            if value.is_some() { 1.0 } else { 0.0 },
+        );
+        Ok(frame.return_to)
+    }
+
+    fn run_code(&mut self, code: &CodeLine) -> Result<Option<String>, Failure> {
+        // Parameters set on a line take effect after the whole line is read
+        let mut values = Vec::with_capacity(code.words.len());
+        for part in &code.words {
+            values.push(match part {
+                Part::Word { expr, .. } => Some(self.eval(expr)?),
+                Part::Text(_) => None,
+            });
+        }
+        let mut assignments = Vec::with_capacity(code.assignments.len());
+        for (target, expr) in &code.assignments {
+            let target = match target {
+                ParamRef::Numbered(number) => Target::Numbered(self.parameter_number(number)?),
+                ParamRef::Named(name) => Target::Named(name.clone()),
+            };
+            assignments.push((target, self.eval(expr)?));
+        }
+        for (target, value) in assignments {
+            self.assign(target, value)?;
+        }
+
+        let words: Vec<(char, f64)> = code
+            .words
+            .iter()
+            .zip(&values)
+            .filter_map(|(part, value)| match (part, value) {
+                (Part::Word { letter, .. }, Some(v)) => Some((*letter, *v)),
+                _ => None,
+            })
+            .collect();
+        if words.is_empty() && code.verbatim.is_none() {
+            return Ok(None);
+        }
+        self.track_motion(&words);
+        if words
+            .iter()
+            .any(|(l, v)| *l == 'M' && (is_code(*v, 2.0) || is_code(*v, 30.0)))
+        {
+            self.finished = true;
+        }
+        if let Some(text) = &code.verbatim {
+            return Ok(Some(text.clone()));
+        }
+        let parts: Vec<String> = code
+            .words
+            .iter()
+            .zip(values)
+            .map(|(part, value)| match part {
+                Part::Text(text) => text.clone(),
+                Part::Word {
+                    letter, literal, ..
+                } => match literal {
+                    Some(text) => text.clone(),
+                    None => format!("{}{}", letter, format_number(value.unwrap_or(0.0), DIGITS)),
+                },
+            })
+            .collect();
+        Ok(Some(parts.join(" ")))
+    }
+
+    /// Follow the position and probe moves of an output line
+    fn track_motion(&mut self, words: &[(char, f64)]) {
+        let has_g = |code: f64| words.iter().any(|(l, v)| *l == 'G' && is_code(*v, code));
+        if has_g(90.0) {
+            self.machine.relative = false;
+        }
+        if has_g(91.0) {
+            self.machine.relative = true;
+        }
+        let axes: Vec<(usize, f64)> = words
+            .iter()
+            .filter_map(|(letter, value)| {
+                let axis = match letter {
+                    'X' => 0,
+                    'Y' => 1,
+                    'Z' => 2,
+                    _ => return None,
+                };
+                Some((axis, *value))
+            })
+            .collect();
+        if axes.is_empty() {
+            return;
+        }
+        if [53.0, 28.0, 30.0, 10.0, 92.0].iter().any(|c| has_g(*c)) {
+            // Machine coordinates and offset changes: the work position is
+            // no longer known from the program alone
+            for (axis, _) in axes {
+                self.machine.pos[axis] = None;
+            }
+            return;
+        }
+        for (axis, value) in axes {
+            self.machine.pos[axis] = if self.machine.relative {
+                self.machine.pos[axis].map(|p| p + value)
+            } else {
+                Some(value)
+            };
+        }
+        if [38.2, 38.3, 38.4, 38.5].iter().any(|c| has_g(*c)) {
+            if self.live {
+                self.machine.probe_pending = true;
+            } else {
+                self.machine.probe = self.machine.pos.map(|p| p.unwrap_or(0.0));
+                self.machine.probe_success = true;
+            }
+        }
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("the program frame is never popped")
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<f64, Failure> {
+        let value = match expr {
+            Expr::Number(n) => *n,
+            Expr::Param(ParamRef::Numbered(number)) => {
+                self.read_numbered(self.parameter_number(number)?)?
+            }
+            Expr::Param(ParamRef::Named(name)) => self.read_named(name)?,
+            Expr::Exists(name) => {
+                if self.read_named(name).is_ok() {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Expr::Neg(inner) => -self.eval(inner)?,
+            Expr::Func(func, inner) => func.apply(self.eval(inner)?)?,
+            Expr::Atan(y, x) => self.eval(y)?.atan2(self.eval(x)?).to_degrees(),
+            Expr::Binary(op, a, b) => op.apply(self.eval(a)?, self.eval(b)?)?,
+        };
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err(Failure::error("expression does not give a finite number"))
+        }
+    }
+
+    fn parameter_number(&self, expr: &Expr) -> Result<u32, Failure> {
+        let value = self.eval(expr)?;
+        let number = value.round();
+        if (value - number).abs() > 1e-6 || number < 1.0 || number > MAX_PARAMETER as f64 {
+            return Err(Failure::error(format!(
+                "parameter number {} must be a whole number from 1 to {}",
+                format_number(value, DIGITS),
+                MAX_PARAMETER
+            )));
+        }
+        Ok(number as u32)
+    }
+
+    fn read_numbered(&self, number: u32) -> Result<f64, Failure> {
+        if (1..=LOCALS as u32).contains(&number) {
+            return Ok(self
+                .frames
+                .last()
+                .map_or(0.0, |f| f.locals[number as usize - 1]));
+        }
+        if let Some(axis) = PROBE_PARAMETERS.iter().position(|p| *p == number) {
+            self.probe_ready()?;
+            return Ok(self.machine.probe[axis]);
+        }
+        if number == PROBE_SUCCESS_PARAMETER {
+            self.probe_ready()?;
+            return Ok(if self.machine.probe_success { 1.0 } else { 0.0 });
+        }
+        if let Some(axis) = POSITION_PARAMETERS.iter().position(|p| *p == number) {
+            return self.position(axis);
+        }
+        Ok(self.numbered.get(&number).copied().unwrap_or(0.0))
+    }
+
+    fn read_named(&self, name: &str) -> Result<f64, Failure> {
+        match name {
+            "_x" => return self.position(0),
+            "_y" => return self.position(1),
+            "_z" => return self.position(2),
+            _ => {}
+        }
+        let value = if name.starts_with('_') {
+            self.named.get(name)
+        } else {
+            self.frames.last().and_then(|f| f.named.get(name))
+        };
+        value
+            .copied()
+            .ok_or_else(|| Failure::error(format!("parameter #<{}> is not defined", name)))
+    }
+
+    fn probe_ready(&self) -> Result<(), Failure> {
+        if self.machine.probe_pending {
+            Err(Failure::Pending)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn position(&self, axis: usize) -> Result<f64, Failure> {
+        self.probe_ready()?;
+        self.machine.pos[axis].ok_or_else(|| {
+            Failure::error(format!(
+                "the {} position is not known here",
+                ['X', 'Y', 'Z'][axis]
+            ))
+        })
+    }
+
+    fn assign(&mut self, target: Target, value: f64) -> Result<(), Failure> {
+        match target {
+            Target::Numbered(number) if (1..=LOCALS as u32).contains(&number) => {
+                self.frame_mut().locals[number as usize - 1] = value;
+            }
+            Target::Numbered(number)
+                if PROBE_PARAMETERS.contains(&number)
+                    || POSITION_PARAMETERS.contains(&number)
+                    || number == PROBE_SUCCESS_PARAMETER =>
+            {
+                return Err(Failure::error(format!(
+                    "parameter #{} is read-only",
+                    number
+                )));
+            }
+            Target::Numbered(number) => {
+                self.numbered.insert(number, value);
+            }
+            Target::Named(name) if matches!(name.as_str(), "_x" | "_y" | "_z") => {
+                return Err(Failure::error(format!(
+                    "parameter #<{}> is read-only",
+                    name
+                )));
+            }
+            Target::Named(name) if name.starts_with('_') => {
+                self.named.insert(name, value);
+            }
+            Target::Named(name) => {
+                self.frame_mut().named.insert(name, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Expand a program offline into flat G-code
+///
+/// Errors are reported one per line, prefixed with their source line.
+pub fn expand_program(source: &str) -> Result<String, String> {
+    let program = ParametricProgram::parse(source).map_err(|errors| {
+        errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    let lines = ParametricExpander::new(program)
+        .expand_all()
+        .map_err(|e| e.to_string())?;
+    let mut out = String::with_capacity(source.len());
+    for line in lines {
+        out.push_str(&line.text);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Parametric Expansion Processor
+///
+/// Collects the whole program and releases it expanded to flat G-code in
+/// [`CommandProcessor::finish`], since subroutines and loops need lines
+/// that come later. Probe moves are assumed to touch at their end point;
+/// use a live [`ParametricExpander`] to stream programs that read probe
+/// results.
+#[derive(Debug)]
+pub struct ParametricProcessor {
+    config: ProcessorConfig,
+    source: Mutex<Vec<String>>,
+}
+
+impl ParametricProcessor {
+    /// Create a new parametric expansion processor
+    pub fn new() -> Self {
+        Self {
+            config: ProcessorConfig::new(),
+            source: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for ParametricProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandProcessor for ParametricProcessor {
+    fn name(&self) -> &str {
+        "parametric_expansion"
+    }
+
+    fn description(&self) -> &str {
+        "Expands parameters, expressions and O-word subroutines and loops to flat G-code"
+    }
+
+    fn process(
+        &self,
+        command: &GcodeCommand,
+        _state: &GcodeState,
+    ) -> Result<Vec<GcodeCommand>, String> {
+        self.source
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(command.command.clone());
+        Ok(vec![])
+    }
+
+    fn finish(&self, _state: &GcodeState) -> Result<Vec<GcodeCommand>, String> {
+        let source = std::mem::take(&mut *self.source.lock().unwrap_or_else(|e| e.into_inner()));
+        let expanded = expand_program(&source.join("\n"))?;
+        Ok(expanded.lines().map(GcodeCommand::new).collect())
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+}
+
+#[derive(Debug)]
+enum Failure {
+    /// A probe result is needed that has not been recorded yet
+    Pending,
+    Error(String),
+}
+
+impl Failure {
+    fn error(message: impl Into<String>) -> Self {
+        Failure::Error(message.into())
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Error(message)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    /// Statement to continue at after the subroutine returns
+    return_to: usize,
+    locals: [f64; LOCALS],
+    named: HashMap<String, f64>,
+    /// Iterations left of the active `repeat` loops, by statement index
+    repeats: HashMap<usize, u64>,
+}
+
+impl Frame {
+    fn new(return_to: usize) -> Self {
+        Self {
+            return_to,
+            locals: [0.0; LOCALS],
+            named: HashMap::new(),
+            repeats: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct MachineState {
+    /// Work position, unknown until set or moved to
+    pos: [Option<f64>; 3],
+    relative: bool,
+    probe: [f64; 3],
+    probe_success: bool,
+    /// A probe move was output and its result is not recorded yet
+    probe_pending: bool,
+}
+
+enum Target {
+    Numbered(u32),
+    Named(String),
+}
+
+#[derive(Debug, Clone)]
+struct Statement {
+    /// 1-based source line
+    line: usize,
+    /// O-word number or name of a control statement
+    label: Option<String>,
+    kind: Kind,
+}
+
+/// A statement; block statements hold the indices they jump to
+#[derive(Debug, Clone)]
+enum Kind {
+    Empty,
+    Code(CodeLine),
+    Sub {
+        end: usize,
+    },
+    EndSub {
+        value: Option<Expr>,
+    },
+    Return {
+        value: Option<Expr>,
+    },
+    Call {
+        name: String,
+        args: Vec<Expr>,
+    },
+    If {
+        condition: Expr,
+        next: usize,
+    },
+    ElseIf {
+        condition: Expr,
+        next: usize,
+        end: usize,
+    },
+    Else {
+        end: usize,
+    },
+    EndIf,
+    While {
+        condition: Expr,
+        end: usize,
+    },
+    EndWhile {
+        start: usize,
+    },
+    Do,
+    DoWhile {
+        condition: Expr,
+        start: usize,
+    },
+    Repeat {
+        count: Expr,
+        end: usize,
+    },
+    EndRepeat {
+        start: usize,
+    },
+    Break {
+        start: usize,
+        end: usize,
+    },
+    Continue {
+        target: usize,
+    },
+}
+
+impl Kind {
+    fn reads_machine_state(&self) -> bool {
+        let exprs: Vec<&Expr> = match self {
+            Kind::Code(code) => code
+                .words
+                .iter()
+                .filter_map(|p| match p {
+                    Part::Word { expr, .. } => Some(expr),
+                    Part::Text(_) => None,
+                })
+                .chain(code.assignments.iter().map(|(_, e)| e))
+                .collect(),
+            Kind::EndSub { value } | Kind::Return { value } => value.iter().collect(),
+            Kind::Call { args, .. } => args.iter().collect(),
+            Kind::If { condition, .. }
+            | Kind::ElseIf { condition, .. }
+            | Kind::While { condition, .. }
+            | Kind::DoWhile { condition, .. } => vec![condition],
+            Kind::Repeat { count, .. } => vec![count],
+            _ => Vec::new(),
+        };
+        exprs.into_iter().any(Expr::reads_machine_state)
+    }
+}
+
+/// A G-code line with the expressions still to evaluate
+#[derive(Debug, Clone)]
+struct CodeLine {
+    words: Vec<Part>,
+    assignments: Vec<(ParamRef, Expr)>,
+    /// The source text, when the line has nothing to evaluate or assign
+    verbatim: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Word {
+        letter: char,
+        expr: Expr,
+        /// Source text of a word with a plain number
+        literal: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum ParamRef {
+    Numbered(Box<Expr>),
+    Named(String),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    Param(ParamRef),
+    Exists(String),
+    Neg(Box<Expr>),
+    Func(Func, Box<Expr>),
+    Atan(Box<Expr>, Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn reads_machine_state(&self) -> bool {
+        match self {
+            Expr::Number(_) => false,
+            Expr::Param(ParamRef::Numbered(number)) => match number.as_ref() {
+                Expr::Number(n) => {
+                    let n = n.round() as u32;
+                    PROBE_PARAMETERS.contains(&n)
+                        || POSITION_PARAMETERS.contains(&n)
+                        || n == PROBE_SUCCESS_PARAMETER
+                }
+                // Computed parameter numbers may point anywhere
+                _ => true,
+            },
+            Expr::Param(ParamRef::Named(name)) | Expr::Exists(name) => {
+                matches!(name.as_str(), "_x" | "_y" | "_z")
+            }
+            Expr::Neg(inner) | Expr::Func(_, inner) => inner.reads_machine_state(),
+            Expr::Atan(a, b) | Expr::Binary(_, a, b) => {
+                a.reads_machine_state() || b.reads_machine_state()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Sqrt,
+    Abs,
+    Exp,
+    Ln,
+    Round,
+    Fix,
+    Fup,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "SIN" => Func::Sin,
+            "COS" => Func::Cos,
+            "TAN" => Func::Tan,
+            "ASIN" => Func::Asin,
+            "ACOS" => Func::Acos,
+            "SQRT" => Func::Sqrt,
+            "ABS" => Func::Abs,
+            "EXP" => Func::Exp,
+            "LN" => Func::Ln,
+            "ROUND" => Func::Round,
+            "FIX" => Func::Fix,
+            "FUP" => Func::Fup,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, x: f64) -> Result<f64, String> {
+        Ok(match self {
+            Func::Sin => x.to_radians().sin(),
+            Func::Cos => x.to_radians().cos(),
+            Func::Tan => x.to_radians().tan(),
+            Func::Asin | Func::Acos if !(-1.0..=1.0).contains(&x) => {
+                return Err(format!("ASIN/ACOS argument {} is outside -1 to 1", x))
+            }
+            Func::Asin => x.asin().to_degrees(),
+            Func::Acos => x.acos().to_degrees(),
+            Func::Sqrt if x < 0.0 => return Err(format!("SQRT of negative number {}", x)),
+            Func::Sqrt => x.sqrt(),
+            Func::Abs => x.abs(),
+            Func::Exp => x.exp(),
+            Func::Ln if x <= 0.0 => return Err(format!("LN of non-positive number {}", x)),
+            Func::Ln => x.ln(),
+            Func::Round => x.round(),
+            Func::Fix => x.floor(),
+            Func::Fup => x.ceil(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Pow,
+    Mul,
+    Div,
+    Mod,
+    Add,
+    Sub,
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    And,
+    Or,
+    Xor,
+}
+
+impl Op {
+    /// Binding strength; higher binds tighter
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Pow => 4,
+            Op::Mul | Op::Div | Op::Mod => 3,
+            Op::Add | Op::Sub => 2,
+            Op::Eq | Op::Ne | Op::Gt | Op::Ge | Op::Lt | Op::Le => 1,
+            Op::And | Op::Or | Op::Xor => 0,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "MOD" => Op::Mod,
+            "EQ" => Op::Eq,
+            "NE" => Op::Ne,
+            "GT" => Op::Gt,
+            "GE" => Op::Ge,
+            "LT" => Op::Lt,
+            "LE" => Op::Le,
+            "AND" => Op::And,
+            "OR" => Op::Or,
+            "XOR" => Op::Xor,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, a: f64, b: f64) -> Result<f64, String> {
+        let flag = |b: bool| if b { 1.0 } else { 0.0 };
+        Ok(match self {
+            Op::Pow => a.powf(b),
+            Op::Mul => a * b,
+            Op::Div if b == 0.0 => return Err("division by zero".to_string()),
+            Op::Div => a / b,
+            Op::Mod if b == 0.0 => return Err("MOD by zero".to_string()),
+            Op::Mod => a.rem_euclid(b),
+            Op::Add => a + b,
+            Op::Sub => a - b,
+            Op::Eq => flag((a - b).abs() < 1e-9),
+            Op::Ne => flag((a - b).abs() >= 1e-9),
+            Op::Gt => flag(a > b),
+            Op::Ge => flag(a >= b),
+            Op::Lt => flag(a < b),
+            Op::Le => flag(a <= b),
+            Op::And => flag(truthy(a) && truthy(b)),
+            Op::Or => flag(truthy(a) || truthy(b)),
+            Op::Xor => flag(truthy(a) != truthy(b)),
+        })
+    }
+}
+
+fn truthy(value: f64) -> bool {
+    value.abs() > 1e-9
+}
+
+fn is_code(value: f64, code: f64) -> bool {
+    (value - code).abs() < 1e-6
+}
+
+/// Code of a line without its comments
+fn strip_comments(line: &str) -> String {
+    let mut code = String::with_capacity(line.len());
+    let mut in_comment = false;
+    for c in line.chars() {
+        match c {
+            '(' => in_comment = true,
+            ')' if in_comment => in_comment = false,
+            ';' if !in_comment => break,
+            _ if !in_comment => code.push(c),
+            _ => {}
+        }
+    }
+    code
+}
+
+/// Character cursor over one source line
+struct Cursor {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Cursor {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_spaces();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(format!("expected '{}' but found '{}'", expected, c)),
+            None => Err(format!("expected '{}' at end of line", expected)),
+        }
+    }
+
+    fn at_end_or_comment(&mut self) -> bool {
+        matches!(self.peek(), None | Some('(') | Some(';'))
+    }
+
+    /// Letters (spaces between them ignored), upper-cased
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            word.push(c.to_ascii_uppercase());
+            self.pos += 1;
+        }
+        word
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        self.skip_spaces();
+        let start = self.pos;
+        let mut text = String::new();
+        while let Some(&c) = self.chars.get(self.pos) {
+            if c.is_ascii_digit() || c == '.' {
+                text.push(c);
+                self.pos += 1;
+            } else if c.is_whitespace() && !text.is_empty() {
+                // Spaces inside numbers are allowed, as in "G 38 . 2"
+                let rest = self.chars[self.pos..].iter().find(|c| !c.is_whitespace());
+                if rest.is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            } else {
+                break;
+            }
+        }
+        text.parse().map_err(|_| {
+            self.pos = start;
+            format!("expected a number but found '{}'", self.rest())
+        })
+    }
+
+    /// `<name>`, lower-cased with spaces removed
+    fn name(&mut self) -> Result<String, String> {
+        self.expect('<')?;
+        let mut name = String::new();
+        loop {
+            match self.chars.get(self.pos) {
+                Some('>') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(c) if c.is_whitespace() => {}
+                Some(c) => name.push(c.to_ascii_lowercase()),
+                None => return Err("unterminated parameter name".to_string()),
+            }
+            self.pos += 1;
+        }
+        if name.is_empty() {
+            return Err("empty parameter name".to_string());
+        }
+        Ok(name)
+    }
+
+    fn comment(&mut self) -> String {
+        self.skip_spaces();
+        let mut text = String::new();
+        if self.chars.get(self.pos) == Some(&';') {
+            text.extend(&self.chars[self.pos..]);
+            self.pos = self.chars.len();
+            return text;
+        }
+        while let Some(&c) = self.chars.get(self.pos) {
+            text.push(c);
+            self.pos += 1;
+            if c == ')' {
+                break;
+            }
+        }
+        text
+    }
+
+    fn rest(&self) -> String {
+        self.chars[self.pos.min(self.chars.len())..]
+            .iter()
+            .collect()
+    }
+}
+
+/// A value: number, parameter, bracketed expression or function
+fn parse_value(cursor: &mut Cursor) -> Result<Expr, String> {
+    match cursor.peek() {
+        Some('[') => {
+            cursor.pos += 1;
+            let expr = parse_expr(cursor, 0)?;
+            cursor.expect(']')?;
+            Ok(expr)
+        }
+        Some('#') => {
+            cursor.pos += 1;
+            Ok(Expr::Param(parse_param(cursor)?))
+        }
+        Some('-') => {
+            cursor.pos += 1;
+            Ok(match parse_value(cursor)? {
+                Expr::Number(n) => Expr::Number(-n),
+                other => Expr::Neg(Box::new(other)),
+            })
+        }
+        Some('+') => {
+            cursor.pos += 1;
+            parse_value(cursor)
+        }
+        Some(c) if c.is_ascii_digit() || c == '.' => Ok(Expr::Number(cursor.number()?)),
+        Some(c) if c.is_ascii_alphabetic() => {
+            let name = cursor.word();
+            if name == "EXISTS" {
+                cursor.expect('[')?;
+                cursor.expect('#')?;
+                let param = cursor.name()?;
+                cursor.expect(']')?;
+                return Ok(Expr::Exists(param));
+            }
+            if name == "ATAN" {
+                let y = parse_value(cursor)?;
+                cursor.expect('/')?;
+                let x = parse_value(cursor)?;
+                return Ok(Expr::Atan(Box::new(y), Box::new(x)));
+            }
+            let func =
+                Func::from_name(&name).ok_or_else(|| format!("unknown function {}", name))?;
+            if cursor.peek() != Some('[') {
+                return Err(format!("{} needs its argument in brackets", name));
+            }
+            Ok(Expr::Func(func, Box::new(parse_value(cursor)?)))
+        }
+        Some(c) => Err(format!("expected a value but found '{}'", c)),
+        None => Err("expected a value at end of line".to_string()),
+    }
+}
+
+fn parse_param(cursor: &mut Cursor) -> Result<ParamRef, String> {
+    if cursor.peek() == Some('<') {
+        return Ok(ParamRef::Named(cursor.name()?));
+    }
+    Ok(ParamRef::Numbered(Box::new(parse_value(cursor)?)))
+}
+
+/// Binary operators at or above `min_precedence`, by precedence climbing
+fn parse_expr(cursor: &mut Cursor, min_precedence: u8) -> Result<Expr, String> {
+    let mut left = parse_value(cursor)?;
+    loop {
+        let start = cursor.pos;
+        let op = match cursor.peek() {
+            Some('*') if cursor.chars.get(cursor.pos + 1) == Some(&'*') => {
+                cursor.pos += 2;
+                Op::Pow
+            }
+            Some('*') => {
+                cursor.pos += 1;
+                Op::Mul
+            }
+            Some('/') => {
+                cursor.pos += 1;
+                Op::Div
+            }
+            Some('+') => {
+                cursor.pos += 1;
+                Op::Add
+            }
+            Some('-') => {
+                cursor.pos += 1;
+                Op::Sub
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = cursor.word();
+                Op::from_name(&name).ok_or_else(|| format!("unknown operator {}", name))?
+            }
+            _ => break,
+        };
+        if op.precedence() < min_precedence {
+            cursor.pos = start;
+            break;
+        }
+        let right = parse_expr(cursor, op.precedence() + 1)?;
+        left = Expr::Binary(op, Box::new(left), Box::new(right));
+    }
+    Ok(left)
+}
+
+/// Parse a source line, with the O-word label of control statements
+fn parse_line(text: &str) -> Result<(Option<String>, Kind), String> {
+    let mut cursor = Cursor::new(text);
+    // A block delete or line number may come before an O-word
+    if cursor.peek() == Some('/') {
+        cursor.pos += 1;
+    }
+    if matches!(cursor.peek(), Some('N' | 'n')) {
+        let start = cursor.pos;
+        cursor.pos += 1;
+        if cursor.number().is_err() {
+            cursor.pos = start;
+        }
+    }
+    if matches!(cursor.peek(), Some('O' | 'o')) {
+        cursor.pos += 1;
+        return parse_o_word(&mut cursor);
+    }
+    parse_code(text).map(|kind| (None, kind))
+}
+
+fn parse_o_word(cursor: &mut Cursor) -> Result<(Option<String>, Kind), String> {
+    let name = match cursor.peek() {
+        Some('<') => cursor.name()?,
+        Some(c) if c.is_ascii_digit() => format!("{}", cursor.number()?),
+        _ => return Err("O-word needs a number or <name>".to_string()),
+    };
+    let keyword = cursor.word().to_ascii_lowercase();
+    let optional_value = |cursor: &mut Cursor| -> Result<Option<Expr>, String> {
+        if cursor.at_end_or_comment() {
+            Ok(None)
+        } else {
+            parse_value(cursor).map(Some)
+        }
+    };
+    let kind = match keyword.as_str() {
+        // A bare program number, as at the top of many files
+        "" => return Ok((None, Kind::Empty)),
+        "sub" => Kind::Sub { end: 0 },
+        "endsub" => Kind::EndSub {
+            value: optional_value(cursor)?,
+        },
+        "return" => Kind::Return {
+            value: optional_value(cursor)?,
+        },
+        "call" => {
+            let mut args = Vec::new();
+            while !cursor.at_end_or_comment() {
+                args.push(parse_value(cursor)?);
+            }
+            Kind::Call {
+                name: name.clone(),
+                args,
+            }
+        }
+        "if" => Kind::If {
+            condition: parse_value(cursor)?,
+            next: 0,
+        },
+        "elseif" => Kind::ElseIf {
+            condition: parse_value(cursor)?,
+            next: 0,
+            end: 0,
+        },
+        "else" => Kind::Else { end: 0 },
+        "endif" => Kind::EndIf,
+        "while" => Kind::While {
+            condition: parse_value(cursor)?,
+            end: 0,
+        },
+        "endwhile" => Kind::EndWhile { start: 0 },
+        "do" => Kind::Do,
+        "repeat" => Kind::Repeat {
+            count: parse_value(cursor)?,
+            end: 0,
+        },
+        "endrepeat" => Kind::EndRepeat { start: 0 },
+        "break" => Kind::Break { start: 0, end: 0 },
+        "continue" => Kind::Continue { target: 0 },
+        other => return Err(format!("unknown O-word keyword '{}'", other)),
+    };
+    if !cursor.at_end_or_comment() {
+        return Err(format!(
+            "unexpected '{}' after O-word",
+            cursor.rest().trim()
+        ));
+    }
+    Ok((Some(name), kind))
+}
+
+/// An O-word block still waiting for its closing statement
+struct OpenBlock {
+    label: String,
+    index: usize,
+    /// `elseif`/`else` statements of an `if`
+    branches: Vec<usize>,
+    /// `break`/`continue` statements of a loop
+    exits: Vec<usize>,
+}
+
+/// Match O-word blocks by label and fill in the indices they jump to
+fn resolve_blocks(
+    statements: &mut [Statement],
+) -> Result<HashMap<String, usize>, Vec<ExpansionError>> {
+    let mut subs = HashMap::new();
+    let mut open: Vec<OpenBlock> = Vec::new();
+    let mut errors = Vec::new();
+
+    for i in 0..statements.len() {
+        let Some(label) = statements[i].label.clone() else {
+            continue;
+        };
+        let line = statements[i].line;
+        let word = keyword(&statements[i].kind);
+        // The innermost open block, if it has this label and keyword
+        let innermost = |open: &[OpenBlock], opener: &str| {
+            open.last()
+                .filter(|b| b.label == label && keyword(&statements[b.index].kind) == opener)
+                .map(|b| b.index)
+        };
+        let mut unmatched = |opener: &str| {
+            errors.push(ExpansionError::new(
+                line,
+                format!("O{} {} without a matching {}", label, word, opener),
+            ))
+        };
+
+        match word {
+            "sub" => {
+                if !open.is_empty() {
+                    errors.push(ExpansionError::new(
+                        line,
+                        format!("O{} sub cannot be defined inside another block", label),
+                    ));
+                }
+                open.push(OpenBlock::new(label, i));
+            }
+            "while" if innermost(&open, "do").is_some() => {
+                // `while` after `do` closes the loop instead of opening one
+                let block = open.pop().expect("do block is open");
+                let kind = std::mem::replace(&mut statements[i].kind, Kind::Empty);
+                if let Kind::While { condition, .. } = kind {
+                    statements[i].kind = Kind::DoWhile {
+                        condition,
+                        start: block.index,
+                    };
+                }
+                patch_exits(statements, &block.exits, block.index, i, i);
+            }
+            "if" | "while" | "do" | "repeat" => open.push(OpenBlock::new(label, i)),
+            "elseif" | "else" => {
+                if innermost(&open, "if").is_none() {
+                    unmatched("if");
+                    continue;
+                }
+                let block = open.last_mut().expect("if block is open");
+                let after_else = block
+                    .branches
+                    .last()
+                    .is_some_and(|b| matches!(statements[*b].kind, Kind::Else { .. }));
+                if after_else {
+                    errors.push(ExpansionError::new(
+                        line,
+                        format!("O{} {} after else", label, word),
+                    ));
+                    continue;
+                }
+                block.branches.push(i);
+            }
+            "endif" => {
+                if innermost(&open, "if").is_none() {
+                    unmatched("if");
+                    continue;
+                }
+                let block = open.pop().expect("if block is open");
+                let mut chain = vec![block.index];
+                chain.extend(&block.branches);
+                chain.push(i);
+                for pair in chain.windows(2) {
+                    match &mut statements[pair[0]].kind {
+                        Kind::If { next, .. } => *next = pair[1],
+                        Kind::ElseIf { next, end, .. } => {
+                            *next = pair[1];
+                            *end = i;
+                        }
+                        Kind::Else { end } => *end = i,
+                        _ => {}
+                    }
+                }
+            }
+            "endwhile" | "endrepeat" => {
+                let opener = if word == "endwhile" {
+                    "while"
+                } else {
+                    "repeat"
+                };
+                if innermost(&open, opener).is_none() {
+                    unmatched(opener);
+                    continue;
+                }
+                let block = open.pop().expect("loop block is open");
+                let start = block.index;
+                match &mut statements[start].kind {
+                    Kind::While { end, .. } | Kind::Repeat { end, .. } => *end = i,
+                    _ => {}
+                }
+                match &mut statements[i].kind {
+                    Kind::EndWhile { start: s } | Kind::EndRepeat { start: s } => *s = start,
+                    _ => {}
+                }
+                // `continue` re-tests a while loop and counts down a repeat
+                let retry = if word == "endwhile" { start } else { i };
+                patch_exits(statements, &block.exits, start, i, retry);
+            }
+            "break" | "continue" => {
+                let block = open.iter_mut().rev().find(|b| {
+                    b.label == label
+                        && matches!(
+                            statements[b.index].kind,
+                            Kind::While { .. } | Kind::Do | Kind::Repeat { .. }
+                        )
+                });
+                match block {
+                    Some(block) => block.exits.push(i),
+                    None => unmatched("loop"),
+                }
+            }
+            "endsub" => {
+                if innermost(&open, "sub").is_none() {
+                    unmatched("sub");
+                    continue;
+                }
+                let block = open.pop().expect("sub block is open");
+                if let Kind::Sub { end } = &mut statements[block.index].kind {
+                    *end = i;
+                }
+                if subs.insert(label.clone(), block.index).is_some() {
+                    errors.push(ExpansionError::new(
+                        line,
+                        format!("subroutine O{} is defined twice", label),
+                    ));
+                }
+            }
+            "return" => {
+                let in_sub = open.first().is_some_and(|b| {
+                    b.label == label && matches!(statements[b.index].kind, Kind::Sub { .. })
+                });
+                if !in_sub {
+                    unmatched("sub");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for block in open {
+        let statement = &statements[block.index];
+        errors.push(ExpansionError::new(
+            statement.line,
+            format!(
+                "O{} {} is never closed",
+                block.label,
+                keyword(&statement.kind)
+            ),
+        ));
+    }
+    if errors.is_empty() {
+        Ok(subs)
+    } else {
+        Err(errors)
+    }
+}
+
+impl OpenBlock {
+    fn new(label: String, index: usize) -> Self {
+        Self {
+            label,
+            index,
+            branches: Vec::new(),
+            exits: Vec::new(),
+        }
+    }
+}
+
+/// Point a loop's `break` and `continue` statements at its ends
+fn patch_exits(
+    statements: &mut [Statement],
+    exits: &[usize],
+    start: usize,
+    end: usize,
+    retry: usize,
+) {
+    for &exit in exits {
+        match &mut statements[exit].kind {
+            Kind::Break { start: s, end: e } => {
+                *s = start;
+                *e = end;
+            }
+            Kind::Continue { target } => *target = retry,
+            _ => {}
+        }
+    }
+}
+
+/// O-word keyword of a control statement
+fn keyword(kind: &Kind) -> &'static str {
+    match kind {
+        Kind::Empty | Kind::Code(_) => "",
+        Kind::Sub { .. } => "sub",
+        Kind::EndSub { .. } => "endsub",
+        Kind::Return { .. } => "return",
+        Kind::Call { .. } => "call",
+        Kind::If { .. } => "if",
+        Kind::ElseIf { .. } => "elseif",
+        Kind::Else { .. } => "else",
+        Kind::EndIf => "endif",
+        Kind::While { .. } | Kind::DoWhile { .. } => "while",
+        Kind::EndWhile { .. } => "endwhile",
+        Kind::Do => "do",
+        Kind::Repeat { .. } => "repeat",
+        Kind::EndRepeat { .. } => "endrepeat",
+        Kind::Break { .. } => "break",
+        Kind::Continue { .. } => "continue",
+    }
+}
+
+fn parse_code(text: &str) -> Result<Kind, String> {
+    let mut cursor = Cursor::new(text);
+    let mut words = Vec::new();
+    let mut assignments = Vec::new();
+    let mut evaluated = false;
+    while let Some(c) = cursor.peek() {
+        match c {
+            '(' | ';' => words.push(Part::Text(cursor.comment())),
+            '%' => {
+                cursor.pos += 1;
+                words.push(Part::Text("%".to_string()));
+            }
+            '/' if words.is_empty() => {
+                cursor.pos += 1;
+                words.push(Part::Text("/".to_string()));
+            }
+            '#' => {
+                cursor.pos += 1;
+                let target = parse_param(&mut cursor)?;
+                cursor.expect('=')?;
+                assignments.push((target, parse_value(&mut cursor)?));
+            }
+            c if c.is_ascii_alphabetic() => {
+                cursor.pos += 1;
+                let letter = c.to_ascii_uppercase();
+                let start = cursor.pos;
+                let expr = parse_value(&mut cursor)?;
+                let literal = match expr {
+                    Expr::Number(_) => Some(format!(
+                        "{}{}",
+                        letter,
+                        cursor.chars[start..cursor.pos]
+                            .iter()
+                            .filter(|c| !c.is_whitespace())
+                            .collect::<String>()
+                    )),
+                    _ => {
+                        evaluated = true;
+                        None
+                    }
+                };
+                words.push(Part::Word {
+                    letter,
+                    expr,
+                    literal,
+                });
+            }
+            other => return Err(format!("unexpected character '{}'", other)),
+        }
+    }
+    let verbatim = (!evaluated && assignments.is_empty()).then(|| text.trim().to_string());
+    Ok(Kind::Code(CodeLine {
+        words,
+        assignments,
+        verbatim,
+    }))
+}
@@ -87,6 +87,14 @@ pub trait CommandProcessor: Send + Sync {
         state: &GcodeState,
     ) -> Result<Vec<GcodeCommand>, String>;
 
+    /// Finish processing at the end of a program
+    ///
+    /// Processors that hold commands back to look ahead (like arc fitting)
+    /// return them here. The default implementation returns nothing.
+    fn finish(&self, _state: &GcodeState) -> Result<Vec<GcodeCommand>, String> {
+        Ok(vec![])
+    }
+
     /// Check if this processor is enabled
     fn is_enabled(&self) -> bool {
         true
@@ -239,9 +247,47 @@ impl ProcessorPipeline {
             }
         }
 
+        for cmd in self.finish(state)? {
+            self.update_state(&cmd, state)?;
+            results.push(cmd);
+        }
+
         Ok(results)
     }
 
+    /// Flush commands held back by processors at the end of a program
+    ///
+    /// Commands released by a processor still pass through the processors
+    /// after it. [`Self::process_commands`] calls this automatically; call it
+    /// directly when feeding commands one at a time with
+    /// [`Self::process_command`].
+    pub fn finish(&self, state: &GcodeState) -> Result<Vec<GcodeCommand>, String> {
+        let mut pending: Vec<GcodeCommand> = Vec::new();
+
+        for processor in &self.processors {
+            if !processor.is_enabled() {
+                continue;
+            }
+
+            let mut next_commands = Vec::new();
+            for cmd in &pending {
+                next_commands.extend(
+                    processor
+                        .process(cmd, state)
+                        .map_err(|e| format!("Processor '{}' error: {}", processor.name(), e))?,
+                );
+            }
+            next_commands.extend(
+                processor
+                    .finish(state)
+                    .map_err(|e| format!("Processor '{}' error: {}", processor.name(), e))?,
+            );
+            pending = next_commands;
+        }
+
+        Ok(pending)
+    }
+
     /// Update G-Code state based on a command
     fn update_state(&self, command: &GcodeCommand, state: &mut GcodeState) -> Result<(), String> {
         let cmd_upper = command.command.to_uppercase();
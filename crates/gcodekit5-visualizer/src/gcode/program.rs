@@ -0,0 +1,156 @@
+//! Helpers shared by processors that rewrite whole programs
+//!
+//! Runs a program through a processor line by line and tracks the modal
+//! state and program position that coordinate-rewriting processors need.
+
+use super::transform::{Token, Word};
+use super::{GcodeCommand, GcodeState};
+
+/// Run every line of `gcode` through `process`, then `finish`
+///
+/// Errors are reported with the line number they occurred on.
+pub(super) fn apply_to_program<P, F>(gcode: &str, process: P, finish: F) -> Result<String, String>
+where
+    P: Fn(&GcodeCommand, &GcodeState) -> Result<Vec<GcodeCommand>, String>,
+    F: FnOnce(&GcodeState) -> Result<Vec<GcodeCommand>, String>,
+{
+    let state = GcodeState::new();
+    let mut out = String::with_capacity(gcode.len());
+    for (index, line) in gcode.lines().enumerate() {
+        let processed = process(&GcodeCommand::new(line), &state)
+            .map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        for command in processed {
+            out.push_str(&command.command);
+            out.push('\n');
+        }
+    }
+    for command in finish(&state)? {
+        out.push_str(&command.command);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// The words of a tokenized line, without its comments
+pub(super) fn words(tokens: &[Token]) -> Vec<&Word> {
+    tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Word(w) => Some(w),
+            Token::Comment(_) => None,
+        })
+        .collect()
+}
+
+/// Value of the first word with `letter`
+pub(super) fn value(words: &[&Word], letter: char) -> Option<f64> {
+    words.iter().find(|w| w.letter == letter).map(|w| w.value)
+}
+
+/// Whether the line contains the G code `code`
+pub(super) fn has_g(words: &[&Word], code: f64) -> bool {
+    words
+        .iter()
+        .any(|w| w.letter == 'G' && (w.value - code).abs() < 1e-6)
+}
+
+/// Modal state and program position
+#[derive(Debug, Clone)]
+pub(super) struct ModalState {
+    /// Position in program coordinates
+    pub(super) pos: [Option<f64>; 3],
+    /// Motion mode ×10 (G38.2 → 382, G81 → 810)
+    pub(super) motion: i32,
+    pub(super) plane: u8,
+    pub(super) relative: bool,
+    pub(super) arc_absolute: bool,
+    pub(super) inches: bool,
+    pub(super) inverse_time: bool,
+}
+
+impl ModalState {
+    /// Apply the modes set by the G words of a line
+    ///
+    /// Returns whether the line names a motion mode.
+    pub(super) fn apply(&mut self, words: &[&Word]) -> bool {
+        let mut motion = false;
+        for word in words.iter().filter(|w| w.letter == 'G') {
+            match (word.value * 10.0).round() as i32 {
+                170 => self.plane = 17,
+                180 => self.plane = 18,
+                190 => self.plane = 19,
+                200 => self.inches = true,
+                210 => self.inches = false,
+                900 => self.relative = false,
+                910 => self.relative = true,
+                901 => self.arc_absolute = true,
+                911 => self.arc_absolute = false,
+                930 => self.inverse_time = true,
+                940 | 950 => self.inverse_time = false,
+                code @ (0 | 10 | 20 | 30 | 800..=890) => {
+                    self.motion = code;
+                    motion = true;
+                }
+                _ => {}
+            }
+        }
+        motion
+    }
+
+    /// A canned cycle is active (G80 cancels it)
+    pub(super) fn is_canned(&self) -> bool {
+        (810..=890).contains(&self.motion)
+    }
+
+    /// Update the position for a line that is passed through unchanged
+    ///
+    /// Moves in machine coordinates (G53), homing (G28/G30), work offset
+    /// changes (G10, G92.1-G92.3) and dwells (G4) are not rewritten. Returns
+    /// whether the line is one of them.
+    pub(super) fn pass_through(&mut self, words: &[&Word]) -> bool {
+        if has_g(words, 28.0) || has_g(words, 30.0) || has_g(words, 10.0) {
+            // Homing and new work offsets move the program position
+            self.pos = [None; 3];
+            return true;
+        }
+        if has_g(words, 4.0) {
+            return true;
+        }
+        if has_g(words, 53.0) {
+            self.forget_named_axes(words);
+            return true;
+        }
+        if words
+            .iter()
+            .any(|w| w.letter == 'G' && (w.value - 92.0).abs() > 1e-6 && w.value.floor() == 92.0)
+        {
+            // G92.1/G92.2/G92.3 change offsets without naming the position
+            self.pos = [None; 3];
+            return true;
+        }
+        false
+    }
+
+    /// Mark the axes named on a line as unknown
+    pub(super) fn forget_named_axes(&mut self, words: &[&Word]) {
+        for (pos, letter) in self.pos.iter_mut().zip(['X', 'Y', 'Z']) {
+            if value(words, letter).is_some() {
+                *pos = None;
+            }
+        }
+    }
+}
+
+impl Default for ModalState {
+    fn default() -> Self {
+        Self {
+            pos: [None; 3],
+            motion: 0,
+            plane: 17,
+            relative: false,
+            arc_absolute: false,
+            inches: false,
+            inverse_time: false,
+        }
+    }
+}
@@ -0,0 +1,605 @@
+//! Geometric transform processors
+//!
+//! Translate, rotate, mirror and scale a program in the XY plane (plus
+//! scale/offset along Z). The transforms follow the program's modal state:
+//! absolute coordinates are mapped as points and incremental ones as
+//! vectors, arc centre offsets and radii are rewritten, and mirroring
+//! swaps G2 and G3. Moves in machine coordinates (G53), homing and work
+//! offset commands are passed through unchanged.
+
+use std::sync::Mutex;
+
+use super::program::{self, ModalState};
+use super::{CommandProcessor, GcodeCommand, GcodeState, ProcessorConfig};
+
+const EPSILON: f64 = 1e-9;
+
+/// Axis to mirror across
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAxis {
+    /// Flip X (mirror across a vertical line)
+    X,
+    /// Flip Y (mirror across a horizontal line)
+    Y,
+}
+
+/// Affine transform of XY with independent scale and offset along Z
+///
+/// Maps `(x, y, z)` to `(m·(x, y) + t, z_scale·z + z_offset)`. Offsets are
+/// in millimetres.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AffineTransform {
+    /// Linear part, `x' = m[0][0]·x + m[0][1]·y`, `y' = m[1][0]·x + m[1][1]·y`
+    pub m: [[f64; 2]; 2],
+    /// XY translation
+    pub t: [f64; 2],
+    pub z_scale: f64,
+    pub z_offset: f64,
+}
+
+impl AffineTransform {
+    pub fn identity() -> Self {
+        Self {
+            m: [[1.0, 0.0], [0.0, 1.0]],
+            t: [0.0, 0.0],
+            z_scale: 1.0,
+            z_offset: 0.0,
+        }
+    }
+
+    /// Shift by `(dx, dy, dz)`
+    pub fn translation(dx: f64, dy: f64, dz: f64) -> Self {
+        Self {
+            t: [dx, dy],
+            z_offset: dz,
+            ..Self::identity()
+        }
+    }
+
+    /// Rotate counter-clockwise by `degrees` about `center`
+    pub fn rotation(degrees: f64, center: (f64, f64)) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        // Snap so quarter turns stay exact
+        let snap = |v: f64| if v.abs() < 1e-12 { 0.0 } else { v };
+        let (sin, cos) = (snap(sin), snap(cos));
+        Self {
+            m: [[cos, -sin], [sin, cos]],
+            ..Self::identity()
+        }
+        .about(center)
+    }
+
+    /// Mirror across the line `axis = about`
+    pub fn mirror(axis: MirrorAxis, about: f64) -> Self {
+        let m = match axis {
+            MirrorAxis::X => [[-1.0, 0.0], [0.0, 1.0]],
+            MirrorAxis::Y => [[1.0, 0.0], [0.0, -1.0]],
+        };
+        let center = match axis {
+            MirrorAxis::X => (about, 0.0),
+            MirrorAxis::Y => (0.0, about),
+        };
+        Self {
+            m,
+            ..Self::identity()
+        }
+        .about(center)
+    }
+
+    /// Scale about `center`; Z is scaled about Z0
+    pub fn scale(sx: f64, sy: f64, sz: f64, center: (f64, f64)) -> Self {
+        Self {
+            m: [[sx, 0.0], [0.0, sy]],
+            z_scale: sz,
+            ..Self::identity()
+        }
+        .about(center)
+    }
+
+    /// Apply `self`, then `next`
+    pub fn then(&self, next: &AffineTransform) -> Self {
+        let a = &next.m;
+        let b = &self.m;
+        Self {
+            m: [
+                [
+                    a[0][0] * b[0][0] + a[0][1] * b[1][0],
+                    a[0][0] * b[0][1] + a[0][1] * b[1][1],
+                ],
+                [
+                    a[1][0] * b[0][0] + a[1][1] * b[1][0],
+                    a[1][0] * b[0][1] + a[1][1] * b[1][1],
+                ],
+            ],
+            t: {
+                let [x, y] = next.apply_vector(self.t[0], self.t[1]);
+                [x + next.t[0], y + next.t[1]]
+            },
+            z_scale: next.z_scale * self.z_scale,
+            z_offset: next.z_scale * self.z_offset + next.z_offset,
+        }
+    }
+
+    /// Same linear part, fixed point moved from the origin to `center`
+    fn about(mut self, center: (f64, f64)) -> Self {
+        let [x, y] = self.apply_vector(center.0, center.1);
+        self.t = [center.0 - x, center.1 - y];
+        self
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.m[0][0] * self.m[1][1] - self.m[0][1] * self.m[1][0]
+    }
+
+    /// Map a point in the XY plane
+    pub fn apply_point(&self, x: f64, y: f64) -> [f64; 2] {
+        let [vx, vy] = self.apply_vector(x, y);
+        [vx + self.t[0], vy + self.t[1]]
+    }
+
+    /// Map a direction (no translation)
+    pub fn apply_vector(&self, x: f64, y: f64) -> [f64; 2] {
+        [
+            self.m[0][0] * x + self.m[0][1] * y,
+            self.m[1][0] * x + self.m[1][1] * y,
+        ]
+    }
+
+    /// The XY part is a rotation/reflection with uniform scale
+    fn is_conformal(&self) -> bool {
+        let [[a, b], [c, d]] = self.m;
+        let col_x = a * a + c * c;
+        let col_y = b * b + d * d;
+        (col_x - col_y).abs() < EPSILON * col_x.max(1.0) && (a * b + c * d).abs() < EPSILON
+    }
+
+    /// Offsets in inches when the program runs in G20
+    fn in_units(&self, inches: bool) -> Self {
+        if !inches {
+            return *self;
+        }
+        Self {
+            t: [self.t[0] / 25.4, self.t[1] / 25.4],
+            z_offset: self.z_offset / 25.4,
+            ..*self
+        }
+    }
+}
+
+impl Default for AffineTransform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// Transform Processor
+///
+/// Applies an [`AffineTransform`] to every coordinate in a program. Create
+/// one with [`Self::translate`], [`Self::rotate`], [`Self::mirror`] or
+/// [`Self::scale`]; the processor name matches the operation.
+///
+/// Arcs in G17 need a transform with uniform XY scale, arcs in G18/G19 one
+/// that keeps them in their plane with equal scale on both plane axes;
+/// other arcs are reported as errors. Tracking the position starts at the
+/// first move that sets an axis, so rotating a move that only names one axis
+/// before the other axis is known is an error too.
+#[derive(Debug)]
+pub struct TransformProcessor {
+    name: &'static str,
+    transform: AffineTransform,
+    config: ProcessorConfig,
+    state: Mutex<ModalState>,
+}
+
+impl TransformProcessor {
+    /// Processor for an arbitrary transform
+    pub fn new(transform: AffineTransform) -> Self {
+        Self::with_name("transform", transform, ProcessorConfig::new())
+    }
+
+    /// Shift the program by `(dx, dy, dz)` millimetres
+    pub fn translate(dx: f64, dy: f64, dz: f64) -> Self {
+        let config = ProcessorConfig::new()
+            .with_option("dx", dx.to_string())
+            .with_option("dy", dy.to_string())
+            .with_option("dz", dz.to_string());
+        Self::with_name(
+            "translate",
+            AffineTransform::translation(dx, dy, dz),
+            config,
+        )
+    }
+
+    /// Rotate counter-clockwise by `degrees` about `center`
+    pub fn rotate(degrees: f64, center: (f64, f64)) -> Self {
+        let config = ProcessorConfig::new()
+            .with_option("angle", degrees.to_string())
+            .with_option("center_x", center.0.to_string())
+            .with_option("center_y", center.1.to_string());
+        Self::with_name("rotate", AffineTransform::rotation(degrees, center), config)
+    }
+
+    /// Mirror across the line `axis = about`
+    pub fn mirror(axis: MirrorAxis, about: f64) -> Self {
+        let name = match axis {
+            MirrorAxis::X => "x",
+            MirrorAxis::Y => "y",
+        };
+        let config = ProcessorConfig::new()
+            .with_option("axis", name)
+            .with_option("about", about.to_string());
+        Self::with_name("mirror", AffineTransform::mirror(axis, about), config)
+    }
+
+    /// Scale by `(sx, sy, sz)` about `center`
+    pub fn scale(sx: f64, sy: f64, sz: f64, center: (f64, f64)) -> Self {
+        let config = ProcessorConfig::new()
+            .with_option("scale_x", sx.to_string())
+            .with_option("scale_y", sy.to_string())
+            .with_option("scale_z", sz.to_string())
+            .with_option("center_x", center.0.to_string())
+            .with_option("center_y", center.1.to_string());
+        Self::with_name("scale", AffineTransform::scale(sx, sy, sz, center), config)
+    }
+
+    fn with_name(name: &'static str, transform: AffineTransform, config: ProcessorConfig) -> Self {
+        Self {
+            name,
+            transform,
+            config,
+            state: Mutex::new(ModalState::default()),
+        }
+    }
+
+    pub fn transform(&self) -> &AffineTransform {
+        &self.transform
+    }
+
+    /// Transform a whole program, reporting errors with their line number
+    pub fn apply_to_program(&self, gcode: &str) -> Result<String, String> {
+        program::apply_to_program(gcode, |c, s| self.process(c, s), |s| self.finish(s))
+    }
+
+    fn transform_line(&self, text: &str, state: &mut ModalState) -> Result<String, String> {
+        let tokens = tokenize(text)?;
+        let words = program::words(&tokens);
+        let value = |letter: char| program::value(&words, letter);
+
+        state.apply(&words);
+        let axes = [value('X'), value('Y'), value('Z')];
+        let has_axes = axes.iter().any(Option::is_some);
+
+        // Machine coordinates, homing, work offsets and dwell stay as they are
+        if state.pass_through(&words) {
+            return Ok(text.to_string());
+        }
+
+        let probing = words
+            .iter()
+            .any(|w| w.letter == 'G' && w.value.floor() == 38.0);
+        let sets_position = program::has_g(&words, 92.0);
+        let canned = state.is_canned() && !sets_position;
+        let arc = (state.motion == 20 || state.motion == 30) && !sets_position && !probing;
+        let has_arc_words = ['I', 'J', 'K', 'R'].iter().any(|l| value(*l).is_some());
+
+        let canned_retract = canned && value('R').is_some();
+        if !(has_axes || (arc && has_arc_words) || canned_retract) {
+            return Ok(text.to_string());
+        }
+
+        let transform = self.transform.in_units(state.inches);
+        let relative = state.relative && !sets_position;
+
+        // New axis values
+        let mut out_axes: [Option<f64>; 3] = [None; 3];
+        let [[_, m01], [m10, _]] = transform.m;
+        let emit_x = axes[0].is_some() || (axes[1].is_some() && m01.abs() > EPSILON);
+        let emit_y = axes[1].is_some() || (axes[0].is_some() && m10.abs() > EPSILON);
+        if emit_x || emit_y {
+            if relative {
+                let [x, y] = transform.apply_vector(axes[0].unwrap_or(0.0), axes[1].unwrap_or(0.0));
+                out_axes[0] = emit_x.then_some(x);
+                out_axes[1] = emit_y.then_some(y);
+            } else {
+                let known = |axis: usize, name: char| {
+                    axes[axis].or(state.pos[axis]).ok_or_else(|| {
+                        format!(
+                            "{} position is unknown; it is needed to transform this move",
+                            name
+                        )
+                    })
+                };
+                let x_needed = emit_x || (emit_y && m10.abs() > EPSILON);
+                let y_needed = emit_y || (emit_x && m01.abs() > EPSILON);
+                let x = if x_needed { known(0, 'X')? } else { 0.0 };
+                let y = if y_needed { known(1, 'Y')? } else { 0.0 };
+                let [tx, ty] = transform.apply_point(x, y);
+                out_axes[0] = emit_x.then_some(tx);
+                out_axes[1] = emit_y.then_some(ty);
+            }
+        }
+        if let Some(z) = axes[2] {
+            out_axes[2] = Some(if relative {
+                transform.z_scale * z
+            } else {
+                transform.z_scale * z + transform.z_offset
+            });
+        }
+
+        // Arc centre and radius
+        let mut flip = false;
+        let mut arc_words: Vec<(char, f64)> = Vec::new();
+        if arc && has_arc_words {
+            flip = self.arc_flips(&transform, state.plane)?;
+            if let Some(r) = value('R') {
+                arc_words.push(('R', r * self.arc_scale(&transform, state.plane)));
+            } else {
+                let offsets = [value('I'), value('J'), value('K')];
+                let mapped = self.map_arc_offsets(&transform, state, offsets)?;
+                for (letter, v) in ['I', 'J', 'K'].into_iter().zip(mapped) {
+                    if let Some(v) = v {
+                        arc_words.push((letter, v));
+                    }
+                }
+            }
+        }
+
+        // Track the untransformed position
+        for (axis, v) in axes.iter().enumerate() {
+            if let Some(v) = v {
+                state.pos[axis] = if probing {
+                    None
+                } else if relative {
+                    state.pos[axis].map(|p| p + v)
+                } else {
+                    Some(*v)
+                };
+            }
+        }
+        if canned {
+            // The retract height depends on G98/G99
+            state.pos[2] = None;
+        }
+
+        // Rebuild the line with the new words in place of the old ones
+        let digits = if state.inches { 5 } else { 4 };
+        let mut parts: Vec<String> = Vec::with_capacity(tokens.len() + 2);
+        let mut axes_written = false;
+        let mut arc_written = false;
+        for token in &tokens {
+            let word = match token {
+                Token::Comment(c) => {
+                    parts.push(c.clone());
+                    continue;
+                }
+                Token::Word(w) => w,
+            };
+            match word.letter {
+                'X' | 'Y' | 'Z' => {
+                    if !axes_written {
+                        axes_written = true;
+                        for (letter, v) in ['X', 'Y', 'Z'].into_iter().zip(out_axes) {
+                            if let Some(v) = v {
+                                parts.push(format!("{}{}", letter, format_number(v, digits)));
+                            }
+                        }
+                    }
+                }
+                'I' | 'J' | 'K' | 'R' if arc => {
+                    if !arc_written {
+                        arc_written = true;
+                        for (letter, v) in &arc_words {
+                            parts.push(format!("{}{}", letter, format_number(*v, digits)));
+                        }
+                    }
+                }
+                'R' if canned => {
+                    let r = if relative {
+                        transform.z_scale * word.value
+                    } else {
+                        transform.z_scale * word.value + transform.z_offset
+                    };
+                    parts.push(format!("R{}", format_number(r, digits)));
+                }
+                'Q' if canned => parts.push(format!(
+                    "Q{}",
+                    format_number(transform.z_scale.abs() * word.value, digits)
+                )),
+                'G' if flip && (word.value == 2.0 || word.value == 3.0) => {
+                    parts.push(if word.value == 2.0 { "G3" } else { "G2" }.to_string())
+                }
+                _ => parts.push(word.text.clone()),
+            }
+        }
+        Ok(parts.join(" "))
+    }
+
+    /// Whether the arc direction reverses in the active plane
+    fn arc_flips(&self, transform: &AffineTransform, plane: u8) -> Result<bool, String> {
+        let [[m00, m01], [m10, m11]] = transform.m;
+        let sz = transform.z_scale;
+        match plane {
+            18 => {
+                if m10.abs() > EPSILON || (m00.abs() - sz.abs()).abs() > EPSILON {
+                    return Err("arc in the XZ plane (G18) would not stay circular".to_string());
+                }
+                Ok(m00 * sz < 0.0)
+            }
+            19 => {
+                if m01.abs() > EPSILON || (m11.abs() - sz.abs()).abs() > EPSILON {
+                    return Err("arc in the YZ plane (G19) would not stay circular".to_string());
+                }
+                Ok(m11 * sz < 0.0)
+            }
+            _ => {
+                if !transform.is_conformal() {
+                    return Err(
+                        "non-uniform scaling turns arcs into ellipses; expand arcs first"
+                            .to_string(),
+                    );
+                }
+                Ok(transform.determinant() < 0.0)
+            }
+        }
+    }
+
+    /// Radius scale factor in the active plane
+    fn arc_scale(&self, transform: &AffineTransform, plane: u8) -> f64 {
+        match plane {
+            18 | 19 => transform.z_scale.abs(),
+            _ => transform.determinant().abs().sqrt(),
+        }
+    }
+
+    /// New I/J/K words; centre points in G90.1, offsets otherwise
+    fn map_arc_offsets(
+        &self,
+        transform: &AffineTransform,
+        state: &ModalState,
+        offsets: [Option<f64>; 3],
+    ) -> Result<[Option<f64>; 3], String> {
+        let [i, j, k] = offsets;
+        let mut out = [None; 3];
+        let xy = i.is_some() || j.is_some();
+        if xy {
+            let emit_i = i.is_some() || (j.is_some() && transform.m[0][1].abs() > EPSILON);
+            let emit_j = j.is_some() || (i.is_some() && transform.m[1][0].abs() > EPSILON);
+            let [x, y] = if state.arc_absolute {
+                let x = i.or(state.pos[0]).ok_or("X position is unknown")?;
+                let y = j.or(state.pos[1]).ok_or("Y position is unknown")?;
+                transform.apply_point(x, y)
+            } else {
+                transform.apply_vector(i.unwrap_or(0.0), j.unwrap_or(0.0))
+            };
+            out[0] = emit_i.then_some(x);
+            out[1] = emit_j.then_some(y);
+        }
+        if let Some(k) = k {
+            out[2] = Some(if state.arc_absolute {
+                transform.z_scale * k + transform.z_offset
+            } else {
+                transform.z_scale * k
+            });
+        }
+        Ok(out)
+    }
+}
+
+impl CommandProcessor for TransformProcessor {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn description(&self) -> &str {
+        match self.name {
+            "translate" => "Shifts all coordinates by a fixed offset",
+            "rotate" => "Rotates coordinates about a point in the XY plane",
+            "mirror" => "Mirrors coordinates across an axis, swapping G2/G3",
+            "scale" => "Scales coordinates about a point",
+            _ => "Applies an affine transform to all coordinates",
+        }
+    }
+
+    fn process(
+        &self,
+        command: &GcodeCommand,
+        _state: &GcodeState,
+    ) -> Result<Vec<GcodeCommand>, String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let text = self.transform_line(&command.command, &mut state)?;
+        if text == command.command {
+            return Ok(vec![command.clone()]);
+        }
+        let mut processed = command.clone();
+        processed.command = text.clone();
+        processed.line = text;
+        Ok(vec![processed])
+    }
+
+    fn finish(&self, _state: &GcodeState) -> Result<Vec<GcodeCommand>, String> {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = ModalState::default();
+        Ok(vec![])
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn config(&self) -> &ProcessorConfig {
+        &self.config
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Word {
+    pub(super) letter: char,
+    pub(super) value: f64,
+    pub(super) text: String,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Token {
+    Word(Word),
+    Comment(String),
+}
+
+/// Split a line into words and comments, keeping their order
+pub(super) fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => {
+                let mut comment = String::from('(');
+                for c in chars.by_ref() {
+                    comment.push(c);
+                    if c == ')' {
+                        break;
+                    }
+                }
+                tokens.push(Token::Comment(comment));
+            }
+            ';' => {
+                let mut comment = String::from(';');
+                comment.extend(chars.by_ref());
+                tokens.push(Token::Comment(comment));
+            }
+            '%' => tokens.push(Token::Comment("%".to_string())),
+            c if c.is_ascii_alphabetic() => {
+                let mut number = String::new();
+                while let Some(&d) = chars.peek() {
+                    if d.is_ascii_digit() || matches!(d, '.' | '-' | '+') {
+                        number.push(d);
+                        chars.next();
+                    } else if d == ' ' && number.is_empty() {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let letter = c.to_ascii_uppercase();
+                let value = number
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid value for {}: '{}'", letter, number))?;
+                tokens.push(Token::Word(Word {
+                    letter,
+                    value,
+                    text: format!("{}{}", letter, number),
+                }));
+            }
+            other => return Err(format!("unexpected character '{}'", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Format a coordinate with up to `digits` decimals and no trailing zeros
+pub(super) fn format_number(value: f64, digits: usize) -> String {
+    let text = format!("{:.*}", digits, value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" | "" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
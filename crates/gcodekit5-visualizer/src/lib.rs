@@ -12,27 +12,38 @@ pub mod visualizer;
 pub use visualizer::{
     generate_surface_mesh, render_g1_to_path, render_g2_to_path, render_g3_to_path,
     render_g4_to_path, render_grid_to_path, render_intensity_overlay, render_origin_to_path,
-    render_rapid_moves_to_path, render_toolpath_to_path, Camera, Camera3D, GCodeCommand, Point3D,
-    Renderer, Scene, StockSimulator3D, ToolpathSegment, ToolpathSegmentType, Visualizer,
-    VisualizerControls, VoxelGrid,
+    render_rapid_moves_to_path, render_toolpath_to_path, Camera, Camera3D, CollisionDetector,
+    CollisionIssue, CollisionKind, CollisionSettings, DeviationMap, DeviationSettings,
+    DeviationStats, DiffKind, DiffRegion, DiffSettings, GCodeCommand, GcodeStreamParser,
+    ParseEvent, ParseJob, ParseProgress, ParsedToolpath, Point3D, PreviewColor, PreviewFormat,
+    PreviewOptions, PreviewProjection, PreviewRenderer, Renderer, Scene, StockSimulator3D,
+    TimelinePlayback, ToolpathBuffers, ToolpathDiff, ToolpathFilter, ToolpathLod, ToolpathSegment,
+    ToolpathSegmentType, ToolpathTimeline, Visualizer, VisualizerControls, VoxelGrid,
 };
 
 pub use gcode::{
+    expand_program,
     stream::{FileStreamReader, GcodeStreamReader, PausableStream, StringStreamReader},
-    CommandId, CommandLengthProcessor, CommandListener, CommandListenerHandle,
-    CommandNumberGenerator, CommandProcessor, CommandResponse, CommandState, CommentProcessor,
-    DecimalProcessor, EmptyLineRemoverProcessor, GcodeCommand, GcodeParser, GcodeState, ModalState,
-    ProcessorConfig, ProcessorHandle, ProcessorPipeline, ProcessorRegistry, WhitespaceProcessor,
+    AffineTransform, ArcFitStats, ArcFitter, BacklashCompensator, CommandId,
+    CommandLengthProcessor, CommandListener, CommandListenerHandle, CommandNumberGenerator,
+    CommandProcessor, CommandResponse, CommandState, CommentProcessor, DecimalProcessor,
+    DynamicPowerProcessor, EmptyLineRemoverProcessor, ExpandedLine, ExpansionError, ExpansionStep,
+    GcodeCommand, GcodeParser, GcodeState, LaserSettings, MirrorAxis, ModalState,
+    OverscanProcessor, ParametricExpander, ParametricProcessor, ParametricProgram,
+    PowerClampProcessor, PowerCurve, PowerScaleProcessor, PowerScaling, ProcessorConfig,
+    ProcessorHandle, ProcessorPipeline, ProcessorRegistry, RedundantPowerRemover,
+    TransformProcessor, WhitespaceProcessor,
 };
 
 pub use utils::{
-    AdvancedProber, Alarm, AlarmManager, AlarmType, AutoConnectConfig, BackupEntry, BackupManager,
-    BasicProber, Bookmark, BookmarkManager, CommandHistory, CustomAction, CustomMacro, DataLogger,
-    DropEvent, DropFileType, DropIndicatorState, DropTarget, DropZone, ExportOptions,
-    FeedRateStats, FileComparison, FileEncoding, FileExporter, FileFormat, FileProcessingPipeline,
-    FileReadStats, FileStatistics, FileValidation, GcodeFileReader, GcodeTemplate, HeightPoint,
-    HistoryEntry, LogEntry, NetworkConfig, PendantButton, PendantConfig, PerformanceMetrics,
-    ProbeMesh, ProbePoint, ProcessedFile, ProgramState, RecentFileEntry, RecentFilesManager,
+    AdvancedProber, Alarm, AlarmManager, AlarmType, AutoConnectConfig, AutoLeveler, BackupEntry,
+    BackupManager, BasicProber, Bookmark, BookmarkManager, CommandHistory, CustomAction,
+    CustomMacro, DataLogger, DropEvent, DropFileType, DropIndicatorState, DropTarget, DropZone,
+    ExportOptions, FeedRateStats, FileComparison, FileEncoding, FileExporter, FileFormat,
+    FileProcessingPipeline, FileReadStats, FileStatistics, FileValidation, GcodeFileReader,
+    GcodeTemplate, HeightMap, HeightPoint, HistoryEntry, JobReport, LogEntry, NetworkConfig,
+    PendantButton, PendantConfig, PerformanceMetrics, ProbeGrid, ProbeMesh, ProbePoint,
+    ProcessedFile, ProgramState, RecentFileEntry, RecentFilesManager, ReportFormat, ReportOptions,
     SimulationPosition, Simulator, SoftLimits, SpindleStats, Stepper, TemplateLibrary,
     TemplateVariable, ToolInfo, ToolLibrary, ToolOffset, ToolOffsetManager, ValidationIssue,
     ValidationResult, ValidationSeverity, WorkCoordinateSystem, WorkOffset,
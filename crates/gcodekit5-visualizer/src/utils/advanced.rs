@@ -14,6 +14,8 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::visualizer::{DiffSettings, ToolpathDiff};
+
 // ============================================================================
 // TASK 97: FILE VALIDATION UI
 // ============================================================================
@@ -240,6 +242,16 @@ impl FileComparison {
             self.change_percentage()
         )
     }
+
+    /// Compare where the two programs cut rather than their text, see
+    /// [`ToolpathDiff`]
+    pub fn geometric_diff(&self, settings: DiffSettings) -> ToolpathDiff {
+        ToolpathDiff::compare(
+            &self.original_lines.join("\n"),
+            &self.processed_lines.join("\n"),
+            settings,
+        )
+    }
 }
 
 // ============================================================================
@@ -699,6 +711,18 @@ mod tests {
         assert_eq!(comparison.modified_count, 1);
     }
 
+    #[test]
+    fn test_geometric_file_comparison() {
+        // Reformatted but cutting the same path
+        let original = "G0 X0 Y0 Z1\nG1 Z-1 F100\nG1 X10\n";
+        let reposted = "N10 G0 X0.000 Y0.000 Z1.000\nN20 G1 Z-1.000 F100\nN30 X10.000\n";
+        let comparison = FileComparison::new(original, reposted);
+        assert_eq!(comparison.modified_count, 3);
+        assert!(comparison
+            .geometric_diff(DiffSettings::default())
+            .is_equivalent());
+    }
+
     #[test]
     fn test_template_expansion() {
         let mut template = GcodeTemplate::new("move", "Move Template", "G0 X{{X}} Y{{Y}}");
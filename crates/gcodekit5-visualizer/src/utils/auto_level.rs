@@ -0,0 +1,773 @@
+//! Height-map auto-leveling
+//!
+//! Probes a grid over the job, keeps the heights in a [`ProbeMesh`] and
+//! rewrites a program so every Z follows the measured surface. Long feed
+//! moves and arcs are split so the tool tracks the surface between probe
+//! points, which keeps engraving depth constant on PCBs and warped boards.
+//!
+//! Typical workflow:
+//! 1. [`ProbeGrid::for_program`] plans the grid over the job's cutting area.
+//! 2. [`ProbeGrid::to_gcode`] is streamed to the controller; each `[PRB:]`
+//!    report gives one height, in grid order.
+//! 3. [`ProbeGrid::build_mesh`] turns the heights into a [`ProbeMesh`], which
+//!    can be saved and loaded.
+//! 4. [`AutoLeveler::apply`] corrects the program.
+
+use std::f64::consts::TAU;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::phase6_extended::ProbeMesh;
+use crate::visualizer::Visualizer;
+
+/// Distance below which two probe coordinates are the same grid line
+const GRID_EPSILON: f64 = 1e-6;
+
+/// Probe positions and probing parameters for a height map
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeGrid {
+    pub x_min: f64,
+    pub y_min: f64,
+    pub x_max: f64,
+    pub y_max: f64,
+    /// Number of probe points along X (at least 2)
+    pub cols: usize,
+    /// Number of probe points along Y (at least 2)
+    pub rows: usize,
+    /// Z height for moves between probe points
+    pub clearance_z: f64,
+    /// Lowest Z the probe may reach before the cycle fails
+    pub probe_min_z: f64,
+    /// Probing feed rate in mm/min
+    pub probe_feed: f64,
+}
+
+impl ProbeGrid {
+    /// Grid with a fixed number of points over a rectangle (mm)
+    pub fn new(min: (f64, f64), max: (f64, f64), cols: usize, rows: usize) -> Self {
+        Self {
+            x_min: min.0.min(max.0),
+            y_min: min.1.min(max.1),
+            x_max: min.0.max(max.0),
+            y_max: min.1.max(max.1),
+            cols: cols.max(2),
+            rows: rows.max(2),
+            clearance_z: 2.0,
+            probe_min_z: -5.0,
+            probe_feed: 50.0,
+        }
+    }
+
+    /// Grid with points no further apart than `spacing`
+    pub fn with_spacing(min: (f64, f64), max: (f64, f64), spacing: f64) -> Self {
+        let spacing = spacing.max(0.1);
+        let count = |span: f64| ((span.abs() / spacing).ceil() as usize + 1).max(2);
+        Self::new(min, max, count(max.0 - min.0), count(max.1 - min.1))
+    }
+
+    /// Grid over the cutting moves of a program, grown by `margin` on each
+    /// side. Returns `None` if the program has no cutting moves.
+    pub fn for_program(gcode: &str, spacing: f64, margin: f64) -> Option<Self> {
+        let mut visualizer = Visualizer::new();
+        visualizer.parse_gcode(gcode);
+        let (min_x, max_x, min_y, max_y, _, _) = visualizer.get_cutting_bounds()?;
+        Some(Self::with_spacing(
+            (min_x as f64 - margin, min_y as f64 - margin),
+            (max_x as f64 + margin, max_y as f64 + margin),
+            spacing,
+        ))
+    }
+
+    /// Set clearance height, probe depth limit and probing feed rate
+    pub fn with_probe(mut self, clearance_z: f64, probe_min_z: f64, probe_feed: f64) -> Self {
+        self.clearance_z = clearance_z;
+        self.probe_min_z = probe_min_z;
+        self.probe_feed = probe_feed;
+        self
+    }
+
+    pub fn x_spacing(&self) -> f64 {
+        (self.x_max - self.x_min) / (self.cols - 1) as f64
+    }
+
+    pub fn y_spacing(&self) -> f64 {
+        (self.y_max - self.y_min) / (self.rows - 1) as f64
+    }
+
+    pub fn point_count(&self) -> usize {
+        self.cols * self.rows
+    }
+
+    /// Probe positions in probing order: row by row from `y_min`, each row
+    /// from `x_min`
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        let (dx, dy) = (self.x_spacing(), self.y_spacing());
+        (0..self.rows)
+            .flat_map(|row| {
+                (0..self.cols)
+                    .map(move |col| (self.x_min + col as f64 * dx, self.y_min + row as f64 * dy))
+            })
+            .collect()
+    }
+
+    /// Probing program: one `G38.2` cycle per grid point, in [`Self::points`]
+    /// order. Z must be zeroed near the surface before running it.
+    pub fn to_gcode(&self) -> String {
+        let mut gcode = format!(
+            "; Auto-level probe grid {} x {} ({} points)\n\
+             ; Area X{:.3}..{:.3} Y{:.3}..{:.3}\n\
+             G21\nG90\nG0 Z{:.3}\n",
+            self.cols,
+            self.rows,
+            self.point_count(),
+            self.x_min,
+            self.x_max,
+            self.y_min,
+            self.y_max,
+            self.clearance_z
+        );
+        for (x, y) in self.points() {
+            gcode.push_str(&format!(
+                "G0 X{:.3} Y{:.3}\nG38.2 Z{:.3} F{:.1}\nG0 Z{:.3}\n",
+                x, y, self.probe_min_z, self.probe_feed, self.clearance_z
+            ));
+        }
+        gcode.push_str(&format!("G0 X{:.3} Y{:.3}\n", self.x_min, self.y_min));
+        gcode
+    }
+
+    /// Build the mesh from probed heights, `heights[row][col]` as returned by
+    /// `ProbingSystem::generate_mesh(cols, rows)`
+    pub fn build_mesh(&self, heights: &[Vec<f32>]) -> Result<ProbeMesh> {
+        if heights.len() != self.rows || heights.iter().any(|row| row.len() != self.cols) {
+            bail!(
+                "Expected {} x {} probe heights, got {} rows",
+                self.cols,
+                self.rows,
+                heights.len()
+            );
+        }
+        let heights: Vec<Vec<f64>> = heights
+            .iter()
+            .map(|row| row.iter().map(|z| *z as f64).collect())
+            .collect();
+        Ok(ProbeMesh::from_grid(
+            self.x_min,
+            self.y_min,
+            self.x_spacing(),
+            self.y_spacing(),
+            &heights,
+        ))
+    }
+}
+
+/// Regular grid of heights with bilinear interpolation
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeightMap {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    /// Heights row by row, `z[row * xs.len() + col]`
+    z: Vec<f64>,
+}
+
+impl HeightMap {
+    /// Build from a mesh whose points cover every intersection of their
+    /// distinct X and Y coordinates; `None` for irregular point sets
+    pub fn from_mesh(mesh: &ProbeMesh) -> Option<Self> {
+        let distinct = |values: Vec<f64>| {
+            let mut values = values;
+            values.sort_by(|a, b| a.total_cmp(b));
+            values.dedup_by(|a, b| (*a - *b).abs() < GRID_EPSILON);
+            values
+        };
+        let xs = distinct(mesh.points.iter().map(|p| p.x).collect());
+        let ys = distinct(mesh.points.iter().map(|p| p.y).collect());
+        if xs.is_empty() || xs.len() * ys.len() != mesh.points.len() {
+            return None;
+        }
+
+        let index = |values: &[f64], v: f64| {
+            values
+                .iter()
+                .position(|candidate| (candidate - v).abs() < GRID_EPSILON)
+        };
+        let mut z = vec![None; mesh.points.len()];
+        for point in &mesh.points {
+            let cell = index(&ys, point.y)? * xs.len() + index(&xs, point.x)?;
+            z[cell] = Some(point.z);
+        }
+        let z = z.into_iter().collect::<Option<Vec<f64>>>()?;
+        Some(Self { xs, ys, z })
+    }
+
+    /// Interpolated height; positions outside the grid use the nearest edge
+    pub fn z_at(&self, x: f64, y: f64) -> f64 {
+        let (col, tx) = Self::locate(&self.xs, x);
+        let (row, ty) = Self::locate(&self.ys, y);
+        let cols = self.xs.len();
+        let at = |r: usize, c: usize| self.z[r.min(self.ys.len() - 1) * cols + c.min(cols - 1)];
+        let bottom = at(row, col) * (1.0 - tx) + at(row, col + 1) * tx;
+        let top = at(row + 1, col) * (1.0 - tx) + at(row + 1, col + 1) * tx;
+        bottom * (1.0 - ty) + top * ty
+    }
+
+    /// Cell index and fraction across the cell, clamped to the grid
+    fn locate(values: &[f64], v: f64) -> (usize, f64) {
+        if values.len() < 2 || v <= values[0] {
+            return (0, 0.0);
+        }
+        let last = values.len() - 1;
+        if v >= values[last] {
+            return (last - 1, 1.0);
+        }
+        let cell = values
+            .partition_point(|x| *x <= v)
+            .saturating_sub(1)
+            .min(last - 1);
+        let span = values[cell + 1] - values[cell];
+        (cell, ((v - values[cell]) / span).clamp(0.0, 1.0))
+    }
+}
+
+/// Axis positions tracked while rewriting a program
+type Position = [Option<f64>; 3];
+
+/// One word of a G-code line, e.g. `X12.5`
+#[derive(Debug, Clone)]
+struct Word {
+    letter: char,
+    value: f64,
+    text: String,
+}
+
+/// Rewrites a program so every Z is offset by the probed surface height
+#[derive(Debug, Clone)]
+pub struct AutoLeveler {
+    map: HeightMap,
+    max_segment_length: f64,
+    arc_tolerance: f64,
+}
+
+impl AutoLeveler {
+    /// Leveler for a mesh measured in millimetres. The mesh must be a
+    /// regular grid, as produced by [`ProbeGrid::build_mesh`].
+    pub fn new(mesh: &ProbeMesh) -> Result<Self> {
+        let map = HeightMap::from_mesh(mesh)
+            .ok_or_else(|| anyhow!("Probe mesh is empty or not a regular grid"))?;
+        Ok(Self {
+            map,
+            max_segment_length: 1.0,
+            arc_tolerance: 0.01,
+        })
+    }
+
+    /// Longest feed move emitted without subdivision (mm, default 1.0)
+    pub fn with_segment_length(mut self, length: f64) -> Self {
+        self.max_segment_length = length.max(0.01);
+        self
+    }
+
+    /// Maximum chord deviation when arcs are linearised (mm, default 0.01)
+    pub fn with_arc_tolerance(mut self, tolerance: f64) -> Self {
+        self.arc_tolerance = tolerance.max(0.0001);
+        self
+    }
+
+    /// Height map used for the correction
+    pub fn height_map(&self) -> &HeightMap {
+        &self.map
+    }
+
+    /// Correct every Z in a program.
+    ///
+    /// Feed moves are split into segments of at most the segment length and
+    /// arcs become corrected G1 chords; rapids only get their end point
+    /// corrected. Lines in machine coordinates (G53), homing and probing
+    /// moves are passed through unchanged.
+    pub fn apply(&self, gcode: &str) -> Result<String> {
+        let mut state = LevelState::default();
+        let mut out = String::with_capacity(gcode.len() * 2);
+        for (index, line) in gcode.lines().enumerate() {
+            self.level_line(line, &mut state, &mut out)
+                .map_err(|e| anyhow!("Line {}: {}", index + 1, e))?;
+        }
+        Ok(out)
+    }
+
+    fn level_line(&self, line: &str, state: &mut LevelState, out: &mut String) -> Result<()> {
+        let (code, comment) = split_comment(line);
+        let words = parse_words(&code)?;
+        if words.is_empty() {
+            push_line(out, line);
+            return Ok(());
+        }
+
+        let g_codes: Vec<f64> = words
+            .iter()
+            .filter(|w| w.letter == 'G')
+            .map(|w| w.value)
+            .collect();
+        let has_g = |code: f64| g_codes.iter().any(|g| (g - code).abs() < 1e-3);
+
+        // Modal state set on this line
+        if has_g(90.0) {
+            state.relative = false;
+        }
+        if has_g(91.0) {
+            state.relative = true;
+        }
+        if has_g(20.0) {
+            state.to_mm = 25.4;
+        }
+        if has_g(21.0) {
+            state.to_mm = 1.0;
+        }
+        for (code, plane) in [(17.0, Plane::XY), (18.0, Plane::ZX), (19.0, Plane::YZ)] {
+            if has_g(code) {
+                state.plane = plane;
+            }
+        }
+        if has_g(93.0) {
+            bail!("inverse time feed (G93) cannot be leveled");
+        }
+        if has_g(80.0) {
+            state.motion = None;
+        }
+        let motion_word = [0.0, 1.0, 2.0, 3.0].into_iter().find(|m| has_g(*m));
+        if let Some(m) = motion_word {
+            state.motion = Some(m as u8);
+        }
+
+        let axis_value = |letter: char| words.iter().find(|w| w.letter == letter).map(|w| w.value);
+        let axes = [axis_value('X'), axis_value('Y'), axis_value('Z')];
+        let has_axes = axes.iter().any(Option::is_some);
+
+        // Non-modal commands that move in other coordinate frames
+        if has_g(53.0) || has_g(28.0) || has_g(30.0) || g_codes.iter().any(|g| g.floor() == 38.0) {
+            push_line(out, line);
+            if has_g(28.0) || has_g(30.0) {
+                state.pos = [None; 3];
+            } else {
+                for (pos, axis) in state.pos.iter_mut().zip(axes) {
+                    if axis.is_some() {
+                        *pos = None;
+                    }
+                }
+            }
+            if axes[2].is_some() || has_g(28.0) || has_g(30.0) {
+                state.out_z = None;
+            }
+            return Ok(());
+        }
+        if has_g(92.0) {
+            push_line(out, line);
+            for (pos, axis) in state.pos.iter_mut().zip(axes) {
+                if axis.is_some() {
+                    *pos = axis;
+                }
+            }
+            if axes[2].is_some() {
+                state.out_z = axes[2];
+            }
+            return Ok(());
+        }
+        if has_g(10.0) || has_g(4.0) {
+            push_line(out, line);
+            return Ok(());
+        }
+
+        let Some(motion) = state.motion.filter(|_| has_axes) else {
+            push_line(out, line);
+            return Ok(());
+        };
+
+        let start = state.pos;
+        let target = self.target(state, &start, &axes);
+        let arc = motion == 2 || motion == 3;
+
+        // Words kept on the first emitted segment
+        let motion_codes = [0.0, 1.0, 2.0, 3.0];
+        let mut prefix = Vec::new();
+        let mut suffix = Vec::new();
+        for word in &words {
+            match word.letter {
+                'X' | 'Y' | 'Z' => {}
+                'I' | 'J' | 'K' | 'R' if arc => {}
+                'G' if motion_codes.iter().any(|m| (word.value - m).abs() < 1e-3) => {}
+                'N' | 'G' => prefix.push(word.text.clone()),
+                _ => suffix.push(word.text.clone()),
+            }
+        }
+
+        let (Some(end), Some(begin)) = (known(&target), known(&start)) else {
+            // Position not fully known: only the end point can be corrected
+            self.emit_partial(
+                state, motion, &target, &prefix, &suffix, &comment, &words, out,
+            );
+            state.pos = target;
+            return Ok(());
+        };
+
+        let points = if arc {
+            let offsets = [
+                axis_word(&words, 'I'),
+                axis_word(&words, 'J'),
+                axis_word(&words, 'K'),
+            ];
+            let radius = axis_word(&words, 'R');
+            self.arc_points(state, begin, end, motion == 2, offsets, radius)?
+        } else if motion == 1 {
+            self.line_points(state, begin, end)
+        } else {
+            vec![end]
+        };
+
+        let out_motion = if motion == 0 { 0 } else { 1 };
+        for (i, point) in points.iter().enumerate() {
+            let mut parts: Vec<String> = Vec::new();
+            if i == 0 {
+                parts.extend(prefix.iter().cloned());
+            }
+            parts.push(format!("G{}", out_motion));
+            parts.extend(self.coordinates(state, point));
+            if i == 0 {
+                parts.extend(suffix.iter().cloned());
+                if !comment.is_empty() {
+                    parts.push(comment.clone());
+                }
+            }
+            push_line(out, &parts.join(" "));
+        }
+        state.pos = target;
+        Ok(())
+    }
+
+    /// End position of a move in program coordinates
+    fn target(&self, state: &LevelState, start: &Position, axes: &[Option<f64>; 3]) -> Position {
+        let mut target = *start;
+        for i in 0..3 {
+            if let Some(value) = axes[i] {
+                target[i] = if state.relative {
+                    start[i].map(|s| s + value)
+                } else {
+                    Some(value)
+                };
+            }
+        }
+        target
+    }
+
+    /// Surface offset at a position in program units
+    fn offset(&self, state: &LevelState, x: f64, y: f64) -> f64 {
+        self.map.z_at(x * state.to_mm, y * state.to_mm) / state.to_mm
+    }
+
+    /// Axis words for a corrected point, absolute or relative to the last
+    /// emitted position
+    fn coordinates(&self, state: &mut LevelState, point: &[f64; 3]) -> Vec<String> {
+        let z = point[2] + self.offset(state, point[0], point[1]);
+        let words = if state.relative {
+            let from = state.out_xy.unwrap_or([point[0], point[1]]);
+            let from_z = state.out_z.unwrap_or(z);
+            vec![
+                format!("X{}", format_number(point[0] - from[0])),
+                format!("Y{}", format_number(point[1] - from[1])),
+                format!("Z{}", format_number(z - from_z)),
+            ]
+        } else {
+            vec![
+                format!("X{}", format_number(point[0])),
+                format!("Y{}", format_number(point[1])),
+                format!("Z{}", format_number(z)),
+            ]
+        };
+        state.out_xy = Some([point[0], point[1]]);
+        state.out_z = Some(z);
+        words
+    }
+
+    /// Move whose start or end is partly unknown, e.g. the first move of a
+    /// program. The Z word is corrected when X, Y and Z of the end are known.
+    #[allow(clippy::too_many_arguments)]
+    fn emit_partial(
+        &self,
+        state: &mut LevelState,
+        motion: u8,
+        target: &Position,
+        prefix: &[String],
+        suffix: &[String],
+        comment: &str,
+        words: &[Word],
+        out: &mut String,
+    ) {
+        let Some(end) = known(target).filter(|_| !state.relative && motion < 2) else {
+            // Cannot correct; keep the line but make the motion mode explicit
+            let mut parts: Vec<String> = prefix.to_vec();
+            parts.push(format!("G{}", motion));
+            parts.extend(
+                words
+                    .iter()
+                    .filter(|w| matches!(w.letter, 'X' | 'Y' | 'Z' | 'I' | 'J' | 'K' | 'R'))
+                    .map(|w| w.text.clone()),
+            );
+            parts.extend(suffix.iter().cloned());
+            if !comment.is_empty() {
+                parts.push(comment.to_string());
+            }
+            push_line(out, &parts.join(" "));
+            if let Some(z) = target[2] {
+                state.out_z = if state.relative {
+                    state
+                        .out_z
+                        .zip(words.iter().find(|w| w.letter == 'Z'))
+                        .map(|(o, w)| o + w.value)
+                } else {
+                    Some(z)
+                };
+            }
+            state.out_xy = None;
+            return;
+        };
+
+        let mut parts: Vec<String> = prefix.to_vec();
+        parts.push(format!("G{}", motion));
+        parts.extend(self.coordinates(state, &end));
+        parts.extend(suffix.iter().cloned());
+        if !comment.is_empty() {
+            parts.push(comment.to_string());
+        }
+        push_line(out, &parts.join(" "));
+    }
+
+    /// Points along a feed move, no further apart in XY than the segment length
+    fn line_points(&self, state: &LevelState, start: [f64; 3], end: [f64; 3]) -> Vec<[f64; 3]> {
+        let length = ((end[0] - start[0]).powi(2) + (end[1] - start[1]).powi(2)).sqrt();
+        let max_length = self.max_segment_length / state.to_mm;
+        let steps = ((length / max_length).ceil() as usize).max(1);
+        (1..=steps)
+            .map(|i| {
+                let t = i as f64 / steps as f64;
+                [
+                    start[0] + (end[0] - start[0]) * t,
+                    start[1] + (end[1] - start[1]) * t,
+                    start[2] + (end[2] - start[2]) * t,
+                ]
+            })
+            .collect()
+    }
+
+    /// Chord end points of an arc in the active plane, helical moves included
+    fn arc_points(
+        &self,
+        state: &LevelState,
+        start: [f64; 3],
+        end: [f64; 3],
+        clockwise: bool,
+        offsets: [Option<f64>; 3],
+        radius: Option<f64>,
+    ) -> Result<Vec<[f64; 3]>> {
+        let (a, b, n) = state.plane.axes();
+        let (sa, sb) = (start[a], start[b]);
+        let (ea, eb) = (end[a], end[b]);
+
+        let (ca, cb) = match radius {
+            Some(r) => {
+                let (dx, dy) = (ea - sa, eb - sb);
+                let d = (dx * dx + dy * dy).sqrt();
+                if d < 1e-9 {
+                    bail!("R-form arc with identical start and end point");
+                }
+                let h2 = r * r - (d / 2.0).powi(2);
+                // Allow rounding in the programmed radius
+                let h = if h2 < 0.0 && h2 > -1e-6 * r * r {
+                    0.0
+                } else {
+                    h2.sqrt()
+                };
+                if h.is_nan() {
+                    bail!("arc radius {} is too small for the end point", r);
+                }
+                // Centre to the left of the chord for counter-clockwise short arcs
+                let side = if clockwise { -1.0 } else { 1.0 } * r.signum();
+                (
+                    sa + dx / 2.0 - side * h * dy / d,
+                    sb + dy / 2.0 + side * h * dx / d,
+                )
+            }
+            None => (
+                sa + offsets[a].unwrap_or(0.0),
+                sb + offsets[b].unwrap_or(0.0),
+            ),
+        };
+
+        let r = ((sa - ca).powi(2) + (sb - cb).powi(2)).sqrt();
+        if r < 1e-9 {
+            bail!("arc has zero radius");
+        }
+        let start_angle = (sb - cb).atan2(sa - ca);
+        let end_angle = (eb - cb).atan2(ea - ca);
+        let mut sweep = if clockwise {
+            start_angle - end_angle
+        } else {
+            end_angle - start_angle
+        };
+        if sweep <= 1e-9 {
+            sweep += TAU;
+        }
+
+        // Chord count from the tolerance and the segment length
+        let tolerance = (self.arc_tolerance / state.to_mm).min(r);
+        let max_angle = (2.0 * (1.0 - tolerance / r).acos())
+            .min(self.max_segment_length / state.to_mm / r)
+            .max(1e-3);
+        let steps = ((sweep / max_angle).ceil() as usize).max(1);
+        let direction = if clockwise { -1.0 } else { 1.0 };
+
+        Ok((1..=steps)
+            .map(|i| {
+                if i == steps {
+                    return end;
+                }
+                let t = i as f64 / steps as f64;
+                let angle = start_angle + direction * sweep * t;
+                let mut p = [0.0; 3];
+                p[a] = ca + r * angle.cos();
+                p[b] = cb + r * angle.sin();
+                p[n] = start[n] + (end[n] - start[n]) * t;
+                p
+            })
+            .collect())
+    }
+}
+
+/// Arc plane with its two in-plane axes and the normal axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Plane {
+    #[default]
+    XY,
+    ZX,
+    YZ,
+}
+
+impl Plane {
+    /// Indices of the first and second plane axis and the normal axis
+    fn axes(&self) -> (usize, usize, usize) {
+        match self {
+            Self::XY => (0, 1, 2),
+            Self::ZX => (2, 0, 1),
+            Self::YZ => (1, 2, 0),
+        }
+    }
+}
+
+/// Modal state while rewriting a program
+#[derive(Debug, Clone)]
+struct LevelState {
+    /// Program position (uncorrected)
+    pos: Position,
+    /// Last XY sent to the controller
+    out_xy: Option<[f64; 2]>,
+    /// Last corrected Z sent to the controller
+    out_z: Option<f64>,
+    motion: Option<u8>,
+    relative: bool,
+    plane: Plane,
+    /// Millimetres per program unit
+    to_mm: f64,
+}
+
+impl Default for LevelState {
+    fn default() -> Self {
+        Self {
+            pos: [None; 3],
+            out_xy: None,
+            out_z: None,
+            motion: None,
+            relative: false,
+            plane: Plane::XY,
+            to_mm: 1.0,
+        }
+    }
+}
+
+fn known(position: &Position) -> Option<[f64; 3]> {
+    Some([position[0]?, position[1]?, position[2]?])
+}
+
+fn axis_word(words: &[Word], letter: char) -> Option<f64> {
+    words.iter().find(|w| w.letter == letter).map(|w| w.value)
+}
+
+fn push_line(out: &mut String, line: &str) {
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// Split a line into code and comment text (`(...)` and `;...`)
+fn split_comment(line: &str) -> (String, String) {
+    let mut code = String::new();
+    let mut comment = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => {
+                comment.push(c);
+                for c in chars.by_ref() {
+                    comment.push(c);
+                    if c == ')' {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                comment.push(c);
+                comment.extend(chars.by_ref());
+            }
+            _ => code.push(c),
+        }
+    }
+    (code, comment.trim().to_string())
+}
+
+/// Parse address words; spaces inside a word (`X 10`) are allowed
+fn parse_words(code: &str) -> Result<Vec<Word>> {
+    let mut words = Vec::new();
+    let mut chars = code.chars().filter(|c| !c.is_whitespace()).peekable();
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            continue;
+        }
+        if !c.is_ascii_alphabetic() {
+            bail!("unexpected character '{}'", c);
+        }
+        let letter = c.to_ascii_uppercase();
+        let mut number = String::new();
+        while let Some(&d) = chars.peek() {
+            if d.is_ascii_digit() || matches!(d, '.' | '-' | '+') {
+                number.push(d);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let value = number
+            .parse::<f64>()
+            .map_err(|_| anyhow!("invalid value for {}: '{}'", letter, number))?;
+        words.push(Word {
+            letter,
+            value,
+            text: format!("{}{}", letter, number),
+        });
+    }
+    Ok(words)
+}
+
+/// Format a coordinate with up to 4 decimals and no trailing zeros
+fn format_number(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" | "" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
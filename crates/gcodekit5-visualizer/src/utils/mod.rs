@@ -1,17 +1,20 @@
 //! Utility functions and helpers
 
 pub mod advanced;
+pub mod auto_level;
 pub mod export;
 pub mod file_io;
 pub mod phase6_extended;
 pub mod phase7;
 pub mod processing;
+pub mod report;
 
 pub use advanced::{
     AdvancedProber, BackupEntry, BackupManager, BasicProber, FileComparison, GcodeTemplate,
     ProbePoint, TemplateLibrary, TemplateVariable, ValidationIssue, ValidationResult,
     ValidationSeverity,
 };
+pub use auto_level::{AutoLeveler, HeightMap, ProbeGrid};
 pub use export::{
     DropEvent, DropFileType, DropIndicatorState, DropTarget, DropZone, ExportOptions, FileExporter,
     FileFormat,
@@ -28,15 +31,18 @@ pub use phase6_extended::{
     WorkCoordinateSystem, WorkOffset,
 };
 pub use phase7::{
-    BufferDiagnostics, CalibrationResult, CalibrationStep, CalibrationStepType, CalibrationWizard,
-    CommunicationDiagnostics, DiagnosticReport, EmergencyStopManager, EmergencyStopState,
-    ExportFormat, FeedHoldManager, FormatExporter, MotionInterlock, PerformanceProfiler, Plugin,
-    PluginConfig, PluginError, PluginMetadata, PluginRegistry, PostProcessor, SafetyError,
-    SafetyFeaturesManager,
+    BacklashProcedure, BufferDiagnostics, CalibrationResult, CalibrationStep, CalibrationStepType,
+    CalibrationWizard, CommunicationDiagnostics, DiagnosticReport, EmergencyStopManager,
+    EmergencyStopState, ExportFormat, FeedHoldManager, FormatExporter, MotionInterlock,
+    PerformanceProfiler, Plugin, PluginConfig, PluginError, PluginMetadata, PluginRegistry,
+    PostProcessor, SafetyError, SafetyFeaturesManager,
 };
 pub use processing::{
     FeedRateStats, FileProcessingPipeline, FileStatistics, ProcessedFile, SpindleStats,
 };
+pub use report::{
+    HistogramBin, JobReport, MotionSummary, ReportFormat, ReportOptions, ToolChange, ToolUsage,
+};
 
 /// Format a float to a reasonable number of decimal places
 pub fn format_float(value: f64, precision: usize) -> String {
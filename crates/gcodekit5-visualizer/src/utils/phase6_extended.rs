@@ -19,10 +19,11 @@
 //! Task 119: Data logging
 //! Task 120: Alarms and notifications
 
+use super::auto_level::HeightMap;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 // ============================================================================
@@ -74,13 +75,46 @@ impl ProbeMesh {
         self.points.push(point);
     }
 
+    /// Create a mesh from a regular grid of heights, `heights[row][col]`
+    /// with row 0 at `y_min` and column 0 at `x_min`
+    pub fn from_grid(
+        x_min: f64,
+        y_min: f64,
+        x_spacing: f64,
+        y_spacing: f64,
+        heights: &[Vec<f64>],
+    ) -> Self {
+        let mut mesh = Self::new(x_spacing, y_spacing);
+        for (row, values) in heights.iter().enumerate() {
+            for (col, z) in values.iter().enumerate() {
+                mesh.add_point(HeightPoint {
+                    x: x_min + col as f64 * x_spacing,
+                    y: y_min + row as f64 * y_spacing,
+                    z: *z,
+                });
+            }
+        }
+        mesh
+    }
+
     /// Get Z offset at position (interpolated)
+    ///
+    /// Uses bilinear interpolation when the points form a regular grid,
+    /// clamping to the edge outside the probed area. Irregular point sets
+    /// fall back to the average of up to 4 nearest points.
+    ///
+    /// The grid is rebuilt on every call; callers sampling many positions
+    /// should build a [`HeightMap`] once with [`HeightMap::from_mesh`].
     pub fn get_z_offset(&self, x: f64, y: f64) -> Option<f64> {
         if self.points.is_empty() {
             return None;
         }
 
-        // Find 4 nearest points for bilinear interpolation
+        if let Some(map) = HeightMap::from_mesh(self) {
+            return Some(map.z_at(x, y));
+        }
+
+        // Find the nearest points
         let mut nearest = self
             .points
             .iter()
@@ -88,13 +122,38 @@ impl ProbeMesh {
             .collect::<Vec<_>>();
         nearest.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
 
-        if nearest.len() < 4 {
-            return Some(nearest[0].0.z);
+        // Simple average of up to 4 nearest
+        let nearest = &nearest[..nearest.len().min(4)];
+        let avg = nearest.iter().map(|(p, _)| p.z).sum::<f64>() / nearest.len() as f64;
+        Some(avg)
+    }
+
+    /// Shift all heights so the offset at (x, y) is zero, e.g. at the point
+    /// where the work Z zero was set
+    pub fn normalize_to(&mut self, x: f64, y: f64) {
+        let Some(reference) = self.get_z_offset(x, y) else {
+            return;
+        };
+        self.z_min = f64::MAX;
+        self.z_max = f64::MIN;
+        for point in &mut self.points {
+            point.z -= reference;
+            self.z_min = self.z_min.min(point.z);
+            self.z_max = self.z_max.max(point.z);
         }
+    }
 
-        // Simple average of 4 nearest
-        let avg = nearest[..4].iter().map(|(p, _)| p.z).sum::<f64>() / 4.0;
-        Some(avg)
+    /// Save mesh to a JSON file
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Load mesh from a JSON file
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
     }
 
     /// Get mesh statistics
@@ -14,6 +14,7 @@
 //! Dependencies: anyhow, thiserror, serde, chrono, uuid, regex
 
 use anyhow::{anyhow, Result};
+use gcodekit5_core::{CommentStyle, NumberFormat, PostContext, PostDefinition};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
@@ -375,6 +376,9 @@ impl Default for PluginRegistry {
 // ============================================================================
 
 /// Export format types
+///
+/// Presets for [`PostProcessor::for_format`]; anything else is described by
+/// a [`PostDefinition`] and uses [`ExportFormat::Custom`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ExportFormat {
     /// Standard G-code
@@ -387,6 +391,8 @@ pub enum ExportFormat {
     Haas,
     /// Siemens format
     Siemens,
+    /// User or built-in post-processor definition
+    Custom,
 }
 
 impl fmt::Display for ExportFormat {
@@ -397,11 +403,15 @@ impl fmt::Display for ExportFormat {
             ExportFormat::FANUC => write!(f, "FANUC"),
             ExportFormat::Haas => write!(f, "Haas"),
             ExportFormat::Siemens => write!(f, "Siemens"),
+            ExportFormat::Custom => write!(f, "Custom"),
         }
     }
 }
 
 /// Post-processor configuration
+///
+/// Wraps a [`PostDefinition`]; the pattern fields describe the tool-change
+/// and spindle blocks of the presets and are folded into the definition.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PostProcessor {
     pub name: String,
@@ -411,75 +421,103 @@ pub struct PostProcessor {
     pub spindle_off_pattern: String,
     pub feed_rate_multiplier: f64,
     pub precision_digits: usize,
+    pub definition: PostDefinition,
 }
 
 impl PostProcessor {
     /// Create default post-processor for format
     pub fn for_format(format: ExportFormat) -> Self {
-        match format {
-            ExportFormat::StandardGcode => Self {
-                name: "Standard G-code".to_string(),
-                format,
-                tool_change_pattern: "M6".to_string(),
-                spindle_on_pattern: "M3 S{speed}".to_string(),
-                spindle_off_pattern: "M5".to_string(),
-                feed_rate_multiplier: 1.0,
-                precision_digits: 4,
-            },
-            ExportFormat::LinuxCNC => Self {
-                name: "LinuxCNC".to_string(),
-                format,
-                tool_change_pattern: "M6 T{tool}".to_string(),
-                spindle_on_pattern: "M3 S{speed}".to_string(),
-                spindle_off_pattern: "M5".to_string(),
-                feed_rate_multiplier: 1.0,
-                precision_digits: 5,
-            },
-            ExportFormat::FANUC => Self {
-                name: "FANUC".to_string(),
-                format,
-                tool_change_pattern: "T{tool}M6".to_string(),
-                spindle_on_pattern: "S{speed}M3".to_string(),
-                spindle_off_pattern: "M5".to_string(),
-                feed_rate_multiplier: 0.95,
-                precision_digits: 4,
-            },
-            ExportFormat::Haas => Self {
-                name: "Haas".to_string(),
-                format,
-                tool_change_pattern: "M6".to_string(),
-                spindle_on_pattern: "M3 S{speed}".to_string(),
-                spindle_off_pattern: "M5".to_string(),
-                feed_rate_multiplier: 1.0,
-                precision_digits: 4,
-            },
-            ExportFormat::Siemens => Self {
-                name: "Siemens".to_string(),
-                format,
-                tool_change_pattern: "M6T{tool}".to_string(),
-                spindle_on_pattern: "M3S{speed}".to_string(),
-                spindle_off_pattern: "M5".to_string(),
-                feed_rate_multiplier: 1.0,
-                precision_digits: 5,
+        let preset = |name: &str, tool: &str, spindle_on: &str, precision: usize| Self {
+            name: name.to_string(),
+            format,
+            tool_change_pattern: tool.to_string(),
+            spindle_on_pattern: spindle_on.to_string(),
+            spindle_off_pattern: "M5".to_string(),
+            feed_rate_multiplier: 1.0,
+            precision_digits: precision,
+            definition: PostDefinition::default(),
+        };
+        let mut processor = match format {
+            ExportFormat::LinuxCNC => {
+                let definition = PostDefinition::builtin("LinuxCNC").unwrap_or_default();
+                return Self::from_definition(definition);
+            }
+            ExportFormat::FANUC => {
+                let mut processor = preset("FANUC", "T{tool} M6", "S{speed} {direction}", 4);
+                processor.feed_rate_multiplier = 0.95;
+                processor
+            }
+            ExportFormat::Haas => preset("Haas", "T{tool} M6", "S{speed} {direction}", 4),
+            ExportFormat::Siemens => preset("Siemens", "T{tool} M6", "S{speed} {direction}", 5),
+            ExportFormat::StandardGcode | ExportFormat::Custom => {
+                preset("Standard G-code", "", "", 4)
+            }
+        };
+        processor.definition = processor.build_definition();
+        processor
+    }
+
+    /// Post-processor for a definition (built-in or loaded from a file)
+    pub fn from_definition(definition: PostDefinition) -> Self {
+        let precision_digits = definition
+            .format_for('X')
+            .map_or(4, |f| f.decimals as usize);
+        Self {
+            name: definition.name.clone(),
+            format: if definition.name == "LinuxCNC" {
+                ExportFormat::LinuxCNC
+            } else {
+                ExportFormat::Custom
             },
+            tool_change_pattern: definition.tool_change.join("\n"),
+            spindle_on_pattern: definition.spindle_on.join("\n"),
+            spindle_off_pattern: definition.spindle_off.join("\n"),
+            feed_rate_multiplier: 1.0,
+            precision_digits,
+            definition,
         }
     }
 
-    /// Convert G-code line for target format
-    pub fn convert_line(&self, line: &str) -> String {
-        let result = line.to_string();
-
-        // Apply precision-based formatting if needed
-        if line.contains('F') {
-            // Feed rate formatting would go here
+    /// Definition for the presets without a built-in definition
+    fn build_definition(&self) -> PostDefinition {
+        let mut definition = PostDefinition {
+            name: self.name.clone(),
+            ..PostDefinition::default()
+        };
+        let lines = |pattern: &str| -> Vec<String> {
+            pattern
+                .split('\n')
+                .filter(|l| !l.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        definition.tool_change = lines(&self.tool_change_pattern);
+        definition.spindle_on = lines(&self.spindle_on_pattern);
+        if self.format == ExportFormat::StandardGcode {
+            return definition;
         }
+        let coordinate = NumberFormat {
+            decimals: self.precision_digits as u8,
+            trim_zeros: true,
+            force_decimal_point: true,
+            leading_zero: true,
+        };
+        definition.formats.insert("XYZIJKR".to_string(), coordinate);
+        definition.header = vec!["%".to_string(), "O0001 ({program})".to_string()];
+        definition.footer = vec!["M30".to_string(), "%".to_string()];
+        definition.comment_style = CommentStyle::Parentheses;
+        definition.line_numbers.enabled = self.format == ExportFormat::FANUC;
+        definition
+    }
 
-        // Apply spindle patterns
-        if line.contains("M3") || line.contains("M4") {
-            // Could apply custom spindle pattern here
-        }
+    /// Convert G-code line for target format
+    pub fn convert_line(&self, line: &str) -> String {
+        self.definition.convert_line(line)
+    }
 
-        result
+    /// Convert a whole program, adding the header, footer and blocks
+    pub fn process(&self, gcode: &str, context: &PostContext) -> String {
+        self.definition.process(gcode, context)
     }
 }
 
@@ -497,12 +535,9 @@ impl FormatExporter {
 
     /// Export G-code to format
     pub fn export(&self, gcode_lines: &[String]) -> Result<String> {
-        let converted_lines: Vec<String> = gcode_lines
-            .iter()
-            .map(|line| self.post_processor.convert_line(line))
-            .collect();
-
-        Ok(converted_lines.join("\n"))
+        Ok(self
+            .post_processor
+            .process(&gcode_lines.join("\n"), &PostContext::default()))
     }
 
     /// Export to file
@@ -622,11 +657,48 @@ impl CalibrationWizard {
         }
     }
 
+    /// Backlash measurement with a dial indicator, one step per axis in
+    /// `axes` (for example `"XYZ"`)
+    pub fn backlash_measurement(axes: &str) -> Self {
+        let steps = axes
+            .chars()
+            .map(|c| c.to_ascii_uppercase())
+            .filter(|c| matches!(c, 'X' | 'Y' | 'Z'))
+            .map(|axis| CalibrationStep {
+                step_type: CalibrationStepType::BacklashMeasurement,
+                axis: axis.to_string(),
+                description: format!("Measure {} backlash with a dial indicator", axis),
+                target_value: 0.0,
+                tolerance: 0.1,
+            })
+            .collect();
+
+        Self {
+            current_step: 0,
+            steps,
+            results: Vec::new(),
+        }
+    }
+
+    /// Latest measurement recorded for `axis`
+    pub fn measured(&self, axis: &str) -> Option<f64> {
+        self.results
+            .iter()
+            .rev()
+            .find(|r| r.axis == axis)
+            .map(|r| r.measured_value)
+    }
+
     /// Get current step
     pub fn current_step(&self) -> Option<&CalibrationStep> {
         self.steps.get(self.current_step)
     }
 
+    /// Steps of this calibration
+    pub fn steps(&self) -> &[CalibrationStep] {
+        &self.steps
+    }
+
     /// Get results
     pub fn results(&self) -> &[CalibrationResult] {
         &self.results
@@ -685,6 +757,53 @@ impl CalibrationWizard {
     }
 }
 
+/// Moves for measuring one axis's backlash with a dial indicator
+///
+/// The preload moves away and back so the axis arrives travelling in the
+/// positive direction; the operator then zeroes an indicator against the
+/// carriage. The reversal moves further on and back to the same commanded
+/// point, arriving from the other side: the carriage stops short by the
+/// backlash, which the indicator reads. Both run in millimetres and leave
+/// the controller in absolute mode, and must be sent without compensation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BacklashProcedure {
+    pub axis: char,
+    /// Distance moved each way, larger than the expected backlash
+    pub travel: f64,
+    pub feed_rate: f64,
+}
+
+impl BacklashProcedure {
+    /// Procedure for `axis` with 2 mm moves at 100 mm/min
+    pub fn new(axis: char) -> Self {
+        Self {
+            axis: axis.to_ascii_uppercase(),
+            travel: 2.0,
+            feed_rate: 100.0,
+        }
+    }
+
+    /// Take up the play towards positive before zeroing the indicator
+    pub fn preload_gcode(&self) -> String {
+        self.moves(-self.travel)
+    }
+
+    /// Reverse onto the zeroed point; the indicator then reads the backlash
+    pub fn reversal_gcode(&self) -> String {
+        self.moves(self.travel)
+    }
+
+    fn moves(&self, first: f64) -> String {
+        format!(
+            "G21 G91\nG1 {axis}{:.3} F{:.0}\nG1 {axis}{:.3}\nG90\n",
+            first,
+            self.feed_rate,
+            -first,
+            axis = self.axis
+        )
+    }
+}
+
 // ============================================================================
 // Task 125: Diagnostic Tools
 // ============================================================================
@@ -954,6 +1073,22 @@ mod tests {
         assert!(exporter.export(&lines).is_ok());
     }
 
+    #[test]
+    fn test_preset_definitions() {
+        let fanuc = PostProcessor::for_format(ExportFormat::FANUC);
+        let out = fanuc.process(
+            "T1 M6\nM3 S8000\nG0 X10 (rapid)\nM30\n",
+            &PostContext::new("P"),
+        );
+        assert!(out.starts_with("%\nO0001 (P)\n"), "{}", out);
+        assert!(out.contains("X10. (rapid)"), "{}", out);
+        assert!(out.trim_end().ends_with('%'));
+
+        let linuxcnc = PostProcessor::for_format(ExportFormat::LinuxCNC);
+        assert_eq!(linuxcnc.format, ExportFormat::LinuxCNC);
+        assert_eq!(linuxcnc.definition.name, "LinuxCNC");
+    }
+
     // Task 124: Calibration Tests
     #[test]
     fn test_calibration_wizard() {
@@ -0,0 +1,964 @@
+//! Job Analysis Report
+//!
+//! Summarises a G-code program for quotes and job travellers: extents, cut
+//! and rapid distance and time per tool, feed and spindle histograms, tool
+//! changes, validation warnings and a thumbnail. A [`JobReport`] is written
+//! as a self-contained HTML page, JSON or CSV.
+//!
+//! Times come from the same estimate as the visualizer timeline: cutting
+//! moves run at their modal feed rate and rapids at the given rapid rate,
+//! without acceleration.
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::advanced::{ValidationIssue, ValidationSeverity};
+use crate::utils::processing::{BoundingBox, FeedRateStats, SpindleStats};
+use crate::visualizer::{
+    GCodeCommand, Point3D, PreviewOptions, PreviewRenderer, SegmentKind, Visualizer,
+    DEFAULT_FEED_RATE_MM_MIN, DEFAULT_RAPID_RATE_MM_MIN,
+};
+
+/// Output format of a job report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    /// Pick the format from a file extension (`.html`, `.json` or `.csv`)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "html" | "htm" => Some(Self::Html),
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    /// File extension without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// Settings used when building a report
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportOptions {
+    /// Rapid rate (mm/min) used for time estimates
+    pub rapid_rate_mm_min: f32,
+    /// Number of bins in the feed and spindle histograms
+    pub histogram_bins: usize,
+    /// Thumbnail size in pixels; 0 leaves the thumbnail out
+    pub thumbnail_size: u32,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            rapid_rate_mm_min: DEFAULT_RAPID_RATE_MM_MIN,
+            histogram_bins: 10,
+            thumbnail_size: 256,
+        }
+    }
+}
+
+/// Distance (mm) and estimated time (s) spent moving
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MotionSummary {
+    pub cut_distance: f64,
+    pub rapid_distance: f64,
+    pub cut_time: f64,
+    pub rapid_time: f64,
+    pub dwell_time: f64,
+}
+
+impl MotionSummary {
+    /// Total estimated time in seconds
+    pub fn total_time(&self) -> f64 {
+        self.cut_time + self.rapid_time + self.dwell_time
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.cut_distance += other.cut_distance;
+        self.rapid_distance += other.rapid_distance;
+        self.cut_time += other.cut_time;
+        self.rapid_time += other.rapid_time;
+        self.dwell_time += other.dwell_time;
+    }
+}
+
+/// Motion with one tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolUsage {
+    /// Tool number; `None` for moves before the first tool change
+    pub tool: Option<u32>,
+    /// Comment on the tool change line, usually the tool's name
+    pub description: Option<String>,
+    pub motion: MotionSummary,
+}
+
+/// Tool change in the program
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolChange {
+    /// 1-based source line
+    pub line: u32,
+    pub tool: u32,
+    /// Comment on the tool change line
+    pub description: Option<String>,
+}
+
+/// One bar of a histogram: cutting distance and time with a value in
+/// `min..=max`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBin {
+    pub min: f64,
+    pub max: f64,
+    pub distance: f64,
+    pub time: f64,
+}
+
+/// Analysis of a G-code program
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobReport {
+    /// Job name, usually the file name
+    pub name: String,
+    pub generated: DateTime<Utc>,
+    /// Number of source lines
+    pub lines: usize,
+    /// Extents of all motion, including rapids
+    pub bounding_box: BoundingBox,
+    pub summary: MotionSummary,
+    /// Usage per tool, in order of first use
+    pub tools: Vec<ToolUsage>,
+    pub tool_changes: Vec<ToolChange>,
+    /// Feed rates of cutting moves; the average is weighted by distance
+    pub feed_rate_stats: FeedRateStats,
+    /// Spindle speeds while cutting; the average is weighted by time
+    pub spindle_stats: SpindleStats,
+    /// Cutting distance and time by feed rate (mm/min)
+    pub feed_histogram: Vec<HistogramBin>,
+    /// Cutting distance and time by spindle speed (RPM or S value)
+    pub spindle_histogram: Vec<HistogramBin>,
+    /// Problems found in the program, by line
+    pub warnings: Vec<ValidationIssue>,
+    /// Toolpath preview as an SVG document
+    pub thumbnail_svg: Option<String>,
+}
+
+/// Tool and spindle state changes found by scanning the source lines
+#[derive(Debug, Default)]
+struct ProgramScan {
+    /// Line from which a tool is loaded, with its description
+    tools: Vec<(u32, u32, Option<String>)>,
+    /// Line from which the spindle is on or off
+    spindle: Vec<(u32, bool)>,
+    /// Line from which each S value applies
+    speeds: Vec<(u32, f64)>,
+    /// Number of M3/M4 commands
+    spindle_starts: u64,
+}
+
+impl ProgramScan {
+    fn new(gcode: &str) -> Self {
+        let mut scan = Self::default();
+        // Without M6, selecting a tool with T is taken as the change
+        let uses_m6 = gcode
+            .lines()
+            .any(|line| words(line).any(|(letter, value)| letter == 'M' && value == 6.0));
+        let mut selected = None;
+
+        for (index, line) in gcode.lines().enumerate() {
+            let number = index as u32 + 1;
+            let mut change = false;
+            for (letter, value) in words(line) {
+                match (letter, value as u32) {
+                    ('T', tool) => {
+                        selected = Some(tool);
+                        change |= !uses_m6;
+                    }
+                    ('M', 6) => change = true,
+                    ('M', 3 | 4) if value.fract() == 0.0 => {
+                        scan.spindle.push((number, true));
+                        scan.spindle_starts += 1;
+                    }
+                    ('M', 2 | 5 | 30) if value.fract() == 0.0 => scan.spindle.push((number, false)),
+                    ('S', _) => scan.speeds.push((number, value)),
+                    _ => {}
+                }
+            }
+            if let (true, Some(tool)) = (change, selected) {
+                scan.tools.push((number, tool, comment(line)));
+            }
+        }
+        scan
+    }
+
+    /// Index into `tools` of the tool loaded at `line`
+    fn tool_at(&self, line: u32) -> Option<usize> {
+        self.tools
+            .partition_point(|&(l, _, _)| l <= line)
+            .checked_sub(1)
+    }
+
+    /// Whether the spindle is on at `line`; `None` when the program never
+    /// starts or stops it
+    fn spindle_at(&self, line: u32) -> Option<bool> {
+        if self.spindle.is_empty() {
+            return None;
+        }
+        let slot = self.spindle.partition_point(|&(l, _)| l <= line);
+        Some(slot > 0 && self.spindle[slot - 1].1)
+    }
+
+    /// Spindle speed set at or before `line`
+    fn speed_at(&self, line: u32) -> f64 {
+        let slot = self.speeds.partition_point(|&(l, _)| l <= line);
+        slot.checked_sub(1).map_or(0.0, |i| self.speeds[i].1)
+    }
+}
+
+impl JobReport {
+    /// Analyse `gcode`. `name` identifies the job in the report.
+    pub fn generate(name: impl Into<String>, gcode: &str, options: &ReportOptions) -> Self {
+        let mut vis = Visualizer::new();
+        vis.set_rapid_rate(options.rapid_rate_mm_min);
+        vis.parse_gcode(gcode);
+        let buffers = vis.buffers();
+        let timeline = vis.timeline();
+        let scan = ProgramScan::new(gcode);
+
+        // Slot 0 collects moves before the first tool change
+        let mut usage = vec![MotionSummary::default(); scan.tools.len() + 1];
+        let mut bbox = BoundingBox::new();
+        let mut feeds = Vec::new();
+        let mut speeds = Vec::new();
+        let mut warnings = Vec::new();
+        let mut missing_feed = false;
+        let mut spindle_off = false;
+
+        for (index, command) in buffers.iter().enumerate() {
+            let line = buffers.lines()[index];
+            let time = timeline.end_time(index) - timeline.start_time(index);
+            let length = timeline.segment_length(buffers, index) as f64;
+            let motion = &mut usage[scan.tool_at(line).map_or(0, |slot| slot + 1)];
+
+            match &command {
+                GCodeCommand::Move { from, to, .. } => {
+                    bbox.update(from.x, from.y, from.z);
+                    bbox.update(to.x, to.y, to.z);
+                }
+                GCodeCommand::Arc {
+                    from,
+                    to,
+                    center,
+                    clockwise,
+                    ..
+                } => arc_extents(*from, *to, *center, *clockwise, &mut bbox),
+                GCodeCommand::Dwell { .. } => {}
+            }
+
+            match buffers.kind(index) {
+                SegmentKind::Rapid => {
+                    motion.rapid_distance += length;
+                    motion.rapid_time += time;
+                }
+                SegmentKind::Dwell => motion.dwell_time += time,
+                SegmentKind::Linear | SegmentKind::ArcCw | SegmentKind::ArcCcw => {
+                    motion.cut_distance += length;
+                    motion.cut_time += time;
+
+                    let feed = buffers.feed_rate(index).filter(|f| *f > 0.0);
+                    if feed.is_none() && !missing_feed {
+                        missing_feed = true;
+                        warnings.push(
+                            ValidationIssue::new(
+                                line,
+                                ValidationSeverity::Warning,
+                                "Cutting move without a feed rate",
+                            )
+                            .with_suggestion(format!(
+                                "Set F before cutting; {} mm/min was assumed",
+                                DEFAULT_FEED_RATE_MM_MIN
+                            )),
+                        );
+                    }
+                    feeds.push((
+                        feed.unwrap_or(DEFAULT_FEED_RATE_MM_MIN) as f64,
+                        length,
+                        time,
+                    ));
+
+                    let speed = scan.speed_at(line);
+                    match scan.spindle_at(line) {
+                        Some(false) if !spindle_off => {
+                            spindle_off = true;
+                            warnings.push(
+                                ValidationIssue::new(
+                                    line,
+                                    ValidationSeverity::Warning,
+                                    "Cutting move with the spindle off",
+                                )
+                                .with_suggestion("Start the spindle with M3 or M4 first"),
+                            );
+                        }
+                        Some(false) => {}
+                        _ if speed > 0.0 => speeds.push((speed, length, time)),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let mut summary = MotionSummary::default();
+        for motion in &usage {
+            summary.add(motion);
+        }
+        let tools = usage
+            .iter()
+            .enumerate()
+            .filter(|(slot, motion)| *slot > 0 || motion.total_time() > 0.0)
+            .map(|(slot, motion)| {
+                let loaded = slot.checked_sub(1).map(|i| &scan.tools[i]);
+                (loaded, motion)
+            })
+            .fold(Vec::<ToolUsage>::new(), |mut tools, (loaded, motion)| {
+                let tool = loaded.map(|(_, tool, _)| *tool);
+                // Reloading the same tool adds to its usage
+                match tools.iter_mut().find(|t| t.tool == tool) {
+                    Some(existing) => existing.motion.add(motion),
+                    None => tools.push(ToolUsage {
+                        tool,
+                        description: loaded.and_then(|(_, _, d)| d.clone()),
+                        motion: *motion,
+                    }),
+                }
+                tools
+            });
+
+        let thumbnail_svg = (options.thumbnail_size > 0).then(|| {
+            PreviewRenderer::new(PreviewOptions::thumbnail(options.thumbnail_size))
+                .render_svg(vis.commands())
+        });
+
+        Self {
+            name: name.into(),
+            generated: Utc::now(),
+            lines: gcode.lines().count(),
+            bounding_box: if bbox.is_valid() { bbox } else { zero_box() },
+            summary,
+            tools,
+            tool_changes: scan
+                .tools
+                .iter()
+                .map(|(line, tool, description)| ToolChange {
+                    line: *line,
+                    tool: *tool,
+                    description: description.clone(),
+                })
+                .collect(),
+            feed_rate_stats: feed_stats(&feeds),
+            spindle_stats: spindle_stats(&speeds, scan.spindle_starts),
+            feed_histogram: histogram(&feeds, options.histogram_bins),
+            spindle_histogram: histogram(&speeds, options.histogram_bins),
+            warnings,
+            thumbnail_svg,
+        }
+    }
+
+    /// Read and analyse a G-code file, named after the file
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read
+    pub fn from_file(path: impl AsRef<Path>, options: &ReportOptions) -> Result<Self> {
+        let path = path.as_ref();
+        let gcode = std::fs::read_to_string(path)?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Self::generate(name, &gcode, options))
+    }
+
+    /// Add issues from other checks, e.g. the G-code validator or collision
+    /// detection. Warnings are kept in line order.
+    pub fn with_issues(mut self, issues: impl IntoIterator<Item = ValidationIssue>) -> Self {
+        self.warnings.extend(issues);
+        self.warnings.sort_by_key(|issue| issue.line_number);
+        self
+    }
+
+    /// Render the report in the given format
+    ///
+    /// # Errors
+    /// Returns error if JSON serialisation fails
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        Ok(match format {
+            ReportFormat::Html => self.to_html(),
+            ReportFormat::Json => self.to_json()?,
+            ReportFormat::Csv => self.to_csv(),
+        })
+    }
+
+    /// Write the report to `path`, in the format given by its extension
+    ///
+    /// # Errors
+    /// Returns error if the extension is not a report format or the file
+    /// cannot be written
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let format = ReportFormat::from_path(path)
+            .ok_or_else(|| anyhow!("Unsupported report format: {}", path.display()))?;
+        std::fs::write(path, self.render(format)?)?;
+        Ok(())
+    }
+
+    /// Pretty-printed JSON
+    ///
+    /// # Errors
+    /// Returns error if serialisation fails
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// CSV with one table per section, separated by blank lines
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let mut row = |cells: &[String]| {
+            let cells: Vec<String> = cells.iter().map(|c| csv_field(c)).collect();
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        };
+        let bb = &self.bounding_box;
+        let s = &self.summary;
+
+        row(&["Summary".into()]);
+        row(&["item".into(), "value".into()]);
+        for (item, value) in [
+            ("name", self.name.clone()),
+            ("generated", self.generated.to_rfc3339()),
+            ("lines", self.lines.to_string()),
+            ("min_x_mm", format!("{:.3}", bb.min_x)),
+            ("max_x_mm", format!("{:.3}", bb.max_x)),
+            ("min_y_mm", format!("{:.3}", bb.min_y)),
+            ("max_y_mm", format!("{:.3}", bb.max_y)),
+            ("min_z_mm", format!("{:.3}", bb.min_z)),
+            ("max_z_mm", format!("{:.3}", bb.max_z)),
+            ("cut_distance_mm", format!("{:.3}", s.cut_distance)),
+            ("rapid_distance_mm", format!("{:.3}", s.rapid_distance)),
+            ("cut_time_s", format!("{:.1}", s.cut_time)),
+            ("rapid_time_s", format!("{:.1}", s.rapid_time)),
+            ("dwell_time_s", format!("{:.1}", s.dwell_time)),
+            ("total_time_s", format!("{:.1}", s.total_time())),
+            ("tool_changes", self.tool_changes.len().to_string()),
+            ("warnings", self.warnings.len().to_string()),
+        ] {
+            row(&[item.into(), value]);
+        }
+
+        row(&[]);
+        row(&["Tools".into()]);
+        row(&[
+            "tool".into(),
+            "description".into(),
+            "cut_distance_mm".into(),
+            "rapid_distance_mm".into(),
+            "cut_time_s".into(),
+            "rapid_time_s".into(),
+            "dwell_time_s".into(),
+            "total_time_s".into(),
+        ]);
+        for usage in &self.tools {
+            let m = &usage.motion;
+            row(&[
+                usage.tool.map(|t| t.to_string()).unwrap_or_default(),
+                usage.description.clone().unwrap_or_default(),
+                format!("{:.3}", m.cut_distance),
+                format!("{:.3}", m.rapid_distance),
+                format!("{:.1}", m.cut_time),
+                format!("{:.1}", m.rapid_time),
+                format!("{:.1}", m.dwell_time),
+                format!("{:.1}", m.total_time()),
+            ]);
+        }
+
+        row(&[]);
+        row(&["Tool Changes".into()]);
+        row(&["line".into(), "tool".into(), "description".into()]);
+        for change in &self.tool_changes {
+            row(&[
+                change.line.to_string(),
+                change.tool.to_string(),
+                change.description.clone().unwrap_or_default(),
+            ]);
+        }
+
+        for (title, unit, bins) in [
+            ("Feed Histogram", "mm_min", &self.feed_histogram),
+            ("Spindle Histogram", "rpm", &self.spindle_histogram),
+        ] {
+            row(&[]);
+            row(&[title.into()]);
+            row(&[
+                format!("min_{}", unit),
+                format!("max_{}", unit),
+                "distance_mm".into(),
+                "time_s".into(),
+            ]);
+            for bin in bins {
+                row(&[
+                    format!("{:.1}", bin.min),
+                    format!("{:.1}", bin.max),
+                    format!("{:.3}", bin.distance),
+                    format!("{:.1}", bin.time),
+                ]);
+            }
+        }
+
+        row(&[]);
+        row(&["Warnings".into()]);
+        row(&[
+            "line".into(),
+            "severity".into(),
+            "message".into(),
+            "suggestion".into(),
+        ]);
+        for issue in &self.warnings {
+            row(&[
+                issue.line_number.to_string(),
+                severity_name(issue.severity).into(),
+                issue.message.clone(),
+                issue.suggestion.clone().unwrap_or_default(),
+            ]);
+        }
+        csv
+    }
+
+    /// Self-contained HTML page with inline styles and thumbnail
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let bb = &self.bounding_box;
+        let s = &self.summary;
+        let name = escape_html(&self.name);
+
+        let _ = write!(
+            html,
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Job Report – {name}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+h1 {{ margin-bottom: 0.2em; }}
+h2 {{ margin-top: 1.5em; border-bottom: 1px solid #ccc; }}
+.meta {{ color: #666; }}
+.top {{ display: flex; gap: 2em; align-items: flex-start; flex-wrap: wrap; }}
+table {{ border-collapse: collapse; }}
+th, td {{ padding: 0.25em 0.75em; border-bottom: 1px solid #eee; text-align: left; }}
+td.num {{ text-align: right; font-variant-numeric: tabular-nums; }}
+.bar {{ background: #2ec27e; height: 0.9em; }}
+.error {{ color: #c01c28; }}
+.warning {{ color: #a05d00; }}
+</style>
+</head>
+<body>
+<h1>{name}</h1>
+<p class="meta">Generated {generated} · {lines} lines</p>
+<div class="top">
+"#,
+            generated = self.generated.format("%Y-%m-%d %H:%M UTC"),
+            lines = self.lines,
+        );
+
+        if let Some(svg) = &self.thumbnail_svg {
+            let _ = writeln!(html, "<div class=\"thumbnail\">\n{}</div>", svg.trim_end());
+        }
+
+        html.push_str("<table>\n");
+        let mut summary_row = |label: &str, value: String| {
+            let _ = writeln!(
+                html,
+                "<tr><th>{}</th><td class=\"num\">{}</td></tr>",
+                label, value
+            );
+        };
+        summary_row(
+            "Size (X × Y × Z)",
+            format!(
+                "{:.2} × {:.2} × {:.2} mm",
+                bb.width(),
+                bb.height(),
+                bb.depth()
+            ),
+        );
+        summary_row("X range", format!("{:.2} to {:.2} mm", bb.min_x, bb.max_x));
+        summary_row("Y range", format!("{:.2} to {:.2} mm", bb.min_y, bb.max_y));
+        summary_row("Z range", format!("{:.2} to {:.2} mm", bb.min_z, bb.max_z));
+        summary_row("Cut distance", format!("{:.1} mm", s.cut_distance));
+        summary_row("Rapid distance", format!("{:.1} mm", s.rapid_distance));
+        summary_row("Cut time", format_duration(s.cut_time));
+        summary_row("Rapid time", format_duration(s.rapid_time));
+        if s.dwell_time > 0.0 {
+            summary_row("Dwell time", format_duration(s.dwell_time));
+        }
+        summary_row("Estimated run time", format_duration(s.total_time()));
+        summary_row("Tool changes", self.tool_changes.len().to_string());
+        html.push_str("</table>\n</div>\n");
+
+        html.push_str(
+            "<h2>Tools</h2>\n<table>\n<tr><th>Tool</th><th>Description</th>\
+             <th>Cut distance</th><th>Rapid distance</th><th>Cut time</th>\
+             <th>Rapid time</th><th>Total time</th></tr>\n",
+        );
+        for usage in &self.tools {
+            let m = &usage.motion;
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{:.1} mm</td>\
+                 <td class=\"num\">{:.1} mm</td><td class=\"num\">{}</td>\
+                 <td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                usage
+                    .tool
+                    .map(|t| format!("T{}", t))
+                    .unwrap_or_else(|| "–".into()),
+                escape_html(usage.description.as_deref().unwrap_or("")),
+                m.cut_distance,
+                m.rapid_distance,
+                format_duration(m.cut_time),
+                format_duration(m.rapid_time),
+                format_duration(m.total_time()),
+            );
+        }
+        html.push_str("</table>\n");
+
+        if !self.tool_changes.is_empty() {
+            html.push_str(
+                "<h2>Tool Changes</h2>\n<table>\n\
+                 <tr><th>Line</th><th>Tool</th><th>Description</th></tr>\n",
+            );
+            for change in &self.tool_changes {
+                let _ = writeln!(
+                    html,
+                    "<tr><td class=\"num\">{}</td><td>T{}</td><td>{}</td></tr>",
+                    change.line,
+                    change.tool,
+                    escape_html(change.description.as_deref().unwrap_or("")),
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        let feed = &self.feed_rate_stats;
+        let feed_caption = format!(
+            "{:.0}–{:.0} mm/min, average {:.0} mm/min",
+            feed.min_feed, feed.max_feed, feed.avg_feed
+        );
+        let spindle = &self.spindle_stats;
+        let spindle_caption = format!(
+            "{:.0}–{:.0}, average {:.0}",
+            spindle.min_speed, spindle.max_speed, spindle.avg_speed
+        );
+        for (title, caption, unit, bins) in [
+            ("Feed Rates", feed_caption, "mm/min", &self.feed_histogram),
+            (
+                "Spindle Speeds",
+                spindle_caption,
+                "",
+                &self.spindle_histogram,
+            ),
+        ] {
+            if bins.is_empty() {
+                continue;
+            }
+            let _ = write!(
+                html,
+                "<h2>{}</h2>\n<p class=\"meta\">{}</p>\n<table>\n\
+                 <tr><th>Range {}</th><th>Cut time</th><th>Cut distance</th><th></th></tr>\n",
+                title, caption, unit
+            );
+            let longest = bins.iter().map(|b| b.time).fold(0.0f64, f64::max);
+            for bin in bins {
+                let width = if longest > 0.0 {
+                    bin.time / longest * 100.0
+                } else {
+                    0.0
+                };
+                let _ = writeln!(
+                    html,
+                    "<tr><td class=\"num\">{:.0}–{:.0}</td><td class=\"num\">{}</td>\
+                     <td class=\"num\">{:.1} mm</td>\
+                     <td style=\"width: 16em\"><div class=\"bar\" style=\"width: {:.1}%\"></div></td></tr>",
+                    bin.min,
+                    bin.max,
+                    format_duration(bin.time),
+                    bin.distance,
+                    width
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Warnings</h2>\n");
+        if self.warnings.is_empty() {
+            html.push_str("<p>No problems found.</p>\n");
+        } else {
+            html.push_str(
+                "<table>\n<tr><th>Line</th><th>Severity</th><th>Message</th>\
+                 <th>Suggestion</th></tr>\n",
+            );
+            for issue in &self.warnings {
+                let severity = severity_name(issue.severity);
+                let _ = writeln!(
+                    html,
+                    "<tr class=\"{}\"><td class=\"num\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    severity.to_ascii_lowercase(),
+                    issue.line_number,
+                    severity,
+                    escape_html(&issue.message),
+                    escape_html(issue.suggestion.as_deref().unwrap_or("")),
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Letter/value words of a line, skipping comments
+fn words(line: &str) -> impl Iterator<Item = (char, f64)> + '_ {
+    let code = line.split(';').next().unwrap_or("");
+    let mut depth = 0;
+    let code: String = code
+        .chars()
+        .filter(|&c| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = (depth - 1).max(0),
+                _ => return depth == 0,
+            }
+            false
+        })
+        .collect();
+
+    let mut words = Vec::new();
+    let mut chars = code.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_alphabetic() {
+            continue;
+        }
+        let mut number = String::new();
+        while let Some(&d) = chars.peek() {
+            if d.is_ascii_digit() || d == '.' || d == '-' || d == '+' {
+                number.push(d);
+                chars.next();
+            } else if d == ' ' && number.is_empty() {
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if let Ok(value) = number.parse() {
+            words.push((c.to_ascii_uppercase(), value));
+        }
+    }
+    words.into_iter()
+}
+
+/// Text of the first comment on a line
+fn comment(line: &str) -> Option<String> {
+    let start = line.find(['(', ';'])?;
+    let rest = &line[start + 1..];
+    let text = if line[start..].starts_with('(') {
+        rest.split(')').next().unwrap_or(rest)
+    } else {
+        rest
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Grow `bbox` by an XY arc, including the points where it crosses an axis
+fn arc_extents(
+    from: Point3D,
+    to: Point3D,
+    center: Point3D,
+    clockwise: bool,
+    bbox: &mut BoundingBox,
+) {
+    bbox.update(from.x, from.y, from.z);
+    bbox.update(to.x, to.y, to.z);
+
+    let radius = ((from.x - center.x).powi(2) + (from.y - center.y).powi(2)).sqrt();
+    let start = (from.y - center.y).atan2(from.x - center.x);
+    let end = (to.y - center.y).atan2(to.x - center.x);
+    let mut sweep = if clockwise { start - end } else { end - start }.rem_euclid(TAU);
+    if sweep <= 1e-6 {
+        sweep = TAU;
+    }
+    let z = from.z.min(to.z);
+    for quadrant in 0..4 {
+        let angle = quadrant as f32 * FRAC_PI_2;
+        let offset = if clockwise {
+            start - angle
+        } else {
+            angle - start
+        }
+        .rem_euclid(TAU);
+        if offset <= sweep {
+            bbox.update(
+                center.x + radius * angle.cos(),
+                center.y + radius * angle.sin(),
+                z,
+            );
+        }
+    }
+}
+
+fn zero_box() -> BoundingBox {
+    BoundingBox {
+        min_x: 0.0,
+        max_x: 0.0,
+        min_y: 0.0,
+        max_y: 0.0,
+        min_z: 0.0,
+        max_z: 0.0,
+    }
+}
+
+/// Feed statistics from (feed, distance, time) samples
+fn feed_stats(samples: &[(f64, f64, f64)]) -> FeedRateStats {
+    let mut stats = FeedRateStats::new();
+    let mut previous = None;
+    let mut weighted = 0.0;
+    let mut distance = 0.0;
+    for &(feed, length, _) in samples {
+        stats.min_feed = stats.min_feed.min(feed);
+        stats.max_feed = stats.max_feed.max(feed);
+        if previous != Some(feed) {
+            stats.changes += 1;
+            previous = Some(feed);
+        }
+        weighted += feed * length;
+        distance += length;
+    }
+    if samples.is_empty() {
+        stats.min_feed = 0.0;
+    } else if distance > 0.0 {
+        stats.avg_feed = weighted / distance;
+    }
+    stats
+}
+
+/// Spindle statistics from (speed, distance, time) samples
+fn spindle_stats(samples: &[(f64, f64, f64)], starts: u64) -> SpindleStats {
+    let mut stats = SpindleStats::new();
+    let mut weighted = 0.0;
+    let mut time = 0.0;
+    for &(speed, _, duration) in samples {
+        stats.min_speed = stats.min_speed.min(speed);
+        stats.max_speed = stats.max_speed.max(speed);
+        weighted += speed * duration;
+        time += duration;
+    }
+    if samples.is_empty() {
+        stats.min_speed = 0.0;
+    } else if time > 0.0 {
+        stats.avg_speed = weighted / time;
+    }
+    stats.on_time = time.round() as u64;
+    stats.on_count = starts;
+    stats
+}
+
+/// Equal-width histogram of (value, distance, time) samples
+fn histogram(samples: &[(f64, f64, f64)], bins: usize) -> Vec<HistogramBin> {
+    let Some(min) = samples.iter().map(|s| s.0).reduce(f64::min) else {
+        return Vec::new();
+    };
+    let max = samples.iter().map(|s| s.0).fold(min, f64::max);
+    let count = if max - min < 1e-9 { 1 } else { bins.max(1) };
+    let width = (max - min) / count as f64;
+
+    let mut histogram: Vec<HistogramBin> = (0..count)
+        .map(|i| HistogramBin {
+            min: min + width * i as f64,
+            max: if i + 1 == count {
+                max
+            } else {
+                min + width * (i + 1) as f64
+            },
+            distance: 0.0,
+            time: 0.0,
+        })
+        .collect();
+    for &(value, distance, time) in samples {
+        let slot = if width > 0.0 {
+            (((value - min) / width) as usize).min(count - 1)
+        } else {
+            0
+        };
+        histogram[slot].distance += distance;
+        histogram[slot].time += time;
+    }
+    histogram
+}
+
+fn severity_name(severity: ValidationSeverity) -> &'static str {
+    match severity {
+        ValidationSeverity::Error => "Error",
+        ValidationSeverity::Warning => "Warning",
+        ValidationSeverity::Info => "Info",
+    }
+}
+
+/// Format seconds as e.g. `1h 2m 3s`
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (hours, minutes, secs) = (total / 3600, (total % 3600) / 60, total % 60);
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, secs)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, secs)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,324 @@
+//! Collision and gouge detection
+//!
+//! Replays a toolpath against the voxel stock model and flags moves that
+//! would damage the part, the tool or the machine before the job is run:
+//! rapids through remaining material, plunges with tools that cannot plunge,
+//! cuts deeper than the flute length, shank or holder contact and moves
+//! outside the machine's axis limits.
+
+use std::collections::HashSet;
+
+use gcodekit5_core::data::tools::{Tool, ToolProfile, ToolType};
+use glam::Vec3;
+
+use super::features::MachineLimits;
+use super::setup::Vector3;
+use super::stock_removal_3d::{StockSimulator3D, ToolpathSegment, ToolpathSegmentType};
+use crate::utils::advanced::{ValidationIssue, ValidationSeverity};
+
+/// Kind of problem found by the collision check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollisionKind {
+    /// Rapid move (G0) passing through material that has not been cut
+    RapidThroughMaterial,
+    /// Vertical feed into material with a tool that is not centre cutting
+    PlungeWithNonPlungingTool,
+    /// Material engagement deeper than the tool's flute length
+    ExceedsFluteLength,
+    /// Tool shank touching the material above the flutes
+    ShankContact,
+    /// Tool holder or collet nut touching the material
+    HolderContact,
+    /// Move outside the machine's axis limits
+    OutsideAxisLimits,
+}
+
+impl CollisionKind {
+    /// Severity used when reporting the issue in the editor
+    pub fn severity(&self) -> ValidationSeverity {
+        match self {
+            Self::PlungeWithNonPlungingTool => ValidationSeverity::Warning,
+            _ => ValidationSeverity::Error,
+        }
+    }
+
+    /// Short human readable name
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::RapidThroughMaterial => "Rapid through material",
+            Self::PlungeWithNonPlungingTool => "Plunge with non-plunging tool",
+            Self::ExceedsFluteLength => "Cut deeper than flute length",
+            Self::ShankContact => "Shank contact",
+            Self::HolderContact => "Holder contact",
+            Self::OutsideAxisLimits => "Outside axis limits",
+        }
+    }
+
+    fn suggestion(&self) -> &'static str {
+        match self {
+            Self::RapidThroughMaterial => "Raise the safe Z height or use G1 for this move",
+            Self::PlungeWithNonPlungingTool => "Ramp or helix into the material instead",
+            Self::ExceedsFluteLength => "Reduce the cut depth or use a longer tool",
+            Self::ShankContact => "Reduce the cut depth or use a tool with a longer reach",
+            Self::HolderContact => "Increase the tool stick-out or reduce the cut depth",
+            Self::OutsideAxisLimits => "Move the work origin or scale the job to fit the machine",
+        }
+    }
+}
+
+/// A single problem found by [`CollisionDetector`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionIssue {
+    /// What went wrong
+    pub kind: CollisionKind,
+    /// 1-based G-code line of the offending move (0 when unknown)
+    pub line_number: u32,
+    /// Tool tip position in program coordinates where the problem starts
+    pub position: (f32, f32, f32),
+    /// Description including the measured value
+    pub message: String,
+}
+
+impl CollisionIssue {
+    /// Convert to an editor validation issue
+    pub fn to_validation_issue(&self) -> ValidationIssue {
+        ValidationIssue::new(self.line_number, self.kind.severity(), self.message.clone())
+            .with_suggestion(self.kind.suggestion())
+    }
+}
+
+/// Tool, holder and machine parameters used for the collision check
+#[derive(Debug, Clone)]
+pub struct CollisionSettings {
+    /// Length of the cutting flutes, `None` to skip the depth check
+    pub flute_length: Option<f32>,
+    /// Shank diameter when wider than the cutter
+    pub shank_diameter: Option<f32>,
+    /// Diameter of the holder or collet nut, `None` to skip the holder check
+    pub holder_diameter: Option<f32>,
+    /// Distance from the tool tip to the face of the holder
+    pub stick_out: f32,
+    /// Whether the tool can feed straight down into material
+    pub can_plunge: bool,
+    /// Machine travel in program coordinates, `None` to skip the limits check
+    pub machine_limits: Option<MachineLimits>,
+    /// Program coordinates of the voxel grid origin
+    pub stock_origin: (f32, f32, f32),
+}
+
+impl Default for CollisionSettings {
+    fn default() -> Self {
+        Self {
+            flute_length: None,
+            shank_diameter: None,
+            holder_diameter: None,
+            stick_out: 30.0,
+            can_plunge: true,
+            machine_limits: None,
+            stock_origin: (0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl CollisionSettings {
+    /// Settings derived from a tool library entry.
+    ///
+    /// The stick-out defaults to the overall tool length; reduce it to match
+    /// how far the tool is inserted into the collet.
+    pub fn from_tool(tool: &Tool) -> Self {
+        Self {
+            flute_length: (tool.flute_length > 0.0).then_some(tool.flute_length),
+            shank_diameter: tool.shaft_diameter.filter(|d| *d > tool.diameter),
+            stick_out: if tool.length > 0.0 {
+                tool.length
+            } else {
+                Self::default().stick_out
+            },
+            can_plunge: !matches!(tool.tool_type, ToolType::ChamferTool | ToolType::Specialty),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_holder(mut self, diameter: f32, stick_out: f32) -> Self {
+        self.holder_diameter = Some(diameter);
+        self.stick_out = stick_out;
+        self
+    }
+
+    pub fn with_machine_limits(mut self, limits: MachineLimits) -> Self {
+        self.machine_limits = Some(limits);
+        self
+    }
+
+    pub fn with_stock_origin(mut self, origin: (f32, f32, f32)) -> Self {
+        self.stock_origin = origin;
+        self
+    }
+}
+
+/// Replays a toolpath against a [`StockSimulator3D`] and reports collisions
+#[derive(Debug, Clone, Default)]
+pub struct CollisionDetector {
+    settings: CollisionSettings,
+}
+
+impl CollisionDetector {
+    pub fn new(settings: CollisionSettings) -> Self {
+        Self { settings }
+    }
+
+    pub fn settings(&self) -> &CollisionSettings {
+        &self.settings
+    }
+
+    /// Check every move and carve the stock as it goes.
+    ///
+    /// `segments` are in stock coordinates, as passed to the simulator, and
+    /// `lines` holds the source line of each segment. Each kind of problem is
+    /// reported at most once per line.
+    pub fn analyze(
+        &self,
+        simulator: &mut StockSimulator3D,
+        segments: &[ToolpathSegment],
+        lines: &[u32],
+    ) -> Vec<CollisionIssue> {
+        self.analyze_with_progress(simulator, segments, lines, |_| true)
+            .unwrap_or_default()
+    }
+
+    /// Like [`Self::analyze`] with a progress callback in the range [0.0, 1.0].
+    ///
+    /// Returns `None` if the callback returns `false` to cancel the check.
+    pub fn analyze_with_progress<F>(
+        &self,
+        simulator: &mut StockSimulator3D,
+        segments: &[ToolpathSegment],
+        lines: &[u32],
+        mut on_progress: F,
+    ) -> Option<Vec<CollisionIssue>>
+    where
+        F: FnMut(f32) -> bool,
+    {
+        let mut issues = Vec::new();
+        let mut reported = HashSet::new();
+        let tool = *simulator.tool();
+        let tolerance = simulator.get_grid().resolution() * 0.5;
+        let total = segments.len().max(1) as f32;
+
+        for (idx, segment) in segments.iter().enumerate() {
+            if !on_progress(idx as f32 / total) {
+                return None;
+            }
+            let line = lines.get(idx).copied().unwrap_or(0);
+            let rapid = segment.segment_type == ToolpathSegmentType::RapidMove;
+            let mut report = |kind: CollisionKind, at: Vec3, message: String| {
+                if reported.insert((line, kind)) {
+                    issues.push(CollisionIssue {
+                        kind,
+                        line_number: line,
+                        position: self.to_program(at),
+                        message,
+                    });
+                }
+            };
+
+            for (start, end) in simulator.segment_chords(segment) {
+                if let Some(limits) = &self.settings.machine_limits {
+                    if let Some(outside) = [start, end]
+                        .into_iter()
+                        .find(|p| !limits.contains(self.to_program_vector(*p)))
+                    {
+                        let (x, y, z) = self.to_program(outside);
+                        report(
+                            CollisionKind::OutsideAxisLimits,
+                            outside,
+                            format!("Move reaches X{:.3} Y{:.3} Z{:.3}", x, y, z),
+                        );
+                    }
+                }
+
+                let grid = simulator.get_grid();
+                let engagement = grid.sweep_engagement(start, end, &tool, 0.0);
+
+                if let Some(depth) = engagement {
+                    if rapid {
+                        report(
+                            CollisionKind::RapidThroughMaterial,
+                            start,
+                            format!("Rapid move passes {:.3} mm into the stock", depth),
+                        );
+                    } else {
+                        let horizontal = (end - start).truncate().length();
+                        let descent = start.z - end.z;
+                        if !self.settings.can_plunge && descent > 0.0 && horizontal < descent * 0.1
+                        {
+                            report(
+                                CollisionKind::PlungeWithNonPlungingTool,
+                                start,
+                                "Tool is not centre cutting and cannot plunge".to_string(),
+                            );
+                        }
+                        if let Some(flute) = self.settings.flute_length {
+                            if depth > flute + tolerance {
+                                report(
+                                    CollisionKind::ExceedsFluteLength,
+                                    start,
+                                    format!(
+                                        "Cut depth {:.3} mm exceeds flute length {:.3} mm",
+                                        depth, flute
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                }
+
+                if let (Some(flute), Some(shank)) =
+                    (self.settings.flute_length, self.settings.shank_diameter)
+                {
+                    let body = ToolProfile::Flat { diameter: shank };
+                    if grid.sweep_engagement(start, end, &body, flute).is_some() {
+                        report(
+                            CollisionKind::ShankContact,
+                            start,
+                            format!("Shank ({:.3} mm) touches the material", shank),
+                        );
+                    }
+                }
+
+                if let Some(holder) = self.settings.holder_diameter {
+                    let body = ToolProfile::Flat { diameter: holder };
+                    if let Some(depth) =
+                        grid.sweep_engagement(start, end, &body, self.settings.stick_out)
+                    {
+                        report(
+                            CollisionKind::HolderContact,
+                            start,
+                            format!(
+                                "Holder ({:.3} mm) runs {:.3} mm into the material",
+                                holder, depth
+                            ),
+                        );
+                    }
+                }
+
+                if !rapid {
+                    simulator.cut(start, end);
+                }
+            }
+        }
+
+        on_progress(1.0);
+        Some(issues)
+    }
+
+    fn to_program(&self, p: Vec3) -> (f32, f32, f32) {
+        let (ox, oy, oz) = self.settings.stock_origin;
+        (p.x + ox, p.y + oy, p.z + oz)
+    }
+
+    fn to_program_vector(&self, p: Vec3) -> Vector3 {
+        let (x, y, z) = self.to_program(p);
+        Vector3::new(x, y, z)
+    }
+}
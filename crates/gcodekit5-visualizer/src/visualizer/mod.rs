@@ -3,21 +3,31 @@
 //! This module provides:
 //! - 3D rendering engine (setup)
 //! - Toolpath visualization (rendering)
+//! - Streaming parse and level-of-detail toolpath buffers (streaming, toolpath_buffers)
+//! - Line/Z filtering and timeline scrubbing (timeline)
+//! - Geometric comparison of two programs (toolpath_diff)
 //! - Interactive camera controls (controls)
 //! - Grid and axis rendering
 //! - 3D mesh rendering for STL models
 
 pub mod camera;
 pub mod canvas_renderer;
+pub mod collision;
 pub mod controls;
 pub mod features;
 pub mod mesh_renderer;
 pub mod mesh_rendering;
 pub mod mesh_shaders;
+pub mod preview;
 pub mod scene3d;
 pub mod setup;
+pub mod stock_analysis;
 pub mod stock_removal_3d;
+pub mod streaming;
+pub mod timeline;
+pub mod toolpath_buffers;
 pub mod toolpath_cache;
+pub mod toolpath_diff;
 pub mod toolpath_rendering;
 pub mod viewport;
 #[allow(clippy::module_inception)]
@@ -29,18 +39,32 @@ pub use canvas_renderer::{
     render_grid_to_path, render_intensity_overlay, render_origin_to_path,
     render_rapid_moves_to_path, render_toolpath_to_path,
 };
+pub use collision::{CollisionDetector, CollisionIssue, CollisionKind, CollisionSettings};
 pub use controls::{CameraController, ViewPreset, VisualizerControls};
 pub use features::{
     BoundingBox, GridConfig, MachineLimits, SceneFeatures, ToolMarker, WorkCoordinateSystem,
 };
 pub use mesh_renderer::{LightingParams, MeshRenderError, MeshRenderer};
 pub use mesh_rendering::{MeshCollection, MeshMaterial, RenderableMesh};
+pub use preview::{
+    PreviewColor, PreviewFormat, PreviewOptions, PreviewProjection, PreviewRenderer,
+};
 pub use scene3d::{stl_integration, Renderer3D, Scene3D, Scene3DStats};
 pub use setup::{Camera, CameraType, Color, Light, LightType, Renderer, Scene, Vector3};
+pub use stock_analysis::{
+    deviation_color, write_mesh_stl, DeviationMap, DeviationSettings, DeviationStats,
+};
 pub use stock_removal_3d::{
     generate_surface_mesh, StockSimulator3D, ToolpathSegment, ToolpathSegmentType, VoxelGrid,
 };
+pub use streaming::{GcodeStreamParser, ParseEvent, ParseJob, ParseProgress, ParsedToolpath};
+pub use timeline::{
+    TimelinePlayback, ToolpathFilter, ToolpathTimeline, DEFAULT_FEED_RATE_MM_MIN,
+    DEFAULT_RAPID_RATE_MM_MIN,
+};
+pub use toolpath_buffers::{SegmentKind, ToolpathBuffers, ToolpathIter, ToolpathLod};
 pub use toolpath_cache::ToolpathCache;
+pub use toolpath_diff::{DiffKind, DiffRegion, DiffSettings, ToolpathDiff, ToolpathMetrics};
 pub use toolpath_rendering::{
     ArcSegment, LineSegment, MovementType, PathSegment, Toolpath, ToolpathStats,
 };
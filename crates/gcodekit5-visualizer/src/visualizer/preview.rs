@@ -0,0 +1,493 @@
+//! Headless toolpath preview rendering
+//!
+//! Renders parsed G-code to PNG or SVG on the CPU, without a display or GPU,
+//! for thumbnails, the recent files list and command line previews. Programs
+//! can be drawn top-down or isometric; cutting moves can be shaded by laser
+//! power.
+
+use std::f32::consts::PI;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use tiny_skia::{LineCap, LineJoin, Paint, PathBuilder, Pixmap, Stroke, StrokeDash, Transform};
+
+use super::visualizer::{GCodeCommand, Point3D, Visualizer};
+use crate::error::{VisualizationError, VisualizationResult};
+
+/// Number of shades used when drawing laser power as intensity
+const INTENSITY_LEVELS: usize = 16;
+
+/// Largest angle covered by one chord when flattening arcs
+const ARC_STEP: f32 = PI / 36.0;
+
+/// View direction of a preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PreviewProjection {
+    /// Looking down the Z axis
+    #[default]
+    TopDown,
+    /// Isometric view from the front right, Z up
+    Isometric,
+}
+
+impl PreviewProjection {
+    fn project(&self, p: &Point3D) -> (f32, f32) {
+        match self {
+            Self::TopDown => (p.x, p.y),
+            Self::Isometric => {
+                let (sin, cos) = (PI / 6.0).sin_cos();
+                ((p.x - p.y) * cos, (p.x + p.y) * sin + p.z)
+            }
+        }
+    }
+}
+
+/// Output file format of a preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewFormat {
+    Png,
+    Svg,
+}
+
+impl PreviewFormat {
+    /// Pick the format from a file extension (`.png` or `.svg`)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "png" => Some(Self::Png),
+            "svg" => Some(Self::Svg),
+            _ => None,
+        }
+    }
+}
+
+/// 8-bit RGBA colour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl PreviewColor {
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Parse `#RRGGBB` or `#RRGGBBAA` (the `#` is optional)
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim().trim_start_matches('#');
+        if !hex.is_ascii() || (hex.len() != 6 && hex.len() != 8) {
+            return None;
+        }
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Self {
+            r: byte(0)?,
+            g: byte(2)?,
+            b: byte(4)?,
+            a: if hex.len() == 8 { byte(6)? } else { 255 },
+        })
+    }
+
+    /// `#RRGGBB`, without alpha
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Blend from `self` towards `other`; `t` = 0 gives `self`, 1 gives `other`
+    pub fn mix(&self, other: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Self {
+            r: lerp(self.r, other.r),
+            g: lerp(self.g, other.g),
+            b: lerp(self.b, other.b),
+            a: lerp(self.a, other.a),
+        }
+    }
+}
+
+/// Appearance of a rendered preview
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewOptions {
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    pub projection: PreviewProjection,
+    pub background: PreviewColor,
+    pub rapid_color: PreviewColor,
+    pub cut_color: PreviewColor,
+    /// Draw G0 moves (dashed)
+    pub show_rapids: bool,
+    /// Shade cutting moves from the background colour (S0) to the cut colour
+    /// (`max_s_value`), as for laser engraving
+    pub laser_intensity: bool,
+    /// Spindle/laser value drawn at full intensity
+    pub max_s_value: f32,
+    /// Stroke width of cutting moves in pixels
+    pub line_width: f32,
+    /// Empty border around the toolpath in pixels
+    pub margin: f32,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            width: 512,
+            height: 512,
+            projection: PreviewProjection::TopDown,
+            background: PreviewColor::rgba(38, 38, 38, 255),
+            rapid_color: PreviewColor::rgba(229, 165, 10, 160),
+            cut_color: PreviewColor::rgba(46, 194, 126, 255),
+            show_rapids: true,
+            laser_intensity: false,
+            max_s_value: 1000.0,
+            line_width: 1.5,
+            margin: 8.0,
+        }
+    }
+}
+
+impl PreviewOptions {
+    /// Square thumbnail of the given size
+    pub fn thumbnail(size: u32) -> Self {
+        Self {
+            width: size,
+            height: size,
+            line_width: 1.0,
+            margin: (size as f32 * 0.05).max(2.0),
+            ..Self::default()
+        }
+    }
+}
+
+/// Projected polyline in image or drawing coordinates
+type Polyline = Vec<(f32, f32)>;
+
+/// Polylines in image coordinates, grouped by how they are drawn
+#[derive(Debug, Default)]
+struct PreviewScene {
+    rapids: Vec<Polyline>,
+    /// Cutting moves per intensity level; a single level without laser shading
+    cuts: Vec<Vec<Polyline>>,
+}
+
+/// Renders G-code toolpaths to images on the CPU
+#[derive(Debug, Clone, Default)]
+pub struct PreviewRenderer {
+    options: PreviewOptions,
+}
+
+impl PreviewRenderer {
+    pub fn new(options: PreviewOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn options(&self) -> &PreviewOptions {
+        &self.options
+    }
+
+    /// Parse a program and render it as PNG
+    pub fn render_gcode_png(&self, gcode: &str) -> VisualizationResult<Vec<u8>> {
+        let mut vis = Visualizer::new();
+        vis.parse_gcode(gcode);
+        self.render_png(vis.commands(), &[])
+    }
+
+    /// Parse a program and render it as SVG
+    pub fn render_gcode_svg(&self, gcode: &str) -> String {
+        let mut vis = Visualizer::new();
+        vis.parse_gcode(gcode);
+        self.render_svg(vis.commands())
+    }
+
+    /// Render a G-code file to a PNG or SVG file, chosen by the output extension
+    pub fn render_file(&self, input: &Path, output: &Path) -> VisualizationResult<()> {
+        let format = PreviewFormat::from_path(output)
+            .ok_or_else(|| VisualizationError::UnsupportedFormat(output.display().to_string()))?;
+        let gcode = std::fs::read_to_string(input)?;
+        match format {
+            PreviewFormat::Png => std::fs::write(output, self.render_gcode_png(&gcode)?)?,
+            PreviewFormat::Svg => std::fs::write(output, self.render_gcode_svg(&gcode))?,
+        }
+        Ok(())
+    }
+
+    /// Render to straight (not premultiplied) RGBA pixels, row by row
+    pub fn render_rgba(&self, commands: &[GCodeCommand]) -> VisualizationResult<Vec<u8>> {
+        let pixmap = self.render_pixmap(commands)?;
+        Ok(pixmap
+            .pixels()
+            .iter()
+            .flat_map(|p| {
+                let c = p.demultiply();
+                [c.red(), c.green(), c.blue(), c.alpha()]
+            })
+            .collect())
+    }
+
+    /// Render to a PNG image. `text` entries are stored as PNG text chunks,
+    /// e.g. `Thumb::URI` for freedesktop thumbnails.
+    pub fn render_png(
+        &self,
+        commands: &[GCodeCommand],
+        text: &[(&str, &str)],
+    ) -> VisualizationResult<Vec<u8>> {
+        let rgba = self.render_rgba(commands)?;
+        let mut png_data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png_data, self.options.width, self.options.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (keyword, value) in text {
+            encoder
+                .add_text_chunk(keyword.to_string(), value.to_string())
+                .map_err(|e| VisualizationError::RenderingFailed(e.to_string()))?;
+        }
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| VisualizationError::RenderingFailed(e.to_string()))?;
+        writer
+            .write_image_data(&rgba)
+            .map_err(|e| VisualizationError::RenderingFailed(e.to_string()))?;
+        writer
+            .finish()
+            .map_err(|e| VisualizationError::RenderingFailed(e.to_string()))?;
+        Ok(png_data)
+    }
+
+    /// Render to an SVG document with the same framing as the PNG output
+    pub fn render_svg(&self, commands: &[GCodeCommand]) -> String {
+        let opts = &self.options;
+        let scene = self.build_scene(commands);
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = opts.width,
+            h = opts.height
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <rect width="100%" height="100%" fill="{}" fill-opacity="{:.3}"/>"#,
+            opts.background.to_hex(),
+            opts.background.a as f32 / 255.0
+        );
+
+        let mut write_paths = |polylines: &[Polyline], color: PreviewColor, extra: &str| {
+            if polylines.is_empty() {
+                return;
+            }
+            let mut d = String::new();
+            for line in polylines {
+                for (i, (x, y)) in line.iter().enumerate() {
+                    let _ = write!(d, "{}{:.2} {:.2} ", if i == 0 { 'M' } else { 'L' }, x, y);
+                }
+            }
+            let _ = writeln!(
+                svg,
+                r#"  <path d="{}" fill="none" stroke="{}" stroke-opacity="{:.3}" stroke-width="{:.2}" stroke-linecap="round" stroke-linejoin="round"{}/>"#,
+                d.trim_end(),
+                color.to_hex(),
+                color.a as f32 / 255.0,
+                opts.line_width,
+                extra
+            );
+        };
+
+        write_paths(
+            &scene.rapids,
+            opts.rapid_color,
+            r#" stroke-dasharray="4 3""#,
+        );
+        for (level, polylines) in scene.cuts.iter().enumerate() {
+            write_paths(polylines, self.cut_level_color(level, scene.cuts.len()), "");
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn render_pixmap(&self, commands: &[GCodeCommand]) -> VisualizationResult<Pixmap> {
+        let opts = &self.options;
+        let mut pixmap = Pixmap::new(opts.width, opts.height).ok_or_else(|| {
+            VisualizationError::RenderingFailed(format!(
+                "invalid image size {}x{}",
+                opts.width, opts.height
+            ))
+        })?;
+        let bg = opts.background;
+        pixmap.fill(tiny_skia::Color::from_rgba8(bg.r, bg.g, bg.b, bg.a));
+
+        let scene = self.build_scene(commands);
+        let mut stroke = Stroke {
+            width: opts.line_width,
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Round,
+            ..Default::default()
+        };
+
+        let draw =
+            |pixmap: &mut Pixmap, polylines: &[Polyline], color: PreviewColor, stroke: &Stroke| {
+                let mut pb = PathBuilder::new();
+                for line in polylines {
+                    if let Some((&(x, y), rest)) = line.split_first() {
+                        pb.move_to(x, y);
+                        for &(x, y) in rest {
+                            pb.line_to(x, y);
+                        }
+                    }
+                }
+                if let Some(path) = pb.finish() {
+                    let mut paint = Paint::default();
+                    paint.set_color_rgba8(color.r, color.g, color.b, color.a);
+                    paint.anti_alias = true;
+                    pixmap.stroke_path(&path, &paint, stroke, Transform::identity(), None);
+                }
+            };
+
+        for (level, polylines) in scene.cuts.iter().enumerate() {
+            draw(
+                &mut pixmap,
+                polylines,
+                self.cut_level_color(level, scene.cuts.len()),
+                &stroke,
+            );
+        }
+
+        stroke.width = (opts.line_width * 0.75).max(0.5);
+        stroke.dash = StrokeDash::new(vec![4.0, 3.0], 0.0);
+        draw(&mut pixmap, &scene.rapids, opts.rapid_color, &stroke);
+
+        Ok(pixmap)
+    }
+
+    fn cut_level_color(&self, level: usize, levels: usize) -> PreviewColor {
+        if levels <= 1 {
+            return self.options.cut_color;
+        }
+        let t = (level + 1) as f32 / levels as f32;
+        self.options.background.mix(&self.options.cut_color, t)
+    }
+
+    /// Project all moves and fit them into the image, Y pointing down
+    fn build_scene(&self, commands: &[GCodeCommand]) -> PreviewScene {
+        let opts = &self.options;
+        let levels = if opts.laser_intensity {
+            INTENSITY_LEVELS
+        } else {
+            1
+        };
+
+        // Flatten into projected polylines tagged with rapid / intensity level
+        let mut lines: Vec<(Option<usize>, Polyline)> = Vec::new();
+        for cmd in commands {
+            let (points, rapid, intensity) = match cmd {
+                GCodeCommand::Move {
+                    from,
+                    to,
+                    rapid,
+                    intensity,
+                } => (vec![*from, *to], *rapid, *intensity),
+                GCodeCommand::Arc {
+                    from,
+                    to,
+                    center,
+                    clockwise,
+                    intensity,
+                } => (flatten_arc(from, to, center, *clockwise), false, *intensity),
+                GCodeCommand::Dwell { .. } => continue,
+            };
+            if rapid && !opts.show_rapids {
+                continue;
+            }
+            let level = if rapid {
+                None
+            } else if levels > 1 {
+                let power = intensity.unwrap_or(0.0) / opts.max_s_value.max(f32::EPSILON);
+                if power <= 0.0 {
+                    // Laser off: nothing is burnt
+                    continue;
+                }
+                Some(((power.min(1.0) * levels as f32).ceil() as usize).clamp(1, levels) - 1)
+            } else {
+                Some(0)
+            };
+            let projected = points.iter().map(|p| opts.projection.project(p)).collect();
+            lines.push((level, projected));
+        }
+
+        let mut scene = PreviewScene {
+            rapids: Vec::new(),
+            cuts: vec![Vec::new(); levels],
+        };
+        let Some((min_x, min_y, max_x, max_y)) = lines
+            .iter()
+            .flat_map(|(_, points)| points.iter())
+            .fold(None, |acc: Option<(f32, f32, f32, f32)>, &(x, y)| {
+                Some(match acc {
+                    None => (x, y, x, y),
+                    Some((a, b, c, d)) => (a.min(x), b.min(y), c.max(x), d.max(y)),
+                })
+            })
+        else {
+            return scene;
+        };
+
+        let avail_w = (opts.width as f32 - 2.0 * opts.margin).max(1.0);
+        let avail_h = (opts.height as f32 - 2.0 * opts.margin).max(1.0);
+        let span_w = (max_x - min_x).max(f32::EPSILON);
+        let span_h = (max_y - min_y).max(f32::EPSILON);
+        let scale = (avail_w / span_w).min(avail_h / span_h);
+        // Centre the drawing in the image
+        let offset_x = (opts.width as f32 - span_w * scale) / 2.0;
+        let offset_y = (opts.height as f32 - span_h * scale) / 2.0;
+        let to_image = |(x, y): (f32, f32)| {
+            (
+                offset_x + (x - min_x) * scale,
+                opts.height as f32 - (offset_y + (y - min_y) * scale),
+            )
+        };
+
+        for (level, points) in lines {
+            let points = points.into_iter().map(to_image).collect();
+            match level {
+                None => scene.rapids.push(points),
+                Some(level) => scene.cuts[level].push(points),
+            }
+        }
+        scene
+    }
+}
+
+/// Points along an XY arc, including both end points; Z is interpolated
+fn flatten_arc(from: &Point3D, to: &Point3D, center: &Point3D, clockwise: bool) -> Vec<Point3D> {
+    let radius = ((from.x - center.x).powi(2) + (from.y - center.y).powi(2)).sqrt();
+    if !radius.is_finite() || radius < 1e-3 {
+        return vec![*from, *to];
+    }
+    let start = (from.y - center.y).atan2(from.x - center.x);
+    let end = (to.y - center.y).atan2(to.x - center.x);
+    let mut sweep = if clockwise { start - end } else { end - start };
+    if sweep <= 0.0 {
+        sweep += 2.0 * PI;
+    }
+    let steps = ((sweep / ARC_STEP).ceil() as usize).max(1);
+    let direction = if clockwise { -1.0 } else { 1.0 };
+
+    (0..=steps)
+        .map(|i| {
+            if i == steps {
+                return *to;
+            }
+            let t = i as f32 / steps as f32;
+            let angle = start + direction * sweep * t;
+            Point3D::new(
+                center.x + radius * angle.cos(),
+                center.y + radius * angle.sin(),
+                from.z + (to.z - from.z) * t,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,349 @@
+//! STL export and deviation analysis of simulated stock
+//!
+//! Writes the [`StockSimulator3D`] result as a binary STL, and compares it
+//! against a reference model (e.g. one imported with `Model3DImporter`) to
+//! show where a finishing toolpath leaves material behind or cuts into the
+//! part.
+//!
+//! The comparison is made per stock column, as seen from above, which is
+//! what a 3-axis machine can reach: each column's remaining height is
+//! compared with the highest point of the reference surface over it.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use gcodekit5_designer::model3d::Mesh3D;
+use glam::Vec3;
+
+use super::stock_removal_3d::{StockSimulator3D, VoxelGrid};
+
+/// Floats per vertex in the meshes built here and by `generate_surface_mesh`:
+/// position, normal and RGBA colour
+const VERTEX_STRIDE: usize = 10;
+
+const OVERCUT_COLOR: [f32; 4] = [0.85, 0.15, 0.15, 1.0];
+const IN_TOLERANCE_COLOR: [f32; 4] = [0.20, 0.75, 0.30, 1.0];
+const UNDERCUT_COLOR: [f32; 4] = [0.20, 0.40, 0.90, 1.0];
+/// Columns the reference model does not cover keep the stock colour
+const UNCOVERED_COLOR: [f32; 4] = [0.44, 0.50, 0.56, 1.0];
+
+/// Write triangles from an interleaved mesh, as built by
+/// [`generate_surface_mesh`](super::stock_removal_3d::generate_surface_mesh),
+/// as binary STL moved by `offset`. Returns the number of triangles written.
+pub fn write_mesh_stl<W: Write>(vertices: &[f32], offset: Vec3, mut writer: W) -> Result<usize> {
+    let triangle_stride = VERTEX_STRIDE * 3;
+    let count = vertices.len() / triangle_stride;
+
+    let mut header = [0u8; 80];
+    let title = b"GCodeKit5 simulated stock";
+    header[..title.len()].copy_from_slice(title);
+    writer.write_all(&header)?;
+    writer.write_all(&(count as u32).to_le_bytes())?;
+
+    for triangle in vertices.chunks_exact(triangle_stride) {
+        let corner = |i: usize| {
+            let v = &triangle[i * VERTEX_STRIDE..];
+            Vec3::new(v[0], v[1], v[2]) + offset
+        };
+        let (a, b, c) = (corner(0), corner(1), corner(2));
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        for v in [normal, a, b, c] {
+            for component in v.to_array() {
+                writer.write_all(&component.to_le_bytes())?;
+            }
+        }
+        writer.write_all(&0u16.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+impl StockSimulator3D {
+    /// Save the simulated stock surface as a binary STL file.
+    ///
+    /// The voxel grid starts at the stock's bottom corner; `origin` is where
+    /// that corner is in program coordinates, e.g. `(0, 0, -thickness)` for
+    /// stock whose top is at Z0. Returns the number of triangles written.
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be written
+    pub fn export_stl(&self, path: impl AsRef<Path>, origin: Vec3) -> Result<usize> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create STL file {}", path.display()))?;
+        write_mesh_stl(&self.get_mesh(), origin, BufWriter::new(file))
+            .with_context(|| format!("Failed to write STL file {}", path.display()))
+    }
+}
+
+/// Thresholds for classifying and colouring deviations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviationSettings {
+    /// Deviations within ± this many mm count as on target
+    pub tolerance: f32,
+    /// Deviation (mm) at which the colour map is fully saturated
+    pub color_range: f32,
+}
+
+impl Default for DeviationSettings {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.05,
+            color_range: 1.0,
+        }
+    }
+}
+
+/// Colour for a deviation: green within tolerance, blending to red for
+/// over-cut (negative) and blue for under-cut (positive)
+pub fn deviation_color(deviation: f32, settings: &DeviationSettings) -> [f32; 4] {
+    let magnitude = deviation.abs();
+    if magnitude <= settings.tolerance {
+        return IN_TOLERANCE_COLOR;
+    }
+    let span = (settings.color_range - settings.tolerance).max(f32::EPSILON);
+    let t = ((magnitude - settings.tolerance) / span).clamp(0.0, 1.0);
+    let target = if deviation < 0.0 {
+        OVERCUT_COLOR
+    } else {
+        UNDERCUT_COLOR
+    };
+    let mut color = IN_TOLERANCE_COLOR;
+    for (c, target) in color.iter_mut().zip(target) {
+        *c += (target - *c) * t;
+    }
+    color
+}
+
+/// Summary of a [`DeviationMap`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DeviationStats {
+    /// Columns covered by the reference model
+    pub samples: usize,
+    /// Columns outside the reference model, not counted in the statistics
+    pub uncovered: usize,
+    pub within_tolerance: usize,
+    /// Columns cut deeper than the model by more than the tolerance
+    pub overcut: usize,
+    /// Columns left higher than the model by more than the tolerance
+    pub undercut: usize,
+    /// Deepest cut into the model (mm, ≥ 0)
+    pub max_overcut: f32,
+    /// Most material left above the model (mm, ≥ 0)
+    pub max_undercut: f32,
+    /// Mean signed deviation (mm); positive means material left
+    pub mean: f32,
+    /// Root mean square deviation (mm)
+    pub rms: f32,
+}
+
+impl DeviationStats {
+    /// Share of covered columns within tolerance, in percent
+    pub fn within_tolerance_percent(&self) -> f32 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.within_tolerance as f32 / self.samples as f32 * 100.0
+        }
+    }
+}
+
+/// Per-column deviation of simulated stock from a reference model
+#[derive(Debug, Clone)]
+pub struct DeviationMap {
+    width: usize,
+    height: usize,
+    resolution: f32,
+    /// Remaining stock height of each column, in grid coordinates
+    stock_tops: Vec<f32>,
+    /// Stock height minus model height; `None` where the model is absent
+    deviations: Vec<Option<f32>>,
+    settings: DeviationSettings,
+    stats: DeviationStats,
+}
+
+impl DeviationMap {
+    /// Compare `grid` with `reference`.
+    ///
+    /// `stock_origin` is where the grid's bottom corner is in the
+    /// reference model's coordinates, as for [`StockSimulator3D::export_stl`].
+    pub fn compute(
+        grid: &VoxelGrid,
+        stock_origin: Vec3,
+        reference: &Mesh3D,
+        settings: DeviationSettings,
+    ) -> Self {
+        let (width, height, _) = grid.dimensions();
+        let resolution = grid.resolution();
+
+        let stock_tops: Vec<f32> = (0..width * height)
+            .map(|i| grid.column_top(i % width, i / width).unwrap_or(0.0))
+            .collect();
+        let model_tops = reference_heights(reference, width, height, resolution, stock_origin);
+
+        let deviations: Vec<Option<f32>> = stock_tops
+            .iter()
+            .zip(&model_tops)
+            .map(|(stock, model)| model.map(|model| stock - model))
+            .collect();
+
+        let mut stats = DeviationStats::default();
+        let mut sum = 0.0f64;
+        let mut sum_sq = 0.0f64;
+        for deviation in &deviations {
+            let Some(d) = *deviation else {
+                stats.uncovered += 1;
+                continue;
+            };
+            stats.samples += 1;
+            sum += d as f64;
+            sum_sq += (d as f64).powi(2);
+            if d < -settings.tolerance {
+                stats.overcut += 1;
+            } else if d > settings.tolerance {
+                stats.undercut += 1;
+            } else {
+                stats.within_tolerance += 1;
+            }
+            stats.max_overcut = stats.max_overcut.max(-d);
+            stats.max_undercut = stats.max_undercut.max(d);
+        }
+        if stats.samples > 0 {
+            stats.mean = (sum / stats.samples as f64) as f32;
+            stats.rms = (sum_sq / stats.samples as f64).sqrt() as f32;
+        }
+
+        Self {
+            width,
+            height,
+            resolution,
+            stock_tops,
+            deviations,
+            settings,
+            stats,
+        }
+    }
+
+    pub fn stats(&self) -> &DeviationStats {
+        &self.stats
+    }
+
+    pub fn settings(&self) -> &DeviationSettings {
+        &self.settings
+    }
+
+    /// Grid size in columns
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Deviation (mm) of column `(x, y)`; positive means material left
+    /// above the model, negative a cut into it
+    pub fn deviation_at(&self, x: usize, y: usize) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.deviations[y * self.width + x]
+    }
+
+    /// Height-field mesh of the stock top with one vertex per column,
+    /// coloured by deviation, in the layout and grid coordinates of
+    /// `generate_surface_mesh`
+    pub fn to_mesh(&self) -> Vec<f32> {
+        let (w, h) = (self.width, self.height);
+        if w < 2 || h < 2 {
+            return Vec::new();
+        }
+        let res = self.resolution;
+        let top = |x: usize, y: usize| self.stock_tops[y * w + x];
+
+        let vertex = |x: usize, y: usize| {
+            // Central differences for a smooth normal
+            let dx = top((x + 1).min(w - 1), y) - top(x.saturating_sub(1), y);
+            let dy = top(x, (y + 1).min(h - 1)) - top(x, y.saturating_sub(1));
+            let normal = Vec3::new(-dx, -dy, 2.0 * res).normalize();
+            let color = match self.deviations[y * w + x] {
+                Some(d) => deviation_color(d, &self.settings),
+                None => UNCOVERED_COLOR,
+            };
+            let mut v = [0.0f32; VERTEX_STRIDE];
+            v[..3].copy_from_slice(&[(x as f32 + 0.5) * res, (y as f32 + 0.5) * res, top(x, y)]);
+            v[3..6].copy_from_slice(&normal.to_array());
+            v[6..].copy_from_slice(&color);
+            v
+        };
+
+        let mut vertices = Vec::with_capacity((w - 1) * (h - 1) * 6 * VERTEX_STRIDE);
+        for y in 0..h - 1 {
+            for x in 0..w - 1 {
+                let (a, b, c, d) = (
+                    vertex(x, y),
+                    vertex(x + 1, y),
+                    vertex(x + 1, y + 1),
+                    vertex(x, y + 1),
+                );
+                for v in [a, b, c, a, c, d] {
+                    vertices.extend_from_slice(&v);
+                }
+            }
+        }
+        vertices
+    }
+}
+
+/// Highest point of `mesh` above each column centre, in grid coordinates.
+///
+/// Triangles are rasterised onto the columns they cover when seen from
+/// above; vertical faces cover nothing and are left to their neighbours.
+fn reference_heights(
+    mesh: &Mesh3D,
+    width: usize,
+    height: usize,
+    resolution: f32,
+    stock_origin: Vec3,
+) -> Vec<Option<f32>> {
+    let mut tops: Vec<Option<f32>> = vec![None; width * height];
+    for triangle in &mesh.triangles {
+        let [a, b, c] = triangle
+            .vertices
+            .map(|p| Vec3::new(p.x, p.y, p.z) - stock_origin);
+        let area = (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y);
+        if area.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let column = |v: f32, limit: usize| ((v / resolution - 0.5).max(0.0) as usize).min(limit);
+        let min_x = a.x.min(b.x).min(c.x);
+        let max_x = a.x.max(b.x).max(c.x);
+        let min_y = a.y.min(b.y).min(c.y);
+        let max_y = a.y.max(b.y).max(c.y);
+        if max_x < 0.0 || max_y < 0.0 {
+            continue;
+        }
+        let (x0, x1) = (column(min_x, width), column(max_x + resolution, width));
+        let (y0, y1) = (column(min_y, height), column(max_y + resolution, height));
+
+        for y in y0..y1 {
+            let py = (y as f32 + 0.5) * resolution;
+            for x in x0..x1 {
+                let px = (x as f32 + 0.5) * resolution;
+                // Barycentric coordinates of the column centre
+                let w1 = ((b.x - px) * (c.y - py) - (c.x - px) * (b.y - py)) / area;
+                let w2 = ((c.x - px) * (a.y - py) - (a.x - px) * (c.y - py)) / area;
+                let w3 = 1.0 - w1 - w2;
+                const EDGE: f32 = -1e-5;
+                if w1 < EDGE || w2 < EDGE || w3 < EDGE {
+                    continue;
+                }
+                let z = w1 * a.z + w2 * b.z + w3 * c.z;
+                let top = &mut tops[y * width + x];
+                if top.is_none_or(|t| z > t) {
+                    *top = Some(z);
+                }
+            }
+        }
+    }
+    tops
+}
@@ -1,3 +1,4 @@
+use gcodekit5_core::data::tools::ToolProfile;
 use glam::Vec3;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -113,6 +114,126 @@ impl VoxelGrid {
             }
         }
     }
+
+    /// Remove the material swept by a tool whose tip travels in a straight
+    /// line from `start` to `end`.
+    ///
+    /// Every voxel column under the swept cutter is cleared from the lowest
+    /// point reached by the cutting surface upwards, so flat floors, ball
+    /// scallops and V-grooves keep the shape of the tool.
+    pub fn remove_tool_sweep(&mut self, start: Vec3, end: Vec3, tool: &ToolProfile) {
+        let radius = tool.radius();
+        let res = self.resolution;
+        let to_index = |v: f32| (v / res).floor() as i32;
+
+        let min_x = to_index(start.x.min(end.x) - radius).max(0);
+        let max_x = to_index(start.x.max(end.x) + radius).min(self.width as i32 - 1);
+        let min_y = to_index(start.y.min(end.y) - radius).max(0);
+        let max_y = to_index(start.y.max(end.y) + radius).min(self.height as i32 - 1);
+        if min_x > max_x || min_y > max_y || start.z.min(end.z) >= self.depth as f32 * res {
+            return;
+        }
+
+        let from = (start.x, start.y, start.z);
+        let to = (end.x, end.y, end.z);
+        for y in min_y as usize..=max_y as usize {
+            let py = (y as f32 + 0.5) * res;
+            let Some(columns) = Self::sweep_columns(tool, py, from, to, min_x, max_x, res) else {
+                continue;
+            };
+            for x in columns {
+                let px = (x as f32 + 0.5) * res;
+                let Some(bottom) = tool.swept_bottom(px, py, from, to) else {
+                    continue;
+                };
+                // Clear every voxel whose centre lies above the cutter surface
+                let first = ((bottom / res) - 0.5).ceil().max(0.0) as usize;
+                for z in first..self.depth {
+                    self.voxels[z * self.width * self.height + y * self.width + x] = 0;
+                }
+            }
+        }
+    }
+
+    /// Height of the top face of the highest remaining voxel in a column,
+    /// or `None` when the column has been cut through.
+    pub fn column_top(&self, x: usize, y: usize) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        (0..self.depth)
+            .rev()
+            .find(|&z| self.voxels[z * self.width * self.height + y * self.width + x] != 0)
+            .map(|z| (z + 1) as f32 * self.resolution)
+    }
+
+    /// Deepest engagement of a tool whose tip travels from `start` to `end`,
+    /// measured from the cutter surface raised by `lift` up to the top of the
+    /// remaining material.
+    ///
+    /// Only voxels whose centre lies above the raised surface count, matching
+    /// what [`Self::remove_tool_sweep`] would clear. Returns `None` when the
+    /// sweep does not touch any material.
+    pub fn sweep_engagement(
+        &self,
+        start: Vec3,
+        end: Vec3,
+        tool: &ToolProfile,
+        lift: f32,
+    ) -> Option<f32> {
+        let radius = tool.radius();
+        let res = self.resolution;
+        let to_index = |v: f32| (v / res).floor() as i32;
+
+        let min_x = to_index(start.x.min(end.x) - radius).max(0);
+        let max_x = to_index(start.x.max(end.x) + radius).min(self.width as i32 - 1);
+        let min_y = to_index(start.y.min(end.y) - radius).max(0);
+        let max_y = to_index(start.y.max(end.y) + radius).min(self.height as i32 - 1);
+        if min_x > max_x || min_y > max_y {
+            return None;
+        }
+
+        let from = (start.x, start.y, start.z);
+        let to = (end.x, end.y, end.z);
+        let mut deepest: Option<f32> = None;
+        for y in min_y as usize..=max_y as usize {
+            let py = (y as f32 + 0.5) * res;
+            let Some(columns) = Self::sweep_columns(tool, py, from, to, min_x, max_x, res) else {
+                continue;
+            };
+            for x in columns {
+                let Some(top) = self.column_top(x, y) else {
+                    continue;
+                };
+                let px = (x as f32 + 0.5) * res;
+                let Some(bottom) = tool.swept_bottom(px, py, from, to) else {
+                    continue;
+                };
+                let engagement = top - (bottom + lift);
+                if engagement > res * 0.5 && deepest.is_none_or(|d| engagement > d) {
+                    deepest = Some(engagement);
+                }
+            }
+        }
+        deepest
+    }
+
+    /// Columns on the row at `y` within the tool radius of the move, clamped
+    /// to `min_x..=max_x`.
+    fn sweep_columns(
+        tool: &ToolProfile,
+        y: f32,
+        from: (f32, f32, f32),
+        to: (f32, f32, f32),
+        min_x: i32,
+        max_x: i32,
+        res: f32,
+    ) -> Option<std::ops::RangeInclusive<usize>> {
+        let (left, right) = tool.sweep_span(y, from, to)?;
+        let first = ((left / res).floor() as i32).max(min_x);
+        let last = ((right / res).floor() as i32).min(max_x);
+        (first <= last).then_some(first as usize..=last as usize)
+    }
 }
 
 pub fn generate_surface_mesh(grid: &VoxelGrid) -> Vec<f32> {
@@ -247,14 +368,34 @@ pub fn generate_surface_mesh(grid: &VoxelGrid) -> Vec<f32> {
 
 pub struct StockSimulator3D {
     grid: VoxelGrid,
-    tool_radius: f32,
+    tool: ToolProfile,
 }
 
 impl StockSimulator3D {
+    /// Create a simulator cutting with a flat end mill of the given radius
     pub fn new(width: f32, height: f32, depth: f32, resolution: f32, tool_radius: f32) -> Self {
+        Self::with_tool(
+            width,
+            height,
+            depth,
+            resolution,
+            ToolProfile::Flat {
+                diameter: tool_radius * 2.0,
+            },
+        )
+    }
+
+    /// Create a simulator cutting with the given tool profile
+    pub fn with_tool(
+        width: f32,
+        height: f32,
+        depth: f32,
+        resolution: f32,
+        tool: ToolProfile,
+    ) -> Self {
         Self {
             grid: VoxelGrid::new(width, height, depth, resolution),
-            tool_radius,
+            tool,
         }
     }
 
@@ -262,6 +403,14 @@ impl StockSimulator3D {
         &self.grid
     }
 
+    pub fn tool(&self) -> &ToolProfile {
+        &self.tool
+    }
+
+    pub fn set_tool(&mut self, tool: ToolProfile) {
+        self.tool = tool;
+    }
+
     pub fn simulate_toolpath(&mut self, toolpath: &[ToolpathSegment]) {
         let _ = self.simulate_toolpath_with_progress(toolpath, |_| true);
     }
@@ -283,145 +432,84 @@ impl StockSimulator3D {
             if !on_progress((idx as f32) / total) {
                 return false;
             }
-            match segment.segment_type {
-                ToolpathSegmentType::LinearMove => {
-                    let start = Vec3::new(segment.start.0, segment.start.1, segment.start.2);
-                    let end = Vec3::new(segment.end.0, segment.end.1, segment.end.2);
-                    if !self.remove_linear_cancellable(start, end, &mut on_progress) {
-                        return false;
-                    }
-                }
-                ToolpathSegmentType::ArcCW => {
-                    let start = Vec3::new(segment.start.0, segment.start.1, segment.start.2);
-                    let end = Vec3::new(segment.end.0, segment.end.1, segment.end.2);
-                    if let Some(center_tuple) = segment.center {
-                        let center = Vec3::new(center_tuple.0, center_tuple.1, start.z);
-                        if !self.remove_arc_cancellable(start, end, center, true, &mut on_progress)
-                        {
-                            return false;
-                        }
-                    }
-                }
-                ToolpathSegmentType::ArcCCW => {
-                    let start = Vec3::new(segment.start.0, segment.start.1, segment.start.2);
-                    let end = Vec3::new(segment.end.0, segment.end.1, segment.end.2);
-                    if let Some(center_tuple) = segment.center {
-                        let center = Vec3::new(center_tuple.0, center_tuple.1, start.z);
-                        if !self.remove_arc_cancellable(start, end, center, false, &mut on_progress)
-                        {
-                            return false;
-                        }
-                    }
+            if segment.segment_type == ToolpathSegmentType::RapidMove {
+                continue;
+            }
+            for (i, (start, end)) in self.segment_chords(segment).into_iter().enumerate() {
+                if i > 0 && (i & 0xFF) == 0 && !on_progress((idx as f32) / total) {
+                    return false;
                 }
-                _ => {}
+                self.cut(start, end);
             }
         }
         on_progress(1.0)
     }
 
-    #[allow(dead_code)]
-    fn remove_linear(&mut self, start: Vec3, end: Vec3) {
-        let dist = start.distance(end);
-        let steps = (dist / (self.grid.resolution * 0.5)).ceil() as usize;
-        for i in 0..=steps {
-            let t = i as f32 / steps as f32;
-            let pos = start.lerp(end, t);
-            self.grid.remove_sphere(pos, self.tool_radius);
-        }
+    /// Sweep the current tool in a straight line from `start` to `end`
+    pub fn cut(&mut self, start: Vec3, end: Vec3) {
+        self.grid.remove_tool_sweep(start, end, &self.tool);
     }
 
-    fn remove_linear_cancellable<F>(&mut self, start: Vec3, end: Vec3, on_progress: &mut F) -> bool
-    where
-        F: FnMut(f32) -> bool,
-    {
-        let dist = start.distance(end);
-        let steps = (dist / (self.grid.resolution * 0.5)).ceil() as usize;
-        for i in 0..=steps {
-            if (i & 0xFF) == 0 && !on_progress(0.0) {
-                return false;
+    /// Split a segment into straight chords.
+    ///
+    /// Lines and rapids yield a single chord; arcs are split so that the
+    /// deviation from the true arc stays below a quarter of a voxel.
+    pub fn segment_chords(&self, segment: &ToolpathSegment) -> Vec<(Vec3, Vec3)> {
+        let start = Vec3::new(segment.start.0, segment.start.1, segment.start.2);
+        let end = Vec3::new(segment.end.0, segment.end.1, segment.end.2);
+        let clockwise = match segment.segment_type {
+            ToolpathSegmentType::ArcCW => true,
+            ToolpathSegmentType::ArcCCW => false,
+            ToolpathSegmentType::RapidMove | ToolpathSegmentType::LinearMove => {
+                return vec![(start, end)];
             }
-            let t = i as f32 / steps as f32;
-            let pos = start.lerp(end, t);
-            self.grid.remove_sphere(pos, self.tool_radius);
-        }
-        true
-    }
+        };
+        let Some(center) = segment.center else {
+            return vec![(start, end)];
+        };
+        let center = Vec3::new(center.0, center.1, start.z);
 
-    #[allow(dead_code)]
-    fn remove_arc(&mut self, start: Vec3, end: Vec3, center: Vec3, clockwise: bool) {
-        let radius = (start - center).length();
+        let radius = (start - center).truncate().length();
         let start_angle = (start.y - center.y).atan2(start.x - center.x);
         let end_angle = (end.y - center.y).atan2(end.x - center.x);
         let angle_span = if clockwise {
-            if end_angle > start_angle {
+            if end_angle >= start_angle {
                 end_angle - start_angle - 2.0 * std::f32::consts::PI
             } else {
                 end_angle - start_angle
             }
-        } else if end_angle < start_angle {
+        } else if end_angle <= start_angle {
             end_angle - start_angle + 2.0 * std::f32::consts::PI
         } else {
             end_angle - start_angle
         };
-        let arc_length = radius * angle_span.abs();
-        let resolution = self.grid.resolution;
-        let steps = (arc_length / (resolution * 0.5)).ceil() as usize;
-        let steps = steps.max(1);
-        for i in 0..=steps {
-            let t = i as f32 / steps as f32;
-            let angle = start_angle + angle_span * t;
-            let point = Vec3::new(
-                center.x + radius * angle.cos(),
-                center.y + radius * angle.sin(),
-                start.z + (end.z - start.z) * t,
-            );
-            self.grid.remove_sphere(point, self.tool_radius);
-        }
-    }
 
-    fn remove_arc_cancellable<F>(
-        &mut self,
-        start: Vec3,
-        end: Vec3,
-        center: Vec3,
-        clockwise: bool,
-        on_progress: &mut F,
-    ) -> bool
-    where
-        F: FnMut(f32) -> bool,
-    {
-        let radius = (start - center).length();
-        let start_angle = (start.y - center.y).atan2(start.x - center.x);
-        let end_angle = (end.y - center.y).atan2(end.x - center.x);
-        let angle_span = if clockwise {
-            if end_angle > start_angle {
-                end_angle - start_angle - 2.0 * std::f32::consts::PI
-            } else {
-                end_angle - start_angle
-            }
-        } else if end_angle < start_angle {
-            end_angle - start_angle + 2.0 * std::f32::consts::PI
+        let tolerance = self.grid.resolution * 0.25;
+        let max_step = if radius > tolerance {
+            2.0 * (1.0 - tolerance / radius).acos()
         } else {
-            end_angle - start_angle
+            std::f32::consts::FRAC_PI_2
         };
-        let arc_length = radius * angle_span.abs();
-        let resolution = self.grid.resolution;
-        let steps = (arc_length / (resolution * 0.5)).ceil() as usize;
-        let steps = steps.max(1);
-        for i in 0..=steps {
-            if (i & 0xFF) == 0 && !on_progress(0.0) {
-                return false;
-            }
+        let steps = ((angle_span.abs() / max_step.max(1e-3)).ceil() as usize).max(1);
+
+        let mut chords = Vec::with_capacity(steps);
+        let mut previous = start;
+        for i in 1..=steps {
             let t = i as f32 / steps as f32;
-            let angle = start_angle + angle_span * t;
-            let point = Vec3::new(
-                center.x + radius * angle.cos(),
-                center.y + radius * angle.sin(),
-                start.z + (end.z - start.z) * t,
-            );
-            self.grid.remove_sphere(point, self.tool_radius);
+            let point = if i == steps {
+                end
+            } else {
+                let angle = start_angle + angle_span * t;
+                Vec3::new(
+                    center.x + radius * angle.cos(),
+                    center.y + radius * angle.sin(),
+                    start.z + (end.z - start.z) * t,
+                )
+            };
+            chords.push((previous, point));
+            previous = point;
         }
-        true
+        chords
     }
 
     pub fn get_mesh(&self) -> Vec<f32> {
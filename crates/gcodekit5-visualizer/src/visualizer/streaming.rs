@@ -0,0 +1,425 @@
+//! Incremental, cancellable G-code parsing for the visualizer
+//!
+//! [`GcodeStreamParser`] turns G-code into [`ToolpathBuffers`] one line at a
+//! time, so a file never has to be held in memory as `GCodeCommand`s.
+//! [`ParseJob`] runs it on a worker thread fed from
+//! [`GcodeFileReader::read_lines`] (or an in-memory string), reports
+//! progress and can be cancelled at any point.
+
+use super::toolpath_buffers::{ToolpathBuffers, ToolpathLod};
+use super::visualizer::Point3D;
+use crate::utils::GcodeFileReader;
+use anyhow::{anyhow, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use tracing::debug;
+
+/// Number of lines between progress events
+const PROGRESS_INTERVAL_LINES: u64 = 16_384;
+
+/// Parses G-code line by line into columnar toolpath buffers
+#[derive(Debug)]
+pub struct GcodeStreamParser {
+    buffers: ToolpathBuffers,
+    current_pos: Point3D,
+    current_intensity: f32,
+    // Modal motion command (G0-G3) applied to lines without a G word
+    motion: Option<u32>,
+    line_number: u32,
+    hasher: DefaultHasher,
+}
+
+impl Default for GcodeStreamParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GcodeStreamParser {
+    pub fn new() -> Self {
+        Self {
+            buffers: ToolpathBuffers::new(),
+            current_pos: Point3D::new(0.0, 0.0, 0.0),
+            current_intensity: 0.0,
+            motion: None,
+            line_number: 0,
+            hasher: DefaultHasher::new(),
+        }
+    }
+
+    /// Parse the next source line
+    pub fn parse_line(&mut self, line: &str) {
+        self.line_number += 1;
+        line.hash(&mut self.hasher);
+
+        let line = strip_line_number(line.trim());
+        if line.is_empty() || line.starts_with(';') || line.starts_with('(') {
+            return;
+        }
+
+        let command = match extract_gcode_num(line) {
+            Some(motion @ 0..=3) => {
+                self.motion = Some(motion);
+                Some(motion)
+            }
+            // Axis words alone continue the last motion command
+            None if line.starts_with(['X', 'Y', 'Z', 'I', 'J']) => self.motion,
+            other => other,
+        };
+        match command {
+            Some(0) => self.parse_linear_move(line, true),
+            Some(1) => self.parse_linear_move(line, false),
+            Some(2) => self.parse_arc_move(line, true),
+            Some(3) => self.parse_arc_move(line, false),
+            Some(4) => self.parse_dwell(line),
+            _ => {}
+        }
+    }
+
+    /// Number of lines parsed so far
+    pub fn lines_parsed(&self) -> u32 {
+        self.line_number
+    }
+
+    /// Number of segments parsed so far
+    pub fn segment_count(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Finish parsing and build the level-of-detail copies
+    pub fn finish(mut self) -> ParsedToolpath {
+        self.buffers.shrink_to_fit();
+        let lod = ToolpathLod::build(&self.buffers);
+        debug!(
+            "Stream parse complete: {} lines, {} segments, {} LOD levels, {} KiB",
+            self.line_number,
+            self.buffers.len(),
+            lod.len(),
+            self.buffers.memory_bytes() / 1024
+        );
+        ParsedToolpath {
+            buffers: self.buffers,
+            lod,
+            final_position: self.current_pos,
+            final_intensity: self.current_intensity,
+            content_hash: self.hasher.finish(),
+            lines_read: self.line_number as u64,
+        }
+    }
+
+    fn parse_dwell(&mut self, line: &str) {
+        let mut duration = 0.0;
+        for part in line.split_whitespace() {
+            if let Some(('P' | 'X', val)) = split_word(part) {
+                duration = val;
+            }
+        }
+        self.buffers.push_dwell(duration, self.line_number);
+    }
+
+    fn parse_linear_move(&mut self, line: &str, is_rapid: bool) {
+        let mut new_pos = self.current_pos;
+        let mut axis_found = false;
+
+        for part in line.split_whitespace() {
+            let Some((word, val)) = split_word(part) else {
+                continue;
+            };
+            match word {
+                'X' => {
+                    new_pos.x = val;
+                    axis_found = true;
+                }
+                'Y' => {
+                    new_pos.y = val;
+                    axis_found = true;
+                }
+                'Z' => {
+                    new_pos.z = val;
+                    axis_found = true;
+                }
+                'S' => self.current_intensity = val,
+                'F' => self.buffers.set_feed_rate(val),
+                _ => {}
+            }
+        }
+
+        // Only create a segment if at least one axis was given
+        if axis_found {
+            self.buffers
+                .push_move(new_pos, is_rapid, self.current_intensity, self.line_number);
+            self.current_pos = new_pos;
+        }
+    }
+
+    fn parse_arc_move(&mut self, line: &str, clockwise: bool) {
+        let mut new_x = None;
+        let mut new_y = None;
+        let mut new_z = None;
+        let mut offset_i = None;
+        let mut offset_j = None;
+
+        for part in line.split_whitespace() {
+            let Some((word, val)) = split_word(part) else {
+                continue;
+            };
+            match word {
+                'X' => new_x = Some(val),
+                'Y' => new_y = Some(val),
+                'Z' => new_z = Some(val),
+                'I' => offset_i = Some(val),
+                'J' => offset_j = Some(val),
+                'S' => self.current_intensity = val,
+                'F' => self.buffers.set_feed_rate(val),
+                _ => {}
+            }
+        }
+
+        // Omitted end point words keep their value and omitted offsets are
+        // zero, but at least one offset is needed for a centre
+        if offset_i.is_some() || offset_j.is_some() {
+            let to = Point3D::new(
+                new_x.unwrap_or(self.current_pos.x),
+                new_y.unwrap_or(self.current_pos.y),
+                new_z.unwrap_or(self.current_pos.z),
+            );
+            let center = (
+                self.current_pos.x + offset_i.unwrap_or(0.0),
+                self.current_pos.y + offset_j.unwrap_or(0.0),
+            );
+            self.buffers.push_arc(
+                to,
+                center,
+                clockwise,
+                self.current_intensity,
+                self.line_number,
+            );
+            self.current_pos = to;
+        }
+    }
+}
+
+/// Hash of a program as [`GcodeStreamParser`] computes it, line by line
+pub(crate) fn content_hash<'a>(lines: impl IntoIterator<Item = &'a str>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for line in lines {
+        line.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Drop a leading block number such as "N120"
+fn strip_line_number(line: &str) -> &str {
+    match line.strip_prefix('N') {
+        Some(rest) => rest
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .trim_start(),
+        None => line,
+    }
+}
+
+/// Split a word such as "X10.5" into its letter and value
+fn split_word(part: &str) -> Option<(char, f32)> {
+    let mut chars = part.chars();
+    let word = chars.next()?;
+    chars.as_str().parse::<f32>().ok().map(|val| (word, val))
+}
+
+/// Extract G-code command number from line (e.g., "G01 X10" -> Some(1))
+fn extract_gcode_num(line: &str) -> Option<u32> {
+    let after_g = line.strip_prefix('G')?;
+    let end_idx = after_g
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(after_g.len());
+    if end_idx == 0 {
+        return None;
+    }
+    after_g[..end_idx].parse::<u32>().ok()
+}
+
+/// Result of a completed parse
+#[derive(Debug, Clone)]
+pub struct ParsedToolpath {
+    pub buffers: ToolpathBuffers,
+    pub lod: ToolpathLod,
+    /// Tool position after the last move
+    pub final_position: Point3D,
+    /// Last S value seen
+    pub final_intensity: f32,
+    /// Hash of the parsed lines, for skipping unchanged content
+    pub content_hash: u64,
+    pub lines_read: u64,
+}
+
+/// Progress of a running [`ParseJob`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseProgress {
+    pub lines_read: u64,
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub segments: usize,
+}
+
+impl ParseProgress {
+    /// Get progress percentage
+    pub fn percent(&self) -> f64 {
+        if self.total_bytes == 0 {
+            0.0
+        } else {
+            (self.bytes_read as f64 / self.total_bytes as f64 * 100.0).min(100.0)
+        }
+    }
+}
+
+/// Event sent from a [`ParseJob`] worker
+#[derive(Debug)]
+pub enum ParseEvent {
+    Progress(ParseProgress),
+    Finished(Box<ParsedToolpath>),
+    Cancelled,
+    Failed(String),
+}
+
+impl ParseEvent {
+    /// True for the last event a job sends
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, Self::Progress(_))
+    }
+}
+
+/// G-code parse running on a worker thread.
+///
+/// Dropping the job cancels it.
+#[derive(Debug)]
+pub struct ParseJob {
+    cancel: Arc<AtomicBool>,
+    events: mpsc::Receiver<ParseEvent>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ParseJob {
+    /// Parse a file, streaming it with [`GcodeFileReader::read_lines`]
+    ///
+    /// # Errors
+    /// Returns error if the file does not exist or cannot be accessed
+    pub fn spawn_file(path: impl AsRef<Path>) -> Result<Self> {
+        let reader = GcodeFileReader::new(path)?;
+        Ok(Self::spawn(reader.file_size(), move |on_line| {
+            reader.read_lines(on_line).map(|_| ())
+        }))
+    }
+
+    /// Parse G-code that is already in memory, e.g. the editor contents
+    pub fn spawn_text(text: String) -> Self {
+        Self::spawn(text.len() as u64, move |on_line| {
+            text.lines().try_for_each(on_line)
+        })
+    }
+
+    fn spawn<F>(total_bytes: u64, feed: F) -> Self
+    where
+        F: FnOnce(&mut dyn FnMut(&str) -> Result<()>) -> Result<()> + Send + 'static,
+    {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, events) = mpsc::channel();
+        let cancel_worker = cancel.clone();
+        let handle = std::thread::spawn(move || {
+            let event = run_worker(total_bytes, &cancel_worker, &sender, feed);
+            let _ = sender.send(event);
+        });
+
+        Self {
+            cancel,
+            events,
+            handle: Some(handle),
+        }
+    }
+
+    /// Ask the worker to stop. It sends [`ParseEvent::Cancelled`] when it does.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    /// Next event, without blocking
+    pub fn try_next(&self) -> Option<ParseEvent> {
+        self.events.try_recv().ok()
+    }
+
+    /// Block until the job ends and return its final event
+    pub fn wait(mut self) -> ParseEvent {
+        let event = loop {
+            match self.events.recv() {
+                Ok(event) if event.is_terminal() => break event,
+                Ok(_) => continue,
+                Err(_) => break ParseEvent::Failed("parse worker stopped".to_string()),
+            }
+        };
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        event
+    }
+}
+
+impl Drop for ParseJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn run_worker<F>(
+    total_bytes: u64,
+    cancel: &AtomicBool,
+    sender: &mpsc::Sender<ParseEvent>,
+    feed: F,
+) -> ParseEvent
+where
+    F: FnOnce(&mut dyn FnMut(&str) -> Result<()>) -> Result<()>,
+{
+    let mut parser = GcodeStreamParser::new();
+    let mut lines_read = 0u64;
+    let mut bytes_read = 0u64;
+
+    let result = feed(&mut |line: &str| {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow!("parse cancelled"));
+        }
+        parser.parse_line(line);
+        lines_read += 1;
+        bytes_read += line.len() as u64 + 1;
+        if lines_read.is_multiple_of(PROGRESS_INTERVAL_LINES) {
+            sender
+                .send(ParseEvent::Progress(ParseProgress {
+                    lines_read,
+                    bytes_read,
+                    total_bytes,
+                    segments: parser.segment_count(),
+                }))
+                .map_err(|_| anyhow!("parse job dropped"))?;
+        }
+        Ok(())
+    });
+
+    if cancel.load(Ordering::SeqCst) {
+        return ParseEvent::Cancelled;
+    }
+    if let Err(e) = result {
+        return ParseEvent::Failed(e.to_string());
+    }
+
+    let _ = sender.send(ParseEvent::Progress(ParseProgress {
+        lines_read,
+        bytes_read: total_bytes.max(bytes_read),
+        total_bytes,
+        segments: parser.segment_count(),
+    }));
+    ParseEvent::Finished(Box::new(parser.finish()))
+}
@@ -0,0 +1,394 @@
+//! Line/Z filtering and time-based scrubbing of a parsed toolpath
+//!
+//! [`ToolpathFilter`] limits what is drawn to a range of source lines and/or
+//! a Z band. [`ToolpathTimeline`] estimates when each segment runs from its
+//! length and feed rate, so a slider can move a tool marker along the path
+//! and map between playback time and G-code lines.
+
+use super::toolpath_buffers::{SegmentKind, ToolpathBuffers};
+use super::visualizer::{GCodeCommand, Point3D};
+use std::f32::consts::TAU;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::time::Duration;
+
+/// Rapid rate (mm/min) used when the machine's is unknown
+pub const DEFAULT_RAPID_RATE_MM_MIN: f32 = 3000.0;
+
+/// Feed rate (mm/min) used for cutting moves before the first F word
+pub const DEFAULT_FEED_RATE_MM_MIN: f32 = 1000.0;
+
+/// Limits which segments of a toolpath are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ToolpathFilter {
+    /// Inclusive range of 1-based source lines
+    pub line_range: Option<(u32, u32)>,
+    /// Inclusive Z band; a segment is shown when its Z span overlaps it
+    pub z_range: Option<(f32, f32)>,
+}
+
+impl ToolpathFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only show segments from lines `first..=last`
+    pub fn with_line_range(mut self, first: u32, last: u32) -> Self {
+        self.line_range = Some((first.min(last), first.max(last)));
+        self
+    }
+
+    /// Only show segments that reach into `min..=max` on Z
+    pub fn with_z_range(mut self, min: f32, max: f32) -> Self {
+        self.z_range = Some((min.min(max), min.max(max)));
+        self
+    }
+
+    /// True when the filter hides anything
+    pub fn is_active(&self) -> bool {
+        self.line_range.is_some() || self.z_range.is_some()
+    }
+
+    /// Range of segment indices that can pass the line filter. Source lines
+    /// only grow along a toolpath, so this narrows loops before
+    /// [`Self::matches`] is checked per segment.
+    pub fn segment_range(&self, buffers: &ToolpathBuffers) -> Range<usize> {
+        match self.line_range {
+            Some((first, last)) => {
+                let lines = buffers.lines();
+                lines.partition_point(|&l| l < first)..lines.partition_point(|&l| l <= last)
+            }
+            None => 0..buffers.len(),
+        }
+    }
+
+    /// True when segment `index` of `buffers` passes the filter
+    pub fn matches(&self, buffers: &ToolpathBuffers, index: usize) -> bool {
+        if let Some((first, last)) = self.line_range {
+            let line = buffers.lines()[index];
+            if line < first || line > last {
+                return false;
+            }
+        }
+        if let Some((min, max)) = self.z_range {
+            let from = buffers.start_point(index).z;
+            let to = buffers.end_point(index).z;
+            if from.min(to) > max || from.max(to) < min {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Iterate the segments of `buffers` that pass the filter
+    pub fn iter<'a>(
+        &'a self,
+        buffers: &'a ToolpathBuffers,
+    ) -> impl Iterator<Item = GCodeCommand> + 'a {
+        let range = self.segment_range(buffers);
+        buffers
+            .iter()
+            .enumerate()
+            .take(range.end)
+            .skip(range.start)
+            .filter(|(index, _)| self.z_range.is_none() || self.matches(buffers, *index))
+            .map(|(_, command)| command)
+    }
+}
+
+impl Hash for ToolpathFilter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.line_range.hash(state);
+        self.z_range
+            .map(|(min, max)| (min.to_bits(), max.to_bits()))
+            .hash(state);
+    }
+}
+
+/// Estimated run time of every segment of a toolpath.
+///
+/// Cutting moves run at their modal feed rate and rapids at the machine's
+/// rapid rate; acceleration is ignored. Dwells take their P value in seconds.
+#[derive(Debug, Clone, Default)]
+pub struct ToolpathTimeline {
+    /// Time (s) at which each segment finishes
+    end_times: Vec<f64>,
+    /// XY centre of each arc, keyed by segment index
+    arc_centers: Vec<(u32, f32, f32)>,
+}
+
+impl ToolpathTimeline {
+    /// Build the timeline of `buffers`
+    pub fn build(buffers: &ToolpathBuffers, rapid_rate_mm_min: f32) -> Self {
+        let rapid_rate = rapid_rate_mm_min.max(f32::EPSILON) as f64;
+        let mut end_times = Vec::with_capacity(buffers.len());
+        let mut arc_centers = Vec::new();
+        let mut elapsed = 0.0f64;
+
+        for (index, command) in buffers.iter().enumerate() {
+            let feed = buffers
+                .feed_rate(index)
+                .filter(|f| *f > 0.0)
+                .unwrap_or(DEFAULT_FEED_RATE_MM_MIN) as f64;
+
+            elapsed += match command {
+                GCodeCommand::Move {
+                    from,
+                    to,
+                    rapid: true,
+                    ..
+                } => distance(from, to) as f64 / rapid_rate * 60.0,
+                GCodeCommand::Move { from, to, .. } => distance(from, to) as f64 / feed * 60.0,
+                GCodeCommand::Arc {
+                    from,
+                    to,
+                    center,
+                    clockwise,
+                    ..
+                } => {
+                    arc_centers.push((index as u32, center.x, center.y));
+                    let arc = ArcGeometry::new(from, to, (center.x, center.y), clockwise);
+                    arc.length() as f64 / feed * 60.0
+                }
+                GCodeCommand::Dwell { duration, .. } => duration.max(0.0) as f64,
+            };
+            end_times.push(elapsed);
+        }
+
+        Self {
+            end_times,
+            arc_centers,
+        }
+    }
+
+    /// Number of segments
+    pub fn len(&self) -> usize {
+        self.end_times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end_times.is_empty()
+    }
+
+    /// Estimated run time of the whole toolpath, in seconds
+    pub fn total_time(&self) -> f64 {
+        self.end_times.last().copied().unwrap_or(0.0)
+    }
+
+    /// Time (s) at which segment `index` starts
+    pub fn start_time(&self, index: usize) -> f64 {
+        match index {
+            0 => 0.0,
+            i => self.end_times[i - 1],
+        }
+    }
+
+    /// Time (s) at which segment `index` finishes
+    pub fn end_time(&self, index: usize) -> f64 {
+        self.end_times[index]
+    }
+
+    /// Segment running at `time` seconds
+    pub fn segment_at(&self, time: f64) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        let index = self.end_times.partition_point(|&end| end < time);
+        Some(index.min(self.len() - 1))
+    }
+
+    /// Tool position at `time` seconds, interpolated along the segment
+    /// running then
+    pub fn position_at(&self, buffers: &ToolpathBuffers, time: f64) -> Point3D {
+        let Some(index) = self.segment_at(time) else {
+            return buffers.start();
+        };
+        let (start, end) = (self.start_time(index), self.end_time(index));
+        let t = if end > start {
+            ((time - start) / (end - start)).clamp(0.0, 1.0) as f32
+        } else {
+            1.0
+        };
+        self.point_on_segment(buffers, index, t)
+    }
+
+    /// Point a fraction `t` (0..=1) of the way along segment `index`,
+    /// following arcs
+    pub fn point_on_segment(&self, buffers: &ToolpathBuffers, index: usize, t: f32) -> Point3D {
+        let from = buffers.start_point(index);
+        let to = buffers.end_point(index);
+        match buffers.kind(index) {
+            SegmentKind::Rapid | SegmentKind::Linear => lerp(from, to, t),
+            kind @ (SegmentKind::ArcCw | SegmentKind::ArcCcw) => {
+                ArcGeometry::new(from, to, self.arc_center(index), kind == SegmentKind::ArcCw)
+                    .point_at(t)
+            }
+            SegmentKind::Dwell => to,
+        }
+    }
+
+    /// Path length of segment `index` in mm, following arcs
+    pub fn segment_length(&self, buffers: &ToolpathBuffers, index: usize) -> f32 {
+        let from = buffers.start_point(index);
+        let to = buffers.end_point(index);
+        match buffers.kind(index) {
+            SegmentKind::Rapid | SegmentKind::Linear => distance(from, to),
+            kind @ (SegmentKind::ArcCw | SegmentKind::ArcCcw) => {
+                ArcGeometry::new(from, to, self.arc_center(index), kind == SegmentKind::ArcCw)
+                    .length()
+            }
+            SegmentKind::Dwell => 0.0,
+        }
+    }
+
+    /// XY centre of the arc at segment `index`
+    fn arc_center(&self, index: usize) -> (f32, f32) {
+        let slot = self
+            .arc_centers
+            .partition_point(|&(i, _, _)| (i as usize) < index);
+        let (_, cx, cy) = self.arc_centers[slot];
+        (cx, cy)
+    }
+
+    /// Source line of the segment running at `time` seconds
+    pub fn line_at(&self, buffers: &ToolpathBuffers, time: f64) -> Option<u32> {
+        self.segment_at(time).map(|index| buffers.lines()[index])
+    }
+
+    /// Time (s) at which everything up to and including source `line` has
+    /// run, i.e. where the tool is once that line is done
+    pub fn time_at_line(&self, buffers: &ToolpathBuffers, line: u32) -> f64 {
+        let done = buffers.lines().partition_point(|&l| l <= line);
+        match done {
+            0 => 0.0,
+            n => self.end_times[n - 1],
+        }
+    }
+}
+
+/// Play/pause state of a timeline scrubber
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimelinePlayback {
+    time: f64,
+    speed: f64,
+    playing: bool,
+}
+
+impl Default for TimelinePlayback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimelinePlayback {
+    pub fn new() -> Self {
+        Self {
+            time: 0.0,
+            speed: 1.0,
+            playing: false,
+        }
+    }
+
+    /// Current position on the timeline, in seconds
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time.max(0.0);
+    }
+
+    /// Speed multiplier; 1.0 is real time
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.0);
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Move forward by `elapsed` wall-clock time scaled by the speed
+    /// multiplier. Playback stops at `total_time`. Returns the new time.
+    pub fn advance(&mut self, elapsed: Duration, total_time: f64) -> f64 {
+        if self.playing {
+            self.time += elapsed.as_secs_f64() * self.speed;
+            if self.time >= total_time {
+                self.time = total_time;
+                self.playing = false;
+            }
+        }
+        self.time
+    }
+}
+
+fn distance(a: Point3D, b: Point3D) -> f32 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2) + (b.z - a.z).powi(2)).sqrt()
+}
+
+fn lerp(a: Point3D, b: Point3D, t: f32) -> Point3D {
+    Point3D::new(
+        a.x + (b.x - a.x) * t,
+        a.y + (b.y - a.y) * t,
+        a.z + (b.z - a.z) * t,
+    )
+}
+
+/// Helical arc in the XY plane with linear Z travel
+struct ArcGeometry {
+    center: (f32, f32),
+    radius: f32,
+    start_angle: f32,
+    /// Signed sweep, negative for clockwise
+    sweep: f32,
+    z: (f32, f32),
+}
+
+impl ArcGeometry {
+    fn new(from: Point3D, to: Point3D, center: (f32, f32), clockwise: bool) -> Self {
+        let start_angle = (from.y - center.1).atan2(from.x - center.0);
+        let end_angle = (to.y - center.1).atan2(to.x - center.0);
+        let mut sweep = if clockwise {
+            start_angle - end_angle
+        } else {
+            end_angle - start_angle
+        };
+        sweep = sweep.rem_euclid(TAU);
+        // Same start and end point is a full circle
+        if sweep <= 1e-6 {
+            sweep = TAU;
+        }
+        Self {
+            center,
+            radius: ((from.x - center.0).powi(2) + (from.y - center.1).powi(2)).sqrt(),
+            start_angle,
+            sweep: if clockwise { -sweep } else { sweep },
+            z: (from.z, to.z),
+        }
+    }
+
+    fn length(&self) -> f32 {
+        let planar = self.radius * self.sweep.abs();
+        (planar.powi(2) + (self.z.1 - self.z.0).powi(2)).sqrt()
+    }
+
+    fn point_at(&self, t: f32) -> Point3D {
+        let angle = self.start_angle + self.sweep * t;
+        Point3D::new(
+            self.center.0 + self.radius * angle.cos(),
+            self.center.1 + self.radius * angle.sin(),
+            self.z.0 + (self.z.1 - self.z.0) * t,
+        )
+    }
+}
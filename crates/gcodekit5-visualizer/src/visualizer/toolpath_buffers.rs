@@ -0,0 +1,601 @@
+//! Compact columnar storage for parsed toolpaths
+//!
+//! A `Vec<GCodeCommand>` costs around 50 bytes per move, which adds up
+//! quickly for laser raster files with millions of lines. `ToolpathBuffers`
+//! keeps the same geometry as parallel arrays (about 21 bytes per move) and
+//! can build simplified copies of itself for level-of-detail rendering.
+
+use super::viewport::Bounds;
+use super::visualizer::{GCodeCommand, Point3D};
+
+/// Tolerances (mm) of the simplified levels built by [`ToolpathLod`].
+/// The first level only merges collinear moves and is visually lossless.
+const LOD_TOLERANCES_MM: [f32; 5] = [0.001, 0.05, 0.2, 0.8, 3.2];
+
+/// A level is only kept when it has at most this fraction of the segments
+/// of the level it was built from.
+const LOD_MIN_REDUCTION: f32 = 0.75;
+
+/// Largest on-screen error (pixels) accepted when choosing a level.
+const LOD_PIXEL_TOLERANCE: f32 = 0.5;
+
+/// Kind of a segment stored in [`ToolpathBuffers`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SegmentKind {
+    Rapid = 0,
+    Linear = 1,
+    ArcCw = 2,
+    ArcCcw = 3,
+    Dwell = 4,
+}
+
+impl SegmentKind {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Rapid,
+            1 => Self::Linear,
+            2 => Self::ArcCw,
+            3 => Self::ArcCcw,
+            _ => Self::Dwell,
+        }
+    }
+
+    pub fn is_arc(self) -> bool {
+        matches!(self, Self::ArcCw | Self::ArcCcw)
+    }
+}
+
+/// Toolpath geometry stored as parallel arrays.
+///
+/// Segments are contiguous: each one starts where the previous one ended,
+/// and the first starts at [`Self::start`]. Arc centres and dwell durations
+/// are stored once per arc or dwell, in segment order. Feed rates are modal,
+/// so only the segments where they change are recorded.
+#[derive(Debug, Clone, Default)]
+pub struct ToolpathBuffers {
+    start: [f32; 3],
+    kinds: Vec<u8>,
+    ends: Vec<f32>,
+    intensities: Vec<f32>,
+    lines: Vec<u32>,
+    arc_centers: Vec<f32>,
+    dwell_durations: Vec<f32>,
+    /// Modal feed rate changes as (first segment, mm/min), in segment order
+    feed_changes: Vec<(u32, f32)>,
+    bounds: Bounds,
+}
+
+impl ToolpathBuffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create empty buffers whose first segment starts at `start`
+    pub fn with_start(start: Point3D) -> Self {
+        Self {
+            start: [start.x, start.y, start.z],
+            ..Self::default()
+        }
+    }
+
+    /// Build buffers from commands. `lines` holds the 1-based source line
+    /// of each command and may be empty when unknown.
+    ///
+    /// Only the first command's start point is used; later commands are
+    /// assumed to start where the previous one ended.
+    pub fn from_commands(commands: &[GCodeCommand], lines: &[u32]) -> Self {
+        let start = match commands.first() {
+            Some(GCodeCommand::Move { from, .. }) | Some(GCodeCommand::Arc { from, .. }) => *from,
+            Some(GCodeCommand::Dwell { pos, .. }) => *pos,
+            None => Point3D::new(0.0, 0.0, 0.0),
+        };
+        let mut buffers = Self::with_start(start);
+        buffers.reserve(commands.len());
+        for (idx, cmd) in commands.iter().enumerate() {
+            let line = lines.get(idx).copied().unwrap_or(0);
+            match cmd {
+                GCodeCommand::Move {
+                    to,
+                    rapid,
+                    intensity,
+                    ..
+                } => buffers.push_move(*to, *rapid, intensity.unwrap_or(0.0), line),
+                GCodeCommand::Arc {
+                    to,
+                    center,
+                    clockwise,
+                    intensity,
+                    ..
+                } => buffers.push_arc(
+                    *to,
+                    (center.x, center.y),
+                    *clockwise,
+                    intensity.unwrap_or(0.0),
+                    line,
+                ),
+                GCodeCommand::Dwell { duration, .. } => buffers.push_dwell(*duration, line),
+            }
+        }
+        buffers
+    }
+
+    /// Reserve room for `additional` more segments
+    pub fn reserve(&mut self, additional: usize) {
+        self.kinds.reserve(additional);
+        self.ends.reserve(additional * 3);
+        self.intensities.reserve(additional);
+        self.lines.reserve(additional);
+    }
+
+    /// Release spare capacity once no more segments will be added
+    pub fn shrink_to_fit(&mut self) {
+        self.kinds.shrink_to_fit();
+        self.ends.shrink_to_fit();
+        self.intensities.shrink_to_fit();
+        self.lines.shrink_to_fit();
+        self.arc_centers.shrink_to_fit();
+        self.dwell_durations.shrink_to_fit();
+        self.feed_changes.shrink_to_fit();
+    }
+
+    /// Set the feed rate (mm/min) of the segments pushed from now on
+    pub fn set_feed_rate(&mut self, feed: f32) {
+        if self.feed_changes.last().map(|&(_, f)| f) == Some(feed) {
+            return;
+        }
+        let index = self.len() as u32;
+        match self.feed_changes.last_mut() {
+            // Nothing was pushed since the last change, so replace it
+            Some(last) if last.0 == index => last.1 = feed,
+            _ => self.feed_changes.push((index, feed)),
+        }
+    }
+
+    /// Feed rate (mm/min) in effect for a segment, if one was set before it
+    pub fn feed_rate(&self, index: usize) -> Option<f32> {
+        let after = self
+            .feed_changes
+            .partition_point(|&(first, _)| first as usize <= index);
+        after.checked_sub(1).map(|i| self.feed_changes[i].1)
+    }
+
+    /// Append a straight move from the current end point to `to`
+    pub fn push_move(&mut self, to: Point3D, rapid: bool, intensity: f32, line: u32) {
+        let kind = if rapid {
+            SegmentKind::Rapid
+        } else {
+            SegmentKind::Linear
+        };
+        self.push(kind, to, intensity, line);
+    }
+
+    /// Append an arc from the current end point to `to` around the XY `center`
+    pub fn push_arc(
+        &mut self,
+        to: Point3D,
+        center: (f32, f32),
+        clockwise: bool,
+        intensity: f32,
+        line: u32,
+    ) {
+        let kind = if clockwise {
+            SegmentKind::ArcCw
+        } else {
+            SegmentKind::ArcCcw
+        };
+        self.arc_centers.push(center.0);
+        self.arc_centers.push(center.1);
+        self.push(kind, to, intensity, line);
+    }
+
+    /// Append a dwell at the current end point
+    pub fn push_dwell(&mut self, duration: f32, line: u32) {
+        let pos = self.last_point();
+        self.dwell_durations.push(duration);
+        self.kinds.push(SegmentKind::Dwell as u8);
+        self.ends.extend_from_slice(&[pos.x, pos.y, pos.z]);
+        self.intensities.push(0.0);
+        self.lines.push(line);
+    }
+
+    fn push(&mut self, kind: SegmentKind, to: Point3D, intensity: f32, line: u32) {
+        let from = self.last_point();
+        self.bounds.update(from.x, from.y, from.z);
+        self.bounds.update(to.x, to.y, to.z);
+        self.kinds.push(kind as u8);
+        self.ends.extend_from_slice(&[to.x, to.y, to.z]);
+        self.intensities.push(intensity);
+        self.lines.push(line);
+    }
+
+    /// Number of segments
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Start point of the first segment
+    pub fn start(&self) -> Point3D {
+        Point3D::new(self.start[0], self.start[1], self.start[2])
+    }
+
+    /// End point of the last segment, or the start point when empty
+    pub fn last_point(&self) -> Point3D {
+        match self.len() {
+            0 => self.start(),
+            n => self.end_point(n - 1),
+        }
+    }
+
+    pub fn kind(&self, index: usize) -> SegmentKind {
+        SegmentKind::from_u8(self.kinds[index])
+    }
+
+    pub fn start_point(&self, index: usize) -> Point3D {
+        if index == 0 {
+            self.start()
+        } else {
+            self.end_point(index - 1)
+        }
+    }
+
+    pub fn end_point(&self, index: usize) -> Point3D {
+        let i = index * 3;
+        Point3D::new(self.ends[i], self.ends[i + 1], self.ends[i + 2])
+    }
+
+    pub fn intensity(&self, index: usize) -> f32 {
+        self.intensities[index]
+    }
+
+    /// 1-based source line of each segment (0 when unknown)
+    pub fn lines(&self) -> &[u32] {
+        &self.lines
+    }
+
+    /// Bounds of every segment end point, rapids included
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    /// Approximate heap memory used by the buffers, in bytes
+    pub fn memory_bytes(&self) -> usize {
+        self.kinds.capacity()
+            + (self.ends.capacity()
+                + self.intensities.capacity()
+                + self.arc_centers.capacity()
+                + self.dwell_durations.capacity())
+                * std::mem::size_of::<f32>()
+            + self.lines.capacity() * std::mem::size_of::<u32>()
+            + self.feed_changes.capacity() * std::mem::size_of::<(u32, f32)>()
+    }
+
+    /// Iterate the segments as [`GCodeCommand`]s
+    pub fn iter(&self) -> ToolpathIter<'_> {
+        ToolpathIter {
+            buffers: self,
+            index: 0,
+            arc_index: 0,
+            dwell_index: 0,
+            from: self.start(),
+        }
+    }
+
+    /// Materialise all segments as [`GCodeCommand`]s
+    pub fn to_commands(&self) -> Vec<GCodeCommand> {
+        self.iter().collect()
+    }
+
+    /// Build a simplified copy for drawing when `tolerance` mm is below
+    /// what can be seen.
+    ///
+    /// Consecutive straight moves of the same kind are merged while they
+    /// stay within `tolerance` of a common line, keep going the same way
+    /// and share the same S value. Moves shorter than `tolerance` are
+    /// absorbed into their neighbours, blending their S values. Arcs that
+    /// fit inside `tolerance` become straight moves; larger arcs and
+    /// dwells are kept as they are. Each merged segment keeps the source
+    /// line of its last move.
+    pub fn simplify(&self, tolerance: f32) -> ToolpathBuffers {
+        let mut out = ToolpathBuffers::with_start(self.start());
+        let mut run: Option<MergeRun> = None;
+        let mut arcs = 0;
+        let mut dwells = 0;
+        let mut from = self.start;
+
+        for index in 0..self.len() {
+            let kind = self.kind(index);
+            let i = index * 3;
+            let to = [self.ends[i], self.ends[i + 1], self.ends[i + 2]];
+            let intensity = self.intensities[index];
+            let line = self.lines[index];
+
+            let straight_kind = match kind {
+                SegmentKind::Rapid | SegmentKind::Linear => Some(kind),
+                SegmentKind::ArcCw | SegmentKind::ArcCcw => {
+                    let (cx, cy) = (self.arc_centers[arcs * 2], self.arc_centers[arcs * 2 + 1]);
+                    arcs += 1;
+                    let radius = ((from[0] - cx).powi(2) + (from[1] - cy).powi(2)).sqrt();
+                    if radius * 2.0 <= tolerance {
+                        Some(SegmentKind::Linear)
+                    } else {
+                        flush_run(&mut out, run.take());
+                        out.push_arc(
+                            point(to),
+                            (cx, cy),
+                            kind == SegmentKind::ArcCw,
+                            intensity,
+                            line,
+                        );
+                        None
+                    }
+                }
+                SegmentKind::Dwell => {
+                    flush_run(&mut out, run.take());
+                    out.push_dwell(self.dwell_durations[dwells], line);
+                    dwells += 1;
+                    None
+                }
+            };
+
+            if let Some(kind) = straight_kind {
+                let extended = run
+                    .as_mut()
+                    .is_some_and(|r| r.try_extend(kind, to, intensity, line, tolerance));
+                if !extended {
+                    flush_run(&mut out, run.take());
+                    run = Some(MergeRun::new(kind, from, to, intensity, line, tolerance));
+                }
+            }
+            from = to;
+        }
+        flush_run(&mut out, run);
+        out.shrink_to_fit();
+        out
+    }
+}
+
+fn point(p: [f32; 3]) -> Point3D {
+    Point3D::new(p[0], p[1], p[2])
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn length(a: [f32; 3]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+/// A run of straight moves being merged into one segment
+struct MergeRun {
+    kind: SegmentKind,
+    start: [f32; 3],
+    end: [f32; 3],
+    /// Unit direction, set once the run leaves the tolerance radius
+    direction: Option<[f32; 3]>,
+    /// S value of the moves that set the direction
+    intensity: f32,
+    /// False once moves with another S value were absorbed
+    uniform: bool,
+    weighted_intensity: f32,
+    total_length: f32,
+    line: u32,
+}
+
+impl MergeRun {
+    fn new(
+        kind: SegmentKind,
+        start: [f32; 3],
+        end: [f32; 3],
+        intensity: f32,
+        line: u32,
+        tolerance: f32,
+    ) -> Self {
+        let delta = sub(end, start);
+        let len = length(delta);
+        let direction = (len > tolerance).then(|| [delta[0] / len, delta[1] / len, delta[2] / len]);
+        Self {
+            kind,
+            start,
+            end,
+            direction,
+            intensity,
+            uniform: true,
+            weighted_intensity: intensity * len,
+            total_length: len,
+            line,
+        }
+    }
+
+    fn try_extend(
+        &mut self,
+        kind: SegmentKind,
+        to: [f32; 3],
+        intensity: f32,
+        line: u32,
+        tolerance: f32,
+    ) -> bool {
+        if kind != self.kind {
+            return false;
+        }
+        let same_intensity = intensity == self.intensity;
+        let offset = sub(to, self.start);
+        match self.direction {
+            None => {
+                // Everything so far is within the tolerance radius, so the
+                // first move leaving it sets the direction
+                let len = length(offset);
+                if len > tolerance {
+                    self.direction = Some([offset[0] / len, offset[1] / len, offset[2] / len]);
+                    self.intensity = intensity;
+                }
+            }
+            Some(direction) => {
+                if !same_intensity {
+                    return false;
+                }
+                let along = dot(offset, direction);
+                let across = length(sub(
+                    offset,
+                    [
+                        direction[0] * along,
+                        direction[1] * along,
+                        direction[2] * along,
+                    ],
+                ));
+                if across > tolerance || along < dot(sub(self.end, self.start), direction) {
+                    return false;
+                }
+            }
+        }
+        self.uniform &= same_intensity;
+        let step = length(sub(to, self.end));
+        self.weighted_intensity += intensity * step;
+        self.total_length += step;
+        self.end = to;
+        self.line = line;
+        true
+    }
+
+    fn intensity(&self) -> f32 {
+        if !self.uniform && self.total_length > 0.0 {
+            self.weighted_intensity / self.total_length
+        } else {
+            self.intensity
+        }
+    }
+}
+
+fn flush_run(out: &mut ToolpathBuffers, run: Option<MergeRun>) {
+    if let Some(run) = run {
+        out.push_move(
+            point(run.end),
+            run.kind == SegmentKind::Rapid,
+            run.intensity(),
+            run.line,
+        );
+    }
+}
+
+/// Iterator over [`ToolpathBuffers`] segments
+pub struct ToolpathIter<'a> {
+    buffers: &'a ToolpathBuffers,
+    index: usize,
+    arc_index: usize,
+    dwell_index: usize,
+    from: Point3D,
+}
+
+impl Iterator for ToolpathIter<'_> {
+    type Item = GCodeCommand;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.buffers.len() {
+            return None;
+        }
+        let index = self.index;
+        self.index += 1;
+
+        let from = self.from;
+        let to = self.buffers.end_point(index);
+        self.from = to;
+        let intensity = Some(self.buffers.intensities[index]);
+
+        Some(match self.buffers.kind(index) {
+            SegmentKind::Rapid | SegmentKind::Linear => GCodeCommand::Move {
+                from,
+                to,
+                rapid: self.buffers.kind(index) == SegmentKind::Rapid,
+                intensity,
+            },
+            kind @ (SegmentKind::ArcCw | SegmentKind::ArcCcw) => {
+                let i = self.arc_index * 2;
+                self.arc_index += 1;
+                GCodeCommand::Arc {
+                    from,
+                    to,
+                    center: Point3D::new(
+                        self.buffers.arc_centers[i],
+                        self.buffers.arc_centers[i + 1],
+                        from.z,
+                    ),
+                    clockwise: kind == SegmentKind::ArcCw,
+                    intensity,
+                }
+            }
+            SegmentKind::Dwell => {
+                let duration = self.buffers.dwell_durations[self.dwell_index];
+                self.dwell_index += 1;
+                GCodeCommand::Dwell { pos: to, duration }
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.buffers.len() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for ToolpathIter<'_> {}
+
+/// Simplified copies of a toolpath for drawing at low zoom
+#[derive(Debug, Clone, Default)]
+pub struct ToolpathLod {
+    levels: Vec<(f32, ToolpathBuffers)>,
+}
+
+impl ToolpathLod {
+    /// Build the levels for `full`. Each level is simplified from the
+    /// previous one and only kept when it is noticeably smaller.
+    pub fn build(full: &ToolpathBuffers) -> Self {
+        let mut levels: Vec<(f32, ToolpathBuffers)> = Vec::new();
+        for tolerance in LOD_TOLERANCES_MM {
+            let source = levels.last().map(|(_, level)| level).unwrap_or(full);
+            let level = source.simplify(tolerance);
+            if (level.len() as f32) <= source.len() as f32 * LOD_MIN_REDUCTION {
+                levels.push((tolerance, level));
+            }
+        }
+        Self { levels }
+    }
+
+    /// Number of levels kept
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// Tolerance (mm) and geometry of a level, finest first
+    pub fn level(&self, index: usize) -> Option<(f32, &ToolpathBuffers)> {
+        self.levels
+            .get(index)
+            .map(|(tolerance, level)| (*tolerance, level))
+    }
+
+    /// Coarsest level whose error stays below half a pixel at
+    /// `pixels_per_mm`, or `None` when the full geometry is needed
+    pub fn select(&self, pixels_per_mm: f32) -> Option<&ToolpathBuffers> {
+        if pixels_per_mm <= 0.0 || !pixels_per_mm.is_finite() {
+            return self.levels.last().map(|(_, level)| level);
+        }
+        let allowed = LOD_PIXEL_TOLERANCE / pixels_per_mm;
+        self.levels
+            .iter()
+            .rev()
+            .find(|(tolerance, _)| *tolerance <= allowed)
+            .map(|(_, level)| level)
+    }
+}
@@ -1,11 +1,25 @@
+use super::toolpath_buffers::{ToolpathBuffers, ToolpathLod};
 use super::visualizer::{GCodeCommand, Point3D};
 use std::fmt::Write;
+use std::sync::OnceLock;
 use tracing::{debug, trace};
 
+/// Parsed toolpath plus views derived from it.
+///
+/// The columnar [`ToolpathBuffers`] are the source of truth. The command
+/// list and SVG paths are only built the first time they are asked for, so
+/// renderers that draw from the buffers never pay for them.
 #[derive(Debug, Default, Clone)]
 pub struct ToolpathCache {
     content_hash: u64,
-    commands: Vec<GCodeCommand>,
+    buffers: ToolpathBuffers,
+    lod: ToolpathLod,
+    commands: OnceLock<Vec<GCodeCommand>>,
+    svg_paths: OnceLock<SvgPaths>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct SvgPaths {
     cached_path: String,
     cached_rapid_path: String,
     cached_g1_path: String,
@@ -20,72 +34,119 @@ impl ToolpathCache {
     }
 
     pub fn needs_update(&self, new_hash: u64) -> bool {
-        self.content_hash != new_hash || self.commands.is_empty()
+        self.content_hash != new_hash || self.buffers.is_empty()
     }
 
     pub fn update(&mut self, new_hash: u64, commands: Vec<GCodeCommand>) {
+        self.update_with_lines(new_hash, commands, Vec::new());
+    }
+
+    /// Update the cache and record the 1-based source line of each command.
+    pub fn update_with_lines(
+        &mut self,
+        new_hash: u64,
+        commands: Vec<GCodeCommand>,
+        command_lines: Vec<u32>,
+    ) {
+        debug_assert!(command_lines.is_empty() || commands.len() == command_lines.len());
+        let buffers = ToolpathBuffers::from_commands(&commands, &command_lines);
+        let lod = ToolpathLod::build(&buffers);
+        self.update_with_buffers(new_hash, buffers, lod);
+        let _ = self.commands.set(commands);
+    }
+
+    /// Update the cache from already parsed buffers and their
+    /// level-of-detail copies.
+    pub fn update_with_buffers(
+        &mut self,
+        new_hash: u64,
+        buffers: ToolpathBuffers,
+        lod: ToolpathLod,
+    ) {
         self.content_hash = new_hash;
-        self.commands = commands;
-        self.rebuild_paths();
+        self.buffers = buffers;
+        self.lod = lod;
+        self.commands = OnceLock::new();
+        self.svg_paths = OnceLock::new();
+    }
+
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+    }
+
+    pub fn buffers(&self) -> &ToolpathBuffers {
+        &self.buffers
+    }
+
+    pub fn lod(&self) -> &ToolpathLod {
+        &self.lod
     }
 
     pub fn commands(&self) -> &[GCodeCommand] {
-        &self.commands
+        self.commands.get_or_init(|| self.buffers.to_commands())
+    }
+
+    /// 1-based source line numbers, parallel to [`Self::commands`].
+    /// Zero where the cache was filled without line information.
+    pub fn command_lines(&self) -> &[u32] {
+        self.buffers.lines()
     }
 
     pub fn len(&self) -> usize {
-        self.commands.len()
+        self.buffers.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.commands.is_empty()
+        self.buffers.is_empty()
     }
 
     pub fn toolpath_svg(&self) -> &str {
-        &self.cached_path
+        &self.svg_paths().cached_path
     }
 
     pub fn rapid_svg(&self) -> &str {
-        &self.cached_rapid_path
+        &self.svg_paths().cached_rapid_path
     }
 
     pub fn g1_svg(&self) -> &str {
-        &self.cached_g1_path
+        &self.svg_paths().cached_g1_path
     }
 
     pub fn g2_svg(&self) -> &str {
-        &self.cached_g2_path
+        &self.svg_paths().cached_g2_path
     }
 
     pub fn g3_svg(&self) -> &str {
-        &self.cached_g3_path
+        &self.svg_paths().cached_g3_path
     }
 
     pub fn g4_svg(&self) -> &str {
-        &self.cached_g4_path
+        &self.svg_paths().cached_g4_path
     }
 
-    fn rebuild_paths(&mut self) {
-        debug!("Rebuilding SVG paths from {} commands", self.commands.len());
+    fn svg_paths(&self) -> &SvgPaths {
+        self.svg_paths
+            .get_or_init(|| SvgPaths::build(self.commands()))
+    }
+}
 
-        self.cached_path.clear();
-        self.cached_rapid_path.clear();
-        self.cached_g1_path.clear();
-        self.cached_g2_path.clear();
-        self.cached_g3_path.clear();
-        self.cached_g4_path.clear();
+impl SvgPaths {
+    fn build(commands: &[GCodeCommand]) -> Self {
+        debug!("Rebuilding SVG paths from {} commands", commands.len());
 
-        if self.commands.is_empty() {
+        let mut paths = Self::default();
+
+        if commands.is_empty() {
             debug!("No commands to render");
-            return;
+            return paths;
         }
 
-        self.cached_path.reserve(self.commands.len() * 25);
-        self.cached_rapid_path.reserve(self.commands.len() * 10);
-        self.cached_g1_path.reserve(self.commands.len() * 15);
-        self.cached_g2_path.reserve(self.commands.len() * 15);
-        self.cached_g3_path.reserve(self.commands.len() * 15);
-        self.cached_g4_path.reserve(self.commands.len() * 5);
+        paths.cached_path.reserve(commands.len() * 25);
+        paths.cached_rapid_path.reserve(commands.len() * 10);
+        paths.cached_g1_path.reserve(commands.len() * 15);
+        paths.cached_g2_path.reserve(commands.len() * 15);
+        paths.cached_g3_path.reserve(commands.len() * 15);
+        paths.cached_g4_path.reserve(commands.len() * 5);
 
         let mut last_pos: Option<Point3D> = None;
         let mut last_g1_pos: Option<Point3D> = None;
@@ -94,7 +155,7 @@ impl ToolpathCache {
         let mut arc_count = 0;
         let mut invalid_arc_count = 0;
 
-        for (cmd_idx, cmd) in self.commands.iter().enumerate() {
+        for (cmd_idx, cmd) in commands.iter().enumerate() {
             match cmd {
                 GCodeCommand::Move {
                     from,
@@ -104,7 +165,7 @@ impl ToolpathCache {
                 } => {
                     if *rapid {
                         let _ = write!(
-                            self.cached_rapid_path,
+                            paths.cached_rapid_path,
                             "M {:.2} {:.2} L {:.2} {:.2} ",
                             from.x, -from.y, to.x, -to.y
                         );
@@ -114,16 +175,16 @@ impl ToolpathCache {
 
                     // Update combined path
                     if last_pos.is_none() || last_pos != Some(*from) {
-                        let _ = write!(self.cached_path, "M {:.2} {:.2} ", from.x, -from.y);
+                        let _ = write!(paths.cached_path, "M {:.2} {:.2} ", from.x, -from.y);
                     }
-                    let _ = write!(self.cached_path, "L {:.2} {:.2} ", to.x, -to.y);
+                    let _ = write!(paths.cached_path, "L {:.2} {:.2} ", to.x, -to.y);
                     last_pos = Some(*to);
 
                     // Update G1 path
                     if last_g1_pos.is_none() || last_g1_pos != Some(*from) {
-                        let _ = write!(self.cached_g1_path, "M {:.2} {:.2} ", from.x, -from.y);
+                        let _ = write!(paths.cached_g1_path, "M {:.2} {:.2} ", from.x, -from.y);
                     }
-                    let _ = write!(self.cached_g1_path, "L {:.2} {:.2} ", to.x, -to.y);
+                    let _ = write!(paths.cached_g1_path, "L {:.2} {:.2} ", to.x, -to.y);
                     last_g1_pos = Some(*to);
                 }
                 GCodeCommand::Arc {
@@ -138,7 +199,7 @@ impl ToolpathCache {
 
                     // Update combined path
                     if last_pos.is_none() || last_pos != Some(*from) {
-                        let _ = write!(self.cached_path, "M {:.2} {:.2} ", from.x, -from.y);
+                        let _ = write!(paths.cached_path, "M {:.2} {:.2} ", from.x, -from.y);
                     }
 
                     // Skip invalid arcs (radius is zero, NaN, or Infinity)
@@ -167,7 +228,7 @@ impl ToolpathCache {
                         let large_arc = if angle_diff > PI { 1 } else { 0 };
 
                         let _ = write!(
-                            self.cached_path,
+                            paths.cached_path,
                             "A {:.2} {:.2} 0 {} {} {:.2} {:.2} ",
                             radius, radius, large_arc, sweep, to.x, -to.y
                         );
@@ -175,9 +236,9 @@ impl ToolpathCache {
 
                         // Update G2/G3 path
                         let (target_path, last_target_pos) = if *clockwise {
-                            (&mut self.cached_g2_path, &mut last_g2_pos)
+                            (&mut paths.cached_g2_path, &mut last_g2_pos)
                         } else {
-                            (&mut self.cached_g3_path, &mut last_g3_pos)
+                            (&mut paths.cached_g3_path, &mut last_g3_pos)
                         };
 
                         if last_target_pos.is_none() || *last_target_pos != Some(*from) {
@@ -200,14 +261,14 @@ impl ToolpathCache {
                         );
 
                         // Invalid arc - treat as a line segment
-                        let _ = write!(self.cached_path, "L {:.2} {:.2} ", to.x, -to.y);
+                        let _ = write!(paths.cached_path, "L {:.2} {:.2} ", to.x, -to.y);
                         last_pos = Some(*to);
 
                         // Update G2/G3 path
                         let (target_path, last_target_pos) = if *clockwise {
-                            (&mut self.cached_g2_path, &mut last_g2_pos)
+                            (&mut paths.cached_g2_path, &mut last_g2_pos)
                         } else {
-                            (&mut self.cached_g3_path, &mut last_g3_pos)
+                            (&mut paths.cached_g3_path, &mut last_g3_pos)
                         };
 
                         if last_target_pos.is_none() || *last_target_pos != Some(*from) {
@@ -221,7 +282,7 @@ impl ToolpathCache {
                     // Draw a small circle (radius 0.5mm) at dwell position
                     let r = 0.5;
                     let _ = write!(
-                        self.cached_g4_path,
+                        paths.cached_g4_path,
                         "M {:.2} {:.2} m -{:.2} 0 a {:.2} {:.2} 0 1 0 {:.2} 0 a {:.2} {:.2} 0 1 0 -{:.2} 0 ",
                         pos.x, -pos.y, r, r, r, r * 2.0, r, r, r * 2.0
                     );
@@ -231,8 +292,10 @@ impl ToolpathCache {
 
         debug!("Paths rebuilt: {} arcs, {} invalid arcs - total path sizes: toolpath={}, rapid={}, g1={}, g2={}, g3={}, g4={}",
                arc_count, invalid_arc_count,
-               self.cached_path.len(), self.cached_rapid_path.len(),
-               self.cached_g1_path.len(), self.cached_g2_path.len(),
-               self.cached_g3_path.len(), self.cached_g4_path.len());
+               paths.cached_path.len(), paths.cached_rapid_path.len(),
+               paths.cached_g1_path.len(), paths.cached_g2_path.len(),
+               paths.cached_g3_path.len(), paths.cached_g4_path.len());
+
+        paths
     }
 }
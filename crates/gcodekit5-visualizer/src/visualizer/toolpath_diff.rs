@@ -0,0 +1,439 @@
+//! Geometric comparison of two G-code programs
+//!
+//! A line-by-line diff says little when a CAM re-post renumbers or
+//! reformats thousands of lines. [`ToolpathDiff`] instead compares where
+//! the tool cuts: both programs are parsed, their cutting moves are sampled
+//! along the path, and every sample further than the tolerance from the
+//! other program's cutting path is reported. Runs of such samples form
+//! [`DiffRegion`]s — cuts only the modified program makes are
+//! [`DiffKind::Added`], cuts it no longer makes are [`DiffKind::Removed`].
+//!
+//! Rapids are not compared, but the lowest rapid Z of each program is
+//! reported in its [`ToolpathMetrics`] together with run time, depth and
+//! feed rates.
+
+use super::timeline::{ToolpathTimeline, DEFAULT_FEED_RATE_MM_MIN, DEFAULT_RAPID_RATE_MM_MIN};
+use super::toolpath_buffers::{SegmentKind, ToolpathBuffers};
+use super::visualizer::{Point3D, Visualizer};
+
+/// Most grid cells across the index, whatever the tolerance
+const MAX_GRID_CELLS: f32 = 256.0;
+/// Rings of cells searched around a sample before giving up; deviations
+/// beyond this are reported as the search radius
+const SEARCH_RINGS: i32 = 16;
+
+/// Settings for a geometric comparison
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffSettings {
+    /// Largest distance (mm) between the two cutting paths treated as the
+    /// same cut
+    pub tolerance: f32,
+    /// Spacing (mm) of the samples taken along each cutting path
+    pub sample_step: f32,
+    /// Rapid rate (mm/min) used for run time estimates
+    pub rapid_rate_mm_min: f32,
+}
+
+impl Default for DiffSettings {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.05,
+            sample_step: 0.25,
+            rapid_rate_mm_min: DEFAULT_RAPID_RATE_MM_MIN,
+        }
+    }
+}
+
+/// Which program a changed cut belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiffKind {
+    /// Cut only in the modified program
+    Added,
+    /// Cut only in the original program
+    Removed,
+}
+
+/// Contiguous stretch of cutting path with no counterpart in the other
+/// program
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffRegion {
+    pub kind: DiffKind,
+    /// First and last 1-based source line, in the modified program for
+    /// added regions and in the original for removed ones
+    pub first_line: u32,
+    pub last_line: u32,
+    /// Path length (mm)
+    pub length: f32,
+    /// Largest distance (mm) to the other program's cutting path
+    pub max_deviation: f32,
+    /// Lowest Z reached in the region
+    pub min_z: f32,
+    /// Sampled path of the region, for drawing
+    pub points: Vec<Point3D>,
+}
+
+/// Time, depth and feed figures for one program
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ToolpathMetrics {
+    /// Estimated run time (s)
+    pub run_time: f64,
+    pub cut_distance: f64,
+    pub rapid_distance: f64,
+    /// Deepest cutting move
+    pub min_cut_z: Option<f32>,
+    /// Lowest rapid move target
+    pub min_rapid_z: Option<f32>,
+    pub min_feed: f32,
+    pub max_feed: f32,
+    /// Feed rate averaged over cutting distance
+    pub avg_feed: f32,
+}
+
+/// Geometric differences between an original and a modified program
+#[derive(Debug, Clone)]
+pub struct ToolpathDiff {
+    pub settings: DiffSettings,
+    pub original: ToolpathMetrics,
+    pub modified: ToolpathMetrics,
+    /// Changed regions, added ones first, each in program order
+    pub regions: Vec<DiffRegion>,
+    original_cuts: Vec<Vec<Point3D>>,
+    modified_cuts: Vec<Vec<Point3D>>,
+}
+
+impl ToolpathDiff {
+    /// Compare the cutting paths of two programs
+    pub fn compare(original: &str, modified: &str, settings: DiffSettings) -> Self {
+        let (original_metrics, original_chains) = analyse(original, &settings);
+        let (modified_metrics, modified_chains) = analyse(modified, &settings);
+
+        let original_index = SegmentIndex::new(&original_chains, settings.tolerance);
+        let modified_index = SegmentIndex::new(&modified_chains, settings.tolerance);
+
+        let mut regions = find_regions(
+            &modified_chains,
+            &original_index,
+            DiffKind::Added,
+            settings.tolerance,
+        );
+        regions.extend(find_regions(
+            &original_chains,
+            &modified_index,
+            DiffKind::Removed,
+            settings.tolerance,
+        ));
+
+        Self {
+            settings,
+            original: original_metrics,
+            modified: modified_metrics,
+            regions,
+            original_cuts: original_chains.into_iter().map(|c| c.points).collect(),
+            modified_cuts: modified_chains.into_iter().map(|c| c.points).collect(),
+        }
+    }
+
+    /// Whether both programs cut the same path within the tolerance
+    pub fn is_equivalent(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Regions of one kind
+    pub fn regions_of(&self, kind: DiffKind) -> impl Iterator<Item = &DiffRegion> {
+        self.regions.iter().filter(move |r| r.kind == kind)
+    }
+
+    /// Total path length (mm) of the regions of one kind
+    pub fn changed_length(&self, kind: DiffKind) -> f32 {
+        self.regions_of(kind).map(|r| r.length).sum()
+    }
+
+    /// Change in estimated run time (s); positive when the modified
+    /// program takes longer
+    pub fn time_change(&self) -> f64 {
+        self.modified.run_time - self.original.run_time
+    }
+
+    /// Change in deepest cut (mm); negative when the modified program cuts
+    /// deeper
+    pub fn depth_change(&self) -> Option<f32> {
+        Some(self.modified.min_cut_z? - self.original.min_cut_z?)
+    }
+
+    /// Change in average feed rate (mm/min)
+    pub fn feed_change(&self) -> f32 {
+        self.modified.avg_feed - self.original.avg_feed
+    }
+
+    /// Whether the modified program cuts anywhere the original did not, or
+    /// deeper than it
+    pub fn cuts_new_material(&self) -> bool {
+        self.regions_of(DiffKind::Added).next().is_some()
+            || self
+                .depth_change()
+                .is_some_and(|d| d < -self.settings.tolerance)
+    }
+
+    /// Sampled cutting paths of the original program, one per run of
+    /// cutting moves between rapids
+    pub fn original_cuts(&self) -> &[Vec<Point3D>] {
+        &self.original_cuts
+    }
+
+    /// Sampled cutting paths of the modified program
+    pub fn modified_cuts(&self) -> &[Vec<Point3D>] {
+        &self.modified_cuts
+    }
+}
+
+/// Run of cutting moves between rapids, sampled along the path
+#[derive(Debug, Default)]
+struct Chain {
+    points: Vec<Point3D>,
+    /// Source line of each point
+    lines: Vec<u32>,
+}
+
+/// Parse a program into metrics and sampled cutting chains
+fn analyse(gcode: &str, settings: &DiffSettings) -> (ToolpathMetrics, Vec<Chain>) {
+    let mut vis = Visualizer::new();
+    vis.set_rapid_rate(settings.rapid_rate_mm_min);
+    vis.parse_gcode(gcode);
+    let buffers = vis.buffers();
+    let timeline = vis.timeline();
+    let step = settings.sample_step.max(settings.tolerance * 0.5).max(1e-3);
+
+    let mut metrics = ToolpathMetrics {
+        run_time: timeline.total_time(),
+        ..ToolpathMetrics::default()
+    };
+    let mut chains = Vec::new();
+    let mut chain = Chain::default();
+    let mut weighted_feed = 0.0;
+    let mut feeds = (f32::INFINITY, 0.0f32);
+
+    for index in 0..buffers.len() {
+        let length = timeline.segment_length(buffers, index);
+        let to = buffers.end_point(index);
+        match buffers.kind(index) {
+            SegmentKind::Rapid | SegmentKind::Dwell => {
+                if buffers.kind(index) == SegmentKind::Rapid {
+                    metrics.rapid_distance += length as f64;
+                    metrics.min_rapid_z = Some(metrics.min_rapid_z.map_or(to.z, |z| z.min(to.z)));
+                }
+                if !chain.points.is_empty() {
+                    chains.push(std::mem::take(&mut chain));
+                }
+            }
+            SegmentKind::Linear | SegmentKind::ArcCw | SegmentKind::ArcCcw => {
+                metrics.cut_distance += length as f64;
+                metrics.min_cut_z = Some(metrics.min_cut_z.map_or(to.z, |z| z.min(to.z)));
+                let feed = buffers
+                    .feed_rate(index)
+                    .filter(|f| *f > 0.0)
+                    .unwrap_or(DEFAULT_FEED_RATE_MM_MIN);
+                feeds = (feeds.0.min(feed), feeds.1.max(feed));
+                weighted_feed += feed as f64 * length as f64;
+
+                sample_segment(buffers, timeline, index, length, step, &mut chain);
+            }
+        }
+    }
+    if !chain.points.is_empty() {
+        chains.push(chain);
+    }
+
+    if metrics.cut_distance > 0.0 {
+        metrics.min_feed = feeds.0;
+        metrics.max_feed = feeds.1;
+        metrics.avg_feed = (weighted_feed / metrics.cut_distance) as f32;
+    }
+    (metrics, chains)
+}
+
+/// Append points along cutting segment `index` to `chain`
+fn sample_segment(
+    buffers: &ToolpathBuffers,
+    timeline: &ToolpathTimeline,
+    index: usize,
+    length: f32,
+    step: f32,
+    chain: &mut Chain,
+) {
+    let line = buffers.lines()[index];
+    if chain.points.is_empty() {
+        chain.points.push(buffers.start_point(index));
+        chain.lines.push(line);
+    }
+    let count = ((length / step).ceil() as usize).max(1);
+    for i in 1..=count {
+        let t = i as f32 / count as f32;
+        chain
+            .points
+            .push(timeline.point_on_segment(buffers, index, t));
+        chain.lines.push(line);
+    }
+}
+
+/// Walk the chains of one program and collect runs of samples further than
+/// `tolerance` from the other program
+fn find_regions(
+    chains: &[Chain],
+    other: &SegmentIndex,
+    kind: DiffKind,
+    tolerance: f32,
+) -> Vec<DiffRegion> {
+    let mut regions = Vec::new();
+    for chain in chains {
+        let mut current: Option<DiffRegion> = None;
+        for (i, &point) in chain.points.iter().enumerate() {
+            let deviation = other.distance(point);
+            if deviation <= tolerance {
+                regions.extend(current.take());
+                continue;
+            }
+            let line = chain.lines[i];
+            match current.as_mut() {
+                Some(region) => {
+                    let previous = *region.points.last().unwrap_or(&point);
+                    region.length += distance(previous, point);
+                    region.first_line = region.first_line.min(line);
+                    region.last_line = region.last_line.max(line);
+                    region.max_deviation = region.max_deviation.max(deviation);
+                    region.min_z = region.min_z.min(point.z);
+                    region.points.push(point);
+                }
+                None => {
+                    current = Some(DiffRegion {
+                        kind,
+                        first_line: line,
+                        last_line: line,
+                        length: 0.0,
+                        max_deviation: deviation,
+                        min_z: point.z,
+                        points: vec![point],
+                    })
+                }
+            }
+        }
+        regions.extend(current);
+    }
+    regions
+}
+
+/// Uniform XY grid of path segments for nearest-distance queries
+struct SegmentIndex {
+    segments: Vec<(Point3D, Point3D)>,
+    cells: Vec<Vec<u32>>,
+    origin: (f32, f32),
+    cell_size: f32,
+    columns: i32,
+    rows: i32,
+}
+
+impl SegmentIndex {
+    fn new(chains: &[Chain], tolerance: f32) -> Self {
+        let mut segments = Vec::new();
+        for chain in chains {
+            match chain.points.as_slice() {
+                [single] => segments.push((*single, *single)),
+                points => segments.extend(points.windows(2).map(|w| (w[0], w[1]))),
+            }
+        }
+
+        let (mut min, mut max) = ((f32::MAX, f32::MAX), (f32::MIN, f32::MIN));
+        for (a, b) in &segments {
+            for p in [a, b] {
+                min = (min.0.min(p.x), min.1.min(p.y));
+                max = (max.0.max(p.x), max.1.max(p.y));
+            }
+        }
+        if segments.is_empty() {
+            min = (0.0, 0.0);
+            max = (0.0, 0.0);
+        }
+        let extent = (max.0 - min.0).max(max.1 - min.1);
+        let cell_size = (tolerance * 4.0).max(extent / MAX_GRID_CELLS).max(1e-3);
+        let columns = ((max.0 - min.0) / cell_size) as i32 + 1;
+        let rows = ((max.1 - min.1) / cell_size) as i32 + 1;
+
+        let mut index = Self {
+            segments,
+            cells: vec![Vec::new(); (columns * rows) as usize],
+            origin: min,
+            cell_size,
+            columns,
+            rows,
+        };
+        for (i, (a, b)) in index.segments.iter().enumerate() {
+            let (c0, r0) = index.cell_of(a.x.min(b.x), a.y.min(b.y));
+            let (c1, r1) = index.cell_of(a.x.max(b.x), a.y.max(b.y));
+            for r in r0..=r1 {
+                for c in c0..=c1 {
+                    index.cells[(r * columns + c) as usize].push(i as u32);
+                }
+            }
+        }
+        index
+    }
+
+    /// Cell containing an XY position, clamped to the grid
+    fn cell_of(&self, x: f32, y: f32) -> (i32, i32) {
+        let c = ((x - self.origin.0) / self.cell_size).floor() as i32;
+        let r = ((y - self.origin.1) / self.cell_size).floor() as i32;
+        (c.clamp(0, self.columns - 1), r.clamp(0, self.rows - 1))
+    }
+
+    /// Distance from `point` to the nearest indexed segment, searching
+    /// outwards ring by ring. Gives the search radius when nothing is
+    /// within it.
+    fn distance(&self, point: Point3D) -> f32 {
+        let radius = SEARCH_RINGS as f32 * self.cell_size;
+        if self.segments.is_empty() {
+            return radius;
+        }
+        let (x, y) = (
+            ((point.x - self.origin.0) / self.cell_size).floor() as i32,
+            ((point.y - self.origin.1) / self.cell_size).floor() as i32,
+        );
+        let mut best = f32::INFINITY;
+        for ring in 0..=SEARCH_RINGS {
+            // Anything in this ring or beyond is at least this far away
+            if (ring - 1) as f32 * self.cell_size >= best {
+                break;
+            }
+            for r in y - ring..=y + ring {
+                for c in x - ring..=x + ring {
+                    let on_ring = (r - y).abs() == ring || (c - x).abs() == ring;
+                    if !on_ring || r < 0 || c < 0 || r >= self.rows || c >= self.columns {
+                        continue;
+                    }
+                    for &i in &self.cells[(r * self.columns + c) as usize] {
+                        let (a, b) = self.segments[i as usize];
+                        best = best.min(point_segment_distance(point, a, b));
+                    }
+                }
+            }
+        }
+        best.min(radius)
+    }
+}
+
+fn distance(a: Point3D, b: Point3D) -> f32 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2) + (b.z - a.z).powi(2)).sqrt()
+}
+
+fn point_segment_distance(p: Point3D, a: Point3D, b: Point3D) -> f32 {
+    let ab = (b.x - a.x, b.y - a.y, b.z - a.z);
+    let ap = (p.x - a.x, p.y - a.y, p.z - a.z);
+    let length_sq = ab.0 * ab.0 + ab.1 * ab.1 + ab.2 * ab.2;
+    let t = if length_sq > 0.0 {
+        ((ap.0 * ab.0 + ap.1 * ab.1 + ap.2 * ab.2) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    distance(
+        p,
+        Point3D::new(a.x + ab.0 * t, a.y + ab.1 * t, a.z + ab.2 * t),
+    )
+}
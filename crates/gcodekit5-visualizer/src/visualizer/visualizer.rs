@@ -1,13 +1,14 @@
 //! 2D G-Code Visualizer
 //! Parses G-Code toolpaths for canvas-based visualization
 
+use super::streaming::{content_hash, GcodeStreamParser, ParsedToolpath};
+use super::timeline::{ToolpathFilter, ToolpathTimeline, DEFAULT_RAPID_RATE_MM_MIN};
+use super::toolpath_buffers::{ToolpathBuffers, ToolpathLod};
 use super::toolpath_cache::ToolpathCache;
 use super::viewport::{Bounds, ViewportTransform};
 use gcodekit5_core::constants as core_constants;
-use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
-use tracing::{debug, trace};
+use tracing::debug;
 use gcodekit5_designer::toolpath::{Toolpath};
 use std::sync::{mpsc, Arc};
 
@@ -138,6 +139,11 @@ pub struct Visualizer {
     viewport: ViewportTransform,
     /// Dirty flag — set when vertex data needs regeneration
     dirty: bool,
+    /// Line and Z range of the segments to show
+    filter: ToolpathFilter,
+    /// Rapid rate (mm/min) used for the timeline estimate
+    rapid_rate: f32,
+    timeline: ToolpathTimeline,
 
     toolpath_receiver: Arc<mpsc::Receiver<Vec<Toolpath>>>,
     toolpath_sender: mpsc::Sender<Vec<Toolpath>>,
@@ -166,6 +172,9 @@ impl Visualizer {
             toolpath_cache: ToolpathCache::new(),
             viewport: ViewportTransform::new(CANVAS_PADDING),
             dirty: true,
+            filter: ToolpathFilter::default(),
+            rapid_rate: DEFAULT_RAPID_RATE_MM_MIN,
+            timeline: ToolpathTimeline::default(),
 
             toolpath_receiver: Arc::new(receiver),
             toolpath_sender: sender,
@@ -225,31 +234,11 @@ impl Visualizer {
         self.scale_factor
     }
 
-    /// Extract G-code command number from line (e.g., "G01 X10" -> Some(1))
-    fn extract_gcode_num(line: &str) -> Option<u32> {
-        if !line.starts_with('G') {
-            return None;
-        }
-        let after_g = &line[1..];
-        // Find end of number
-        let end_idx = after_g
-        .find(|c: char| !c.is_ascii_digit())
-        .unwrap_or(after_g.len());
-
-        if end_idx == 0 {
-            return None;
-        }
-
-        after_g[..end_idx].parse::<u32>().ok()
-    }
-
     /// Parse G-Code and extract movement commands
     pub fn parse_gcode(&mut self, gcode: &str) {
         debug!("Starting G-code parse, input size: {} bytes", gcode.len());
 
-        let mut hasher = DefaultHasher::new();
-        gcode.hash(&mut hasher);
-        let new_hash = hasher.finish();
+        let new_hash = content_hash(gcode.lines());
 
         if !self.toolpath_cache.needs_update(new_hash) {
             debug!("G-code hash unchanged, skipping parse");
@@ -258,92 +247,41 @@ impl Visualizer {
 
         debug!("Parsing new G-code (hash: {})", new_hash);
 
-        let mut commands = Vec::new();
-        let mut current_pos = Point3D::new(0.0, 0.0, 0.0);
-        self.current_intensity = 0.0;
-        let mut bounds = Bounds::new();
-        let mut _g0_count = 0;
-        let mut _g1_count = 0;
-        let mut _g2_count = 0;
-        let mut _g3_count = 0;
-
-        for (line_num, line) in gcode.lines().enumerate() {
-            let line = line.trim();
-
-            if line.is_empty() || line.starts_with(';') || line.starts_with('(') {
-                continue;
-            }
+        let mut parser = GcodeStreamParser::new();
+        for line in gcode.lines() {
+            parser.parse_line(line);
+        }
+        self.apply_with_hash(new_hash, parser.finish());
+    }
 
-            if let Some(gcode_num) = Self::extract_gcode_num(line) {
-                trace!("Line {}: G{} command", line_num, gcode_num);
-                match gcode_num {
-                    0 => {
-                        _g0_count += 1;
-                        Self::parse_linear_move(
-                            &mut commands,
-                            line,
-                            &mut current_pos,
-                            &mut self.current_intensity,
-                            &mut bounds,
-                            true,
-                        );
-                    }
-                    1 => {
-                        _g1_count += 1;
-                        Self::parse_linear_move(
-                            &mut commands,
-                            line,
-                            &mut current_pos,
-                            &mut self.current_intensity,
-                            &mut bounds,
-                            false,
-                        );
-                    }
-                    2 => {
-                        _g2_count += 1;
-                        Self::parse_arc_move(
-                            &mut commands,
-                            line,
-                            &mut current_pos,
-                            &mut self.current_intensity,
-                            &mut bounds,
-                            true,
-                        );
-                    }
-                    3 => {
-                        _g3_count += 1;
-                        Self::parse_arc_move(
-                            &mut commands,
-                            line,
-                            &mut current_pos,
-                            &mut self.current_intensity,
-                            &mut bounds,
-                            false,
-                        );
-                    }
-                    4 => {
-                        Self::parse_dwell(&mut commands, line, &mut current_pos);
-                    }
-                    _ => {}
-                }
-            }
+    /// Load a toolpath parsed off the UI thread, e.g. by a
+    /// [`ParseJob`](super::streaming::ParseJob). Returns false when the
+    /// same content is already loaded.
+    pub fn apply_parsed(&mut self, parsed: ParsedToolpath) -> bool {
+        if !self.toolpath_cache.needs_update(parsed.content_hash) {
+            debug!("Parsed toolpath unchanged, keeping current one");
+            return false;
         }
+        self.apply_with_hash(parsed.content_hash, parsed);
+        true
+    }
 
+    fn apply_with_hash(&mut self, hash: u64, parsed: ParsedToolpath) {
         debug!(
-            "Parse complete: G0={}, G1={}, G2={}, G3={}, total commands={}",
-            _g0_count,
-            _g1_count,
-            _g2_count,
-            _g3_count,
-            commands.len()
+            "Parse complete: {} lines, total commands={}",
+            parsed.lines_read,
+            parsed.buffers.len()
         );
 
         (
             self.min_x, self.max_x, self.min_y, self.max_y, self.min_z, self.max_z,
-        ) = bounds.finalize_with_padding(BOUNDS_PADDING_FACTOR);
-        self.current_pos = current_pos;
+        ) = parsed.buffers.bounds().finalize_with_padding(BOUNDS_PADDING_FACTOR);
+        self.current_pos = parsed.final_position;
+        self.current_intensity = parsed.final_intensity;
 
-        self.toolpath_cache.update(new_hash, commands);
+        self.timeline = ToolpathTimeline::build(&parsed.buffers, self.rapid_rate);
+        self.toolpath_cache
+            .update_with_buffers(hash, parsed.buffers, parsed.lod);
         self.dirty = true;
         debug!(
             "Bounds: x=[{:.2}, {:.2}], y=[{:.2}, {:.2}], z=[{:.2}, {:.2}]",
@@ -365,179 +303,8 @@ impl Visualizer {
         )
     }
 
-    fn parse_dwell(commands: &mut Vec<GCodeCommand>, line: &str, current_pos: &mut Point3D) {
-        let mut duration = 0.0;
-        for part in line.split_whitespace() {
-            if part.len() < 2 {
-                continue;
-            }
-            let Some(first_char) = part.chars().next() else {
-                continue;
-            };
-            match first_char {
-                'P' | 'X' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        duration = val;
-                    }
-                }
-                _ => {}
-            }
-        }
-        commands.push(GCodeCommand::Dwell {
-            pos: *current_pos,
-            duration,
-        });
-    }
-
-    fn parse_linear_move(
-        commands: &mut Vec<GCodeCommand>,
-        line: &str,
-        current_pos: &mut Point3D,
-        current_intensity: &mut f32,
-        bounds: &mut Bounds,
-        is_rapid: bool,
-    ) {
-        // Extract X and Y directly without HashMap allocation
-        let mut new_x = current_pos.x;
-        let mut new_y = current_pos.y;
-        let mut new_z = current_pos.z;
-        let mut x_found = false;
-        let mut y_found = false;
-        let mut z_found = false;
-
-        for part in line.split_whitespace() {
-            if part.len() < 2 {
-                continue;
-            }
-            let Some(first_char) = part.chars().next() else {
-                continue;
-            };
-            match first_char {
-                'X' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        new_x = val;
-                        x_found = true;
-                    }
-                }
-                'Y' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        new_y = val;
-                        y_found = true;
-                    }
-                }
-                'Z' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        new_z = val;
-                        z_found = true;
-                    }
-                }
-                'S' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        *current_intensity = val;
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        // Only create a command if at least one axis changed
-        if x_found || y_found || z_found {
-            let to = Point3D::new(new_x, new_y, new_z);
-            commands.push(GCodeCommand::Move {
-                from: *current_pos,
-                to,
-                rapid: is_rapid,
-                intensity: Some(*current_intensity),
-            });
-
-            bounds.update(current_pos.x, current_pos.y, current_pos.z);
-            bounds.update(new_x, new_y, new_z);
-            *current_pos = to;
-        }
-    }
-
-    fn parse_arc_move(
-        commands: &mut Vec<GCodeCommand>,
-        line: &str,
-        current_pos: &mut Point3D,
-        current_intensity: &mut f32,
-        bounds: &mut Bounds,
-        clockwise: bool,
-    ) {
-        let mut new_x = None;
-        let mut new_y = None;
-        let mut new_z = None;
-        let mut offset_i = None;
-        let mut offset_j = None;
-
-        for part in line.split_whitespace() {
-            if part.len() < 2 {
-                continue;
-            }
-            let Some(first_char) = part.chars().next() else {
-                continue;
-            };
-            match first_char {
-                'X' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        new_x = Some(val);
-                    }
-                }
-                'Y' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        new_y = Some(val);
-                    }
-                }
-                'Z' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        new_z = Some(val);
-                    }
-                }
-                'I' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        offset_i = Some(val);
-                    }
-                }
-                'J' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        offset_j = Some(val);
-                    }
-                }
-                'S' => {
-                    if let Ok(val) = part[1..].parse::<f32>() {
-                        *current_intensity = val;
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        if let (Some(x), Some(y), Some(i), Some(j)) = (new_x, new_y, offset_i, offset_j) {
-            let z = new_z.unwrap_or(current_pos.z);
-            let to = Point3D::new(x, y, z);
-            let center = Point3D::new(current_pos.x + i, current_pos.y + j, current_pos.z);
-
-            let radius =
-            ((current_pos.x - center.x).powi(2) + (current_pos.y - center.y).powi(2)).sqrt();
-            trace!("Arc: from=({:.2},{:.2}), to=({:.2},{:.2}), center=({:.2},{:.2}), radius={:.4}, cw={}", 
-                   current_pos.x, current_pos.y, x, y, center.x, center.y, radius, clockwise);
-
-            commands.push(GCodeCommand::Arc {
-                from: *current_pos,
-                to,
-                center,
-                clockwise,
-                intensity: Some(*current_intensity),
-            });
-
-            bounds.update(current_pos.x, current_pos.y, current_pos.z);
-            bounds.update(x, y, z);
-            *current_pos = to;
-        }
-    }
-
     /// Extract multiple parameters from G-Code line
-    // Deprecated: Use direct parsing in parse_linear_move/parse_arc_move instead
+    // Deprecated: Use GcodeStreamParser instead
     #[allow(dead_code)]
     fn extract_params(line: &str, param_names: &[char]) -> HashMap<char, f32> {
         let mut params = HashMap::new();
@@ -597,6 +364,61 @@ impl Visualizer {
         self.toolpath_cache.commands()
     }
 
+    /// 1-based source line number of each entry in [`Self::commands`]
+    pub fn command_lines(&self) -> &[u32] {
+        self.toolpath_cache.command_lines()
+    }
+
+    /// Parsed toolpath in its compact columnar form
+    pub fn buffers(&self) -> &ToolpathBuffers {
+        self.toolpath_cache.buffers()
+    }
+
+    /// Simplified copies of the toolpath for drawing at low zoom
+    pub fn lod(&self) -> &ToolpathLod {
+        self.toolpath_cache.lod()
+    }
+
+    /// Geometry to draw at `pixels_per_mm`: the coarsest level of detail
+    /// that stays below half a pixel of error, or the full toolpath
+    pub fn lod_for_scale(&self, pixels_per_mm: f32) -> &ToolpathBuffers {
+        self.toolpath_cache
+            .lod()
+            .select(pixels_per_mm)
+            .unwrap_or_else(|| self.toolpath_cache.buffers())
+    }
+
+    /// Limit the shown segments to a line and/or Z range
+    pub fn set_filter(&mut self, filter: ToolpathFilter) {
+        if filter != self.filter {
+            self.filter = filter;
+            self.dirty = true;
+        }
+    }
+
+    /// Line and Z range of the segments to show
+    pub fn filter(&self) -> &ToolpathFilter {
+        &self.filter
+    }
+
+    /// Set the rapid rate (mm/min) used to estimate the timeline
+    pub fn set_rapid_rate(&mut self, rapid_rate_mm_min: f32) {
+        if rapid_rate_mm_min > 0.0 && rapid_rate_mm_min != self.rapid_rate {
+            self.rapid_rate = rapid_rate_mm_min;
+            self.timeline = ToolpathTimeline::build(self.buffers(), self.rapid_rate);
+        }
+    }
+
+    /// Estimated timing of the full toolpath
+    pub fn timeline(&self) -> &ToolpathTimeline {
+        &self.timeline
+    }
+
+    /// Tool position `time` seconds into the job
+    pub fn position_at_time(&self, time: f64) -> Point3D {
+        self.timeline.position_at(self.buffers(), time)
+    }
+
     /// Increase zoom by 10%
     pub fn zoom_in(&mut self) {
         self.zoom_scale = (self.zoom_scale * ZOOM_STEP).min(MAX_ZOOM);
@@ -649,7 +471,7 @@ impl Visualizer {
         let mut has_content = false;
 
         // Collect bounds of all cutting moves
-        for cmd in self.toolpath_cache.commands() {
+        for cmd in self.toolpath_cache.buffers().iter() {
             match cmd {
                 GCodeCommand::Move {
                     from, to, rapid, ..
@@ -722,10 +544,10 @@ impl Visualizer {
         let mut bounds = Bounds::new();
         let mut has_cutting_moves = false;
 
-        for cmd in self.toolpath_cache.commands() {
+        for cmd in self.toolpath_cache.buffers().iter() {
             match cmd {
                 GCodeCommand::Move { to, rapid, .. } => {
-                    if !*rapid {
+                    if !rapid {
                         bounds.update(to.x, to.y, to.z);
                         has_cutting_moves = true;
                     }
@@ -757,10 +579,10 @@ impl Visualizer {
 
     /// Get the start point of the toolpath (for debugging/testing)
     pub fn get_start_point(&self) -> Option<Point3D> {
-        self.toolpath_cache.commands().first().map(|cmd| match cmd {
-            GCodeCommand::Move { from, .. } => *from,
-            GCodeCommand::Arc { from, .. } => *from,
-            GCodeCommand::Dwell { pos, .. } => *pos,
+        self.toolpath_cache.buffers().iter().next().map(|cmd| match cmd {
+            GCodeCommand::Move { from, .. } => from,
+            GCodeCommand::Arc { from, .. } => from,
+            GCodeCommand::Dwell { pos, .. } => pos,
         })
     }
 
@@ -0,0 +1,174 @@
+// Integration tests for the arc fitting processor
+
+use std::sync::Arc;
+
+use gcodekit5_visualizer::{
+    ArcFitter, CommandProcessor, GcodeCommand, GcodeState, ProcessorPipeline, ProcessorRegistry,
+};
+
+fn run(fitter: &Arc<ArcFitter>, lines: &[String]) -> Vec<String> {
+    let mut pipeline = ProcessorPipeline::new();
+    pipeline.register(fitter.clone());
+    let commands: Vec<GcodeCommand> = lines.iter().map(GcodeCommand::new).collect();
+    let mut state = GcodeState::new();
+    pipeline
+        .process_commands(&commands, &mut state)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.command)
+        .collect()
+}
+
+/// Polyline approximating an arc around the origin, angles in degrees
+fn arc_lines(radius: f64, from: f64, to: f64, steps: usize, z: (f64, f64)) -> Vec<String> {
+    (1..=steps)
+        .map(|i| {
+            let t = i as f64 / steps as f64;
+            let angle = (from + (to - from) * t).to_radians();
+            format!(
+                "G1 X{:.4} Y{:.4} Z{:.4}",
+                radius * angle.cos(),
+                radius * angle.sin(),
+                z.0 + (z.1 - z.0) * t
+            )
+        })
+        .collect()
+}
+
+fn word(line: &str, letter: char) -> Option<f64> {
+    line.split_whitespace()
+        .find(|w| w.starts_with(letter))
+        .and_then(|w| w[1..].parse().ok())
+}
+
+#[test]
+fn test_fits_circle_segments() {
+    let fitter = Arc::new(ArcFitter::new());
+    let mut lines = vec!["G90 G21 G17".to_string(), "G0 X10 Y0 Z0".to_string()];
+    lines.extend(arc_lines(10.0, 0.0, 180.0, 90, (0.0, 0.0)));
+    lines.push("G0 Z5".to_string());
+
+    let out = run(&fitter, &lines);
+    let arcs: Vec<&String> = out.iter().filter(|l| l.starts_with("G3")).collect();
+    assert!(!arcs.is_empty() && arcs.len() <= 3, "{:?}", out);
+    assert!(out.iter().all(|l| !l.starts_with("G1")));
+    assert_eq!(out.last().unwrap(), "G0 Z5");
+
+    // First arc is centred on the origin
+    assert!((word(arcs[0], 'I').unwrap() + 10.0).abs() < 0.01);
+    assert!(word(arcs[0], 'J').unwrap().abs() < 0.01);
+
+    let stats = fitter.stats();
+    assert_eq!(stats.lines_in, 93);
+    assert_eq!(stats.segments_replaced, 90);
+    assert_eq!(stats.reduction(), 90 - stats.arcs_created);
+    assert!(stats.reduction_percent() > 90.0);
+}
+
+#[test]
+fn test_clockwise_and_helical() {
+    let fitter = Arc::new(ArcFitter::new());
+    let mut lines = vec!["G0 X0 Y10 Z0".to_string()];
+    lines.extend(arc_lines(10.0, 90.0, 0.0, 30, (0.0, -1.0)));
+
+    let out = run(&fitter, &lines);
+    assert_eq!(out.len(), 2, "{:?}", out);
+    assert!(out[1].starts_with("G2 X10 Y0 Z-1"), "{}", out[1]);
+}
+
+#[test]
+fn test_keeps_straight_and_corner_paths() {
+    let fitter = Arc::new(ArcFitter::new());
+    let lines: Vec<String> = [
+        "G0 X0 Y0 Z0",
+        "G1 X1 Y0 F100",
+        "G1 X2 Y0",
+        "G1 X3 Y0",
+        "G1 X4 Y0",
+        "G1 X4 Y1",
+        "G1 X4 Y2",
+        "G1 X0 Y2",
+        "G1 X0 Y0",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    let out = run(&fitter, &lines);
+    assert_eq!(out, lines);
+    assert_eq!(fitter.stats().reduction(), 0);
+}
+
+#[test]
+fn test_tolerance_is_respected() {
+    // A square-ish polygon deviates from its circumcircle by more than 0.01
+    let fitter = Arc::new(ArcFitter::with_tolerance(0.01));
+    let mut lines = vec!["G0 X10 Y0 Z0".to_string()];
+    lines.extend(arc_lines(10.0, 0.0, 180.0, 6, (0.0, 0.0)));
+    assert_eq!(run(&fitter, &lines).len(), 7);
+
+    let loose = Arc::new(ArcFitter::with_tolerance(2.0));
+    assert_eq!(run(&loose, &lines).len(), 2);
+}
+
+#[test]
+fn test_plane_selection() {
+    // XZ arc in G18 uses I and K offsets
+    let fitter = Arc::new(ArcFitter::new());
+    let mut lines = vec!["G18".to_string(), "G0 X10 Y0 Z0".to_string()];
+    lines.extend((1..=20).map(|i| {
+        let angle = (i as f64 * 4.5_f64).to_radians();
+        format!("G1 X{:.4} Z{:.4}", 10.0 * angle.cos(), 10.0 * angle.sin())
+    }));
+
+    let out = run(&fitter, &lines);
+    assert_eq!(out.len(), 3, "{:?}", out);
+    assert!(out[2].contains(" I") && out[2].contains(" K"));
+    assert!(!out[2].contains(" J"));
+    // X to Z viewed from +Y is clockwise in G18
+    assert!(out[2].starts_with("G2"));
+}
+
+#[test]
+fn test_modal_moves_after_arc_get_motion_word() {
+    let fitter = Arc::new(ArcFitter::new());
+    let mut lines = vec!["G0 X10 Y0 Z0".to_string()];
+    lines.extend(arc_lines(10.0, 0.0, 90.0, 20, (0.0, 0.0)));
+    lines.push("X5 Y5 (modal G1)".to_string());
+
+    let out = run(&fitter, &lines);
+    assert_eq!(out.last().unwrap(), "G1 X5 Y5 (modal G1)");
+}
+
+#[test]
+fn test_relative_mode_and_finish_flush() {
+    let fitter = Arc::new(ArcFitter::new());
+    let state = GcodeState::new();
+    fitter
+        .process(&GcodeCommand::new("G0 X10 Y0 Z0"), &state)
+        .unwrap();
+    let mut held = 0;
+    for line in arc_lines(10.0, 0.0, 90.0, 30, (0.0, 0.0)) {
+        held += fitter
+            .process(&GcodeCommand::new(line), &state)
+            .unwrap()
+            .len();
+    }
+    assert_eq!(held, 0);
+    let flushed = fitter.finish(&state).unwrap();
+    assert_eq!(flushed.len(), 1);
+
+    // Relative moves are never fitted
+    let mut lines = vec!["G0 X0 Y0 Z0".to_string(), "G91".to_string()];
+    lines.extend((0..10).map(|_| "G1 X1 Y0.1".to_string()));
+    let relative = Arc::new(ArcFitter::new());
+    assert_eq!(run(&relative, &lines).len(), 12);
+}
+
+#[test]
+fn test_registry() {
+    let mut registry = ProcessorRegistry::new();
+    registry.register("arc_fitter", || Arc::new(ArcFitter::new()));
+    let pipeline = registry.create_pipeline(&["arc_fitter"]).unwrap();
+    assert_eq!(pipeline.list_processors()[0].0, "arc_fitter");
+}
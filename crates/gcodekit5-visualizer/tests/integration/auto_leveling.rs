@@ -0,0 +1,139 @@
+// Integration tests for height-map auto-leveling
+
+use gcodekit5_visualizer::{AutoLeveler, HeightMap, HeightPoint, ProbeGrid, ProbeMesh};
+
+/// Surface tilted along X: z = x / 100
+fn tilted_mesh() -> ProbeMesh {
+    let grid = ProbeGrid::new((0.0, 0.0), (100.0, 50.0), 3, 2);
+    grid.build_mesh(&[vec![0.0, 0.5, 1.0], vec![0.0, 0.5, 1.0]])
+        .unwrap()
+}
+
+fn z_values(gcode: &str) -> Vec<f64> {
+    gcode
+        .lines()
+        .filter_map(|line| line.split_whitespace().find(|w| w.starts_with('Z')))
+        .map(|w| w[1..].parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_height_map_bilinear() {
+    let mesh = tilted_mesh();
+    let map = HeightMap::from_mesh(&mesh).unwrap();
+    assert!((map.z_at(25.0, 10.0) - 0.25).abs() < 1e-9);
+    assert!((map.z_at(75.0, 40.0) - 0.75).abs() < 1e-9);
+    // Clamped outside the grid
+    assert!((map.z_at(150.0, -10.0) - 1.0).abs() < 1e-9);
+    assert!((mesh.get_z_offset(50.0, 25.0).unwrap() - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_irregular_mesh_is_rejected() {
+    let mut mesh = tilted_mesh();
+    mesh.points.pop();
+    assert!(HeightMap::from_mesh(&mesh).is_none());
+    assert!(AutoLeveler::new(&mesh).is_err());
+}
+
+#[test]
+fn test_sparse_mesh_normalizes() {
+    let mut mesh = ProbeMesh::new(10.0, 10.0);
+    mesh.add_point(HeightPoint {
+        x: 0.0,
+        y: 0.0,
+        z: 0.2,
+    });
+    mesh.add_point(HeightPoint {
+        x: 10.0,
+        y: 10.0,
+        z: 0.4,
+    });
+    assert!((mesh.get_z_offset(0.0, 0.0).unwrap() - 0.3).abs() < 1e-9);
+
+    mesh.normalize_to(0.0, 0.0);
+    assert!((mesh.z_min + 0.1).abs() < 1e-9);
+    assert!((mesh.z_max - 0.1).abs() < 1e-9);
+}
+
+#[test]
+fn test_probe_grid_gcode() {
+    let grid = ProbeGrid::with_spacing((0.0, 0.0), (20.0, 10.0), 10.0).with_probe(3.0, -2.0, 40.0);
+    assert_eq!((grid.cols, grid.rows), (3, 2));
+    assert_eq!(grid.points()[1], (10.0, 0.0));
+    assert_eq!(grid.points()[3], (0.0, 10.0));
+
+    let gcode = grid.to_gcode();
+    assert_eq!(gcode.matches("G38.2 Z-2.000 F40.0").count(), 6);
+    assert!(gcode.contains("G0 X20.000 Y10.000"));
+    assert!(grid.build_mesh(&[vec![0.0; 3]]).is_err());
+}
+
+#[test]
+fn test_probe_grid_for_program() {
+    let grid = ProbeGrid::for_program("G0 X0 Y0 Z1\nG1 Z-1 F100\nG1 X40 Y20\n", 10.0, 5.0).unwrap();
+    assert!((grid.x_min + 5.0).abs() < 1e-6);
+    assert!((grid.x_max - 45.0).abs() < 1e-6);
+    assert!((grid.y_max - 25.0).abs() < 1e-6);
+    assert_eq!(grid.cols, 6);
+}
+
+#[test]
+fn test_level_subdivides_lines() {
+    let leveler = AutoLeveler::new(&tilted_mesh())
+        .unwrap()
+        .with_segment_length(10.0);
+    let out = leveler
+        .apply("G90 G21\nG0 X0 Y0 Z1\nN10 G1 Z-0.1 F200 (plunge)\nX100\n")
+        .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+
+    assert_eq!(lines[0], "G90 G21");
+    assert_eq!(lines[2], "N10 G1 X0 Y0 Z-0.1 F200 (plunge)");
+    // 100mm in 10mm segments
+    assert_eq!(out.matches("G1").count(), 11);
+    let z = z_values(&out);
+    assert!((z.last().unwrap() - 0.9).abs() < 1e-9);
+    assert!((z[3] - (-0.1 + 0.2)).abs() < 1e-9);
+}
+
+#[test]
+fn test_level_linearises_arcs() {
+    let leveler = AutoLeveler::new(&tilted_mesh()).unwrap();
+    let out = leveler
+        .apply("G0 X10 Y25 Z0\nG1 Z-0.2 F100\nG2 X90 Y25 I40 J0\n")
+        .unwrap();
+    assert!(!out.contains("G2"));
+    let last = out.lines().last().unwrap();
+    assert_eq!(last, "G1 X90 Y25 Z0.7");
+    // Mid-arc points sit above the centre line
+    assert!(out.lines().skip(3).any(|l| l.contains("Y65")));
+}
+
+#[test]
+fn test_level_relative_and_passthrough() {
+    let leveler = AutoLeveler::new(&tilted_mesh()).unwrap();
+    let out = leveler
+        .apply("G0 X0 Y0 Z0\nG91\nG1 X2 F100\nG53 G0 Z-1\nG90\n")
+        .unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[2], "G1 X1 Y0 Z0.01 F100");
+    assert_eq!(lines[3], "G1 X1 Y0 Z0.01");
+    assert_eq!(lines[4], "G53 G0 Z-1");
+
+    assert!(leveler.apply("G93\nG1 X1 F2\n").is_err());
+}
+
+#[test]
+fn test_mesh_save_and_load() {
+    let mut mesh = tilted_mesh();
+    mesh.normalize_to(50.0, 0.0);
+    assert!((mesh.get_z_offset(50.0, 0.0).unwrap()).abs() < 1e-9);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mesh.json");
+    mesh.save_to_file(&path).unwrap();
+    let loaded = ProbeMesh::load_from_file(&path).unwrap();
+    assert_eq!(loaded.points.len(), 6);
+    assert!((loaded.z_min + 0.5).abs() < 1e-9);
+}
@@ -0,0 +1,255 @@
+// Integration tests for the backlash compensation processor
+
+use super::lines;
+use gcodekit5_visualizer::{
+    BacklashCompensator, CommandProcessor, GcodeCommand, GcodeState, ProcessorPipeline,
+};
+use std::sync::Arc;
+
+/// Carriage of one axis with `backlash` of play behind the nut
+struct Axis {
+    backlash: f64,
+    commanded: f64,
+    carriage: f64,
+}
+
+impl Axis {
+    fn move_to(&mut self, commanded: f64) {
+        self.commanded = commanded;
+        // The carriage trails the nut by up to the backlash
+        self.carriage = self.carriage.clamp(commanded - self.backlash, commanded);
+    }
+}
+
+/// Run the X and Y words of one line, calling `moved` for each axis word
+fn run_line(
+    text: &str,
+    pos: &mut [f64; 2],
+    relative: &mut bool,
+    mut moved: impl FnMut(usize, f64),
+) {
+    for word in text.split_whitespace() {
+        match word {
+            "G90" => *relative = false,
+            "G91" => *relative = true,
+            _ => {}
+        }
+        let (letter, value) = word.split_at(1);
+        let axis = match letter {
+            "X" => 0,
+            "Y" => 1,
+            _ => continue,
+        };
+        let value: f64 = value.parse().unwrap();
+        pos[axis] = if *relative { pos[axis] + value } else { value };
+        moved(axis, pos[axis]);
+    }
+}
+
+/// Program positions after each line, and where a machine with backlash
+/// ends up running the compensated output of that line
+fn simulate(compensator: &BacklashCompensator, gcode: &str, backlash: [f64; 2]) -> Vec<[f64; 4]> {
+    let state = GcodeState::new();
+    let mut axes = backlash.map(|b| Axis {
+        backlash: b,
+        commanded: 0.0,
+        carriage: 0.0,
+    });
+    let (mut program, mut relative_in, mut relative_out) = ([0.0; 2], false, false);
+    let mut trace = Vec::new();
+    for line in gcode.lines() {
+        run_line(line, &mut program, &mut relative_in, |_, _| {});
+        let mut commanded = [axes[0].commanded, axes[1].commanded];
+        for command in compensator
+            .process(&GcodeCommand::new(line), &state)
+            .unwrap()
+        {
+            run_line(
+                &command.command,
+                &mut commanded,
+                &mut relative_out,
+                |axis, to| axes[axis].move_to(to),
+            );
+        }
+        trace.push([program[0], program[1], axes[0].carriage, axes[1].carriage]);
+    }
+    trace
+}
+
+#[test]
+fn test_reversal_inserts_take_up() {
+    let compensator = BacklashCompensator::new(0.1, 0.0, 0.0);
+    assert!(compensator.is_active());
+    let out = compensator
+        .apply_to_program("G21 G90\nG0 X0 Y0\nG1 X10 F100\nG1 X0\nG1 X10\nG1 X12\n")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G21 G90",
+            "G0 X0 Y0",
+            "G1 X10 F100",
+            "G1 X9.9",
+            "G1 X-0.1",
+            "G1 X0",
+            "G1 X10",
+            "G1 X12",
+        ]
+    );
+    assert_eq!(compensator.moves_inserted(), 2);
+}
+
+#[test]
+fn test_machine_lands_on_program_position() {
+    let backlash = [0.15, 0.2];
+    let compensator = BacklashCompensator::new(backlash[0], backlash[1], 0.0);
+    let program = "G21 G90\n\
+G0 X0 Y0\n\
+G1 X20 Y5 F500\n\
+G1 X5 Y15\n\
+G1 X25\n\
+Y-3\n\
+G91\n\
+G1 X-4 Y2\n\
+G1 X6 Y6\n\
+G90\n\
+G1 X0 Y0\n";
+    let trace = simulate(&compensator, program, backlash);
+
+    // Once both axes have moved, the machine error stays constant
+    let settled = &trace[3];
+    let error = [settled[2] - settled[0], settled[3] - settled[1]];
+    for step in &trace[3..] {
+        assert!((step[2] - step[0] - error[0]).abs() < 1e-9, "{:?}", step);
+        assert!((step[3] - step[1] - error[1]).abs() < 1e-9, "{:?}", step);
+    }
+
+    // Without compensation the error changes with every reversal
+    let uncompensated = simulate(&BacklashCompensator::new(0.0, 0.0, 0.0), program, backlash);
+    assert!(uncompensated[3..]
+        .iter()
+        .any(|step| (step[2] - step[0] - error[0]).abs() > 0.1));
+}
+
+#[test]
+fn test_relative_mode_take_up() {
+    let compensator = BacklashCompensator::new(0.1, 0.1, 0.0);
+    let out = compensator
+        .apply_to_program("G91\nG1 X5 F100\nG1 X-5\nG1 Y3\nG1 X2 Y-1\n")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G91",
+            "G1 X5 F100",
+            "G1 X-0.1",
+            "G1 X-5",
+            "G1 Y3",
+            "G1 X0.1 Y-0.1",
+            "G1 X2 Y-1",
+        ]
+    );
+}
+
+#[test]
+fn test_arc_split_where_axis_reverses() {
+    let compensator = BacklashCompensator::new(0.0, 0.1, 0.0);
+    let out = compensator
+        .apply_to_program("G21 G90\nG0 X10 Y0\nG1 Z-1 F100\nG2 X10 Y0 I-10 J0\nG1 X20\n")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G21 G90",
+            "G0 X10 Y0",
+            "G1 Z-1 F100",
+            "G2 X0 Y-10 I-10 J0",
+            "G1 Y-9.9",
+            "G2 X0 Y10.1 I0 J10",
+            "G1 Y10",
+            "G2 X10 Y0 I0 J-10",
+            "G1 X20",
+        ]
+    );
+}
+
+#[test]
+fn test_rapids_inverse_time_and_block_numbers() {
+    let compensator = BacklashCompensator::new(0.1, 0.0, 0.0);
+    let out = compensator
+        .apply_to_program("G0 X0\nG0 X10\nG1 X5 F200\nX0\nG0 X20\nG93\nG1 X30 F60\nN30 X0 F60\n")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G0 X0",
+            "G0 X10",
+            "G1 X9.9 F200",
+            "G1 X4.9 F200",
+            "X-0.1",
+            "G0 X0",
+            "G0 X20",
+            "G93",
+            "G1 X30 F60",
+            "G0 X29.9",
+            "N30 G1 X-0.1 F60",
+        ]
+    );
+}
+
+#[test]
+fn test_inches_passthrough_and_canned_cycles() {
+    // 0.254 mm is 0.01 inch
+    let compensator = BacklashCompensator::new(0.254, 0.0, 0.0);
+    let out = compensator
+        .apply_to_program(
+            "G20 G90\nG0 X0 Y0\nG1 X1 F10\nG1 X0 (back)\nG81 X2 Y1 Z-0.1 R0.1 F5\nX3\nG80\nG53 G0 Z0\nM5\n",
+        )
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G20 G90",
+            "G0 X0 Y0",
+            "G1 X1 F10",
+            "G1 X0.99",
+            "G1 X-0.01 (back)",
+            "G81 X1.99 Y1 Z-0.1 R0.1 F5",
+            "X2.99",
+            "G80",
+            "G53 G0 Z0",
+            "M5",
+        ]
+    );
+}
+
+#[test]
+fn test_inactive_and_pipeline() {
+    let program = "G0 X0\nG1 X10 F100\nG1 X0\n";
+    let idle = BacklashCompensator::new(0.0, -1.0, f64::NAN);
+    assert!(!idle.is_active());
+    assert_eq!(idle.backlash(), [0.0; 3]);
+    assert_eq!(idle.apply_to_program(program).unwrap(), program);
+
+    let compensator = Arc::new(BacklashCompensator::new(0.05, 0.0, 0.0));
+    assert_eq!(compensator.name(), "backlash_compensation");
+    assert_eq!(compensator.config().options.get("x").unwrap(), "0.05");
+
+    let mut pipeline = ProcessorPipeline::new();
+    pipeline.register(compensator.clone());
+    let mut state = GcodeState::new();
+    let commands: Vec<GcodeCommand> = program.lines().map(GcodeCommand::new).collect();
+    let out: Vec<String> = pipeline
+        .process_commands(&commands, &mut state)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.command)
+        .collect();
+    assert_eq!(out, vec!["G0 X0", "G1 X10 F100", "G1 X9.95", "G1 X-0.05"]);
+
+    // Errors name the line
+    let err = BacklashCompensator::new(0.1, 0.1, 0.0)
+        .apply_to_program("G0 X0 Y0\nG2 X1 Y0 R0.2\n")
+        .unwrap_err();
+    assert!(err.starts_with("Line 2:"), "{}", err);
+}
@@ -0,0 +1,172 @@
+// Integration tests for collision and gouge detection
+
+use gcodekit5_core::data::tools::ToolProfile;
+use gcodekit5_visualizer::utils::ValidationSeverity;
+use gcodekit5_visualizer::visualizer::{MachineLimits, Vector3};
+use gcodekit5_visualizer::{
+    CollisionDetector, CollisionKind, CollisionSettings, StockSimulator3D, ToolpathSegment,
+    ToolpathSegmentType, Visualizer,
+};
+
+fn segment(
+    segment_type: ToolpathSegmentType,
+    start: (f32, f32, f32),
+    end: (f32, f32, f32),
+) -> ToolpathSegment {
+    ToolpathSegment {
+        segment_type,
+        start,
+        end,
+        center: None,
+        feed_rate: 500.0,
+        spindle_speed: 10000.0,
+    }
+}
+
+fn simulator() -> StockSimulator3D {
+    // 20 x 20 x 10 mm block, voxel Z 10 is the stock top
+    StockSimulator3D::with_tool(20.0, 20.0, 10.0, 0.25, ToolProfile::Flat { diameter: 3.0 })
+}
+
+#[test]
+fn test_rapid_through_material_is_flagged() {
+    let mut sim = simulator();
+    let segments = vec![
+        segment(
+            ToolpathSegmentType::RapidMove,
+            (5.0, 10.0, 12.0),
+            (5.0, 10.0, 9.0),
+        ),
+        segment(
+            ToolpathSegmentType::RapidMove,
+            (5.0, 10.0, 9.0),
+            (15.0, 10.0, 9.0),
+        ),
+    ];
+    let issues = CollisionDetector::default().analyze(&mut sim, &segments, &[3, 4]);
+
+    assert!(!issues.is_empty());
+    assert!(issues
+        .iter()
+        .all(|i| i.kind == CollisionKind::RapidThroughMaterial));
+    assert_eq!(issues[0].line_number, 3);
+    assert_eq!(issues[1].line_number, 4);
+}
+
+#[test]
+fn test_rapid_over_cut_material_is_clear() {
+    let mut sim = simulator();
+    let segments = vec![
+        segment(
+            ToolpathSegmentType::LinearMove,
+            (5.0, 10.0, 12.0),
+            (5.0, 10.0, 8.0),
+        ),
+        segment(
+            ToolpathSegmentType::LinearMove,
+            (5.0, 10.0, 8.0),
+            (15.0, 10.0, 8.0),
+        ),
+        segment(
+            ToolpathSegmentType::RapidMove,
+            (15.0, 10.0, 8.5),
+            (5.0, 10.0, 8.5),
+        ),
+    ];
+    let issues = CollisionDetector::default().analyze(&mut sim, &segments, &[1, 2, 3]);
+    assert!(issues.is_empty(), "unexpected issues: {:?}", issues);
+}
+
+#[test]
+fn test_plunge_and_flute_length() {
+    let mut sim = simulator();
+    let settings = CollisionSettings {
+        flute_length: Some(3.0),
+        can_plunge: false,
+        ..CollisionSettings::default()
+    };
+    let segments = vec![segment(
+        ToolpathSegmentType::LinearMove,
+        (10.0, 10.0, 12.0),
+        (10.0, 10.0, 5.0),
+    )];
+    let issues = CollisionDetector::new(settings).analyze(&mut sim, &segments, &[7]);
+
+    let kinds: Vec<_> = issues.iter().map(|i| i.kind).collect();
+    assert!(kinds.contains(&CollisionKind::PlungeWithNonPlungingTool));
+    assert!(kinds.contains(&CollisionKind::ExceedsFluteLength));
+    assert!(issues.iter().all(|i| i.line_number == 7));
+
+    let warning = issues
+        .iter()
+        .find(|i| i.kind == CollisionKind::PlungeWithNonPlungingTool)
+        .unwrap()
+        .to_validation_issue();
+    assert_eq!(warning.severity, ValidationSeverity::Warning);
+    assert!(warning.suggestion.is_some());
+}
+
+#[test]
+fn test_holder_contact() {
+    let mut sim = simulator();
+    let settings = CollisionSettings::default().with_holder(20.0, 4.0);
+    let segments = vec![
+        segment(
+            ToolpathSegmentType::LinearMove,
+            (10.0, 10.0, 12.0),
+            (10.0, 10.0, 9.0),
+        ),
+        segment(
+            ToolpathSegmentType::LinearMove,
+            (10.0, 10.0, 9.0),
+            (10.0, 10.0, 5.0),
+        ),
+    ];
+    let issues = CollisionDetector::new(settings).analyze(&mut sim, &segments, &[1, 2]);
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, CollisionKind::HolderContact);
+    assert_eq!(issues[0].line_number, 2);
+}
+
+#[test]
+fn test_axis_limits_in_program_coordinates() {
+    let mut sim = simulator();
+    let limits = MachineLimits::new(
+        Vector3::new(0.0, 0.0, -50.0),
+        Vector3::new(100.0, 100.0, 0.0),
+    );
+    // Voxel Z 10 is program Z 0
+    let settings = CollisionSettings::default()
+        .with_machine_limits(limits)
+        .with_stock_origin((0.0, 0.0, -10.0));
+    let segments = vec![
+        segment(
+            ToolpathSegmentType::RapidMove,
+            (2.0, 2.0, 10.0),
+            (2.0, 2.0, 15.0),
+        ),
+        segment(
+            ToolpathSegmentType::RapidMove,
+            (2.0, 2.0, 15.0),
+            (2.0, 2.0, 10.0),
+        ),
+    ];
+    let issues = CollisionDetector::new(settings).analyze(&mut sim, &segments, &[5, 6]);
+
+    let outside: Vec<_> = issues
+        .iter()
+        .filter(|i| i.kind == CollisionKind::OutsideAxisLimits)
+        .collect();
+    assert_eq!(outside.len(), 2);
+    assert!((outside[0].position.2 - 5.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_visualizer_command_lines() {
+    let mut vis = Visualizer::new();
+    vis.parse_gcode("G21\n; comment\nG0 X1 Y1\n\nG1 X5 F100\nG2 X7 Y3 I1 J1\n");
+
+    assert_eq!(vis.commands().len(), vis.command_lines().len());
+    assert_eq!(vis.command_lines(), &[3, 5, 6]);
+}
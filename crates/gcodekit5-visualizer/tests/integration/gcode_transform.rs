@@ -0,0 +1,142 @@
+// Integration tests for the G-code transform processors
+
+use std::sync::Arc;
+
+use super::lines;
+use gcodekit5_visualizer::{
+    AffineTransform, CommandProcessor, GcodeCommand, GcodeState, MirrorAxis, ProcessorPipeline,
+    TransformProcessor,
+};
+
+#[test]
+fn test_translate_absolute_and_relative() {
+    let processor = TransformProcessor::translate(10.0, -5.0, 1.0);
+    let out = processor
+        .apply_to_program("G90 G21\nG0 X1 Y2 Z3\nG1 X4 F100 (cut)\nG91\nG1 X1 Y1 Z-1\nM5")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        [
+            "G90 G21",
+            "G0 X11 Y-3 Z4",
+            "G1 X14 F100 (cut)",
+            "G91",
+            "G1 X1 Y1 Z-1",
+            "M5"
+        ]
+    );
+}
+
+#[test]
+fn test_translate_inch_program() {
+    let processor = TransformProcessor::translate(25.4, 0.0, 0.0);
+    let out = processor.apply_to_program("G20\nG0 X1 Y1").unwrap();
+    assert_eq!(lines(&out)[1], "G0 X2 Y1");
+}
+
+#[test]
+fn test_rotate_quarter_turn_with_arcs() {
+    let processor = TransformProcessor::rotate(90.0, (0.0, 0.0));
+    let out = processor
+        .apply_to_program("G0 X10 Y0\nG3 X0 Y10 I-10 J0\nG1 X5")
+        .unwrap();
+    let out = lines(&out);
+    assert_eq!(out[0], "G0 X0 Y10");
+    assert_eq!(out[1], "G3 X-10 Y0 I0 J-10");
+    // Only X given, but the rotated move needs the current Y too
+    assert_eq!(out[2], "G1 X-10 Y5");
+}
+
+#[test]
+fn test_rotate_needs_known_position() {
+    let processor = TransformProcessor::rotate(45.0, (0.0, 0.0));
+    let err = processor.apply_to_program("G0 X10").unwrap_err();
+    assert!(err.starts_with("Line 1:"), "{}", err);
+}
+
+#[test]
+fn test_mirror_swaps_arc_direction() {
+    let processor = TransformProcessor::mirror(MirrorAxis::X, 0.0);
+    let out = processor
+        .apply_to_program("G0 X10 Y0\nG2 X0 Y-10 I-10 J0 F300\nG3 X-10 Y0 R10")
+        .unwrap();
+    let out = lines(&out);
+    assert_eq!(out[0], "G0 X-10 Y0");
+    assert_eq!(out[1], "G3 X0 Y-10 I10 J0 F300");
+    assert_eq!(out[2], "G2 X10 Y0 R10");
+
+    // Mirroring about a line
+    let processor = TransformProcessor::mirror(MirrorAxis::Y, 5.0);
+    let out = processor.apply_to_program("G0 X1 Y0").unwrap();
+    assert_eq!(out.trim(), "G0 X1 Y10");
+}
+
+#[test]
+fn test_scale_arcs_and_canned_cycles() {
+    let processor = TransformProcessor::scale(2.0, 2.0, 0.5, (0.0, 0.0));
+    let out = processor
+        .apply_to_program("G0 X1 Y1 Z2\nG2 X3 Y1 R1\nG81 X2 Y2 Z-4 R1\nX3\nG80")
+        .unwrap();
+    let out = lines(&out);
+    assert_eq!(out[0], "G0 X2 Y2 Z1");
+    assert_eq!(out[1], "G2 X6 Y2 R2");
+    assert_eq!(out[2], "G81 X4 Y4 Z-2 R0.5");
+    assert_eq!(out[3], "X6");
+
+    // Non-uniform scaling would distort arcs
+    let processor = TransformProcessor::scale(2.0, 1.0, 1.0, (0.0, 0.0));
+    assert!(processor
+        .apply_to_program("G0 X0 Y0\nG2 X2 Y0 I1 J0")
+        .is_err());
+    assert!(processor.apply_to_program("G0 X0 Y0\nG1 X2 Y1").is_ok());
+}
+
+#[test]
+fn test_canned_cycle_cancelled_by_g80() {
+    let processor = TransformProcessor::scale(2.0, 2.0, 0.5, (0.0, 0.0));
+    let out = processor
+        .apply_to_program("G81 X2 Y2 Z-4 R1\nG80\nZ4 R1\nX1 Y1")
+        .unwrap();
+    // R is no longer a retract height once the cycle is cancelled
+    assert_eq!(lines(&out), ["G81 X4 Y4 Z-2 R0.5", "G80", "Z2 R1", "X2 Y2"]);
+}
+
+#[test]
+fn test_machine_coordinates_untouched() {
+    let processor = TransformProcessor::translate(5.0, 5.0, 0.0);
+    let out = processor
+        .apply_to_program("G53 G0 Z-1\nG28\nG10 L20 P1 X0\nG0 X1 Y1")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        ["G53 G0 Z-1", "G28", "G10 L20 P1 X0", "G0 X6 Y6"]
+    );
+}
+
+#[test]
+fn test_processor_in_pipeline() {
+    let processor = Arc::new(TransformProcessor::new(
+        AffineTransform::rotation(180.0, (5.0, 5.0))
+            .then(&AffineTransform::translation(1.0, 0.0, 0.0)),
+    ));
+    assert_eq!(processor.name(), "transform");
+
+    let mut pipeline = ProcessorPipeline::new();
+    pipeline.register(processor.clone());
+    let commands = vec![GcodeCommand::new("G0 X0 Y0"), GcodeCommand::new("G1 X10")];
+    let mut state = GcodeState::new();
+    let out: Vec<String> = pipeline
+        .process_commands(&commands, &mut state)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.command)
+        .collect();
+    assert_eq!(out, ["G0 X11 Y10", "G1 X1"]);
+
+    assert_eq!(
+        TransformProcessor::mirror(MirrorAxis::Y, 0.0)
+            .config()
+            .get_option("axis"),
+        Some("y")
+    );
+}
@@ -0,0 +1,258 @@
+// Integration tests for job analysis reports
+
+use std::f64::consts::PI;
+use std::path::Path;
+
+use gcodekit5_visualizer::utils::MotionSummary;
+use gcodekit5_visualizer::{
+    JobReport, ReportFormat, ReportOptions, ValidationIssue, ValidationSeverity,
+};
+
+/// Slot with a 6 mm end mill, then a plunge and a half circle with a
+/// 3 mm ball nose. Rapids run at the default 3000 mm/min.
+const PROGRAM: &str = "G21\n\
+G90\n\
+T1 M6 (6mm flat end mill)\n\
+M3 S10000\n\
+G0 X0 Y0 Z5\n\
+G1 Z-1 F600\n\
+G1 X10 F1200\n\
+G0 Z5\n\
+M5\n\
+T2 M6 (3mm ball nose)\n\
+M3 S20000\n\
+G0 X20 Y0\n\
+G1 Z-2 F300\n\
+G2 X30 Y0 I5 J0 F600\n\
+G0 Z5\n\
+M5\n\
+M30\n";
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+fn report() -> JobReport {
+    JobReport::generate("slot.nc", PROGRAM, &ReportOptions::default())
+}
+
+fn assert_motion(motion: &MotionSummary, cut: f64, rapid: f64, cut_time: f64, rapid_time: f64) {
+    assert!(close(motion.cut_distance, cut), "{:?}", motion);
+    assert!(close(motion.rapid_distance, rapid), "{:?}", motion);
+    assert!(close(motion.cut_time, cut_time), "{:?}", motion);
+    assert!(close(motion.rapid_time, rapid_time), "{:?}", motion);
+}
+
+#[test]
+fn test_tool_changes_and_usage() {
+    let report = report();
+    assert_eq!(report.name, "slot.nc");
+    assert_eq!(report.lines, 17);
+
+    let changes: Vec<(u32, u32, Option<&str>)> = report
+        .tool_changes
+        .iter()
+        .map(|c| (c.line, c.tool, c.description.as_deref()))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (3, 1, Some("6mm flat end mill")),
+            (10, 2, Some("3mm ball nose")),
+        ]
+    );
+
+    assert_eq!(report.tools.len(), 2);
+    assert_eq!(report.tools[0].tool, Some(1));
+    assert_eq!(
+        report.tools[0].description.as_deref(),
+        Some("6mm flat end mill")
+    );
+    assert_motion(&report.tools[0].motion, 16.0, 11.0, 1.1, 0.22);
+
+    let arc = 5.0 * PI;
+    assert_eq!(report.tools[1].tool, Some(2));
+    assert_motion(
+        &report.tools[1].motion,
+        7.0 + arc,
+        17.0,
+        1.4 + arc / 10.0,
+        0.34,
+    );
+
+    let summary = &report.summary;
+    assert_motion(summary, 23.0 + arc, 28.0, 2.5 + arc / 10.0, 0.56);
+    assert!(close(
+        summary.total_time(),
+        summary.cut_time + summary.rapid_time
+    ));
+}
+
+#[test]
+fn test_bounding_box_includes_arc_extents() {
+    let bb = report().bounding_box;
+    assert!(close(bb.min_x as f64, 0.0) && close(bb.max_x as f64, 30.0));
+    // The clockwise half circle from X20 to X30 bulges to Y5
+    assert!(close(bb.min_y as f64, 0.0) && close(bb.max_y as f64, 5.0));
+    assert!(close(bb.min_z as f64, -2.0) && close(bb.max_z as f64, 5.0));
+
+    let empty = JobReport::generate("empty", "G21\n", &ReportOptions::default());
+    assert_eq!(empty.bounding_box.width(), 0.0);
+    assert!(empty.tools.is_empty());
+    assert!(empty.feed_histogram.is_empty());
+}
+
+#[test]
+fn test_histograms_cover_all_cutting() {
+    let report = report();
+    let total = report.summary;
+
+    let feed = &report.feed_rate_stats;
+    assert!(close(feed.min_feed, 300.0) && close(feed.max_feed, 1200.0));
+    assert_eq!(feed.changes, 4);
+    assert_eq!(report.feed_histogram.len(), 10);
+    let distance: f64 = report.feed_histogram.iter().map(|b| b.distance).sum();
+    let time: f64 = report.feed_histogram.iter().map(|b| b.time).sum();
+    assert!(close(distance, total.cut_distance));
+    assert!(close(time, total.cut_time));
+    // 300 mm/min plunge falls in the first bin, the 1200 mm/min slot in the last
+    assert!(close(report.feed_histogram[0].distance, 7.0));
+    assert!(close(report.feed_histogram[9].distance, 10.0));
+
+    let spindle = &report.spindle_stats;
+    assert!(close(spindle.min_speed, 10000.0) && close(spindle.max_speed, 20000.0));
+    assert_eq!(spindle.on_count, 2);
+    let bins = &report.spindle_histogram;
+    assert!(close(bins[0].distance, 16.0));
+    assert!(close(bins[9].distance, 7.0 + 5.0 * PI));
+    let time: f64 = bins.iter().map(|b| b.time).sum();
+    assert!(close(time, total.cut_time));
+}
+
+#[test]
+fn test_warnings_are_reported_in_line_order() {
+    let program = "G0 X0 Y0 Z1\n\
+M3 S1000\n\
+G1 Z-1\n\
+G1 X5 F100\n\
+M5\n\
+G1 X10\n";
+    let report = JobReport::generate("warn", program, &ReportOptions::default());
+    let lines: Vec<u32> = report.warnings.iter().map(|w| w.line_number).collect();
+    assert_eq!(lines, vec![3, 6]);
+    assert!(report.warnings[0].message.contains("feed rate"));
+    assert!(report.warnings[1].message.contains("spindle off"));
+
+    let report = report.with_issues([ValidationIssue::new(
+        4,
+        ValidationSeverity::Error,
+        "Outside soft limits",
+    )]);
+    let lines: Vec<u32> = report.warnings.iter().map(|w| w.line_number).collect();
+    assert_eq!(lines, vec![3, 4, 6]);
+
+    // No warnings for a clean program
+    assert!(self::report().warnings.is_empty());
+}
+
+#[test]
+fn test_tool_select_without_m6_is_a_change() {
+    let program = "T3 (drill)\nG0 X0 Y0 Z1\nG1 Z-2 F100\n";
+    let report = JobReport::generate("drill", program, &ReportOptions::default());
+    assert_eq!(report.tool_changes.len(), 1);
+    assert_eq!(report.tool_changes[0].tool, 3);
+    assert_eq!(report.tools.len(), 1);
+    assert_eq!(report.tools[0].description.as_deref(), Some("drill"));
+    assert!(close(report.tools[0].motion.cut_distance, 3.0));
+}
+
+#[test]
+fn test_json_round_trip() {
+    let report = report();
+    let json = report.render(ReportFormat::Json).unwrap();
+    let parsed: JobReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.tool_changes, report.tool_changes);
+    assert_eq!(parsed.generated, report.generated);
+    assert_eq!(parsed.tools.len(), report.tools.len());
+    assert_eq!(parsed.feed_histogram.len(), report.feed_histogram.len());
+    assert!(close(parsed.summary.cut_time, report.summary.cut_time));
+    assert!(close(
+        parsed.tools[1].motion.cut_distance,
+        report.tools[1].motion.cut_distance
+    ));
+    assert_eq!(parsed.thumbnail_svg, report.thumbnail_svg);
+}
+
+#[test]
+fn test_csv_sections() {
+    let report = report().with_issues([ValidationIssue::new(
+        7,
+        ValidationSeverity::Warning,
+        "Feed \"fast\", check",
+    )]);
+    let csv = report.render(ReportFormat::Csv).unwrap();
+    for section in [
+        "Summary\n",
+        "Tools\n",
+        "Tool Changes\n",
+        "Feed Histogram\n",
+        "Spindle Histogram\n",
+        "Warnings\n",
+    ] {
+        assert!(csv.contains(section), "missing {}", section);
+    }
+    assert!(csv.contains("1,6mm flat end mill,16.000,11.000,"));
+    assert!(csv.contains("10,2,3mm ball nose\n"));
+    assert!(csv.contains("7,Warning,\"Feed \"\"fast\"\", check\",\n"));
+}
+
+#[test]
+fn test_html_is_self_contained_and_escaped() {
+    let report = JobReport::generate("<Bracket & Co>", PROGRAM, &ReportOptions::default());
+    let html = report.render(ReportFormat::Html).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("&lt;Bracket &amp; Co&gt;"));
+    assert!(!html.contains("<Bracket"));
+    assert!(html.contains("<svg"));
+    assert!(html.contains("3mm ball nose"));
+    assert!(!html.contains("<link") && !html.contains("<script"));
+
+    let options = ReportOptions {
+        thumbnail_size: 0,
+        ..ReportOptions::default()
+    };
+    let report = JobReport::generate("job", PROGRAM, &options);
+    assert!(report.thumbnail_svg.is_none());
+    assert!(!report.to_html().contains("<svg"));
+}
+
+#[test]
+fn test_format_from_path_and_save() {
+    assert_eq!(
+        ReportFormat::from_path(Path::new("job.HTML")),
+        Some(ReportFormat::Html)
+    );
+    assert_eq!(
+        ReportFormat::from_path(Path::new("job.json")),
+        Some(ReportFormat::Json)
+    );
+    assert_eq!(
+        ReportFormat::from_path(Path::new("job.csv")),
+        Some(ReportFormat::Csv)
+    );
+    assert_eq!(ReportFormat::from_path(Path::new("job.txt")), None);
+
+    let dir = tempfile::tempdir().unwrap();
+    let report = report();
+    let path = dir.path().join("job.csv");
+    report.save(&path).unwrap();
+    assert!(std::fs::read_to_string(&path)
+        .unwrap()
+        .starts_with("Summary\n"));
+    assert!(report.save(dir.path().join("job.txt")).is_err());
+
+    let gcode = dir.path().join("part.nc");
+    std::fs::write(&gcode, PROGRAM).unwrap();
+    let report = JobReport::from_file(&gcode, &ReportOptions::default()).unwrap();
+    assert_eq!(report.name, "part.nc");
+}
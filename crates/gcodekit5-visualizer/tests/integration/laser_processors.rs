@@ -0,0 +1,218 @@
+// Integration tests for the laser processors
+
+use super::lines;
+use gcodekit5_visualizer::{
+    CommandProcessor, DynamicPowerProcessor, GcodeCommand, GcodeState, LaserSettings,
+    OverscanProcessor, PowerClampProcessor, PowerCurve, PowerScaling, ProcessorPipeline,
+};
+use std::sync::Arc;
+
+/// Only the processors a test enables
+fn none_enabled() -> LaserSettings {
+    LaserSettings {
+        dynamic_power: false,
+        overscan: 0.0,
+        power_scaling: None,
+        max_power: None,
+        strip_redundant_power: false,
+    }
+}
+
+#[test]
+fn test_dynamic_power_clamp_and_redundant_s() {
+    let state = GcodeState::new();
+    let out = DynamicPowerProcessor::new()
+        .process(&GcodeCommand::new("M03 S500 (on)"), &state)
+        .unwrap();
+    assert_eq!(out[0].command, "M4 S500 (on)");
+
+    let clamp = PowerClampProcessor::new(255.0);
+    assert_eq!(clamp.name(), "laser_power_clamp");
+    let out = clamp
+        .process(&GcodeCommand::new("G1 X1 S-5"), &state)
+        .unwrap();
+    assert_eq!(out[0].command, "G1 X1 S0");
+
+    let settings = LaserSettings {
+        max_power: Some(1000.0),
+        ..LaserSettings::default()
+    };
+    let out = settings
+        .apply_to_program(
+            "G21 G90\nM3 S1200\nG1 X10 F1000 S1200\nG1 X20 S800\nS800\nG1 Y10 S800 (edge)\nM5\nM30\n",
+        )
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G21 G90",
+            "M4 S1000",
+            "G1 X10 F1000",
+            "G1 X20 S800",
+            "G1 Y10 (edge)",
+            "M5",
+            "M30",
+        ]
+    );
+}
+
+#[test]
+fn test_power_scaled_by_feed_and_curve() {
+    let by_feed = LaserSettings {
+        power_scaling: Some(PowerScaling::ByFeed {
+            reference_feed: 1000.0,
+        }),
+        ..none_enabled()
+    };
+    let out = by_feed
+        .apply_to_program("G21\nM4 S1000\nG1 X10 F1000\nG1 X20 F500\nG1 X30\nG1 X40 F2000\nG0 X0\n")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G21",
+            "M4 S1000",
+            "G1 X10 F1000",
+            "G1 X20 F500 S500",
+            "G1 X30",
+            "G1 X40 F2000 S1000",
+            "G0 X0",
+        ]
+    );
+
+    let curve = PowerCurve::new([(3000.0, 100.0), (1000.0, 50.0), (f64::NAN, 1.0)]);
+    assert_eq!(curve.points(), &[(1000.0, 50.0), (3000.0, 100.0)]);
+    assert!((curve.factor(2000.0) - 0.75).abs() < 1e-9);
+    assert!((curve.factor(500.0) - 0.5).abs() < 1e-9);
+    assert!((curve.factor(4000.0) - 1.0).abs() < 1e-9);
+    assert_eq!(PowerCurve::default().factor(100.0), 1.0);
+
+    let by_curve = LaserSettings {
+        power_scaling: Some(PowerScaling::Curve(curve)),
+        ..none_enabled()
+    };
+    // Inch feeds are looked up in mm/min: 40 in/min is 1016 mm/min
+    let out = by_curve
+        .apply_to_program("M4 S800\nG1 X5 F2000\nG20\nG1 X1 F40\n")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec!["M4 S800", "G1 X5 F2000 S600", "G20", "G1 X1 F40 S403.2"]
+    );
+}
+
+#[test]
+fn test_overscan_on_bidirectional_raster() {
+    let settings = LaserSettings {
+        overscan: 2.0,
+        ..none_enabled()
+    };
+    let out = settings
+        .apply_to_program(
+            "G21 G90\nM4 S0\nG0 X10 Y0\nG1 X20 S500 F1000\nG1 X30 S300\nG0 Y1\nG1 X10 S500\nG0 X0 Y5\nM5\n",
+        )
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G21 G90",
+            "M4 S0",
+            "G0 X8 Y0",
+            "G1 X10 S0 F1000",
+            "G1 X20 S500 F1000",
+            "G1 X30 S300",
+            "G1 X32 S0",
+            "G0 Y1 X32",
+            "G1 X30 S0",
+            "G1 X10 S500",
+            "G1 X8 S0",
+            "G0 X0 Y5",
+            "M5",
+        ]
+    );
+}
+
+#[test]
+fn test_overscan_restores_power_and_position() {
+    // 2.54 mm is 0.1 inch
+    let settings = LaserSettings {
+        overscan: 2.54,
+        ..none_enabled()
+    };
+    let out = settings
+        .apply_to_program("G20 G90\nM4\nG0 X1 Y0\nG1 X2 S100 F40\nG0 Y0.5\nG1 X3 Y1\n")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G20 G90",
+            "M4",
+            "G0 X0.9 Y0",
+            "G1 X1 S0 F40",
+            "G1 X2 S100 F40",
+            "G1 X2.1 S0",
+            "G0 Y0.5 X2",
+            "G1 X3 Y1 S100",
+        ]
+    );
+
+    // A relative move after the lead-out first steps back by the overscan
+    let settings = LaserSettings {
+        overscan: 2.0,
+        ..none_enabled()
+    };
+    let out = settings
+        .apply_to_program("M4 S200\nG0 X0 Y0\nG1 Y5 F600\nG91\nG0 X1\n")
+        .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "M4 S200",
+            "G0 X0 Y-2",
+            "G1 Y0 S0 F600",
+            "G1 Y5 F600 S200",
+            "G1 Y7 S0",
+            "G91",
+            "G0 Y-2",
+            "G0 X1",
+        ]
+    );
+}
+
+#[test]
+fn test_overscan_in_pipeline_flushes_at_end() {
+    let overscan = Arc::new(OverscanProcessor::new(2.0));
+    assert_eq!(overscan.name(), "laser_overscan");
+    assert_eq!(overscan.config().options.get("distance").unwrap(), "2");
+
+    let mut pipeline = ProcessorPipeline::new();
+    pipeline.register(overscan.clone());
+    let mut state = GcodeState::new();
+    let commands: Vec<GcodeCommand> = "M4 S200\nG0 X0 Y0\nG1 X5 F600\n"
+        .lines()
+        .map(GcodeCommand::new)
+        .collect();
+    let out: Vec<String> = pipeline
+        .process_commands(&commands, &mut state)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.command)
+        .collect();
+    assert_eq!(
+        out,
+        vec![
+            "M4 S200",
+            "G0 X-2 Y0",
+            "G1 X0 S0 F600",
+            "G1 X5 F600 S200",
+            "G1 X7 S0",
+        ]
+    );
+    assert_eq!(overscan.lines_extended(), 1);
+
+    // Errors name the line
+    let err = LaserSettings::default()
+        .apply_to_program("G0 X0\nG1 X#1\n")
+        .unwrap_err();
+    assert!(err.starts_with("Line 2:"), "{}", err);
+}
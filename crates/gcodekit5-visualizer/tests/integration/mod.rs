@@ -1 +1,19 @@
+pub mod arc_fitting;
+pub mod auto_leveling;
+pub mod backlash_compensation;
+pub mod collision_detection;
+pub mod gcode_transform;
+pub mod job_report;
+pub mod laser_processors;
+pub mod parametric_expansion;
 pub mod phase7_integration;
+pub mod preview_rendering;
+pub mod stock_deviation;
+pub mod streaming_parse;
+pub mod timeline_scrubbing;
+pub mod toolpath_diff;
+
+/// Lines of a processed program
+pub fn lines(gcode: &str) -> Vec<String> {
+    gcode.lines().map(str::to_string).collect()
+}
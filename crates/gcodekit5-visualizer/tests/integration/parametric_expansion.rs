@@ -0,0 +1,217 @@
+// Integration tests for parametric G-code expansion
+
+use super::lines;
+use gcodekit5_visualizer::{
+    expand_program, CommandProcessor, ExpansionStep, GcodeCommand, GcodeState, ParametricExpander,
+    ParametricProcessor, ParametricProgram, ProcessorPipeline,
+};
+use std::sync::Arc;
+
+fn next_line(expander: &mut ParametricExpander) -> String {
+    match expander.next_step().unwrap() {
+        ExpansionStep::Line(line) => line.text,
+        other => panic!("expected a line, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parameters_expressions_and_functions() {
+    let out = expand_program(
+        "#1 = 2\n#<Cut Depth> = [#1 * 1.5]\nG1 X[#1 + 1] Y[SQRT[16]] Z-#<cutdepth> F100\n\
+         G0 X[COS[60] * 10] Y[ATAN[1]/[1]]\n(comment only)\n  G0 X1 Y2 (kept)\n\
+         #1 = 1\n#1 = [#1 + 1] #2 = #1\nG0 X#1 Y#2\n\
+         G0 X[1 + 2 * 3 ** 2] Y[-7 MOD 3] Z[2 GT 1 AND 0 LT 1]\nG0 X[ABS[-2.5]] Y[FUP[1.2]] Z[FIX[-1.2]]\n",
+    )
+    .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G1 X3 Y4 Z-3 F100",
+            "G0 X5 Y45",
+            "(comment only)",
+            "G0 X1 Y2 (kept)",
+            // Parameters set on a line take effect after the line
+            "G0 X2 Y1",
+            "G0 X19 Y2 Z1",
+            "G0 X2.5 Y2 Z-2",
+        ]
+    );
+
+    assert!(ParametricProgram::is_parametric("O100 sub\nO100 endsub"));
+    assert!(ParametricProgram::is_parametric("G0 X#1"));
+    assert!(!ParametricProgram::is_parametric(
+        "O100\nG0 X1 (#1 is a comment)"
+    ));
+}
+
+#[test]
+fn test_subroutines_and_locals() {
+    let out = expand_program(
+        "#<_calls> = 0\n\
+         O100 sub\n\
+           #<_calls> = [#<_calls> + 1]\n\
+           G1 X#1 Y#2\n\
+           O101 if [#1 GT 5]\n\
+             O100 return [1]\n\
+           O101 endif\n\
+         O100 endsub [0]\n\
+         #1 = 99\n\
+         O100 call [2] [3]\n\
+         G0 X#<_value> Y#1\n\
+         O<corner> call [6] [1]\n\
+         G0 X#<_value> Y#<_calls>\n\
+         O<corner> sub\n\
+           O100 call [#1] [#2]\n\
+         O<corner> endsub [#<_value>]\n",
+    )
+    .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec!["G1 X2 Y3", "G0 X0 Y99", "G1 X6 Y1", "G0 X1 Y2"]
+    );
+}
+
+#[test]
+fn test_loops_and_conditionals() {
+    let out = expand_program(
+        "#<i> = 0\n\
+         O1 while [#<i> LT 3]\n\
+           O2 if [#<i> EQ 1]\n\
+             #<i> = [#<i> + 1]\n\
+             O1 continue\n\
+           O2 endif\n\
+           G0 X#<i>\n\
+           #<i> = [#<i> + 1]\n\
+         O1 endwhile\n\
+         O3 repeat [2]\n\
+           G0 Y1\n\
+         O3 endrepeat\n\
+         #<n> = 0\n\
+         O4 do\n\
+           #<n> = [#<n> + 1]\n\
+           O5 if [#<n> GE 4]\n\
+             O4 break\n\
+           O5 elseif [#<n> EQ 2]\n\
+             G0 Z2\n\
+           O5 else\n\
+             G0 Z#<n> (n)\n\
+           O5 endif\n\
+         O4 while [1]\n\
+         M30\n\
+         G0 X100\n",
+    )
+    .unwrap();
+    assert_eq!(
+        lines(&out),
+        vec![
+            "G0 X0",
+            "G0 X2",
+            "G0 Y1",
+            "G0 Y1",
+            "G0 Z1 (n)",
+            "G0 Z2",
+            "G0 Z3 (n)",
+            "M30",
+        ]
+    );
+}
+
+#[test]
+fn test_errors_name_source_lines() {
+    let errors =
+        ParametricProgram::parse("G0 X[1 +\nO100 endwhile\nO200 sub\nG0 X1\n").unwrap_err();
+    let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, vec![1, 2, 3]);
+    assert!(
+        errors[2].to_string().starts_with("Line 3: O200 sub"),
+        "{}",
+        errors[2]
+    );
+
+    let err = expand_program("#<a> = 1\nG0 X#<b>\n").unwrap_err();
+    assert_eq!(err, "Line 2: parameter #<b> is not defined");
+    let err = expand_program("G0 X1\nO1 call\n").unwrap_err();
+    assert_eq!(err, "Line 2: subroutine O1 is not defined");
+    let err = expand_program("#1 = 0\nG0 X[1 / #1]\n").unwrap_err();
+    assert_eq!(err, "Line 2: division by zero");
+    let err = expand_program("O1 while [1]\nO1 endwhile\n").unwrap_err();
+    assert!(err.starts_with("Line 1: program did not finish"), "{}", err);
+}
+
+#[test]
+fn test_machine_state_resolved_live() {
+    let source =
+        "G90 G0 X0 Y0 Z5\nG38.2 Z-10 F100\n#<top> = #5063\nG0 Z[#<top> + 2]\nG0 X[#5420 + 10]\n";
+    let program = ParametricProgram::parse(source).unwrap();
+    assert!(program.reads_machine_state());
+
+    // Offline, the probe is assumed to touch at its target
+    assert_eq!(
+        lines(&expand_program(source).unwrap()),
+        vec!["G90 G0 X0 Y0 Z5", "G38.2 Z-10 F100", "G0 Z-8", "G0 X10"]
+    );
+
+    let mut expander = ParametricExpander::live(program);
+    assert_eq!(next_line(&mut expander), "G90 G0 X0 Y0 Z5");
+    assert_eq!(next_line(&mut expander), "G38.2 Z-10 F100");
+    assert_eq!(
+        expander.next_step().unwrap(),
+        ExpansionStep::WaitingForProbe
+    );
+    assert!(expander.is_waiting_for_probe());
+    expander.record_probe([1.0, 0.0, -3.2], true);
+    assert_eq!(expander.parameter(5070), Some(1.0));
+    assert_eq!(next_line(&mut expander), "G0 Z-1.2");
+    assert_eq!(next_line(&mut expander), "G0 X11");
+    assert_eq!(expander.next_step().unwrap(), ExpansionStep::Finished);
+
+    // Moves in machine coordinates leave the work position unknown
+    let err = expand_program("G0 X1\nG53 G0 X0\nG0 X#<_x>\n").unwrap_err();
+    assert_eq!(err, "Line 3: the X position is not known here");
+    let mut expander =
+        ParametricExpander::new(ParametricProgram::parse("G91 G0 X1\nG0 X#<_x>").unwrap());
+    expander.set_position([5.0, 0.0, 0.0]);
+    let out: Vec<String> = expander
+        .expand_all()
+        .unwrap()
+        .into_iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(out, vec!["G91 G0 X1", "G0 X6"]);
+}
+
+#[test]
+fn test_parametric_processor_in_pipeline() {
+    let processor = Arc::new(ParametricProcessor::new());
+    assert_eq!(processor.name(), "parametric_expansion");
+
+    let mut pipeline = ProcessorPipeline::new();
+    pipeline.register(processor);
+    let mut state = GcodeState::new();
+    let commands: Vec<GcodeCommand> =
+        "O1 repeat [3]\n#<_x0> = [#<_x0> + 1]\nG0 X#<_x0>\nO1 endrepeat"
+            .lines()
+            .map(GcodeCommand::new)
+            .collect();
+    let err = pipeline
+        .process_commands(&commands, &mut state)
+        .unwrap_err();
+    assert!(
+        err.contains("Line 2: parameter #<_x0> is not defined"),
+        "{}",
+        err
+    );
+
+    let commands: Vec<GcodeCommand> =
+        "#<_x0> = 0\nO1 repeat [3]\n#<_x0> = [#<_x0> + 0.5]\nG0 X#<_x0>\nO1 endrepeat"
+            .lines()
+            .map(GcodeCommand::new)
+            .collect();
+    let out: Vec<String> = pipeline
+        .process_commands(&commands, &mut state)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.command)
+        .collect();
+    assert_eq!(out, vec!["G0 X0.5", "G0 X1", "G0 X1.5"]);
+}
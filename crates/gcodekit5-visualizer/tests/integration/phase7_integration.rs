@@ -1,10 +1,10 @@
 // Integration tests for Phase 7 (Tasks 121-150)
 
 use gcodekit5_visualizer::utils::{
-    BufferDiagnostics, CalibrationStepType, CalibrationWizard, CommunicationDiagnostics,
-    DiagnosticReport, EmergencyStopManager, EmergencyStopState, ExportFormat, FeedHoldManager,
-    FormatExporter, MotionInterlock, PerformanceProfiler, PluginConfig, PluginMetadata,
-    PluginRegistry, PostProcessor, SafetyFeaturesManager,
+    BacklashProcedure, BufferDiagnostics, CalibrationStepType, CalibrationWizard,
+    CommunicationDiagnostics, DiagnosticReport, EmergencyStopManager, EmergencyStopState,
+    ExportFormat, FeedHoldManager, FormatExporter, MotionInterlock, PerformanceProfiler,
+    PluginConfig, PluginMetadata, PluginRegistry, PostProcessor, SafetyFeaturesManager,
 };
 
 // ============================================================================
@@ -258,6 +258,34 @@ fn test_backlash_calibration() {
     assert!(wizard.is_complete());
 }
 
+#[test]
+fn test_backlash_wizard_for_chosen_axes() {
+    let mut wizard = CalibrationWizard::backlash_measurement("xz");
+    assert_eq!(wizard.steps().len(), 2);
+    assert_eq!(wizard.current_step().unwrap().axis, "X");
+
+    assert!(wizard.record_measurement(0.04).is_ok());
+    assert_eq!(wizard.current_step().unwrap().axis, "Z");
+    assert!(wizard.record_measurement(0.15).is_ok());
+    assert!(wizard.is_complete());
+    assert!(!wizard.results()[1].passed);
+
+    assert_eq!(wizard.measured("X"), Some(0.04));
+    assert_eq!(wizard.measured("Z"), Some(0.15));
+    assert_eq!(wizard.measured("Y"), None);
+
+    // Approach from below, zero, then come back from above
+    let procedure = BacklashProcedure::new('y');
+    assert_eq!(
+        procedure.preload_gcode(),
+        "G21 G91\nG1 Y-2.000 F100\nG1 Y2.000\nG90\n"
+    );
+    assert_eq!(
+        procedure.reversal_gcode(),
+        "G21 G91\nG1 Y2.000 F100\nG1 Y-2.000\nG90\n"
+    );
+}
+
 #[test]
 fn test_squareness_calibration() {
     let mut wizard = CalibrationWizard::new(CalibrationStepType::SquarenessCheck);
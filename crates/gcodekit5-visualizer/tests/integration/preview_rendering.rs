@@ -0,0 +1,173 @@
+// Integration tests for headless preview rendering
+
+use gcodekit5_visualizer::{
+    GCodeCommand, Point3D, PreviewColor, PreviewFormat, PreviewOptions, PreviewProjection,
+    PreviewRenderer,
+};
+use std::path::Path;
+
+fn square(intensity: Option<f32>) -> Vec<GCodeCommand> {
+    let p = Point3D::new;
+    let corners = [
+        p(0.0, 0.0, -1.0),
+        p(20.0, 0.0, -1.0),
+        p(20.0, 20.0, -1.0),
+        p(0.0, 20.0, -1.0),
+        p(0.0, 0.0, -1.0),
+    ];
+    let mut commands = vec![GCodeCommand::Move {
+        from: p(0.0, 0.0, 5.0),
+        to: corners[0],
+        rapid: true,
+        intensity: None,
+    }];
+    commands.extend(corners.windows(2).map(|w| GCodeCommand::Move {
+        from: w[0],
+        to: w[1],
+        rapid: false,
+        intensity,
+    }));
+    commands
+}
+
+fn pixel(rgba: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+    let i = ((y * width + x) * 4) as usize;
+    [rgba[i], rgba[i + 1], rgba[i + 2], rgba[i + 3]]
+}
+
+#[test]
+fn test_preview_color_from_hex() {
+    assert_eq!(
+        PreviewColor::from_hex("#ff8000"),
+        Some(PreviewColor::rgba(255, 128, 0, 255))
+    );
+    assert_eq!(
+        PreviewColor::from_hex("00ff0080"),
+        Some(PreviewColor::rgba(0, 255, 0, 128))
+    );
+    assert_eq!(PreviewColor::from_hex("#12345"), None);
+    assert_eq!(PreviewColor::from_hex("zzzzzz"), None);
+}
+
+#[test]
+fn test_preview_format_from_path() {
+    assert_eq!(
+        PreviewFormat::from_path(Path::new("a/job.PNG")),
+        Some(PreviewFormat::Png)
+    );
+    assert_eq!(
+        PreviewFormat::from_path(Path::new("job.svg")),
+        Some(PreviewFormat::Svg)
+    );
+    assert_eq!(PreviewFormat::from_path(Path::new("job.gcode")), None);
+}
+
+#[test]
+fn test_preview_png_is_valid_image() {
+    let renderer = PreviewRenderer::new(PreviewOptions::thumbnail(64));
+    let png = renderer
+        .render_png(&square(None), &[("Thumb::URI", "file:///tmp/job.nc")])
+        .unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert!(png.windows(10).any(|w| w == b"Thumb::URI"));
+}
+
+#[test]
+fn test_preview_draws_cut_colour_on_path() {
+    let options = PreviewOptions {
+        width: 100,
+        height: 100,
+        margin: 10.0,
+        line_width: 3.0,
+        show_rapids: false,
+        ..PreviewOptions::default()
+    };
+    let cut = options.cut_color;
+    let background = options.background;
+    let rgba = PreviewRenderer::new(options)
+        .render_rgba(&square(None))
+        .unwrap();
+
+    // The square fills the image inside the margin; Y is flipped
+    assert_eq!(
+        pixel(&rgba, 100, 50, 89),
+        [cut.r, cut.g, cut.b, cut.a],
+        "bottom edge"
+    );
+    assert_eq!(
+        pixel(&rgba, 100, 50, 50),
+        [background.r, background.g, background.b, background.a],
+        "centre is empty"
+    );
+}
+
+#[test]
+fn test_preview_laser_power_as_intensity() {
+    let options = PreviewOptions {
+        width: 100,
+        height: 100,
+        margin: 10.0,
+        line_width: 3.0,
+        show_rapids: false,
+        laser_intensity: true,
+        max_s_value: 1000.0,
+        ..PreviewOptions::default()
+    };
+    let renderer = PreviewRenderer::new(options.clone());
+    let full = renderer.render_rgba(&square(Some(1000.0))).unwrap();
+    let half = renderer.render_rgba(&square(Some(500.0))).unwrap();
+    let off = renderer.render_rgba(&square(Some(0.0))).unwrap();
+
+    let green = |rgba: &[u8]| pixel(rgba, 100, 50, 89)[1];
+    assert_eq!(green(&full), options.cut_color.g);
+    assert!(green(&half) > options.background.g && green(&half) < options.cut_color.g);
+    assert_eq!(green(&off), options.background.g);
+}
+
+#[test]
+fn test_preview_svg_output() {
+    let renderer = PreviewRenderer::new(PreviewOptions {
+        projection: PreviewProjection::Isometric,
+        ..PreviewOptions::default()
+    });
+    let svg = renderer.render_svg(&square(None));
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(r#"viewBox="0 0 512 512""#));
+    assert!(svg.contains("stroke-dasharray"));
+    assert_eq!(svg.matches("<path").count(), 2);
+    assert!(svg.trim_end().ends_with("</svg>"));
+}
+
+#[test]
+fn test_preview_empty_program() {
+    let renderer = PreviewRenderer::default();
+    let rgba = renderer.render_rgba(&[]).unwrap();
+    assert_eq!(rgba.len(), 512 * 512 * 4);
+    assert!(!renderer.render_svg(&[]).contains("<path"));
+}
+
+#[test]
+fn test_preview_render_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("job.nc");
+    std::fs::write(
+        &input,
+        "G0 X0 Y0 Z5\nG1 Z-1 F300\nG1 X10\nG2 X20 Y0 I5 J0\n",
+    )
+    .unwrap();
+
+    let renderer = PreviewRenderer::new(PreviewOptions::thumbnail(32));
+    renderer
+        .render_file(&input, &dir.path().join("job.png"))
+        .unwrap();
+    renderer
+        .render_file(&input, &dir.path().join("job.svg"))
+        .unwrap();
+    assert!(renderer
+        .render_file(&input, &dir.path().join("job.bmp"))
+        .is_err());
+    assert!(dir.path().join("job.png").metadata().unwrap().len() > 0);
+    assert!(std::fs::read_to_string(dir.path().join("job.svg"))
+        .unwrap()
+        .contains("<path"));
+}
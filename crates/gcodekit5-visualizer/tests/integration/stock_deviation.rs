@@ -0,0 +1,130 @@
+// Integration tests for simulated stock STL export and deviation analysis
+
+use gcodekit5_core::data::tools::ToolProfile;
+use gcodekit5_designer::model3d::{Mesh3D, Model3DImporter};
+use gcodekit5_visualizer::visualizer::{deviation_color, write_mesh_stl};
+use gcodekit5_visualizer::{DeviationMap, DeviationSettings, StockSimulator3D};
+use glam::Vec3;
+
+/// Stock top is at Z0 in program coordinates
+const ORIGIN: Vec3 = Vec3::new(0.0, 0.0, -10.0);
+
+fn simulator() -> StockSimulator3D {
+    // 20 x 20 x 10 mm block
+    StockSimulator3D::with_tool(20.0, 20.0, 10.0, 0.5, ToolProfile::Flat { diameter: 4.0 })
+}
+
+/// Slot along X at the given depth below the stock top
+fn cut_slot(sim: &mut StockSimulator3D, depth: f32) {
+    let z = 10.0 - depth;
+    sim.cut(Vec3::new(5.0, 10.0, z), Vec3::new(15.0, 10.0, z));
+}
+
+fn to_reference(sim: &StockSimulator3D) -> Mesh3D {
+    let mut stl = Vec::new();
+    write_mesh_stl(&sim.get_mesh(), ORIGIN, &mut stl).unwrap();
+    Model3DImporter::new()
+        .with_centering(false)
+        .import_stl_data(&stl)
+        .unwrap()
+}
+
+#[test]
+fn test_export_stl_round_trips() {
+    let mut sim = simulator();
+    cut_slot(&mut sim, 2.0);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stock.stl");
+    let written = sim.export_stl(&path, ORIGIN).unwrap();
+    assert_eq!(written, sim.get_mesh().len() / 30);
+
+    let mesh = Model3DImporter::new()
+        .with_centering(false)
+        .import_stl_file(path.to_str().unwrap())
+        .unwrap();
+    assert_eq!(mesh.triangles.len(), written);
+    // Exported in program coordinates: top at Z0, bottom at Z-10
+    assert!((mesh.bounds_max.z - 0.0).abs() < 1e-4);
+    assert!((mesh.bounds_min.z + 10.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_matching_stock_has_no_deviation() {
+    let mut sim = simulator();
+    cut_slot(&mut sim, 2.0);
+    let reference = to_reference(&sim);
+
+    let map = DeviationMap::compute(
+        sim.get_grid(),
+        ORIGIN,
+        &reference,
+        DeviationSettings::default(),
+    );
+    let stats = map.stats();
+    assert_eq!(stats.samples, 40 * 40);
+    assert_eq!(stats.uncovered, 0);
+    assert_eq!(stats.within_tolerance, stats.samples);
+    assert!(stats.rms < 1e-4);
+    assert!((stats.within_tolerance_percent() - 100.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_overcut_and_undercut_are_reported() {
+    let mut part = simulator();
+    cut_slot(&mut part, 2.0);
+    let reference = to_reference(&part);
+    let settings = DeviationSettings::default();
+
+    // Cutting 1 mm too deep gouges the part
+    let mut deep = simulator();
+    cut_slot(&mut deep, 3.0);
+    let map = DeviationMap::compute(deep.get_grid(), ORIGIN, &reference, settings);
+    let stats = map.stats();
+    assert!(stats.overcut > 0);
+    assert_eq!(stats.undercut, 0);
+    assert!((stats.max_overcut - 1.0).abs() < 1e-4);
+    assert!(stats.mean < 0.0);
+    // Slot centre is over-cut, the untouched corner is on target
+    assert!((map.deviation_at(20, 20).unwrap() + 1.0).abs() < 1e-4);
+    assert!(map.deviation_at(0, 0).unwrap().abs() < 1e-4);
+    assert_eq!(map.deviation_at(40, 0), None);
+
+    // Stopping 1 mm short leaves material behind
+    let mut shallow = simulator();
+    cut_slot(&mut shallow, 1.0);
+    let stats = *DeviationMap::compute(shallow.get_grid(), ORIGIN, &reference, settings).stats();
+    assert!(stats.undercut > 0);
+    assert_eq!(stats.overcut, 0);
+    assert!((stats.max_undercut - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_deviation_colour_map() {
+    let mut part = simulator();
+    cut_slot(&mut part, 2.0);
+    let reference = to_reference(&part);
+    let settings = DeviationSettings::default();
+
+    let mut deep = simulator();
+    cut_slot(&mut deep, 3.0);
+    let map = DeviationMap::compute(deep.get_grid(), ORIGIN, &reference, settings);
+
+    // One quad per pair of neighbouring columns, 10 floats per vertex
+    let mesh = map.to_mesh();
+    assert_eq!(mesh.len(), 39 * 39 * 6 * 10);
+
+    let on_target = deviation_color(0.0, &settings);
+    let gouge = deviation_color(-1.0, &settings);
+    let left = deviation_color(1.0, &settings);
+    assert!(gouge[0] > on_target[0] && gouge[1] < on_target[1]);
+    assert!(left[2] > on_target[2] && left[1] < on_target[1]);
+    assert_eq!(deviation_color(0.01, &settings), on_target);
+
+    let colors: Vec<[f32; 4]> = mesh
+        .chunks_exact(10)
+        .map(|v| [v[6], v[7], v[8], v[9]])
+        .collect();
+    assert!(colors.contains(&gouge));
+    assert!(colors.contains(&on_target));
+}
@@ -0,0 +1,206 @@
+// Integration tests for streaming G-code parsing and level-of-detail buffers
+
+use std::io::Write;
+
+use gcodekit5_visualizer::{
+    GCodeCommand, GcodeStreamParser, ParseEvent, ParseJob, Point3D, ToolpathBuffers, Visualizer,
+};
+
+const PROGRAM: &str = "G21\nG0 X0 Y0 Z5\n; comment\nG1 Z-1 F300 S500\nG1 X10 Y0\nG2 X20 Y0 I5 J0\nG4 P0.5\nG3 X10 Y0 I-5 J0 S800\nG0 Z5\n";
+
+/// Laser-style raster: `rows` back-and-forth rows of `pixels` short moves
+fn raster(rows: usize, pixels: usize, step: f32) -> String {
+    let mut out = String::from("G0 X0 Y0\nG1 F3000\n");
+    for row in 0..rows {
+        let y = row as f32 * step;
+        out.push_str(&format!("G0 Y{:.3}\n", y));
+        for p in 1..=pixels {
+            let x = if row % 2 == 0 { p } else { pixels - p } as f32 * step;
+            out.push_str(&format!("G1 X{:.3} S{}\n", x, (p / 8) * 100));
+        }
+    }
+    out
+}
+
+fn wait_finished(job: ParseJob) -> gcodekit5_visualizer::ParsedToolpath {
+    match job.wait() {
+        ParseEvent::Finished(parsed) => *parsed,
+        other => panic!("parse did not finish: {:?}", other),
+    }
+}
+
+#[test]
+fn test_block_numbers_and_modal_moves() {
+    let program = "N10 G0 X0 Y0 Z5\nN20 G1 Z-1 F300\nN30 X10\nY10\nN40 G2 X20 I5\nN50 G0 Z5\n";
+    let mut parser = GcodeStreamParser::new();
+    for line in program.lines() {
+        parser.parse_line(line);
+    }
+    let commands = parser.finish().buffers.to_commands();
+    assert_eq!(commands.len(), 6);
+
+    match &commands[3] {
+        GCodeCommand::Move { to, rapid, .. } => {
+            assert_eq!(*to, Point3D::new(10.0, 10.0, -1.0));
+            assert!(!*rapid);
+        }
+        other => panic!("expected move, got {:?}", other),
+    }
+    // Omitted Y and J keep the current Y and a zero offset
+    match &commands[4] {
+        GCodeCommand::Arc { to, center, .. } => {
+            assert_eq!(*to, Point3D::new(20.0, 10.0, -1.0));
+            assert_eq!((center.x, center.y), (15.0, 10.0));
+        }
+        other => panic!("expected arc, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_stream_parser_matches_parse_gcode() {
+    let mut vis = Visualizer::new();
+    vis.parse_gcode(PROGRAM);
+
+    let mut parser = GcodeStreamParser::new();
+    for line in PROGRAM.lines() {
+        parser.parse_line(line);
+    }
+    let parsed = parser.finish();
+
+    assert_eq!(parsed.buffers.len(), vis.get_command_count());
+    assert_eq!(parsed.buffers.lines(), vis.command_lines());
+    assert_eq!(vis.command_lines(), &[2, 4, 5, 6, 7, 8, 9]);
+    assert_eq!(
+        format!("{:?}", parsed.buffers.to_commands()),
+        format!("{:?}", vis.commands())
+    );
+    assert_eq!(parsed.final_position, Point3D::new(10.0, 0.0, 5.0));
+    assert_eq!(parsed.final_intensity, 800.0);
+
+    match &vis.commands()[3] {
+        GCodeCommand::Arc {
+            center, clockwise, ..
+        } => {
+            assert_eq!(*center, Point3D::new(15.0, 0.0, -1.0));
+            assert!(*clockwise);
+        }
+        other => panic!("expected arc, got {:?}", other),
+    }
+    assert!(matches!(
+        vis.commands()[4],
+        GCodeCommand::Dwell { duration, .. } if duration == 0.5
+    ));
+}
+
+#[test]
+fn test_parse_job_streams_file() {
+    let gcode = raster(60, 400, 0.1);
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(gcode.as_bytes()).unwrap();
+
+    let job = ParseJob::spawn_file(file.path()).unwrap();
+    let mut progress = Vec::new();
+    let parsed = loop {
+        match job.try_next() {
+            Some(ParseEvent::Progress(p)) => progress.push(p),
+            Some(ParseEvent::Finished(parsed)) => break *parsed,
+            Some(other) => panic!("unexpected event {:?}", other),
+            None => std::thread::yield_now(),
+        }
+    };
+
+    assert!(progress.len() >= 2);
+    assert!(progress
+        .windows(2)
+        .all(|w| w[0].bytes_read <= w[1].bytes_read));
+    let last = progress.last().unwrap();
+    assert_eq!(last.percent(), 100.0);
+    assert_eq!(last.lines_read, gcode.lines().count() as u64);
+
+    let mut expected = Visualizer::new();
+    expected.parse_gcode(&gcode);
+    // Both paths hash the content the same way
+    assert!(!expected.apply_parsed(parsed.clone()));
+
+    let mut vis = Visualizer::new();
+    assert!(vis.apply_parsed(parsed.clone()));
+    assert_eq!(vis.get_command_count(), expected.get_command_count());
+    assert_eq!(vis.get_bounds(), expected.get_bounds());
+    assert_eq!(vis.get_cutting_bounds(), expected.get_cutting_bounds());
+    assert!(vis.is_dirty());
+
+    // Same content again is a no-op
+    vis.clear_dirty();
+    assert!(!vis.apply_parsed(parsed));
+    assert!(!vis.is_dirty());
+}
+
+#[test]
+fn test_parse_job_cancel_and_missing_file() {
+    let job = ParseJob::spawn_text(raster(400, 2000, 0.05));
+    job.cancel();
+    assert!(job.is_cancelled());
+    assert!(matches!(job.wait(), ParseEvent::Cancelled));
+
+    assert!(ParseJob::spawn_file("/nonexistent/path/to/file.nc").is_err());
+}
+
+#[test]
+fn test_buffers_are_smaller_than_commands() {
+    let parsed = wait_finished(ParseJob::spawn_text(raster(20, 500, 0.1)));
+    let commands = parsed.buffers.to_commands();
+    let command_bytes = commands.len() * std::mem::size_of::<GCodeCommand>();
+    assert!(parsed.buffers.memory_bytes() * 2 < command_bytes);
+}
+
+#[test]
+fn test_simplify_merges_collinear_moves() {
+    let mut buffers = ToolpathBuffers::new();
+    for i in 1..=1000 {
+        buffers.push_move(
+            Point3D::new(i as f32 * 0.1, i as f32 * 0.05, 0.0),
+            false,
+            300.0,
+            i,
+        );
+    }
+    // A change of S value or direction starts a new segment
+    buffers.push_move(Point3D::new(110.0, 50.0, 0.0), false, 600.0, 1001);
+    buffers.push_move(Point3D::new(110.0, 60.0, 0.0), false, 600.0, 1002);
+    buffers.push_move(Point3D::new(110.0, 50.0, 0.0), false, 600.0, 1003);
+
+    let simplified = buffers.simplify(0.001);
+    assert_eq!(simplified.len(), 4);
+    assert_eq!(simplified.lines(), &[1000, 1001, 1002, 1003]);
+    assert_eq!(simplified.end_point(0), buffers.end_point(999));
+    assert_eq!(simplified.intensity(0), 300.0);
+
+    // Rapids are never merged with cutting moves
+    let mut mixed = ToolpathBuffers::new();
+    mixed.push_move(Point3D::new(1.0, 0.0, 0.0), true, 0.0, 1);
+    mixed.push_move(Point3D::new(2.0, 0.0, 0.0), false, 0.0, 2);
+    assert_eq!(mixed.simplify(0.5).len(), 2);
+}
+
+#[test]
+fn test_lod_decimates_raster_at_low_zoom() {
+    let mut vis = Visualizer::new();
+    vis.parse_gcode(&raster(200, 400, 0.1));
+    let full = vis.buffers().len();
+
+    // Zoomed in far enough, nothing may be dropped
+    assert_eq!(vis.lod_for_scale(10_000.0).len(), full);
+
+    let near = vis.lod_for_scale(20.0).len();
+    let far = vis.lod_for_scale(0.2).len();
+    assert!(!vis.lod().is_empty());
+    assert!(near < full);
+    assert!(far * 10 < full, "far={} full={}", far, full);
+
+    // Simplified geometry still covers the same area
+    let (_, coarsest) = vis.lod().level(vis.lod().len() - 1).unwrap();
+    let bounds = coarsest.bounds();
+    let full_bounds = vis.buffers().bounds();
+    assert!((bounds.max_x - full_bounds.max_x).abs() < 3.5);
+    assert!((bounds.max_y - full_bounds.max_y).abs() < 3.5);
+}
@@ -0,0 +1,158 @@
+// Integration tests for line/Z filtering and timeline scrubbing
+
+use std::time::Duration;
+
+use gcodekit5_visualizer::{
+    Point3D, TimelinePlayback, ToolpathFilter, ToolpathTimeline, Visualizer,
+};
+
+/// Two 10 mm square passes at Z-1 and Z-4 with rapids in between
+const PROGRAM: &str = "G21\n\
+G0 X0 Y0 Z5\n\
+G1 Z-1 F600\n\
+G1 X10 F1200\n\
+G1 Y10\n\
+G1 X0\n\
+G1 Y0\n\
+G0 Z5\n\
+G1 Z-4 F600\n\
+G1 X10 F1200\n\
+G1 Y10\n\
+G1 X0\n\
+G1 Y0\n\
+G0 Z5\n";
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+fn close_point(a: Point3D, b: Point3D) -> bool {
+    (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3 && (a.z - b.z).abs() < 1e-3
+}
+
+#[test]
+fn test_filter_by_line_and_z() {
+    let mut vis = Visualizer::new();
+    vis.parse_gcode(PROGRAM);
+    let buffers = vis.buffers();
+
+    let lines = ToolpathFilter::new().with_line_range(11, 4);
+    assert_eq!(lines.line_range, Some((4, 11)));
+    let range = lines.segment_range(buffers);
+    assert_eq!(&buffers.lines()[range.clone()], &[4, 5, 6, 7, 8, 9, 10, 11]);
+    assert!(range.clone().all(|i| lines.matches(buffers, i)));
+
+    // Only the deep pass, plus the plunge that reaches it
+    let deep = ToolpathFilter::new().with_z_range(-10.0, -3.0);
+    let shown: Vec<u32> = (0..buffers.len())
+        .filter(|&i| deep.matches(buffers, i))
+        .map(|i| buffers.lines()[i])
+        .collect();
+    assert_eq!(shown, vec![9, 10, 11, 12, 13, 14]);
+    assert_eq!(deep.iter(buffers).count(), shown.len());
+    assert_eq!(lines.iter(buffers).count(), 8);
+    assert_eq!(ToolpathFilter::new().iter(buffers).count(), buffers.len());
+
+    assert!(!ToolpathFilter::new().is_active());
+    vis.clear_dirty();
+    vis.set_filter(deep);
+    assert!(vis.is_dirty());
+    assert_eq!(*vis.filter(), deep);
+}
+
+#[test]
+fn test_timeline_uses_feed_rates() {
+    let mut vis = Visualizer::new();
+    vis.set_rapid_rate(6000.0);
+    vis.parse_gcode(PROGRAM);
+    let timeline = vis.timeline();
+    assert_eq!(timeline.len(), vis.buffers().len());
+
+    // Rapid to Z5 (5 mm at 6000), plunge 6 mm at 600, square 40 mm at 1200,
+    // retract 6 mm rapid, plunge 9 mm at 600, square 40 mm, retract 9 mm
+    let expected = 0.05 + 0.6 + 2.0 + 0.06 + 0.9 + 2.0 + 0.09;
+    assert!(
+        close(timeline.total_time(), expected),
+        "{}",
+        timeline.total_time()
+    );
+
+    // A faster rapid rate rebuilds the estimate
+    vis.set_rapid_rate(12000.0);
+    assert!(close(vis.timeline().total_time(), expected - 0.1));
+}
+
+#[test]
+fn test_scrub_position_and_line_sync() {
+    let mut vis = Visualizer::new();
+    vis.set_rapid_rate(6000.0);
+    vis.parse_gcode(PROGRAM);
+    let timeline = vis.timeline();
+    let buffers = vis.buffers();
+
+    // Halfway along the first X move (line 4, starts at 0.65 s, takes 0.5 s)
+    assert!(close_point(
+        vis.position_at_time(0.9),
+        Point3D::new(5.0, 0.0, -1.0)
+    ));
+    assert_eq!(timeline.line_at(buffers, 0.9), Some(4));
+
+    // Selecting a line puts the tool where that line leaves it
+    let t = timeline.time_at_line(buffers, 5);
+    assert!(close(t, 1.65));
+    assert!(close_point(
+        vis.position_at_time(t),
+        Point3D::new(10.0, 10.0, -1.0)
+    ));
+    assert_eq!(timeline.line_at(buffers, t), Some(5));
+
+    // Before the first move and after the last
+    assert!(close(timeline.time_at_line(buffers, 1), 0.0));
+    assert!(close(
+        timeline.time_at_line(buffers, 1000),
+        timeline.total_time()
+    ));
+    assert!(close_point(
+        vis.position_at_time(1e9),
+        Point3D::new(0.0, 0.0, 5.0)
+    ));
+}
+
+#[test]
+fn test_arcs_and_dwells() {
+    // Half circle of radius 10 at F600 then a 2 s dwell
+    let mut vis = Visualizer::new();
+    vis.parse_gcode("G0 X0 Y0\nG1 X10 F600\nG3 X-10 Y0 I-10 J0\nG4 P2\n");
+    let timeline = ToolpathTimeline::build(vis.buffers(), 3000.0);
+
+    let half_circle = std::f64::consts::PI * 10.0 / 600.0 * 60.0;
+    assert!(close(timeline.end_time(2), 1.0 + half_circle));
+    assert!(close(timeline.total_time(), 3.0 + half_circle));
+
+    // Quarter way round is the top of the circle
+    let top = timeline.position_at(vis.buffers(), 1.0 + half_circle / 2.0);
+    assert!(close_point(top, Point3D::new(0.0, 10.0, 0.0)));
+}
+
+#[test]
+fn test_playback_is_time_accurate() {
+    let mut playback = TimelinePlayback::new();
+    playback.set_speed(4.0);
+
+    // Paused playback does not move
+    assert_eq!(playback.advance(Duration::from_millis(500), 10.0), 0.0);
+
+    playback.play();
+    assert!(close(
+        playback.advance(Duration::from_millis(500), 10.0),
+        2.0
+    ));
+    assert!(close(
+        playback.advance(Duration::from_millis(250), 10.0),
+        3.0
+    ));
+
+    // Stops at the end
+    assert!(close(playback.advance(Duration::from_secs(5), 10.0), 10.0));
+    assert!(!playback.is_playing());
+}
@@ -0,0 +1,152 @@
+// Integration tests for geometric G-code comparison
+
+use std::f32::consts::TAU;
+
+use gcodekit5_visualizer::{DiffKind, DiffSettings, FileComparison, ToolpathDiff};
+
+/// 10 mm square at Z-1
+const SQUARE: &str = "G21\n\
+G90\n\
+G0 X0 Y0 Z5\n\
+G1 Z-1 F300\n\
+G1 X10 F600\n\
+G1 Y10\n\
+G1 X0\n\
+G1 Y0\n\
+G0 Z5\n";
+
+fn compare(original: &str, modified: &str) -> ToolpathDiff {
+    ToolpathDiff::compare(original, modified, DiffSettings::default())
+}
+
+fn close(a: f32, b: f32, tolerance: f32) -> bool {
+    (a - b).abs() < tolerance
+}
+
+#[test]
+fn test_reposted_program_is_equivalent() {
+    // Block numbers, modal moves and different number formatting
+    let reposted = "N10 G21 G90\n\
+N20 G0 X0.000 Y0.000 Z5.000\n\
+N30 G1 Z-1.000 F300.\n\
+N40 X10.000 F600.\n\
+N50 Y10.000\n\
+N60 X0.000\n\
+N70 Y0.000\n\
+N80 G0 Z5.000\n";
+
+    let text = FileComparison::new(SQUARE, reposted);
+    assert!(text.modified_count > 0);
+
+    let diff = compare(SQUARE, reposted);
+    assert!(diff.is_equivalent(), "{:?}", diff.regions);
+    assert_eq!(diff.original, diff.modified);
+    assert_eq!(diff.time_change(), 0.0);
+    assert!(!diff.cuts_new_material());
+    assert_eq!(diff.original_cuts().len(), 1);
+}
+
+#[test]
+fn test_linearised_arc_matches_arc() {
+    // Full circle of radius 10 as one G2, and as 64 chords
+    let arc = "G0 X10 Y0 Z1\nG1 Z-1 F500\nG2 X10 Y0 I-10 J0\nG0 Z1\n";
+    let mut chords = String::from("G0 X10 Y0 Z1\nG1 Z-1 F500\n");
+    for i in 1..=64 {
+        let angle = -(i as f32) / 64.0 * TAU;
+        chords.push_str(&format!(
+            "G1 X{:.4} Y{:.4}\n",
+            10.0 * angle.cos(),
+            10.0 * angle.sin()
+        ));
+    }
+    chords.push_str("G0 Z1\n");
+
+    let diff = compare(arc, &chords);
+    assert!(diff.is_equivalent(), "{:?}", diff.regions);
+    // The polygon is slightly shorter than the circle
+    assert!(diff.time_change() < 0.0);
+}
+
+#[test]
+fn test_deeper_pass_is_added() {
+    let deeper = SQUARE.replace("G0 Z5\n", "G1 Z-2 F300\nG1 X10 F600\nG0 Z5\n");
+    let diff = compare(SQUARE, &deeper);
+
+    let added: Vec<_> = diff.regions_of(DiffKind::Added).collect();
+    assert_eq!(added.len(), 1);
+    assert_eq!(diff.regions_of(DiffKind::Removed).count(), 0);
+    let region = added[0];
+    assert_eq!((region.first_line, region.last_line), (9, 10));
+    assert!(close(region.min_z, -2.0, 1e-4));
+    assert!(close(region.max_deviation, 1.0, 1e-3));
+    // Everything beyond the tolerance of the Z-1 square: the plunge's
+    // lower part and the 10 mm slot
+    assert!(region.length > 10.0 && region.length < 11.0);
+
+    assert!(close(diff.depth_change().unwrap(), -1.0, 1e-4));
+    assert!(diff.cuts_new_material());
+    assert!(diff.time_change() > 0.0);
+}
+
+#[test]
+fn test_dropped_side_is_removed() {
+    let three_sides = SQUARE.replace("G1 Y0\n", "");
+    let diff = compare(SQUARE, &three_sides);
+
+    assert_eq!(diff.regions_of(DiffKind::Added).count(), 0);
+    let removed: Vec<_> = diff.regions_of(DiffKind::Removed).collect();
+    assert_eq!(removed.len(), 1);
+    // Line numbers refer to the original program
+    assert_eq!((removed[0].first_line, removed[0].last_line), (8, 8));
+    assert!(close(diff.changed_length(DiffKind::Removed), 9.75, 0.3));
+    assert!(!diff.cuts_new_material());
+    assert!(diff.time_change() < 0.0);
+}
+
+#[test]
+fn test_offset_beyond_tolerance() {
+    let shifted = |dx: f32| {
+        format!(
+            "G0 X{dx} Y0 Z5\nG1 Z-1 F300\nG1 X{} F600\nG0 Z5\n",
+            10.0 + dx
+        )
+    };
+    let original = shifted(0.0);
+
+    // Within the 0.05 mm tolerance: same cut
+    let within = shifted(0.0).replace("Y0", "Y0.02");
+    assert!(compare(&original, &within).is_equivalent());
+
+    // Half a millimetre off: the line is cut somewhere else
+    let moved = shifted(0.0).replace("Y0", "Y0.5");
+    let diff = compare(&original, &moved);
+    let added: Vec<_> = diff.regions_of(DiffKind::Added).collect();
+    let removed: Vec<_> = diff.regions_of(DiffKind::Removed).collect();
+    assert_eq!((added.len(), removed.len()), (1, 1));
+    assert!(close(added[0].max_deviation, 0.5, 1e-3));
+    assert!(close(removed[0].max_deviation, 0.5, 1e-3));
+
+    // Sliding 2 mm along X moves the plunge, and the slot only overlaps
+    // apart from its ends
+    let diff = compare(&original, &shifted(2.0));
+    let added: Vec<_> = diff.regions_of(DiffKind::Added).collect();
+    assert_eq!(added.len(), 2);
+    assert!(close(added[0].length, 6.0, 0.3));
+    assert!(close(added[1].length, 2.0, 0.3));
+    assert_eq!(added[1].first_line, 3);
+    assert!(close(diff.changed_length(DiffKind::Removed), 8.0, 0.5));
+}
+
+#[test]
+fn test_feed_and_time_changes() {
+    let faster = SQUARE.replace("F600", "F1200");
+    let diff = compare(SQUARE, &faster);
+    assert!(diff.is_equivalent());
+    assert!(close(diff.original.max_feed, 600.0, 1e-3));
+    assert!(close(diff.modified.max_feed, 1200.0, 1e-3));
+    assert!(diff.feed_change() > 0.0);
+    // 40 mm of sides at twice the speed saves two seconds
+    assert!((diff.time_change() + 2.0).abs() < 1e-3);
+    assert_eq!(diff.depth_change(), Some(0.0));
+    assert_eq!(diff.original.min_rapid_z, Some(5.0));
+}
@@ -0,0 +1,440 @@
+//! Command line entry points that run without the GUI.
+//!
+//! `gcodekit5 preview` renders toolpath previews for G-code files, e.g. to
+//! add thumbnails to a job archive:
+//!
+//! ```text
+//! gcodekit5 preview jobs/ --output previews/ --size 256x256 --iso
+//! ```
+//!
+//! `gcodekit5 report` writes a job analysis report to attach to quotes and
+//! job travellers:
+//!
+//! ```text
+//! gcodekit5 report part.nc --output part-report.html
+//! ```
+
+use anyhow::{anyhow, bail, Context};
+use gcodekit5_camtools::validator::{GCodeValidator, ValidatorConfig};
+use gcodekit5_visualizer::{
+    JobReport, PreviewColor, PreviewFormat, PreviewOptions, PreviewProjection, PreviewRenderer,
+    ReportFormat, ReportOptions, ValidationIssue, ValidationSeverity,
+};
+use std::path::{Path, PathBuf};
+
+/// File extensions treated as G-code when a directory is given
+const GCODE_EXTENSIONS: [&str; 5] = ["gcode", "nc", "gc", "tap", "ngc"];
+
+const PREVIEW_USAGE: &str = "\
+Usage: gcodekit5 preview <FILE|DIR>... [OPTIONS]
+
+Render toolpath previews of G-code files without opening the GUI.
+Directories are searched recursively for G-code files.
+
+Options:
+  -o, --output <PATH>      Output file (single input) or directory
+  -f, --format <png|svg>   Image format [default: png]
+  -s, --size <WxH>         Image size in pixels [default: 512x512]
+      --iso                Isometric view instead of top-down
+      --laser              Shade cuts by S value (laser power)
+      --max-s <VALUE>      S value drawn at full intensity [default: 1000]
+      --no-rapids          Do not draw rapid moves
+      --rapid-color <HEX>  Rapid move colour, e.g. #e5a50a
+      --cut-color <HEX>    Cutting move colour, e.g. #2ec27e
+      --background <HEX>   Background colour, e.g. #262626ff
+  -h, --help               Show this help";
+
+const REPORT_USAGE: &str = "\
+Usage: gcodekit5 report <FILE> [OPTIONS]
+
+Write a job analysis report: extents, cut and rapid distance and time per
+tool, feed and spindle histograms, tool changes and validator warnings.
+
+Options:
+  -o, --output <PATH>           Output file [default: <FILE>.html]
+  -f, --format <html|json|csv>  Report format [default: from --output, or html]
+      --rapid-rate <MM_MIN>     Rapid rate used for time estimates [default: 3000]
+      --no-thumbnail            Leave out the toolpath thumbnail
+  -h, --help                    Show this help";
+
+/// Parsed `preview` command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewArgs {
+    pub inputs: Vec<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub format: PreviewFormat,
+    pub options: PreviewOptions,
+}
+
+impl PreviewArgs {
+    /// Parse the arguments following `preview`
+    pub fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut parsed = Self {
+            inputs: Vec::new(),
+            output: None,
+            format: PreviewFormat::Png,
+            options: PreviewOptions::default(),
+        };
+        let mut format = None;
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let mut value = |name: &str| {
+                iter.next()
+                    .ok_or_else(|| anyhow!("missing value for {}", name))
+            };
+            let color = |name: &str, hex: &str| {
+                PreviewColor::from_hex(hex)
+                    .ok_or_else(|| anyhow!("invalid colour for {}: {}", name, hex))
+            };
+            match arg.as_str() {
+                "-o" | "--output" => parsed.output = Some(PathBuf::from(value(arg)?)),
+                "-f" | "--format" => {
+                    format = Some(match value(arg)?.to_ascii_lowercase().as_str() {
+                        "png" => PreviewFormat::Png,
+                        "svg" => PreviewFormat::Svg,
+                        other => bail!("unsupported format: {}", other),
+                    })
+                }
+                "-s" | "--size" => {
+                    let size = value(arg)?;
+                    let (w, h) = size
+                        .split_once(['x', 'X'])
+                        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                        .filter(|&(w, h): &(u32, u32)| w > 0 && h > 0)
+                        .ok_or_else(|| anyhow!("invalid size: {} (expected WxH)", size))?;
+                    parsed.options.width = w;
+                    parsed.options.height = h;
+                }
+                "--iso" => parsed.options.projection = PreviewProjection::Isometric,
+                "--laser" => parsed.options.laser_intensity = true,
+                "--max-s" => {
+                    let max = value(arg)?;
+                    parsed.options.max_s_value = max
+                        .parse()
+                        .ok()
+                        .filter(|v: &f32| *v > 0.0)
+                        .ok_or_else(|| anyhow!("invalid --max-s value: {}", max))?;
+                }
+                "--no-rapids" => parsed.options.show_rapids = false,
+                "--rapid-color" => parsed.options.rapid_color = color(arg, value(arg)?)?,
+                "--cut-color" => parsed.options.cut_color = color(arg, value(arg)?)?,
+                "--background" => parsed.options.background = color(arg, value(arg)?)?,
+                other if other.starts_with('-') => bail!("unknown option: {}", other),
+                input => parsed.inputs.push(PathBuf::from(input)),
+            }
+        }
+
+        if parsed.inputs.is_empty() {
+            bail!("no input files given");
+        }
+        // Without --format, a single output file decides the format
+        parsed.format = format
+            .or_else(|| parsed.output.as_deref().and_then(PreviewFormat::from_path))
+            .unwrap_or(PreviewFormat::Png);
+        Ok(parsed)
+    }
+}
+
+/// Parsed `report` command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportArgs {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub format: ReportFormat,
+    pub options: ReportOptions,
+}
+
+impl ReportArgs {
+    /// Parse the arguments following `report`
+    pub fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut input = None;
+        let mut output: Option<PathBuf> = None;
+        let mut format = None;
+        let mut options = ReportOptions::default();
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let mut value = |name: &str| {
+                iter.next()
+                    .ok_or_else(|| anyhow!("missing value for {}", name))
+            };
+            match arg.as_str() {
+                "-o" | "--output" => output = Some(PathBuf::from(value(arg)?)),
+                "-f" | "--format" => {
+                    format = Some(match value(arg)?.to_ascii_lowercase().as_str() {
+                        "html" => ReportFormat::Html,
+                        "json" => ReportFormat::Json,
+                        "csv" => ReportFormat::Csv,
+                        other => bail!("unsupported format: {}", other),
+                    })
+                }
+                "--rapid-rate" => {
+                    let rate = value(arg)?;
+                    options.rapid_rate_mm_min = rate
+                        .parse()
+                        .ok()
+                        .filter(|v: &f32| *v > 0.0)
+                        .ok_or_else(|| anyhow!("invalid --rapid-rate value: {}", rate))?;
+                }
+                "--no-thumbnail" => options.thumbnail_size = 0,
+                other if other.starts_with('-') => bail!("unknown option: {}", other),
+                file if input.is_none() => input = Some(PathBuf::from(file)),
+                file => bail!("only one input file is supported: {}", file),
+            }
+        }
+
+        let input = input.ok_or_else(|| anyhow!("no input file given"))?;
+        let format = format
+            .or_else(|| output.as_deref().and_then(ReportFormat::from_path))
+            .unwrap_or(ReportFormat::Html);
+        let output = output.unwrap_or_else(|| input.with_extension(format.extension()));
+        Ok(Self {
+            input,
+            output,
+            format,
+            options,
+        })
+    }
+}
+
+/// Run a command line subcommand if one was given.
+///
+/// Returns `None` when the arguments do not name a subcommand and the GUI
+/// should start instead.
+pub fn run(args: &[String]) -> Option<anyhow::Result<()>> {
+    match args.get(1).map(String::as_str) {
+        Some("preview") => {
+            let rest = &args[2..];
+            if rest.iter().any(|a| a == "-h" || a == "--help") {
+                println!("{}", PREVIEW_USAGE);
+                return Some(Ok(()));
+            }
+            match PreviewArgs::parse(rest) {
+                Ok(args) => Some(run_preview(&args)),
+                Err(e) => {
+                    eprintln!("{}\n", PREVIEW_USAGE);
+                    Some(Err(e))
+                }
+            }
+        }
+        Some("report") => {
+            let rest = &args[2..];
+            if rest.iter().any(|a| a == "-h" || a == "--help") {
+                println!("{}", REPORT_USAGE);
+                return Some(Ok(()));
+            }
+            match ReportArgs::parse(rest) {
+                Ok(args) => Some(run_report(&args)),
+                Err(e) => {
+                    eprintln!("{}\n", REPORT_USAGE);
+                    Some(Err(e))
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Render a preview for every input file
+pub fn run_preview(args: &PreviewArgs) -> anyhow::Result<()> {
+    let mut jobs = Vec::new();
+    for input in &args.inputs {
+        if input.is_dir() {
+            collect_gcode_files(input, input, &mut jobs)?;
+        } else if input.is_file() {
+            let name = input.file_name().map(PathBuf::from).unwrap_or_default();
+            jobs.push((input.clone(), name));
+        } else {
+            bail!("input not found: {}", input.display());
+        }
+    }
+    if jobs.is_empty() {
+        bail!("no G-code files found");
+    }
+
+    let extension = match args.format {
+        PreviewFormat::Png => "png",
+        PreviewFormat::Svg => "svg",
+    };
+    // A single file given as a file path is written exactly there
+    let single_output = args
+        .output
+        .as_ref()
+        .filter(|out| jobs.len() == 1 && !out.is_dir() && out.extension().is_some());
+
+    let renderer = PreviewRenderer::new(args.options.clone());
+    let mut failed = 0;
+    for (input, relative) in &jobs {
+        let output = match (single_output, &args.output) {
+            (Some(out), _) => out.clone(),
+            (None, Some(dir)) => dir.join(relative).with_extension(extension),
+            (None, None) => input.with_extension(extension),
+        };
+        if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("creating {}", parent.display()))?;
+        }
+
+        let result = std::fs::read_to_string(input)
+            .map_err(anyhow::Error::from)
+            .and_then(|gcode| {
+                match args.format {
+                    PreviewFormat::Png => {
+                        std::fs::write(&output, renderer.render_gcode_png(&gcode)?)?
+                    }
+                    PreviewFormat::Svg => {
+                        std::fs::write(&output, renderer.render_gcode_svg(&gcode))?
+                    }
+                }
+                Ok(())
+            });
+        match result {
+            Ok(()) => println!("{} -> {}", input.display(), output.display()),
+            Err(e) => {
+                eprintln!("{}: {}", input.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} of {} previews failed", failed, jobs.len());
+    }
+    Ok(())
+}
+
+/// Write the analysis report for the input file
+pub fn run_report(args: &ReportArgs) -> anyhow::Result<()> {
+    let gcode = std::fs::read_to_string(&args.input)
+        .with_context(|| format!("reading {}", args.input.display()))?;
+    let name = args
+        .input
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let lines: Vec<String> = gcode.lines().map(str::to_string).collect();
+    let issues = GCodeValidator::new(ValidatorConfig::default())
+        .validate(&lines)
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| ValidationIssue::new(e.line as u32 + 1, ValidationSeverity::Warning, e.message));
+    let report = JobReport::generate(name, &gcode, &args.options).with_issues(issues);
+
+    std::fs::write(&args.output, report.render(args.format)?)
+        .with_context(|| format!("writing {}", args.output.display()))?;
+    println!("{} -> {}", args.input.display(), args.output.display());
+    Ok(())
+}
+
+fn collect_gcode_files(
+    root: &Path,
+    dir: &Path,
+    jobs: &mut Vec<(PathBuf, PathBuf)>,
+) -> anyhow::Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_gcode_files(root, &path, jobs)?;
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| GCODE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            jobs.push((path, relative));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_preview_args() {
+        let parsed = PreviewArgs::parse(&args(&[
+            "a.nc",
+            "jobs",
+            "-o",
+            "out",
+            "--size",
+            "256x128",
+            "--iso",
+            "--cut-color",
+            "#ff0000",
+        ]))
+        .unwrap();
+        assert_eq!(
+            parsed.inputs,
+            vec![PathBuf::from("a.nc"), PathBuf::from("jobs")]
+        );
+        assert_eq!(parsed.output, Some(PathBuf::from("out")));
+        assert_eq!(parsed.format, PreviewFormat::Png);
+        assert_eq!((parsed.options.width, parsed.options.height), (256, 128));
+        assert_eq!(parsed.options.projection, PreviewProjection::Isometric);
+        assert_eq!(parsed.options.cut_color, PreviewColor::rgba(255, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_parse_format_from_output() {
+        let parsed = PreviewArgs::parse(&args(&["a.nc", "-o", "a.svg"])).unwrap();
+        assert_eq!(parsed.format, PreviewFormat::Svg);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(PreviewArgs::parse(&args(&[])).is_err());
+        assert!(PreviewArgs::parse(&args(&["a.nc", "--size", "big"])).is_err());
+        assert!(PreviewArgs::parse(&args(&["a.nc", "--format", "bmp"])).is_err());
+        assert!(PreviewArgs::parse(&args(&["a.nc", "--bogus"])).is_err());
+        assert!(PreviewArgs::parse(&args(&["a.nc", "--output"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_report_args() {
+        let parsed = ReportArgs::parse(&args(&["part.nc"])).unwrap();
+        assert_eq!(parsed.input, PathBuf::from("part.nc"));
+        assert_eq!(parsed.output, PathBuf::from("part.html"));
+        assert_eq!(parsed.format, ReportFormat::Html);
+
+        let parsed = ReportArgs::parse(&args(&[
+            "part.nc",
+            "-o",
+            "out/part.csv",
+            "--rapid-rate",
+            "5000",
+            "--no-thumbnail",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.output, PathBuf::from("out/part.csv"));
+        assert_eq!(parsed.format, ReportFormat::Csv);
+        assert_eq!(parsed.options.rapid_rate_mm_min, 5000.0);
+        assert_eq!(parsed.options.thumbnail_size, 0);
+
+        let parsed = ReportArgs::parse(&args(&["part.nc", "-f", "json"])).unwrap();
+        assert_eq!(parsed.output, PathBuf::from("part.json"));
+
+        assert!(ReportArgs::parse(&args(&[])).is_err());
+        assert!(ReportArgs::parse(&args(&["a.nc", "b.nc"])).is_err());
+        assert!(ReportArgs::parse(&args(&["a.nc", "--format", "pdf"])).is_err());
+        assert!(ReportArgs::parse(&args(&["a.nc", "--rapid-rate", "0"])).is_err());
+    }
+
+    #[test]
+    fn test_run_ignores_gui_arguments() {
+        assert!(run(&args(&["gcodekit5"])).is_none());
+        assert!(run(&args(&["gcodekit5", "job.nc"])).is_none());
+    }
+}
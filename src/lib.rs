@@ -42,25 +42,30 @@ pub use gcodekit5_core::{
     ControllerListener, ControllerListenerHandle, ControllerState, ControllerStatus,
     ControllerTrait, Error, EventDispatcher, FirmwareError, GcodeError, MachineStatus,
     MachineStatusSnapshot, Message, MessageDispatcher, MessageLevel, OverrideState,
-    PartialPosition, Position, Result, SimpleController, Units,
+    PartialPosition, Position, PostContext, PostDefinition, PostLibrary, Result, SimpleController,
+    Units,
 };
 
 pub use gcodekit5_visualizer::{
-    AdvancedProber, Alarm, AlarmManager, AlarmType, AutoConnectConfig, BackupEntry, BackupManager,
-    BasicProber, Bookmark, BookmarkManager, CommandHistory, CommandId, CommandLengthProcessor,
-    CommandListener, CommandListenerHandle, CommandNumberGenerator, CommandProcessor,
-    CommandResponse, CommandState, CommentProcessor, CustomAction, CustomMacro, DataLogger,
-    DecimalProcessor, DropEvent, DropFileType, DropIndicatorState, DropTarget, DropZone,
-    EmptyLineRemoverProcessor, ExportOptions, FeedRateStats, FileComparison, FileEncoding,
-    FileExporter, FileFormat, FileProcessingPipeline, FileReadStats, FileStatistics,
-    FileStreamReader, FileValidation, GcodeCommand, GcodeFileReader, GcodeParser, GcodeState,
-    GcodeStreamReader, GcodeTemplate, HeightPoint, HistoryEntry, LogEntry, ModalState,
-    NetworkConfig, PausableStream, PendantButton, PendantConfig, PerformanceMetrics, ProbeMesh,
-    ProbePoint, ProcessedFile, ProcessorConfig, ProcessorHandle, ProcessorPipeline,
-    ProcessorRegistry, ProgramState, RecentFileEntry, RecentFilesManager, SimulationPosition,
-    Simulator, SoftLimits, SpindleStats, Stepper, StringStreamReader, TemplateLibrary,
-    TemplateVariable, ToolInfo, ToolLibrary, ToolOffset, ToolOffsetManager, ValidationIssue,
-    ValidationResult, ValidationSeverity, WhitespaceProcessor, WorkCoordinateSystem, WorkOffset,
+    AdvancedProber, AffineTransform, Alarm, AlarmManager, AlarmType, ArcFitStats, ArcFitter,
+    AutoConnectConfig, BacklashCompensator, BackupEntry, BackupManager, BasicProber, Bookmark,
+    BookmarkManager, CommandHistory, CommandId, CommandLengthProcessor, CommandListener,
+    CommandListenerHandle, CommandNumberGenerator, CommandProcessor, CommandResponse, CommandState,
+    CommentProcessor, CustomAction, CustomMacro, DataLogger, DecimalProcessor, DropEvent,
+    DropFileType, DropIndicatorState, DropTarget, DropZone, DynamicPowerProcessor,
+    EmptyLineRemoverProcessor, ExpandedLine, ExpansionError, ExpansionStep, ExportOptions,
+    FeedRateStats, FileComparison, FileEncoding, FileExporter, FileFormat, FileProcessingPipeline,
+    FileReadStats, FileStatistics, FileStreamReader, FileValidation, GcodeCommand, GcodeFileReader,
+    GcodeParser, GcodeState, GcodeStreamReader, GcodeTemplate, HeightPoint, HistoryEntry,
+    LaserSettings, LogEntry, MirrorAxis, ModalState, NetworkConfig, OverscanProcessor,
+    ParametricExpander, ParametricProcessor, ParametricProgram, PausableStream, PendantButton,
+    PendantConfig, PerformanceMetrics, PowerClampProcessor, PowerCurve, PowerScaleProcessor,
+    PowerScaling, ProbeMesh, ProbePoint, ProcessedFile, ProcessorConfig, ProcessorHandle,
+    ProcessorPipeline, ProcessorRegistry, ProgramState, RecentFileEntry, RecentFilesManager,
+    RedundantPowerRemover, SimulationPosition, Simulator, SoftLimits, SpindleStats, Stepper,
+    StringStreamReader, TemplateLibrary, TemplateVariable, ToolInfo, ToolLibrary, ToolOffset,
+    ToolOffsetManager, TransformProcessor, ValidationIssue, ValidationResult, ValidationSeverity,
+    WhitespaceProcessor, WorkCoordinateSystem, WorkOffset,
 };
 
 pub use gcodekit5_designer::{
@@ -87,6 +92,7 @@ pub use gcodekit5_ui::{
     MachineSettings, SettingsManager, UiSettings,
 };
 
+pub mod cli;
 pub mod types;
 
 pub use types::GcodeSendState;
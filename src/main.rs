@@ -7,6 +7,12 @@
 use gcodekit5::init_logging;
 
 fn main() -> anyhow::Result<()> {
+    // Headless subcommands such as `preview` run without starting GTK
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(result) = gcodekit5::cli::run(&args) {
+        return result;
+    }
+
     // Initialize logging
     init_logging()?;
 